@@ -195,6 +195,14 @@ impl EnhancedPatternMatcher {
                 }
             }
             Pattern::Range { .. } => {}
+            Pattern::Binding { name, pattern: pat } => {
+                bindings.push(PatternBinding {
+                    name: name.clone(),
+                    is_mutable: false,
+                    ty: "inferred".to_string(),
+                });
+                self.collect_bindings(pat, bindings)?;
+            }
         }
         Ok(())
     }
@@ -363,6 +371,10 @@ impl PatternAnalyzer {
             Pattern::Box(pat) => {
                 self.extract_bindings_recursive(pat, bindings);
             }
+            Pattern::Binding { name, pattern: pat } => {
+                bindings.push(name.clone());
+                self.extract_bindings_recursive(pat, bindings);
+            }
             Pattern::EnumVariant { data, .. } => {
                 if let Some(pat) = data {
                     self.extract_bindings_recursive(pat, bindings);
@@ -444,6 +456,7 @@ impl ReachabilityChecker {
             Pattern::Slice { .. } => true,
             Pattern::Box(_) => true,
             Pattern::EnumVariant { .. } => true,
+            Pattern::Binding { pattern: pat, .. } => self.is_reachable(pat),
         }
     }
 
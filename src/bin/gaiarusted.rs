@@ -7,7 +7,7 @@ use std::fs;
 use std::path::PathBuf;
 use std::process;
 
-use gaiarusted::{CompilationConfig, OutputFormat, compile_files, formatter};
+use gaiarusted::{CompilationConfig, LibcLinkage, OutputFormat, compile_files, formatter};
 use std::time::Instant;
 
 #[derive(Debug)]
@@ -22,6 +22,19 @@ struct CliArgs {
     debug: bool,
     discover_mode: bool,
     show_output: bool,
+    warnings_as_errors: bool,
+    no_color: bool,
+    print_symbols: bool,
+    test_harness: bool,
+    bench: bool,
+    no_std: bool,
+    max_errors: usize,
+    keep_intermediates: bool,
+    backtrace: bool,
+    stack_protector: bool,
+    force_frame_pointers: bool,
+    static_libc: bool,
+    overflow_checks: bool,
 }
 
 impl CliArgs {
@@ -36,6 +49,19 @@ impl CliArgs {
         let mut debug = false;
         let mut discover_mode = false;
         let mut show_output = false;
+        let mut warnings_as_errors = false;
+        let mut no_color = false;
+        let mut print_symbols = false;
+        let mut test_harness = false;
+        let mut bench = false;
+        let mut no_std = false;
+        let mut max_errors = 100;
+        let mut keep_intermediates = false;
+        let mut backtrace = false;
+        let mut stack_protector = false;
+        let mut force_frame_pointers = false;
+        let mut static_libc = false;
+        let mut overflow_checks = false;
 
         let mut i = 1;
         while i < args.len() {
@@ -111,6 +137,75 @@ impl CliArgs {
                     discover_mode = true;
                     i += 1;
                 }
+                "--werror" => {
+                    warnings_as_errors = true;
+                    i += 1;
+                }
+                "--no-color" => {
+                    no_color = true;
+                    i += 1;
+                }
+                "--bench" => {
+                    bench = true;
+                    i += 1;
+                }
+                "--test" => {
+                    test_harness = true;
+                    i += 1;
+                }
+                "--no-std" => {
+                    no_std = true;
+                    i += 1;
+                }
+                "--keep-intermediates" => {
+                    keep_intermediates = true;
+                    i += 1;
+                }
+                "--backtrace" => {
+                    backtrace = true;
+                    i += 1;
+                }
+                "-fstack-protector" => {
+                    stack_protector = true;
+                    i += 1;
+                }
+                "-fno-omit-frame-pointer" => {
+                    force_frame_pointers = true;
+                    i += 1;
+                }
+                "-static" => {
+                    static_libc = true;
+                    i += 1;
+                }
+                arg if arg.starts_with("--max-errors=") => {
+                    max_errors = arg["--max-errors=".len()..].parse::<usize>()
+                        .map_err(|_| "Invalid --max-errors value".to_string())?;
+                    i += 1;
+                }
+                arg if arg.starts_with("--emit=") => {
+                    output_format = match &arg["--emit=".len()..] {
+                        "tac" => OutputFormat::ThreeAddressCode,
+                        "asm" => OutputFormat::Assembly,
+                        "obj" => OutputFormat::Object,
+                        other => return Err(format!("Unknown --emit value: {}", other)),
+                    };
+                    i += 1;
+                }
+                arg if arg.starts_with("--overflow-checks=") => {
+                    overflow_checks = match &arg["--overflow-checks=".len()..] {
+                        "on" => true,
+                        "off" => false,
+                        other => return Err(format!("Unknown --overflow-checks value: {}", other)),
+                    };
+                    i += 1;
+                }
+                arg if arg.starts_with("--print=") => {
+                    match &arg["--print=".len()..] {
+                        "symbols" => print_symbols = true,
+                        other => return Err(format!("Unknown --print value: {}", other)),
+                    }
+                    i += 1;
+                }
                 arg if arg.starts_with('-') => {
                     return Err(format!("Unknown option: {}", arg));
                 }
@@ -136,6 +231,19 @@ impl CliArgs {
             debug,
             discover_mode,
             show_output,
+            warnings_as_errors,
+            no_color,
+            print_symbols,
+            test_harness,
+            bench,
+            no_std,
+            max_errors,
+            keep_intermediates,
+            backtrace,
+            stack_protector,
+            force_frame_pointers,
+            static_libc,
+            overflow_checks,
         })
     }
 
@@ -162,6 +270,20 @@ impl CliArgs {
         println!("    -S, --show-output            Display generated output in terminal");
         println!("    -g, --debug                  Include debug information");
         println!("    --discover                   Auto-discover .rs files in directory");
+        println!("    --werror                     Treat all warnings as errors");
+        println!("    --no-color                   Plain ASCII diagnostics (also NO_COLOR, or stderr not a tty)");
+        println!("    --emit=tac                   Write the generated code as a three-address listing instead of x86");
+        println!("    --print=symbols              List defined (T) and referenced (U) symbols");
+        println!("    --test                       Build a test runner for the #[test] functions");
+        println!("    --bench                      Build and run the #[bench] functions, reporting ns/iter");
+        println!("    --no-std                     Emit only your functions: no main wrapper or runtime");
+        println!("    --max-errors=<N>             Stop after N errors (default: 100, 0 = no limit)");
+        println!("    --keep-intermediates         Keep the .s/.o files next to the output");
+        println!("    --backtrace                  Print the calls leading to a panic");
+        println!("    -fstack-protector            Abort when a stack array overruns its frame");
+        println!("    -fno-omit-frame-pointer      Keep the RBP frame in every function, for perf --call-graph fp");
+        println!("    -static                      Link libc statically into the executable");
+        println!("    --overflow-checks=<on|off>   Reject overflowing constants and panic on out-of-range shifts (default: off)");
         println!("    -h, --help                   Print this help message");
         println!("    --help-formats               Show detailed format information");
         println!("    --version                    Print version");
@@ -484,16 +606,25 @@ fn print_detailed_error(error: &gaiarusted::CompileError, error_num: usize, tota
     }
 }
 
+/// Wrap `text` in an ANSI escape `code` unless colors are off
+fn paint(text: &str, code: &str) -> String {
+    if formatter::color_enabled() {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
 fn format_error(text: &str) -> String {
-    format!("\x1b[1;31m{}\x1b[0m", text)
+    paint(text, "1;31")
 }
 
 fn format_warning(text: &str) -> String {
-    format!("\x1b[1;33m{}\x1b[0m", text)
+    paint(text, "1;33")
 }
 
 fn format_info(text: &str) -> String {
-    format!("\x1b[1;36m{}\x1b[0m", text)
+    paint(text, "1;36")
 }
 
 fn main() {
@@ -514,7 +645,20 @@ fn main() {
         .set_output_format(cli_args.output_format)
         .set_opt_level(cli_args.opt_level)
         .with_verbose(cli_args.verbose)
-        .with_debug(cli_args.debug);
+        .with_debug(cli_args.debug)
+        .warnings_as_errors(cli_args.warnings_as_errors)
+        .no_color(cli_args.no_color)
+        .with_print_symbols(cli_args.print_symbols)
+        .with_test_harness(cli_args.test_harness)
+        .set_no_std(cli_args.no_std)
+        .set_max_errors(cli_args.max_errors)
+        .backtrace(cli_args.backtrace)
+        .stack_protector(cli_args.stack_protector)
+        .with_overflow_checks(cli_args.overflow_checks)
+        .force_frame_pointers(cli_args.force_frame_pointers)
+        .set_libc_linkage(if cli_args.static_libc { LibcLinkage::Static } else { LibcLinkage::Dynamic })
+        // `-S` prints the assembly after the build, so it has to stick around
+        .keep_intermediates(cli_args.keep_intermediates || cli_args.show_output);
 
     // Add libraries and library paths
     for lib_path in cli_args.lib_paths {
@@ -563,6 +707,22 @@ fn main() {
         }
     }
 
+    if cli_args.bench {
+        match gaiarusted::testing::bench::run_benches(&config) {
+            Ok(results) => {
+                for result in &results {
+                    println!("{}", result);
+                }
+                return;
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    formatter::set_color(!config.no_color && gaiarusted::error_reporting::stderr_supports_color());
     let start = Instant::now();
     formatter::start_compilation(&format!("{} file(s)", config.source_files.len()));
 
@@ -570,12 +730,30 @@ fn main() {
     match compile_files(&config) {
         Ok(result) => {
             let total_time = start.elapsed();
+            for warning in &result.warnings {
+                eprintln!("{}: {}", format_warning("warning"), warning.message);
+                let location = match (&warning.file, warning.location) {
+                    (Some(file), Some(location)) => Some(format!("{}:{}", file.display(), location)),
+                    (Some(file), None) => Some(file.display().to_string()),
+                    (None, Some(location)) => Some(location.to_string()),
+                    (None, None) => None,
+                };
+                if let Some(location) = location {
+                    eprintln!("  --> {}", location);
+                }
+                if let Some(context) = &warning.context {
+                    eprintln!("  = note: {}", context);
+                }
+                if let Some(help) = &warning.help {
+                    eprintln!("  = help: {}", help);
+                }
+            }
             if result.success {
                 formatter::success(&format!("compiled to '{}'", config.output_path.display()));
                 println!();
-                println!("{}summary{}", formatter::Colors::DIM, formatter::Colors::RESET);
-                println!("  {}•{} {} lines of code", formatter::Colors::CYAN, formatter::Colors::RESET, result.stats.total_lines);
-                println!("  {}•{} {} ms total", formatter::Colors::CYAN, formatter::Colors::RESET, total_time.as_millis());
+                println!("{}summary{}", formatter::Colors::dim(), formatter::Colors::reset());
+                println!("  {}{}{} {} lines of code", formatter::Colors::cyan(), formatter::glyph("•", "-"), formatter::Colors::reset(), result.stats.total_lines);
+                println!("  {}{}{} {} ms total", formatter::Colors::cyan(), formatter::glyph("•", "-"), formatter::Colors::reset(), total_time.as_millis());
                 println!();
                 
                 if cli_args.show_output {
@@ -590,6 +768,12 @@ fn main() {
                     }
                 }
                 
+                if let Some(symbols) = &result.symbols {
+                    println!("Symbols:");
+                    print!("{}", symbols);
+                    println!();
+                }
+
                 println!("Output files:");
                 for file in &result.output_files {
                     if file.exists() {
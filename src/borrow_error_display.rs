@@ -96,9 +96,9 @@ impl BorrowError {
         // Header: error[CODE]: error_type
         output.push_str(&format!(
             "{}error[{}]{}: {}: `{}`\n",
-            crate::formatter::Colors::RED,
+            crate::formatter::Colors::red(),
             self.error_code,
-            crate::formatter::Colors::RESET,
+            crate::formatter::Colors::reset(),
             self.error_type,
             self.variable
         ));
@@ -116,8 +116,8 @@ impl BorrowError {
             output.push_str("\n");
             output.push_str(&format!(
                 "{}help:{} Try:\n",
-                crate::formatter::Colors::GREEN,
-                crate::formatter::Colors::RESET
+                crate::formatter::Colors::green(),
+                crate::formatter::Colors::reset()
             ));
             for (idx, suggestion) in self.suggestions.iter().enumerate() {
                 output.push_str(&format!("  {}. {}\n", idx + 1, suggestion));
@@ -160,7 +160,7 @@ impl BorrowError {
         let start_line = *all_lines.first().unwrap_or(&self.primary_line);
         let end_line = *all_lines.last().unwrap_or(&self.primary_line);
 
-        output.push_str(&format!("{}  |{}\n", padding, crate::formatter::Colors::DIM));
+        output.push_str(&format!("{}  |{}\n", padding, crate::formatter::Colors::dim()));
 
         for display_line in start_line..=end_line {
             if display_line > 0 && display_line <= source_lines.len() {
@@ -170,9 +170,9 @@ impl BorrowError {
                 // Print source line
                 output.push_str(&format!(
                     "{}{}|{} {}\n",
-                    crate::formatter::Colors::DIM,
+                    crate::formatter::Colors::dim(),
                     display_line,
-                    crate::formatter::Colors::RESET,
+                    crate::formatter::Colors::reset(),
                     line_content
                 ));
 
@@ -182,8 +182,8 @@ impl BorrowError {
                         output.push_str(&format!(
                             "{}  |{} {}",
                             padding,
-                            crate::formatter::Colors::DIM,
-                            crate::formatter::Colors::RESET
+                            crate::formatter::Colors::dim(),
+                            crate::formatter::Colors::reset()
                         ));
 
                         // Position annotation under the variable
@@ -196,18 +196,18 @@ impl BorrowError {
                                 output.push_str(&format!(
                                     "{}{}{}{}\n",
                                     spaces,
-                                    crate::formatter::Colors::RED,
+                                    crate::formatter::Colors::red(),
                                     "^".repeat(self.variable.len()),
-                                    crate::formatter::Colors::RESET
+                                    crate::formatter::Colors::reset()
                                 ));
                             } else {
                                 output.push_str(&format!(
                                     "{}{}{} {}{}\n",
                                     spaces,
-                                    crate::formatter::Colors::CYAN,
+                                    crate::formatter::Colors::cyan(),
                                     symbol,
                                     event.description(),
-                                    crate::formatter::Colors::RESET
+                                    crate::formatter::Colors::reset()
                                 ));
                             }
                         }
@@ -226,9 +226,9 @@ impl BorrowError {
         output.push_str("\n");
         output.push_str(&format!(
             "{}`{}` no longer owns the data because:{}\n",
-            crate::formatter::Colors::CYAN,
+            crate::formatter::Colors::cyan(),
             self.variable,
-            crate::formatter::Colors::RESET
+            crate::formatter::Colors::reset()
         ));
 
         // Sort events by line number and describe what happened
@@ -239,10 +239,10 @@ impl BorrowError {
             if *event != OwnershipEvent::Used {
                 output.push_str(&format!(
                     "{}• Line {}: {}{}\n",
-                    crate::formatter::Colors::DIM,
+                    crate::formatter::Colors::dim(),
                     line,
                     event.description(),
-                    crate::formatter::Colors::RESET
+                    crate::formatter::Colors::reset()
                 ));
             }
         }
@@ -283,6 +283,7 @@ mod tests {
                 mutable: false,
                 inner: Box::new(Type::Named("i32".to_string())),
             },
+            attributes: Vec::new(),
         }];
         let return_type = None;
 
@@ -313,6 +314,7 @@ mod tests {
                 mutable: false,
                 inner: Box::new(Type::Named("i32".to_string())),
             },
+            attributes: Vec::new(),
         }];
         let return_type = None;
 
@@ -351,6 +353,7 @@ mod tests {
                     mutable: false,
                     inner: Box::new(Type::Named("i32".to_string())),
                 },
+                attributes: Vec::new(),
             },
             Parameter {
                 name: "y".to_string(),
@@ -360,6 +363,7 @@ mod tests {
                     mutable: false,
                     inner: Box::new(Type::Named("str".to_string())),
                 },
+                attributes: Vec::new(),
             },
         ];
         let return_type = None;
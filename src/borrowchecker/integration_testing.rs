@@ -108,6 +108,7 @@ mod integration_tests {
         let field_access = HirExpression::FieldAccess {
             object: Box::new(HirExpression::Variable("d".to_string())),
             field: "i".to_string(),
+            location: None,
         };
         
         let _result = analyzer.analyze_expression(&field_access);
@@ -134,6 +135,7 @@ mod integration_tests {
         let field_access = HirExpression::FieldAccess {
             object: Box::new(HirExpression::Variable("d".to_string())),
             field: "i".to_string(),
+            location: None,
         };
         
         let result = analyzer.analyze_expression(&field_access);
@@ -262,6 +264,7 @@ mod integration_tests {
         let union_field = HirExpression::FieldAccess {
             object: Box::new(HirExpression::Variable("item".to_string())),
             field: "i".to_string(),
+            location: None,
         };
         
         let field_result = analyzer.analyze_expression(&union_field);
@@ -367,6 +370,7 @@ mod integration_tests {
         let union_access = HirExpression::FieldAccess {
             object: Box::new(HirExpression::Variable("result".to_string())),
             field: "Ok".to_string(),
+            location: None,
         };
         let union_result = analyzer.analyze_expression(&union_access);
         println!("Union access detected: {}", !union_result.unions_detected.is_empty());
@@ -546,9 +550,11 @@ mod integration_tests {
                 HirExpression::FieldAccess {
                     object: Box::new(HirExpression::Variable("obj".to_string())),
                     field: "field1".to_string(),
+                    location: None,
                 }
             ),
             field: "field2".to_string(),
+            location: None,
         };
         
         let result = analyzer.analyze_expression(&nested);
@@ -642,6 +648,7 @@ mod integration_tests {
         let expr = HirExpression::FieldAccess {
             object: Box::new(HirExpression::Variable("d".to_string())),
             field: "field".to_string(),
+            location: None,
         };
         
         let result1 = analyzer.analyze_expression(&expr);
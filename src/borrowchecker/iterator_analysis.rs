@@ -121,7 +121,7 @@ impl IteratorAnalyzer {
             }
 
             // Field access: iterate over a field
-            HirExpression::FieldAccess { object, field: _ } => {
+            HirExpression::FieldAccess { object, .. } => {
                 // Recursively analyze the object
                 self.analyze_iterator(object.as_ref())
             }
@@ -362,6 +362,7 @@ mod tests {
         let expr = HirExpression::FieldAccess {
             object: Box::new(HirExpression::Variable("my_struct".to_string())),
             field: "data".to_string(),
+            location: None,
         };
 
         // This will recursively check the object (my_struct), which is registered
@@ -47,7 +47,7 @@ pub use lifetime_validation::{LifetimeValidator, FunctionLifetimeValidator, Stru
 pub use impl_lifetimes::{SelfKind, ImplMethodValidator, ImplLifetimeError, MethodLifetimeLocation};
 
 use crate::lowering::{HirExpression, HirItem, HirStatement, HirType};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
 /// Borrow checking error with optional structured information for narrative formatting
@@ -157,6 +157,8 @@ pub struct BorrowEnv {
     lifetime_ctx: LifetimeContext,
     /// Ownership states for each binding (name -> state)
     ownership_states: HashMap<String, OwnershipState>,
+    /// Fields moved out of a struct binding that is otherwise still usable
+    moved_fields: HashMap<String, HashSet<String>>,
 }
 
 impl BorrowEnv {
@@ -166,6 +168,7 @@ impl BorrowEnv {
             scopes: ScopeStack::new(),
             lifetime_ctx: LifetimeContext::new(),
             ownership_states: HashMap::new(),
+            moved_fields: HashMap::new(),
         }
     }
 
@@ -199,6 +202,7 @@ impl BorrowEnv {
         self.scopes
             .add_binding(name.clone(), ty, is_mutable, None)
             .map_err(|e| BorrowCheckError::simple(e))?;
+        self.moved_fields.remove(&name);
         self.ownership_states.insert(name, OwnershipState::Owned);
         Ok(())
     }
@@ -214,6 +218,7 @@ impl BorrowEnv {
         self.scopes
             .add_binding(name.clone(), ty, is_mutable, Some(lifetime))
             .map_err(|e| BorrowCheckError::simple(e))?;
+        self.moved_fields.remove(&name);
         self.ownership_states.insert(name, OwnershipState::Owned);
         Ok(())
     }
@@ -279,6 +284,34 @@ impl BorrowEnv {
         Ok(())
     }
 
+    /// Mark one field of a struct binding as moved out, leaving the others
+    /// usable
+    fn move_field(&mut self, name: &str, field: &str) {
+        self.moved_fields.entry(name.to_string()).or_default().insert(field.to_string());
+    }
+
+    /// The error for reading `name.field` after it was moved, on its own or
+    /// with all of `name`
+    fn moved_field_error(&self, name: &str, field: &str) -> Option<BorrowCheckError> {
+        if self.ownership_states.get(name) == Some(&OwnershipState::Moved) {
+            return Some(BorrowCheckError::simple(format!("Value {} used after move", name)));
+        }
+        if self.moved_fields.get(name).is_some_and(|fields| fields.contains(field)) {
+            let mut error = BorrowCheckError::simple(format!("Value {}.{} used after move", name, field));
+            error.error_code = Some("E0382".to_string());
+            error.variable = Some(format!("{}.{}", name, field));
+            return Some(error);
+        }
+        None
+    }
+
+    /// The fields moved out of `name`, in name order, if any were
+    fn moved_fields(&self, name: &str) -> Vec<String> {
+        let mut fields: Vec<String> = self.moved_fields.get(name).into_iter().flatten().cloned().collect();
+        fields.sort();
+        fields
+    }
+
     /// Create an immutable borrow
     pub fn borrow_immutable(&mut self, name: &str) -> BorrowCheckResult<()> {
         // Check if binding exists
@@ -356,6 +389,10 @@ impl BorrowEnv {
 /// Borrow checker: enforces ownership and borrowing rules
 pub struct BorrowChecker {
     env: BorrowEnv,
+    /// User structs whose values move on assignment (no `#[derive(Copy)]`)
+    move_structs: HashSet<String>,
+    /// Each user struct's fields and their types
+    struct_fields: HashMap<String, Vec<(String, HirType)>>,
 }
 
 impl BorrowChecker {
@@ -363,18 +400,33 @@ impl BorrowChecker {
     pub fn new() -> Self {
         BorrowChecker {
             env: BorrowEnv::new(),
+            move_structs: HashSet::new(),
+            struct_fields: HashMap::new(),
         }
     }
 
     /// Check all items for borrow safety
     pub fn check_items(&mut self, items: &[HirItem]) -> BorrowCheckResult<()> {
+        self.collect_move_structs(items);
         for item in items {
             match item {
                 HirItem::Function {
                     params,
+                    return_type,
                     body,
+                    location,
                     ..
                 } => {
+                    if matches!(return_type, Some(HirType::Reference(_) | HirType::MutableReference(_))) {
+                        if let Some(mut error) = returned_local_reference(params, body) {
+                            if let Some(location) = location {
+                                error.line = Some(location.line);
+                                error.column = Some(location.column);
+                            }
+                            return Err(error);
+                        }
+                    }
+
                     // Create new scope for function
                     self.env.push_scope();
 
@@ -398,8 +450,12 @@ impl BorrowChecker {
                 }
                 HirItem::Static { .. } => {
                 }
+                HirItem::ExternBlock { .. } => {
+                }
                 HirItem::AssociatedType { .. } => {
                 }
+                HirItem::AssociatedConst { .. } => {
+                }
                 HirItem::Use { .. } => {
                 }
                 HirItem::Impl { methods, .. } => {
@@ -414,6 +470,48 @@ impl BorrowChecker {
         Ok(())
     }
 
+    /// Record which structs move rather than copy
+    fn collect_move_structs(&mut self, items: &[HirItem]) {
+        for item in items {
+            match item {
+                HirItem::Struct { name, fields, derives, .. } => {
+                    if !derives.iter().any(|d| d == "Copy") {
+                        self.move_structs.insert(name.clone());
+                    }
+                    self.struct_fields.insert(name.clone(), fields.clone());
+                }
+                HirItem::Module { items, .. } => self.collect_move_structs(items),
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether `let b = a;` moves `a`, i.e. its type isn't Copy
+    fn moves_on_assign(&self, name: &str) -> bool {
+        match self.env.lookup(name) {
+            Some(binding) => matches!(&binding.ty, HirType::Named(ty) if self.move_structs.contains(ty)),
+            None => false,
+        }
+    }
+
+    /// Whether `let b = a.field;` moves the field out of `a`: the field holds
+    /// a non-Copy struct, a `String`, a `Vec` or a `Box`
+    fn field_moves_on_assign(&self, name: &str, field: &str) -> bool {
+        let Some(HirType::Named(struct_name)) = self.env.lookup(name).map(|binding| binding.ty) else {
+            return false;
+        };
+        let field_ty = self
+            .struct_fields
+            .get(&struct_name)
+            .and_then(|fields| fields.iter().find(|(field_name, _)| field_name == field))
+            .map(|(_, ty)| ty);
+        match field_ty {
+            Some(HirType::Named(ty)) => self.move_structs.contains(ty),
+            Some(HirType::String | HirType::Vec(_) | HirType::Box(_)) => true,
+            _ => false,
+        }
+    }
+
     /// Check a list of statements
     fn check_statements(&mut self, statements: &[HirStatement]) -> BorrowCheckResult<()> {
         for stmt in statements {
@@ -428,6 +526,19 @@ impl BorrowChecker {
             HirStatement::Let { name, mutable, ty, init } => {
                 // Check the right-hand side expression
                 self.check_expression(init)?;
+                match init {
+                    HirExpression::Variable(source) if self.moves_on_assign(source) => {
+                        self.env.move_binding(source)?;
+                    }
+                    HirExpression::FieldAccess { object, field, .. } => {
+                        if let HirExpression::Variable(source) = object.as_ref() {
+                            if self.field_moves_on_assign(source, field) {
+                                self.env.move_field(source, field);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
 
                 self.env.bind(name.clone(), ty.clone(), *mutable)?;
             }
@@ -495,16 +606,29 @@ impl BorrowChecker {
                 // Check the condition expression
                 self.check_expression(condition)?;
                 
-                // Check the then body
+                // Check each branch from the same starting state; a value
+                // moved in either branch counts as moved afterwards
+                let before = self.env.ownership_states.clone();
+                let fields_before = self.env.moved_fields.clone();
                 for stmt in then_body {
                     self.check_statement(stmt)?;
                 }
                 
                 // Check the else body if present
                 if let Some(else_stmts) = else_body {
+                    let after_then = std::mem::replace(&mut self.env.ownership_states, before);
+                    let fields_after_then = std::mem::replace(&mut self.env.moved_fields, fields_before);
                     for stmt in else_stmts {
                         self.check_statement(stmt)?;
                     }
+                    for (name, state) in after_then {
+                        if state == OwnershipState::Moved {
+                            self.env.ownership_states.insert(name, state);
+                        }
+                    }
+                    for (name, fields) in fields_after_then {
+                        self.env.moved_fields.entry(name).or_default().extend(fields);
+                    }
                 }
             }
 
@@ -534,6 +658,18 @@ impl BorrowChecker {
                     if binding.state == OwnershipState::Moved {
                         return Err(BorrowCheckError::simple(format!("Value {} used after move", name)));
                     }
+                    let moved_fields = self.env.moved_fields(name);
+                    if !moved_fields.is_empty() {
+                        let mut error = BorrowCheckError::simple(format!(
+                            "Value {} used after partial move of field {}",
+                            name,
+                            moved_fields.join(", ")
+                        ));
+                        error.error_code = Some("E0382".to_string());
+                        error.variable = Some(name.clone());
+                        error.add_suggestion("use the fields that weren't moved, or move a clone of the field instead");
+                        return Err(error);
+                    }
                 }
                 Ok(())
             }
@@ -549,8 +685,26 @@ impl BorrowChecker {
                 Ok(())
             }
 
-            HirExpression::Assign { target: _, value } => {
+            HirExpression::Assign { target, value } => {
                 self.check_expression(value)?;
+                // Assigning a fresh value makes a moved-from variable usable again
+                match target.as_ref() {
+                    HirExpression::Variable(name) => {
+                        if self.env.ownership_states.get(name) == Some(&OwnershipState::Moved) {
+                            self.env.ownership_states.insert(name.clone(), OwnershipState::Owned);
+                        }
+                        self.env.moved_fields.remove(name);
+                    }
+                    // Assigning a moved-out field fills it back in
+                    HirExpression::FieldAccess { object, field, .. } => {
+                        if let HirExpression::Variable(name) = object.as_ref() {
+                            if let Some(fields) = self.env.moved_fields.get_mut(name) {
+                                fields.remove(field);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
                 Ok(())
             }
 
@@ -562,10 +716,17 @@ impl BorrowChecker {
                 Ok(())
             }
 
-            HirExpression::FieldAccess { object, .. } => {
-                self.check_expression(object)?;
-                Ok(())
-            }
+            // Reading one field only needs that field, so a struct with other
+            // fields moved out is fine here
+            HirExpression::FieldAccess { object, field, .. } => match object.as_ref() {
+                HirExpression::Variable(name) if self.env.lookup(name).is_some() => {
+                    match self.env.moved_field_error(name, field) {
+                        Some(error) => Err(error),
+                        None => Ok(()),
+                    }
+                }
+                _ => self.check_expression(object),
+            },
 
             HirExpression::TupleAccess { object, .. } => {
                 self.check_expression(object)?;
@@ -604,6 +765,13 @@ impl BorrowChecker {
                 Ok(())
             }
 
+            HirExpression::Loop { body } => {
+                self.env.push_scope();
+                self.check_statements(body)?;
+                self.env.pop_scope();
+                Ok(())
+            }
+
             HirExpression::Match { scrutinee, arms } => {
                 self.check_expression(scrutinee)?;
                 for arm in arms {
@@ -696,6 +864,163 @@ impl BorrowChecker {
     }
 }
 
+/// The error for a function that returns a reference to one of its own
+/// locals, which is dropped when the function returns. Parameters and
+/// statics outlive the call, so references to them are fine.
+fn returned_local_reference(params: &[(String, HirType)], body: &[HirStatement]) -> Option<BorrowCheckError> {
+    let mut locals = HashSet::new();
+    collect_locals(body, &mut locals);
+    let mut references: HashSet<String> = params
+        .iter()
+        .filter(|(_, ty)| matches!(ty, HirType::Reference(_) | HirType::MutableReference(_)))
+        .map(|(name, _)| name.clone())
+        .collect();
+    collect_reference_locals(body, &mut references);
+    let mut returned = Vec::new();
+    returned_expressions(body, &mut returned);
+    for expr in returned {
+        if let Some(local) = referenced_local(expr, &locals, &references) {
+            let mut error = BorrowCheckError::simple(format!(
+                "cannot return reference to local variable `{}`: returns a reference to data owned by the current function",
+                local
+            ));
+            error.error_code = Some("E0515".to_string());
+            error.variable = Some(local.to_string());
+            error.add_suggestion("return the value itself instead of a reference to it");
+            return Some(error);
+        }
+    }
+    None
+}
+
+/// Names bound by `let` or a `for` loop anywhere in `body`
+fn collect_locals(body: &[HirStatement], locals: &mut HashSet<String>) {
+    for stmt in body {
+        match stmt {
+            HirStatement::Let { name, .. } => {
+                locals.insert(name.clone());
+            }
+            HirStatement::For { var, body, .. } => {
+                locals.insert(var.clone());
+                collect_locals(body, locals);
+            }
+            HirStatement::While { body, .. } | HirStatement::UnsafeBlock(body) => collect_locals(body, locals),
+            HirStatement::If { then_body, else_body, .. } => {
+                collect_locals(then_body, locals);
+                if let Some(else_body) = else_body {
+                    collect_locals(else_body, locals);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Locals in `body` that hold a reference: declared with a reference type,
+/// initialized with a borrow, or copied from another such name
+fn collect_reference_locals(body: &[HirStatement], references: &mut HashSet<String>) {
+    for stmt in body {
+        match stmt {
+            HirStatement::Let { name, ty, init, .. } => {
+                let holds_reference = matches!(ty, HirType::Reference(_) | HirType::MutableReference(_))
+                    || match init {
+                        HirExpression::UnaryOp {
+                            op: crate::lowering::UnaryOp::Reference | crate::lowering::UnaryOp::MutableReference,
+                            ..
+                        } => true,
+                        HirExpression::Variable(source) => references.contains(source),
+                        _ => false,
+                    };
+                if holds_reference {
+                    references.insert(name.clone());
+                }
+            }
+            HirStatement::For { body, .. } | HirStatement::While { body, .. } | HirStatement::UnsafeBlock(body) => {
+                collect_reference_locals(body, references)
+            }
+            HirStatement::If { then_body, else_body, .. } => {
+                collect_reference_locals(then_body, references);
+                if let Some(else_body) = else_body {
+                    collect_reference_locals(else_body, references);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The expressions `body` can return: the operands of its `return`s and
+/// its tail expression
+fn returned_expressions<'a>(body: &'a [HirStatement], returned: &mut Vec<&'a HirExpression>) {
+    for stmt in body {
+        match stmt {
+            HirStatement::Return(Some(expr)) => tail_expressions(expr, returned),
+            HirStatement::For { body, .. } | HirStatement::While { body, .. } | HirStatement::UnsafeBlock(body) => {
+                returned_expressions(body, returned)
+            }
+            HirStatement::If { then_body, else_body, .. } => {
+                returned_expressions(then_body, returned);
+                if let Some(else_body) = else_body {
+                    returned_expressions(else_body, returned);
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(HirStatement::Expression(tail)) = body.last() {
+        tail_expressions(tail, returned);
+    }
+}
+
+/// The values a returned expression can produce, looking through blocks
+/// and `if` branches
+fn tail_expressions<'a>(expr: &'a HirExpression, returned: &mut Vec<&'a HirExpression>) {
+    match expr {
+        HirExpression::Block(stmts, tail) => {
+            returned_expressions(stmts, returned);
+            if let Some(tail) = tail {
+                tail_expressions(tail, returned);
+            }
+        }
+        HirExpression::If { then_body, else_body, .. } => {
+            returned_expressions(then_body, returned);
+            if let Some(else_body) = else_body {
+                returned_expressions(else_body, returned);
+            }
+        }
+        _ => returned.push(expr),
+    }
+}
+
+/// The local that `&expr` borrows from, if `expr` is a reference into one.
+/// A field or element reached through a local that holds a reference
+/// belongs to the referent, not to the local.
+fn referenced_local<'a>(
+    expr: &'a HirExpression,
+    locals: &HashSet<String>,
+    references: &HashSet<String>,
+) -> Option<&'a str> {
+    let HirExpression::UnaryOp {
+        op: crate::lowering::UnaryOp::Reference | crate::lowering::UnaryOp::MutableReference,
+        operand,
+    } = expr
+    else {
+        return None;
+    };
+    let mut place = &**operand;
+    let mut projected = false;
+    loop {
+        match place {
+            HirExpression::Variable(name) if projected && references.contains(name) => return None,
+            HirExpression::Variable(name) => return locals.contains(name).then_some(name.as_str()),
+            HirExpression::FieldAccess { object, .. } => place = object,
+            HirExpression::Index { array, .. } => place = array,
+            _ => return None,
+        }
+        projected = true;
+    }
+}
+
 /// Public API: Check borrow safety for all items
 pub fn check_borrows(items: &[HirItem]) -> Result<(), BorrowCheckError> {
     let mut checker = BorrowChecker::new();
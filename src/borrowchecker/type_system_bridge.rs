@@ -160,7 +160,7 @@ impl TypeSystemBridge {
             }
 
             // Field access: extract the field type
-            HirExpression::FieldAccess { object, field } => {
+            HirExpression::FieldAccess { object, field, .. } => {
                 let _object_type = self.infer_expression_type(object)?;
                 // Would need field type information to fully resolve
                 // For now, return a placeholder
@@ -77,7 +77,7 @@ impl UnionTypeDetector {
 
             // Field access: check if the object is a union
             // e.g., obj.field - need to check if obj's type is a union
-            HirExpression::FieldAccess { object, field: _ } => {
+            HirExpression::FieldAccess { object, .. } => {
                 // Recursively check the object's type
                 self.detect_union_type(object.as_ref(), _scope)
             }
@@ -330,6 +330,7 @@ mod tests {
         let expr = HirExpression::FieldAccess {
             object: inner,
             field: "field1".to_string(),
+            location: None,
         };
 
         assert_eq!(
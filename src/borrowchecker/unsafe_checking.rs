@@ -343,7 +343,9 @@ impl UnsafeChecker {
             }
             HirItem::Const { .. } => Ok(()),
             HirItem::Static { .. } => Ok(()),
+            HirItem::ExternBlock { .. } => Ok(()),
             HirItem::AssociatedType { .. } => Ok(()),
+            HirItem::AssociatedConst { .. } => Ok(()),
             HirItem::Use { .. } => Ok(()),
             HirItem::Impl { methods, .. } => {
                 for method in methods {
@@ -204,6 +204,9 @@ impl CaptureAnalyzer {
                 self.collect_vars_from_expr(condition, used_vars);
                 self.collect_used_vars(body, used_vars);
             }
+            HirExpression::Loop { body } => {
+                self.collect_used_vars(body, used_vars);
+            }
             HirExpression::Match { scrutinee, arms } => {
                 self.collect_vars_from_expr(scrutinee, used_vars);
                 for arm in arms {
@@ -250,7 +253,8 @@ impl CaptureAnalyzer {
                     *is_mutated = true;
                     return;
                 }
-                HirStatement::Expression(HirExpression::While { body, .. }) => {
+                HirStatement::Expression(HirExpression::While { body, .. })
+                | HirStatement::Expression(HirExpression::Loop { body }) => {
                     self.check_mutation(body, is_mutated);
                 }
                 HirStatement::Expression(HirExpression::If { then_body, else_body, .. }) => {
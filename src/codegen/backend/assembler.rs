@@ -7,17 +7,27 @@ use std::process::Command;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::config::LibcLinkage;
+
 pub struct Assembler {
     output_dir: PathBuf,
+    libc_linkage: LibcLinkage,
 }
 
 impl Assembler {
     pub fn new<P: AsRef<Path>>(output_dir: P) -> Self {
         Assembler {
             output_dir: output_dir.as_ref().to_path_buf(),
+            libc_linkage: LibcLinkage::Dynamic,
         }
     }
 
+    /// Link libc statically (`-static`) or through the dynamic loader
+    pub fn with_libc_linkage(mut self, linkage: LibcLinkage) -> Self {
+        self.libc_linkage = linkage;
+        self
+    }
+
     /// Assemble x86-64 assembly to an object file using GNU as
     pub fn assemble_to_object(
         &self,
@@ -53,11 +63,36 @@ impl Assembler {
         output_exe: &Path,
         libraries: &[&str],
     ) -> Result<(), String> {
+        let status = self.link_command(object_files, output_exe, libraries)
+            .status()
+            .map_err(|e| format!("Failed to invoke linker (ld): {}", e))?;
+
+        if !status.success() {
+            return Err("Linker failed".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// The `ld` invocation `link_executable` runs
+    pub fn link_command(
+        &self,
+        object_files: &[&Path],
+        output_exe: &Path,
+        libraries: &[&str],
+    ) -> Command {
         let mut cmd = Command::new("ld");
 
         // Add standard C runtime
-        cmd.arg("-dynamic-linker")
-            .arg("/lib64/ld-linux-x86-64.so.2");
+        match self.libc_linkage {
+            LibcLinkage::Dynamic => {
+                cmd.arg("-dynamic-linker")
+                    .arg("/lib64/ld-linux-x86-64.so.2");
+            }
+            LibcLinkage::Static => {
+                cmd.arg("-static");
+            }
+        }
 
         // Add object files
         for obj_file in object_files {
@@ -90,8 +125,20 @@ impl Assembler {
             .arg("-L/usr/lib64");
 
         // Link with C library and math library
-        cmd.arg("-lc");
-        cmd.arg("-lm");  // Math library for sin, cos, pow, sqrt, etc.
+        match self.libc_linkage {
+            LibcLinkage::Dynamic => {
+                cmd.arg("-lc");
+                cmd.arg("-lm");  // Math library for sin, cos, pow, sqrt, etc.
+            }
+            LibcLinkage::Static => {
+                // libc.a leans on libgcc for unwinding, and the archives
+                // reference each other, so they're searched as a group
+                if let Some(gcc_dir) = gcc_lib_dir() {
+                    cmd.arg(format!("-L{}", gcc_dir.display()));
+                }
+                cmd.args(["--start-group", "-lc", "-lm", "-lgcc", "-lgcc_eh", "--end-group"]);
+            }
+        }
 
         // Add CRT terminator files
         if std::path::Path::new("/usr/lib/crtn.o").exists() {
@@ -101,15 +148,7 @@ impl Assembler {
         }
 
         cmd.arg("-o").arg(output_exe);
-
-        let status = cmd.status()
-            .map_err(|e| format!("Failed to invoke linker (ld): {}", e))?;
-
-        if !status.success() {
-            return Err("Linker failed".to_string());
-        }
-
-        Ok(())
+        cmd
     }
 
     /// Complete compilation pipeline: assembly → object → executable
@@ -133,6 +172,24 @@ impl Assembler {
     }
 }
 
+/// The newest GCC's library directory, where `libgcc.a` and
+/// `libgcc_eh.a` live for static links
+fn gcc_lib_dir() -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir("/usr/lib/gcc/x86_64-linux-gnu")
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|dir| dir.join("libgcc_eh.a").exists())
+        .collect();
+    dirs.sort_by_key(|dir| {
+        dir.file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.split('.').next())
+            .and_then(|major| major.parse::<u32>().ok())
+            .unwrap_or(0)
+    });
+    dirs.pop()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
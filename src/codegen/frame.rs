@@ -0,0 +1,60 @@
+//! # Leaf Frame Omission
+//!
+//! Every function opens with `push rbp; mov rbp, rsp` and closes with
+//! `mov rsp, rbp; pop rbp`. A function that calls nothing and never addresses
+//! its stack frame gets nothing out of that, so from `-O2` this pass drops the
+//! frame of such leaves. Profilers that unwind through RBP
+//! (`perf --call-graph fp`) lose the leaf's caller when it's missing, which is
+//! why `CompilationConfig::force_frame_pointers` turns the pass off.
+
+use super::{Register, X86Instruction, X86Operand};
+
+/// Drop the frame setup and teardown of the function whose instructions start
+/// at `start` when it is a leaf that never touches RBP or RSP otherwise.
+/// Returns whether the frame was dropped.
+pub fn omit_leaf_frame(instructions: &mut Vec<X86Instruction>, start: usize) -> bool {
+    let body = &instructions[start..];
+    let is_leaf = body.iter().all(|instr| match instr {
+        X86Instruction::Call { .. } | X86Instruction::CallIndirect { .. } => false,
+        X86Instruction::Label { .. }
+        | X86Instruction::Jmp { .. }
+        | X86Instruction::Je { .. }
+        | X86Instruction::Jne { .. }
+        | X86Instruction::Jl { .. }
+        | X86Instruction::Jle { .. }
+        | X86Instruction::Jg { .. }
+        | X86Instruction::Jge { .. } => true,
+        instr if is_frame_instruction(instr) => true,
+        X86Instruction::Push { .. } | X86Instruction::Pop { .. } => false,
+        instr => {
+            let text = instr.to_string();
+            !text.contains("rbp") && !text.contains("rsp")
+        }
+    });
+    if !is_leaf {
+        return false;
+    }
+    let mut index = start;
+    instructions.retain(|instr| {
+        let keep = index < start || !is_frame_instruction(instr);
+        index += 1;
+        keep
+    });
+    true
+}
+
+/// `push rbp`, `mov rbp, rsp`, `sub rsp, N`, `mov rsp, rbp` or `pop rbp`
+fn is_frame_instruction(instr: &X86Instruction) -> bool {
+    let register = |operand: &X86Operand, expected: Register| {
+        matches!(operand, X86Operand::Register(reg) if *reg == expected)
+    };
+    match instr {
+        X86Instruction::Push { reg } | X86Instruction::Pop { reg } => *reg == Register::RBP,
+        X86Instruction::Mov { dst, src } => {
+            (register(dst, Register::RBP) && register(src, Register::RSP))
+                || (register(dst, Register::RSP) && register(src, Register::RBP))
+        }
+        X86Instruction::Sub { dst, src } => register(dst, Register::RSP) && matches!(src, X86Operand::Immediate(_)),
+        _ => false,
+    }
+}
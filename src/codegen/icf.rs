@@ -0,0 +1,95 @@
+//! # Identical Code Folding
+//!
+//! Monomorphization often produces specializations whose machine code is
+//! exactly the same (`first::<i64>` and `first::<bool>` when the `T` value is
+//! never touched). This pass compares the generated instructions of every
+//! function with their block labels normalized, keeps the first of each group
+//! of identical functions, and points calls to the others at it. A folded
+//! function's symbol stays defined as an alias of the kept copy, so function
+//! pointers and exported names still resolve.
+
+use super::X86Instruction;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// A function folded into an identical one that was kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldedFunction {
+    pub name: String,
+    pub kept: String,
+}
+
+/// Fold identical functions in `instructions`, where `functions` gives each
+/// function's symbol and the range of its instructions, in emission order
+pub fn fold_identical_functions(
+    instructions: &mut Vec<X86Instruction>,
+    functions: &[(String, Range<usize>)],
+) -> Vec<FoldedFunction> {
+    let mut kept_bodies: HashMap<Vec<String>, &str> = HashMap::new();
+    let mut folded = Vec::new();
+    for (name, range) in functions {
+        let body = normalized_body(name, &instructions[range.clone()]);
+        match kept_bodies.get(&body) {
+            Some(kept) => folded.push(FoldedFunction { name: name.clone(), kept: kept.to_string() }),
+            None => {
+                kept_bodies.insert(body, name);
+            }
+        }
+    }
+    if folded.is_empty() {
+        return folded;
+    }
+
+    let targets: HashMap<&str, &str> = folded.iter().map(|f| (f.name.as_str(), f.kept.as_str())).collect();
+    let removed: Vec<&Range<usize>> = functions
+        .iter()
+        .filter(|(name, _)| targets.contains_key(name.as_str()))
+        .map(|(_, range)| range)
+        .collect();
+    let mut index = 0;
+    instructions.retain(|_| {
+        let keep = !removed.iter().any(|range| range.contains(&index));
+        index += 1;
+        keep
+    });
+    for instr in instructions.iter_mut() {
+        if let X86Instruction::Call { func } = instr {
+            if let Some(kept) = targets.get(func.as_str()) {
+                *func = kept.to_string();
+            }
+        }
+    }
+    folded
+}
+
+/// The function's instructions as text, with its own name and the labels it
+/// defines replaced by placeholders so two copies compare equal
+fn normalized_body(name: &str, body: &[X86Instruction]) -> Vec<String> {
+    let mut labels: HashMap<&str, String> = HashMap::new();
+    labels.insert(name, "<self>".to_string());
+    for instr in body {
+        if let X86Instruction::Label { name: label } = instr {
+            let placeholder = format!("<L{}>", labels.len());
+            labels.entry(label).or_insert(placeholder);
+        }
+    }
+    let rename = |label: &str| labels.get(label).cloned().unwrap_or_else(|| label.to_string());
+    body.iter()
+        .map(|instr| {
+            let normalized = match instr {
+                X86Instruction::Label { name } => X86Instruction::Label { name: rename(name) },
+                X86Instruction::Jmp { label } => X86Instruction::Jmp { label: rename(label) },
+                X86Instruction::Je { label } => X86Instruction::Je { label: rename(label) },
+                X86Instruction::Jne { label } => X86Instruction::Jne { label: rename(label) },
+                X86Instruction::Jl { label } => X86Instruction::Jl { label: rename(label) },
+                X86Instruction::Jle { label } => X86Instruction::Jle { label: rename(label) },
+                X86Instruction::Jg { label } => X86Instruction::Jg { label: rename(label) },
+                X86Instruction::Jge { label } => X86Instruction::Jge { label: rename(label) },
+                X86Instruction::Call { func } => X86Instruction::Call { func: rename(func) },
+                X86Instruction::Lea { dst, src } => X86Instruction::Lea { dst: dst.clone(), src: rename(src) },
+                other => other.clone(),
+            };
+            normalized.to_string()
+        })
+        .collect()
+}
@@ -1167,6 +1167,8 @@ impl FusionMirGenerator {
             name: func_name,
             params,
             return_type,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
             basic_blocks,
         }
     }
@@ -2268,6 +2270,7 @@ mod tests {
             params: vec![("x".to_string(), HirType::Int64)],
             body_statements: vec![
                 Statement {
+                    id: 0,
                     place: Place::Local("temp".to_string()),
                     rvalue: Rvalue::BinaryOp(
                         BinaryOp::Multiply,
@@ -2582,6 +2585,7 @@ mod tests {
             params: vec![("x".to_string(), HirType::Int64)],
             body_statements: vec![
                 Statement {
+                    id: 0,
                     place: Place::Local("result".to_string()),
                     rvalue: Rvalue::BinaryOp(
                         BinaryOp::Multiply,
@@ -2621,6 +2625,7 @@ mod tests {
             params: vec![("n".to_string(), HirType::Int64)],
             body_statements: vec![
                 Statement {
+                    id: 0,
                     place: Place::Local("cmp".to_string()),
                     rvalue: Rvalue::BinaryOp(
                         BinaryOp::GreaterEqual,
@@ -87,6 +87,8 @@ impl MirLowerer {
             name,
             params: param_types,
             return_type,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
             basic_blocks: blocks,
         });
     }
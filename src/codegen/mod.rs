@@ -40,9 +40,15 @@ pub mod smart_pointer_codegen;
 pub mod vtable_generation;
 pub mod dynamic_dispatch;
 pub mod stdlib_codegen;
+pub mod string_interner;
+pub mod icf;
+pub mod frame;
+pub mod tac;
 
+use crate::config::AsmSyntax;
 use crate::mir::{Mir, MirFunction, Statement, Terminator};
 use crate::runtime;
+use string_interner::StringInterner;
 use crate::lowering::{get_struct_field_index, get_struct_field_count};
 use std::collections::HashMap;
 use std::fmt;
@@ -136,6 +142,131 @@ impl fmt::Display for X86Operand {
     }
 }
 
+impl X86Operand {
+    /// AT&T spelling of the operand: `%rax`, `$5`, `-8(%rbp)`
+    pub fn to_att(&self) -> String {
+        match self {
+            X86Operand::Register(reg) => format!("%{}", reg),
+            X86Operand::Immediate(val) => format!("${}", val),
+            X86Operand::Memory { base, offset: 0 } => format!("(%{})", base),
+            X86Operand::Memory { base, offset } => format!("{}(%{})", offset, base),
+        }
+    }
+}
+
+/// Low byte register of `reg` for `setcc`/`movzx`, as the Intel output names it
+fn byte_register(reg: Register) -> Option<&'static str> {
+    match reg {
+        Register::RAX => Some("al"),
+        Register::RBX => Some("bl"),
+        Register::RCX => Some("cl"),
+        Register::RDX => Some("dl"),
+        _ => None,
+    }
+}
+
+/// Width and signedness of an integer type narrower than a register
+fn narrow_integer(ty: &crate::lowering::HirType) -> Option<(u32, bool)> {
+    use crate::lowering::HirType;
+    match ty {
+        HirType::Int32 => Some((32, true)),
+        HirType::UInt32 => Some((32, false)),
+        HirType::Bool => Some((8, false)),
+        HirType::Named(name) => match name.as_str() {
+            "i16" => Some((16, true)),
+            "i8" => Some((8, true)),
+            "u16" => Some((16, false)),
+            "u8" => Some((8, false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The name of the low `bits` (32, 16 or 8) of a register
+fn sized_register(reg: Register, bits: u32) -> &'static str {
+    let names: [&'static str; 3] = match reg {
+        Register::RAX => ["eax", "ax", "al"],
+        Register::RBX => ["ebx", "bx", "bl"],
+        Register::RCX => ["ecx", "cx", "cl"],
+        Register::RDX => ["edx", "dx", "dl"],
+        Register::RSI => ["esi", "si", "sil"],
+        Register::RDI => ["edi", "di", "dil"],
+        Register::RBP => ["ebp", "bp", "bpl"],
+        Register::RSP => ["esp", "sp", "spl"],
+        Register::R8 => ["r8d", "r8w", "r8b"],
+        Register::R9 => ["r9d", "r9w", "r9b"],
+        Register::R10 => ["r10d", "r10w", "r10b"],
+        Register::R11 => ["r11d", "r11w", "r11b"],
+        Register::R12 => ["r12d", "r12w", "r12b"],
+        Register::R13 => ["r13d", "r13w", "r13b"],
+        Register::R14 => ["r14d", "r14w", "r14b"],
+        Register::R15 => ["r15d", "r15w", "r15b"],
+    };
+    match bits {
+        32 => names[0],
+        16 => names[1],
+        _ => names[2],
+    }
+}
+
+/// Whether `name` is one of the print routines the hosted runtime builds on printf
+fn is_libc_print(name: &str) -> bool {
+    matches!(
+        name,
+        "__builtin_println" | "__builtin_printf" | "printf" | "gaia_print_str" | "gaia_print_i32"
+            | "gaia_print_i64" | "gaia_print_bool" | "gaia_print_f64"
+    )
+}
+
+/// The syscall-backed routine that replaces a print call in no_std mode,
+/// if there is one
+fn no_std_print_symbol(name: &str) -> Option<&'static str> {
+    match name {
+        "__builtin_println" => Some("gaia_write_line"),
+        "gaia_print_str" => Some("gaia_write_str"),
+        "gaia_print_i32" => Some("gaia_write_i32_line"),
+        "gaia_print_i64" => Some("gaia_write_i64_line"),
+        _ => None,
+    }
+}
+
+/// A `printf` of a lone `{}` argument as the typed print routine that
+/// prints the same thing, so no_std mode can route it to a syscall wrapper
+fn plain_printf_routine(func_name: &str, args: &[crate::mir::Operand]) -> Option<(&'static str, Vec<crate::mir::Operand>)> {
+    if func_name != "printf" || args.len() != 2 {
+        return None;
+    }
+    let crate::mir::Operand::Constant(crate::mir::Constant::String(format)) = &args[0] else {
+        return None;
+    };
+    let routine = match format.as_str() {
+        "%d\n" => "gaia_print_i32",
+        "%ld\n" | "%lld\n" => "gaia_print_i64",
+        "%s\n" => "__builtin_println",
+        "%s" => "gaia_print_str",
+        _ => return None,
+    };
+    Some((routine, vec![args[1].clone()]))
+}
+
+/// Rewrite an Intel operand string (as used by the SSE instructions, e.g.
+/// `xmm0`, `qword ptr [rbp - 8]`, `qword ptr [rip + label]`) in AT&T syntax
+fn intel_operand_to_att(operand: &str) -> String {
+    let operand = operand.trim_start_matches("qword ptr ");
+    let Some(address) = operand.strip_prefix('[').and_then(|a| a.strip_suffix(']')) else {
+        return format!("%{}", operand);
+    };
+    // `[base + displacement]`, where the displacement is a number or a label
+    if let Some((base, displacement)) = address.split_once(" + ") {
+        return format!("{}(%{})", displacement, base);
+    }
+    match address.split_once(" - ") {
+        Some((base, offset)) => format!("-{}(%{})", offset, base),
+        None => format!("(%{})", address),
+    }
+}
+
 /// x86-64 instruction
 #[derive(Debug, Clone)]
 pub enum X86Instruction {
@@ -185,10 +316,15 @@ pub enum X86Instruction {
     Setge { dst: X86Operand },
     /// call function
     Call { func: String },
+    /// call reg - indirect call through a function pointer
+    CallIndirect { reg: Register },
     /// ret
     Ret,
     /// movzx dst, src - move with zero extension
     Movzx { dst: Register, src: Register },
+    /// movsx/movsxd (signed) or movzx (unsigned) dst, src - widen the low
+    /// `bits` of src to all 64 bits of dst
+    Extend { dst: Register, src: Register, bits: u32, signed: bool },
     /// push reg
     Push { reg: Register },
     /// pop reg
@@ -221,6 +357,8 @@ pub enum X86Instruction {
     Mulsd { dst: String, src: String },
     /// divsd dst, src (divide scalar double precision floating point)
     Divsd { dst: String, src: String },
+    /// sqrtsd dst, src (square root scalar double precision floating point)
+    Sqrtsd { dst: String, src: String },
 }
 
 impl fmt::Display for X86Instruction {
@@ -313,8 +451,19 @@ impl fmt::Display for X86Instruction {
                 write!(f, "    setge {}", operand)
             }
             X86Instruction::Call { func } => write!(f, "    call {}", func),
+            X86Instruction::CallIndirect { reg } => write!(f, "    call {}", reg),
             X86Instruction::Ret => write!(f, "    ret"),
             X86Instruction::Movzx { dst, src } => write!(f, "    movzx {}, {}", dst, src),
+            X86Instruction::Extend { dst, src, bits, signed } => {
+                let narrow = sized_register(*src, *bits);
+                match (signed, bits) {
+                    (true, 32) => write!(f, "    movsxd {}, {}", dst, narrow),
+                    (true, _) => write!(f, "    movsx {}, {}", dst, narrow),
+                    // Writing a 32-bit register clears the upper half
+                    (false, 32) => write!(f, "    mov {}, {}", sized_register(*dst, 32), narrow),
+                    (false, _) => write!(f, "    movzx {}, {}", sized_register(*dst, 32), narrow),
+                }
+            }
             X86Instruction::Push { reg } => write!(f, "    push {}", reg),
             X86Instruction::Pop { reg } => write!(f, "    pop {}", reg),
             X86Instruction::Label { name } => write!(f, "{}:", name),
@@ -331,6 +480,90 @@ impl fmt::Display for X86Instruction {
             X86Instruction::Subsd { dst, src } => write!(f, "    subsd {}, {}", dst, src),
             X86Instruction::Mulsd { dst, src } => write!(f, "    mulsd {}, {}", dst, src),
             X86Instruction::Divsd { dst, src } => write!(f, "    divsd {}, {}", dst, src),
+            X86Instruction::Sqrtsd { dst, src } => write!(f, "    sqrtsd {}, {}", dst, src),
+        }
+    }
+}
+
+impl X86Instruction {
+    /// AT&T spelling of the instruction: operands reversed, `%`/`$`
+    /// prefixes and size suffixes. `Display` gives the Intel spelling.
+    pub fn to_att(&self) -> String {
+        let binary = |mnemonic: &str, dst: &X86Operand, src: &X86Operand| {
+            format!("    {}q {}, {}", mnemonic, src.to_att(), dst.to_att())
+        };
+        let sse = |mnemonic: &str, dst: &str, src: &str| {
+            format!("    {} {}, {}", mnemonic, intel_operand_to_att(src), intel_operand_to_att(dst))
+        };
+        let set = |mnemonic: &str, dst: &X86Operand| {
+            let operand = match dst {
+                X86Operand::Register(reg) => byte_register(*reg).map(|name| format!("%{}", name)),
+                _ => None,
+            };
+            format!("    {} {}", mnemonic, operand.unwrap_or_else(|| dst.to_att()))
+        };
+        match self {
+            X86Instruction::Mov { dst, src } => binary("mov", dst, src),
+            X86Instruction::Lea { dst, src } => format!("    leaq {}(%rip), {}", src, dst.to_att()),
+            X86Instruction::LeaMemory { dst, base, offset } => {
+                let address = X86Operand::Memory { base: *base, offset: *offset };
+                format!("    leaq {}, {}", address.to_att(), dst.to_att())
+            }
+            X86Instruction::Add { dst, src } => binary("add", dst, src),
+            X86Instruction::Sub { dst, src } => binary("sub", dst, src),
+            X86Instruction::IMul { dst, src } => binary("imul", dst, src),
+            X86Instruction::IDiv { src } => format!("    idivq {}", src.to_att()),
+            X86Instruction::Xor { dst, src } => binary("xor", dst, src),
+            X86Instruction::Cmp { dst, src } => binary("cmp", dst, src),
+            X86Instruction::And { dst, src } => binary("and", dst, src),
+            X86Instruction::Or { dst, src } => binary("or", dst, src),
+            X86Instruction::Shl { dst, src } => binary("shl", dst, src),
+            X86Instruction::Shr { dst, src } => binary("shr", dst, src),
+            X86Instruction::Sar { dst, src } => binary("sar", dst, src),
+            X86Instruction::Neg { dst } => format!("    negq {}", dst.to_att()),
+            X86Instruction::Sete { dst } => set("sete", dst),
+            X86Instruction::Setne { dst } => set("setne", dst),
+            X86Instruction::Setl { dst } => set("setl", dst),
+            X86Instruction::Setle { dst } => set("setle", dst),
+            X86Instruction::Setg { dst } => set("setg", dst),
+            X86Instruction::Setge { dst } => set("setge", dst),
+            X86Instruction::Movzx { dst, src } => match byte_register(*src) {
+                Some(byte) => format!("    movzbq %{}, %{}", byte, dst),
+                None => format!("    movq %{}, %{}", src, dst),
+            },
+            X86Instruction::Extend { dst, src, bits, signed } => {
+                let narrow = sized_register(*src, *bits);
+                match (signed, bits) {
+                    (true, 32) => format!("    movslq %{}, %{}", narrow, dst),
+                    (true, 16) => format!("    movswq %{}, %{}", narrow, dst),
+                    (true, _) => format!("    movsbq %{}, %{}", narrow, dst),
+                    (false, 32) => format!("    movl %{}, %{}", narrow, sized_register(*dst, 32)),
+                    (false, 16) => format!("    movzwl %{}, %{}", narrow, sized_register(*dst, 32)),
+                    (false, _) => format!("    movzbl %{}, %{}", narrow, sized_register(*dst, 32)),
+                }
+            }
+            X86Instruction::Push { reg } => format!("    pushq %{}", reg),
+            X86Instruction::Pop { reg } => format!("    popq %{}", reg),
+            X86Instruction::CallIndirect { reg } => format!("    call *%{}", reg),
+            X86Instruction::Cqo => "    cqto".to_string(),
+            X86Instruction::Movsd { dst, src } => sse("movsd", dst, src),
+            X86Instruction::Addsd { dst, src } => sse("addsd", dst, src),
+            X86Instruction::Subsd { dst, src } => sse("subsd", dst, src),
+            X86Instruction::Mulsd { dst, src } => sse("mulsd", dst, src),
+            X86Instruction::Divsd { dst, src } => sse("divsd", dst, src),
+            X86Instruction::Sqrtsd { dst, src } => sse("sqrtsd", dst, src),
+            // Jumps, calls, labels and operand-less instructions read the same
+            X86Instruction::Jmp { .. }
+            | X86Instruction::Je { .. }
+            | X86Instruction::Jne { .. }
+            | X86Instruction::Jl { .. }
+            | X86Instruction::Jle { .. }
+            | X86Instruction::Jg { .. }
+            | X86Instruction::Jge { .. }
+            | X86Instruction::Call { .. }
+            | X86Instruction::Ret
+            | X86Instruction::Label { .. }
+            | X86Instruction::Nop => self.to_string(),
         }
     }
 }
@@ -372,6 +605,28 @@ impl RegisterAllocator {
     }
 }
 
+/// Symbols a compiled unit defines and calls, for linking diagnostics
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    /// Labels of the unit's own functions, in emission order
+    pub defined: Vec<String>,
+    /// Call targets the unit doesn't define (runtime routines, stdlib, libc), sorted
+    pub referenced: Vec<String>,
+}
+
+impl fmt::Display for SymbolTable {
+    /// One symbol per line in `nm` style: `T` for defined, `U` for referenced
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for symbol in &self.defined {
+            writeln!(f, "T {}", symbol)?;
+        }
+        for symbol in &self.referenced {
+            writeln!(f, "U {}", symbol)?;
+        }
+        Ok(())
+    }
+}
+
 /// x86-64 code generator
 pub struct Codegen {
     instructions: Vec<X86Instruction>,
@@ -386,7 +641,7 @@ pub struct Codegen {
     min_collection_offset: i64,
     /// Size of the collection at min_collection_offset (for proper collision detection)
     collection_size: i64,
-    string_constants: HashMap<String, String>,
+    strings: StringInterner,
     float_constants: HashMap<String, f64>, // label -> f64 value
     /// Maps variable name to struct name (for field index lookup)
     var_struct_types: HashMap<String, String>,
@@ -403,6 +658,40 @@ pub struct Codegen {
     /// When Index returns a pointer for a struct array, we register the destination temporary
     /// This allows field access on the temporary to know it's dereferencing an array element pointer
     temp_array_element_pointers: HashMap<String, String>,
+    /// Locals holding the address of a struct's data, such as a `&self` or
+    /// `&mut T` parameter: var_name -> struct name. Field `i` is at `ptr - 8*i`.
+    struct_pointer_types: HashMap<String, String>,
+    /// Element type of each Vec local, from the MIR function and carried
+    /// along copies: vec_var -> element type
+    vec_element_types: HashMap<String, crate::lowering::HirType>,
+    /// Bit width of each narrow integer local, from the MIR function
+    integer_widths: HashMap<String, u32>,
+    /// Syntax the instructions are printed in
+    syntax: AsmSyntax,
+    /// Emit only the program's functions, without the `main` wrapper or runtime
+    no_std: bool,
+    /// Panic on shifts by at least the operand's bit width instead of letting
+    /// the hardware mask the count
+    overflow_checks: bool,
+    /// Push each function's name on the runtime's shadow stack on entry and
+    /// pop it on return, so a panic can print a backtrace
+    backtrace: bool,
+    /// Guard the frames of functions with stack arrays with a canary
+    stack_protector: bool,
+    /// The current function's canary slot and the label its failed check
+    /// jumps to, when it has one
+    stack_canary: Option<(i64, String)>,
+    /// How many instructions each function's body came to, by MIR name
+    instruction_counts: HashMap<String, usize>,
+    /// Host builtins' names and the external symbols their calls go to
+    builtin_symbols: HashMap<String, String>,
+    /// Stdlib functions compiled into the unit, which it doesn't define itself
+    library_functions: std::collections::HashSet<String>,
+    /// Merge functions whose generated code is identical
+    fold_identical_functions: bool,
+    /// Drop the RBP frame of functions that call nothing and keep nothing
+    /// on the stack
+    omit_leaf_frames: bool,
 }
 
 impl Codegen {
@@ -418,13 +707,170 @@ impl Codegen {
             min_collection_offset: i64::MAX,
             float_constants: HashMap::new(),
             collection_size: 0,
-            string_constants: HashMap::new(),
+            strings: StringInterner::new(),
             var_struct_types: HashMap::new(),
             array_variables: HashMap::new(),
             function_return_types: HashMap::new(),
             multifield_struct_returns: std::collections::HashSet::new(),
             struct_field_counts: HashMap::new(),
             temp_array_element_pointers: HashMap::new(),
+            struct_pointer_types: HashMap::new(),
+            vec_element_types: HashMap::new(),
+            integer_widths: HashMap::new(),
+            syntax: AsmSyntax::Intel,
+            no_std: false,
+            overflow_checks: false,
+            backtrace: false,
+            stack_protector: false,
+            stack_canary: None,
+            instruction_counts: HashMap::new(),
+            builtin_symbols: HashMap::new(),
+            library_functions: std::collections::HashSet::new(),
+            fold_identical_functions: false,
+            omit_leaf_frames: false,
+        }
+    }
+
+    /// Print the generated instructions in `syntax` instead of Intel syntax
+    pub fn with_syntax(mut self, syntax: AsmSyntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Leave out the `main` wrapper and runtime; a freestanding `main` keeps its own name
+    pub fn with_no_std(mut self, no_std: bool) -> Self {
+        self.no_std = no_std;
+        self
+    }
+
+    /// Check shift amounts at runtime, panicking like rustc's debug builds
+    pub fn with_overflow_checks(mut self, overflow_checks: bool) -> Self {
+        self.overflow_checks = overflow_checks;
+        self
+    }
+
+    /// Record calls for panic backtraces
+    pub fn with_backtrace(mut self, backtrace: bool) -> Self {
+        self.backtrace = backtrace;
+        self
+    }
+
+    /// Check a canary in the frame of each function with a stack array
+    pub fn with_stack_protector(mut self, stack_protector: bool) -> Self {
+        self.stack_protector = stack_protector;
+        self
+    }
+
+    /// Emit one copy of functions whose generated code is identical and
+    /// call it in place of the others
+    pub fn with_identical_function_folding(mut self, enabled: bool) -> Self {
+        self.fold_identical_functions = enabled;
+        self
+    }
+
+    /// Leave out the frame pointer setup in leaf functions that never
+    /// address their stack frame
+    pub fn with_leaf_frame_omission(mut self, enabled: bool) -> Self {
+        self.omit_leaf_frames = enabled;
+        self
+    }
+
+    /// Emit calls to host builtins as calls to their external symbols
+    pub fn with_custom_builtins(mut self, builtins: &[crate::utilities::builtins::CustomBuiltin]) -> Self {
+        for builtin in builtins {
+            self.builtin_symbols.insert(builtin.name.clone(), builtin.symbol.clone());
+        }
+        self
+    }
+
+    /// List these stdlib functions as referenced rather than defined in the
+    /// symbol table, like the runtime routines
+    pub fn with_library_functions(mut self, names: &[String]) -> Self {
+        let symbols: Vec<String> = names.iter().map(|name| self.symbol_name(name)).collect();
+        self.library_functions.extend(symbols);
+        self
+    }
+
+    /// Load `__stack_chk_guard` into R11. RAX may hold the return value.
+    fn emit_load_stack_guard(&mut self) {
+        self.instructions.push(X86Instruction::Lea {
+            dst: X86Operand::Register(Register::R11),
+            src: "__stack_chk_guard".to_string(),
+        });
+        self.instructions.push(X86Instruction::Mov {
+            dst: X86Operand::Register(Register::R11),
+            src: X86Operand::Memory { base: Register::R11, offset: 0 },
+        });
+    }
+
+    /// Before a return, make sure the current function's canary is intact
+    fn emit_stack_canary_check(&mut self) {
+        if let Some((offset, fail_label)) = self.stack_canary.clone() {
+            self.emit_load_stack_guard();
+            self.instructions.push(X86Instruction::Cmp {
+                dst: X86Operand::Memory { base: Register::RBP, offset },
+                src: X86Operand::Register(Register::R11),
+            });
+            self.instructions.push(X86Instruction::Jne { label: fail_label });
+        }
+    }
+
+    /// Pop the current function off the backtrace shadow stack before a
+    /// return. The runtime routine leaves RAX and the argument registers alone.
+    fn emit_backtrace_pop(&mut self) {
+        if self.backtrace {
+            self.instructions.push(X86Instruction::Call { func: "gaia_backtrace_pop".to_string() });
+        }
+    }
+
+    /// With overflow checks on, panic unless the shift count in RCX is within
+    /// 0..`bits`. x86 masks the count to 6 bits, so `x << 64` would shift by 0.
+    fn emit_shift_count_check(&mut self, bits: u32, message: &str) {
+        if !self.overflow_checks {
+            return;
+        }
+        let fail_label = self.new_label();
+        let ok_label = self.new_label();
+        self.instructions.push(X86Instruction::Cmp {
+            dst: X86Operand::Register(Register::RCX),
+            src: X86Operand::Immediate(bits as i64),
+        });
+        self.instructions.push(X86Instruction::Jge { label: fail_label.clone() });
+        self.instructions.push(X86Instruction::Cmp {
+            dst: X86Operand::Register(Register::RCX),
+            src: X86Operand::Immediate(0),
+        });
+        self.instructions.push(X86Instruction::Jge { label: ok_label.clone() });
+        self.instructions.push(X86Instruction::Label { name: fail_label });
+        self.instructions.push(X86Instruction::Lea {
+            dst: X86Operand::Register(Register::RDI),
+            src: message.to_string(),
+        });
+        self.instructions.push(X86Instruction::Call { func: "panic".to_string() });
+        self.instructions.push(X86Instruction::Label { name: ok_label });
+    }
+
+    /// How many bits the integer `operand` holds; 64 unless it's a narrow local
+    fn operand_bits(&self, operand: &crate::mir::Operand) -> u32 {
+        match operand {
+            crate::mir::Operand::Copy(crate::mir::Place::Local(name))
+            | crate::mir::Operand::Move(crate::mir::Place::Local(name)) => {
+                self.integer_widths.get(name).copied().unwrap_or(64)
+            }
+            _ => 64,
+        }
+    }
+
+    /// The assembly label for a MIR function. A hosted `main` becomes
+    /// `gaia_main`, which the runtime's `main` wrapper calls.
+    fn symbol_name(&self, name: &str) -> String {
+        if name == "main" && !self.no_std {
+            "gaia_main".to_string()
+        } else if name.contains("::") {
+            // Mangle qualified names: Point::new -> Point_impl_new
+            name.replace("::", "_impl_")
+        } else {
+            name.to_string()
         }
     }
 
@@ -433,10 +879,110 @@ impl Codegen {
         let mut asm = String::new();
         
         // Assembly header
-        asm.push_str(".intel_syntax noprefix\n");
+        asm.push_str(match self.syntax {
+            AsmSyntax::Intel => ".intel_syntax noprefix\n",
+            AsmSyntax::Att => ".att_syntax\n",
+        });
         asm.push_str(".text\n");
-        asm.push_str(".globl gaia_main\n");
-        asm.push_str(".globl main\n\n");
+        if self.no_std {
+            // Everything is exported so the embedding project can call it
+            for func in &mir.functions {
+                asm.push_str(&format!(".globl {}\n", self.symbol_name(&func.name)));
+            }
+            asm.push('\n');
+        } else {
+            asm.push_str(".globl gaia_main\n");
+            asm.push_str(".globl main\n\n");
+        }
+        
+        let function_ranges = self.generate_functions(mir)?;
+        let folded = if self.fold_identical_functions {
+            icf::fold_identical_functions(&mut self.instructions, &function_ranges)
+        } else {
+            Vec::new()
+        };
+        
+        // Convert instructions to assembly
+        for instr in &self.instructions {
+            match self.syntax {
+                AsmSyntax::Intel => asm.push_str(&format!("{}\n", instr)),
+                AsmSyntax::Att => asm.push_str(&format!("{}\n", instr.to_att())),
+            }
+        }
+        for function in &folded {
+            asm.push_str(&format!(".set {}, {}\n", function.name, function.kept));
+        }
+        
+        // Add data section for mutable static variables
+        if mir.globals.iter().any(|g| g.is_static && g.is_mutable) {
+            asm.push_str("\n.section .data\n");
+            for global in &mir.globals {
+                if global.is_static && global.is_mutable {
+                    asm.push_str(&format!("    {}: .quad {}\n", global.name, global.value));
+                }
+            }
+        }
+        
+        // Add rodata section with string constants and const values
+        let has_rodata_globals = mir.globals.iter().any(|g| !g.is_static || !g.is_mutable);
+        if !self.strings.is_empty() || !self.float_constants.is_empty() || has_rodata_globals {
+            asm.push_str("\n.section .rodata\n");
+            
+            // Add read-only globals (constants and immutable statics)
+            for global in &mir.globals {
+                if !global.is_static || !global.is_mutable {
+                    asm.push_str(&format!("    {}: .quad {}\n", global.name, global.value));
+                }
+            }
+            
+            // Add float constants, in label order so the output is deterministic
+            let mut float_constants: Vec<_> = self.float_constants.iter().collect();
+            float_constants.sort_by(|a, b| a.0.cmp(b.0));
+            for (float_key, float_value) in float_constants {
+                // Use .quad to store 64-bit floating point as bits
+                let bits = float_value.to_bits();
+                asm.push_str(&format!("    {}: .quad {}\n", float_key, bits));
+            }
+            
+            // Add string constants
+            for (label, string) in self.strings.entries() {
+                let escaped = string
+                    .replace("\\", "\\\\")
+                    .replace("\"", "\\\"")
+                    .replace("\n", "\\n")
+                    .replace("\t", "\\t")
+                    .replace("\r", "\\r");
+                asm.push_str(&format!("    {}: .string \"{}\"\n", label, escaped));
+            }
+        }
+        
+        if self.no_std {
+            // Without libc, printing goes through the raw syscall wrappers
+            asm.push('\n');
+            if self.syntax == AsmSyntax::Att {
+                asm.push_str(".intel_syntax noprefix\n");
+            }
+            asm.push_str(&runtime::generate_syscall_io());
+            return Ok(asm);
+        }
+        
+        // Include runtime support, which is written in Intel syntax
+        asm.push_str("\n");
+        if self.syntax == AsmSyntax::Att {
+            asm.push_str(".intel_syntax noprefix\n");
+        }
+        asm.push_str(&runtime::generate_main_wrapper());
+        asm.push_str("\n");
+        asm.push_str(&runtime::generate_runtime_assembly());
+        
+        Ok(asm)
+    }
+
+    /// Generate the instructions of every function in `mir`, returning the
+    /// symbol and instruction range of each, in order
+    fn generate_functions(&mut self, mir: &Mir) -> CodegenResult<Vec<(String, std::ops::Range<usize>)>> {
+        // Label every string literal up front, in program order
+        self.strings = StringInterner::from_mir(mir);
         
         // Pre-pass: build function return type map and struct field counts
         // First, scan all functions to find aggregate statements and count fields
@@ -452,13 +998,7 @@ impl Codegen {
         
         // Now build function return type map
         for func in &mir.functions {
-            let func_name = if func.name == "main" {
-                "gaia_main".to_string()
-            } else if func.name.contains("::") {
-                func.name.replace("::", "_impl_")
-            } else {
-                func.name.clone()
-            };
+            let func_name = self.symbol_name(&func.name);
             self.function_return_types.insert(func_name.clone(), func.return_type.clone());
             
             // Track if this function returns a struct or array of structs
@@ -491,63 +1031,57 @@ impl Codegen {
         }
         
         // Generate code for each function
+        let mut function_ranges = Vec::new();
         for func in &mir.functions {
+            let start = self.instructions.len();
             self.generate_function(func)?;
-        }
-        
-        // Convert instructions to assembly
-        for instr in &self.instructions {
-            asm.push_str(&format!("{}\n", instr));
-        }
-        
-        // Add data section for mutable static variables
-        if mir.globals.iter().any(|g| g.is_static && g.is_mutable) {
-            asm.push_str("\n.section .data\n");
-            for global in &mir.globals {
-                if global.is_static && global.is_mutable {
-                    asm.push_str(&format!("    {}: .quad {}\n", global.name, global.value));
-                }
+            if self.omit_leaf_frames {
+                frame::omit_leaf_frame(&mut self.instructions, start);
             }
+            self.instruction_counts.insert(func.name.clone(), self.instructions.len() - start);
+            function_ranges.push((self.symbol_name(&func.name), start..self.instructions.len()));
         }
-        
-        // Add rodata section with string constants and const values
-        let has_rodata_globals = mir.globals.iter().any(|g| !g.is_static || !g.is_mutable);
-        if !self.string_constants.is_empty() || !self.float_constants.is_empty() || has_rodata_globals {
-            asm.push_str("\n.section .rodata\n");
-            
-            // Add read-only globals (constants and immutable statics)
-            for global in &mir.globals {
-                if !global.is_static || !global.is_mutable {
-                    asm.push_str(&format!("    {}: .quad {}\n", global.name, global.value));
-                }
-            }
-            
-            // Add float constants
-            for (float_key, float_value) in &self.float_constants {
-                // Use .quad to store 64-bit floating point as bits
-                let bits = float_value.to_bits();
-                asm.push_str(&format!("    {}: .quad {}\n", float_key, bits));
-            }
-            
-            // Add string constants
-            for (string, label) in &self.string_constants {
-                let escaped = string
-                    .replace("\\", "\\\\")
-                    .replace("\"", "\\\"")
-                    .replace("\n", "\\n")
-                    .replace("\t", "\\t")
-                    .replace("\r", "\\r");
-                asm.push_str(&format!("    {}: .string \"{}\"\n", label, escaped));
-            }
+        Ok(function_ranges)
+    }
+
+    /// The three-address listing of every function in `mir` (`--emit=tac`),
+    /// read off the instructions `generate` would assemble
+    pub fn generate_tac(&mut self, mir: &Mir) -> CodegenResult<String> {
+        let function_ranges = self.generate_functions(mir)?;
+        let mut out = String::new();
+        for (func, (_, range)) in mir.functions.iter().zip(function_ranges) {
+            let returns_value = !matches!(&func.return_type, crate::lowering::HirType::Tuple(fields) if fields.is_empty());
+            out.push_str(&tac::listing(&func.name, func.params.len(), returns_value, &self.instructions[range]));
+            out.push('\n');
         }
-        
-        // Include runtime support
-        asm.push_str("\n");
-        asm.push_str(&runtime::generate_main_wrapper());
-        asm.push_str("\n");
-        asm.push_str(&runtime::generate_runtime_assembly());
-        
-        Ok(asm)
+        Ok(out)
+    }
+
+    /// The symbols defined and called by the instructions from the last
+    /// `generate`. Only function labels count as definitions; basic block
+    /// labels are local.
+    pub fn symbols(&self) -> SymbolTable {
+        let defined: Vec<String> = self.instructions.iter().filter_map(|instr| match instr {
+            X86Instruction::Label { name }
+                if self.function_return_types.contains_key(name) && !self.library_functions.contains(name) =>
+            {
+                Some(name.clone())
+            }
+            _ => None,
+        }).collect();
+        let mut referenced: Vec<String> = self.instructions.iter().filter_map(|instr| match instr {
+            X86Instruction::Call { func } if !defined.contains(func) => Some(func.clone()),
+            _ => None,
+        }).collect();
+        referenced.sort();
+        referenced.dedup();
+        SymbolTable { defined, referenced }
+    }
+
+    /// The number of instructions generated for each function, keyed by
+    /// its name, to see where the code size goes
+    pub fn instruction_counts(&self) -> &HashMap<String, usize> {
+        &self.instruction_counts
     }
 
     /// Generate code for a function
@@ -558,20 +1092,17 @@ impl Codegen {
          self.struct_data_locations.clear();  // IMPORTANT: Clear struct data locations for new function
          self.array_variables.clear();  // IMPORTANT: Clear array variable registrations
          self.temp_array_element_pointers.clear();  // IMPORTANT: Clear temporary array element pointers
+         self.struct_pointer_types.clear();
+         self.float_stack_offsets.clear();
+         self.vec_element_types = func.vec_element_types.clone();
+         self.integer_widths = func.integer_widths.clone();
+         self.stack_canary = None;
          self.stack_offset = -8;
          self.min_collection_offset = i64::MAX;
          self.collection_size = 0;
         
         // Mangle function names for assembly compatibility
-        // Replace :: with _impl_ for qualified names like Point::new
-        let func_name = if func.name == "main" {
-            "gaia_main".to_string()
-        } else if func.name.contains("::") {
-            // Mangle qualified names: Point::new -> Point_impl_new
-            func.name.replace("::", "_impl_")
-        } else {
-            func.name.clone()
-        };
+        let func_name = self.symbol_name(&func.name);
         
         // Determine if this function needs to use a return buffer (for multi-field struct returns)
         let needs_return_buffer = self.multifield_struct_returns.contains(&func_name);
@@ -590,6 +1121,17 @@ impl Codegen {
         
         // Remember position of prologue so we can add stack allocation later
         let prologue_end_idx = self.instructions.len();
+
+        // Name the function on the backtrace shadow stack before the
+        // parameter registers are spilled; the push only touches R10/R11
+        if self.backtrace {
+            let name_label = self.allocate_string(func.name.clone());
+            self.instructions.push(X86Instruction::Lea {
+                dst: X86Operand::Register(Register::R10),
+                src: name_label,
+            });
+            self.instructions.push(X86Instruction::Call { func: "gaia_backtrace_push".to_string() });
+        }
         
         // Allocate space for locals (parameters)
         let mut allocator = RegisterAllocator::new();
@@ -623,10 +1165,14 @@ impl Codegen {
                     // This is a struct parameter - register its data location
                     self.var_struct_types.insert(param_name.clone(), struct_name.clone());
                     self.struct_data_locations.insert(param_name.clone(), offset);
+                } else if let Some(struct_name) = self.pointee_struct(&func.name, param_type) {
+                    // A borrowed struct arrives as the address of its data
+                    self.struct_pointer_types.insert(param_name.clone(), struct_name);
                 } else if param_name == "self" && func.name.contains("::") {
                     // Also handle self parameter for methods
                     let struct_name = func.name.split("::").next().unwrap_or("").to_string();
-                    if !struct_name.is_empty() {
+                    // A primitive impl's `self` is the value itself
+                    if !struct_name.is_empty() && !crate::lowering::PRIMITIVE_IMPL_TYPES.contains(&struct_name.as_str()) {
                         self.var_struct_types.insert(param_name.clone(), struct_name.clone());
                         // IMPORTANT: Register struct data location for self parameter
                         // The self parameter is stored at 'offset' on stack, and that's where the struct data is
@@ -659,10 +1205,14 @@ impl Codegen {
                 // This is a struct parameter - register its data location
                 self.var_struct_types.insert(param_name.clone(), struct_name.clone());
                 self.struct_data_locations.insert(param_name.clone(), frame_offset);
+            } else if let Some(struct_name) = self.pointee_struct(&func.name, param_type) {
+                // A borrowed struct arrives as the address of its data
+                self.struct_pointer_types.insert(param_name.clone(), struct_name);
             } else if param_name == "self" && func.name.contains("::") {
                 // Also handle self parameter for methods
                 let struct_name = func.name.split("::").next().unwrap_or("").to_string();
-                if !struct_name.is_empty() {
+                // A primitive impl's `self` is the value itself
+                if !struct_name.is_empty() && !crate::lowering::PRIMITIVE_IMPL_TYPES.contains(&struct_name.as_str()) {
                     self.var_struct_types.insert(param_name.clone(), struct_name.clone());
                     // IMPORTANT: Register struct data location for self parameter
                     // The self parameter is stored at 'frame_offset' on stack, and that's where the struct data is
@@ -675,6 +1225,22 @@ impl Codegen {
         if func.params.len() > 0 {
             self.stack_offset = -8 - (func.params.len() as i64 * 8);
         }
+
+        // The canary sits above every local, so an array overrunning its end
+        // reaches the canary before the saved RBP and return address
+        let has_stack_array = func.basic_blocks.iter()
+            .flat_map(|block| &block.statements)
+            .any(|stmt| matches!(stmt.rvalue, crate::mir::Rvalue::Array(_)));
+        if self.stack_protector && has_stack_array {
+            let offset = self.stack_offset;
+            self.stack_offset -= 8;
+            self.emit_load_stack_guard();
+            self.instructions.push(X86Instruction::Mov {
+                dst: X86Operand::Memory { base: Register::RBP, offset },
+                src: X86Operand::Register(Register::R11),
+            });
+            self.stack_canary = Some((offset, format!("{}_stack_chk_fail", func_name)));
+        }
         
         // Generate code for each basic block
         for (block_idx, block) in func.basic_blocks.iter().enumerate() {
@@ -698,6 +1264,8 @@ impl Codegen {
                         dst: X86Operand::Register(Register::RAX),
                         src: X86Operand::Immediate(0),
                     });
+                    self.emit_stack_canary_check();
+                    self.emit_backtrace_pop();
                     // Restore stack pointer before restoring RBP
                     self.instructions.push(X86Instruction::Mov {
                         dst: X86Operand::Register(Register::RSP),
@@ -708,7 +1276,7 @@ impl Codegen {
                 }
                 Terminator::Return(Some(operand)) => {
                      // For main function (gaia_main), always return 0, not the last expression
-                     if func_name == "gaia_main" {
+                     if func.name == "main" {
                         self.instructions.push(X86Instruction::Mov {
                             dst: X86Operand::Register(Register::RAX),
                             src: X86Operand::Immediate(0),
@@ -766,8 +1334,16 @@ impl Codegen {
                         if let crate::mir::Operand::Copy(crate::mir::Place::Local(ref var_name)) |
                                crate::mir::Operand::Move(crate::mir::Place::Local(ref var_name)) = operand 
                         {
+                            let transparent = self.var_struct_types.get(var_name)
+                                .is_some_and(|name| crate::lowering::is_transparent_struct(name));
                             // Check if this is a struct stored on the stack
-                            if let Some(&struct_offset) = self.struct_data_locations.get(var_name) {
+                            if let (true, Some(&struct_offset)) = (transparent, self.struct_data_locations.get(var_name)) {
+                                // A transparent struct is returned as its field's value
+                                self.instructions.push(X86Instruction::Mov {
+                                    dst: X86Operand::Register(Register::RAX),
+                                    src: X86Operand::Memory { base: Register::RBP, offset: struct_offset },
+                                });
+                            } else if let Some(&struct_offset) = self.struct_data_locations.get(var_name) {
                                 // For structs, return the address on the stack
                                 // Calculate the absolute address: RBP + struct_offset
                                 self.instructions.push(X86Instruction::Mov {
@@ -799,6 +1375,8 @@ impl Codegen {
                             });
                         }
                     }
+                    self.emit_stack_canary_check();
+                    self.emit_backtrace_pop();
                     // Restore stack pointer before restoring RBP
                     self.instructions.push(X86Instruction::Mov {
                         dst: X86Operand::Register(Register::RSP),
@@ -845,12 +1423,51 @@ impl Codegen {
                         }
                     }
                 }
+                Terminator::Assert(cond, kind, target) => {
+                    let success_label = format!("{}_bb{}", func_name, target);
+                    if let crate::mir::Operand::Constant(crate::mir::Constant::Bool(true)) = cond {
+                        self.instructions.push(X86Instruction::Jmp { label: success_label });
+                    } else {
+                        // Without the condition the check can't be emitted at all
+                        let cond_operand = self.operand_to_x86(cond)?;
+                        self.instructions.push(X86Instruction::Mov {
+                            dst: X86Operand::Register(Register::RAX),
+                            src: cond_operand,
+                        });
+                        self.instructions.push(X86Instruction::Cmp {
+                            dst: X86Operand::Register(Register::RAX),
+                            src: X86Operand::Immediate(0),
+                        });
+                        self.instructions.push(X86Instruction::Jne { label: success_label });
+                        // Failed check: the runtime prints the message and exits
+                        match kind {
+                            crate::mir::AssertKind::BoundsCheck { len, index } => {
+                                for (operand, reg) in [(index, Register::RDI), (len, Register::RSI)] {
+                                    if let Ok(src) = self.operand_to_x86(operand) {
+                                        self.instructions.push(X86Instruction::Mov {
+                                            dst: X86Operand::Register(reg),
+                                            src,
+                                        });
+                                    }
+                                }
+                                self.instructions.push(X86Instruction::Call {
+                                    func: "gaia_panic_bounds_check".to_string(),
+                                });
+                            }
+                        }
+                    }
+                }
                 Terminator::Unreachable => {
                     self.instructions.push(X86Instruction::Nop);
                 }
             }
         }
         
+        if let Some((_, fail_label)) = self.stack_canary.take() {
+            self.instructions.push(X86Instruction::Label { name: fail_label });
+            self.instructions.push(X86Instruction::Call { func: "gaia_stack_chk_fail".to_string() });
+        }
+        
         // If we've allocated local stack space, add sub rsp instruction
          // IMPORTANT: System V AMD64 ABI requires RSP % 16 == 0 BEFORE any CALL instruction
          // After push rbp, RSP % 16 == 8
@@ -933,8 +1550,25 @@ impl Codegen {
                             });
                         }
                     }
+                    crate::mir::Operand::Copy(crate::mir::Place::Field(place, field_name)) if self.struct_pointer_field(place, field_name).is_some() => {
+                         // `(*ptr).field`: load the struct's address, then the field below it
+                         let (ptr_offset, field_offset) = self.struct_pointer_field(place, field_name).unwrap_or_default();
+                         self.instructions.push(X86Instruction::Mov {
+                             dst: X86Operand::Register(Register::RAX),
+                             src: X86Operand::Memory { base: Register::RBP, offset: ptr_offset },
+                         });
+                         self.instructions.push(X86Instruction::Mov {
+                             dst: X86Operand::Register(Register::RAX),
+                             src: X86Operand::Memory { base: Register::RAX, offset: field_offset },
+                         });
+                     }
                     crate::mir::Operand::Copy(crate::mir::Place::Field(place, field_name)) => {
-                         // The struct variable holds the struct data or a POINTER to struct data
+                         // The struct variable holds the struct data or a POINTER to struct data.
+                         // A `self` taken by value reads `(*self).field` from the data directly.
+                         let place = match place.as_ref() {
+                             crate::mir::Place::Deref(inner) if matches!(inner.as_ref(), crate::mir::Place::Local(name) if self.struct_data_locations.contains_key(name)) => inner,
+                             _ => place,
+                         };
                          match place.as_ref() {
                               crate::mir::Place::Local(name) => {
                                   // Check if this is a struct variable (has struct data location registered)
@@ -1160,6 +1794,13 @@ impl Codegen {
                     if self.struct_data_locations.contains_key(src_name) {
                     }
                     
+                    // A copy of a Vec holds the same elements
+                    if let crate::mir::Place::Local(ref dst_name) = stmt.place {
+                        if let Some(elem_type) = self.vec_element_types.get(src_name).cloned() {
+                            self.vec_element_types.insert(dst_name.clone(), elem_type);
+                        }
+                    }
+                    
                     // Propagate struct type information (crucial for field access lookups)
                     if let Some(struct_type) = self.var_struct_types.get(src_name).cloned() {
                         if let crate::mir::Place::Local(ref dst_name) = stmt.place {
@@ -1539,6 +2180,8 @@ impl Codegen {
                             dst: X86Operand::Register(Register::RCX),
                             src: right_val,
                         });
+                        let bits = self.operand_bits(left);
+                        self.emit_shift_count_check(bits, "shl_overflow_msg");
                         self.instructions.push(X86Instruction::Shl {
                             dst: X86Operand::Register(Register::RAX),
                             src: X86Operand::Register(Register::RCX),
@@ -1549,6 +2192,8 @@ impl Codegen {
                             dst: X86Operand::Register(Register::RCX),
                             src: right_val,
                         });
+                        let bits = self.operand_bits(left);
+                        self.emit_shift_count_check(bits, "shr_overflow_msg");
                         self.instructions.push(X86Instruction::Sar {
                             dst: X86Operand::Register(Register::RAX),
                             src: X86Operand::Register(Register::RCX),
@@ -1576,6 +2221,25 @@ impl Codegen {
                                     dst: X86Operand::Register(Register::RAX),
                                     src: X86Operand::Immediate(var_offset),
                                 });
+                            } else if let (Some(&struct_offset), true) = (
+                                self.struct_data_locations.get(var_name),
+                                self.var_struct_types.contains_key(var_name) && !self.array_variables.contains_key(var_name),
+                            ) {
+                                // A struct's address is that of its first field
+                                self.instructions.push(X86Instruction::Mov {
+                                    dst: X86Operand::Register(Register::RAX),
+                                    src: X86Operand::Register(Register::RBP),
+                                });
+                                self.instructions.push(X86Instruction::Add {
+                                    dst: X86Operand::Register(Register::RAX),
+                                    src: X86Operand::Immediate(struct_offset),
+                                });
+                            } else if let Some(&struct_offset) = self.struct_data_locations.get(var_name) {
+                                // An array is passed by reference the same way as by value
+                                self.instructions.push(X86Instruction::Mov {
+                                    dst: X86Operand::Register(Register::RAX),
+                                    src: X86Operand::Memory { base: Register::RBP, offset: struct_offset },
+                                });
                             } else {
                                 // Variable not found, return 0
                                 self.instructions.push(X86Instruction::Mov {
@@ -1626,6 +2290,14 @@ impl Codegen {
                 }
             }
             crate::mir::Rvalue::Call(func_name, args) => {
+                let plain_print;
+                let (func_name, args) = match plain_printf_routine(func_name, args).filter(|_| self.no_std) {
+                    Some((routine, rest)) => {
+                        plain_print = (routine.to_string(), rest);
+                        (&plain_print.0, &plain_print.1)
+                    }
+                    None => (func_name, args),
+                };
                 // Special handling for __extract_enum_value
                 if func_name == "__extract_enum_value" && !args.is_empty() {
                     // Extract the inner value from Option<T> or Result<T, E>
@@ -1664,8 +2336,57 @@ impl Codegen {
                     last_part.chars().next().map(|c| c.is_uppercase()).unwrap_or(false) &&
                     !last_part.starts_with("_enum_constructor")
                 };
-                
-                if is_enum_constructor && !args.is_empty() {
+
+                // Float math builtins: args in XMM0/XMM1, result in XMM0
+                // sqrt is a single instruction; the rest call into libm
+                let float_builtin = match func_name.as_str() {
+                    "sqrt" | "floor" | "ceil" | "round" | "pow" | "sin" | "cos" => Some(func_name.as_str()),
+                    "ln" => Some("log"),
+                    _ => None,
+                };
+                if let Some(libm_symbol) = float_builtin.filter(|_| !self.function_return_types.contains_key(func_name)) {
+                    for (i, arg) in args.iter().take(2).enumerate() {
+                        let xmm = format!("xmm{}", i);
+                        let src = match arg {
+                            crate::mir::Operand::Constant(crate::mir::Constant::Float(f)) => {
+                                format!("qword ptr [rip + {}]", self.allocate_float(*f))
+                            }
+                            crate::mir::Operand::Constant(crate::mir::Constant::Integer(n)) => {
+                                format!("qword ptr [rip + {}]", self.allocate_float(*n as f64))
+                            }
+                            crate::mir::Operand::Copy(crate::mir::Place::Local(name))
+                            | crate::mir::Operand::Move(crate::mir::Place::Local(name)) => {
+                                let offset = self.get_var_location(name);
+                                format!("qword ptr [rbp {}]", if offset < 0 { format!("- {}", -offset) } else { format!("+ {}", offset) })
+                            }
+                            _ => {
+                                return Err(CodegenError {
+                                    message: format!("unsupported argument to float builtin '{}'", func_name),
+                                })
+                            }
+                        };
+                        self.instructions.push(X86Instruction::Movsd { dst: xmm, src });
+                    }
+
+                    if libm_symbol == "sqrt" {
+                        self.instructions.push(X86Instruction::Sqrtsd {
+                            dst: "xmm0".to_string(),
+                            src: "xmm0".to_string(),
+                        });
+                    } else {
+                        self.instructions.push(X86Instruction::Call { func: libm_symbol.to_string() });
+                    }
+
+                    if let crate::mir::Place::Local(ref var_name) = stmt.place {
+                        let offset = self.get_var_location(var_name);
+                        self.float_stack_offsets.insert(offset);
+                        self.instructions.push(X86Instruction::Movsd {
+                            dst: format!("qword ptr [rbp {}]", if offset < 0 { format!("- {}", -offset) } else { format!("+ {}", offset) }),
+                            src: "xmm0".to_string(),
+                        });
+                    }
+                    skip_final_store = true;
+                } else if is_enum_constructor && !args.is_empty() {
                     // For enum constructors with arguments, create [tag:i64][value:i64] layout
                     // Determine the tag based on the variant name
                     let variant_tag = match func_name.as_str() {
@@ -1683,6 +2404,9 @@ impl Codegen {
                     self.stack_offset -= 16;  // Allocate 16 bytes (2 x i64)
                     let tag_offset = self.stack_offset;
                     let value_offset = self.stack_offset + 8;
+                    // stack_offset is the next free slot; step past the tag so the
+                    // result's own slot doesn't overwrite it
+                    self.stack_offset -= 8;
                     
                     // Store the tag
                     self.instructions.push(X86Instruction::Mov {
@@ -1734,6 +2458,7 @@ impl Codegen {
                     self.stack_offset -= 16;
                     let tag_offset = self.stack_offset;
                     let value_offset = self.stack_offset + 8;
+                    self.stack_offset -= 8;
                     
                     // Store the tag
                     self.instructions.push(X86Instruction::Mov {
@@ -1755,6 +2480,34 @@ impl Codegen {
                         src: X86Operand::Register(Register::RAX),
                     });
                     
+                    // Return pointer to the tag
+                    self.instructions.push(X86Instruction::Mov {
+                        dst: X86Operand::Register(Register::RAX),
+                        src: X86Operand::Register(Register::RBP),
+                    });
+                    self.instructions.push(X86Instruction::Add {
+                        dst: X86Operand::Register(Register::RAX),
+                        src: X86Operand::Immediate(tag_offset),
+                    });
+                } else if func_name == "__enum_new" && !args.is_empty() {
+                    // User enum with payload: args are [tag, payload...]
+                    // Layout: [tag:i64][payload:i64...] at ascending addresses
+                    // The tag takes the lowest slot; stack_offset ends on the next free slot
+                    let tag_offset = self.stack_offset - 8 * (args.len() as i64 - 1);
+                    self.stack_offset -= 8 * args.len() as i64;
+
+                    for (slot, arg) in args.iter().enumerate() {
+                        let arg_val = self.operand_to_x86(arg)?;
+                        self.instructions.push(X86Instruction::Mov {
+                            dst: X86Operand::Register(Register::RAX),
+                            src: arg_val,
+                        });
+                        self.instructions.push(X86Instruction::Mov {
+                            dst: X86Operand::Memory { base: Register::RBP, offset: tag_offset + 8 * slot as i64 },
+                            src: X86Operand::Register(Register::RAX),
+                        });
+                    }
+
                     // Return pointer to the tag
                     self.instructions.push(X86Instruction::Mov {
                         dst: X86Operand::Register(Register::RAX),
@@ -2146,6 +2899,57 @@ impl Codegen {
                         src: X86Operand::Register(Register::RAX),
                     });
                     skip_final_store = true;
+                } else if func_name == "gaia_vec_push" && args.len() == 2 {
+                    // rdi = vec pointer, rsi = element. Every slot is 8 bytes: an f64
+                    // keeps its bits, and a struct is copied to the heap and stored
+                    // by pointer, with fields at positive offsets like array elements.
+                    let elem_type = match &args[0] {
+                        crate::mir::Operand::Copy(crate::mir::Place::Local(vec_name)) => self.vec_element_types.get(vec_name).cloned(),
+                        _ => None,
+                    };
+                    let struct_element = match (&elem_type, &args[1]) {
+                        (Some(crate::lowering::HirType::Named(struct_name)), crate::mir::Operand::Copy(crate::mir::Place::Local(elem_name))) => {
+                            self.struct_data_locations.get(elem_name).map(|&base| (struct_name.clone(), base))
+                        }
+                        _ => None,
+                    };
+                    let element = if let Some((struct_name, base)) = struct_element {
+                        let field_count = self.struct_field_counts.get(&struct_name)
+                            .copied()
+                            .unwrap_or_else(|| crate::lowering::get_struct_field_count(&struct_name))
+                            .max(1) as i64;
+                        self.instructions.push(X86Instruction::Mov {
+                            dst: X86Operand::Register(Register::RDI),
+                            src: X86Operand::Immediate(field_count * 8),
+                        });
+                        self.instructions.push(X86Instruction::Call { func: "malloc".to_string() });
+                        for i in 0..field_count {
+                            self.instructions.push(X86Instruction::Mov {
+                                dst: X86Operand::Register(Register::RCX),
+                                src: X86Operand::Memory { base: Register::RBP, offset: base - i * 8 },
+                            });
+                            self.instructions.push(X86Instruction::Mov {
+                                dst: X86Operand::Memory { base: Register::RAX, offset: i * 8 },
+                                src: X86Operand::Register(Register::RCX),
+                            });
+                        }
+                        X86Operand::Register(Register::RAX)
+                    } else {
+                        self.operand_to_x86(&args[1])?
+                    };
+                    self.instructions.push(X86Instruction::Mov {
+                        dst: X86Operand::Register(Register::RSI),
+                        src: element,
+                    });
+                    let vec_val = self.operand_to_x86(&args[0])?;
+                    self.instructions.push(X86Instruction::Mov {
+                        dst: X86Operand::Register(Register::RDI),
+                        src: vec_val,
+                    });
+                    self.instructions.push(X86Instruction::Call {
+                        func: "gaia_vec_push".to_string(),
+                    });
+                    skip_final_store = true;
                 } else if func_name == "push" || func_name == "Vec::push" || func_name == "HashMap::push" {
                     // Vec::push - call runtime function
                     // rdi = self (vec pointer), rsi = value
@@ -2201,6 +3005,9 @@ impl Codegen {
                     // Use HashMap get for BTreeMap/HashMap, Vec get for Vec
                     let runtime_func = if func_name.contains("HashMap") || func_name.contains("BTreeMap") {
                         "gaia_hashmap_get"
+                    } else if func_name == "Vec::get" {
+                        // Vec::get returns an Option
+                        "gaia_vec_get_checked"
                     } else {
                         "gaia_vec_get"
                     };
@@ -2715,6 +3522,7 @@ impl Codegen {
                     // __builtin_vec_from([elements]) - Create vector from array
                     // Arguments: array operand
                     // Returns: vector (in RAX)
+                    // Vec layout: [capacity:i64][length:i64][data...] with data[i] at +16 + i*8
                     
                     if args.is_empty() {
                         // No array argument, create empty vector
@@ -2726,21 +3534,22 @@ impl Codegen {
                         // Get the array argument
                         let array_operand = &args[0];
                         
-                        // Allocate a vector structure (capacity + length + data)
-                        self.stack_offset -= 8; // Pointer to vec metadata
-                        let vec_ptr_offset = self.stack_offset;
-                        
-                        // Determine array size from the operand
-                        let elem_count = if let crate::mir::Operand::Copy(crate::mir::Place::Local(var_name)) |
-                                              crate::mir::Operand::Move(crate::mir::Place::Local(var_name)) = array_operand {
-                            // Look up the array's element count
-                            // For now, allocate enough space (simplified)
-                            16 // Conservative estimate
+                        // Look up the source array (element i lives at array_base - i*8)
+                        let source_array = if let crate::mir::Operand::Copy(crate::mir::Place::Local(var_name)) |
+                                                  crate::mir::Operand::Move(crate::mir::Place::Local(var_name)) = array_operand {
+                            self.array_variables.get(var_name).copied()
                         } else {
-                            8 // Default
+                            None
                         };
+                        let elem_count = source_array.map(|(count, _)| count as i64).unwrap_or(0);
+                        
+                        // Leave room to push after construction, like Vec::new (14 slots)
+                        let capacity = elem_count.max(14);
                         
-                        let vec_size = 16 + (elem_count as i64) * 8; // capacity + length + elements
+                        self.stack_offset -= 8; // Slot for the Vec pointer
+                        let vec_ptr_offset = self.stack_offset;
+                        
+                        let vec_size = 16 + capacity * 8; // capacity + length + elements
                         self.stack_offset -= vec_size;
                         let vec_data_offset = self.stack_offset;
                         
@@ -2753,45 +3562,26 @@ impl Codegen {
                         // Initialize capacity field (at vec_data_offset)
                         self.instructions.push(X86Instruction::Mov {
                             dst: X86Operand::Memory { base: Register::RBP, offset: vec_data_offset },
-                            src: X86Operand::Immediate(elem_count),
+                            src: X86Operand::Immediate(capacity),
                         });
                         
                         // Initialize length field (at vec_data_offset + 8)
-                        // Length = number of elements being inserted
-                        if let crate::mir::Operand::Copy(crate::mir::Place::Local(var_name)) |
-                               crate::mir::Operand::Move(crate::mir::Place::Local(var_name)) = array_operand {
-                            // If source is a named array, get its length from metadata
-                            // For now, set to elem_count
-                            self.instructions.push(X86Instruction::Mov {
-                                dst: X86Operand::Memory { base: Register::RBP, offset: vec_data_offset + 8 },
-                                src: X86Operand::Immediate(elem_count),
-                            });
-                        } else {
-                            self.instructions.push(X86Instruction::Mov {
-                                dst: X86Operand::Memory { base: Register::RBP, offset: vec_data_offset + 8 },
-                                src: X86Operand::Immediate(elem_count),
-                            });
-                        }
+                        self.instructions.push(X86Instruction::Mov {
+                            dst: X86Operand::Memory { base: Register::RBP, offset: vec_data_offset + 8 },
+                            src: X86Operand::Immediate(elem_count),
+                        });
                         
                         // Copy array elements to vector data area
-                        if let crate::mir::Operand::Copy(crate::mir::Place::Local(var_name)) |
-                               crate::mir::Operand::Move(crate::mir::Place::Local(var_name)) = array_operand {
-                            // Copy from source array to vector
-                            if let Some(&src_offset) = self.struct_data_locations.get(var_name) {
-                                for i in 0..elem_count {
-                                    // Stack grows downward, so subtract offsets
-                                    let src_elem_offset = src_offset - (i as i64) * 8;
-                                    let dst_elem_offset = vec_data_offset - 16 - (i as i64) * 8;
-                                    
-                                    self.instructions.push(X86Instruction::Mov {
-                                        dst: X86Operand::Register(Register::RAX),
-                                        src: X86Operand::Memory { base: Register::RBP, offset: src_elem_offset },
-                                    });
-                                    self.instructions.push(X86Instruction::Mov {
-                                        dst: X86Operand::Memory { base: Register::RBP, offset: dst_elem_offset },
-                                        src: X86Operand::Register(Register::RAX),
-                                    });
-                                }
+                        if let Some((count, array_base)) = source_array {
+                            for i in 0..count as i64 {
+                                self.instructions.push(X86Instruction::Mov {
+                                    dst: X86Operand::Register(Register::RAX),
+                                    src: X86Operand::Memory { base: Register::RBP, offset: array_base - i * 8 },
+                                });
+                                self.instructions.push(X86Instruction::Mov {
+                                    dst: X86Operand::Memory { base: Register::RBP, offset: vec_data_offset + 16 + i * 8 },
+                                    src: X86Operand::Register(Register::RAX),
+                                });
                             }
                         }
                         
@@ -2806,6 +3596,10 @@ impl Codegen {
                         });
                         
                         // Store vector pointer in variable slot
+                        self.instructions.push(X86Instruction::Mov {
+                            dst: X86Operand::Memory { base: Register::RBP, offset: vec_ptr_offset },
+                            src: X86Operand::Register(Register::RAX),
+                        });
                         if let crate::mir::Place::Local(ref var_name) = stmt.place {
                             self.var_locations.insert(var_name.clone(), vec_ptr_offset);
                         }
@@ -3287,7 +4081,7 @@ impl Codegen {
                                         dst: X86Operand::Register(Register::RAX),
                                         src: X86Operand::Memory { 
                                             base: Register::RBP, 
-                                            offset: array_base + (i as i64) * 8
+                                            offset: array_base - (i as i64) * 8
                                         },
                                     });
                                     // Store to wrapper
@@ -3348,7 +4142,21 @@ impl Codegen {
                 } else {
                     // Regular function call
                     // Mangle function names for assembly compatibility
-                    let mangled_func_name = if func_name.contains("::") {
+                    let mangled_func_name = if let Some(symbol) = self.builtin_symbols.get(func_name) {
+                        symbol.clone()
+                    } else if self.no_std && is_libc_print(func_name) {
+                        match no_std_print_symbol(func_name) {
+                            Some(symbol) => symbol.to_string(),
+                            None => {
+                                return Err(CodegenError {
+                                    message: format!(
+                                        "`{}` needs libc; only plain string and integer printing is available in no_std mode",
+                                        func_name
+                                    ),
+                                });
+                            }
+                        }
+                    } else if func_name.contains("::") {
                         // Mangle qualified names: Point::new -> Point_impl_new
                         func_name.replace("::", "_impl_")
                     } else {
@@ -3478,6 +4286,13 @@ impl Codegen {
                                 }
                             }
                             X86Operand::Register(Register::RAX)
+                        } else if let Some(fn_name) = self.function_operand(arg) {
+                            // A named function passed as a value is its address
+                            self.instructions.push(X86Instruction::Lea {
+                                dst: X86Operand::Register(Register::RAX),
+                                src: fn_name,
+                            });
+                            X86Operand::Register(Register::RAX)
                         } else {
                             self.operand_to_x86(arg)?
                         };
@@ -3552,9 +4367,28 @@ impl Codegen {
                         }
                     }
                     
-                    self.instructions.push(X86Instruction::Call {
-                        func: mangled_func_name.clone(),
-                    });
+                    if let Some(&offset) = self.var_locations.get(&mangled_func_name) {
+                        // Calling a local holding a function pointer: r11 is
+                        // caller-saved and never carries an argument
+                        self.instructions.push(X86Instruction::Mov {
+                            dst: X86Operand::Register(Register::R11),
+                            src: X86Operand::Memory { base: Register::RBP, offset },
+                        });
+                        self.instructions.push(X86Instruction::CallIndirect { reg: Register::R11 });
+                    } else {
+                        self.instructions.push(X86Instruction::Call {
+                            func: mangled_func_name.clone(),
+                        });
+                        // A C function leaves the bits above a narrow return value undefined
+                        if let Some((bits, signed)) = crate::lowering::extern_return_type(func_name).as_ref().and_then(narrow_integer) {
+                            self.instructions.push(X86Instruction::Extend {
+                                dst: Register::RAX,
+                                src: Register::RAX,
+                                bits,
+                                signed,
+                            });
+                        }
+                    }
                     if stack_adjust > 0 {
                         self.instructions.push(X86Instruction::Add {
                             dst: X86Operand::Register(Register::RSP),
@@ -3790,9 +4624,26 @@ impl Codegen {
                     // struct_base should point to the START of the allocated space (the current stack_offset)
                     // BEFORE we decrement stack_offset.
                     // Then decrement stack_offset to mark the space as allocated.
-                    let struct_base = self.stack_offset;
-                    self.stack_offset -= struct_size;
-                    
+                    // A variable given another value of its struct type, like the
+                    // result of an `if` whose arms each build one, keeps its slot
+                    // when no copy shares it
+                    let reused_base = match &stmt.place {
+                        crate::mir::Place::Local(var_name) if self.var_struct_types.get(var_name) == Some(struct_name) => {
+                            self.struct_data_locations.get(var_name).copied().filter(|&base| {
+                                self.struct_data_locations.values().filter(|&&other| other == base).count() == 1
+                            })
+                        }
+                        _ => None,
+                    };
+                    let struct_base = match reused_base {
+                        Some(base) => base,
+                        None => {
+                            let base = self.stack_offset;
+                            self.stack_offset -= struct_size;
+                            base
+                        }
+                    };
+
                     // Now fields are stored at: struct_base, struct_base-8, struct_base-16, ...
                     // And stack_offset points to the next available location
                     
@@ -4148,54 +4999,39 @@ impl Codegen {
                 }
             }
             crate::mir::Rvalue::Closure { fn_ptr, captures } => {
-                // Closure creation: allocate closure object with fn_ptr and captured values
+                // Closure creation: the object goes on the heap so a closure
+                // can outlive the frame that built it (e.g. when returned)
                 // Closure layout: [fn_ptr:i64][capture1:i64][capture2:i64]...
-                let closure_size = 8 + (captures.len() as i64) * 8; // fn_ptr + captured values
-                // closure_base should be set BEFORE decrementing stack_offset
-                let closure_base = self.stack_offset;
-                self.stack_offset -= closure_size;
+                let closure_size = 8 + (captures.len() as i64) * 8;
+                self.instructions.push(X86Instruction::Mov {
+                    dst: X86Operand::Register(Register::RDI),
+                    src: X86Operand::Immediate(closure_size),
+                });
+                self.instructions.push(X86Instruction::Call { func: "malloc".to_string() });
                 
                 // Store function pointer at offset 0
                 self.instructions.push(X86Instruction::Lea {
-                    dst: X86Operand::Register(Register::RAX),
+                    dst: X86Operand::Register(Register::RCX),
                     src: fn_ptr.clone(),
                 });
                 self.instructions.push(X86Instruction::Mov {
-                    dst: X86Operand::Memory { base: Register::RBP, offset: closure_base },
-                    src: X86Operand::Register(Register::RAX),
+                    dst: X86Operand::Memory { base: Register::RAX, offset: 0 },
+                    src: X86Operand::Register(Register::RCX),
                 });
                 
-                // Store captured values at offsets -8, -16, -24, etc. (stack grows downward)
+                // Store captured values after it
                 for (i, operand) in captures.iter().enumerate() {
-                    let capture_offset = closure_base - 8 - (i as i64) * 8;
                     let val = self.operand_to_x86(operand)?;
-                    
                     self.instructions.push(X86Instruction::Mov {
-                        dst: X86Operand::Register(Register::RAX),
+                        dst: X86Operand::Register(Register::RCX),
                         src: val,
                     });
                     self.instructions.push(X86Instruction::Mov {
-                        dst: X86Operand::Memory { base: Register::RBP, offset: capture_offset },
-                        src: X86Operand::Register(Register::RAX),
+                        dst: X86Operand::Memory { base: Register::RAX, offset: 8 + (i as i64) * 8 },
+                        src: X86Operand::Register(Register::RCX),
                     });
                 }
-                
-                // Return closure pointer (in RAX)
-                self.instructions.push(X86Instruction::Mov {
-                    dst: X86Operand::Register(Register::RAX),
-                    src: X86Operand::Register(Register::RBP),
-                });
-                self.instructions.push(X86Instruction::Add {
-                    dst: X86Operand::Register(Register::RAX),
-                    src: X86Operand::Immediate(closure_base),
-                });
-                
-                // Register the closure data location
-                if let crate::mir::Place::Local(ref var_name) = stmt.place {
-                    self.struct_data_locations.insert(var_name.clone(), closure_base);
-                    self.allocate_var(var_name.clone());
-                }
-                skip_final_store = true;
+                // The closure pointer in RAX is stored to the place as usual
             }
             crate::mir::Rvalue::Deref(place) => {
                 // Dereference: *ptr where ptr is a Box or pointer
@@ -4252,10 +5088,9 @@ impl Codegen {
                 if let Some(return_type) = self.function_return_types.get(&mangled_func_name).cloned() {
                     match return_type {
                         crate::lowering::HirType::Named(struct_name) => {
-                            // This function returns a struct - handle the struct return
-                            self.handle_struct_return(&struct_name, name)?;
-                            // Skip the regular store, we've already handled it
-                            skip_final_store = true;
+                            // This function returns a struct - handle the struct return.
+                            // Enums come back in RAX and take the regular store.
+                            skip_final_store = self.handle_struct_return(&struct_name, name)?;
                         }
                         crate::lowering::HirType::Array { element_type, size } => {
                             // Array of structs return - the data is already in the buffer at the right location
@@ -4409,13 +5244,32 @@ impl Codegen {
                                     // Register the temporary name with the struct type
                                     self.temp_array_element_pointers.insert(name.clone(), struct_type);
                                 }
+                                match self.vec_element_types.get(array_name).cloned() {
+                                    Some(crate::lowering::HirType::Float64) => {
+                                        self.float_stack_offsets.insert(offset);
+                                    }
+                                    Some(crate::lowering::HirType::Named(struct_type)) => {
+                                        self.temp_array_element_pointers.insert(name.clone(), struct_type);
+                                    }
+                                    _ => {}
+                                }
                             }
                         }
                     } else {
                     }
                 }
                 crate::mir::Place::Field(place, field_name) => {
-                    if let crate::mir::Place::Local(obj_name) = place.as_ref() {
+                    if let Some((ptr_offset, field_offset)) = self.struct_pointer_field(place, field_name) {
+                        // `(*ptr).field = value`: store below the struct's address
+                        self.instructions.push(X86Instruction::Mov {
+                            dst: X86Operand::Register(Register::RCX),
+                            src: X86Operand::Memory { base: Register::RBP, offset: ptr_offset },
+                        });
+                        self.instructions.push(X86Instruction::Mov {
+                            dst: X86Operand::Memory { base: Register::RCX, offset: field_offset },
+                            src: X86Operand::Register(Register::RAX),
+                        });
+                    } else if let crate::mir::Place::Local(obj_name) = place.as_ref() {
                         if let Some(&struct_base) = self.struct_data_locations.get(obj_name) {
                             let field_idx = self.get_field_index(obj_name, field_name);
                             // Stack grows downward, so subtract offset from base
@@ -4456,12 +5310,102 @@ impl Codegen {
                         });
                     }
                 }
+                crate::mir::Place::DynamicIndex(base, idx_name) => {
+                    // Indexed store: v[i] = value, with the value in RAX
+                    if let crate::mir::Place::Local(array_name) = base.as_ref() {
+                        let idx_offset = self.get_var_location(idx_name);
+                        self.instructions.push(X86Instruction::Mov {
+                            dst: X86Operand::Register(Register::RCX),
+                            src: X86Operand::Memory { base: Register::RBP, offset: idx_offset },
+                        });
+                        if let Some(&array_base) = self.struct_data_locations.get(array_name) {
+                            // Stack array: element i lives at array_base - i*8. The
+                            // MIR already asserted the index is in bounds.
+                            self.instructions.push(X86Instruction::Shl {
+                                dst: X86Operand::Register(Register::RCX),
+                                src: X86Operand::Immediate(3),
+                            });
+                            self.instructions.push(X86Instruction::Mov {
+                                dst: X86Operand::Register(Register::RDX),
+                                src: X86Operand::Register(Register::RBP),
+                            });
+                            self.instructions.push(X86Instruction::Sub {
+                                dst: X86Operand::Register(Register::RDX),
+                                src: X86Operand::Register(Register::RCX),
+                            });
+                            self.instructions.push(X86Instruction::Mov {
+                                dst: X86Operand::Memory { base: Register::RDX, offset: array_base },
+                                src: X86Operand::Register(Register::RAX),
+                            });
+                        } else if let Some(&vec_offset) = self.var_locations.get(array_name) {
+                            // Vec: [capacity][length][data...] behind the pointer. Its
+                            // length is only known at runtime, so check it here.
+                            let fail_label = self.new_label();
+                            let ok_label = self.new_label();
+                            self.instructions.push(X86Instruction::Mov {
+                                dst: X86Operand::Register(Register::RDX),
+                                src: X86Operand::Memory { base: Register::RBP, offset: vec_offset },
+                            });
+                            self.instructions.push(X86Instruction::Cmp {
+                                dst: X86Operand::Register(Register::RCX),
+                                src: X86Operand::Memory { base: Register::RDX, offset: 8 },
+                            });
+                            self.instructions.push(X86Instruction::Jge { label: fail_label.clone() });
+                            self.instructions.push(X86Instruction::Cmp {
+                                dst: X86Operand::Register(Register::RCX),
+                                src: X86Operand::Immediate(0),
+                            });
+                            self.instructions.push(X86Instruction::Jge { label: ok_label.clone() });
+                            self.instructions.push(X86Instruction::Label { name: fail_label });
+                            self.instructions.push(X86Instruction::Mov {
+                                dst: X86Operand::Register(Register::RDI),
+                                src: X86Operand::Register(Register::RCX),
+                            });
+                            self.instructions.push(X86Instruction::Mov {
+                                dst: X86Operand::Register(Register::RSI),
+                                src: X86Operand::Memory { base: Register::RDX, offset: 8 },
+                            });
+                            self.instructions.push(X86Instruction::Call {
+                                func: "gaia_panic_bounds_check".to_string(),
+                            });
+                            self.instructions.push(X86Instruction::Label { name: ok_label });
+                            self.instructions.push(X86Instruction::Shl {
+                                dst: X86Operand::Register(Register::RCX),
+                                src: X86Operand::Immediate(3),
+                            });
+                            self.instructions.push(X86Instruction::Add {
+                                dst: X86Operand::Register(Register::RDX),
+                                src: X86Operand::Register(Register::RCX),
+                            });
+                            self.instructions.push(X86Instruction::Mov {
+                                dst: X86Operand::Memory { base: Register::RDX, offset: 16 },
+                                src: X86Operand::Register(Register::RAX),
+                            });
+                        }
+                    }
+                }
                 _ => {}
             }
         }
         Ok(())
     }
     
+    /// The function named by `operand`, if it refers to a function rather
+    /// than a local
+    fn function_operand(&self, operand: &crate::mir::Operand) -> Option<String> {
+        match operand {
+            crate::mir::Operand::Copy(crate::mir::Place::Local(name))
+            | crate::mir::Operand::Move(crate::mir::Place::Local(name))
+                if !self.var_locations.contains_key(name)
+                    && !self.struct_data_locations.contains_key(name)
+                    && self.function_return_types.contains_key(name) =>
+            {
+                Some(name.clone())
+            }
+            _ => None,
+        }
+    }
+
     /// Convert an operand to x86 operand
     fn operand_to_x86(&self, operand: &crate::mir::Operand) -> CodegenResult<X86Operand> {
         match operand {
@@ -4603,14 +5547,7 @@ impl Codegen {
 
     /// Allocate a label for a string constant
     fn allocate_string(&mut self, string: String) -> String {
-        if let Some(label) = self.string_constants.get(&string) {
-            label.clone()
-        } else {
-            let label = format!("str_{}", self.label_counter);
-            self.label_counter += 1;
-            self.string_constants.insert(string, label.clone());
-            label
-        }
+        self.strings.intern(&string)
     }
 
     fn allocate_float(&mut self, float: f64) -> String {
@@ -4627,6 +5564,34 @@ impl Codegen {
 
     /// Get the field index for a struct field
     /// First tries the struct registry, then falls back to hardcoded mappings
+    /// The struct a `&S` / `&mut S` parameter of `func_name` points at.
+    /// `&self` names the impl's type, which is the prefix of `func_name`.
+    fn pointee_struct(&self, func_name: &str, param_type: &crate::lowering::HirType) -> Option<String> {
+        let (crate::lowering::HirType::Reference(inner) | crate::lowering::HirType::MutableReference(inner)) = param_type else {
+            return None;
+        };
+        let crate::lowering::HirType::Named(name) = inner.as_ref() else {
+            return None;
+        };
+        let name = if name == "Self" { func_name.split("::").next()? } else { name.as_str() };
+        (get_struct_field_count(name) > 0).then(|| name.to_string())
+    }
+
+    /// Frame offset of the pointer and offset from it of `field`, when
+    /// `place` is `*ptr` for a local holding a struct's address
+    fn struct_pointer_field(&self, place: &crate::mir::Place, field_name: &str) -> Option<(i64, i64)> {
+        let crate::mir::Place::Deref(inner) = place else {
+            return None;
+        };
+        let crate::mir::Place::Local(ptr_name) = inner.as_ref() else {
+            return None;
+        };
+        let struct_name = self.struct_pointer_types.get(ptr_name)?;
+        let ptr_offset = *self.var_locations.get(ptr_name)?;
+        let field_index = get_struct_field_index(struct_name, field_name)?;
+        Some((ptr_offset, -(field_index as i64) * 8))
+    }
+
     fn get_field_index(&self, var_name: &str, field_name: &str) -> usize {
         // Validate inputs
         if field_name.is_empty() {
@@ -4674,12 +5639,15 @@ impl Codegen {
     /// 1. Allocate space on the caller's stack for the struct
     /// 2. Copy the struct data from the address in RAX to our allocated space
     /// 3. Register the destination variable as having struct data
-    fn handle_struct_return(&mut self, struct_name: &str, dst_var: &str) -> CodegenResult<()> {
+    ///
+    /// Returns false when `struct_name` has no fields (e.g. an enum), in which
+    /// case the value in RAX should be stored as-is.
+    fn handle_struct_return(&mut self, struct_name: &str, dst_var: &str) -> CodegenResult<bool> {
         // Get the struct field count to know how much data to copy
         let field_count = self.get_cached_struct_field_count(struct_name);
         if field_count == 0 {
             // Struct not found or has no fields - just store RAX as-is
-            return Ok(());
+            return Ok(false);
         }
         
         let struct_size = (field_count as i64) * 8;
@@ -4718,12 +5686,16 @@ impl Codegen {
         self.var_struct_types.insert(dst_var.to_string(), struct_name.to_string());
         self.struct_data_locations.insert(dst_var.to_string(), struct_base);
         
-        Ok(())
+        Ok(true)
     }
 }
 
 /// Generate x86-64 assembly from MIR
 pub fn generate_code(mir: &Mir) -> CodegenResult<String> {
-    let mut codegen = Codegen::new();
-    codegen.generate(mir)
+    generate_code_with_syntax(mir, AsmSyntax::Intel)
+}
+
+/// Generate assembly for `mir` in the given syntax
+pub fn generate_code_with_syntax(mir: &Mir, syntax: AsmSyntax) -> CodegenResult<String> {
+    Codegen::new().with_syntax(syntax).generate(mir)
 }
@@ -2,9 +2,15 @@
 //!
 //! Tracks live ranges and calculates register needs for better allocation decisions.
 //! Determines when spilling is necessary and selects optimal spill candidates.
+//! Locals are weighted by the loop depth of the blocks that use them, so hot
+//! loop variables keep their registers and cold locals spill first.
 
+use crate::mir::{successors, MirFunction, Operand, Place, Rvalue, Terminator};
 use std::collections::{HashMap, HashSet, BTreeMap};
 
+/// How much more a use one loop deeper counts for
+const LOOP_WEIGHT: usize = 10;
+
 /// A live range for a variable
 #[derive(Debug, Clone)]
 pub struct LiveRange {
@@ -206,6 +212,35 @@ impl RegisterPressureAnalyzer {
         candidates.first().map(|(name, _)| name.clone())
     }
 
+    /// Give registers to `func`'s locals hottest first and spill the rest.
+    /// Returns the spilled locals, coldest first.
+    pub fn allocate_by_loop_depth(&mut self, func: &MirFunction) -> Vec<String> {
+        let weights = use_weights(func);
+        let mut locals: Vec<(&String, &usize)> = weights.iter().collect();
+        locals.sort_by(|(a, wa), (b, wb)| wb.cmp(wa).then_with(|| a.cmp(b)));
+
+        let mut spilled = Vec::new();
+        for (name, _) in locals {
+            if self.allocate_to_register(name).is_err() {
+                self.allocate_to_stack(name);
+                if let Some(range) = self.live_ranges.get_mut(name.as_str()) {
+                    range.spilled = true;
+                }
+                spilled.push(name.clone());
+            }
+        }
+        spilled.reverse();
+        spilled
+    }
+
+    /// The register holding `var`, if it got one
+    pub fn register_of(&self, var: &str) -> Option<&str> {
+        self.registers
+            .iter()
+            .find(|reg| reg.current_value.as_deref() == Some(var))
+            .map(|reg| reg.name.as_str())
+    }
+
     /// Estimate code size from register pressure
     pub fn estimate_spill_code(&self) -> usize {
         let peak = self.peak_pressure();
@@ -234,6 +269,141 @@ impl RegisterPressureAnalyzer {
     }
 }
 
+/// How many loops each block of `func` sits in. A loop is found from each
+/// back edge (a jump to a block still on the depth-first search stack) and
+/// covers every block that reaches the back edge without passing its header.
+pub fn loop_depths(func: &MirFunction) -> Vec<usize> {
+    let count = func.basic_blocks.len();
+    let targets: Vec<Vec<usize>> = func
+        .basic_blocks
+        .iter()
+        .map(|block| successors(&block.terminator).into_iter().filter(|&t| t < count).collect())
+        .collect();
+    let mut predecessors = vec![Vec::new(); count];
+    for (block, succs) in targets.iter().enumerate() {
+        for &succ in succs {
+            predecessors[succ].push(block);
+        }
+    }
+
+    // Iterative DFS from the entry block, recording back edges by header
+    let mut back_edges: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    let mut visited = vec![false; count];
+    let mut on_stack = vec![false; count];
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    if count > 0 {
+        visited[0] = true;
+        on_stack[0] = true;
+        stack.push((0, 0));
+    }
+    while let Some((block, next)) = stack.last_mut() {
+        let block = *block;
+        if let Some(&succ) = targets[block].get(*next) {
+            *next += 1;
+            if on_stack[succ] {
+                back_edges.entry(succ).or_default().push(block);
+            } else if !visited[succ] {
+                visited[succ] = true;
+                on_stack[succ] = true;
+                stack.push((succ, 0));
+            }
+        } else {
+            on_stack[block] = false;
+            stack.pop();
+        }
+    }
+
+    let mut depths = vec![0; count];
+    for (header, latches) in back_edges {
+        let mut body: HashSet<usize> = HashSet::from([header]);
+        let mut worklist = latches;
+        while let Some(block) = worklist.pop() {
+            if body.insert(block) {
+                worklist.extend(predecessors[block].iter().copied());
+            }
+        }
+        for block in body {
+            depths[block] += 1;
+        }
+    }
+    depths
+}
+
+/// Every local's uses and definitions in `func`, each counted as
+/// `LOOP_WEIGHT` to the power of its block's loop depth
+pub fn use_weights(func: &MirFunction) -> HashMap<String, usize> {
+    let depths = loop_depths(func);
+    let mut weights: HashMap<String, usize> = HashMap::new();
+    for (param, _) in &func.params {
+        weights.entry(param.clone()).or_default();
+    }
+    for (block, depth) in func.basic_blocks.iter().zip(depths) {
+        let weight = LOOP_WEIGHT.saturating_pow(depth as u32);
+        let mut mentioned = Vec::new();
+        for stmt in &block.statements {
+            place_locals(&stmt.place, &mut mentioned);
+            rvalue_locals(&stmt.rvalue, &mut mentioned);
+        }
+        match &block.terminator {
+            Terminator::If(cond, _, _) => operand_local(cond, &mut mentioned),
+            Terminator::Return(Some(op)) => operand_local(op, &mut mentioned),
+            Terminator::Assert(cond, crate::mir::AssertKind::BoundsCheck { len, index }, _) => {
+                for op in [cond, len, index] {
+                    operand_local(op, &mut mentioned);
+                }
+            }
+            Terminator::Goto(_) | Terminator::Return(None) | Terminator::Unreachable => {}
+        }
+        for local in mentioned {
+            let entry = weights.entry(local.to_string()).or_default();
+            *entry = entry.saturating_add(weight);
+        }
+    }
+    weights
+}
+
+fn place_root(place: &Place) -> &str {
+    match place {
+        Place::Local(name) => name,
+        Place::Field(inner, _) | Place::Index(inner, _) | Place::DynamicIndex(inner, _) | Place::Deref(inner) => {
+            place_root(inner)
+        }
+    }
+}
+
+/// The local a place is rooted at, plus the index local of `v[i]`
+fn place_locals<'a>(place: &'a Place, out: &mut Vec<&'a str>) {
+    out.push(place_root(place));
+    if let Place::DynamicIndex(_, index) = place {
+        out.push(index);
+    }
+}
+
+fn operand_local<'a>(operand: &'a Operand, out: &mut Vec<&'a str>) {
+    if let Operand::Copy(place) | Operand::Move(place) = operand {
+        place_locals(place, out);
+    }
+}
+
+fn rvalue_locals<'a>(rvalue: &'a Rvalue, out: &mut Vec<&'a str>) {
+    match rvalue {
+        Rvalue::Use(op) | Rvalue::UnaryOp(_, op) => operand_local(op, out),
+        Rvalue::BinaryOp(_, l, r) => {
+            operand_local(l, out);
+            operand_local(r, out);
+        }
+        Rvalue::Call(_, ops) | Rvalue::Aggregate(_, ops) | Rvalue::Array(ops) => {
+            ops.iter().for_each(|op| operand_local(op, out));
+        }
+        Rvalue::Closure { captures, .. } => captures.iter().for_each(|op| operand_local(op, out)),
+        Rvalue::Ref(place) | Rvalue::Deref(place) | Rvalue::Field(place, _) => out.push(place_root(place)),
+        Rvalue::Index(place, op) => {
+            out.push(place_root(place));
+            operand_local(op, out);
+        }
+    }
+}
+
 /// Live range calculation algorithm
 pub struct LiveRangeCalculator {
     /// Function body (instruction sequence)
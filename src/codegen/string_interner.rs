@@ -0,0 +1,88 @@
+//! String literal interning for `.rodata`
+//!
+//! Every distinct string constant in a `Mir` gets one label, numbered in the
+//! order the literals first appear in the program (`str_0`, `str_1`, ...).
+//! The numbering doesn't depend on the order functions are generated in, so
+//! the emitted `.rodata` is identical from build to build.
+
+use crate::mir::{Constant, Mir, Operand, Rvalue, Terminator};
+use std::collections::HashMap;
+
+/// Deduplicated string constants with stable, insertion-ordered labels
+#[derive(Debug, Clone, Default)]
+pub struct StringInterner {
+    indices: HashMap<String, usize>,
+    strings: Vec<String>,
+}
+
+impl StringInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Intern every string constant in `mir`, in program order
+    pub fn from_mir(mir: &Mir) -> Self {
+        let mut interner = Self::new();
+        for func in &mir.functions {
+            for block in &func.basic_blocks {
+                for stmt in &block.statements {
+                    for operand in rvalue_operands(&stmt.rvalue) {
+                        interner.intern_operand(operand);
+                    }
+                }
+                match &block.terminator {
+                    Terminator::If(cond, _, _) | Terminator::Assert(cond, _, _) => interner.intern_operand(cond),
+                    Terminator::Return(Some(value)) => interner.intern_operand(value),
+                    _ => {}
+                }
+            }
+        }
+        interner
+    }
+
+    /// The label for `string`, assigning the next one on first use
+    pub fn intern(&mut self, string: &str) -> String {
+        if let Some(&index) = self.indices.get(string) {
+            return label(index);
+        }
+        let index = self.strings.len();
+        self.indices.insert(string.to_string(), index);
+        self.strings.push(string.to_string());
+        label(index)
+    }
+
+    fn intern_operand(&mut self, operand: &Operand) {
+        if let Operand::Constant(Constant::String(s)) = operand {
+            self.intern(s);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// `(label, string)` pairs in label order
+    pub fn entries(&self) -> impl Iterator<Item = (String, &str)> {
+        self.strings.iter().enumerate().map(|(index, s)| (label(index), s.as_str()))
+    }
+}
+
+fn label(index: usize) -> String {
+    format!("str_{}", index)
+}
+
+fn rvalue_operands(rvalue: &Rvalue) -> Vec<&Operand> {
+    match rvalue {
+        Rvalue::Use(operand) | Rvalue::UnaryOp(_, operand) | Rvalue::Index(_, operand) => vec![operand],
+        Rvalue::BinaryOp(_, left, right) => vec![left, right],
+        Rvalue::Call(_, operands)
+        | Rvalue::Aggregate(_, operands)
+        | Rvalue::Array(operands)
+        | Rvalue::Closure { captures: operands, .. } => operands.iter().collect(),
+        Rvalue::Ref(_) | Rvalue::Deref(_) | Rvalue::Field(_, _) => Vec::new(),
+    }
+}
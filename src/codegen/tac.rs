@@ -0,0 +1,440 @@
+//! # Three-Address Code Listing
+//!
+//! A textual form of the instructions codegen produces (`--emit=tac`), read
+//! off the same instruction stream `Codegen::generate` assembles, with the
+//! machine locations taken back out. Each stack slot becomes a virtual
+//! register, values passing through scratch registers are named where they
+//! are used, and calls take their arguments from the argument registers:
+//!
+//! ```text
+//! fn madd(%v0, %v1, %v2) {
+//! bb0:
+//!     %v3 = copy %v0
+//!     %v4 = copy %v1
+//!     %v5 = copy %v2
+//!     %v6 = mul %v4, %v5
+//!     %v7 = add %v3, %v6
+//!     ret %v7
+//! }
+//! ```
+//!
+//! Only the frame setup and teardown are left out, so the listing shows what
+//! the generated code computes without having to follow registers and stack
+//! offsets by hand.
+
+use super::{Register, X86Instruction, X86Operand};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// The registers integer arguments are passed in, in order
+const ARGUMENT_REGISTERS: [Register; 6] = [
+    Register::RDI,
+    Register::RSI,
+    Register::RDX,
+    Register::RCX,
+    Register::R8,
+    Register::R9,
+];
+
+/// The three-address listing of one function, given the instructions
+/// codegen emitted for it (starting at its label)
+pub fn listing(name: &str, param_count: usize, returns_value: bool, instructions: &[X86Instruction]) -> String {
+    let mut tac = FunctionTac::default();
+    let params: Vec<String> = (0..param_count).map(|_| tac.fresh()).collect();
+    for (reg, param) in ARGUMENT_REGISTERS.iter().zip(&params) {
+        tac.registers.insert(reg.to_string(), Value::Named(param.clone()));
+    }
+
+    let symbol = match instructions.first() {
+        Some(X86Instruction::Label { name }) => name.clone(),
+        _ => name.to_string(),
+    };
+    let block_prefix = format!("{}_", symbol);
+    tac.in_prologue = true;
+    for instr in instructions.iter().skip(1) {
+        tac.instruction(instr, &block_prefix, returns_value);
+    }
+
+    let mut out = format!("fn {}({}) {{\n", name, params.join(", "));
+    for line in &tac.lines {
+        let indent = if line.ends_with(':') { "" } else { "    " };
+        let _ = writeln!(out, "{}{}", indent, line);
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// What a register holds
+#[derive(Clone)]
+enum Value {
+    /// A virtual register or constant
+    Named(String),
+    /// An operation not yet given a register; it gets one where it is used,
+    /// or takes the slot it is stored to
+    Pending(String),
+}
+
+/// Where an instruction operand lives
+enum Location {
+    /// A machine register, by name
+    Register(String),
+    /// A stack slot, by its offset from RBP
+    Slot(i64),
+    /// Memory at an address computed from virtual registers
+    Memory(String),
+    Immediate(String),
+}
+
+#[derive(Default)]
+struct FunctionTac {
+    registers: HashMap<String, Value>,
+    slots: HashMap<i64, String>,
+    /// Which argument registers were written since the last call
+    arguments: [bool; 6],
+    comparison: Option<(String, String)>,
+    /// Before the first block, where parameters are spilled to their slots
+    in_prologue: bool,
+    next: usize,
+    lines: Vec<String>,
+}
+
+impl FunctionTac {
+    fn fresh(&mut self) -> String {
+        self.next += 1;
+        format!("%v{}", self.next - 1)
+    }
+
+    fn emit(&mut self, line: String) {
+        self.lines.push(line);
+    }
+
+    /// The virtual register a stack slot lives in
+    fn slot(&mut self, offset: i64) -> String {
+        if let Some(name) = self.slots.get(&offset) {
+            return name.clone();
+        }
+        let name = self.fresh();
+        self.slots.insert(offset, name.clone());
+        name
+    }
+
+    fn location(&mut self, operand: &X86Operand) -> Location {
+        match operand {
+            X86Operand::Register(reg) => Location::Register(reg.to_string()),
+            X86Operand::Immediate(value) => Location::Immediate(value.to_string()),
+            X86Operand::Memory { base: Register::RBP, offset } => Location::Slot(*offset),
+            X86Operand::Memory { base, offset } => {
+                let base = self.register(&base.to_string());
+                Location::Memory(address(&base, *offset))
+            }
+        }
+    }
+
+    /// The location of an SSE operand, which codegen keeps as Intel text
+    /// (`xmm0`, `qword ptr [rbp - 8]`, `qword ptr [rip + label]`)
+    fn text_location(&mut self, operand: &str) -> Location {
+        let operand = operand.trim_start_matches("qword ptr ");
+        let Some(inner) = operand.strip_prefix('[').and_then(|a| a.strip_suffix(']')) else {
+            return Location::Register(operand.to_string());
+        };
+        if let Some(label) = inner.strip_prefix("rip + ") {
+            return Location::Memory(format!("@{}", label));
+        }
+        let (base, offset) = match (inner.split_once(" - "), inner.split_once(" + ")) {
+            (Some((base, offset)), _) => (base, -offset.parse::<i64>().unwrap_or(0)),
+            (_, Some((base, offset))) => (base, offset.parse::<i64>().unwrap_or(0)),
+            _ => (inner, 0),
+        };
+        if base == "rbp" {
+            return Location::Slot(offset);
+        }
+        let base = self.register(base);
+        Location::Memory(address(&base, offset))
+    }
+
+    /// Name what a register holds, giving a pending operation its register
+    fn register(&mut self, reg: &str) -> String {
+        match self.registers.get(reg).cloned() {
+            Some(Value::Named(name)) => name,
+            Some(Value::Pending(op)) => {
+                let name = self.fresh();
+                self.emit(format!("{} = {}", name, op));
+                self.registers.insert(reg.to_string(), Value::Named(name.clone()));
+                name
+            }
+            None if reg == "rsp" || reg == "rbp" => "%sp".to_string(),
+            None => "undef".to_string(),
+        }
+    }
+
+    fn read(&mut self, location: &Location) -> String {
+        match location {
+            Location::Register(reg) => self.register(reg),
+            Location::Slot(offset) => self.slot(*offset),
+            Location::Memory(address) => {
+                let name = self.fresh();
+                self.emit(format!("{} = load {}", name, address));
+                name
+            }
+            Location::Immediate(value) => value.clone(),
+        }
+    }
+
+    /// What a move takes from `location`: a register's pending operation
+    /// moves along with it rather than being named at the register
+    fn take(&mut self, location: &Location) -> Value {
+        match location {
+            Location::Register(reg) => match self.registers.get(reg) {
+                Some(pending @ Value::Pending(_)) => pending.clone(),
+                _ => Value::Named(self.register(reg)),
+            },
+            _ => Value::Named(self.read(location)),
+        }
+    }
+
+    fn write(&mut self, location: &Location, value: Value) {
+        match location {
+            Location::Register(reg) => {
+                if let Some(index) = ARGUMENT_REGISTERS.iter().position(|arg| arg.to_string() == *reg) {
+                    self.arguments[index] = true;
+                }
+                self.registers.insert(reg.clone(), value);
+            }
+            Location::Slot(offset) => {
+                // A parameter spilled to its slot keeps the parameter's register
+                if let (true, Value::Named(name), false) = (self.in_prologue, &value, self.slots.contains_key(offset)) {
+                    if name.starts_with("%v") {
+                        self.slots.insert(*offset, name.clone());
+                        return;
+                    }
+                }
+                let slot = self.slot(*offset);
+                match value {
+                    Value::Pending(op) => self.emit(format!("{} = {}", slot, op)),
+                    Value::Named(name) => self.emit(format!("{} = copy {}", slot, name)),
+                }
+            }
+            Location::Memory(address) => {
+                let value = match value {
+                    Value::Named(name) => name,
+                    Value::Pending(op) => {
+                        let name = self.fresh();
+                        self.emit(format!("{} = {}", name, op));
+                        name
+                    }
+                };
+                self.emit(format!("store {}, {}", address, value));
+            }
+            Location::Immediate(_) => {}
+        }
+    }
+
+    fn mov(&mut self, dst: Location, src: Location) {
+        let value = self.take(&src);
+        // A pending operation moves to the new register instead of being copied
+        if let (Location::Register(_), Location::Register(reg), Value::Pending(_)) = (&dst, &src, &value) {
+            self.registers.remove(reg);
+        }
+        // The value now lives in the slot, so later uses read it from there
+        if let (Location::Slot(offset), Location::Register(reg), Value::Pending(_)) = (&dst, &src, &value) {
+            self.write(&dst, value);
+            let slot = self.slot(*offset);
+            self.registers.insert(reg.clone(), Value::Named(slot));
+            return;
+        }
+        self.write(&dst, value);
+    }
+
+    fn binary(&mut self, mnemonic: &str, dst: Location, src: Location) {
+        let left = self.read(&dst);
+        let right = self.read(&src);
+        self.write(&dst, Value::Pending(format!("{} {}, {}", mnemonic, left, right)));
+    }
+
+    fn set(&mut self, mnemonic: &str, dst: &X86Operand) {
+        let (left, right) = self.comparison.clone().unwrap_or_else(|| ("undef".to_string(), "undef".to_string()));
+        let dst = self.location(dst);
+        self.write(&dst, Value::Pending(format!("{} {}, {}", mnemonic, left, right)));
+    }
+
+    fn branch(&mut self, mnemonic: &str, label: &str) {
+        let (left, right) = self.comparison.clone().unwrap_or_else(|| ("undef".to_string(), "undef".to_string()));
+        if mnemonic == "ne" && right == "0" {
+            self.emit(format!("br {}, {}", left, label));
+            return;
+        }
+        let cond = self.fresh();
+        self.emit(format!("{} = {} {}, {}", cond, mnemonic, left, right));
+        self.emit(format!("br {}, {}", cond, label));
+    }
+
+    fn call(&mut self, callee: String) {
+        // Arguments fill the registers in order, so a scratch write to a later
+        // one (RCX around a `setcc`) isn't taken for an argument
+        let count = self.arguments.iter().take_while(|written| **written).count();
+        let args: Vec<String> = ARGUMENT_REGISTERS[..count]
+            .iter()
+            .map(|reg| self.register(&reg.to_string()))
+            .collect();
+        self.arguments = [false; 6];
+        let result = self.fresh();
+        self.emit(format!("{} = call {}({})", result, callee, args.join(", ")));
+        self.registers.insert(Register::RAX.to_string(), Value::Named(result));
+    }
+
+    fn instruction(&mut self, instr: &X86Instruction, block_prefix: &str, returns_value: bool) {
+        if touches_frame(instr) {
+            return;
+        }
+        match instr {
+            X86Instruction::Label { name } => {
+                self.in_prologue = false;
+                // Codegen reloads from the slots in each block, so whatever
+                // was left pending is dead
+                self.registers.retain(|_, value| matches!(value, Value::Named(_)));
+                self.arguments = [false; 6];
+                let name = name.strip_prefix(block_prefix).unwrap_or(name);
+                self.emit(format!("{}:", name));
+            }
+            X86Instruction::Mov { dst, src } => {
+                let src = self.location(src);
+                let dst = self.location(dst);
+                self.mov(dst, src);
+            }
+            X86Instruction::Movzx { dst, src } => {
+                let src = Location::Register(src.to_string());
+                self.mov(Location::Register(dst.to_string()), src);
+            }
+            X86Instruction::Extend { dst, src, bits, signed } => {
+                let value = self.register(&src.to_string());
+                let op = format!("{}ext{} {}", if *signed { "s" } else { "z" }, bits, value);
+                self.write(&Location::Register(dst.to_string()), Value::Pending(op));
+            }
+            X86Instruction::Lea { dst, src } => {
+                let dst = self.location(dst);
+                self.write(&dst, Value::Named(format!("@{}", src)));
+            }
+            X86Instruction::LeaMemory { dst, base, offset } => {
+                let target = match base {
+                    Register::RBP => self.slot(*offset),
+                    base => {
+                        let base = self.register(&base.to_string());
+                        address(&base, *offset)
+                    }
+                };
+                let dst = self.location(dst);
+                self.write(&dst, Value::Pending(format!("addr {}", target)));
+            }
+            X86Instruction::Xor { dst, src } if matches!((dst, src), (X86Operand::Register(a), X86Operand::Register(b)) if a == b) => {
+                let dst = self.location(dst);
+                self.write(&dst, Value::Named("0".to_string()));
+            }
+            X86Instruction::Add { dst, src } => self.operands("add", dst, src),
+            X86Instruction::Sub { dst, src } => self.operands("sub", dst, src),
+            X86Instruction::IMul { dst, src } => self.operands("mul", dst, src),
+            X86Instruction::Xor { dst, src } => self.operands("xor", dst, src),
+            X86Instruction::And { dst, src } => self.operands("and", dst, src),
+            X86Instruction::Or { dst, src } => self.operands("or", dst, src),
+            X86Instruction::Shl { dst, src } => self.operands("shl", dst, src),
+            X86Instruction::Shr { dst, src } => self.operands("shr", dst, src),
+            X86Instruction::Sar { dst, src } => self.operands("sar", dst, src),
+            X86Instruction::IDiv { src } => {
+                let src = self.location(src);
+                let dividend = self.register("rax");
+                let divisor = self.read(&src);
+                self.registers.insert("rax".to_string(), Value::Pending(format!("div {}, {}", dividend, divisor)));
+                self.registers.insert("rdx".to_string(), Value::Pending(format!("rem {}, {}", dividend, divisor)));
+            }
+            X86Instruction::Neg { dst } => {
+                let dst = self.location(dst);
+                let value = self.read(&dst);
+                self.write(&dst, Value::Pending(format!("neg {}", value)));
+            }
+            X86Instruction::Cmp { dst, src } => {
+                let dst = self.location(dst);
+                let src = self.location(src);
+                let left = self.read(&dst);
+                let right = self.read(&src);
+                self.comparison = Some((left, right));
+            }
+            X86Instruction::Sete { dst } => self.set("eq", dst),
+            X86Instruction::Setne { dst } => self.set("ne", dst),
+            X86Instruction::Setl { dst } => self.set("lt", dst),
+            X86Instruction::Setle { dst } => self.set("le", dst),
+            X86Instruction::Setg { dst } => self.set("gt", dst),
+            X86Instruction::Setge { dst } => self.set("ge", dst),
+            X86Instruction::Je { label } => self.branch("eq", strip(label, block_prefix)),
+            X86Instruction::Jne { label } => self.branch("ne", strip(label, block_prefix)),
+            X86Instruction::Jl { label } => self.branch("lt", strip(label, block_prefix)),
+            X86Instruction::Jle { label } => self.branch("le", strip(label, block_prefix)),
+            X86Instruction::Jg { label } => self.branch("gt", strip(label, block_prefix)),
+            X86Instruction::Jge { label } => self.branch("ge", strip(label, block_prefix)),
+            X86Instruction::Jmp { label } => {
+                let label = strip(label, block_prefix);
+                self.emit(format!("jmp {}", label));
+            }
+            X86Instruction::Call { func } => self.call(format!("@{}", func)),
+            X86Instruction::CallIndirect { reg } => {
+                let target = self.register(&reg.to_string());
+                self.call(format!("*{}", target));
+            }
+            X86Instruction::Ret => {
+                let line = if returns_value { format!("ret {}", self.register("rax")) } else { "ret".to_string() };
+                self.emit(line);
+            }
+            X86Instruction::Pop { reg } => {
+                self.registers.remove(&reg.to_string());
+            }
+            X86Instruction::Push { .. } | X86Instruction::Cqo | X86Instruction::Nop => {}
+            X86Instruction::Movsd { dst, src } => {
+                let src = self.text_location(src);
+                let dst = self.text_location(dst);
+                self.mov(dst, src);
+            }
+            X86Instruction::Addsd { dst, src } => self.text_operands("fadd", dst, src),
+            X86Instruction::Subsd { dst, src } => self.text_operands("fsub", dst, src),
+            X86Instruction::Mulsd { dst, src } => self.text_operands("fmul", dst, src),
+            X86Instruction::Divsd { dst, src } => self.text_operands("fdiv", dst, src),
+            X86Instruction::Sqrtsd { dst, src } => {
+                let src = self.text_location(src);
+                let value = self.read(&src);
+                let dst = self.text_location(dst);
+                self.write(&dst, Value::Pending(format!("fsqrt {}", value)));
+            }
+        }
+    }
+
+    fn operands(&mut self, mnemonic: &str, dst: &X86Operand, src: &X86Operand) {
+        let dst = self.location(dst);
+        let src = self.location(src);
+        self.binary(mnemonic, dst, src);
+    }
+
+    fn text_operands(&mut self, mnemonic: &str, dst: &str, src: &str) {
+        let dst = self.text_location(dst);
+        let src = self.text_location(src);
+        self.binary(mnemonic, dst, src);
+    }
+}
+
+/// Whether `instr` only sets up or tears down the stack frame
+fn touches_frame(instr: &X86Instruction) -> bool {
+    let frame = |operand: &X86Operand| matches!(operand, X86Operand::Register(Register::RSP | Register::RBP));
+    match instr {
+        X86Instruction::Mov { dst, .. } | X86Instruction::Add { dst, .. } | X86Instruction::Sub { dst, .. } => frame(dst),
+        X86Instruction::Push { reg } | X86Instruction::Pop { reg } => *reg == Register::RBP,
+        _ => false,
+    }
+}
+
+fn address(base: &str, offset: i64) -> String {
+    match offset {
+        0 => format!("[{}]", base),
+        offset if offset > 0 => format!("[{} + {}]", base, offset),
+        offset => format!("[{} - {}]", base, -offset),
+    }
+}
+
+fn strip<'a>(label: &'a str, block_prefix: &str) -> &'a str {
+    label.strip_prefix(block_prefix).unwrap_or(label)
+}
@@ -21,6 +21,8 @@
 //! }
 //! ```
 
+use crate::lowering::HirItem;
+use crate::typesystem::is_vtable_method;
 use std::collections::HashMap;
 
 /// Entry in a vtable (method pointer)
@@ -93,6 +95,20 @@ impl VTableGenerator {
         self.vtables.insert(trait_name, info);
     }
     
+    /// Register a trait from its lowered methods. Methods that need a sized
+    /// receiver, like `fn consume(self)`, can't be called through a trait
+    /// object and get no vtable slot.
+    pub fn register_trait_items(&mut self, trait_name: String, methods: &[HirItem]) {
+        let dispatchable = methods
+            .iter()
+            .filter_map(|method| match method {
+                HirItem::Function { name, params, .. } if is_vtable_method(params) => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        self.register_trait(trait_name, dispatchable);
+    }
+    
     /// Get vtable info for a trait
     pub fn get_vtable(&self, trait_name: &str) -> Option<&VTableInfo> {
         self.vtables.get(trait_name)
@@ -164,6 +180,36 @@ mod tests {
         assert_eq!(vtable.methods.get("move"), Some(&1));
     }
     
+    #[test]
+    fn test_vtable_skips_by_value_self_methods() {
+        use crate::lowering::HirType;
+        let method = |name: &str, self_ty: HirType| HirItem::Function {
+            name: name.to_string(),
+            generics: vec![],
+            params: vec![("self".to_string(), self_ty)],
+            return_type: None,
+            body: vec![],
+            is_public: false,
+            where_clause: vec![],
+            location: None,
+        };
+        let mut gen = VTableGenerator::new();
+        gen.register_trait_items(
+            "Shape".to_string(),
+            &[
+                method("area", HirType::Reference(Box::new(HirType::Named("Self".to_string())))),
+                method("consume", HirType::Named("Self".to_string())),
+                method("grow", HirType::MutableReference(Box::new(HirType::Named("Self".to_string())))),
+            ],
+        );
+        
+        let vtable = gen.get_vtable("Shape").unwrap();
+        assert_eq!(vtable.method_count, 2);
+        assert_eq!(vtable.methods.get("area"), Some(&0));
+        assert_eq!(vtable.methods.get("grow"), Some(&1));
+        assert_eq!(vtable.methods.get("consume"), None);
+    }
+    
     #[test]
     fn test_vtable_generation() {
         let mut gen = VTableGenerator::new();
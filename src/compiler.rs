@@ -1,13 +1,16 @@
 //! High-level compiler API for multi-file compilation
 
+use std::collections::HashMap;
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Instant;
 
 use crate::config::{CompilationConfig, OutputFormat};
 use crate::lexer;
+use crate::macros::{expansion::MacroExpansionPass, MacroExpander};
 use crate::parser;
 use crate::lowering;
 use crate::typechecker;
@@ -15,6 +18,9 @@ use crate::borrowchecker;
 use crate::mir;
 use crate::codegen;
 use crate::codegen::backend::assembler::Assembler;
+use crate::compiler_integration;
+use crate::lints;
+use crate::utilities::error_reporting::{stderr_supports_color, Diagnostic, ErrorReporter, Severity, SourceLocation};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorKind {
@@ -101,6 +107,10 @@ pub struct CompilationResult {
     pub output_files: Vec<PathBuf>,
     pub stats: CompilationStats,
     pub errors: Vec<CompileError>,
+    /// Warnings that didn't stop the build (empty under `warnings_as_errors`)
+    pub warnings: Vec<Diagnostic>,
+    /// Defined and referenced symbols, when `print_symbols` is set and codegen ran
+    pub symbols: Option<codegen::SymbolTable>,
 }
 
 #[derive(Debug, Clone)]
@@ -112,12 +122,16 @@ pub struct CompilationStats {
     pub lexing_time_ms: u128,
     pub parsing_time_ms: u128,
     pub lowering_time_ms: u128,
+    /// Time spent obtaining the stdlib prelude HIR (microseconds; ~0 once cached)
+    pub prelude_lowering_time_us: u128,
     pub typechecking_time_ms: u128,
     pub borrowchecking_time_ms: u128,
     pub mir_lowering_time_ms: u128,
     pub mir_optimization_time_ms: u128,
     pub codegen_time_ms: u128,
     pub output_time_ms: u128,
+    /// Instructions codegen emitted for each function, keyed by name
+    pub instructions_per_function: HashMap<String, usize>,
 }
 
 impl CompilationStats {
@@ -130,19 +144,32 @@ impl CompilationStats {
             lexing_time_ms: 0,
             parsing_time_ms: 0,
             lowering_time_ms: 0,
+            prelude_lowering_time_us: 0,
             typechecking_time_ms: 0,
             borrowchecking_time_ms: 0,
             mir_lowering_time_ms: 0,
             mir_optimization_time_ms: 0,
             codegen_time_ms: 0,
             output_time_ms: 0,
+            instructions_per_function: HashMap::new(),
         }
     }
+
+    /// Functions by the number of instructions they compiled to, largest first
+    pub fn largest_functions(&self) -> Vec<(&str, usize)> {
+        let mut sizes: Vec<(&str, usize)> = self.instructions_per_function
+            .iter()
+            .map(|(name, &count)| (name.as_str(), count))
+            .collect();
+        sizes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        sizes
+    }
 }
 
 /// Compile multiple files according to configuration
 pub fn compile_files(config: &CompilationConfig) -> Result<CompilationResult, CompileError> {
     let total_start = Instant::now();
+    crate::formatter::set_color(!config.no_color && stderr_supports_color());
     
     // Initialize dashboard for real-time progress display
     let mut dashboard = crate::dashboard::Dashboard::new();
@@ -152,11 +179,19 @@ pub fn compile_files(config: &CompilationConfig) -> Result<CompilationResult, Co
     let mut stats = CompilationStats::new();
     let mut errors = Vec::new();
     let mut output_files = Vec::new();
+    let mut symbols = None;
     let mut all_hir_items = Vec::new();
 
+    // The stdlib prelude is lowered once per process and cached. Do it before
+    // the user's files, since lowering resets the thread-local registries.
+    let prelude_start = Instant::now();
+    crate::stdlib::prelude_source::prelude_hir();
+    stats.prelude_lowering_time_us = prelude_start.elapsed().as_micros();
+
     // Parsing phase - compile main file first, then handle modules
     dashboard.start_phase("Parsing");
     let mut module_loader = crate::module_loader::ModuleLoader::new(".");
+    let mut lint_warnings = Vec::new();
     
     // Find the main file (conventionally main.rs or lib.rs)
     let main_file_path = config.source_files.iter()
@@ -170,7 +205,7 @@ pub fn compile_files(config: &CompilationConfig) -> Result<CompilationResult, Co
             println!("📝 Compiling: {}", main_source_file.display());
         }
 
-        match compile_single_file(main_source_file, config, &mut stats, &mut module_loader) {
+        match compile_single_file(main_source_file, config, true, &mut stats, &mut module_loader, &mut lint_warnings) {
             Ok((hir_items, loc)) => {
                 stats.files_compiled += 1;
                 stats.total_lines += loc;
@@ -195,7 +230,7 @@ pub fn compile_files(config: &CompilationConfig) -> Result<CompilationResult, Co
                 println!("📝 Compiling: {}", source_file.display());
             }
 
-            match compile_single_file(source_file, config, &mut stats, &mut module_loader) {
+            match compile_single_file(source_file, config, false, &mut stats, &mut module_loader, &mut lint_warnings) {
                 Ok((hir_items, loc)) => {
                     stats.files_compiled += 1;
                     stats.total_lines += loc;
@@ -215,14 +250,72 @@ pub fn compile_files(config: &CompilationConfig) -> Result<CompilationResult, Co
     }
     dashboard.end_phase("Parsing");
 
+    // An executable needs an entry point. Without this an empty file gets
+    // as far as the linker and fails on an undefined `gaia_main`.
+    let needs_main = matches!(config.output_format, OutputFormat::Executable | OutputFormat::BashScript);
+    if errors.is_empty() && needs_main && !defines_main(&all_hir_items) {
+        let help = match main_file_path.as_deref().and_then(|root| wrapped_main(root, &all_hir_items)) {
+            Some((file, module)) => format!(
+                "`{}` isn't named `main.rs` or `lib.rs`, so its items are compiled as module `{}` and its `main` \
+                 is `{}::main`; rename the file to `main.rs`",
+                file, module, module
+            ),
+            None => "add a `fn main() { ... }` or build a library with `--format lib`".to_string(),
+        };
+        let mut error = CompileError::new("Entry Point", "no `main` function found", ErrorKind::CodeIssue)
+            .with_help(&help);
+        error.file = main_file_path.clone();
+        errors.push(error);
+    }
+
     if !errors.is_empty() {
         let total_elapsed = total_start.elapsed().as_millis();
         stats.compilation_time_ms = total_elapsed;
+        cap_errors(&mut errors, config.max_errors);
         return Ok(CompilationResult {
             success: false,
             output_files: Vec::new(),
             stats,
             errors,
+            warnings: Vec::new(),
+            symbols: None,
+        });
+    }
+
+    // The lints ran on each file's own items, before the prelude is merged in
+    let mut reporter = ErrorReporter::new()
+        .with_warnings_as_errors(config.warnings_as_errors)
+        .with_color(crate::formatter::color_enabled());
+    for warning in lint_warnings {
+        reporter.add(warning);
+    }
+    let mut warnings = Vec::new();
+    for diagnostic in reporter.diagnostics() {
+        if diagnostic.severity == Severity::Error {
+            let mut error = CompileError::new(
+                &diagnostic.phase,
+                &format!("{} (warnings are treated as errors)", diagnostic.message),
+                ErrorKind::CodeIssue,
+            );
+            error.help = diagnostic.help.clone();
+            error.file = diagnostic.file.clone().or_else(|| main_file_path.clone());
+            error.line = diagnostic.location.map(|location| location.line);
+            error.column = diagnostic.location.map(|location| location.column);
+            errors.push(error);
+        } else {
+            warnings.push(diagnostic.clone());
+        }
+    }
+
+    // Merge in the stdlib prelude lowered above
+    let prelude_functions = crate::stdlib::prelude_source::merge_prelude(&mut all_hir_items);
+
+    // Host builtins are declared like an extern block, so they type-check
+    // against their registered signatures
+    if !config.custom_builtins.is_empty() {
+        all_hir_items.push(lowering::HirItem::ExternBlock {
+            abi: "C".to_string(),
+            functions: config.custom_builtins.iter().map(|builtin| builtin.declaration()).collect(),
         });
     }
 
@@ -249,7 +342,14 @@ pub fn compile_files(config: &CompilationConfig) -> Result<CompilationResult, Co
     dashboard.start_phase("Borrow Checking");
     let bc_start = Instant::now();
     if let Err(e) = borrowchecker::check_borrows(&all_hir_items) {
-        errors.push(CompileError::new("Borrow Checking", &e.to_string(), ErrorKind::CodeIssue));
+        let mut error = CompileError::new("Borrow Checking", &e.to_string(), ErrorKind::CodeIssue);
+        if let Some(file) = config.source_files.first() {
+            error = error.with_file(file.clone());
+        }
+        if let (Some(line), Some(column)) = (e.line, e.column) {
+            error = error.with_location(line, column);
+        }
+        errors.push(error);
     }
     stats.borrowchecking_time_ms = bc_start.elapsed().as_millis();
     dashboard.end_phase("Borrow Checking");
@@ -257,25 +357,35 @@ pub fn compile_files(config: &CompilationConfig) -> Result<CompilationResult, Co
     if !errors.is_empty() {
         let total_elapsed = total_start.elapsed().as_millis();
         stats.compilation_time_ms = total_elapsed;
+        cap_errors(&mut errors, config.max_errors);
         return Ok(CompilationResult {
             success: false,
             output_files: Vec::new(),
             stats,
             errors,
+            warnings,
+            symbols: None,
         });
     }
 
     // MIR Lowering phase
     dashboard.start_phase("MIR Lowering");
     let mir_lower_start = Instant::now();
-    match mir::lower_to_mir(&all_hir_items) {
+    match mir::lower_to_mir(&all_hir_items).and_then(|mir_items| mir_items.verify().map(|()| mir_items)) {
         Ok(mir_items) => {
             stats.mir_lowering_time_ms = mir_lower_start.elapsed().as_millis();
             dashboard.end_phase("MIR Lowering");
             
             let mir_opt_start = Instant::now();
             let mut optimized_mir = mir_items.clone();
-            if let Err(e) = mir::optimize_mir(&mut optimized_mir, config.opt_level) {
+            if config.ssa_renaming {
+                for func in &mut optimized_mir.functions {
+                    mir::rename_to_ssa(func);
+                }
+            }
+            if let Err(e) = mir::optimize_mir(&mut optimized_mir, config.opt_level)
+                .and_then(|()| optimized_mir.verify())
+            {
                 errors.push(CompileError::new("MIR Optimization", &e.to_string(), ErrorKind::InternalError));
             }
             stats.mir_optimization_time_ms = mir_opt_start.elapsed().as_millis();
@@ -284,10 +394,29 @@ pub fn compile_files(config: &CompilationConfig) -> Result<CompilationResult, Co
                 // Code Generation phase
                 dashboard.start_phase("Code Generation");
                 let codegen_start = Instant::now();
-                match codegen::generate_code(&optimized_mir) {
+                let mut generator = codegen::Codegen::new()
+                    .with_syntax(config.asm_syntax)
+                    .with_no_std(config.no_std)
+                    .with_overflow_checks(config.overflow_checks)
+                    .with_backtrace(config.backtrace)
+                    .with_stack_protector(config.stack_protector)
+                    .with_identical_function_folding(config.opt_level >= 2)
+                    .with_leaf_frame_omission(config.opt_level >= 2 && !config.force_frame_pointers)
+                    .with_custom_builtins(&config.custom_builtins)
+                    .with_library_functions(&prelude_functions);
+                let generated = if config.output_format == OutputFormat::ThreeAddressCode {
+                    generator.generate_tac(&optimized_mir)
+                } else {
+                    generator.generate(&optimized_mir)
+                };
+                match generated {
                     Ok(assembly) => {
+                        if config.print_symbols {
+                            symbols = Some(generator.symbols());
+                        }
                         stats.codegen_time_ms = codegen_start.elapsed().as_millis();
                         stats.assembly_size = assembly.len();
+                        stats.instructions_per_function = generator.instruction_counts().clone();
                         dashboard.end_phase("Code Generation");
                         
                         let output_start = Instant::now();
@@ -296,9 +425,9 @@ pub fn compile_files(config: &CompilationConfig) -> Result<CompilationResult, Co
                                 output_files = files;
                                 stats.output_time_ms = output_start.elapsed().as_millis();
                             }
-                            Err(e) => {
+                            Err(output_errors) => {
                                 stats.output_time_ms = output_start.elapsed().as_millis();
-                                errors.push(CompileError::new("Output Generation", &e, ErrorKind::InternalError));
+                                errors.extend(output_errors);
                             }
                         }
                     }
@@ -325,20 +454,133 @@ pub fn compile_files(config: &CompilationConfig) -> Result<CompilationResult, Co
     let total_elapsed = total_start.elapsed().as_millis();
     stats.compilation_time_ms = total_elapsed;
 
+    let success = errors.is_empty();
+    cap_errors(&mut errors, config.max_errors);
     Ok(CompilationResult {
-        success: errors.is_empty(),
+        success,
         output_files,
         stats,
         errors,
+        warnings,
+        symbols,
+    })
+}
+
+/// Drop everything past the first `max_errors` errors and note that the
+/// list was cut short. A cap of 0 keeps every error.
+fn cap_errors(errors: &mut Vec<CompileError>, max_errors: usize) {
+    if max_errors == 0 || errors.len() <= max_errors {
+        return;
+    }
+    errors.truncate(max_errors);
+    errors.push(CompileError::new(
+        "Compilation",
+        &format!("aborting due to {} previous errors; more may exist", max_errors),
+        ErrorKind::CodeIssue,
+    ));
+}
+
+/// Whether the crate root defines a `fn main`
+fn defines_main(items: &[lowering::HirItem]) -> bool {
+    items.iter().any(|item| matches!(item, lowering::HirItem::Function { name, .. } if name == "main"))
+}
+
+/// The file name and module of a crate root that lowering wrapped in a
+/// module named after it, when its `fn main` ended up inside
+fn wrapped_main(root: &std::path::Path, items: &[lowering::HirItem]) -> Option<(String, String)> {
+    let stem = root.file_stem()?.to_string_lossy();
+    items.iter().find_map(|item| match item {
+        lowering::HirItem::Module { name, items, .. } if *name == stem && defines_main(items) => {
+            Some((root.file_name()?.to_string_lossy().into_owned(), name.clone()))
+        }
+        _ => None,
     })
 }
 
+/// The `main` of a `--test` build: run each test, then report and exit
+fn test_harness_main(tests: &[String]) -> String {
+    let mut main = String::from("fn main() {\n");
+    for test in tests {
+        main.push_str(&format!("    gaia_run_test({}, \"{}\");\n", test, test));
+    }
+    main.push_str("    gaia_test_summary();\n}\n");
+    main
+}
+
+/// Swap the crate's `main` for the test harness. Only top-level `#[test]`
+/// functions are collected.
+fn add_test_harness(mut ast: parser::Program) -> Result<parser::Program, String> {
+    let tests = functions_with_attribute(&ast, "test");
+    ast.retain(|item| !matches!(item, parser::Item::Function { name, .. } if name == "main"));
+
+    let harness = lexer::lex(&test_harness_main(&tests))
+        .map_err(|e| e.to_string())
+        .and_then(parser::parse)?;
+    ast.extend(harness);
+    Ok(ast)
+}
+
+/// The `main` of a bench build: run each bench with a fresh `Bencher`, whose
+/// `iter` times `iterations` calls of its closure, and report the time per call
+fn bench_harness_main(benches: &[String], iterations: u64) -> String {
+    let mut main = String::from("#[repr(transparent)]\nstruct Bencher {\n    iterations: i64,\n}\n\nfn main() {\n");
+    for bench in benches {
+        main.push_str(&format!("    let mut {}_bencher = Bencher {{ iterations: {} }};\n", bench, iterations));
+        main.push_str(&format!("    {}(&mut {}_bencher);\n", bench, bench));
+        main.push_str(&format!(
+            "    println!(\"test {} ... bench: {{}} ns/iter\", gaia_bench_elapsed() / {});\n",
+            bench, iterations
+        ));
+    }
+    main.push_str("}\n");
+    main
+}
+
+/// The names of the crate's top-level functions carrying `#[name]`
+fn functions_with_attribute(ast: &parser::Program, name: &str) -> Vec<String> {
+    ast.iter()
+        .filter_map(|item| match item {
+            parser::Item::Function { name: function, attributes, .. } if attributes.iter().any(|attr| attr.name == name) => {
+                Some(function.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Drop the `#[test]` functions, here and in nested modules
+fn strip_test_functions(items: &mut Vec<parser::Item>) {
+    items.retain(|item| {
+        !matches!(item, parser::Item::Function { attributes, .. } if attributes.iter().any(|attr| attr.name == "test"))
+    });
+    for item in items {
+        if let parser::Item::Module { items, .. } = item {
+            strip_test_functions(items);
+        }
+    }
+}
+
+/// Swap the crate's `main` for the bench harness, which also defines the
+/// `Bencher` the `#[bench]` functions take
+fn add_bench_harness(mut ast: parser::Program, iterations: u64) -> Result<parser::Program, String> {
+    let benches = functions_with_attribute(&ast, "bench");
+    ast.retain(|item| !matches!(item, parser::Item::Function { name, .. } if name == "main"));
+
+    let harness = lexer::lex(&bench_harness_main(&benches, iterations))
+        .map_err(|e| e.to_string())
+        .and_then(parser::parse)?;
+    ast.extend(harness);
+    Ok(ast)
+}
+
 /// Compile a single source file
 fn compile_single_file(
     source_file: &std::path::Path,
-    _config: &CompilationConfig,
+    config: &CompilationConfig,
+    crate_root: bool,
     stats: &mut CompilationStats,
     _module_loader: &mut crate::module_loader::ModuleLoader,
+    lint_warnings: &mut Vec<Diagnostic>,
 ) -> Result<(Vec<lowering::HirItem>, usize), CompileError> {
     let source = fs::read_to_string(source_file).map_err(|e| {
         CompileError::new("File Reading", &format!("Failed to read file: {}", e), ErrorKind::InternalError)
@@ -346,35 +588,190 @@ fn compile_single_file(
     })?;
 
     let loc = source.lines().count();
+    let hir = lower_source(&source, source_file, config, crate_root, stats, lint_warnings)?;
+    Ok((hir, loc))
+}
 
+/// Run the front half of the pipeline on one file's `source`: lex, expand
+/// macros, parse (loading `mod name;` files next to `source_file`), lint
+/// the AST, add a harness to the crate root, lower to HIR and lint that
+pub(crate) fn lower_source(
+    source: &str,
+    source_file: &std::path::Path,
+    config: &CompilationConfig,
+    crate_root: bool,
+    stats: &mut CompilationStats,
+    lint_warnings: &mut Vec<Diagnostic>,
+) -> Result<Vec<lowering::HirItem>, CompileError> {
     let lex_start = Instant::now();
-    let tokens = lexer::lex(&source).map_err(|e| {
-        CompileError::new("Lexing", &e.to_string(), ErrorKind::CodeIssue)
+    let (tokens, positions): (Vec<_>, Vec<_>) = lexer::lex_with_positions(source).map_err(|e| {
+        let error = CompileError::new("Lexing", &e.to_string(), ErrorKind::CodeIssue)
+            .with_file(source_file.to_path_buf());
+        match e.location() {
+            Some(opened) => error.with_location(opened.line, opened.column),
+            None => error,
+        }
+    })?.into_iter().unzip();
+    stats.lexing_time_ms += lex_start.elapsed().as_millis();
+
+    // A missing closer otherwise surfaces as a confusing error at the end
+    // of the file, so point at the delimiter that was left open instead
+    if let Some(index) = parser::unclosed_delimiter(&tokens) {
+        let location = char_location(source, positions[index]);
+        return Err(CompileError::new(
+            "Parsing",
+            &format!("unclosed delimiter `{}` opened here", tokens[index]),
+            ErrorKind::CodeIssue,
+        )
+        .with_file(source_file.to_path_buf())
+        .with_location(location.line, location.column));
+    }
+
+    // User `macro_rules!` invocations are expanded before the parser sees them
+    let token_count = tokens.len();
+    let tokens = MacroExpansionPass::new(MacroExpander::new()).expand_tokens(tokens).map_err(|e| {
+        CompileError::new("Macro Expansion", &e, ErrorKind::CodeIssue)
             .with_file(source_file.to_path_buf())
     })?;
-    stats.lexing_time_ms += lex_start.elapsed().as_millis();
+    // Expansion that changed the token stream leaves the positions misaligned
+    let locations = if tokens.len() == token_count {
+        token_locations(source, &positions)
+    } else {
+        Vec::new()
+    };
 
     let parse_start = Instant::now();
-    let ast = parser::parse_with_modules(tokens, source_file.to_str()).map_err(|e| {
+    let mut ast = parser::parse_with_modules(tokens, locations, source_file.to_str()).map_err(|e| {
         CompileError::new("Parsing", &e.to_string(), ErrorKind::CodeIssue)
             .with_file(source_file.to_path_buf())
     })?;
     stats.parsing_time_ms += parse_start.elapsed().as_millis();
+    // Like rustc, only a `--test` build compiles the `#[test]` functions
+    if !config.test_harness {
+        strip_test_functions(&mut ast);
+    }
+    let allows = lints::LintAllows::collect(&ast);
+    lint_warnings.extend(lints::run_ast_lints(&ast, &allows).into_iter().map(|warning| warning.with_file(source_file.to_path_buf())));
+    // The harnesses replace the crate root's `main`
+    let ast = if crate_root && config.test_harness {
+        add_test_harness(ast).map_err(|e| {
+            CompileError::new("Test Harness", &e, ErrorKind::InternalError)
+                .with_file(source_file.to_path_buf())
+        })?
+    } else if crate_root && config.bench_harness {
+        add_bench_harness(ast, config.bench_iterations).map_err(|e| {
+            CompileError::new("Bench Harness", &e, ErrorKind::InternalError)
+                .with_file(source_file.to_path_buf())
+        })?
+    } else {
+        ast
+    };
 
     let lower_start = Instant::now();
     // Set current file for module-qualified function names
     lowering::set_current_file(source_file.to_str().unwrap_or("main.rs"));
+    lowering::set_overflow_checks(config.overflow_checks);
     let hir = lowering::lower(&ast).map_err(|e| {
         CompileError::new("Lowering", &e.to_string(), ErrorKind::CodeIssue)
             .with_file(source_file.to_path_buf())
     })?;
     stats.lowering_time_ms += lower_start.elapsed().as_millis();
+    lint_warnings.extend(lints::run_lints(&hir, &allows).into_iter().map(|warning| warning.with_file(source_file.to_path_buf())));
 
-    Ok((hir, loc))
+    Ok(hir)
+}
+
+/// The line and column of the character at `offset` in `source`
+fn char_location(source: &str, offset: usize) -> SourceLocation {
+    let byte_pos = source.char_indices().nth(offset).map_or(source.len(), |(pos, _)| pos);
+    let line_start = source[..byte_pos].rfind('\n').map_or(0, |newline| newline + 1);
+    let line = source[..byte_pos].matches('\n').count() + 1;
+    let column = source[line_start..byte_pos].chars().count() + 1;
+    SourceLocation::new(line, column, byte_pos)
+}
+
+/// The locations of ascending character offsets into `source`, found in a
+/// single pass
+fn token_locations(source: &str, offsets: &[usize]) -> Vec<SourceLocation> {
+    let mut locations = Vec::with_capacity(offsets.len());
+    let mut chars = source.char_indices().enumerate().peekable();
+    let (mut line, mut column) = (1, 1);
+    for &offset in offsets {
+        while let Some(&(index, (_, ch))) = chars.peek() {
+            if index >= offset {
+                break;
+            }
+            if ch == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+            chars.next();
+        }
+        let byte_pos = chars.peek().map_or(source.len(), |&(_, (pos, _))| pos);
+        locations.push(SourceLocation::new(line, column, byte_pos));
+    }
+    locations
+}
+
+/// Counter that keeps concurrent builds in one process out of each
+/// other's intermediates directory
+static INTERMEDIATE_DIRS: AtomicUsize = AtomicUsize::new(0);
+
+/// The `.s`/`.o` files a build goes through on the way to its output.
+/// They sit next to the output when kept, and otherwise in a fresh
+/// directory under the temp dir that's removed once the build is done.
+struct Intermediates {
+    asm_file: PathBuf,
+    obj_file: PathBuf,
+    scratch_dir: Option<PathBuf>,
+}
+
+impl Intermediates {
+    fn new(config: &CompilationConfig) -> Result<Self, String> {
+        if config.keep_intermediates {
+            return Ok(Intermediates {
+                asm_file: PathBuf::from(format!("{}.s", config.output_path.display())),
+                obj_file: PathBuf::from(format!("{}.o", config.output_path.display())),
+                scratch_dir: None,
+            });
+        }
+        let base = config.temp_dir.clone().unwrap_or_else(std::env::temp_dir);
+        let dir = base.join(format!(
+            "gaiarusted-{}-{}",
+            std::process::id(),
+            INTERMEDIATE_DIRS.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create temp directory {}: {}", dir.display(), e))?;
+        let stem = config.output_path.file_name().map_or("output".into(), |name| name.to_string_lossy());
+        Ok(Intermediates {
+            asm_file: dir.join(format!("{}.s", stem)),
+            obj_file: dir.join(format!("{}.o", stem)),
+            scratch_dir: Some(dir),
+        })
+    }
+
+    /// Clean up, returning the intermediate files that were kept. A build
+    /// that failed leaves its scratch directory behind, so errors located
+    /// in its `.s` still point at a file.
+    fn finish(self, built: bool) -> Vec<PathBuf> {
+        match self.scratch_dir {
+            Some(dir) => {
+                if built {
+                    let _ = fs::remove_dir_all(dir);
+                }
+                Vec::new()
+            }
+            None => [self.asm_file, self.obj_file].into_iter().filter(|file| file.exists()).collect(),
+        }
+    }
 }
 
 /// Write output files based on configuration
-fn write_output(config: &CompilationConfig, assembly: &str) -> Result<Vec<PathBuf>, String> {
+fn write_output(config: &CompilationConfig, assembly: &str) -> Result<Vec<PathBuf>, Vec<CompileError>> {
+    let output_error = |e: String| vec![CompileError::new("Output Generation", &e, ErrorKind::InternalError)];
     let mut files = Vec::new();
     let output_path = config.output_path_with_extension();
     let output_dir = config.output_path.parent().unwrap_or_else(|| std::path::Path::new("."));
@@ -382,57 +779,60 @@ fn write_output(config: &CompilationConfig, assembly: &str) -> Result<Vec<PathBu
     match config.output_format {
         OutputFormat::Assembly => {
             fs::write(&output_path, assembly)
-                .map_err(|e| format!("Failed to write assembly file: {}", e))?;
+                .map_err(|e| output_error(format!("Failed to write assembly file: {}", e)))?;
+            files.push(output_path);
+        }
+        OutputFormat::ThreeAddressCode => {
+            fs::write(&output_path, assembly)
+                .map_err(|e| output_error(format!("Failed to write three-address listing: {}", e)))?;
             files.push(output_path);
         }
         OutputFormat::Object => {
-            let asm_file = format!("{}.s", config.output_path.display());
-            fs::write(&asm_file, assembly)
-                .map_err(|e| format!("Failed to write assembly file: {}", e))?;
+            let temps = Intermediates::new(config).map_err(output_error)?;
+            let built = fs::write(&temps.asm_file, assembly)
+                .map_err(|e| output_error(format!("Failed to write assembly file: {}", e)))
+                .and_then(|_| compiler_integration::assemble_verified(&temps.asm_file, &output_path));
+            files.extend(temps.finish(built.is_ok()));
+            built?;
             
-            let assembler = Assembler::new(output_dir);
-            assembler.assemble_to_object(assembly, &output_path)?;
-            
-            files.push(PathBuf::from(&asm_file));
             files.push(output_path);
         }
         OutputFormat::Executable => {
-            let asm_file = format!("{}.s", config.output_path.display());
-            fs::write(&asm_file, assembly)
-                .map_err(|e| format!("Failed to write assembly file: {}", e))?;
-            
-            let assembler = Assembler::new(output_dir);
-            assembler.compile_to_executable(assembly, &output_path)?;
+            let temps = Intermediates::new(config).map_err(output_error)?;
+            let assembler = Assembler::new(output_dir).with_libc_linkage(config.libc_linkage);
+            let built = fs::write(&temps.asm_file, assembly)
+                .map_err(|e| output_error(format!("Failed to write assembly file: {}", e)))
+                .and_then(|_| compiler_integration::assemble_verified(&temps.asm_file, &temps.obj_file))
+                .and_then(|_| assembler.link_executable(&[&temps.obj_file], &output_path, &[]).map_err(output_error));
+            files.extend(temps.finish(built.is_ok()));
+            built?;
             
             fs::set_permissions(&output_path, std::fs::Permissions::from_mode(0o755))
-                .map_err(|e| format!("Failed to set executable permissions: {}", e))?;
+                .map_err(|e| output_error(format!("Failed to set executable permissions: {}", e)))?;
             
-            files.push(PathBuf::from(&asm_file));
             files.push(output_path);
         }
         OutputFormat::BashScript => {
             let asm_file = format!("{}.s", config.output_path.display());
             fs::write(&asm_file, assembly)
-                .map_err(|e| format!("Failed to write assembly file: {}", e))?;
+                .map_err(|e| output_error(format!("Failed to write assembly file: {}", e)))?;
             files.push(PathBuf::from(&asm_file));
             let binary_file = config.output_path.clone();
-            generate_bash_script(config, &asm_file, &output_path, &binary_file)?;
+            generate_bash_script(config, &asm_file, &output_path, &binary_file).map_err(output_error)?;
             files.push(output_path);
         }
         OutputFormat::Library => {
-            let asm_file = format!("{}.s", config.output_path.display());
-            fs::write(&asm_file, assembly)
-                .map_err(|e| format!("Failed to write assembly file: {}", e))?;
-            
-            let assembler = Assembler::new(output_dir);
-            let obj_file = format!("{}.o", config.output_path.display());
-            assembler.assemble_to_object(assembly, &PathBuf::from(&obj_file))?;
-            
+            let temps = Intermediates::new(config).map_err(output_error)?;
             let lib_file = format!("{}.a", config.output_path.display());
-            create_static_library(&obj_file, &lib_file)?;
+            let built = fs::write(&temps.asm_file, assembly)
+                .map_err(|e| output_error(format!("Failed to write assembly file: {}", e)))
+                .and_then(|_| compiler_integration::assemble_verified(&temps.asm_file, &temps.obj_file))
+                .and_then(|_| {
+                    create_static_library(&temps.obj_file.to_string_lossy(), &lib_file).map_err(output_error)
+                });
+            files.extend(temps.finish(built.is_ok()));
+            built?;
             
-            files.push(PathBuf::from(&asm_file));
-            files.push(PathBuf::from(&obj_file));
             files.push(PathBuf::from(&lib_file));
         }
     }
@@ -1,13 +1,16 @@
 //! Compiler Integration Layer
 //!
 //! Orchestrates monomorphization, symbol metadata collection, and LTO analysis
-//! across the compilation pipeline.
+//! across the compilation pipeline, and verifies the generated assembly.
 
 use crate::codegen::monomorphization::{MonomorphizationRegistry, collect_generics};
+use crate::compiler::{CompileError, ErrorKind};
 use crate::codegen::optimization::lto::{SymbolTable, FunctionInfo, SymbolVisibility, LinkTimeOptimizer};
 use crate::mir::{Mir, MirFunction, BasicBlock, Terminator, Operand, Rvalue};
 use crate::parser::ast::Item;
 use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::Command;
 
 /// Symbol metadata collected during compilation
 #[derive(Debug, Clone)]
@@ -383,6 +386,71 @@ impl IntegrationPipeline {
     }
 }
 
+/// Assemble the generated `asm_file` into `output_obj` with
+/// `as --fatal-warnings`. Anything the assembler complains about means
+/// codegen emitted bad assembly, so each message becomes a `CompileError`
+/// pointing at the line of the `.s` file and quoting its instruction.
+pub fn assemble_verified(asm_file: &Path, output_obj: &Path) -> Result<(), Vec<CompileError>> {
+    let output = Command::new("as")
+        .arg("--fatal-warnings")
+        .arg("-o")
+        .arg(output_obj)
+        .arg(asm_file)
+        .output()
+        .map_err(|e| {
+            vec![CompileError::new("Assembly", &format!("Failed to invoke assembler (as): {}", e), ErrorKind::InternalError)]
+        })?;
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let assembly = std::fs::read_to_string(asm_file).unwrap_or_default();
+    let mut errors = assembler_errors(&String::from_utf8_lossy(&output.stderr), &assembly);
+    for error in &mut errors {
+        error.file = Some(asm_file.to_path_buf());
+    }
+    if errors.is_empty() {
+        errors.push(CompileError::new("Assembly", "Assembler failed", ErrorKind::CompilerBug));
+    }
+    Err(errors)
+}
+
+/// Translate the assembler's `file:line: Error: message` and
+/// `file:line: Warning: message` lines into errors. The file-level
+/// summary `as` adds under `--fatal-warnings` carries no line and is
+/// dropped in favour of the warnings it counts.
+pub fn assembler_errors(stderr: &str, assembly: &str) -> Vec<CompileError> {
+    let lines: Vec<&str> = assembly.lines().collect();
+    let mut errors = Vec::new();
+    for message in stderr.lines() {
+        let Some((line, severity, text)) = parse_assembler_message(message) else { continue };
+        let mut error = CompileError::new(
+            "Assembly",
+            &format!("assembler {}: {}", severity.to_lowercase(), text),
+            ErrorKind::CompilerBug,
+        );
+        error.line = Some(line);
+        if let Some(instruction) = lines.get(line - 1) {
+            error.help = Some(format!("generated instruction: `{}`", instruction.trim()));
+        }
+        errors.push(error);
+    }
+    errors
+}
+
+/// Split `path:line: Severity: text` into its parts
+fn parse_assembler_message(message: &str) -> Option<(usize, &str, &str)> {
+    for severity in ["Error", "Warning"] {
+        let marker = format!(": {}: ", severity);
+        if let Some(at) = message.find(&marker) {
+            let (location, text) = (&message[..at], &message[at + marker.len()..]);
+            let line = location.rsplit(':').next()?.parse().ok().filter(|&line| line > 0)?;
+            return Some((line, severity, text));
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -424,6 +492,8 @@ mod tests {
             name: "foo".to_string(),
             params: Vec::new(),
             return_type: crate::lowering::HirType::Int64,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
             basic_blocks: vec![BasicBlock {
                 statements: Vec::new(),
                 terminator: Terminator::Return(None),
@@ -448,6 +518,8 @@ mod tests {
                 name: "live".to_string(),
                 params: Vec::new(),
                 return_type: crate::lowering::HirType::Int64,
+                vec_element_types: std::collections::HashMap::new(),
+                integer_widths: std::collections::HashMap::new(),
                 basic_blocks: vec![BasicBlock {
                     statements: Vec::new(),
                     terminator: Terminator::Return(None),
@@ -457,6 +529,8 @@ mod tests {
                 name: "dead".to_string(),
                 params: Vec::new(),
                 return_type: crate::lowering::HirType::Int64,
+                vec_element_types: std::collections::HashMap::new(),
+                integer_widths: std::collections::HashMap::new(),
                 basic_blocks: vec![BasicBlock {
                     statements: Vec::new(),
                     terminator: Terminator::Return(None),
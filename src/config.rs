@@ -4,6 +4,19 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use std::collections::HashMap;
 
+use crate::utilities::builtins::CustomBuiltin;
+
+/// Describe why a source file can't be used
+fn source_file_error(path: &Path, error: &std::io::Error) -> String {
+    match error.kind() {
+        std::io::ErrorKind::NotFound => format!("Source file not found: {}", path.display()),
+        std::io::ErrorKind::PermissionDenied => {
+            format!("Permission denied reading source file: {}", path.display())
+        }
+        _ => format!("Cannot read source file {}: {}", path.display(), error),
+    }
+}
+
 /// Output format for compiled code
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputFormat {
@@ -17,6 +30,8 @@ pub enum OutputFormat {
     BashScript,
     /// Library (static or dynamic)
     Library,
+    /// Three-address listing of the generated code, with stack slots as virtual registers (`--emit=tac`)
+    ThreeAddressCode,
 }
 
 impl OutputFormat {
@@ -27,6 +42,7 @@ impl OutputFormat {
             OutputFormat::Executable => "",
             OutputFormat::BashScript => ".sh",
             OutputFormat::Library => ".a",
+            OutputFormat::ThreeAddressCode => ".tac",
         }
     }
 
@@ -37,6 +53,7 @@ impl OutputFormat {
             OutputFormat::Executable => "Executable Binary",
             OutputFormat::BashScript => "Bash Script",
             OutputFormat::Library => "Static Library",
+            OutputFormat::ThreeAddressCode => "Three-Address Code",
         }
     }
 }
@@ -47,6 +64,27 @@ impl std::fmt::Display for OutputFormat {
     }
 }
 
+/// Assembly syntax of the generated code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AsmSyntax {
+    /// `mov rax, 5` (`.intel_syntax noprefix`)
+    #[default]
+    Intel,
+    /// `movq $5, %rax` (`.att_syntax`)
+    Att,
+}
+
+/// How the C library is linked into executables
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LibcLinkage {
+    /// Resolve libc at load time through `ld-linux-x86-64.so.2`
+    #[default]
+    Dynamic,
+    /// Copy libc (and libm) into the binary with `-static`, so it runs
+    /// without a matching libc on the target machine
+    Static,
+}
+
 /// Configuration for compilation
 #[derive(Debug, Clone)]
 pub struct CompilationConfig {
@@ -62,10 +100,47 @@ pub struct CompilationConfig {
      pub output_format: OutputFormat,
      /// Optimization level (0-3)
      pub opt_level: u32,
+     /// Rename MIR locals into SSA form before optimizing
+     pub ssa_renaming: bool,
+     /// Syntax of the emitted assembly
+     pub asm_syntax: AsmSyntax,
      /// Enable verbose output
      pub verbose: bool,
      /// Enable debug info
      pub debug: bool,
+     /// Treat integer overflow in constant expressions as a compile error, and
+     /// panic at runtime on shifts by at least the operand's bit width
+     pub overflow_checks: bool,
+     /// Fail the build if any warning is reported (`--werror`)
+     pub warnings_as_errors: bool,
+    /// Render diagnostics as plain ASCII without ANSI colors (`--no-color`)
+    pub no_color: bool,
+     /// Collect the defined and referenced symbols after codegen (`--print=symbols`)
+     pub print_symbols: bool,
+     /// Build a test harness that runs the `#[test]` functions (`--test`)
+     pub test_harness: bool,
+     /// Build a bench harness that runs each `#[bench]` function in a timed loop
+     pub bench_harness: bool,
+     /// How many times `Bencher::iter` calls its closure in a bench build
+     pub bench_iterations: u64,
+     /// Emit only the user's functions, without the `main` wrapper or runtime (`--no-std`)
+     pub no_std: bool,
+     /// Stop reporting after this many errors; 0 reports them all (`--max-errors=N`)
+     pub max_errors: usize,
+     /// Keep the `.s`/`.o` files next to the output instead of deleting them (`--keep-intermediates`)
+     pub keep_intermediates: bool,
+     /// Print the chain of function calls when the program panics (`--backtrace`)
+     pub backtrace: bool,
+     /// Check a stack canary in functions with stack arrays (`-fstack-protector`)
+     pub stack_protector: bool,
+     /// Keep the RBP frame in every function, even leaves that don't need it (`-fno-omit-frame-pointer`)
+     pub force_frame_pointers: bool,
+     /// Whether executables link libc statically or dynamically (`-static`)
+     pub libc_linkage: LibcLinkage,
+     /// Host functions callable from compiled code as builtins
+     pub custom_builtins: Vec<CustomBuiltin>,
+     /// Where intermediate files go when they aren't kept (default: the system temp dir)
+     pub temp_dir: Option<PathBuf>,
      /// Metadata about discovered modules
      pub module_map: HashMap<String, PathBuf>,
      /// Crate name (from Gaia.toml or Cargo.toml)
@@ -86,8 +161,26 @@ impl CompilationConfig {
             output_path: PathBuf::from("output"),
             output_format: OutputFormat::Executable,
             opt_level: 2,
+            ssa_renaming: false,
+            asm_syntax: AsmSyntax::Intel,
             verbose: false,
             debug: false,
+            overflow_checks: false,
+            warnings_as_errors: false,
+            no_color: false,
+            print_symbols: false,
+            test_harness: false,
+            bench_harness: false,
+            bench_iterations: 1000,
+            no_std: false,
+            max_errors: 100,
+            keep_intermediates: false,
+            backtrace: false,
+            stack_protector: false,
+            force_frame_pointers: false,
+            libc_linkage: LibcLinkage::Dynamic,
+            custom_builtins: Vec::new(),
+            temp_dir: None,
             module_map: HashMap::new(),
             crate_name: "unknown".to_string(),
             crate_version: "0.0.0".to_string(),
@@ -95,16 +188,20 @@ impl CompilationConfig {
         }
     }
 
-    /// Add a source file
+    /// Add a source file. The path is checked here rather than when
+    /// compiling: it must be a readable file with a `.rs` extension. It is
+    /// stored canonicalized.
     pub fn add_source_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self, String> {
-        let path = path.as_ref().to_path_buf();
-        if !path.exists() {
-            return Err(format!("Source file not found: {}", path.display()));
-        }
-        if !path.extension().map_or(false, |ext| ext == "rs") {
+        let path = path.as_ref();
+        let canonical = fs::canonicalize(path).map_err(|e| source_file_error(path, &e))?;
+        if canonical.extension() != Some(std::ffi::OsStr::new("rs")) {
             return Err(format!("Source file must have .rs extension: {}", path.display()));
         }
-        self.source_files.push(path);
+        if !canonical.is_file() {
+            return Err(format!("Source path is not a file: {}", path.display()));
+        }
+        fs::File::open(&canonical).map_err(|e| source_file_error(path, &e))?;
+        self.source_files.push(canonical);
         Ok(self)
     }
 
@@ -188,6 +285,20 @@ impl CompilationConfig {
         self
     }
 
+    /// Give every definition of a MIR local its own name before the
+    /// optimization passes run, joining versions with copies where
+    /// control flow merges
+    pub fn ssa_renaming(mut self, enabled: bool) -> Self {
+        self.ssa_renaming = enabled;
+        self
+    }
+
+    /// Set the syntax of the emitted assembly
+    pub fn set_asm_syntax(mut self, syntax: AsmSyntax) -> Self {
+        self.asm_syntax = syntax;
+        self
+    }
+
     /// Enable verbose output
     pub fn with_verbose(mut self, verbose: bool) -> Self {
         self.verbose = verbose;
@@ -200,6 +311,110 @@ impl CompilationConfig {
         self
     }
 
+    /// Enable overflow checks
+    pub fn with_overflow_checks(mut self, overflow_checks: bool) -> Self {
+        self.overflow_checks = overflow_checks;
+        self
+    }
+
+    /// Treat every warning as an error
+    pub fn warnings_as_errors(mut self, enabled: bool) -> Self {
+        self.warnings_as_errors = enabled;
+        self
+    }
+
+    /// Never color diagnostics, even when stderr is a terminal
+    pub fn no_color(mut self, enabled: bool) -> Self {
+        self.no_color = enabled;
+        self
+    }
+
+    /// Report the symbols the compiled unit defines and references
+    pub fn with_print_symbols(mut self, enabled: bool) -> Self {
+        self.print_symbols = enabled;
+        self
+    }
+
+    /// Replace `main` with a harness that runs every `#[test]` function
+    pub fn with_test_harness(mut self, enabled: bool) -> Self {
+        self.test_harness = enabled;
+        self
+    }
+
+    /// Replace `main` with a harness that runs every `#[bench]` function and
+    /// prints the time per iteration its `Bencher::iter` measured
+    pub fn with_bench_harness(mut self, enabled: bool) -> Self {
+        self.bench_harness = enabled;
+        self
+    }
+
+    /// Set how many times `Bencher::iter` calls its closure
+    pub fn set_bench_iterations(mut self, iterations: u64) -> Self {
+        self.bench_iterations = iterations.max(1);
+        self
+    }
+
+    /// Leave out the `main` wrapper and runtime helpers, for freestanding
+    /// targets that provide their own entry point
+    pub fn set_no_std(mut self, enabled: bool) -> Self {
+        self.no_std = enabled;
+        self
+    }
+
+    /// Cap the number of errors reported before giving up; 0 means no cap
+    pub fn set_max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = max_errors;
+        self
+    }
+
+    /// Keep the assembly and object files a build goes through, next to
+    /// the output, rather than deleting them once it's linked
+    pub fn keep_intermediates(mut self, enabled: bool) -> Self {
+        self.keep_intermediates = enabled;
+        self
+    }
+
+    /// Track calls in a shadow stack so a panic prints the functions it
+    /// happened in, innermost first
+    pub fn backtrace(mut self, enabled: bool) -> Self {
+        self.backtrace = enabled;
+        self
+    }
+
+    /// Give functions with stack arrays a canary copied from
+    /// `__stack_chk_guard`, checked before they return; a mismatch aborts
+    /// through `gaia_stack_chk_fail`
+    pub fn stack_protector(mut self, enabled: bool) -> Self {
+        self.stack_protector = enabled;
+        self
+    }
+
+    /// Keep the `push rbp; mov rbp, rsp` frame in every function at every
+    /// optimization level, for profilers that unwind through frame pointers
+    pub fn force_frame_pointers(mut self, enabled: bool) -> Self {
+        self.force_frame_pointers = enabled;
+        self
+    }
+
+    /// Link executables against libc statically or dynamically
+    pub fn set_libc_linkage(mut self, linkage: LibcLinkage) -> Self {
+        self.libc_linkage = linkage;
+        self
+    }
+
+    /// Expose a host function to compiled code as a builtin; calls to it
+    /// are emitted as calls to its external symbol
+    pub fn register_builtin(mut self, builtin: CustomBuiltin) -> Self {
+        self.custom_builtins.push(builtin);
+        self
+    }
+
+    /// Put intermediate files under `path` instead of the system temp dir
+    pub fn set_temp_dir<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.temp_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
     /// Load configuration from a Gaia.toml file
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
         let path = path.as_ref();
@@ -562,6 +777,7 @@ lib-paths = "/usr/lib, /usr/local/lib"
         assert_eq!(OutputFormat::Object.extension(), ".o");
         assert_eq!(OutputFormat::Executable.extension(), "");
         assert_eq!(OutputFormat::Library.extension(), ".a");
+        assert_eq!(OutputFormat::ThreeAddressCode.extension(), ".tac");
     }
 
     #[test]
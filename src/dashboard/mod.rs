@@ -6,6 +6,8 @@
 use std::time::{Duration, Instant};
 use std::collections::HashMap;
 
+use crate::formatter::glyph;
+
 pub mod animator;
 pub mod metrics;
 
@@ -53,7 +55,7 @@ impl Dashboard {
     pub fn start_phase(&mut self, phase_name: &str) {
         self.metrics.start_phase(phase_name);
         if self.enabled {
-            print!("{}  {:<30}", crate::formatter::Colors::CYAN, phase_name);
+            print!("{}  {:<30}", crate::formatter::Colors::cyan(), phase_name);
             std::io::Write::flush(&mut std::io::stdout()).ok();
         }
     }
@@ -66,7 +68,7 @@ impl Dashboard {
 
         if self.enabled {
             let bar = self.create_progress_bar(&duration);
-            println!("{} {:>6}ms{}", bar, duration.as_millis(), crate::formatter::Colors::RESET);
+            println!("{} {:>6}ms{}", bar, duration.as_millis(), crate::formatter::Colors::reset());
         }
     }
 
@@ -82,9 +84,9 @@ impl Dashboard {
 
         format!(
             "{}{}{}",
-            crate::formatter::Colors::GREEN,
-            "█".repeat(filled),
-            "░".repeat(empty)
+            crate::formatter::Colors::green(),
+            glyph("█", "#").repeat(filled),
+            glyph("░", ".").repeat(empty)
         )
     }
 
@@ -96,23 +98,31 @@ impl Dashboard {
 
         println!("\n");
         println!(
-            "{}╔═══════════════════════════════════════════════════════════════╗{}",
-            crate::formatter::Colors::CYAN,
-            crate::formatter::Colors::RESET
+            "{}{}{}{}{}",
+            crate::formatter::Colors::cyan(),
+            glyph("╔", "+"),
+            glyph("═", "=").repeat(63),
+            glyph("╗", "+"),
+            crate::formatter::Colors::reset()
         );
         println!(
-            "{}║{} Compilation Summary{}{}                                        {}║{}",
-            crate::formatter::Colors::CYAN,
-            crate::formatter::Colors::BOLD,
-            crate::formatter::Colors::CYAN,
-            crate::formatter::Colors::RESET,
-            crate::formatter::Colors::CYAN,
-            crate::formatter::Colors::RESET
+            "{}{}{} Compilation Summary{}{}                                        {}{}{}",
+            crate::formatter::Colors::cyan(),
+            glyph("║", "|"),
+            crate::formatter::Colors::bold(),
+            crate::formatter::Colors::cyan(),
+            crate::formatter::Colors::reset(),
+            crate::formatter::Colors::cyan(),
+            glyph("║", "|"),
+            crate::formatter::Colors::reset()
         );
         println!(
-            "{}╠═══════════════════════════════════════════════════════════════╣{}",
-            crate::formatter::Colors::CYAN,
-            crate::formatter::Colors::RESET
+            "{}{}{}{}{}",
+            crate::formatter::Colors::cyan(),
+            glyph("╠", "+"),
+            glyph("═", "=").repeat(63),
+            glyph("╣", "+"),
+            crate::formatter::Colors::reset()
         );
 
         // Display phase breakdown
@@ -128,50 +138,60 @@ impl Dashboard {
             let empty = 20 - filled;
             let bar = format!(
                 "{}{}{}{}",
-                crate::formatter::Colors::GREEN,
-                "█".repeat(filled),
-                "░".repeat(empty),
-                crate::formatter::Colors::RESET
+                crate::formatter::Colors::green(),
+                glyph("█", "#").repeat(filled),
+                glyph("░", ".").repeat(empty),
+                crate::formatter::Colors::reset()
             );
             
             // Display in decimal milliseconds for better precision on fast phases
             let duration_ms = duration.as_micros() as f64 / 1000.0;
             println!(
-                "{}║{} {:<20} {} {:>7.2}ms  {:>5.1}%{}                    {}║{}",
-                crate::formatter::Colors::CYAN,
-                crate::formatter::Colors::RESET,
+                "{}{}{} {:<20} {} {:>7.2}ms  {:>5.1}%{}                    {}{}{}",
+                crate::formatter::Colors::cyan(),
+                glyph("║", "|"),
+                crate::formatter::Colors::reset(),
                 phase_name,
                 bar,
                 duration_ms,
                 percent,
-                crate::formatter::Colors::RESET,
-                crate::formatter::Colors::CYAN,
-                crate::formatter::Colors::RESET
+                crate::formatter::Colors::reset(),
+                crate::formatter::Colors::cyan(),
+                glyph("║", "|"),
+                crate::formatter::Colors::reset()
             );
         }
 
         // Display total with fancy box
         println!(
-            "{}╠═══════════════════════════════════════════════════════════════╣{}",
-            crate::formatter::Colors::CYAN,
-            crate::formatter::Colors::RESET
+            "{}{}{}{}{}",
+            crate::formatter::Colors::cyan(),
+            glyph("╠", "+"),
+            glyph("═", "=").repeat(63),
+            glyph("╣", "+"),
+            crate::formatter::Colors::reset()
         );
         let total_ms = self.total_time.as_micros() as f64 / 1000.0;
         println!(
-            "{}║{} Total Compilation Time: {}{:.2}ms{}{}                          {}║{}",
-            crate::formatter::Colors::CYAN,
-            crate::formatter::Colors::RESET,
-            crate::formatter::Colors::BOLD,
+            "{}{}{} Total Compilation Time: {}{:.2}ms{}{}                          {}{}{}",
+            crate::formatter::Colors::cyan(),
+            glyph("║", "|"),
+            crate::formatter::Colors::reset(),
+            crate::formatter::Colors::bold(),
             total_ms,
-            crate::formatter::Colors::RESET,
-            crate::formatter::Colors::RESET,
-            crate::formatter::Colors::CYAN,
-            crate::formatter::Colors::RESET
+            crate::formatter::Colors::reset(),
+            crate::formatter::Colors::reset(),
+            crate::formatter::Colors::cyan(),
+            glyph("║", "|"),
+            crate::formatter::Colors::reset()
         );
         println!(
-            "{}╚═══════════════════════════════════════════════════════════════╝{}",
-            crate::formatter::Colors::CYAN,
-            crate::formatter::Colors::RESET
+            "{}{}{}{}{}",
+            crate::formatter::Colors::cyan(),
+            glyph("╚", "+"),
+            glyph("═", "=").repeat(63),
+            glyph("╝", "+"),
+            crate::formatter::Colors::reset()
         );
         println!();
     }
@@ -4,20 +4,44 @@
 //! Focus: Simple, clean, informative.
 
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
-/// Color codes for terminal output
+static COLOR: AtomicBool = AtomicBool::new(true);
+
+/// Turn ANSI colors and Unicode markers on or off for everything printed
+/// through this module and the phase dashboard. The compiler passes
+/// `!config.no_color && stderr_supports_color()`.
+pub fn set_color(enabled: bool) {
+    COLOR.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether output is colored, as last set by [`set_color`]
+pub fn color_enabled() -> bool {
+    COLOR.load(Ordering::Relaxed)
+}
+
+/// `unicode` when output is colored, else its plain ASCII stand-in
+pub fn glyph(unicode: &'static str, ascii: &'static str) -> &'static str {
+    if color_enabled() { unicode } else { ascii }
+}
+
+/// Color codes for terminal output, empty while colors are off
 pub struct Colors;
 
 impl Colors {
-    pub const RED: &'static str = "\x1b[31m";
-    pub const YELLOW: &'static str = "\x1b[33m";
-    pub const GREEN: &'static str = "\x1b[32m";
-    pub const BLUE: &'static str = "\x1b[34m";
-    pub const CYAN: &'static str = "\x1b[36m";
-    pub const BOLD: &'static str = "\x1b[1m";
-    pub const DIM: &'static str = "\x1b[2m";
-    pub const RESET: &'static str = "\x1b[0m";
+    fn code(code: &'static str) -> &'static str {
+        glyph(code, "")
+    }
+
+    pub fn red() -> &'static str { Self::code("\x1b[31m") }
+    pub fn yellow() -> &'static str { Self::code("\x1b[33m") }
+    pub fn green() -> &'static str { Self::code("\x1b[32m") }
+    pub fn blue() -> &'static str { Self::code("\x1b[34m") }
+    pub fn cyan() -> &'static str { Self::code("\x1b[36m") }
+    pub fn bold() -> &'static str { Self::code("\x1b[1m") }
+    pub fn dim() -> &'static str { Self::code("\x1b[2m") }
+    pub fn reset() -> &'static str { Self::code("\x1b[0m") }
 }
 
 /// Compilation status
@@ -32,19 +56,19 @@ pub enum Status {
 impl Status {
     pub fn symbol(&self) -> &'static str {
         match self {
-            Status::Success => "✓",
-            Status::Warning => "⚠",
-            Status::Error => "✗",
-            Status::Info => "•",
+            Status::Success => glyph("✓", "ok"),
+            Status::Warning => glyph("⚠", "!"),
+            Status::Error => glyph("✗", "x"),
+            Status::Info => glyph("•", "-"),
         }
     }
 
     pub fn color(&self) -> &'static str {
         match self {
-            Status::Success => Colors::GREEN,
-            Status::Warning => Colors::YELLOW,
-            Status::Error => Colors::RED,
-            Status::Info => Colors::CYAN,
+            Status::Success => Colors::green(),
+            Status::Warning => Colors::yellow(),
+            Status::Error => Colors::red(),
+            Status::Info => Colors::cyan(),
         }
     }
 }
@@ -115,10 +139,10 @@ impl Phase {
             "{}[Phase {}]{} {} ({})",
             status.color(),
             self.number,
-            Colors::RESET,
+            Colors::reset(),
             self.name,
-            Colors::DIM,
-        ) + self.description + Colors::RESET
+            Colors::dim(),
+        ) + self.description + Colors::reset()
         }
 }
 
@@ -163,24 +187,24 @@ impl Message {
             "{}{}{}",
             self.severity.color(),
             self.severity.symbol(),
-            Colors::RESET
+            Colors::reset()
         );
 
-        println!("{} {}", prefix, Colors::BOLD.to_string() + &self.title + Colors::RESET);
+        println!("{} {}", prefix, Colors::bold().to_string() + &self.title + Colors::reset());
 
         if let Some(details) = &self.details {
             println!("  {}", details);
         }
 
         if let Some(phase) = &self.phase {
-            println!("  {}{} in {}{}", Colors::DIM, "→", phase, Colors::RESET);
+            println!("  {}{} in {}{}", Colors::dim(), glyph("→", "->"), phase, Colors::reset());
         }
 
         if let Some(suggestion) = &self.suggestion {
             println!(
                 "  {}hint:{} {}",
-                Colors::CYAN,
-                Colors::RESET,
+                Colors::cyan(),
+                Colors::reset(),
                 suggestion
             );
         }
@@ -200,58 +224,60 @@ impl Stats {
     pub fn print(&self) {
         println!();
         println!(
-            "{}─────────────────────────────────────────{}",
-            Colors::DIM,
-            Colors::RESET
+            "{}{}{}",
+            Colors::dim(),
+            glyph("─", "-").repeat(41),
+            Colors::reset()
         );
         println!(
             "{}Compilation Summary{}",
-            Colors::BOLD,
-            Colors::RESET
+            Colors::bold(),
+            Colors::reset()
         );
         println!(
-            "{}─────────────────────────────────────────{}",
-            Colors::DIM,
-            Colors::RESET
+            "{}{}{}",
+            Colors::dim(),
+            glyph("─", "-").repeat(41),
+            Colors::reset()
         );
 
         println!(
             "  {} {}files{}",
-            Colors::CYAN,
+            Colors::cyan(),
             self.files,
-            Colors::RESET
+            Colors::reset()
         );
         println!(
             "  {} {}lines of code{}",
-            Colors::CYAN,
+            Colors::cyan(),
             self.lines,
-            Colors::RESET
+            Colors::reset()
         );
         println!(
             "  {} {}bytes of assembly{}",
-            Colors::CYAN,
+            Colors::cyan(),
             self.assembly_size,
-            Colors::RESET
+            Colors::reset()
         );
         println!(
             "  {} {}{}ms total{}",
-            Colors::GREEN,
+            Colors::green(),
             self.total_time.as_millis(),
-            Colors::RESET,
-            Colors::RESET
+            Colors::reset(),
+            Colors::reset()
         );
 
         println!();
-        println!("{}Phase breakdown:{}", Colors::DIM, Colors::RESET);
+        println!("{}Phase breakdown:{}", Colors::dim(), Colors::reset());
         for (phase, duration) in &self.phases {
             let percent = (duration.as_millis() as f64 / self.total_time.as_millis() as f64) * 100.0;
             println!(
                 "  {} {}ms {:.1}% {}{}",
-                Colors::CYAN,
+                Colors::cyan(),
                 duration.as_millis(),
                 percent,
                 phase,
-                Colors::RESET
+                Colors::reset()
             );
         }
 
@@ -262,44 +288,48 @@ impl Stats {
 /// Simple progress indicator
 pub fn progress(phase: &Phase) {
     println!(
-        "{}→ {} {}...{}",
-        Colors::BLUE,
+        "{}{} {} {}...{}",
+        Colors::blue(),
+        glyph("→", "->"),
         phase.name,
-        Colors::DIM,
-        Colors::RESET
+        Colors::dim(),
+        Colors::reset()
     );
 }
 
 /// Success banner
 pub fn success(msg: &str) {
     println!(
-        "{}{}✓ {}{}",
-        Colors::GREEN,
-        Colors::BOLD,
+        "{}{}{} {}{}",
+        Colors::green(),
+        Colors::bold(),
+        Status::Success.symbol(),
         msg,
-        Colors::RESET
+        Colors::reset()
     );
 }
 
 /// Error banner
 pub fn error(msg: &str) {
     eprintln!(
-        "{}{}✗ {}{}",
-        Colors::RED,
-        Colors::BOLD,
+        "{}{}{} {}{}",
+        Colors::red(),
+        Colors::bold(),
+        Status::Error.symbol(),
         msg,
-        Colors::RESET
+        Colors::reset()
     );
 }
 
 /// Info message
 pub fn info(msg: &str) {
     println!(
-        "{}{}• {}{}",
-        Colors::CYAN,
-        Colors::BOLD,
+        "{}{}{} {}{}",
+        Colors::cyan(),
+        Colors::bold(),
+        Status::Info.symbol(),
         msg,
-        Colors::RESET
+        Colors::reset()
     );
 }
 
@@ -308,9 +338,9 @@ pub fn start_compilation(input_file: &str) {
     println!();
     println!(
         "{}Compiling {}{}",
-        Colors::BLUE,
+        Colors::blue(),
         input_file,
-        Colors::RESET
+        Colors::reset()
     );
 }
 
@@ -324,27 +354,27 @@ pub fn type_mismatch(
     println!();
     eprintln!(
         "{}error{}[E0308]: {}",
-        Colors::RED,
-        Colors::RESET,
+        Colors::red(),
+        Colors::reset(),
         "type mismatch"
     );
     eprintln!(
         "{}  {}{}",
-        Colors::DIM,
+        Colors::dim(),
         context,
-        Colors::RESET
+        Colors::reset()
     );
     eprintln!(
         "{}  expected: {} found: {}{}",
-        Colors::CYAN,
+        Colors::cyan(),
         expected,
         found,
-        Colors::RESET
+        Colors::reset()
     );
 
     if !suggestions.is_empty() {
         eprintln!();
-        eprintln!("{}possible solutions:{}",Colors::YELLOW, Colors::RESET);
+        eprintln!("{}possible solutions:{}",Colors::yellow(), Colors::reset());
         for (i, suggestion) in suggestions.iter().enumerate() {
             eprintln!("  {}. {}", i + 1, suggestion);
         }
@@ -363,20 +393,20 @@ pub fn type_mismatch_with_suggestions(
     eprintln!();
     eprintln!(
         "{}error{}[E0308]: mismatched types",
-        Colors::RED,
-        Colors::RESET
+        Colors::red(),
+        Colors::reset()
     );
     eprintln!(
         "{}  expected: {}{}",
-        Colors::CYAN,
+        Colors::cyan(),
         expected,
-        Colors::RESET
+        Colors::reset()
     );
     eprintln!(
         "{}  found:    {}{}",
-        Colors::YELLOW,
+        Colors::yellow(),
         found,
-        Colors::RESET
+        Colors::reset()
     );
 
     let mut suggestions = TypeErrorSuggester::suggest_type_mismatch(expected, found, variable);
@@ -385,7 +415,7 @@ pub fn type_mismatch_with_suggestions(
         suggestions.sort_by(|a, b| b.confidence.cmp(&a.confidence));
         
         eprintln!();
-        eprintln!("{}help:{} Consider these options:", Colors::GREEN, Colors::RESET);
+        eprintln!("{}help:{} Consider these options:", Colors::green(), Colors::reset());
         for (i, suggestion) in suggestions.iter().take(3).enumerate() {
             eprintln!("  {}. {}", i + 1, suggestion.code);
         }
@@ -415,16 +445,16 @@ pub fn borrow_error(
     eprintln!();
     eprintln!(
         "{}error{}[E0502]: {}",
-        Colors::RED,
-        Colors::RESET,
+        Colors::red(),
+        Colors::reset(),
         error_type
     );
     eprintln!(
         "{}  variable `{}` {}{}",
-        Colors::CYAN,
+        Colors::cyan(),
         variable,
         reason,
-        Colors::RESET
+        Colors::reset()
     );
     eprintln!();
 }
@@ -438,14 +468,14 @@ pub fn lifetime_error(
     eprintln!();
     eprintln!(
         "{}error{}[E0623]: {}",
-        Colors::RED,
-        Colors::RESET,
+        Colors::red(),
+        Colors::reset(),
         "lifetime mismatch"
     );
-    eprintln!("{}  {}────────────────────{}",Colors::DIM, Colors::RESET, Colors::RESET);
-    eprintln!("{}  {} {}",Colors::CYAN, scope_a, Colors::RESET);
-    eprintln!("{}  │{}",Colors::DIM, Colors::RESET);
-    eprintln!("{}  └─→ conflicts with {}{}",Colors::DIM, scope_b, Colors::RESET);
+    eprintln!("{}  {}{}{}",Colors::dim(), Colors::reset(), glyph("─", "-").repeat(20), Colors::reset());
+    eprintln!("{}  {} {}",Colors::cyan(), scope_a, Colors::reset());
+    eprintln!("{}  {}{}",Colors::dim(), glyph("│", "|"), Colors::reset());
+    eprintln!("{}  {} conflicts with {}{}",Colors::dim(), glyph("└─→", "`->"), scope_b, Colors::reset());
     eprintln!();
 }
 
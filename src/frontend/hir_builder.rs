@@ -0,0 +1,42 @@
+//! HIR Builder
+//!
+//! Runs the front half of the pipeline (lex → macro expansion → parse →
+//! module resolution → lowering) and stops there, so tools and tests can
+//! inspect the HIR without type or borrow checking it.
+
+use crate::compiler::{self, CompilationStats};
+use crate::config::CompilationConfig;
+use crate::lowering::HirItem;
+use crate::stdlib::prelude_source;
+use crate::utilities::error_reporting::{Diagnostic, SourceLocation};
+use std::path::Path;
+
+/// Build the fully-resolved HIR for `source` as a crate root, with the
+/// stdlib prelude merged in as `compile_files` does.
+///
+/// This is the front half `compile_files` runs on each file, so macros are
+/// expanded and `mod name;` declarations are loaded relative to `base_dir`.
+/// The diagnostics are the lint warnings for `source`, or, when a phase
+/// fails, the error that stopped the pipeline, and the item list is empty.
+pub fn build_hir(source: &str, base_dir: &Path) -> (Vec<HirItem>, Vec<Diagnostic>) {
+    // Lowering resets the thread-local registries, so the prelude goes first
+    prelude_source::prelude_hir();
+
+    // Lower as the crate root so items keep their plain names
+    let root = base_dir.join("main.rs");
+    let mut stats = CompilationStats::new();
+    let mut lint_warnings = Vec::new();
+    match compiler::lower_source(source, &root, &CompilationConfig::new(), true, &mut stats, &mut lint_warnings) {
+        Ok(mut hir) => {
+            prelude_source::merge_prelude(&mut hir);
+            (hir, lint_warnings)
+        }
+        Err(e) => {
+            let mut error = Diagnostic::error(&e.phase, &e.message);
+            error.file = e.file;
+            error.location = e.line.map(|line| SourceLocation::new(line, e.column.unwrap_or(1), 0));
+            error.help = e.help;
+            (Vec::new(), vec![error])
+        }
+    }
+}
@@ -6,9 +6,13 @@
 //! - Macro optimization
 //! - Type-safe DSL support
 //! - Enhanced procedural macros
+//! - HIR builder for inspecting lowered programs
 
 pub mod derive_macros;
+pub mod hir_builder;
 pub mod macro_metavariables;
 pub mod macro_optimizer;
 pub mod procedural_macros_enhanced;
 pub mod type_safe_dsl;
+
+pub use hir_builder::build_hir;
@@ -50,12 +50,15 @@
 
 pub mod token;
 
+use crate::utilities::error_reporting::SourceLocation;
 use std::fmt;
 
 /// The main lexer struct. Contains the source code and current position.
 pub struct Lexer {
     input: Vec<char>,
     position: usize,
+    /// Character offset where the most recent token starts
+    token_start: usize,
 }
 
 impl Lexer {
@@ -69,6 +72,7 @@ impl Lexer {
         Lexer {
             input: input.chars().collect(),
             position: 0,
+            token_start: 0,
         }
     }
 
@@ -107,6 +111,20 @@ impl Lexer {
         self.position >= self.input.len()
     }
 
+    /// Line and column (both 1-based) of a character offset into the input.
+    fn location_of(&self, offset: usize) -> SourceLocation {
+        let before = &self.input[..offset.min(self.input.len())];
+        let line_start = before.iter().rposition(|&c| c == '\n').map_or(0, |newline| newline + 1);
+        let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+        let byte_pos = before.iter().map(|c| c.len_utf8()).sum();
+        SourceLocation::new(line, before.len() - line_start + 1, byte_pos)
+    }
+
+    /// Where the token being read opened, for errors about a literal that never closes.
+    fn opened_at(&self) -> SourceLocation {
+        self.location_of(self.token_start)
+    }
+
     /// Skip whitespace (spaces, tabs, newlines).
     fn skip_whitespace(&mut self) {
         while let Some(ch) = self.current_char() {
@@ -119,7 +137,7 @@ impl Lexer {
     }
 
     /// Skip a comment. Assumes we're at the start of a comment.
-    fn skip_comment(&mut self) {
+    fn skip_comment(&mut self) -> Result<(), LexError> {
         // Single-line comment: //
         if self.current_char() == Some('/') && self.peek_char(1) == Some('/') {
             self.advance(); // skip first /
@@ -135,6 +153,7 @@ impl Lexer {
         }
         // Multi-line comment: /* ... */
         else if self.current_char() == Some('/') && self.peek_char(1) == Some('*') {
+            let opened = self.location_of(self.position);
             self.advance(); // skip /
             self.advance(); // skip *
             // Skip until */
@@ -142,11 +161,13 @@ impl Lexer {
                 if self.current_char() == Some('*') && self.peek_char(1) == Some('/') {
                     self.advance(); // skip *
                     self.advance(); // skip /
-                    break;
+                    return Ok(());
                 }
                 self.advance();
             }
+            return Err(LexError::UnterminatedBlockComment(opened));
         }
+        Ok(())
     }
 
     /// Read a number (integer or float).
@@ -310,6 +331,7 @@ impl Lexer {
 
     /// Read a string literal.
     fn read_string(&mut self) -> Result<token::Token, LexError> {
+        let opened = self.opened_at();
         self.advance(); // skip opening quote
         let mut string = String::new();
 
@@ -325,11 +347,12 @@ impl Lexer {
                     Some('r') => { string.push('\r'); self.advance(); }
                     Some('\\') => { string.push('\\'); self.advance(); }
                     Some('"') => { string.push('"'); self.advance(); }
+                    Some('\n') | Some('\r') if self.at_line_end() => self.skip_line_continuation(),
                     Some(ch) => {
                         string.push(ch);
                         self.advance();
                     }
-                    None => return Err(LexError::UnterminatedString),
+                    None => return Err(LexError::UnterminatedString(opened)),
                 }
             } else {
                 string.push(ch);
@@ -337,7 +360,7 @@ impl Lexer {
             }
         }
 
-        Err(LexError::UnterminatedString)
+        Err(LexError::UnterminatedString(opened))
     }
 
     /// Check if this looks like a lifetime (e.g., 'a, 'static, '_).
@@ -379,6 +402,7 @@ impl Lexer {
 
     /// Read a lifetime token (e.g., 'a, 'static, '_).
     fn read_lifetime(&mut self) -> Result<token::Token, LexError> {
+        let opened = self.opened_at();
         self.advance(); // skip opening quote
         let mut lifetime = String::new();
 
@@ -392,7 +416,7 @@ impl Lexer {
         }
 
         if lifetime.is_empty() {
-            Err(LexError::UnterminatedChar)
+            Err(LexError::UnterminatedChar(opened))
         } else {
             Ok(token::Token::Lifetime(lifetime))
         }
@@ -400,6 +424,7 @@ impl Lexer {
 
     /// Read a character literal.
     fn read_char(&mut self) -> Result<token::Token, LexError> {
+        let opened = self.opened_at();
         self.advance(); // skip opening quote
         let mut char_val = '\0';
 
@@ -413,7 +438,7 @@ impl Lexer {
                     Some('\\') => '\\',
                     Some('\'') => '\'',
                     Some(c) => c,
-                    None => return Err(LexError::UnterminatedChar),
+                    None => return Err(LexError::UnterminatedChar(opened)),
                 };
                 self.advance();
             } else {
@@ -426,12 +451,13 @@ impl Lexer {
             self.advance(); // skip closing quote
             Ok(token::Token::Char(char_val))
         } else {
-            Err(LexError::UnterminatedChar)
+            Err(LexError::UnterminatedChar(opened))
         }
     }
 
     /// Read a raw string: r"..."
     fn read_raw_string(&mut self) -> Result<token::Token, LexError> {
+        let opened = self.opened_at();
         self.advance(); // skip opening quote
         let mut string = String::new();
 
@@ -445,11 +471,12 @@ impl Lexer {
             }
         }
 
-        Err(LexError::UnterminatedString)
+        Err(LexError::UnterminatedString(opened))
     }
 
     /// Read a raw string with hashes: r#"..."#
     fn read_raw_string_with_hashes(&mut self) -> Result<token::Token, LexError> {
+        let opened = self.opened_at();
         // Count opening hashes
         let mut hash_count = 0;
         while self.current_char() == Some('#') {
@@ -458,7 +485,7 @@ impl Lexer {
         }
 
         if self.current_char() != Some('"') {
-            return Err(LexError::UnterminatedString);
+            return Err(LexError::UnterminatedString(opened));
         }
         self.advance(); // skip opening quote
 
@@ -490,11 +517,12 @@ impl Lexer {
             }
         }
 
-        Err(LexError::UnterminatedString)
+        Err(LexError::UnterminatedString(opened))
     }
 
     /// Read a byte string: b"..."
     fn read_byte_string(&mut self) -> Result<token::Token, LexError> {
+        let opened = self.opened_at();
         self.advance(); // skip opening quote
         let mut bytes = Vec::new();
 
@@ -511,25 +539,58 @@ impl Lexer {
                     Some('\\') => { bytes.push(b'\\'); self.advance(); }
                     Some('"') => { bytes.push(b'"'); self.advance(); }
                     Some('0') => { bytes.push(0u8); self.advance(); }
+                    Some('x') => bytes.push(self.read_byte_escape().ok_or(LexError::UnterminatedString(opened))?),
+                    Some('\n') | Some('\r') if self.at_line_end() => self.skip_line_continuation(),
                     Some(ch) if ch.is_ascii() => { 
                         bytes.push(ch as u8); 
                         self.advance(); 
                     }
-                    _ => return Err(LexError::UnterminatedString),
+                    _ => return Err(LexError::UnterminatedString(opened)),
                 }
             } else if ch.is_ascii() {
                 bytes.push(ch as u8);
                 self.advance();
             } else {
-                return Err(LexError::UnterminatedString);
+                return Err(LexError::UnterminatedString(opened));
             }
         }
 
-        Err(LexError::UnterminatedString)
+        Err(LexError::UnterminatedString(opened))
+    }
+
+    /// Whether the lexer is at a `\n` or `\r\n` line ending
+    fn at_line_end(&self) -> bool {
+        match self.current_char() {
+            Some('\n') => true,
+            Some('\r') => self.peek_char(1) == Some('\n'),
+            _ => false,
+        }
+    }
+
+    /// Skip a `\`-newline line continuation in a string literal, starting at
+    /// the newline: the newline and the next line's leading whitespace are
+    /// dropped
+    fn skip_line_continuation(&mut self) {
+        while self.current_char().is_some_and(char::is_whitespace) {
+            self.advance();
+        }
+    }
+
+    /// Read the two hex digits of a `\xNN` escape, starting at the `x`
+    fn read_byte_escape(&mut self) -> Option<u8> {
+        self.advance(); // skip 'x'
+        let mut value = 0u8;
+        for _ in 0..2 {
+            let digit = self.current_char()?.to_digit(16)?;
+            value = value * 16 + digit as u8;
+            self.advance();
+        }
+        Some(value)
     }
 
     /// Read a byte character: b'...'
     fn read_byte_char(&mut self) -> Result<token::Token, LexError> {
+        let opened = self.opened_at();
         self.advance(); // skip opening quote
         let mut byte_val: u8 = 0;
 
@@ -537,21 +598,27 @@ impl Lexer {
             if ch == '\\' {
                 self.advance();
                 byte_val = match self.current_char() {
-                    Some('n') => b'\n',
-                    Some('t') => b'\t',
-                    Some('r') => b'\r',
-                    Some('\\') => b'\\',
-                    Some('\'') => b'\'',
-                    Some('0') => 0u8,
-                    Some(c) if c.is_ascii() => c as u8,
-                    _ => return Err(LexError::UnterminatedChar),
+                    Some('x') => self.read_byte_escape().ok_or(LexError::UnterminatedChar(opened))?,
+                    escaped => {
+                        let byte = match escaped {
+                            Some('n') => b'\n',
+                            Some('t') => b'\t',
+                            Some('r') => b'\r',
+                            Some('\\') => b'\\',
+                            Some('\'') => b'\'',
+                            Some('0') => 0u8,
+                            Some(c) if c.is_ascii() => c as u8,
+                            _ => return Err(LexError::UnterminatedChar(opened)),
+                        };
+                        self.advance();
+                        byte
+                    }
                 };
-                self.advance();
             } else if ch.is_ascii() {
                 byte_val = ch as u8;
                 self.advance();
             } else {
-                return Err(LexError::UnterminatedChar);
+                return Err(LexError::UnterminatedChar(opened));
             }
         }
 
@@ -559,7 +626,7 @@ impl Lexer {
             self.advance(); // skip closing quote
             Ok(token::Token::ByteChar(byte_val))
         } else {
-            Err(LexError::UnterminatedChar)
+            Err(LexError::UnterminatedChar(opened))
         }
     }
 
@@ -595,13 +662,14 @@ impl Lexer {
 
             // Check for comments
             if self.current_char() == Some('/') && (self.peek_char(1) == Some('/') || self.peek_char(1) == Some('*')) {
-                self.skip_comment();
+                self.skip_comment()?;
                 continue;
             }
 
             break;
         }
 
+        self.token_start = self.position;
         let ch = match self.current_char() {
             Some(c) => c,
             None => return Ok(None),
@@ -869,8 +937,24 @@ impl Lexer {
 pub enum LexError {
     UnexpectedCharacter(char),
     InvalidNumber(String),
-    UnterminatedString,
-    UnterminatedChar,
+    /// A string literal with no closing quote; holds where it opened
+    UnterminatedString(SourceLocation),
+    /// A char literal with no closing quote; holds where it opened
+    UnterminatedChar(SourceLocation),
+    /// A `/*` with no matching `*/`; holds where it opened
+    UnterminatedBlockComment(SourceLocation),
+}
+
+impl LexError {
+    /// Where in the source the error points, when it is known
+    pub fn location(&self) -> Option<SourceLocation> {
+        match self {
+            LexError::UnterminatedString(opened)
+            | LexError::UnterminatedChar(opened)
+            | LexError::UnterminatedBlockComment(opened) => Some(*opened),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for LexError {
@@ -878,8 +962,9 @@ impl fmt::Display for LexError {
         match self {
             LexError::UnexpectedCharacter(ch) => write!(f, "Unexpected character: '{}'", ch),
             LexError::InvalidNumber(num) => write!(f, "Invalid number: {}", num),
-            LexError::UnterminatedString => write!(f, "Unterminated string"),
-            LexError::UnterminatedChar => write!(f, "Unterminated character literal"),
+            LexError::UnterminatedString(opened) => write!(f, "unterminated string literal opened at {}", opened),
+            LexError::UnterminatedChar(opened) => write!(f, "unterminated character literal opened at {}", opened),
+            LexError::UnterminatedBlockComment(opened) => write!(f, "unterminated block comment opened at {}", opened),
         }
     }
 }
@@ -903,6 +988,20 @@ pub fn lex(input: &str) -> Result<Vec<token::Token>, LexError> {
     Ok(tokens)
 }
 
+/// Like [`lex`], but pairs each token with the character offset it starts
+/// at, for diagnostics that need to point back into the source.
+pub fn lex_with_positions(input: &str) -> Result<Vec<(token::Token, usize)>, LexError> {
+    let mut lexer = Lexer::new(input);
+    let mut tokens = Vec::new();
+
+    while let Some(token) = lexer.next_token()? {
+        tokens.push((token, lexer.token_start));
+    }
+
+    tokens.push((token::Token::Eof, lexer.input.len()));
+    Ok(tokens)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -925,4 +1024,11 @@ mod tests {
         let tokens = lex("variable_name").unwrap();
         assert!(matches!(tokens[0], token::Token::Identifier(_)));
     }
+
+    #[test]
+    fn test_string_line_continuation() {
+        let tokens = lex("\"hello, \\\n        world\" b\"a\\\r\n  b\"").unwrap();
+        assert_eq!(tokens[0], token::Token::String("hello, world".to_string()));
+        assert_eq!(tokens[1], token::Token::ByteString(b"ab".to_vec()));
+    }
 }
\ No newline at end of file
@@ -58,6 +58,7 @@ pub mod typechecker;
 pub mod typesystem;
 pub mod borrowchecker;
 pub mod analysis;
+pub mod lints;
 
 // Codegen: IR, Lowering, Code Generation
 pub mod lowering;
@@ -124,10 +125,10 @@ pub mod modules {
     pub use crate::utilities::modules::*;
 }
 
-pub use config::{CompilationConfig, OutputFormat};
+pub use config::{AsmSyntax, CompilationConfig, LibcLinkage, OutputFormat};
 pub use compiler::{compile_files, CompilationResult, CompileError, ErrorKind};
 pub use utilities::error_reporting::{Diagnostic, ErrorReporter, SourceLocation, Severity};
-pub use utilities::builtins::BuiltinFunction;
+pub use utilities::builtins::{BuiltinFunction, CustomBuiltin};
 pub use utilities::profiling::{Profiler, CompilationStats as ProfileStats};
 pub use utilities::colors::{Color, Colored};
 pub use cargo_api::{CargoAPI, CargoProject, CargoManifest, CargoBuildConfig, BuildProfile, CrateType};
@@ -0,0 +1,598 @@
+//! # Lints
+//!
+//! Warning-level checks over the lowered HIR, plus a few over the AST for
+//! facts lowering doesn't keep. Lints never fail a build on their own;
+//! `CompilationConfig::warnings_as_errors` promotes them through the
+//! `ErrorReporter`.
+
+use crate::lowering::{BinaryOp, HirExpression, HirItem, HirStatement};
+use crate::parser::ast::{Attribute, Block, Expression, Item, Statement};
+use crate::utilities::error_reporting::Diagnostic;
+use std::collections::{HashMap, HashSet};
+
+/// Phase name attached to lint diagnostics
+pub const LINT_PHASE: &str = "Lints";
+
+/// Run every lint over `items` and return the resulting warnings, leaving
+/// out the ones `allows` turns off
+pub fn run_lints(items: &[HirItem], allows: &LintAllows) -> Vec<Diagnostic> {
+    let mut warnings = Vec::new();
+    for item in items {
+        lint_item(item, "", allows, &mut warnings);
+    }
+    warnings
+}
+
+fn lint_item(item: &HirItem, prefix: &str, allows: &LintAllows, warnings: &mut Vec<Diagnostic>) {
+    match item {
+        HirItem::Function { name, body, location, .. } => {
+            // An instantiated generic (`max.i64`) is allowed like its template
+            let template = name.split('.').next().unwrap_or(name);
+            let allowed = allows.0.get(&format!("{}{}", prefix, template));
+            let allowed = |lint: &str| allowed.is_some_and(|allowed| is_allowed(allowed, lint));
+            let mut found = Vec::new();
+            if !allowed("unused_variables") {
+                check_unused_variables(name, body, &mut found);
+            }
+            if !allowed("unconditional_recursion") {
+                check_unconditional_recursion(name, body, &mut found);
+            }
+            // HIR keeps no spans below the item, so point at the function
+            warnings.extend(found.into_iter().map(|warning| Diagnostic { location: *location, ..warning }));
+        }
+        HirItem::Module { name, items, .. } => {
+            let prefix = format!("{}{}::", prefix, name);
+            for item in items {
+                lint_item(item, &prefix, allows, warnings);
+            }
+        }
+        // Impl methods are already named `Type::method`
+        HirItem::Impl { methods, .. } => {
+            for method in methods {
+                lint_item(method, prefix, allows, warnings);
+            }
+        }
+        HirItem::Trait { name, methods, .. } => {
+            let prefix = format!("{}{}::", prefix, name);
+            for method in methods {
+                lint_item(method, &prefix, allows, warnings);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The lints `#[allow(...)]` turns off in each function of a parsed file,
+/// including the ones allowed on its enclosing modules, impls and traits.
+/// Keys follow the lowered names: the module path, then `Type::method`,
+/// `Trait::method` or the function's own name.
+#[derive(Debug, Default)]
+pub struct LintAllows(HashMap<String, HashSet<String>>);
+
+impl LintAllows {
+    pub fn collect(program: &[Item]) -> Self {
+        let mut allows = LintAllows::default();
+        allows.collect_items(program, "", &HashSet::new());
+        allows
+    }
+
+    fn collect_items(&mut self, items: &[Item], prefix: &str, inherited: &HashSet<String>) {
+        for item in items {
+            match item {
+                Item::Function { name, attributes, .. } => {
+                    self.0.insert(format!("{}{}", prefix, name), with_allows(inherited, attributes));
+                }
+                Item::Module { name, items, attributes, .. } => {
+                    self.collect_items(items, &format!("{}{}::", prefix, name), &with_allows(inherited, attributes));
+                }
+                Item::Impl { struct_name: name, methods, attributes, .. }
+                | Item::Trait { name, methods, attributes, .. } => {
+                    self.collect_items(methods, &format!("{}{}::", prefix, name), &with_allows(inherited, attributes));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// `inherited` plus the lints `attributes` allow
+fn with_allows(inherited: &HashSet<String>, attributes: &[Attribute]) -> HashSet<String> {
+    let mut allowed = inherited.clone();
+    allowed.extend(attributes.iter().filter(|attr| attr.name == "allow").flat_map(|attr| attr.args.iter().cloned()));
+    allowed
+}
+
+/// Whether `lint` is off in `allowed`, by its own name or through the
+/// `unused` or `warnings` group
+fn is_allowed(allowed: &HashSet<String>, lint: &str) -> bool {
+    allowed.contains(lint) || allowed.contains("warnings") || (lint.starts_with("unused_") && allowed.contains("unused"))
+}
+
+/// Warn about `let` bindings that are never read. A name counts as used if
+/// it's referenced anywhere in the function, so shadowed bindings are judged
+/// together. Names starting with `_` (including compiler temporaries) are
+/// exempt, as in rustc.
+fn check_unused_variables(function: &str, body: &[HirStatement], warnings: &mut Vec<Diagnostic>) {
+    let mut bindings = Vec::new();
+    let mut used = HashSet::new();
+    for stmt in body {
+        visit_stmt(stmt, &mut bindings, &mut used);
+    }
+
+    let mut reported = HashSet::new();
+    for name in bindings {
+        if name.starts_with('_') || used.contains(&name) || !reported.insert(name.clone()) {
+            continue;
+        }
+        warnings.push(
+            Diagnostic::warning(LINT_PHASE, &format!("unused variable: `{}`", name))
+                .with_context(&format!("in function `{}`", function))
+                .with_help(&format!("if this is intentional, prefix it with an underscore: `_{}`", name)),
+        );
+    }
+}
+
+/// Every name the function bodies in `items` refer to, including the
+/// functions they call
+pub(crate) fn referenced_names(items: &[HirItem]) -> HashSet<String> {
+    let mut used = HashSet::new();
+    for item in items {
+        match item {
+            HirItem::Function { body, .. } => visit_stmts(body, &mut Vec::new(), &mut used),
+            HirItem::Module { items, .. } => used.extend(referenced_names(items)),
+            HirItem::Impl { methods, .. } | HirItem::Trait { methods, .. } => used.extend(referenced_names(methods)),
+            _ => {}
+        }
+    }
+    used
+}
+
+fn visit_stmts(stmts: &[HirStatement], bindings: &mut Vec<String>, used: &mut HashSet<String>) {
+    for stmt in stmts {
+        visit_stmt(stmt, bindings, used);
+    }
+}
+
+fn visit_stmt(stmt: &HirStatement, bindings: &mut Vec<String>, used: &mut HashSet<String>) {
+    match stmt {
+        HirStatement::Let { name, init, .. } => {
+            bindings.push(name.clone());
+            visit_expr(init, bindings, used);
+        }
+        HirStatement::Expression(expr) | HirStatement::Return(Some(expr)) => {
+            visit_expr(expr, bindings, used);
+        }
+        HirStatement::For { iter, body, .. } => {
+            visit_expr(iter, bindings, used);
+            visit_stmts(body, bindings, used);
+        }
+        HirStatement::While { condition, body } => {
+            visit_expr(condition, bindings, used);
+            visit_stmts(body, bindings, used);
+        }
+        HirStatement::If { condition, then_body, else_body } => {
+            visit_expr(condition, bindings, used);
+            visit_stmts(then_body, bindings, used);
+            if let Some(else_body) = else_body {
+                visit_stmts(else_body, bindings, used);
+            }
+        }
+        HirStatement::UnsafeBlock(body) => visit_stmts(body, bindings, used),
+        HirStatement::Return(None)
+        | HirStatement::Break
+        | HirStatement::Continue
+        | HirStatement::Item(_) => {}
+    }
+}
+
+fn visit_expr(expr: &HirExpression, bindings: &mut Vec<String>, used: &mut HashSet<String>) {
+    match expr {
+        HirExpression::Variable(name) => {
+            used.insert(name.clone());
+        }
+        HirExpression::BinaryOp { left, right, .. } => {
+            visit_expr(left, bindings, used);
+            visit_expr(right, bindings, used);
+        }
+        HirExpression::UnaryOp { operand, .. } => visit_expr(operand, bindings, used),
+        HirExpression::Assign { target, value } => {
+            // Writing to a variable doesn't read it, but writing through a
+            // field or index does read the base
+            if !matches!(target.as_ref(), HirExpression::Variable(_)) {
+                visit_expr(target, bindings, used);
+            }
+            visit_expr(value, bindings, used);
+        }
+        HirExpression::If { condition, then_body, else_body } => {
+            visit_expr(condition, bindings, used);
+            visit_stmts(then_body, bindings, used);
+            if let Some(else_body) = else_body {
+                visit_stmts(else_body, bindings, used);
+            }
+        }
+        HirExpression::While { condition, body } => {
+            visit_expr(condition, bindings, used);
+            visit_stmts(body, bindings, used);
+        }
+        HirExpression::Loop { body } => visit_stmts(body, bindings, used),
+        HirExpression::Match { scrutinee, arms } => {
+            visit_expr(scrutinee, bindings, used);
+            for arm in arms {
+                if let Some(guard) = &arm.guard {
+                    visit_expr(guard, bindings, used);
+                }
+                visit_stmts(&arm.body, bindings, used);
+            }
+        }
+        HirExpression::Call { func, args } => {
+            visit_expr(func, bindings, used);
+            for arg in args {
+                visit_expr(arg, bindings, used);
+            }
+        }
+        HirExpression::MethodCall { receiver, args, .. } => {
+            visit_expr(receiver, bindings, used);
+            for arg in args {
+                visit_expr(arg, bindings, used);
+            }
+        }
+        HirExpression::FieldAccess { object, .. } | HirExpression::TupleAccess { object, .. } => {
+            visit_expr(object, bindings, used);
+        }
+        HirExpression::Index { array, index } => {
+            visit_expr(array, bindings, used);
+            visit_expr(index, bindings, used);
+        }
+        HirExpression::StructLiteral { fields, .. } | HirExpression::EnumStructVariant { fields, .. } => {
+            for (_, value) in fields {
+                visit_expr(value, bindings, used);
+            }
+        }
+        HirExpression::ArrayLiteral(elements)
+        | HirExpression::Tuple(elements)
+        | HirExpression::EnumVariant { args: elements, .. } => {
+            for element in elements {
+                visit_expr(element, bindings, used);
+            }
+        }
+        HirExpression::Range { start, end, .. } => {
+            for bound in [start, end].into_iter().flatten() {
+                visit_expr(bound, bindings, used);
+            }
+        }
+        HirExpression::Block(stmts, tail) => {
+            visit_stmts(stmts, bindings, used);
+            if let Some(tail) = tail {
+                visit_expr(tail, bindings, used);
+            }
+        }
+        HirExpression::Closure { body, captures, .. } => {
+            used.extend(captures.iter().map(|(name, _)| name.clone()));
+            visit_stmts(body, bindings, used);
+        }
+        HirExpression::Try { value } => visit_expr(value, bindings, used),
+        HirExpression::Integer(_)
+        | HirExpression::Float(_)
+        | HirExpression::String(_)
+        | HirExpression::Bool(_) => {}
+    }
+}
+
+/// Warn about a function whose every path calls itself before it can
+/// return, which can only end in a stack overflow
+fn check_unconditional_recursion(function: &str, body: &[HirStatement], warnings: &mut Vec<Diagnostic>) {
+    if stmts_flow(function, body) == Flow::Recurses {
+        warnings.push(
+            Diagnostic::warning(LINT_PHASE, "function cannot return without recursing")
+                .with_context(&format!("in function `{}`", function))
+                .with_help("a `loop` may express intention better if this is on purpose"),
+        );
+    }
+}
+
+/// What running some code is sure to do, as far as recursion goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flow {
+    /// Every path calls the function
+    Recurses,
+    /// Some path can leave (`return`, `break`, `?`) without calling it
+    Exits,
+    /// Control reaches the code that follows without calling it
+    FallsThrough,
+}
+
+impl Flow {
+    /// `self` followed by `next`, which only runs if `self` falls through
+    fn then(self, next: impl FnOnce() -> Flow) -> Flow {
+        match self {
+            Flow::FallsThrough => next(),
+            flow => flow,
+        }
+    }
+
+    /// One of two branches runs: both must recurse for the whole to, and
+    /// either one leaving is a way out
+    fn either(self, other: Flow) -> Flow {
+        match (self, other) {
+            (Flow::Exits, _) | (_, Flow::Exits) => Flow::Exits,
+            (Flow::Recurses, Flow::Recurses) => Flow::Recurses,
+            _ => Flow::FallsThrough,
+        }
+    }
+}
+
+/// Run `stmts` in order
+fn stmts_flow(function: &str, stmts: &[HirStatement]) -> Flow {
+    stmts.iter().fold(Flow::FallsThrough, |flow, stmt| flow.then(|| stmt_flow(function, stmt)))
+}
+
+/// Run `exprs` in order
+fn exprs_flow<'a>(function: &str, exprs: impl IntoIterator<Item = &'a HirExpression>) -> Flow {
+    exprs.into_iter().fold(Flow::FallsThrough, |flow, expr| flow.then(|| expr_flow(function, expr)))
+}
+
+/// A loop body may run zero times, but a way out of it is still one
+fn loop_body_flow(function: &str, body: &[HirStatement]) -> Flow {
+    stmts_flow(function, body).either(Flow::FallsThrough)
+}
+
+/// An `if` runs one branch; without an `else` the other falls through
+fn if_flow(function: &str, condition: &HirExpression, then_body: &[HirStatement], else_body: Option<&[HirStatement]>) -> Flow {
+    expr_flow(function, condition).then(|| {
+        let else_flow = else_body.map_or(Flow::FallsThrough, |else_body| stmts_flow(function, else_body));
+        stmts_flow(function, then_body).either(else_flow)
+    })
+}
+
+fn stmt_flow(function: &str, stmt: &HirStatement) -> Flow {
+    match stmt {
+        HirStatement::Let { init, .. } | HirStatement::Expression(init) => expr_flow(function, init),
+        HirStatement::Return(value) => exprs_flow(function, value).then(|| Flow::Exits),
+        HirStatement::While { condition, body } => {
+            expr_flow(function, condition).then(|| loop_body_flow(function, body))
+        }
+        HirStatement::For { iter, body, .. } => expr_flow(function, iter).then(|| loop_body_flow(function, body)),
+        HirStatement::If { condition, then_body, else_body } => {
+            if_flow(function, condition, then_body, else_body.as_deref())
+        }
+        HirStatement::UnsafeBlock(body) => stmts_flow(function, body),
+        // Leaving early
+        HirStatement::Break | HirStatement::Continue => Flow::Exits,
+        // A nested item runs separately
+        HirStatement::Item(_) => Flow::FallsThrough,
+    }
+}
+
+fn expr_flow(function: &str, expr: &HirExpression) -> Flow {
+    let flow = |expr: &HirExpression| expr_flow(function, expr);
+    match expr {
+        HirExpression::Call { func, args } => {
+            let recursive = matches!(func.as_ref(), HirExpression::Variable(callee) if callee == function);
+            flow(func).then(|| exprs_flow(function, args)).then(|| {
+                if recursive { Flow::Recurses } else { Flow::FallsThrough }
+            })
+        }
+        HirExpression::MethodCall { receiver, args, .. } => flow(receiver).then(|| exprs_flow(function, args)),
+        // `&&` and `||` may skip their right-hand side
+        HirExpression::BinaryOp { op: BinaryOp::And | BinaryOp::Or, left, right } => {
+            flow(left).then(|| flow(right).either(Flow::FallsThrough))
+        }
+        HirExpression::BinaryOp { left, right, .. } => flow(left).then(|| flow(right)),
+        HirExpression::UnaryOp { operand, .. } => flow(operand),
+        HirExpression::Assign { target, value } => flow(target).then(|| flow(value)),
+        HirExpression::FieldAccess { object, .. } | HirExpression::TupleAccess { object, .. } => flow(object),
+        HirExpression::Index { array, index } => flow(array).then(|| flow(index)),
+        // `?` returns early on an error
+        HirExpression::Try { value } => flow(value).then(|| Flow::Exits),
+        HirExpression::If { condition, then_body, else_body } => {
+            if_flow(function, condition, then_body, else_body.as_deref())
+        }
+        HirExpression::Match { scrutinee, arms } => flow(scrutinee).then(|| {
+            arms.iter()
+                .map(|arm| stmts_flow(function, &arm.body))
+                .reduce(Flow::either)
+                .unwrap_or(Flow::FallsThrough)
+        }),
+        HirExpression::While { condition, body } => flow(condition).then(|| loop_body_flow(function, body)),
+        // A `loop` is left only through `break` or `return`
+        HirExpression::Loop { body } => stmts_flow(function, body).then(|| Flow::Exits),
+        HirExpression::Block(stmts, tail) => stmts_flow(function, stmts).then(|| exprs_flow(function, tail.as_deref())),
+        HirExpression::ArrayLiteral(elements)
+        | HirExpression::Tuple(elements)
+        | HirExpression::EnumVariant { args: elements, .. } => exprs_flow(function, elements),
+        HirExpression::StructLiteral { fields, .. } | HirExpression::EnumStructVariant { fields, .. } => {
+            exprs_flow(function, fields.iter().map(|(_, value)| value))
+        }
+        // A closure body runs only when the closure is called
+        _ => Flow::FallsThrough,
+    }
+}
+
+/// Run the AST lints over a parsed file. These see syntax that lowering
+/// erases: a block's tail expression and a discarded `expr;` statement both
+/// lower to the same HIR statement.
+pub fn run_ast_lints(program: &[Item], allows: &LintAllows) -> Vec<Diagnostic> {
+    let mut must_use = MustUse::default();
+    let mut module = vec!["crate".to_string()];
+    collect_must_use(program, &mut module, &mut must_use);
+
+    let mut warnings = Vec::new();
+    if !must_use.functions.is_empty() {
+        check_unused_results(program, &mut module, "", &must_use, allows, &mut warnings);
+    }
+    warnings
+}
+
+/// The functions marked `#[must_use]`, by path from the crate root
+/// (`crate::checks::verify`), and the paths each module imports with `use`
+#[derive(Default)]
+struct MustUse {
+    functions: HashSet<String>,
+    imports: HashMap<Vec<String>, Vec<Vec<String>>>,
+}
+
+impl MustUse {
+    /// Whether a call to `path` from inside `module` names a `#[must_use]`
+    /// function: the first segment may be an import of the module, and the
+    /// path may start from `crate`, `self` or `super`, or else from `module`
+    fn marks(&self, module: &[String], path: &str) -> bool {
+        let segments: Vec<&str> = path.split("::").collect();
+        let imported = self.imports.get(module).into_iter().flatten().find(|import| {
+            import.last().map(String::as_str) == Some(segments[0])
+        });
+        let resolved = match imported {
+            Some(import) => {
+                let import: Vec<&str> = import.iter().map(String::as_str).collect();
+                resolve_path(&resolve_path(module, &import), &segments[1..])
+            }
+            None => resolve_path(module, &segments),
+        };
+        self.functions.contains(&resolved.join("::"))
+    }
+}
+
+/// `path` followed from `module`, with its leading `crate`, `self` and
+/// `super` segments applied
+fn resolve_path(module: &[String], path: &[&str]) -> Vec<String> {
+    let mut base = module.to_vec();
+    let mut rest = path;
+    while let Some((&first, tail)) = rest.split_first() {
+        match first {
+            "crate" => base.truncate(1),
+            "self" => {}
+            "super" if base.len() > 1 => {
+                base.pop();
+            }
+            "super" => {}
+            _ => break,
+        }
+        rest = tail;
+    }
+    base.extend(rest.iter().map(|segment| segment.to_string()));
+    base
+}
+
+/// Record the `#[must_use]` functions and the `use` imports under `module`
+fn collect_must_use(items: &[Item], module: &mut Vec<String>, must_use: &mut MustUse) {
+    for item in items {
+        match item {
+            Item::Function { name, attributes, .. } if attributes.iter().any(|attr| attr.name == "must_use") => {
+                must_use.functions.insert(format!("{}::{}", module.join("::"), name));
+            }
+            Item::Use { path, is_glob: false, .. } => {
+                must_use.imports.entry(module.clone()).or_default().push(path.clone());
+            }
+            Item::Module { name, items, .. } => {
+                module.push(name.clone());
+                collect_must_use(items, module, must_use);
+                module.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Warn about calls to `#[must_use]` functions in statement position, where
+/// the result is thrown away. `let _ = f();` is the way to opt out.
+fn check_unused_results(
+    items: &[Item],
+    module: &mut Vec<String>,
+    prefix: &str,
+    must_use: &MustUse,
+    allows: &LintAllows,
+    warnings: &mut Vec<Diagnostic>,
+) {
+    for item in items {
+        match item {
+            Item::Function { name, body, location, .. } => {
+                let allowed = allows.0.get(&format!("{}{}", prefix, name));
+                if allowed.is_some_and(|allowed| is_allowed(allowed, "unused_must_use")) {
+                    continue;
+                }
+                let mut discarded = Vec::new();
+                discarded_calls_in_block(body, &mut discarded);
+                for callee in discarded.into_iter().filter(|callee| must_use.marks(module, callee)) {
+                    let warning =
+                        Diagnostic::warning(LINT_PHASE, &format!("unused return value of `{}` that must be used", callee))
+                            .with_context(&format!("in function `{}`", name))
+                            .with_help(&format!("use `let _ = {}(...);` to ignore the resulting value", callee));
+                    warnings.push(Diagnostic { location: *location, ..warning });
+                }
+            }
+            Item::Module { name, items, .. } => {
+                module.push(name.clone());
+                check_unused_results(items, module, &format!("{}{}::", prefix, name), must_use, allows, warnings);
+                module.pop();
+            }
+            Item::Impl { struct_name, methods, .. } => {
+                let prefix = format!("{}{}::", prefix, struct_name);
+                check_unused_results(methods, module, &prefix, must_use, allows, warnings);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The calls in statement position in `block`, whose results are discarded
+fn discarded_calls_in_block(block: &Block, discarded: &mut Vec<String>) {
+    for stmt in &block.statements {
+        discarded_calls_in_stmt(stmt, discarded);
+    }
+    if let Some(tail) = &block.expression {
+        discarded_calls_in_expr(tail, discarded);
+    }
+}
+
+fn discarded_calls_in_stmt(stmt: &Statement, discarded: &mut Vec<String>) {
+    match stmt {
+        Statement::Expression(Expression::FunctionCall { name, .. }) => discarded.push(name.clone()),
+        Statement::Expression(expr) => discarded_calls_in_expr(expr, discarded),
+        Statement::Let { initializer, .. } => discarded_calls_in_expr(initializer, discarded),
+        Statement::Return(Some(expr)) | Statement::Break(Some(expr)) => {
+            discarded_calls_in_expr(expr, discarded)
+        }
+        Statement::For { iter, body, .. } => {
+            discarded_calls_in_expr(iter, discarded);
+            discarded_calls_in_block(body, discarded);
+        }
+        Statement::While { condition, body } => {
+            discarded_calls_in_expr(condition, discarded);
+            discarded_calls_in_block(body, discarded);
+        }
+        Statement::If { condition, then_body, else_body } => {
+            discarded_calls_in_expr(condition, discarded);
+            discarded_calls_in_block(then_body, discarded);
+            if let Some(else_body) = else_body {
+                discarded_calls_in_stmt(else_body, discarded);
+            }
+        }
+        Statement::UnsafeBlock(body) => discarded_calls_in_block(body, discarded),
+        _ => {}
+    }
+}
+
+/// Find the blocks nested inside an expression; calls in value position are
+/// fine, so only statements inside those blocks can discard a result
+fn discarded_calls_in_expr(expr: &Expression, discarded: &mut Vec<String>) {
+    match expr {
+        Expression::If { then_body, else_body, .. } => {
+            discarded_calls_in_block(then_body, discarded);
+            if let Some(else_body) = else_body {
+                discarded_calls_in_expr(else_body, discarded);
+            }
+        }
+        Expression::Match { arms, .. } => {
+            for arm in arms {
+                discarded_calls_in_expr(&arm.body, discarded);
+            }
+        }
+        Expression::Loop(body)
+        | Expression::Block(body)
+        | Expression::UnsafeBlock(body)
+        | Expression::AsyncBlock(body)
+        | Expression::While { body, .. }
+        | Expression::For { body, .. } => discarded_calls_in_block(body, discarded),
+        Expression::Closure { body, .. } => discarded_calls_in_expr(body, discarded),
+        _ => {}
+    }
+}
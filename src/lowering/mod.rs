@@ -18,6 +18,7 @@ use std::fmt;
 use std::cell::RefCell;
 use std::io::Write;
 use std::collections::{HashMap, HashSet};
+use crate::utilities::error_reporting::SourceLocation;
 
 // Module for for-loop desugaring to iterator protocol
 pub mod for_loop_desugar;
@@ -39,8 +40,56 @@ thread_local! {
     static UNSAFE_DEPTH: RefCell<usize> = RefCell::new(0);
     // Registry of unsafe functions
     static UNSAFE_FUNCTIONS: RefCell<std::collections::HashSet<String>> = RefCell::new(std::collections::HashSet::new());
+    // Functions declared in `extern` blocks, by C name, with their declared return type
+    static EXTERN_FUNCTIONS: RefCell<HashMap<String, HirType>> = RefCell::new(HashMap::new());
     // PHASE 5.2: Macro expansion - global macro expander with builtin macros registered
     static MACRO_EXPANDER: RefCell<MacroExpander> = RefCell::new(MacroExpander::with_builtins());
+    // Payload field types of tuple variants, for enums where any variant carries data
+    static ENUM_PAYLOADS: RefCell<HashMap<String, HashMap<String, Vec<HirType>>>> = RefCell::new(HashMap::new());
+    // Whether overflowing constant expressions are reported instead of left for runtime
+    static OVERFLOW_CHECKS: RefCell<bool> = const { RefCell::new(false) };
+    // Traits listed in each struct's #[derive(...)] attributes
+    static STRUCT_DERIVES: RefCell<HashMap<String, Vec<String>>> = RefCell::new(HashMap::new());
+    // Structs marked `#[repr(transparent)]`, laid out and passed as their one field
+    static TRANSPARENT_STRUCTS: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+    // Evaluated `const` items, inlined as literals wherever they're used
+    static CONST_VALUES: RefCell<HashMap<String, HirExpression>> = RefCell::new(HashMap::new());
+    // Methods from inherent impls on primitives (`impl i64 { ... }`), keyed by type name
+    static PRIMITIVE_IMPLS: RefCell<HashMap<String, HashSet<String>>> = RefCell::new(HashMap::new());
+    // Methods from inherent impls on enums (`impl Direction { ... }`), keyed by enum name
+    static ENUM_IMPLS: RefCell<HashMap<String, HashSet<String>>> = RefCell::new(HashMap::new());
+    // Numbers the temporaries lowering introduces so nested desugarings don't collide
+    static TEMP_COUNTER: RefCell<usize> = const { RefCell::new(0) };
+    // Methods that several traits provide for a type without an inherent method of
+    // that name, keyed by (type, method), with the providing traits that are in scope
+    static TRAIT_METHOD_PROVIDERS: RefCell<HashMap<(String, String), Vec<String>>> = RefCell::new(HashMap::new());
+    // Generic functions bounded by a trait declared in the crate. The bound's methods
+    // and associated consts (`T::MAX`) only resolve once the type parameter is known,
+    // so they are lowered once per concrete type they're called with.
+    static GENERIC_TEMPLATES: RefCell<HashMap<String, Item>> = RefCell::new(HashMap::new());
+    // Instantiations of those templates still to lower: (instance name, template, type args)
+    static PENDING_INSTANCES: RefCell<Vec<(String, String, Vec<String>)>> = const { RefCell::new(Vec::new()) };
+    static LOWERED_INSTANCES: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+    // Type parameters bound to concrete types while lowering an instantiation
+    static TYPE_SUBSTITUTIONS: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+    // `impl From<Source> for Target` conversions by target: each source type and
+    // the function its `from` is lowered to
+    static FROM_IMPLS: RefCell<HashMap<String, Vec<(HirType, String)>>> = RefCell::new(HashMap::new());
+    // Parameter types of every function, so an `.into()` argument converts to
+    // the type the callee takes
+    static FUNCTION_PARAMS: RefCell<HashMap<String, Vec<HirType>>> = RefCell::new(HashMap::new());
+    // Declared return type of the function being lowered, for `.into()` in
+    // return position
+    static RETURN_TYPE: RefCell<Option<HirType>> = const { RefCell::new(None) };
+}
+
+/// A fresh name for a lowering-introduced temporary
+fn fresh_temp(prefix: &str) -> String {
+    TEMP_COUNTER.with(|counter| {
+        let mut counter = counter.borrow_mut();
+        *counter += 1;
+        format!("{}_{}", prefix, counter)
+    })
 }
 
 /// Tracks available variables in the current scope
@@ -104,6 +153,11 @@ pub fn get_enum_variant(enum_name: &str, variant_name: &str) -> Option<i64> {
     })
 }
 
+/// Whether `name` is a registered enum type
+pub fn is_enum(name: &str) -> bool {
+    ENUM_REGISTRY.with(|registry| registry.borrow().contains_key(name))
+}
+
 fn register_enum_variants(enum_name: String, variants: Vec<String>) {
     ENUM_REGISTRY.with(|registry| {
         let mut reg = registry.borrow_mut();
@@ -119,6 +173,41 @@ fn clear_enum_registry() {
     ENUM_REGISTRY.with(|registry| {
         registry.borrow_mut().clear();
     });
+    ENUM_PAYLOADS.with(|registry| {
+        registry.borrow_mut().clear();
+    });
+}
+
+fn register_enum_payloads(enum_name: String, payloads: HashMap<String, Vec<HirType>>) {
+    ENUM_PAYLOADS.with(|registry| {
+        registry.borrow_mut().insert(enum_name, payloads);
+    });
+}
+
+/// Whether any variant of this enum carries data. Such enums are laid out as
+/// `[tag:i64][payload:i64...]` and passed by pointer; fieldless enums are plain
+/// discriminant integers.
+pub fn enum_has_payload(enum_name: &str) -> bool {
+    ENUM_PAYLOADS.with(|registry| registry.borrow().contains_key(enum_name))
+}
+
+/// Size of a payload enum's data area in 8-byte slots: the largest variant's
+/// field count, so every variant fits in the same `[tag][payload...]` layout
+pub fn enum_payload_slots(enum_name: &str) -> usize {
+    ENUM_PAYLOADS.with(|registry| {
+        registry
+            .borrow()
+            .get(enum_name)
+            .and_then(|variants| variants.values().map(Vec::len).max())
+            .unwrap_or(0)
+    })
+}
+
+/// Payload field types of a tuple variant
+pub fn get_enum_variant_payload(enum_name: &str, variant_name: &str) -> Option<Vec<HirType>> {
+    ENUM_PAYLOADS.with(|registry| {
+        registry.borrow().get(enum_name).and_then(|variants| variants.get(variant_name).cloned())
+    })
 }
 
 fn register_struct_fields(struct_name: String, fields: Vec<(String, HirType)>) {
@@ -127,6 +216,35 @@ fn register_struct_fields(struct_name: String, fields: Vec<(String, HirType)>) {
     });
 }
 
+fn register_struct_derives(struct_name: String, derives: Vec<String>) {
+    STRUCT_DERIVES.with(|registry| {
+        registry.borrow_mut().insert(struct_name, derives);
+    });
+}
+
+/// Whether the struct has `trait_name` in a `#[derive(...)]` attribute
+pub fn struct_derives(struct_name: &str, trait_name: &str) -> bool {
+    STRUCT_DERIVES.with(|registry| {
+        registry
+            .borrow()
+            .get(struct_name)
+            .is_some_and(|derives| derives.iter().any(|d| d == trait_name))
+    })
+}
+
+/// Whether the struct is `#[repr(transparent)]`
+pub fn is_transparent_struct(struct_name: &str) -> bool {
+    TRANSPARENT_STRUCTS.with(|registry| registry.borrow().contains(struct_name))
+}
+
+/// The type of a transparent struct's one field
+pub fn transparent_field_type(struct_name: &str) -> Option<HirType> {
+    if !is_transparent_struct(struct_name) {
+        return None;
+    }
+    STRUCT_REGISTRY.with(|registry| registry.borrow().get(struct_name)?.first().map(|(_, ty)| ty.clone()))
+}
+
 fn get_struct_field_type(struct_name: &str, field_name: &str) -> Option<HirType> {
     STRUCT_REGISTRY.with(|registry| {
         registry.borrow().get(struct_name).and_then(|fields| {
@@ -190,10 +308,28 @@ pub fn get_struct_field_name(struct_name: &str, index: usize) -> Option<String>
     })
 }
 
+/// Number of positional fields if `struct_name` is a tuple struct, whose
+/// fields are registered as `0`, `1`, ...
+fn tuple_struct_arity(struct_name: &str) -> Option<usize> {
+    STRUCT_REGISTRY.with(|registry| {
+        let registry = registry.borrow();
+        let fields = registry.get(struct_name)?;
+        let positional = !fields.is_empty()
+            && fields.iter().enumerate().all(|(index, (name, _))| *name == index.to_string());
+        positional.then_some(fields.len())
+    })
+}
+
 fn clear_struct_registry() {
     STRUCT_REGISTRY.with(|registry| {
         registry.borrow_mut().clear();
     });
+    STRUCT_DERIVES.with(|registry| {
+        registry.borrow_mut().clear();
+    });
+    TRANSPARENT_STRUCTS.with(|registry| {
+        registry.borrow_mut().clear();
+    });
 }
 
 fn register_function_return_type(func_name: String, return_type: HirType) {
@@ -304,6 +440,12 @@ fn clear_unsafe_functions() {
     });
 }
 
+/// The declared return type of a function from an `extern` block. C returns
+/// narrow integers in the low bits of RAX only, so callers widen them.
+pub fn extern_return_type(name: &str) -> Option<HirType> {
+    EXTERN_FUNCTIONS.with(|funcs| funcs.borrow().get(name).cloned())
+}
+
 // PHASE 5.2: Macro expansion functions
 pub fn register_macro(name: String, rules: Vec<crate::macros::MacroRule>) {
     use crate::macros::MacroDefinition;
@@ -349,6 +491,17 @@ fn pop_module() {
     });
 }
 
+/// Enable or disable compile-time overflow errors for constant expressions
+pub fn set_overflow_checks(enabled: bool) {
+    OVERFLOW_CHECKS.with(|c| {
+        *c.borrow_mut() = enabled;
+    });
+}
+
+fn overflow_checks_enabled() -> bool {
+    OVERFLOW_CHECKS.with(|c| *c.borrow())
+}
+
 pub fn set_current_file(file_path: &str) {
     // Extract module name from file path (e.g., "utils.rs" -> "utils")
     let file_name = std::path::Path::new(file_path)
@@ -436,11 +589,405 @@ pub fn find_operator_impl(type_name: &str, op: &BinaryOp) -> Option<String> {
     })
 }
 
+/// Record every `impl From<Source> for Target`. A target with one `From` impl
+/// keeps the plain `Target::from` name; with several, each `from` is lowered
+/// as `Target::from.<source>`, which no user function can be called.
+fn register_from_impls(items: &[Item]) {
+    let mut impls: HashMap<String, Vec<HirType>> = HashMap::new();
+    for item in items {
+        if let Item::Impl { trait_name: Some(trait_name), trait_args, struct_name, .. } = item {
+            if short_trait_name(trait_name) == "From" && trait_args.len() == 1 {
+                impls.entry(struct_name.clone()).or_default().push(convert_type(&trait_args[0]));
+            }
+        }
+    }
+    FROM_IMPLS.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        registry.clear();
+        for (target, sources) in impls {
+            let single = sources.len() == 1;
+            let entries = sources.into_iter().map(|source| {
+                let name = if single {
+                    format!("{}::from", target)
+                } else {
+                    format!("{}::from.{}", target, mangle_type_name(&source))
+                };
+                (source, name)
+            }).collect();
+            registry.insert(target, entries);
+        }
+    });
+}
+
+/// A type as it can appear in a symbol: `&str` -> `ref_str`
+fn mangle_type_name(ty: &HirType) -> String {
+    match ty {
+        HirType::Reference(inner) | HirType::MutableReference(inner) => format!("ref_{}", mangle_type_name(inner)),
+        ty => ty.to_string().chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect(),
+    }
+}
+
+/// The function `Target::from` lowers to for an argument of type `source`:
+/// the impl for that exact type, else the only impl whose source is an
+/// integer when `source` is, else the only impl there is
+fn from_impl_for(target: &str, source: &HirType) -> Option<String> {
+    fn peel(ty: &HirType) -> &HirType {
+        match ty {
+            HirType::Reference(inner) | HirType::MutableReference(inner) => peel(inner),
+            ty => ty,
+        }
+    }
+    let impls = FROM_IMPLS.with(|registry| registry.borrow().get(target).cloned())?;
+    if let Some((_, name)) = impls.iter().find(|(from, _)| peel(from) == peel(source)) {
+        return Some(name.clone());
+    }
+    if is_integer_type(source) {
+        let integers: Vec<&String> = impls.iter().filter(|(from, _)| is_integer_type(from)).map(|(_, name)| name).collect();
+        if let [name] = integers.as_slice() {
+            return Some((*name).clone());
+        }
+    }
+    match impls.as_slice() {
+        [(_, name)] => Some(name.clone()),
+        _ => None,
+    }
+}
+
+fn is_integer_type(ty: &HirType) -> bool {
+    matches!(ty, HirType::Int32 | HirType::Int64 | HirType::UInt32 | HirType::UInt64 | HirType::USize | HirType::ISize)
+}
+
+/// `value.into()` converts to whatever type the context expects: a struct
+/// through the `From` impl for the value's type, a `String` as a copy of a
+/// string, and an integer from another integer unchanged
+fn desugar_into(target: &HirType, expr: HirExpression) -> HirExpression {
+    let HirExpression::MethodCall { receiver, method, args } = expr else { return expr };
+    if method != "into" || !args.is_empty() {
+        return HirExpression::MethodCall { receiver, method, args };
+    }
+    let source = infer_hir_type(&receiver);
+    match target {
+        // A new String with the same text, as `format!("{}", value)` makes
+        target if is_string_type(target) && is_string_type(&source) => {
+            formatted_string(vec![HirExpression::String("{}".to_string()), *receiver], false)
+        }
+        HirType::Named(target) => match from_impl_for(target, &source) {
+            Some(from) => HirExpression::Call {
+                func: Box::new(HirExpression::Variable(from)),
+                args: vec![*receiver],
+            },
+            None => HirExpression::MethodCall { receiver, method, args },
+        },
+        target if is_integer_type(target) && is_integer_type(&source) => *receiver,
+        _ => HirExpression::MethodCall { receiver, method, args },
+    }
+}
+
+/// `bencher.iter(|| body)` on the bench harness's `Bencher`: run `body`
+/// `bencher.iterations` times and record the nanoseconds that took with
+/// `gaia_bench_record`. Closures can't be passed to functions yet, so the loop
+/// is expanded where `iter` is called.
+fn bencher_iter(bencher: &Expression, body: &Expression) -> Block {
+    let start = fresh_temp("__bench_start");
+    let count = fresh_temp("__bench_i");
+    let field = |name: &str| Expression::FieldAccess { object: Box::new(bencher.clone()), field: name.to_string(), location: None };
+    let now = || Expression::FunctionCall { name: "gaia_bench_now".to_string(), args: Vec::new() };
+    let binary = |left: Expression, op: parser::BinaryOp, right: Expression| Expression::Binary {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+    };
+    let local = |name: &str, mutable: bool, initializer: Expression| Statement::Let {
+        name: name.to_string(),
+        mutable,
+        ty: None,
+        initializer,
+        attributes: Vec::new(),
+        pattern: None,
+    };
+    let step = Expression::Assign {
+        target: Box::new(Expression::Variable(count.clone())),
+        value: Box::new(binary(Expression::Variable(count.clone()), parser::BinaryOp::Add, Expression::Integer(1))),
+    };
+    Block {
+        statements: vec![
+            local(&start, false, now()),
+            local(&count, true, Expression::Integer(0)),
+            Statement::While {
+                condition: Box::new(binary(Expression::Variable(count.clone()), parser::BinaryOp::Less, field("iterations"))),
+                body: Block {
+                    statements: vec![Statement::Expression(body.clone()), Statement::Expression(step)],
+                    expression: None,
+                },
+            },
+            Statement::Expression(Expression::FunctionCall {
+                name: "gaia_bench_record".to_string(),
+                args: vec![binary(now(), parser::BinaryOp::Subtract, Expression::Variable(start))],
+            }),
+        ],
+        expression: None,
+    }
+}
+
+/// `for var in iter` over a user type implementing `Iterator` (or
+/// `IntoIterator`) becomes
+/// `let mut it = iter; loop { match it.next() { Some(var) => body, _ => break } }`,
+/// with `Type::into_iter(iter)` in place of `iter` for an `IntoIterator` type.
+/// `it.next()` borrows `it`, so each call sees the previous call's changes.
+fn desugar_user_iterator_for(type_name: &str, var: &str, iter: &Expression, body: &Block) -> Option<Vec<Statement>> {
+    let (is_iterator, is_into_iterator) = IMPL_REGISTRY.with(|registry| {
+        let reg = registry.borrow();
+        let traits = reg.get(type_name);
+        (
+            traits.is_some_and(|traits| traits.contains_key("Iterator")),
+            traits.is_some_and(|traits| traits.contains_key("IntoIterator")),
+        )
+    });
+    let method_call = |receiver: Expression, method: &str| Expression::MethodCall {
+        receiver: Box::new(receiver),
+        method: method.to_string(),
+        type_args: Vec::new(),
+        args: Vec::new(),
+    };
+    let (source, iter_type) = if is_iterator {
+        (iter.clone(), type_name.to_string())
+    } else if is_into_iterator {
+        let into_iter = format!("{}::into_iter", type_name);
+        let iter_type = match get_function_return_type(&into_iter) {
+            Some(HirType::Named(name)) => name,
+            _ => return None,
+        };
+        (Expression::FunctionCall { name: into_iter, args: vec![iter.clone()] }, iter_type)
+    } else {
+        return None;
+    };
+
+    let iter_name = fresh_temp("__user_iter");
+    let next_arms = vec![
+        parser::MatchArm {
+            pattern: Pattern::EnumVariant {
+                path: vec!["Some".to_string()],
+                data: Some(Box::new(Pattern::Identifier(var.to_string()))),
+            },
+            guard: None,
+            body: Expression::Block(body.clone()),
+        },
+        parser::MatchArm {
+            pattern: Pattern::Wildcard,
+            guard: None,
+            body: Expression::Block(Block { statements: vec![Statement::Break(None)], expression: None }),
+        },
+    ];
+    let step = Expression::Match {
+        scrutinee: Box::new(method_call(Expression::Variable(iter_name.clone()), "next")),
+        arms: next_arms,
+    };
+    Some(vec![
+        Statement::Let {
+            name: iter_name,
+            mutable: true,
+            ty: Some(Type::Named(iter_type)),
+            initializer: source,
+            attributes: Vec::new(),
+            pattern: None,
+        },
+        Statement::Expression(Expression::Loop(Block { statements: vec![Statement::Expression(step)], expression: None })),
+    ])
+}
+
 /// Clear the impl registry (for testing/cleanup)
 fn clear_impl_registry() {
     IMPL_REGISTRY.with(|registry| {
         registry.borrow_mut().clear();
     });
+    PRIMITIVE_IMPLS.with(|impls| impls.borrow_mut().clear());
+    ENUM_IMPLS.with(|impls| impls.borrow_mut().clear());
+    TRAIT_METHOD_PROVIDERS.with(|providers| providers.borrow_mut().clear());
+}
+
+/// Last path segment of a trait name: `shapes::Area` -> `Area`
+fn short_trait_name(trait_name: &str) -> &str {
+    trait_name.rsplit("::").next().unwrap_or(trait_name)
+}
+
+/// Decide which trait impl methods can't take the plain `Type::method` name:
+/// those shadowed by an inherent method of the same name, and those that more
+/// than one trait provides for the same type. These are lowered as
+/// `Type::Trait::method` instead. Methods several traits provide are recorded
+/// so a call can pick the one in-scope trait or report the ambiguity.
+fn plan_trait_method_names(items: &[Item]) -> HashSet<(String, String, String)> {
+    let method_names = |methods: &[Item]| -> Vec<String> {
+        methods.iter().filter_map(|method| match method {
+            Item::Function { name, .. } => Some(name.clone()),
+            _ => None,
+        }).collect()
+    };
+
+    // Traits defined at the top level of the file or imported with `use`
+    let mut in_scope: HashSet<String> = HashSet::new();
+    for item in items {
+        match item {
+            Item::Trait { name, .. } => {
+                in_scope.insert(name.clone());
+            }
+            Item::Use { path, is_glob: false, .. } => {
+                in_scope.extend(path.last().cloned());
+            }
+            Item::Use { path, is_glob: true, .. } => {
+                for module in items {
+                    if let Item::Module { name, items: module_items, .. } = module {
+                        if path.last() == Some(name) {
+                            in_scope.extend(module_items.iter().filter_map(|item| match item {
+                                Item::Trait { name, .. } => Some(name.clone()),
+                                _ => None,
+                            }));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut inherent: HashSet<(String, String)> = HashSet::new();
+    let mut providers: HashMap<(String, String), Vec<String>> = HashMap::new();
+    for item in items {
+        let Item::Impl { struct_name, trait_name, methods, .. } = item else { continue };
+        // Each `From` impl gets its own name (see `register_from_impls`)
+        if trait_name.as_deref().is_some_and(|trait_name| short_trait_name(trait_name) == "From") {
+            continue;
+        }
+        for method in method_names(methods) {
+            let key = (struct_name.clone(), method);
+            match trait_name {
+                Some(trait_name) => providers.entry(key).or_default().push(trait_name.clone()),
+                None => {
+                    inherent.insert(key);
+                }
+            }
+        }
+    }
+
+    let mut renamed = HashSet::new();
+    for ((type_name, method), traits) in providers {
+        let shadowed = inherent.contains(&(type_name.clone(), method.clone()));
+        if !shadowed && traits.len() < 2 {
+            continue;
+        }
+        for trait_name in &traits {
+            renamed.insert((type_name.clone(), trait_name.clone(), method.clone()));
+        }
+        if !shadowed {
+            let visible = traits
+                .iter()
+                .map(|trait_name| short_trait_name(trait_name).to_string())
+                .filter(|trait_name| in_scope.contains(trait_name))
+                .collect();
+            TRAIT_METHOD_PROVIDERS.with(|registry| {
+                registry.borrow_mut().insert((type_name, method), visible);
+            });
+        }
+    }
+    renamed
+}
+
+/// Resolve `receiver.method()` on a struct to the trait that provides it when
+/// several traits do. Returns the `Trait::method` name to call, or an error if
+/// the choice is ambiguous or no providing trait is in scope.
+fn trait_method_for_call(receiver_ty: &HirType, method: &str) -> LowerResult<Option<String>> {
+    let type_name = match receiver_ty {
+        HirType::Named(name) => name,
+        HirType::Reference(inner) | HirType::MutableReference(inner) => match &**inner {
+            HirType::Named(name) => name,
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    let Some(traits) = TRAIT_METHOD_PROVIDERS.with(|registry| {
+        registry.borrow().get(&(type_name.clone(), method.to_string())).cloned()
+    }) else {
+        return Ok(None);
+    };
+    match traits.as_slice() {
+        [trait_name] => Ok(Some(format!("{}::{}", trait_name, method))),
+        [] => Err(LowerError {
+            message: format!(
+                "no method named `{}` found for `{}` in the current scope: the traits that provide it are not imported",
+                method, type_name
+            ),
+        }),
+        _ => Err(LowerError {
+            message: format!(
+                "multiple applicable items in scope: `{}` is provided for `{}` by traits {}",
+                method,
+                type_name,
+                traits.iter().map(|t| format!("`{}`", t)).collect::<Vec<_>>().join(" and ")
+            ),
+        }),
+    }
+}
+
+/// Type names that can carry an inherent impl block's methods
+pub const PRIMITIVE_IMPL_TYPES: &[&str] = &["i32", "i64", "u32", "u64", "usize", "isize", "f64", "bool", "char"];
+
+/// Record the methods of an `impl <primitive> { ... }` block
+fn register_primitive_impl(type_name: &str, method_names: Vec<String>) {
+    PRIMITIVE_IMPLS.with(|impls| {
+        impls.borrow_mut().entry(type_name.to_string()).or_default().extend(method_names);
+    });
+}
+
+/// Record the methods of an `impl <enum> { ... }` block
+fn register_enum_impl(enum_name: &str, method_names: Vec<String>) {
+    ENUM_IMPLS.with(|impls| {
+        impls.borrow_mut().entry(enum_name.to_string()).or_default().extend(method_names);
+    });
+}
+
+/// Resolve `receiver.method()` on an enum value to the qualified name of a
+/// method from an `impl <enum>` block. Enum methods take the tagged value as
+/// `self`, so the call is a plain function call like a primitive impl's.
+fn enum_impl_method(receiver_ty: &HirType, method: &str) -> Option<String> {
+    let HirType::Named(enum_name) = receiver_ty else {
+        return None;
+    };
+    ENUM_IMPLS.with(|impls| {
+        impls
+            .borrow()
+            .get(enum_name)
+            .is_some_and(|methods| methods.contains(method))
+            .then(|| format!("{}::{}", enum_name, method))
+    })
+}
+
+/// Resolve `receiver.method()` on a primitive receiver to the qualified name
+/// of a method from an `impl <primitive>` block. An unsuffixed integer
+/// literal defaults to i32 here, so it also matches the other integer types.
+fn primitive_impl_method(receiver_ty: &HirType, method: &str) -> Option<String> {
+    let type_name = match receiver_ty {
+        HirType::Int32 => "i32",
+        HirType::Int64 => "i64",
+        HirType::UInt32 => "u32",
+        HirType::UInt64 => "u64",
+        HirType::USize => "usize",
+        HirType::ISize => "isize",
+        HirType::Float64 => "f64",
+        HirType::Bool => "bool",
+        HirType::Char => "char",
+        _ => return None,
+    };
+    let candidates: &[&str] = if type_name == "i32" {
+        &["i32", "i64", "isize", "u32", "u64", "usize"]
+    } else {
+        &[type_name]
+    };
+    PRIMITIVE_IMPLS.with(|impls| {
+        let impls = impls.borrow();
+        candidates
+            .iter()
+            .find(|name| impls.get(**name).is_some_and(|methods| methods.contains(method)))
+            .map(|name| format!("{}::{}", name, method))
+    })
 }
 
 fn collect_variables_from_expr(expr: &HirExpression, vars: &mut HashSet<String>) {
@@ -476,6 +1023,11 @@ fn collect_variables_from_expr(expr: &HirExpression, vars: &mut HashSet<String>)
                 collect_variables_from_stmt(stmt, vars);
             }
         }
+        HirExpression::Loop { body } => {
+            for stmt in body {
+                collect_variables_from_stmt(stmt, vars);
+            }
+        }
         HirExpression::Match { scrutinee, arms } => {
             collect_variables_from_expr(scrutinee, vars);
             for arm in arms {
@@ -528,6 +1080,10 @@ fn collect_variables_from_expr(expr: &HirExpression, vars: &mut HashSet<String>)
                 collect_variables_from_expr(expr, vars);
             }
         }
+        HirExpression::Closure { captures, .. } => {
+            // A nested closure reads its captures from the enclosing scope
+            vars.extend(captures.iter().map(|(name, _)| name.clone()));
+        }
         _ => {}
     }
 }
@@ -623,6 +1179,43 @@ fn convert_rust_format_to_printf_with_types(rust_fmt: &str, arg_types: &[HirType
     result
 }
 
+/// `format!(fmt, args...)`: format into a new heap string through
+/// `gaia_format`, with `newline` keeping the trailing newline `writeln!` needs
+fn formatted_string(mut args: Vec<HirExpression>, newline: bool) -> HirExpression {
+    if let Some(HirExpression::String(fmt_str)) = args.first() {
+        let arg_types: Vec<HirType> = args[1..].iter().map(infer_hir_type).collect();
+        let mut printf_fmt = convert_rust_format_to_printf_with_types(fmt_str, &arg_types);
+        if !newline {
+            printf_fmt.pop();
+        }
+        args[0] = HirExpression::String(printf_fmt);
+    }
+    HirExpression::Call {
+        func: Box::new(HirExpression::Variable("gaia_format".to_string())),
+        args,
+    }
+}
+
+/// `write!(buf, fmt, args...)`: append the formatted text to the String
+/// `buf` and evaluate to `Ok(())`, as `fmt::Write::write_fmt` does
+fn append_formatted(mut args: Vec<HirExpression>, newline: bool) -> HirExpression {
+    let buffer = args.remove(0);
+    let appended = HirExpression::Call {
+        func: Box::new(HirExpression::Variable("gaia_string_concat".to_string())),
+        args: vec![buffer.clone(), formatted_string(args, newline)],
+    };
+    HirExpression::Block(
+        vec![HirStatement::Expression(HirExpression::Assign {
+            target: Box::new(buffer),
+            value: Box::new(appended),
+        })],
+        Some(Box::new(HirExpression::Call {
+            func: Box::new(HirExpression::Variable("Ok".to_string())),
+            args: vec![HirExpression::Tuple(Vec::new())],
+        })),
+    )
+}
+
 fn get_printf_format_spec(ty: &HirType) -> &'static str {
     match ty {
         HirType::Float64 => "%f",
@@ -656,10 +1249,13 @@ pub enum HirItem {
          body: Vec<HirStatement>,
          is_public: bool,
          where_clause: Vec<parser::WhereConstraint>,
+         /// Where the function's name is in the source, when known
+         location: Option<SourceLocation>,
      },
     /// Struct definition
     Struct {
         name: String,
+        generics: Vec<GenericParam>,
         fields: Vec<(String, HirType)>,
         #[doc(hidden)]
         derives: Vec<String>, // derive attributes like ["Clone", "Debug"]
@@ -677,6 +1273,13 @@ pub enum HirItem {
         ty: Option<HirType>,
         is_public: bool,
     },
+    /// Associated const in a trait or impl
+    AssociatedConst {
+        name: String,
+        ty: HirType,
+        value: Option<HirExpression>,
+        is_public: bool,
+    },
     /// Use statement / re-export: `use path::to::item;` or `pub use path::to::item;`
     Use {
         path: Vec<String>,
@@ -720,6 +1323,12 @@ pub enum HirItem {
         generics: Vec<String>,
         is_public: bool,
     },
+    /// Extern block: `extern "C" { fn puts(s: *const i8) -> i32; }`.
+    /// The functions are bodiless declarations, called by their C names.
+    ExternBlock {
+        abi: String,
+        functions: Vec<HirItem>,
+    },
 }
 
 /// HIR statements (simplified from parser statements)
@@ -807,6 +1416,11 @@ pub enum HirExpression {
         body: Vec<HirStatement>,
     },
 
+    /// Infinite loop: `loop { body }`, left only through `break` or `return`
+    Loop {
+        body: Vec<HirStatement>,
+    },
+
     /// Match expression (simplified pattern support)
     Match {
         scrutinee: Box<HirExpression>,
@@ -830,6 +1444,8 @@ pub enum HirExpression {
     FieldAccess {
         object: Box<HirExpression>,
         field: String,
+        /// Where the field name is written, when it comes from source
+        location: Option<SourceLocation>,
     },
 
     /// Tuple access: tuple.0, tuple.1, etc.
@@ -1146,8 +1762,12 @@ fn lower_type(ty: &Type) -> LowerResult<HirType> {
             "isize" => Ok(HirType::ISize),
             "f64" => Ok(HirType::Float64),
             "bool" => Ok(HirType::Bool),
-            "str" => Ok(HirType::String),
-            _ => Ok(HirType::Named(name.clone())),
+            "str" | "String" => Ok(HirType::String),
+            _ => match substituted_type_name(name) {
+                // The bound type may be a primitive: `T` in `twice::<i64>`
+                Some(concrete) if concrete != *name => lower_type(&Type::Named(concrete)),
+                _ => Ok(HirType::Named(name.clone())),
+            },
         },
         Type::Reference { lifetime: _, mutable: _, inner } => {
             let inner_hir = lower_type(inner)?;
@@ -1160,18 +1780,14 @@ fn lower_type(ty: &Type) -> LowerResult<HirType> {
         Type::Array { element, size } => {
             let elem_hir = lower_type(element)?;
             // Extract the size from the expression if present
-            let size_value = if let Some(size_expr) = size {
-                if let Expression::Integer(n) = size_expr.as_ref() {
-                    Some(*n as usize)
-                } else if let Expression::Variable(_name) = size_expr.as_ref() {
-                    // For const generics, we'll treat as unknown for now
-                    None
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+            // Lengths that aren't constants (e.g. const generic parameters) stay unknown
+            let size_value = size
+                .as_ref()
+                .and_then(|size_expr| lower_expression(size_expr).ok())
+                .and_then(|hir| match const_value(&hir) {
+                    Some(HirExpression::Integer(n)) if n >= 0 => Some(n as usize),
+                    _ => None,
+                });
             
             Ok(HirType::Array {
                 element_type: Box::new(elem_hir),
@@ -1276,6 +1892,692 @@ fn lower_type(ty: &Type) -> LowerResult<HirType> {
     }
 }
 
+/// Condition selecting a match arm whose pattern is the enum variant `path`.
+/// Option/Result and payload-carrying user enums compare the tag slot;
+/// fieldless user enums compare the discriminant directly.
+fn enum_pattern_condition(scrutinee: &HirExpression, path: &[String]) -> HirExpression {
+    let tag_equals = |tag: i64| HirExpression::BinaryOp {
+        op: BinaryOp::Equal,
+        left: Box::new(HirExpression::Call {
+            func: Box::new(HirExpression::Variable("__enum_tag".to_string())),
+            args: vec![scrutinee.clone()],
+        }),
+        right: Box::new(HirExpression::Integer(tag)),
+    };
+
+    match path {
+        [variant] => match variant.as_str() {
+            "Some" | "Ok" => tag_equals(1),
+            "None" | "Err" => tag_equals(0),
+            _ => HirExpression::Bool(true),
+        },
+        [.., enum_name, variant] => match get_enum_variant(enum_name, variant) {
+            Some(discriminant) if enum_has_payload(enum_name) => tag_equals(discriminant),
+            Some(_) => HirExpression::BinaryOp {
+                op: BinaryOp::Equal,
+                left: Box::new(scrutinee.clone()),
+                right: Box::new(HirExpression::EnumVariant {
+                    enum_name: enum_name.clone(),
+                    variant_name: variant.clone(),
+                    args: Vec::new(),
+                }),
+            },
+            None => HirExpression::Bool(true),
+        },
+        [] => HirExpression::Bool(true),
+    }
+}
+
+/// `Option`/`Result` methods with runtime support (`gaia_option_*` and
+/// `gaia_result_*`), as `Option::unwrap(x)`-style calls. Returns `None` when
+/// the receiver isn't known to be an `Option` or `Result`.
+fn option_method(receiver: &HirExpression, method: &str, args: &[HirExpression]) -> Option<HirExpression> {
+    let enum_name = match infer_hir_type(receiver) {
+        HirType::Option(_) => "Option",
+        HirType::Result { .. } => "Result",
+        HirType::Named(name) if name == "Option" => "Option",
+        HirType::Named(name) if name == "Result" => "Result",
+        _ => return None,
+    };
+    let supported = match enum_name {
+        "Option" => matches!(method, "is_some" | "is_none" | "unwrap" | "unwrap_or"),
+        _ => matches!(method, "is_ok" | "is_err" | "unwrap" | "unwrap_err" | "unwrap_or"),
+    };
+    if !supported {
+        return None;
+    }
+    Some(HirExpression::Call {
+        func: Box::new(HirExpression::Variable(format!("{}::{}", enum_name, method))),
+        args: std::iter::once(receiver.clone()).chain(args.iter().cloned()).collect(),
+    })
+}
+
+/// `x.and_then(f)` and `x.or_else(f)` on an `Option` or `Result`: a tag
+/// check that either calls `f` with the payload or passes `x` through
+/// unchanged. Returns `None` for other receivers and methods.
+fn option_combinator(receiver: &HirExpression, method: &str, args: &[HirExpression]) -> Option<HirExpression> {
+    let receiver_ty = infer_hir_type(receiver);
+    let is_result = match &receiver_ty {
+        HirType::Option(_) => false,
+        HirType::Result { .. } => true,
+        HirType::Named(name) if name == "Option" => false,
+        HirType::Named(name) if name == "Result" => true,
+        _ => return None,
+    };
+    // The variant that runs the closure; the other one short-circuits
+    let calling_variant = match (method, is_result) {
+        ("and_then", false) => "Some",
+        ("and_then", true) => "Ok",
+        ("or_else", false) => "None",
+        ("or_else", true) => "Err",
+        _ => return None,
+    };
+    let [callback] = args else { return None };
+
+    let value_name = fresh_temp("__combinator_value");
+    let callback_name = fresh_temp("__combinator_fn");
+    let value = HirExpression::Variable(value_name.clone());
+    // `None` has no payload to hand to `or_else`
+    let callback_args = if calling_variant == "None" {
+        Vec::new()
+    } else {
+        vec![HirExpression::Call {
+            func: Box::new(HirExpression::Variable("__extract_enum_field".to_string())),
+            args: vec![value.clone(), HirExpression::Integer(0)],
+        }]
+    };
+    let callback_ty = infer_hir_type(callback);
+    add_binding(value_name.clone(), receiver_ty.clone());
+    add_binding(callback_name.clone(), callback_ty.clone());
+
+    Some(HirExpression::Block(
+        vec![
+            HirStatement::Let { name: value_name, mutable: false, ty: receiver_ty, init: receiver.clone() },
+            HirStatement::Let { name: callback_name.clone(), mutable: false, ty: callback_ty, init: callback.clone() },
+        ],
+        Some(Box::new(HirExpression::If {
+            condition: Box::new(enum_pattern_condition(&value, &[calling_variant.to_string()])),
+            then_body: vec![HirStatement::Expression(HirExpression::Call {
+                func: Box::new(HirExpression::Variable(callback_name)),
+                args: callback_args,
+            })],
+            else_body: Some(vec![HirStatement::Expression(value)]),
+        })),
+    ))
+}
+
+/// `v.iter().position(f)` and `v.iter().find(f)` over a `Vec` or array: a
+/// loop that calls `f` on each element in turn and stops at the first one it
+/// accepts, giving `Some(index)` or `Some(element)`, else `None`. As in Rust,
+/// `position` passes `f` a `&T` and `find` a `&&T`, so `|x| *x == 3` and
+/// `|x| **x > 1` work. Returns `None` for other receivers and methods.
+fn iterator_search(receiver: &HirExpression, method: &str, args: &[HirExpression]) -> Option<HirExpression> {
+    if !matches!(method, "position" | "find") {
+        return None;
+    }
+    let HirExpression::MethodCall { receiver: collection, method: iter_method, args: iter_args } = receiver else {
+        return None;
+    };
+    if !matches!(iter_method.as_str(), "iter" | "into_iter") || !iter_args.is_empty() {
+        return None;
+    }
+    let collection_ty = infer_hir_type(collection);
+    let element_ty = match &collection_ty {
+        HirType::Vec(element_ty) | HirType::Array { element_type: element_ty, .. } => (**element_ty).clone(),
+        _ => return None,
+    };
+    let [predicate] = args else { return None };
+    let item_ty = HirType::Reference(Box::new(element_ty.clone()));
+    let arg_ty = if method == "find" { HirType::Reference(Box::new(item_ty.clone())) } else { item_ty.clone() };
+    // An untyped closure parameter takes the reference it's passed
+    let predicate = match predicate {
+        HirExpression::Closure { params, body, return_type, is_move, captures }
+            if matches!(params.as_slice(), [(_, HirType::Unknown)]) =>
+        {
+            HirExpression::Closure {
+                params: vec![(params[0].0.clone(), arg_ty)],
+                body: body.clone(),
+                return_type: return_type.clone(),
+                is_move: *is_move,
+                captures: captures.clone(),
+            }
+        }
+        _ => predicate.clone(),
+    };
+
+    let mut stmts = Vec::new();
+    // A named collection is read in place; anything else is evaluated once
+    let collection = match collection.as_ref() {
+        HirExpression::Variable(_) => (**collection).clone(),
+        _ => {
+            let name = fresh_temp("__search_collection");
+            add_binding(name.clone(), collection_ty.clone());
+            stmts.push(HirStatement::Let { name: name.clone(), mutable: false, ty: collection_ty, init: (**collection).clone() });
+            HirExpression::Variable(name)
+        }
+    };
+    let predicate_name = fresh_temp("__search_fn");
+    let element_name = fresh_temp("__search_element");
+    let item_name = fresh_temp("__search_item");
+    let len_name = fresh_temp("__search_len");
+    let index_name = fresh_temp("__search_index");
+    let result_name = fresh_temp("__search_result");
+    let predicate_ty = infer_hir_type(&predicate);
+    let result_ty = HirType::Option(Box::new(if method == "position" { HirType::Int64 } else { element_ty.clone() }));
+    add_binding(predicate_name.clone(), predicate_ty.clone());
+    add_binding(element_name.clone(), element_ty.clone());
+    add_binding(item_name.clone(), item_ty.clone());
+    add_binding(len_name.clone(), HirType::Int64);
+    add_binding(index_name.clone(), HirType::Int64);
+    add_binding(result_name.clone(), result_ty.clone());
+
+    let len = HirExpression::Variable(len_name.clone());
+    let index = HirExpression::Variable(index_name.clone());
+    let result = HirExpression::Variable(result_name.clone());
+    let element = HirExpression::Index { array: Box::new(collection.clone()), index: Box::new(index.clone()) };
+    let found = if method == "position" { index.clone() } else { element.clone() };
+    let reference = |value: HirExpression| HirExpression::UnaryOp { op: UnaryOp::Reference, operand: Box::new(value) };
+    // The predicate borrows a local copy: a struct stored in a `Vec` isn't laid
+    // out the way a `&T` expects, and `find`'s `&&T` needs a `&T` to point at
+    let mut borrow = vec![HirStatement::Let {
+        name: element_name.clone(),
+        mutable: false,
+        ty: element_ty.clone(),
+        init: copy_fields(&element),
+    }];
+    let mut predicate_arg = reference(HirExpression::Variable(element_name.clone()));
+    if method == "find" {
+        borrow.push(HirStatement::Let { name: item_name.clone(), mutable: false, ty: item_ty, init: predicate_arg });
+        predicate_arg = reference(HirExpression::Variable(item_name));
+    }
+    let assign = |target: &HirExpression, value: HirExpression| {
+        HirStatement::Expression(HirExpression::Assign { target: Box::new(target.clone()), value: Box::new(value) })
+    };
+
+    stmts.extend([
+        HirStatement::Let { name: predicate_name.clone(), mutable: false, ty: predicate_ty, init: predicate },
+        HirStatement::Let {
+            name: len_name,
+            mutable: false,
+            ty: HirType::Int64,
+            init: HirExpression::MethodCall { receiver: Box::new(collection), method: "len".to_string(), args: Vec::new() },
+        },
+        HirStatement::Let { name: index_name, mutable: true, ty: HirType::Int64, init: HirExpression::Integer(0) },
+        HirStatement::Let {
+            name: result_name,
+            mutable: true,
+            ty: result_ty,
+            init: HirExpression::Call { func: Box::new(HirExpression::Variable("None".to_string())), args: Vec::new() },
+        },
+        // A match ends the loop by moving the index past the end
+        HirStatement::While {
+            condition: Box::new(HirExpression::BinaryOp {
+                op: BinaryOp::Less,
+                left: Box::new(index.clone()),
+                right: Box::new(len.clone()),
+            }),
+            body: borrow.into_iter().chain([HirStatement::If {
+                condition: Box::new(HirExpression::Call {
+                    func: Box::new(HirExpression::Variable(predicate_name)),
+                    args: vec![predicate_arg],
+                }),
+                then_body: vec![
+                    assign(&result, HirExpression::Call {
+                        func: Box::new(HirExpression::Variable("Some".to_string())),
+                        args: vec![found],
+                    }),
+                    assign(&index, len),
+                ],
+                else_body: Some(vec![assign(&index, HirExpression::BinaryOp {
+                    op: BinaryOp::Add,
+                    left: Box::new(index.clone()),
+                    right: Box::new(HirExpression::Integer(1)),
+                })]),
+            }]).collect(),
+        },
+    ]);
+    Some(HirExpression::Block(stmts, Some(Box::new(result))))
+}
+
+/// Types of the first `count` payload slots bound by an enum variant pattern
+fn enum_pattern_field_types(scrutinee: &HirExpression, path: &[String], count: usize) -> Vec<HirType> {
+    let field_types = match path {
+        [variant] => {
+            let inner = match (variant.as_str(), infer_hir_type(scrutinee)) {
+                ("Some", HirType::Option(inner)) => *inner,
+                ("Ok", HirType::Result { ok_type, .. }) => *ok_type,
+                ("Err", HirType::Result { err_type, .. }) => *err_type,
+                _ => HirType::Int64,
+            };
+            vec![inner]
+        }
+        [.., enum_name, variant] => get_enum_variant_payload(enum_name, variant).unwrap_or_default(),
+        [] => Vec::new(),
+    };
+
+    (0..count)
+        .map(|index| field_types.get(index).cloned().unwrap_or(HirType::Int64))
+        .collect()
+}
+
+/// Hash of a value whose struct type has `#[derive(Hash)]`, folding each
+/// field into the running hash with the `gaia_hash_combine` runtime call.
+/// Returns `None` when the value's type doesn't derive `Hash`.
+fn derived_hash(value: &HirExpression) -> Option<HirExpression> {
+    // Hashing a reference hashes the value behind it
+    let value = match value {
+        HirExpression::UnaryOp { op: UnaryOp::Reference, operand } => &**operand,
+        _ => value,
+    };
+    let struct_name = match infer_hir_type(value) {
+        HirType::Named(name) if struct_derives(&name, "Hash") => name,
+        _ => return None,
+    };
+    let fields = STRUCT_REGISTRY.with(|registry| registry.borrow().get(&struct_name).cloned())?;
+
+    let mut hash = HirExpression::Integer(0);
+    for (field_name, field_ty) in fields {
+        let field = HirExpression::FieldAccess {
+            object: Box::new(value.clone()),
+            field: field_name,
+            location: None,
+        };
+        // Nested #[derive(Hash)] structs contribute their own derived hash,
+        // and strings hash their contents rather than their address
+        let field_hash = match field_ty {
+            HirType::Named(_) => derived_hash(&field).unwrap_or(field),
+            ty if is_string_type(&ty) => runtime_call("gaia_hash_str", vec![field]),
+            _ => field,
+        };
+        hash = HirExpression::Call {
+            func: Box::new(HirExpression::Variable("gaia_hash_combine".to_string())),
+            args: vec![hash, field_hash],
+        };
+    }
+    Some(hash)
+}
+
+/// `String` or `&str`
+fn is_string_type(ty: &HirType) -> bool {
+    match ty {
+        HirType::String => true,
+        HirType::Reference(inner) => matches!(**inner, HirType::String),
+        _ => false,
+    }
+}
+
+fn runtime_call(name: &str, args: Vec<HirExpression>) -> HirExpression {
+    HirExpression::Call {
+        func: Box::new(HirExpression::Variable(name.to_string())),
+        args,
+    }
+}
+
+/// A `#[derive(Hash)]` value as a map key: its derived hash stored with its
+/// fields, so the runtime can tell apart keys whose hashes collide by
+/// comparing the fields the way the derived `PartialEq` would (strings by
+/// content, nested derived structs field by field)
+fn derived_key(value: &HirExpression) -> Option<HirExpression> {
+    let value = match value {
+        HirExpression::UnaryOp { op: UnaryOp::Reference, operand } => &**operand,
+        _ => value,
+    };
+    let hash = derived_hash(value)?;
+    let mut fields = Vec::new();
+    flatten_key_fields(value, &mut fields);
+    let key = runtime_call("gaia_hash_key", vec![hash, HirExpression::Integer(fields.len() as i64)]);
+    Some(fields.into_iter().fold(key, |key, (is_string, field)| {
+        runtime_call("gaia_hash_key_field", vec![key, HirExpression::Integer(is_string as i64), field])
+    }))
+}
+
+/// The scalar fields of a derived-`Hash` value, nested structs expanded in
+/// place, each flagged with whether it compares as a string
+fn flatten_key_fields(value: &HirExpression, out: &mut Vec<(bool, HirExpression)>) {
+    let fields = match infer_hir_type(value) {
+        HirType::Named(name) => STRUCT_REGISTRY.with(|registry| registry.borrow().get(&name).cloned()),
+        _ => None,
+    };
+    for (field_name, field_ty) in fields.unwrap_or_default() {
+        let field = HirExpression::FieldAccess { object: Box::new(value.clone()), field: field_name, location: None };
+        match field_ty {
+            HirType::Named(ref name) if struct_derives(name, "Hash") => flatten_key_fields(&field, out),
+            ty => out.push((is_string_type(&ty), field)),
+        }
+    }
+}
+
+/// Whether `map.method(..)` hashes its first argument as a key
+fn is_hash_collection(ty: &HirType) -> bool {
+    matches!(ty, HirType::Named(name) if name == "HashMap" || name == "HashSet")
+}
+
+/// Strip a leading `&` and return the struct name when the value's type
+/// derives `trait_name`, along with the value itself
+fn derived_struct_operand<'a>(value: &'a HirExpression, trait_name: &str) -> Option<(&'a HirExpression, String)> {
+    let value = match value {
+        HirExpression::UnaryOp { op: UnaryOp::Reference, operand } => &**operand,
+        _ => value,
+    };
+    match infer_hir_type(value) {
+        HirType::Named(name) if struct_derives(&name, trait_name) => Some((value, name)),
+        _ => None,
+    }
+}
+
+/// `u32`, `u64` or `usize`, which order as unsigned integers
+fn is_unsigned_type(ty: &HirType) -> bool {
+    matches!(ty, HirType::UInt32 | HirType::UInt64 | HirType::USize)
+}
+
+/// A variable or a field path off one, which reads the same value however
+/// often it's evaluated
+fn is_place(value: &HirExpression) -> bool {
+    match value {
+        HirExpression::Variable(_) => true,
+        HirExpression::FieldAccess { object, .. } => is_place(object),
+        _ => false,
+    }
+}
+
+/// `value` as an operand evaluated once: places are used as they are,
+/// anything else is bound to a fresh temporary pushed onto `stmts`
+fn bind_once(stmts: &mut Vec<HirStatement>, prefix: &str, value: &HirExpression) -> HirExpression {
+    if is_place(value) {
+        return value.clone();
+    }
+    let name = fresh_temp(prefix);
+    let ty = infer_hir_type(value);
+    add_binding(name.clone(), ty.clone());
+    stmts.push(HirStatement::Let { name: name.clone(), mutable: false, ty, init: value.clone() });
+    HirExpression::Variable(name)
+}
+
+/// `value`, evaluated after the bindings in `stmts`
+fn with_bindings(stmts: Vec<HirStatement>, value: HirExpression) -> HirExpression {
+    if stmts.is_empty() {
+        value
+    } else {
+        HirExpression::Block(stmts, Some(Box::new(value)))
+    }
+}
+
+/// Field-wise equality of two values whose struct type has
+/// `#[derive(PartialEq)]`. Each operand is evaluated once. Returns `None`
+/// when the type doesn't derive it.
+fn derived_eq(left: &HirExpression, right: &HirExpression) -> Option<HirExpression> {
+    let (left, struct_name) = derived_struct_operand(left, "PartialEq")?;
+    let (right, _) = derived_struct_operand(right, "PartialEq")?;
+    let fields = STRUCT_REGISTRY.with(|registry| registry.borrow().get(&struct_name).cloned())?;
+
+    let mut stmts = Vec::new();
+    let left = bind_once(&mut stmts, "__eq_left", left);
+    let right = bind_once(&mut stmts, "__eq_right", right);
+    let mut eq = HirExpression::Bool(true);
+    for (index, (field_name, _)) in fields.into_iter().enumerate() {
+        let l = HirExpression::FieldAccess { object: Box::new(left.clone()), field: field_name.clone(), location: None };
+        let r = HirExpression::FieldAccess { object: Box::new(right.clone()), field: field_name, location: None };
+        let field_eq = derived_eq(&l, &r).unwrap_or(HirExpression::BinaryOp {
+            op: BinaryOp::Equal,
+            left: Box::new(l),
+            right: Box::new(r),
+        });
+        eq = if index == 0 {
+            field_eq
+        } else {
+            HirExpression::BinaryOp { op: BinaryOp::And, left: Box::new(eq), right: Box::new(field_eq) }
+        };
+    }
+    Some(with_bindings(stmts, eq))
+}
+
+/// Field-wise copy of a value whose struct type has `#[derive(Clone)]`: a
+/// fresh struct whose Vec fields get their own buffer and whose derived-Clone
+/// fields are cloned in turn. The value is evaluated once. Returns `None`
+/// when the type doesn't derive it.
+fn derived_clone(value: &HirExpression) -> Option<HirExpression> {
+    let (value, struct_name) = derived_struct_operand(value, "Clone")?;
+    let fields = STRUCT_REGISTRY.with(|registry| registry.borrow().get(&struct_name).cloned())?;
+
+    let mut stmts = Vec::new();
+    let value = bind_once(&mut stmts, "__clone_src", value);
+    let fields = fields
+        .into_iter()
+        .map(|(field_name, field_ty)| {
+            let field = HirExpression::FieldAccess { object: Box::new(value.clone()), field: field_name.clone(), location: None };
+            let copy = match field_ty {
+                HirType::Vec(_) => HirExpression::Call {
+                    func: Box::new(HirExpression::Variable("gaia_vec_clone".to_string())),
+                    args: vec![field],
+                },
+                _ => derived_clone(&field).unwrap_or(field),
+            };
+            (field_name, copy)
+        })
+        .collect();
+    Some(with_bindings(stmts, HirExpression::StructLiteral { name: struct_name, fields }))
+}
+
+/// A `std::cmp::Ordering` variant
+fn ordering(variant: &str) -> HirExpression {
+    HirExpression::EnumVariant {
+        enum_name: "Ordering".to_string(),
+        variant_name: variant.to_string(),
+        args: Vec::new(),
+    }
+}
+
+/// Three-way comparison of two values whose struct type has
+/// `#[derive(PartialOrd)]` or `#[derive(Ord)]`: fields are compared in
+/// declaration order and the first unequal one decides. Strings compare by
+/// content, unsigned integers as unsigned, and fields of derived-ordering
+/// types by their own derived `cmp`. Each operand is evaluated once.
+/// Evaluates to an `Ordering`. Returns `None` when the type doesn't derive
+/// an ordering.
+fn derived_cmp(left: &HirExpression, right: &HirExpression) -> Option<HirExpression> {
+    let orders = |value| {
+        derived_struct_operand(value, "PartialOrd").or_else(|| derived_struct_operand(value, "Ord"))
+    };
+    let (left, struct_name) = orders(left)?;
+    let (right, _) = orders(right)?;
+    let fields = STRUCT_REGISTRY.with(|registry| registry.borrow().get(&struct_name).cloned())?;
+
+    let mut stmts = Vec::new();
+    let left = bind_once(&mut stmts, "__cmp_left", left);
+    let right = bind_once(&mut stmts, "__cmp_right", right);
+    // Fold from the last field so the first field ends up outermost
+    let mut cmp: Option<HirExpression> = None;
+    for (field_name, field_ty) in fields.into_iter().rev() {
+        let l = HirExpression::FieldAccess { object: Box::new(left.clone()), field: field_name.clone(), location: None };
+        let r = HirExpression::FieldAccess { object: Box::new(right.clone()), field: field_name, location: None };
+        let field_cmp = if is_string_type(&field_ty) {
+            runtime_call("gaia_str_cmp", vec![l, r])
+        } else if is_unsigned_type(&field_ty) {
+            runtime_call("gaia_ucmp", vec![l, r])
+        } else {
+            derived_cmp(&l, &r).unwrap_or_else(|| runtime_call("gaia_cmp", vec![l, r]))
+        };
+        cmp = Some(match cmp {
+            None => field_cmp,
+            Some(rest) => HirExpression::Call {
+                func: Box::new(HirExpression::Variable("gaia_cmp_then".to_string())),
+                args: vec![field_cmp, rest],
+            },
+        });
+    }
+    Some(with_bindings(stmts, cmp.unwrap_or_else(|| ordering("Equal"))))
+}
+
+/// `left.cmp(&right)` when the struct type has its own `impl Ord`
+fn implemented_cmp(left: &HirExpression, right: &HirExpression) -> Option<HirExpression> {
+    let HirType::Named(struct_name) = infer_hir_type(left) else { return None };
+    let implements_cmp = IMPL_REGISTRY.with(|registry| {
+        registry
+            .borrow()
+            .get(&struct_name)
+            .into_iter()
+            .flatten()
+            .any(|(trait_name, methods)| short_trait_name(trait_name) == "Ord" && methods.iter().any(|m| m == "cmp"))
+    });
+    implements_cmp.then(|| HirExpression::MethodCall {
+        receiver: Box::new(left.clone()),
+        method: "cmp".to_string(),
+        args: vec![HirExpression::UnaryOp { op: UnaryOp::Reference, operand: Box::new(right.clone()) }],
+    })
+}
+
+/// `std::cmp::min`/`max` (also bare or via `cmp::`). On two values of a
+/// struct type with an ordering the smaller/larger one is picked by its
+/// `cmp`, derived or implemented, one field at a time since struct values
+/// are placed at fixed stack slots. As in `std`, on equal values `min`
+/// picks the first and `max` the second. On anything else the path
+/// resolves to the prelude's integer `min`/`max`. Returns `None` for other
+/// calls.
+fn ordering_call(name: &str, args: &[HirExpression]) -> Option<HirExpression> {
+    let is_max = match name {
+        "min" | "cmp::min" | "std::cmp::min" => false,
+        "max" | "cmp::max" | "std::cmp::max" => true,
+        _ => return None,
+    };
+    let [a, b] = args else { return None };
+    let cmp = |a: &HirExpression, b: &HirExpression| implemented_cmp(a, b).or_else(|| derived_cmp(a, b));
+    let Some(ord) = cmp(a, b) else {
+        let base = if is_max { "max" } else { "min" };
+        return (name != base).then(|| HirExpression::Call {
+            func: Box::new(HirExpression::Variable(base.to_string())),
+            args: args.to_vec(),
+        });
+    };
+
+    // Each operand and the comparison are evaluated once
+    let mut stmts = Vec::new();
+    let a = bind_once(&mut stmts, "__ord_left", a);
+    let b = bind_once(&mut stmts, "__ord_right", b);
+    let ord = bind_once(&mut stmts, "__ord", &cmp(&a, &b).unwrap_or(ord));
+    // `min` keeps `a` unless it's greater, `max` takes `b` unless `a` is greater
+    let b_wins = HirExpression::BinaryOp {
+        op: if is_max { BinaryOp::NotEqual } else { BinaryOp::Equal },
+        left: Box::new(ord),
+        right: Box::new(ordering("Greater")),
+    };
+    Some(HirExpression::Block(stmts, Some(Box::new(pick_fields(&b_wins, &a, &b)))))
+}
+
+/// `vec.sort()` on a Vec of structs with an ordering, derived or
+/// implemented: an insertion sort (stable, like `sort`) that compares
+/// elements by their `cmp` and swaps their slots. Returns `None` for other
+/// element types, which the runtime sorts as integers.
+fn struct_vec_sort(vec: &HirExpression) -> Option<HirExpression> {
+    let HirType::Vec(element) = infer_hir_type(vec) else { return None };
+    let HirType::Named(_) = *element else { return None };
+    let i = fresh_temp("__sort_i");
+    let j = fresh_temp("__sort_j");
+    let var = |name: &str| HirExpression::Variable(name.to_string());
+    let binary = |op, left, right| HirExpression::BinaryOp { op, left: Box::new(left), right: Box::new(right) };
+    let element = |index| HirExpression::Index { array: Box::new(vec.clone()), index: Box::new(index) };
+    let before_j = binary(BinaryOp::Subtract, var(&j), HirExpression::Integer(1));
+    add_binding(i.clone(), HirType::Int64);
+    add_binding(j.clone(), HirType::Int64);
+
+    // Sink element `i` past the earlier ones greater than it
+    let mut sink = Vec::new();
+    let (earlier, later) = (element(before_j.clone()), element(var(&j)));
+    let ord = match derived_cmp(&earlier, &later) {
+        Some(ord) => ord,
+        // An `impl Ord` takes `&self`, which wants the struct on the stack
+        // rather than in the Vec's heap slot, so it compares field-wise copies
+        None => {
+            let earlier = bind_once(&mut sink, "__sort_earlier", &copy_fields(&earlier));
+            let later = bind_once(&mut sink, "__sort_later", &copy_fields(&later));
+            implemented_cmp(&earlier, &later)?
+        }
+    };
+    sink.extend([
+        HirStatement::If {
+            condition: Box::new(binary(BinaryOp::NotEqual, ord, ordering("Greater"))),
+            then_body: vec![HirStatement::Break],
+            else_body: None,
+        },
+        HirStatement::Expression(runtime_call("gaia_vec_swap", vec![vec.clone(), before_j.clone(), var(&j)])),
+        HirStatement::Expression(HirExpression::Assign { target: Box::new(var(&j)), value: Box::new(before_j) }),
+    ]);
+    let len = HirExpression::MethodCall { receiver: Box::new(vec.clone()), method: "len".to_string(), args: Vec::new() };
+    let outer = vec![
+        HirStatement::Let { name: j.clone(), mutable: true, ty: HirType::Int64, init: var(&i) },
+        HirStatement::While {
+            condition: Box::new(binary(BinaryOp::Greater, var(&j), HirExpression::Integer(0))),
+            body: sink,
+        },
+        HirStatement::Expression(HirExpression::Assign {
+            target: Box::new(var(&i)),
+            value: Box::new(binary(BinaryOp::Add, var(&i), HirExpression::Integer(1))),
+        }),
+    ];
+    Some(HirExpression::Block(
+        vec![
+            HirStatement::Let { name: i.clone(), mutable: true, ty: HirType::Int64, init: HirExpression::Integer(1) },
+            HirStatement::While { condition: Box::new(binary(BinaryOp::Less, var(&i), len)), body: outer },
+        ],
+        None,
+    ))
+}
+
+/// A struct literal copying `value` field by field (nested structs included)
+fn copy_fields(value: &HirExpression) -> HirExpression {
+    let struct_name = match infer_hir_type(value) {
+        HirType::Named(name) => name,
+        _ => return value.clone(),
+    };
+    match STRUCT_REGISTRY.with(|registry| registry.borrow().get(&struct_name).cloned()) {
+        Some(fields) => {
+            let fields = fields
+                .into_iter()
+                .map(|(field_name, _)| {
+                    let field = HirExpression::FieldAccess {
+                        object: Box::new(value.clone()),
+                        field: field_name.clone(),
+                        location: None,
+                    };
+                    (field_name, copy_fields(&field))
+                })
+                .collect();
+            HirExpression::StructLiteral { name: struct_name, fields }
+        }
+        None => value.clone(),
+    }
+}
+
+/// A copy of `b` where `b_wins` holds and of `a` otherwise, built field
+/// by field (nested structs included)
+fn pick_fields(b_wins: &HirExpression, a: &HirExpression, b: &HirExpression) -> HirExpression {
+    let struct_name = match infer_hir_type(a) {
+        HirType::Named(name) => name,
+        _ => String::new(),
+    };
+    match STRUCT_REGISTRY.with(|registry| registry.borrow().get(&struct_name).cloned()) {
+        Some(fields) => {
+            let fields = fields
+                .into_iter()
+                .map(|(field_name, _)| {
+                    let field = |value: &HirExpression| HirExpression::FieldAccess {
+                        object: Box::new(value.clone()),
+                        field: field_name.clone(),
+                        location: None,
+                    };
+                    let picked = pick_fields(b_wins, &field(a), &field(b));
+                    (field_name, picked)
+                })
+                .collect();
+            HirExpression::StructLiteral { name: struct_name, fields }
+        }
+        None => HirExpression::If {
+            condition: Box::new(b_wins.clone()),
+            then_body: vec![HirStatement::Expression(b.clone())],
+            else_body: Some(vec![HirStatement::Expression(a.clone())]),
+        },
+    }
+}
+
 /// Infer the type of a HirExpression for println argument type-awareness
 fn infer_hir_type(expr: &HirExpression) -> HirType {
     match expr {
@@ -1283,6 +2585,8 @@ fn infer_hir_type(expr: &HirExpression) -> HirType {
         HirExpression::Float(_) => HirType::Float64,
         HirExpression::String(_) => HirType::String,
         HirExpression::Bool(_) => HirType::Bool,
+        HirExpression::StructLiteral { name, .. } => HirType::Named(name.clone()),
+        HirExpression::EnumVariant { enum_name, .. } => HirType::Named(enum_name.clone()),
         HirExpression::Variable(_name) => {
             // Try to look up the variable type from scope tracker
             SCOPE_TRACKER.with(|tracker| {
@@ -1295,6 +2599,13 @@ fn infer_hir_type(expr: &HirExpression) -> HirType {
                 HirType::Unknown
             })
         }
+        HirExpression::BinaryOp {
+            op: BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::Less | BinaryOp::LessEqual
+                | BinaryOp::Greater | BinaryOp::GreaterEqual | BinaryOp::And | BinaryOp::Or,
+            ..
+        } => HirType::Bool,
+        // A shift has the type of the value being shifted, whatever the amount's type
+        HirExpression::BinaryOp { op: BinaryOp::LeftShift | BinaryOp::RightShift, left, .. } => infer_hir_type(left),
         HirExpression::BinaryOp { left, right, .. } => {
             // Infer from operands
             let left_ty = infer_hir_type(left);
@@ -1318,6 +2629,35 @@ fn infer_hir_type(expr: &HirExpression) -> HirType {
                 if let Some(ret_ty) = get_function_return_type(func_name) {
                     return ret_ty;
                 }
+                // Variant constructors of the built-in enums, and their accessors
+                let payload = || args.first().map_or(HirType::Unknown, infer_hir_type);
+                match func_name.as_str() {
+                    "Some" | "None" => return HirType::Option(Box::new(payload())),
+                    "__builtin_vec_from" => {
+                        let elem = match args.first() {
+                            Some(HirExpression::ArrayLiteral(elements)) => elements.first().map_or(HirType::Unknown, infer_hir_type),
+                            _ => HirType::Unknown,
+                        };
+                        return HirType::Vec(Box::new(elem));
+                    }
+                    "Vec::new" => return HirType::Named("Vec".to_string()),
+                    "HashMap::new" => return HirType::Named("HashMap".to_string()),
+                    "HashSet::new" => return HirType::Named("HashSet".to_string()),
+                    "gaia_format" | "gaia_string_concat" => return HirType::String,
+                    "Ok" => return HirType::Result { ok_type: Box::new(payload()), err_type: Box::new(HirType::Unknown) },
+                    "Err" => return HirType::Result { ok_type: Box::new(HirType::Unknown), err_type: Box::new(payload()) },
+                    "Option::unwrap" | "Option::unwrap_or" | "Result::unwrap" | "Result::unwrap_or" => {
+                        return match payload() {
+                            HirType::Option(inner) | HirType::Result { ok_type: inner, .. } => *inner,
+                            _ => HirType::Unknown,
+                        };
+                    }
+                    _ => {}
+                }
+                // Float math builtins
+                if matches!(func_name.as_str(), "sqrt" | "floor" | "ceil" | "round" | "pow" | "sin" | "cos" | "ln") {
+                    return HirType::Float64;
+                }
                 
                 // Enum value extraction - infer from the argument's inner type
                 if func_name == "__extract_enum_value" && !args.is_empty() {
@@ -1360,7 +2700,15 @@ fn infer_hir_type(expr: &HirExpression) -> HirType {
             }
             HirType::Unknown
         }
-        HirExpression::FieldAccess { object, field } => {
+        // A clone has its receiver's type
+        HirExpression::MethodCall { receiver, method, args } if method == "clone" && args.is_empty() => infer_hir_type(receiver),
+        // Vec::get is bounds-checked and Vec::pop may find the Vec empty; both return an Option
+        HirExpression::MethodCall { receiver, method, .. } if method == "get" || method == "pop" => match infer_hir_type(receiver) {
+            HirType::Vec(elem) => HirType::Option(elem),
+            HirType::Named(name) if name == "Vec" => HirType::Option(Box::new(HirType::Unknown)),
+            _ => HirType::Unknown,
+        },
+        HirExpression::FieldAccess { object, field, .. } => {
             // Try to infer the field type from struct definition
             let obj_ty = infer_hir_type(object);
             
@@ -1383,23 +2731,628 @@ fn infer_hir_type(expr: &HirExpression) -> HirType {
             
             HirType::Unknown
         }
+        HirExpression::Index { array, .. } => match infer_hir_type(array) {
+            HirType::Vec(elem) | HirType::Array { element_type: elem, .. } => *elem,
+            _ => HirType::Unknown,
+        },
+        HirExpression::Block(_, Some(tail)) => infer_hir_type(tail),
+        // Either branch's value will do; take the first one that's known
+        HirExpression::If { then_body, else_body, .. } => {
+            let branch_type = |body: &[HirStatement]| match body.last() {
+                Some(HirStatement::Expression(value)) => infer_hir_type(value),
+                _ => HirType::Unknown,
+            };
+            match branch_type(then_body) {
+                HirType::Unknown => else_body.as_deref().map_or(HirType::Unknown, branch_type),
+                ty => ty,
+            }
+        }
+        HirExpression::UnaryOp { op: UnaryOp::Negate | UnaryOp::BitwiseNot, operand } => infer_hir_type(operand),
+        HirExpression::UnaryOp { op: UnaryOp::Not, .. } => HirType::Bool,
+        HirExpression::UnaryOp { op: UnaryOp::Reference, operand } => HirType::Reference(Box::new(infer_hir_type(operand))),
+        HirExpression::UnaryOp { op: UnaryOp::MutableReference, operand } => {
+            HirType::MutableReference(Box::new(infer_hir_type(operand)))
+        }
         _ => HirType::Unknown,
     }
 }
 
-/// Lower an expression from AST to HIR
-/// Phase 6.5b: Constant folding - evaluate binary operations at compile time
-fn try_fold_binary_op(left: i64, right: i64, op: &parser::BinaryOp) -> Option<i64> {
-    match op {
-        parser::BinaryOp::Add => Some(left.wrapping_add(right)),
-        parser::BinaryOp::Subtract => Some(left.wrapping_sub(right)),
-        parser::BinaryOp::Multiply => Some(left.wrapping_mul(right)),
-        parser::BinaryOp::Divide => {
-            if right == 0 { None } else { Some(left / right) }
+/// Bytes between consecutive `T`s behind a `*const T`/`*mut T`: the size of
+/// `T`, as `size_of::<T>()` reports it.
+fn pointee_stride(pointee: &HirType) -> LowerResult<i64> {
+    scalar_pointee(pointee)?;
+    type_layout(pointee, &mut Vec::new()).map(|(size, _)| size)
+}
+
+/// Rejects a struct pointee: codegen keeps struct fields in 8-byte slots
+/// laid out downwards from the struct's address, which neither the
+/// `size_of` stride nor a load or store through the pointer follows
+fn scalar_pointee(pointee: &HirType) -> LowerResult<()> {
+    match pointee {
+        HirType::Named(name) if STRUCT_REGISTRY.with(|registry| registry.borrow().contains_key(name)) => Err(LowerError {
+            message: format!("raw pointers to structs are not supported yet: cannot dereference or offset a `*{}`", name),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Width in bytes and signedness of an integer pointee that's loaded and
+/// stored with fewer than 8 bytes
+fn narrow_pointee(pointee: &HirType) -> Option<(i64, bool)> {
+    match pointee {
+        HirType::Int32 | HirType::Char => Some((4, true)),
+        HirType::UInt32 => Some((4, false)),
+        HirType::Bool => Some((1, false)),
+        HirType::Named(name) => match name.as_str() {
+            "i16" => Some((2, true)),
+            "u16" => Some((2, false)),
+            "i8" => Some((1, true)),
+            "u8" => Some((1, false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `target = value`. Writing through a narrow raw pointer (lowered to a
+/// `gaia_ptr_read` call) stores only the pointee's bytes.
+fn assign_to(target: HirExpression, value: HirExpression) -> HirExpression {
+    match target {
+        HirExpression::Call { func, mut args } if matches!(func.as_ref(), HirExpression::Variable(name) if name == "gaia_ptr_read") => {
+            args.truncate(2);
+            args.push(value);
+            runtime_call("gaia_ptr_write", args)
+        }
+        target => HirExpression::Assign {
+            target: Box::new(target),
+            value: Box::new(value),
+        },
+    }
+}
+
+/// Size and alignment in bytes of `ty` under Rust's default layout, for
+/// `size_of`/`align_of`. `visiting` holds the structs being measured, to
+/// reject types that contain themselves.
+fn type_layout(ty: &HirType, visiting: &mut Vec<String>) -> LowerResult<(i64, i64)> {
+    let unsized_error = |what: &str| LowerError {
+        message: format!("cannot compute the size of `{}` at compile time", what),
+    };
+    match ty {
+        HirType::Bool => Ok((1, 1)),
+        HirType::Char | HirType::Int32 | HirType::UInt32 => Ok((4, 4)),
+        HirType::Int64 | HirType::UInt64 | HirType::USize | HirType::ISize | HirType::Float64 => Ok((8, 8)),
+        HirType::String | HirType::Vec(_) => Ok((24, 8)),
+        HirType::Reference(_)
+        | HirType::MutableReference(_)
+        | HirType::Pointer(_)
+        | HirType::Box(_)
+        | HirType::Function { .. } => Ok((8, 8)),
+        HirType::Array { element_type, size: Some(len) } => {
+            let (size, align) = type_layout(element_type, visiting)?;
+            Ok((size * *len as i64, align))
+        }
+        HirType::Tuple(elements) => {
+            let layouts = elements
+                .iter()
+                .map(|element| type_layout(element, visiting))
+                .collect::<LowerResult<Vec<_>>>()?;
+            Ok(aggregate_layout(layouts))
+        }
+        HirType::Named(name) => match name.as_str() {
+            "i8" | "u8" => Ok((1, 1)),
+            "i16" | "u16" => Ok((2, 2)),
+            "f32" | "char" => Ok((4, 4)),
+            "i128" | "u128" => Ok((16, 16)),
+            "String" | "Vec" => Ok((24, 8)),
+            _ if visiting.contains(name) => Err(LowerError {
+                message: format!("recursive type `{}` has infinite size", name),
+            }),
+            _ => {
+                if let Some(fields) = STRUCT_REGISTRY.with(|registry| registry.borrow().get(name).cloned()) {
+                    visiting.push(name.clone());
+                    let layouts = fields
+                        .iter()
+                        .map(|(_, field_ty)| type_layout(field_ty, visiting))
+                        .collect::<LowerResult<Vec<_>>>()?;
+                    visiting.pop();
+                    return Ok(aggregate_layout(layouts));
+                }
+                // Fieldless enums need one byte for up to 256 variants
+                match ENUM_REGISTRY.with(|registry| registry.borrow().get(name).map(|variants| variants.len())) {
+                    Some(0) => Ok((0, 1)),
+                    Some(count) if count <= 256 && !enum_has_payload(name) => Ok((1, 1)),
+                    _ => Err(unsized_error(name)),
+                }
+            }
+        },
+        other => Err(unsized_error(&other.to_string())),
+    }
+}
+
+/// Layout of a struct or tuple from its field layouts. Like rustc, fields
+/// are placed in decreasing order of alignment, so padding only appears at
+/// the end to round the size up to the alignment.
+fn aggregate_layout(mut fields: Vec<(i64, i64)>) -> (i64, i64) {
+    fields.sort_by_key(|&(_, align)| std::cmp::Reverse(align));
+    let align = fields.iter().map(|&(_, align)| align).max().unwrap_or(1);
+    let mut size = 0;
+    for (field_size, field_align) in fields {
+        size = (size + field_align - 1) / field_align * field_align + field_size;
+    }
+    ((size + align - 1) / align * align, align)
+}
+
+/// Resolve `<int type>::MIN`/`MAX` paths that fit in an i64
+fn integer_limit_constant(ty: &str, constant: &str) -> Option<i64> {
+    let (min, max) = match ty {
+        "i8" => (i8::MIN as i64, i8::MAX as i64),
+        "i16" => (i16::MIN as i64, i16::MAX as i64),
+        "i32" => (i32::MIN as i64, i32::MAX as i64),
+        "i64" | "isize" => (i64::MIN, i64::MAX),
+        "u8" => (0, u8::MAX as i64),
+        "u16" => (0, u16::MAX as i64),
+        "u32" => (0, u32::MAX as i64),
+        _ => return None,
+    };
+    match constant {
+        "MIN" => Some(min),
+        "MAX" => Some(max),
+        _ => None,
+    }
+}
+
+/// Evaluate every `const` item and record its value under its module path
+/// (`crate::N`, `crate::config::N`). Consts may refer to each other in any
+/// order, so evaluation repeats until no more initializers can be resolved.
+/// A `use` of a const then makes it visible under the importing module too.
+fn register_const_values(ast: &[Item]) {
+    CONST_VALUES.with(|values| values.borrow_mut().clear());
+    let mut consts = Vec::new();
+    let mut imports = Vec::new();
+    collect_consts(ast, &mut vec!["crate".to_string()], &mut consts, &mut imports);
+
+    let mut pending: Vec<&(Vec<String>, String, Expression)> = consts.iter().collect();
+    loop {
+        let before = pending.len();
+        pending.retain(|(module, name, value)| {
+            let outer = MODULE_PATH.with(|path| path.replace(module.clone()));
+            let literal = lower_expression(value).ok().and_then(|hir| const_value(&hir));
+            MODULE_PATH.with(|path| *path.borrow_mut() = outer);
+            match literal {
+                Some(literal) => {
+                    let key = format!("{}::{}", module.join("::"), name);
+                    CONST_VALUES.with(|values| values.borrow_mut().insert(key, literal));
+                    false
+                }
+                None => true,
+            }
+        });
+        if pending.is_empty() || pending.len() == before {
+            break;
         }
-        parser::BinaryOp::Modulo => {
-            if right == 0 { None } else { Some(left % right) }
+    }
+
+    for (module, path) in imports {
+        let Some(name) = path.last() else { continue };
+        if let Some(literal) = resolve_const_path(&module, &path.join("::")) {
+            let key = format!("{}::{}", module.join("::"), name);
+            CONST_VALUES.with(|values| values.borrow_mut().insert(key, literal));
+        }
+    }
+}
+
+/// The `const` items under `module`, and the paths its `use`s import
+fn collect_consts(
+    items: &[Item],
+    module: &mut Vec<String>,
+    consts: &mut Vec<(Vec<String>, String, Expression)>,
+    imports: &mut Vec<(Vec<String>, Vec<String>)>,
+) {
+    for item in items {
+        match item {
+            Item::Const { name, value, .. } => consts.push((module.clone(), name.clone(), value.clone())),
+            Item::Use { path, is_glob: false, .. } => imports.push((module.clone(), path.clone())),
+            Item::Module { name, items, .. } => {
+                module.push(name.clone());
+                collect_consts(items, module, consts, imports);
+                module.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The const `path` names from inside `module`: an associated `Type::NAME`,
+/// a path from `crate`, `self` or `super`, or one relative to `module`
+fn resolve_const_path(module: &[String], path: &str) -> Option<HirExpression> {
+    if let Some(value) = get_const_value(path) {
+        return Some(value);
+    }
+    let mut base = module.to_vec();
+    let mut rest = path;
+    loop {
+        if let Some(tail) = rest.strip_prefix("crate::") {
+            base.truncate(1);
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("self::") {
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("super::") {
+            if base.len() > 1 {
+                base.pop();
+            }
+            rest = tail;
+        } else {
+            break;
+        }
+    }
+    get_const_value(&format!("{}::{}", base.join("::"), rest))
+}
+
+/// The value of the const `name` refers to where it's used, unless a local
+/// or parameter of that name shadows it
+fn const_in_scope(name: &str) -> Option<HirExpression> {
+    if !name.contains("::") && SCOPE_TRACKER.with(|tracker| tracker.borrow().is_in_scope(name)) {
+        return None;
+    }
+    let module = MODULE_PATH.with(|path| path.borrow().clone());
+    resolve_const_path(&module, name)
+}
+
+fn get_const_value(name: &str) -> Option<HirExpression> {
+    CONST_VALUES.with(|values| values.borrow().get(name).cloned())
+}
+
+/// Record associated consts as `Type::NAME` constants. An `impl Trait for T`
+/// takes the trait's default for any const it doesn't define itself.
+fn register_associated_consts(items: &[Item]) {
+    let consts = |members: &[Item]| -> Vec<(String, Option<Expression>)> {
+        members.iter().filter_map(|member| match member {
+            Item::AssociatedConst { name, value, .. } => Some((name.clone(), value.clone())),
+            _ => None,
+        }).collect()
+    };
+    let defaults: HashMap<String, Vec<(String, Option<Expression>)>> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Trait { name, methods, .. } => Some((name.clone(), consts(methods))),
+            _ => None,
+        })
+        .collect();
+
+    for item in items {
+        let Item::Impl { struct_name, trait_name, methods, .. } = item else { continue };
+        let mut values = consts(methods);
+        if let Some(trait_defaults) = trait_name.as_ref().and_then(|t| defaults.get(t)) {
+            for (name, default) in trait_defaults {
+                if !values.iter().any(|(defined, _)| defined == name) {
+                    values.push((name.clone(), default.clone()));
+                }
+            }
+        }
+        for (name, value) in values {
+            let literal = value
+                .and_then(|v| lower_expression(&v).ok())
+                .and_then(|hir| const_value(&hir));
+            if let Some(literal) = literal {
+                CONST_VALUES.with(|c| c.borrow_mut().insert(format!("{}::{}", struct_name, name), literal));
+            }
+        }
+    }
+}
+
+/// Find the generic functions bounded by a trait declared in the crate and
+/// record them as templates to instantiate per call
+fn register_generic_templates(items: &[Item]) {
+    let crate_traits: HashSet<&String> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Trait { name, .. } => Some(name),
+            _ => None,
+        })
+        .collect();
+
+    GENERIC_TEMPLATES.with(|templates| {
+        let mut templates = templates.borrow_mut();
+        templates.clear();
+        for item in items {
+            let Item::Function { name, generics, where_clause, .. } = item else { continue };
+            let bounded = generics.iter().any(|g| match g {
+                GenericParam::Type { bounds, .. } => bounds.iter().any(|b| crate_traits.contains(b)),
+                _ => false,
+            }) || where_clause.iter().any(|w| w.bounds.iter().any(|b| crate_traits.contains(b)));
+            if bounded {
+                templates.insert(name.clone(), item.clone());
+            }
         }
+    });
+    PENDING_INSTANCES.with(|pending| pending.borrow_mut().clear());
+    LOWERED_INSTANCES.with(|lowered| lowered.borrow_mut().clear());
+}
+
+/// The name a type parameter is bound to for an argument of type `ty`: the
+/// type behind any references, which may be a struct or a primitive
+fn generic_argument_type_name(ty: &HirType) -> Option<String> {
+    match ty {
+        HirType::Reference(inner) | HirType::MutableReference(inner) => generic_argument_type_name(inner),
+        HirType::Named(name) => Some(name.clone()),
+        HirType::Int32
+        | HirType::Int64
+        | HirType::UInt32
+        | HirType::UInt64
+        | HirType::USize
+        | HirType::ISize
+        | HirType::Float64
+        | HirType::Bool
+        | HirType::Char => Some(ty.to_string()),
+        _ => None,
+    }
+}
+
+/// An unsuffixed integer literal bound to a type parameter takes the one
+/// integer type that implements all of the parameter's bounds, if there is one
+fn integer_type_implementing(bounds: &[&String]) -> Option<String> {
+    let integers = ["i32", "i64", "u32", "u64", "usize", "isize"];
+    let implementing: Vec<&str> = IMPL_REGISTRY.with(|registry| {
+        let registry = registry.borrow();
+        integers
+            .into_iter()
+            .filter(|ty| registry.get(*ty).is_some_and(|traits| bounds.iter().all(|b| traits.contains_key(b.as_str()))))
+            .collect()
+    });
+    match implementing.as_slice() {
+        [integer] if !bounds.is_empty() => Some(integer.to_string()),
+        _ => None,
+    }
+}
+
+/// A call to a generic template becomes a call to its instantiation for
+/// the concrete types, taken from the turbofish or else from the arguments
+/// passed for parameters of type `T` / `&T`. Returns `None` for other calls.
+fn instantiate_generic_call(name: &str, type_args: &[Type], args: &[HirExpression]) -> LowerResult<Option<String>> {
+    let Some(Item::Function { generics, params, where_clause, .. }) =
+        GENERIC_TEMPLATES.with(|templates| templates.borrow().get(name).cloned())
+    else {
+        return Ok(None);
+    };
+    let type_params: Vec<(&String, Vec<&String>)> = generics.iter().filter_map(|g| match g {
+        GenericParam::Type { name, bounds, .. } => {
+            let where_bounds = where_clause.iter().filter(|c| c.param_name == *name).flat_map(|c| &c.bounds);
+            Some((name, bounds.iter().chain(where_bounds).collect()))
+        }
+        _ => None,
+    }).collect();
+
+    let mut concrete = Vec::new();
+    for (i, (type_param, bounds)) in type_params.iter().enumerate() {
+        let from_turbofish = type_args.get(i).and_then(|ty| generic_argument_type_name(&lower_type(ty).ok()?));
+        let from_args = || params.iter().zip(args).find_map(|(param, arg)| {
+            let is_param_type = match &param.ty {
+                Type::Named(n) => n == *type_param,
+                Type::Reference { inner, .. } => matches!(&**inner, Type::Named(n) if n == *type_param),
+                _ => false,
+            };
+            if !is_param_type {
+                return None;
+            }
+            let literal_type = match arg {
+                HirExpression::Integer(_) => integer_type_implementing(bounds),
+                _ => None,
+            };
+            literal_type.or_else(|| generic_argument_type_name(&infer_hir_type(arg)))
+        });
+        match from_turbofish.or_else(from_args) {
+            Some(ty) => concrete.push(ty),
+            None => {
+                return Err(LowerError {
+                    message: format!("cannot infer type for type parameter `{}` in call to `{}`", type_param, name),
+                })
+            }
+        }
+    }
+
+    // `.` can't appear in a Rust identifier, so an instance can't collide
+    // with a function the user wrote
+    let instance = format!("{}.{}", name, concrete.join("."));
+    let is_new = LOWERED_INSTANCES.with(|lowered| lowered.borrow_mut().insert(instance.clone()));
+    if is_new {
+        PENDING_INSTANCES.with(|pending| {
+            pending.borrow_mut().push((instance.clone(), name.to_string(), concrete));
+        });
+    }
+    Ok(Some(instance))
+}
+
+/// Lower every requested instantiation of a generic template, with its
+/// type parameters bound to the concrete types. Lowering one may request more.
+fn lower_pending_instances() -> LowerResult<Vec<HirItem>> {
+    let mut lowered = Vec::new();
+    while let Some((instance, template, concrete)) = PENDING_INSTANCES.with(|pending| pending.borrow_mut().pop()) {
+        let Some(Item::Function { generics, params, return_type, body, is_unsafe, is_async, is_pub, attributes, abi, location, .. }) =
+            GENERIC_TEMPLATES.with(|templates| templates.borrow().get(&template).cloned())
+        else {
+            continue;
+        };
+        let bindings: HashMap<String, String> = generics
+            .iter()
+            .filter_map(|g| match g {
+                GenericParam::Type { name, .. } => Some(name.clone()),
+                _ => None,
+            })
+            .zip(concrete)
+            .collect();
+        let item = Item::Function {
+            name: instance,
+            generics: Vec::new(),
+            params,
+            return_type,
+            body,
+            is_unsafe,
+            is_async,
+            is_pub,
+            attributes,
+            where_clause: Vec::new(),
+            abi,
+            location,
+        };
+        TYPE_SUBSTITUTIONS.with(|subs| *subs.borrow_mut() = bindings);
+        let result = lower_item(&item);
+        TYPE_SUBSTITUTIONS.with(|subs| subs.borrow_mut().clear());
+        lowered.push(result?);
+    }
+    Ok(lowered)
+}
+
+/// The concrete type a type parameter is bound to in the instantiation being lowered
+fn substituted_type_name(name: &str) -> Option<String> {
+    TYPE_SUBSTITUTIONS.with(|subs| subs.borrow().get(name).cloned())
+}
+
+/// The value of a lowered branch body: its trailing expression statement
+/// becomes the block's tail
+fn block_value(mut stmts: Vec<HirStatement>) -> HirExpression {
+    match stmts.pop() {
+        Some(HirStatement::Expression(tail)) => HirExpression::Block(stmts, Some(Box::new(tail))),
+        Some(other) => {
+            stmts.push(other);
+            HirExpression::Block(stmts, None)
+        }
+        None => HirExpression::Block(stmts, None),
+    }
+}
+
+/// Reduce a lowered expression to a literal if it is a compile-time constant
+fn const_value(expr: &HirExpression) -> Option<HirExpression> {
+    match expr {
+        HirExpression::Integer(_)
+        | HirExpression::Float(_)
+        | HirExpression::Bool(_)
+        | HirExpression::String(_) => Some(expr.clone()),
+        HirExpression::UnaryOp { op, operand } => match (op, const_value(operand)?) {
+            (UnaryOp::Negate, HirExpression::Integer(n)) => Some(HirExpression::Integer(n.wrapping_neg())),
+            (UnaryOp::Negate, HirExpression::Float(f)) => Some(HirExpression::Float(-f)),
+            (UnaryOp::BitwiseNot, HirExpression::Integer(n)) => Some(HirExpression::Integer(!n)),
+            (UnaryOp::Not, HirExpression::Bool(b)) => Some(HirExpression::Bool(!b)),
+            _ => None,
+        },
+        // Integer operations are already folded while lowering
+        HirExpression::BinaryOp { op, left, right } => match (const_value(left)?, const_value(right)?) {
+            (HirExpression::Float(l), HirExpression::Float(r)) => match op {
+                BinaryOp::Add => Some(HirExpression::Float(l + r)),
+                BinaryOp::Subtract => Some(HirExpression::Float(l - r)),
+                BinaryOp::Multiply => Some(HirExpression::Float(l * r)),
+                BinaryOp::Divide => Some(HirExpression::Float(l / r)),
+                _ => None,
+            },
+            (HirExpression::Bool(l), HirExpression::Bool(r)) => match op {
+                BinaryOp::And => Some(HirExpression::Bool(l && r)),
+                BinaryOp::Or => Some(HirExpression::Bool(l || r)),
+                BinaryOp::Equal => Some(HirExpression::Bool(l == r)),
+                BinaryOp::NotEqual => Some(HirExpression::Bool(l != r)),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Evaluate `value as ty` for a constant operand, following Rust's cast
+/// semantics: integer casts wrap, float-to-integer casts saturate (NaN
+/// becomes 0), and integer-to-float casts round to the nearest float.
+fn fold_cast(value: &HirExpression, ty: &Type) -> Option<HirExpression> {
+    let Type::Named(target) = ty else {
+        return None;
+    };
+    let result = match (const_value(value)?, target.as_str()) {
+        (HirExpression::Bool(b), _) => return fold_cast(&HirExpression::Integer(b as i64), ty),
+        (HirExpression::Integer(n), "f64") => HirExpression::Float(n as f64),
+        (HirExpression::Integer(n), "f32") => HirExpression::Float(n as f32 as f64),
+        (HirExpression::Integer(n), target) => HirExpression::Integer(wrap_integer(n, target)?),
+        (HirExpression::Float(f), "f64") => HirExpression::Float(f),
+        (HirExpression::Float(f), "f32") => HirExpression::Float(f as f32 as f64),
+        (HirExpression::Float(f), target) => HirExpression::Integer(match target {
+            "i8" => f as i8 as i64,
+            "i16" => f as i16 as i64,
+            "i32" => f as i32 as i64,
+            "i64" | "isize" => f as i64,
+            "u8" => f as u8 as i64,
+            "u16" => f as u16 as i64,
+            "u32" => f as u32 as i64,
+            "u64" | "usize" => f as u64 as i64,
+            _ => return None,
+        }),
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// Truncate an integer to `target`'s width, reinterpreting the bits as
+/// `target` would (64-bit unsigned values keep their bit pattern)
+fn wrap_integer(n: i64, target: &str) -> Option<i64> {
+    Some(match target {
+        "i8" => n as i8 as i64,
+        "i16" => n as i16 as i64,
+        "i32" => n as i32 as i64,
+        "i64" | "isize" | "u64" | "usize" => n,
+        "u8" | "char" => n as u8 as i64,
+        "u16" => n as u16 as i64,
+        "u32" => n as u32 as i64,
+        _ => return None,
+    })
+}
+
+/// Lower a non-constant `value as ty`. Every integer is held in a 64-bit
+/// register, so narrowing casts mask (unsigned) or shift left and back
+/// (signed) to reproduce the wrap, and pointer casts pass the value through.
+fn lower_runtime_cast(value: HirExpression, ty: &Type) -> LowerResult<HirExpression> {
+    let shift_pair = |bits: i64| HirExpression::BinaryOp {
+        op: BinaryOp::RightShift,
+        left: Box::new(HirExpression::BinaryOp {
+            op: BinaryOp::LeftShift,
+            left: Box::new(value.clone()),
+            right: Box::new(HirExpression::Integer(64 - bits)),
+        }),
+        right: Box::new(HirExpression::Integer(64 - bits)),
+    };
+    let mask = |bits: u32| HirExpression::BinaryOp {
+        op: BinaryOp::BitwiseAnd,
+        left: Box::new(value.clone()),
+        right: Box::new(HirExpression::Integer((1i64 << bits) - 1)),
+    };
+    let is_float = matches!(infer_hir_type(&value), HirType::Float64);
+
+    let target = match ty {
+        Type::Pointer { .. } => return Ok(value),
+        Type::Named(target) => target.as_str(),
+        _ => return Err(LowerError { message: format!("unsupported cast to `{:?}`", ty) }),
+    };
+    match (target, is_float) {
+        ("f64" | "f32", true) => Ok(value),
+        ("i64" | "isize" | "u64" | "usize", false) => Ok(value),
+        ("i8", false) => Ok(shift_pair(8)),
+        ("i16", false) => Ok(shift_pair(16)),
+        ("i32", false) => Ok(shift_pair(32)),
+        ("u8" | "char", false) => Ok(mask(8)),
+        ("u16", false) => Ok(mask(16)),
+        ("u32", false) => Ok(mask(32)),
+        _ => Err(LowerError {
+            message: format!(
+                "casts to `{}` from a non-constant value are not supported yet; only constant expressions can convert between integers and floats",
+                target
+            ),
+        }),
+    }
+}
+
+/// Fold an integer binary operation. Returns None when the operation can't be
+/// evaluated at compile time, including when it would overflow.
+fn try_fold_binary_op(left: i64, right: i64, op: &parser::BinaryOp) -> Option<i64> {
+    match op {
+        parser::BinaryOp::Add => left.checked_add(right),
+        parser::BinaryOp::Subtract => left.checked_sub(right),
+        parser::BinaryOp::Multiply => left.checked_mul(right),
+        parser::BinaryOp::Divide => left.checked_div(right),
+        parser::BinaryOp::Modulo => left.checked_rem(right),
         parser::BinaryOp::Equal => Some(if left == right { 1 } else { 0 }),
         parser::BinaryOp::NotEqual => Some(if left != right { 1 } else { 0 }),
         parser::BinaryOp::Less => Some(if left < right { 1 } else { 0 }),
@@ -1420,6 +3373,36 @@ fn try_fold_binary_op(left: i64, right: i64, op: &parser::BinaryOp) -> Option<i6
     }
 }
 
+/// The condition under which a match arm's pattern accepts `scrutinee`
+fn match_pattern_condition(scrutinee: &HirExpression, pattern: &Pattern) -> LowerResult<HirExpression> {
+    let compare = |op, bound: &Expression| -> LowerResult<HirExpression> {
+        Ok(HirExpression::BinaryOp {
+            op,
+            left: Box::new(scrutinee.clone()),
+            right: Box::new(lower_expression(bound)?),
+        })
+    };
+    Ok(match pattern {
+        // Compare scrutinee with literal
+        Pattern::Literal(lit) => compare(BinaryOp::Equal, lit)?,
+        Pattern::Identifier(name) if name == "None" => {
+            enum_pattern_condition(scrutinee, std::slice::from_ref(name))
+        }
+        Pattern::EnumVariant { path, .. } => enum_pattern_condition(scrutinee, path),
+        // `start..=end` / `start..end`: a two-sided comparison
+        Pattern::Range { start, end, inclusive } => HirExpression::BinaryOp {
+            op: BinaryOp::And,
+            left: Box::new(compare(BinaryOp::GreaterEqual, start)?),
+            right: Box::new(compare(if *inclusive { BinaryOp::LessEqual } else { BinaryOp::Less }, end)?),
+        },
+        // `name @ pattern` matches whatever the inner pattern does
+        Pattern::Binding { pattern, .. } => match_pattern_condition(scrutinee, pattern)?,
+        // Identifiers and wildcards always match; tuple and struct patterns
+        // are simplified to always match too
+        _ => HirExpression::Bool(true),
+    })
+}
+
 fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
     match expr {
         Expression::Integer(n) => Ok(HirExpression::Integer(*n)),
@@ -1433,16 +3416,62 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
             Ok(HirExpression::Integer(code_point))
         }
 
-        Expression::Variable(name) => Ok(HirExpression::Variable(name.clone())),
+        Expression::Variable(name) if name == "None" => {
+            // Built like the other variant constructors, e.g. `Some(x)`
+            Ok(HirExpression::Call {
+                func: Box::new(HirExpression::Variable(name.clone())),
+                args: Vec::new(),
+            })
+        }
+
+        Expression::Variable(name) => {
+            // `T::MAX` in an instantiation names the bound type's const
+            let name = &match name.split_once("::") {
+                Some((head, rest)) => match substituted_type_name(head) {
+                    Some(ty) => format!("{}::{}", ty, rest),
+                    None => name.clone(),
+                },
+                None => name.clone(),
+            };
+            Ok(const_in_scope(name).unwrap_or_else(|| HirExpression::Variable(name.clone())))
+        }
 
         Expression::Binary { left, op, right } => {
             let left_hir = lower_expression(left)?;
             let right_hir = lower_expression(right)?;
             
             // Phase 6.5b: Constant folding - evaluate compile-time constants
-            if let (HirExpression::Integer(l), HirExpression::Integer(r)) = (&left_hir, &right_hir) {
-                if let Some(result) = try_fold_binary_op(*l, *r, op) {
-                    return Ok(HirExpression::Integer(result));
+            // (negative literals like `-1` included)
+            if let (Some(HirExpression::Integer(l)), Some(HirExpression::Integer(r))) =
+                (const_value(&left_hir), const_value(&right_hir))
+            {
+                if let Some(result) = try_fold_binary_op(l, r, op) {
+                    // A folded comparison is still a bool, not 1 or 0
+                    let is_comparison = matches!(op,
+                        parser::BinaryOp::Equal | parser::BinaryOp::NotEqual | parser::BinaryOp::Less
+                            | parser::BinaryOp::LessEq | parser::BinaryOp::Greater | parser::BinaryOp::GreaterEq);
+                    return Ok(if is_comparison { HirExpression::Bool(result != 0) } else { HirExpression::Integer(result) });
+                }
+                // Overflowing expressions are left unfolded for the runtime, unless
+                // overflow checks make them a compile-time error
+                // (`i64::MIN / -1` and `i64::MIN % -1` overflow too; dividing by
+                // zero doesn't)
+                let attempt = match op {
+                    parser::BinaryOp::Add => Some(format!("add `{}` and `{}`", l, r)),
+                    parser::BinaryOp::Subtract => Some(format!("subtract `{}` and `{}`", l, r)),
+                    parser::BinaryOp::Multiply => Some(format!("multiply `{}` and `{}`", l, r)),
+                    parser::BinaryOp::Divide if r == -1 => Some(format!("divide `{}` by `{}`", l, r)),
+                    parser::BinaryOp::Modulo if r == -1 => {
+                        Some(format!("calculate the remainder of `{}` divided by `{}`", l, r))
+                    }
+                    _ => None,
+                };
+                if let Some(attempt) = attempt {
+                    if overflow_checks_enabled() {
+                        return Err(LowerError {
+                            message: format!("this arithmetic operation will overflow: attempt to {} with overflow", attempt),
+                        });
+                    }
                 }
             }
             
@@ -1466,6 +3495,36 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
                 parser::BinaryOp::LeftShift => BinaryOp::LeftShift,
                 parser::BinaryOp::RightShift => BinaryOp::RightShift,
             };
+            // #[derive(PartialEq, PartialOrd)] structs compare field by field
+            match op_hir {
+                BinaryOp::Equal | BinaryOp::NotEqual => {
+                    if let Some(eq) = derived_eq(&left_hir, &right_hir) {
+                        return Ok(if op_hir == BinaryOp::Equal {
+                            eq
+                        } else {
+                            HirExpression::UnaryOp { op: UnaryOp::Not, operand: Box::new(eq) }
+                        });
+                    }
+                }
+                BinaryOp::Less | BinaryOp::LessEqual | BinaryOp::Greater | BinaryOp::GreaterEqual => {
+                    // `a < b` is `a.cmp(&b) == Less`, `a <= b` is `a.cmp(&b) != Greater`, ...
+                    if let Some(cmp) = derived_cmp(&left_hir, &right_hir) {
+                        let (op, variant) = match op_hir {
+                            BinaryOp::Less => (BinaryOp::Equal, "Less"),
+                            BinaryOp::LessEqual => (BinaryOp::NotEqual, "Greater"),
+                            BinaryOp::Greater => (BinaryOp::Equal, "Greater"),
+                            _ => (BinaryOp::NotEqual, "Less"),
+                        };
+                        return Ok(HirExpression::BinaryOp {
+                            op,
+                            left: Box::new(cmp),
+                            right: Box::new(ordering(variant)),
+                        });
+                    }
+                }
+                _ => {}
+            }
+
             Ok(HirExpression::BinaryOp {
                 op: op_hir,
                 left: Box::new(left_hir),
@@ -1475,6 +3534,26 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
 
         Expression::Unary { op, operand } => {
             let operand_hir = lower_expression(operand)?;
+            if *op == parser::UnaryOp::Dereference
+                && matches!(infer_hir_type(&operand_hir), HirType::Pointer(_))
+                && !is_in_unsafe_context()
+            {
+                return Err(LowerError {
+                    message: "cannot dereference raw pointer outside of unsafe block".to_string(),
+                });
+            }
+            // A narrow pointee must not be read with a full 8-byte load
+            if *op == parser::UnaryOp::Dereference {
+                if let HirType::Pointer(pointee) = infer_hir_type(&operand_hir) {
+                    scalar_pointee(&pointee)?;
+                    if let Some((size, signed)) = narrow_pointee(&pointee) {
+                        return Ok(runtime_call(
+                            "gaia_ptr_read",
+                            vec![operand_hir, HirExpression::Integer(size), HirExpression::Integer(signed as i64)],
+                        ));
+                    }
+                }
+            }
             let op_hir = match op {
                 parser::UnaryOp::Negate => UnaryOp::Negate,
                 parser::UnaryOp::Not => UnaryOp::Not,
@@ -1492,20 +3571,30 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
         Expression::Assign { target, value } => {
             let target_hir = lower_expression(target)?;
             let value_hir = lower_expression(value)?;
-            Ok(HirExpression::Assign {
-                target: Box::new(target_hir),
-                value: Box::new(value_hir),
-            })
+            Ok(assign_to(target_hir, value_hir))
         }
 
-        Expression::CompoundAssign { target, op: _, value } => {
-            // For now, desugar compound assignments as regular assignments
+        Expression::CompoundAssign { target, op, value } => {
+            // `x op= y` desugars to `x = x op y`
+            let op = match op {
+                parser::CompoundOp::AddAssign => parser::BinaryOp::Add,
+                parser::CompoundOp::SubtractAssign => parser::BinaryOp::Subtract,
+                parser::CompoundOp::MultiplyAssign => parser::BinaryOp::Multiply,
+                parser::CompoundOp::DivideAssign => parser::BinaryOp::Divide,
+                parser::CompoundOp::ModuloAssign => parser::BinaryOp::Modulo,
+                parser::CompoundOp::AndAssign => parser::BinaryOp::BitwiseAnd,
+                parser::CompoundOp::OrAssign => parser::BinaryOp::BitwiseOr,
+                parser::CompoundOp::XorAssign => parser::BinaryOp::BitwiseXor,
+                parser::CompoundOp::LeftShiftAssign => parser::BinaryOp::LeftShift,
+                parser::CompoundOp::RightShiftAssign => parser::BinaryOp::RightShift,
+            };
             let target_hir = lower_expression(target)?;
-            let value_hir = lower_expression(value)?;
-            Ok(HirExpression::Assign {
-                target: Box::new(target_hir),
-                value: Box::new(value_hir),
-            })
+            let value_hir = lower_expression(&Expression::Binary {
+                left: target.clone(),
+                op,
+                right: value.clone(),
+            })?;
+            Ok(assign_to(target_hir, value_hir))
         }
 
         Expression::If {
@@ -1526,6 +3615,12 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
             } else {
                 None
             };
+            // A condition that folds to a literal is made only of literals
+            // and operators, so dropping it loses no side effects
+            if let Some(HirExpression::Bool(taken)) = const_value(&cond_hir) {
+                let branch = if taken { then_hir } else { else_hir.unwrap_or_default() };
+                return Ok(block_value(branch));
+            }
             Ok(HirExpression::If {
                 condition: Box::new(cond_hir),
                 then_body: then_hir,
@@ -1543,137 +3638,88 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
         }
 
         Expression::Loop(body) => {
-            // Desugar loop { ... } to while true { ... }
-            let body_stmts = lower_block(body)?;
-            Ok(HirExpression::While {
-                condition: Box::new(HirExpression::Bool(true)),
-                body: body_stmts,
-            })
+            let body = lower_block(body)?;
+            Ok(HirExpression::Loop { body })
         }
 
         Expression::Match {
             scrutinee,
             arms,
         } => {
-            let scrutinee_hir = lower_expression(scrutinee)?;
+            // Every arm reads the scrutinee again, so anything but a plain
+            // variable is evaluated once into a temporary first
+            let (scrutinee_let, scrutinee_hir) = match lower_expression(scrutinee)? {
+                value @ HirExpression::Variable(_) => (None, value),
+                value => {
+                    let name = fresh_temp("__match_scrutinee");
+                    let ty = infer_hir_type(&value);
+                    add_binding(name.clone(), ty.clone());
+                    let binding = HirStatement::Let { name: name.clone(), mutable: false, ty, init: value };
+                    (Some(binding), HirExpression::Variable(name))
+                }
+            };
             
             // Desugar match into nested if-else statements
             // Process arms in reverse to build the else-chain correctly
             let mut result_expr: Option<HirExpression> = None;
             
             for arm in arms.iter().rev() {
-                let pattern_condition = match &arm.pattern {
-                    Pattern::Literal(lit) => {
-                        // Compare scrutinee with literal
-                        HirExpression::BinaryOp {
-                            op: BinaryOp::Equal,
-                            left: Box::new(scrutinee_hir.clone()),
-                            right: Box::new(lower_expression(lit)?),
-                        }
-                    }
-                    Pattern::Identifier(_name) => {
-                        // Identifiers always match (binding), so use true
-                        HirExpression::Bool(true)
-                    }
-                    Pattern::EnumVariant { path, data } => {
-                        // For builtin enum variants, check the variant
-                        if path.len() == 1 {
-                            match path[0].as_str() {
-                                "Some" => {
-                                    // Check if scrutinee is Some (not None)
-                                    // We'll use a method call is_some() when available
-                                    // For now, just match - proper implementation pending
-                                    HirExpression::Bool(true)
-                                }
-                                "Ok" => HirExpression::Bool(true),
-                                "Err" => HirExpression::Bool(true),
-                                _ => HirExpression::Bool(true),
-                            }
-                        } else {
-                            HirExpression::Bool(true)
-                        }
-                    }
-                    Pattern::Tuple(_) => {
-                        // Simplified tuple patterns
-                        HirExpression::Bool(true)
-                    }
-                    Pattern::Struct { .. } => {
-                        // Simplified struct patterns
-                        HirExpression::Bool(true)
-                    }
-                    Pattern::Wildcard => {
-                        // Wildcard always matches
-                        HirExpression::Bool(true)
-                    }
-                    Pattern::Range { .. } => {
-                        // Range patterns for numbers
-                        HirExpression::Bool(true)
-                    }
-                    _ => {
-                        // Handle other patterns
-                        HirExpression::Bool(true)
-                    }
-                };
+                let pattern_condition = match_pattern_condition(&scrutinee_hir, &arm.pattern)?;
                 
                 // Extract bindings from the pattern and create let bindings in the arm body
                 let mut arm_body = Vec::new();
                 
+                // `name @ pattern` binds the whole scrutinee
+                let mut pattern = &arm.pattern;
+                while let Pattern::Binding { name, pattern: inner } = pattern {
+                    let ty = infer_hir_type(&scrutinee_hir);
+                    arm_body.push(HirStatement::Let {
+                        name: name.clone(),
+                        mutable: false,
+                        ty: ty.clone(),
+                        init: scrutinee_hir.clone(),
+                    });
+                    add_binding(name.clone(), ty);
+                    pattern = inner;
+                }
+
                 // Add let bindings for pattern variables
-                if let Pattern::EnumVariant { path, data: Some(inner_pattern) } = &arm.pattern {
-                    // Extract variable name from inner pattern
-                    if let Pattern::Identifier(var_name) = &**inner_pattern {
-                        // Infer the type of the scrutinee to extract the inner type
-                        let scrutinee_type = infer_hir_type(&scrutinee_hir);
-                        
-                        // Determine the inner type from the pattern
-                        let inner_type = if path.len() == 1 {
-                            match path[0].as_str() {
-                                "Some" => {
-                                    // Extract inner type from Option<T>
-                                    match scrutinee_type {
-                                        HirType::Option(ref inner) => (**inner).clone(),
-                                        _ => HirType::Int64, // Default fallback
-                                    }
-                                }
-                                "Ok" => {
-                                    // Extract ok type from Result<T, E>
-                                    match scrutinee_type {
-                                        HirType::Result { ref ok_type, .. } => (**ok_type).clone(),
-                                        _ => HirType::Int64, // Default fallback
-                                    }
-                                }
-                                "Err" => {
-                                    // Extract error type from Result<T, E>
-                                    match scrutinee_type {
-                                        HirType::Result { ref err_type, .. } => (**err_type).clone(),
-                                        _ => HirType::Int64, // Default fallback
-                                    }
-                                }
-                                _ => HirType::Unknown,
-                            }
-                        } else {
-                            HirType::Unknown
-                        };
-                        
-                        // Extract the value using a special method call that codegen will recognize
-                        // This desugars to calling __extract_enum_value(scrutinee, variant_index)
+                if let Pattern::EnumVariant { path, data: Some(inner_pattern) } = pattern {
+                    // `Variant(a, b)` binds each payload slot in order
+                    let names: Vec<Option<&String>> = match &**inner_pattern {
+                        Pattern::Identifier(var_name) => vec![Some(var_name)],
+                        Pattern::Tuple(fields) => fields
+                            .iter()
+                            .map(|field| match field {
+                                Pattern::Identifier(var_name) => Some(var_name),
+                                _ => None,
+                            })
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                    let field_types = enum_pattern_field_types(&scrutinee_hir, path, names.len());
+
+                    for (index, (var_name, inner_type)) in names.into_iter().zip(field_types).enumerate() {
+                        let Some(var_name) = var_name else { continue };
+
+                        // Load the payload slot after the tag check has selected this arm
                         let extract_expr = HirExpression::Call {
-                            func: Box::new(HirExpression::Variable("__extract_enum_value".to_string())),
-                            args: vec![scrutinee_hir.clone()],
+                            func: Box::new(HirExpression::Variable("__extract_enum_field".to_string())),
+                            args: vec![scrutinee_hir.clone(), HirExpression::Integer(index as i64)],
                         };
-                        
+
                         arm_body.push(HirStatement::Let {
-                             name: var_name.clone(),
-                             mutable: false,
-                             ty: inner_type.clone(),
-                             init: extract_expr,
+                            name: var_name.clone(),
+                            mutable: false,
+                            ty: inner_type.clone(),
+                            init: extract_expr,
                         });
-                        
+
                         // Add the binding to the scope tracker so it's available for the arm body
                         add_binding(var_name.clone(), inner_type);
-                     }
-                 }
-                
+                    }
+                }
+
                 let arm_body_expr = lower_expression(&arm.body)?;
                 arm_body.push(HirStatement::Expression(arm_body_expr));
                 
@@ -1686,9 +3732,10 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
                 });
             }
             
-            match result_expr {
-                Some(expr) => Ok(expr),
-                None => Err(LowerError {
+            match (result_expr, scrutinee_let) {
+                (Some(expr), None) => Ok(expr),
+                (Some(expr), Some(binding)) => Ok(HirExpression::Block(vec![binding], Some(Box::new(expr)))),
+                (None, _) => Err(LowerError {
                     message: "Match expression with no arms".to_string(),
                 }),
             }
@@ -1705,6 +3752,47 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
             let args_hir: Result<Vec<_>, _> =
                 args.iter().map(|arg| lower_expression(arg)).collect();
             let mut args_final = args_hir?;
+
+            // An `.into()` argument converts to the parameter's type
+            if let Some(param_types) = FUNCTION_PARAMS.with(|registry| registry.borrow().get(name).cloned()) {
+                args_final = args_final
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, arg)| match param_types.get(index) {
+                        Some(ty) => desugar_into(ty, arg),
+                        None => arg,
+                    })
+                    .collect();
+            }
+
+            // `Target::from(value)` calls the impl for the value's type
+            if let (Some(target), [value]) = (name.strip_suffix("::from"), args_final.as_slice()) {
+                if let Some(from) = from_impl_for(target, &infer_hir_type(value)).filter(|from| from != name) {
+                    return Ok(HirExpression::Call {
+                        func: Box::new(HirExpression::Variable(from)),
+                        args: args_final,
+                    });
+                }
+            }
+
+            if let Some(picked) = ordering_call(name, &args_final) {
+                return Ok(picked);
+            }
+
+            // `Point(3, 4)` constructs a tuple struct
+            if tuple_struct_arity(name) == Some(args_final.len()) {
+                return Ok(HirExpression::StructLiteral {
+                    name: name.clone(),
+                    fields: args_final.into_iter().enumerate().map(|(index, arg)| (index.to_string(), arg)).collect(),
+                });
+            }
+
+            if let Some(instance) = instantiate_generic_call(name, &[], &args_final)? {
+                return Ok(HirExpression::Call {
+                    func: Box::new(HirExpression::Variable(instance)),
+                    args: args_final,
+                });
+            }
             
             if name.contains("::") {
                 let parts: Vec<&str> = name.split("::").collect();
@@ -1722,6 +3810,16 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
                 }
             }
             
+            match name.as_str() {
+                "format" => return Ok(formatted_string(args_final, false)),
+                "write" | "writeln" if args_final.len() >= 2 => {
+                    return Ok(append_formatted(args_final, name == "writeln"));
+                }
+                // A String starts out as the empty literal; appends always allocate
+                "String::new" if args_final.is_empty() => return Ok(HirExpression::String(String::new())),
+                _ => {}
+            }
+
             let func_name = match name.as_str() {
                 "__builtin_println" | "println" => {
                     if args_final.len() > 1 {
@@ -1859,11 +3957,12 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
             })
         }
 
-        Expression::FieldAccess { object, field } => {
+        Expression::FieldAccess { object, field, location } => {
             let object_hir = lower_expression(object)?;
             Ok(HirExpression::FieldAccess {
                 object: Box::new(object_hir),
                 field: field.clone(),
+                location: *location,
             })
         }
 
@@ -1918,15 +4017,7 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
             Ok(HirExpression::ArrayLiteral(elements_hir?))
         }
 
-        Expression::Block(block) => {
-            let block_hir = lower_block(block)?;
-            let last_expr = if let Some(e) = &block.expression {
-                Some(Box::new(lower_expression(e)?))
-            } else {
-                None
-            };
-            Ok(HirExpression::Block(block_hir, last_expr))
-        }
+        Expression::Block(block) => lower_block_expression(block),
 
         Expression::Range { start, end, inclusive } => {
             // Desugar range expressions into a special RangeExpression
@@ -1967,15 +4058,32 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
             let body_stmts = lower_block(body)?;
             
             // Check if iter is a simple range
-            if let HirExpression::Range { start: Some(_s), end: Some(e), inclusive } = &iter_expr {
+            if let HirExpression::Range { start: Some(s), end: Some(e), inclusive } = &iter_expr {
                 // Desugar simple range iteration:
                 // let mut var = start;
                 // while var < end { body; var = var + 1; }
+                // The bound is evaluated once, into a preheader temp, so the
+                // body can't change how many times the loop runs
                 let var_name = var.clone();
+                let mut preheader = vec![HirStatement::Let {
+                    name: var_name.clone(),
+                    mutable: true,
+                    ty: infer_hir_type(s),
+                    init: (**s).clone(),
+                }];
+                let end_name = fresh_temp("__for_end");
+                let end_ty = infer_hir_type(e);
+                add_binding(end_name.clone(), end_ty.clone());
+                preheader.push(HirStatement::Let {
+                    name: end_name.clone(),
+                    mutable: false,
+                    ty: end_ty,
+                    init: (**e).clone(),
+                });
                 let condition = HirExpression::BinaryOp {
                     op: if *inclusive { BinaryOp::LessEqual } else { BinaryOp::Less },
                     left: Box::new(HirExpression::Variable(var_name.clone())),
-                    right: e.clone(),
+                    right: Box::new(HirExpression::Variable(end_name)),
                 };
                 
                 let increment = HirExpression::Assign {
@@ -1990,10 +4098,11 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
                 let mut while_body = body_stmts.clone();
                 while_body.push(HirStatement::Expression(increment));
                 
-                Ok(HirExpression::While {
+                preheader.push(HirStatement::Expression(HirExpression::While {
                     condition: Box::new(condition),
                     body: while_body,
-                })
+                }));
+                Ok(HirExpression::Block(preheader, None))
             } else {
                 // For general iterators, we need full iterator support
                 // For now, simplified: assume iteration over collections
@@ -2023,7 +4132,15 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
                 None => HirType::Unknown,
             };
             
-            let lowered_body = lower_expression(body)?;
+            // Parameters shadow outer bindings and are visible to closures
+            // nested in the body, which capture through this one
+            push_scope();
+            for (param_name, param_type) in &typed_params {
+                add_binding(param_name.clone(), param_type.clone());
+            }
+            let lowered_body = lower_expression(body);
+            pop_scope();
+            let lowered_body = lowered_body?;
             
             let mut body_stmts = match lowered_body {
                 HirExpression::Block(stmts, final_expr) => {
@@ -2070,22 +4187,151 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
         // New Expression variants from expanded AST
         Expression::MethodCall { receiver, method, type_args: _, args } => {
             let receiver_hir = lower_expression(receiver)?;
-            let args_hir: Result<Vec<_>, _> = args
+
+            if let (true, [Expression::Closure { params, body, .. }]) = (method == "iter", args.as_slice()) {
+                let is_bencher = match infer_hir_type(&receiver_hir) {
+                    HirType::Reference(inner) | HirType::MutableReference(inner) => *inner == HirType::Named("Bencher".to_string()),
+                    ty => ty == HirType::Named("Bencher".to_string()),
+                };
+                if is_bencher && params.is_empty() {
+                    return lower_expression(&Expression::Block(bencher_iter(receiver, body)));
+                }
+            }
+            let mut args_hir = args
                 .iter()
                 .map(lower_expression)
-                .collect();
-            
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // #[derive(Hash)]: `value.hash()` expands to the field-wise combination
+            if method == "hash" && args_hir.is_empty() {
+                if let Some(hash) = derived_hash(&receiver_hir) {
+                    return Ok(hash);
+                }
+            }
+
+            // #[derive(Clone)]: `value.clone()` copies field by field
+            if method == "clone" && args_hir.is_empty() {
+                if let Some(copy) = derived_clone(&receiver_hir) {
+                    return Ok(copy);
+                }
+            }
+
+            if matches!(method.as_str(), "sort" | "sort_unstable") && args_hir.is_empty() {
+                if let Some(sort) = struct_vec_sort(&receiver_hir) {
+                    return Ok(sort);
+                }
+            }
+
+            // #[derive(PartialEq, Ord)]: `a.eq(&b)` and `a.cmp(&b)` expand in place
+            if let [other] = args_hir.as_slice() {
+                let derived = match method.as_str() {
+                    "eq" => derived_eq(&receiver_hir, other),
+                    "cmp" => derived_cmp(&receiver_hir, other),
+                    _ => None,
+                };
+                if let Some(derived) = derived {
+                    return Ok(derived);
+                }
+                // Integers and strings order by value
+                if method == "cmp" {
+                    let other = match other {
+                        HirExpression::UnaryOp { op: UnaryOp::Reference, operand } => operand.as_ref().clone(),
+                        other => other.clone(),
+                    };
+                    match infer_hir_type(&receiver_hir) {
+                        ty if is_string_type(&ty) => return Ok(runtime_call("gaia_str_cmp", vec![receiver_hir, other])),
+                        HirType::Int32 | HirType::Int64 | HirType::UInt32 | HirType::ISize | HirType::Char => {
+                            return Ok(runtime_call("gaia_cmp", vec![receiver_hir, other]))
+                        }
+                        HirType::UInt64 | HirType::USize => return Ok(runtime_call("gaia_ucmp", vec![receiver_hir, other])),
+                        _ => {}
+                    }
+                }
+            }
+
+            // Raw pointer arithmetic: `p.add(n)` / `p.offset(n)` step by whole elements
+            if let (HirType::Pointer(pointee), "add" | "offset", [count]) =
+                (infer_hir_type(&receiver_hir), method.as_str(), args_hir.as_slice())
+            {
+                return Ok(HirExpression::BinaryOp {
+                    op: BinaryOp::Add,
+                    left: Box::new(receiver_hir),
+                    right: Box::new(HirExpression::BinaryOp {
+                        op: BinaryOp::Multiply,
+                        left: Box::new(count.clone()),
+                        right: Box::new(HirExpression::Integer(pointee_stride(&pointee)?)),
+                    }),
+                });
+            }
+
+            if let Some(lowered) = option_method(&receiver_hir, method, &args_hir)
+                .or_else(|| option_combinator(&receiver_hir, method, &args_hir))
+                .or_else(|| iterator_search(&receiver_hir, method, &args_hir))
+            {
+                return Ok(lowered);
+            }
+
+            // Methods from `impl i64 { ... }` and `impl MyEnum { ... }` are plain
+            // functions taking the receiver
+            let receiver_ty = infer_hir_type(&receiver_hir);
+            if let Some(qualified) = primitive_impl_method(&receiver_ty, method)
+                .or_else(|| enum_impl_method(&receiver_ty, method))
+            {
+                return Ok(HirExpression::Call {
+                    func: Box::new(HirExpression::Variable(qualified)),
+                    args: std::iter::once(receiver_hir).chain(args_hir).collect(),
+                });
+            }
+
+            // A method several traits provide resolves to the one in-scope trait
+            let method = &trait_method_for_call(&receiver_ty, method)?.unwrap_or_else(|| method.clone());
+
+            // Keys of a #[derive(Hash)] type are stored with their derived hash,
+            // and an equal key already in the map stands in for the new one so
+            // the runtime's identity compare finds it. The new key is freed
+            // unless `insert`/`entry` stores it. The map is read twice, so only
+            // place expressions qualify as the receiver.
+            let is_keyed = matches!(method.as_str(), "insert" | "get" | "contains_key" | "contains" | "remove" | "entry");
+            let stores_key = matches!(method.as_str(), "insert" | "entry");
+            let is_place = matches!(receiver_hir, HirExpression::Variable(_) | HirExpression::FieldAccess { .. });
+            if is_keyed && is_place && is_hash_collection(&infer_hir_type(&receiver_hir)) {
+                if let Some(key) = args_hir.first().and_then(derived_key) {
+                    args_hir[0] = runtime_call(
+                        "gaia_hashmap_canonical_key",
+                        vec![receiver_hir.clone(), key, HirExpression::Integer(stores_key as i64)],
+                    );
+                }
+            }
+
+            // `map.entry(key).or_insert(default)` gets a pointer to the value
+            // slot, inserting the default when the key is missing
+            if let (
+                "or_insert",
+                [default],
+                HirExpression::MethodCall { receiver: map, method: entry, args: key },
+            ) = (method.as_str(), args_hir.as_slice(), &receiver_hir)
+            {
+                if entry == "entry" && key.len() == 1 {
+                    return Ok(HirExpression::Call {
+                        func: Box::new(HirExpression::Variable("gaia_hashmap_entry_or_insert".to_string())),
+                        args: vec![(**map).clone(), key[0].clone(), default.clone()],
+                    });
+                }
+            }
+
             Ok(HirExpression::MethodCall {
                 receiver: Box::new(receiver_hir),
                 method: method.clone(),
-                args: args_hir?,
+                args: args_hir,
             })
         }
 
-        Expression::Cast { value: _, ty: _ } => {
-            Err(LowerError {
-                message: "Type casts not yet fully supported".to_string(),
-            })
+        Expression::Cast { value, ty } => {
+            let value_hir = lower_expression(value)?;
+            match fold_cast(&value_hir, ty) {
+                Some(folded) => Ok(folded),
+                None => lower_runtime_cast(value_hir, ty),
+            }
         }
 
         Expression::Try { value } => {
@@ -2098,20 +4344,9 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
         Expression::UnsafeBlock(block) => {
             // PHASE 4.2: Enter unsafe context
             enter_unsafe_context();
-            let block_hir = lower_block(block);
-            let last_expr = if let Some(e) = &block.expression {
-                let expr_result = lower_expression(e);
-                if expr_result.is_ok() {
-                    Some(Box::new(expr_result?))
-                } else {
-                    exit_unsafe_context();
-                    return expr_result;
-                }
-            } else {
-                None
-            };
+            let block_hir = lower_block_expression(block);
             exit_unsafe_context();
-            Ok(HirExpression::Block(block_hir?, last_expr))
+            block_hir
         }
 
         Expression::AsyncBlock(_) => {
@@ -2128,23 +4363,28 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
 
         Expression::Path { segments, is_absolute: _ } => {
             if segments.len() == 2 {
-                let enum_name = &segments[0];
+                let enum_name = &substituted_type_name(&segments[0]).unwrap_or_else(|| segments[0].clone());
                 let variant_name = &segments[1];
                 
-                if get_enum_variant(enum_name, variant_name).is_some() {
+                if let Some(value) = const_in_scope(&format!("{}::{}", enum_name, variant_name)) {
+                    // Associated const (`Type::MAX`, or `T::MAX` in an
+                    // instantiation) or a module's const (`config::N`)
+                    Ok(value)
+                } else if get_enum_variant(enum_name, variant_name).is_some() {
                     Ok(HirExpression::EnumVariant {
                         enum_name: enum_name.clone(),
                         variant_name: variant_name.clone(),
                         args: Vec::new(),
                     })
+                } else if let Some(value) = integer_limit_constant(enum_name, variant_name) {
+                    // Integer limits like i64::MAX are compile-time constants
+                    Ok(HirExpression::Integer(value))
                 } else {
                     Ok(HirExpression::Variable(format!("{}::{}", enum_name, variant_name)))
                 }
-            } else if segments.len() > 1 {
-                let path = segments.join("::");
-                Ok(HirExpression::Variable(path))
             } else {
-                Ok(HirExpression::Variable(segments[0].clone()))
+                let path = segments.join("::");
+                Ok(const_in_scope(&path).unwrap_or(HirExpression::Variable(path)))
             }
         }
 
@@ -2154,12 +4394,31 @@ fn lower_expression(expr: &Expression) -> LowerResult<HirExpression> {
             })
         }
 
-        Expression::GenericCall { name, type_args: _, args } => {
+        Expression::GenericCall { name, type_args, args } => {
+            // `size_of::<T>()` / `align_of::<T>()` (also via `std::mem::`) are
+            // evaluated here, so MIR only ever sees the constant
+            let callee = name.rsplit("::").next().unwrap_or(name);
+            if let ("size_of" | "align_of", [ty], true) = (callee, type_args.as_slice(), args.is_empty()) {
+                let (size, align) = type_layout(&lower_type(ty)?, &mut Vec::new())?;
+                return Ok(HirExpression::Integer(if callee == "size_of" { size } else { align }));
+            }
+
             // Handle generic-style calls like `Type::method(args)`
             // Format: "Type::method" from the parser
             let args_hir: Result<Vec<_>, _> =
                 args.iter().map(|arg| lower_expression(arg)).collect();
             let args_final = args_hir?;
+
+            if let Some(picked) = ordering_call(name, &args_final) {
+                return Ok(picked);
+            }
+
+            if let Some(instance) = instantiate_generic_call(name, type_args, &args_final)? {
+                return Ok(HirExpression::Call {
+                    func: Box::new(HirExpression::Variable(instance)),
+                    args: args_final,
+                });
+            }
             
             // Debug: log GenericCall lowering
             let _ = std::fs::OpenOptions::new().create(true).append(true).open("/tmp/gaiarusted_debug.log")
@@ -2304,6 +4563,26 @@ fn lower_block(block: &Block) -> LowerResult<Vec<HirStatement>> {
     Ok(statements)
 }
 
+/// Lower a block used as a value: its statements run for effect and the
+/// trailing expression (or a trailing `if`/`else`) becomes the block's value
+fn lower_block_expression(block: &Block) -> LowerResult<HirExpression> {
+    let mut statements = lower_statements(&block.statements)?;
+    let last_expr = match &block.expression {
+        Some(e) => Some(Box::new(lower_expression(e)?)),
+        None => match statements.pop() {
+            Some(HirStatement::If { condition, then_body, else_body: Some(else_body) }) => {
+                Some(Box::new(HirExpression::If { condition, then_body, else_body: Some(else_body) }))
+            }
+            Some(other) => {
+                statements.push(other);
+                None
+            }
+            None => None,
+        },
+    };
+    Ok(HirExpression::Block(statements, last_expr))
+}
+
 /// Lower a statement from AST to HIR
 fn lower_statement(stmt: &Statement) -> LowerResult<HirStatement> {
     match stmt {
@@ -2323,6 +4602,7 @@ fn lower_statement(stmt: &Statement) -> LowerResult<HirStatement> {
                 // Try to infer type from initializer expression
                 infer_hir_type(&init_hir)
             };
+            let init_hir = desugar_into(&ty, init_hir);
             add_binding(name.clone(), ty.clone());
             Ok(HirStatement::Let {
                 name: name.clone(),
@@ -2339,7 +4619,11 @@ fn lower_statement(stmt: &Statement) -> LowerResult<HirStatement> {
 
         Statement::Return(expr_opt) => {
             let expr_hir = if let Some(e) = expr_opt {
-                Some(lower_expression(e)?)
+                let value = lower_expression(e)?;
+                Some(match RETURN_TYPE.with(|ty| ty.borrow().clone()) {
+                    Some(ty) => desugar_into(&ty, value),
+                    None => value,
+                })
             } else {
                 None
             };
@@ -2356,6 +4640,11 @@ fn lower_statement(stmt: &Statement) -> LowerResult<HirStatement> {
             body,
         } => {
             let iter_hir = lower_expression(iter)?;
+            if let HirType::Named(type_name) = infer_hir_type(&iter_hir) {
+                if let Some(desugared) = desugar_user_iterator_for(&type_name, var, iter, body) {
+                    return Ok(HirStatement::Expression(HirExpression::Block(lower_statements(&desugared)?, None)));
+                }
+            }
             let body_hir = lower_statements(&body.statements)?;
             Ok(HirStatement::For {
                 var: var.clone(),
@@ -2383,12 +4672,18 @@ fn lower_statement(stmt: &Statement) -> LowerResult<HirStatement> {
         } => {
             let cond_hir = lower_expression(condition)?;
             let then_hir = lower_block(then_body)?;
-            let else_hir = if let Some(else_stmt) = else_body {
-                // else_body is a Statement, which could be another If (for else-if) or a block
-                // We need to convert it to Vec<HirStatement>
-                Some(vec![lower_statement(else_stmt)?])
-            } else {
-                None
+            let else_hir = match else_body.as_deref() {
+                // The parser stores `else { .. }` as an `if true { .. }`, so
+                // lower it as the plain block rather than a branch that is
+                // always taken
+                Some(Statement::If { condition, then_body: else_block, else_body: None })
+                    if matches!(**condition, Expression::Bool(true)) =>
+                {
+                    Some(lower_block(else_block)?)
+                }
+                // Otherwise it's an `else if`
+                Some(else_stmt) => Some(vec![lower_statement(else_stmt)?]),
+                None => None,
             };
             Ok(HirStatement::If {
                 condition: Box::new(cond_hir),
@@ -2593,6 +4888,7 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
               attributes: _,
               where_clause,
               abi: _,
+              location,
           } => {
               // PHASE 4.2: Register unsafe functions
               if *is_unsafe {
@@ -2642,6 +4938,7 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
                           body: vec![],
                           is_public: true,  // Constructors are always accessible
                           where_clause: vec![],
+                          location: None,
                       });
                   }
               }
@@ -2665,14 +4962,26 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
                   register_function_return_type(name.clone(), rt.clone());
               }
 
-              let mut body_hir = lower_block(body)?;
+              let outer_return_type = RETURN_TYPE.with(|ty| ty.replace(ret_type_hir.clone()));
+              // Parameters are in scope so the body can infer their types
+              push_scope();
+              for (param_name, param_ty) in params_hir.iter().flatten() {
+                  add_binding(param_name.clone(), param_ty.clone());
+              }
+              let body_hir = lower_block(body);
+              pop_scope();
+              RETURN_TYPE.with(|ty| *ty.borrow_mut() = outer_return_type);
+              let mut body_hir = body_hir?;
               
               // Handle implicit returns: if the last statement is an expression or if statement,
               // convert it to an explicit return statement
               if !body_hir.is_empty() {
                   match &body_hir[body_hir.len() - 1] {
                       HirStatement::Expression(expr) => {
-                          let expr_clone = expr.clone();
+                          let expr_clone = match &ret_type_hir {
+                              Some(ty) => desugar_into(ty, expr.clone()),
+                              None => expr.clone(),
+                          };
                           // Remove the expression statement and replace with return
                           body_hir.pop();
                           body_hir.push(HirStatement::Return(Some(expr_clone)));
@@ -2700,10 +5009,11 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
                   body: body_hir,
                   is_public: *is_pub,
                   where_clause: where_clause.clone(),
+                  location: *location,
               })
           }
 
-        Item::Struct { name, generics: _, fields, is_pub, attributes, where_clause: _ } => {
+        Item::Struct { name, generics, fields, is_pub, attributes, where_clause: _ } => {
              // Get visibility from registry (registered by parser)
              let visibility = if let Some(vis) = get_visibility(name) {
                  vis
@@ -2730,6 +5040,7 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
 
              Ok(HirItem::Struct {
                  name: name.clone(),
+                 generics: generics.clone(),
                  fields: fields_hir?,
                  derives,
                  is_public: *is_pub,
@@ -2780,6 +5091,7 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
                      return_type,
                      body,
                      is_pub: method_is_pub,
+                     location,
                      ..
                  } = item
                  {
@@ -2807,6 +5119,7 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
                          body: body_hir,
                          is_public: *method_is_pub,
                          where_clause: vec![],
+                         location: *location,
                      });
                  }
              }
@@ -2831,6 +5144,7 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
         Item::Impl {
              generics,
              trait_name,
+             trait_args: _,
              struct_name,
              methods,
              is_unsafe,
@@ -2842,7 +5156,7 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
              let methods_hir: Result<Vec<_>, _> = methods
                  .iter()
                  .filter_map(|item| {
-                     if let Item::Function { name, params, return_type, body, is_pub, .. } = item {
+                     if let Item::Function { name, params, return_type, body, is_pub, location, .. } = item {
                          let qualified_name = format!("{}::{}", struct_name, name);
                          
                          let params_hir: Result<Vec<_>, _> = params
@@ -2874,6 +5188,7 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
                                      body: b,
                                      is_public: *is_pub,
                                      where_clause: vec![],
+                                     location: *location,
                                  })
                              }
                              (Ok(p), None, Ok(b)) => {
@@ -2885,6 +5200,7 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
                                      body: b,
                                      is_public: *is_pub,
                                      where_clause: vec![],
+                                     location: *location,
                                  })
                              }
                              _ => Err(LowerError {
@@ -2977,13 +5293,24 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
             })
         }
 
-        Item::ExternBlock { abi: _, items: _, attributes: _ } => {
-            Ok(HirItem::Struct {
-                derives: Vec::new(),
-                name: "extern".to_string(),
-                fields: vec![(format!("_extern_marker"), HirType::Tuple(vec![]))],
-                is_public: false,
-            })
+        Item::ExternBlock { abi, items, attributes: _ } => {
+            let functions = items
+                .iter()
+                .map(|item| match item {
+                    Item::Function { name, params, return_type, is_pub, location, .. } => Ok(HirItem::Function {
+                        name: name.clone(),
+                        generics: Vec::new(),
+                        params: params.iter().map(|p| (p.name.clone(), convert_type(&p.ty))).collect(),
+                        return_type: return_type.as_ref().map(convert_type),
+                        body: Vec::new(),
+                        is_public: *is_pub,
+                        where_clause: Vec::new(),
+                        location: *location,
+                    }),
+                    _ => Err(LowerError { message: "extern blocks may only declare functions".to_string() }),
+                })
+                .collect::<LowerResult<Vec<_>>>()?;
+            Ok(HirItem::ExternBlock { abi: abi.clone(), functions })
         }
 
         Item::MacroDefinition { name, rules, attributes: _ } => {
@@ -3003,6 +5330,7 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
             Ok(HirItem::Struct {
                 derives: Vec::new(),
                 name: format!("macro_{}", name),
+                generics: Vec::new(),
                 fields: Vec::new(),
                 is_public: false,
             })
@@ -3021,18 +5349,76 @@ fn lower_item(item: &Item) -> LowerResult<HirItem> {
                  is_public: true,  // Associated types in traits are public by default
              })
          }
+
+        Item::AssociatedConst { name, ty, value, attributes: _ } => {
+            let value_hir = match value {
+                Some(v) => Some(lower_expression(v)?),
+                None => None,
+            };
+
+            Ok(HirItem::AssociatedConst {
+                name: name.clone(),
+                ty: lower_type(ty)?,
+                value: value_hir,
+                is_public: true,
+            })
+        }
+    }
+}
+
+/// Copy into every `impl Trait for T` the methods `Trait` gives a default
+/// body that the impl doesn't override. `Self` in their signatures is bound
+/// to `T` when the impl's methods are expanded, like any other impl method.
+///
+/// A trait method declared without a body parses to an empty block, so an
+/// empty default body can't be told apart from none and isn't inherited.
+fn inherit_default_methods(items: &mut [Item]) {
+    let has_body = |method: &&Item| matches!(method, Item::Function { body, .. } if !body.statements.is_empty() || body.expression.is_some());
+    let defaults: HashMap<String, Vec<Item>> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Trait { name, methods, .. } => Some((name.clone(), methods.iter().filter(has_body).cloned().collect())),
+            _ => None,
+        })
+        .collect();
+
+    for item in items.iter_mut() {
+        let Item::Impl { trait_name: Some(trait_name), methods, .. } = item else { continue };
+        let Some(trait_defaults) = defaults.get(trait_name) else { continue };
+        let overridden: Vec<String> = methods
+            .iter()
+            .filter_map(|method| match method {
+                Item::Function { name, .. } => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        methods.extend(
+            trait_defaults
+                .iter()
+                .filter(|method| matches!(method, Item::Function { name, .. } if !overridden.contains(name)))
+                .cloned(),
+        );
     }
 }
 
 /// Lower the entire AST to HIR
 pub fn lower(ast: &[Item]) -> LowerResult<Vec<HirItem>> {
     clear_enum_registry();
+    // `std::cmp::Ordering`, valued like the runtime's three-way comparisons
+    ENUM_REGISTRY.with(|registry| {
+        let variants = [("Less", -1), ("Equal", 0), ("Greater", 1)];
+        registry.borrow_mut().insert(
+            "Ordering".to_string(),
+            variants.iter().map(|&(variant, value)| (variant.to_string(), value)).collect(),
+        );
+    });
     clear_struct_registry();
     clear_function_registry();
     clear_impl_registry();
     clear_scope_tracker();
     // PHASE 4.2: Clear unsafe tracking for fresh lowering
     clear_unsafe_functions();
+    EXTERN_FUNCTIONS.with(|funcs| funcs.borrow_mut().clear());
     UNSAFE_DEPTH.with(|d| { *d.borrow_mut() = 0; });
     // DON'T clear visibility registry here - parser already registered values
     // We'll clear it at the end after lowering is complete
@@ -3076,11 +5462,20 @@ pub fn lower(ast: &[Item]) -> LowerResult<Vec<HirItem>> {
             name: param.name.clone(),
             ty: replace_self_in_type(&param.ty, struct_name),
             mutable: param.mutable,
+            attributes: param.attributes.clone(),
         }
     }
     
+    // Consts first, so array lengths in item signatures can refer to them
+    register_const_values(ast);
+
     // First pass: register enums, structs, and unsafe functions
     let mut all_items = ast.to_vec();
+    inherit_default_methods(&mut all_items);
+    register_associated_consts(&all_items);
+    register_generic_templates(&all_items);
+    register_from_impls(&all_items);
+    let trait_qualified_methods = plan_trait_method_names(&all_items);
     
     for item in ast {
         if let Item::Enum { name, variants, .. } = item {
@@ -3093,18 +5488,64 @@ pub fn lower(ast: &[Item]) -> LowerResult<Vec<HirItem>> {
                 })
                 .collect();
             register_enum_variants(name.clone(), variant_names);
-        } else if let Item::Struct { name, fields, .. } = item {
+
+            let payloads: HashMap<String, Vec<HirType>> = variants
+                .iter()
+                .filter_map(|v| match v {
+                    EnumVariant::Tuple(n, types) => Some((
+                        n.clone(),
+                        types.iter().map(|t| lower_type(t).unwrap_or(HirType::Int64)).collect(),
+                    )),
+                    _ => None,
+                })
+                .collect();
+            if !payloads.is_empty() {
+                register_enum_payloads(name.clone(), payloads);
+            }
+        } else if let Item::Struct { name, fields, attributes, .. } = item {
             // Register struct fields with their types for later type inference
             let field_types: Vec<(String, HirType)> = fields
                 .iter()
                 .map(|f| (f.name.clone(), lower_type(&f.ty).unwrap_or(HirType::Unknown)))
                 .collect();
             register_struct_fields(name.clone(), field_types);
+
+            let derives: Vec<String> = attributes
+                .iter()
+                .filter(|attr| attr.name == "derive")
+                .flat_map(|attr| attr.args.iter().cloned())
+                .collect();
+            register_struct_derives(name.clone(), derives);
+
+            let transparent = attributes
+                .iter()
+                .any(|attr| attr.name == "repr" && attr.args.iter().any(|arg| arg == "transparent"));
+            if transparent {
+                if fields.len() != 1 {
+                    return Err(LowerError {
+                        message: format!(
+                            "transparent struct `{}` needs exactly one field, but has {}",
+                            name,
+                            fields.len()
+                        ),
+                    });
+                }
+                TRANSPARENT_STRUCTS.with(|registry| registry.borrow_mut().insert(name.clone()));
+            }
         } else if let Item::Function { name, is_unsafe, .. } = item {
             // PHASE 4.2: Register unsafe functions before processing bodies
             if *is_unsafe {
                 register_unsafe_function(name.clone());
             }
+        } else if let Item::ExternBlock { items, .. } = item {
+            // Foreign functions are unsafe to call
+            for foreign in items {
+                if let Item::Function { name, return_type, .. } = foreign {
+                    register_unsafe_function(name.clone());
+                    let return_type = return_type.as_ref().map_or(HirType::Tuple(Vec::new()), convert_type);
+                    EXTERN_FUNCTIONS.with(|funcs| funcs.borrow_mut().insert(name.clone(), return_type));
+                }
+            }
         }
     }
     
@@ -3112,7 +5553,7 @@ pub fn lower(ast: &[Item]) -> LowerResult<Vec<HirItem>> {
     // Also register trait impls for operator overloading
     let mut expanded_items = Vec::new();
     for item in &all_items {
-        if let Item::Impl { struct_name, trait_name, methods, .. } = item {
+        if let Item::Impl { struct_name, trait_name, trait_args, methods, .. } = item {
             // Register trait impl methods for operator lookup
             if let Some(trait_name) = trait_name {
                 let method_names: Vec<String> = methods.iter().filter_map(|method| {
@@ -3123,6 +5564,18 @@ pub fn lower(ast: &[Item]) -> LowerResult<Vec<HirItem>> {
                     }
                 }).collect();
                 register_impl(struct_name.clone(), trait_name.clone(), method_names);
+            } else if PRIMITIVE_IMPL_TYPES.contains(&struct_name.as_str()) {
+                let method_names = methods.iter().filter_map(|method| match method {
+                    Item::Function { name, .. } => Some(name.clone()),
+                    _ => None,
+                }).collect();
+                register_primitive_impl(struct_name, method_names);
+            } else if is_enum(struct_name) {
+                let method_names = methods.iter().filter_map(|method| match method {
+                    Item::Function { name, .. } => Some(name.clone()),
+                    _ => None,
+                }).collect();
+                register_enum_impl(struct_name, method_names);
             }
             
             for method in methods {
@@ -3138,9 +5591,24 @@ pub fn lower(ast: &[Item]) -> LowerResult<Vec<HirItem>> {
                     attributes,
                     where_clause,
                     abi,
+                    location,
                 } = method
                 {
-                    let qualified_name = format!("{}::{}", struct_name, name);
+                    let from_source = match (trait_name, trait_args.as_slice()) {
+                        (Some(trait_name), [source]) if short_trait_name(trait_name) == "From" && name == "from" => {
+                            Some(convert_type(source))
+                        }
+                        _ => None,
+                    };
+                    let qualified_name = match (&from_source, trait_name) {
+                        (Some(source), _) => {
+                            from_impl_for(struct_name, source).unwrap_or_else(|| format!("{}::from", struct_name))
+                        }
+                        (None, Some(trait_name)) if trait_qualified_methods.contains(&(struct_name.clone(), trait_name.clone(), name.clone())) => {
+                            format!("{}::{}::{}", struct_name, short_trait_name(trait_name), name)
+                        }
+                        _ => format!("{}::{}", struct_name, name),
+                    };
                     // Replace Self in return type and parameters
                     let new_return_type = return_type.as_ref().map(|rt| replace_self_in_type(rt, struct_name));
                     let new_params: Vec<Parameter> = params.iter().map(|p| replace_self_in_param(p, struct_name)).collect();
@@ -3157,6 +5625,7 @@ pub fn lower(ast: &[Item]) -> LowerResult<Vec<HirItem>> {
                         attributes: attributes.clone(),
                         where_clause: where_clause.clone(),
                         abi: abi.clone(),
+                        location: *location,
                     });
                 }
             }
@@ -3180,6 +5649,7 @@ pub fn lower(ast: &[Item]) -> LowerResult<Vec<HirItem>> {
                                 name: format!("arg{}", i),
                                 ty: ty.clone(),
                                 mutable: false,
+                                attributes: Vec::new(),
                             })
                             .collect();
                         
@@ -3197,6 +5667,7 @@ pub fn lower(ast: &[Item]) -> LowerResult<Vec<HirItem>> {
                             attributes: vec![],
                             where_clause: vec![],
                             abi: None,
+                            location: None,
                         });
                     }
                     EnumVariant::Struct(variant_name, fields) => {
@@ -3207,6 +5678,7 @@ pub fn lower(ast: &[Item]) -> LowerResult<Vec<HirItem>> {
                                 name: field.name.clone(),
                                 ty: field.ty.clone(),
                                 mutable: false,
+                                attributes: Vec::new(),
                             })
                             .collect();
                         
@@ -3224,6 +5696,7 @@ pub fn lower(ast: &[Item]) -> LowerResult<Vec<HirItem>> {
                             attributes: vec![],
                             where_clause: vec![],
                             abi: None,
+                            location: None,
                         });
                     }
                 }
@@ -3231,15 +5704,31 @@ pub fn lower(ast: &[Item]) -> LowerResult<Vec<HirItem>> {
         }
     }
     
-    // Add regular items (non-impl)
+    // Add regular items (non-impl). Generic templates are only lowered
+    // per instantiation.
     for item in &all_items {
-        if !matches!(item, Item::Impl { .. }) {
+        let is_template = matches!(item, Item::Function { name, .. }
+            if GENERIC_TEMPLATES.with(|templates| templates.borrow().contains_key(name)));
+        if !matches!(item, Item::Impl { .. }) && !is_template {
             expanded_items.push(item.clone());
         }
     }
     
+    // Parameter types up front, so calls to functions defined later convert
+    // their `.into()` arguments too
+    FUNCTION_PARAMS.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        registry.clear();
+        for item in &expanded_items {
+            if let Item::Function { name, params, .. } = item {
+                registry.insert(name.clone(), params.iter().map(|param| convert_type(&param.ty)).collect());
+            }
+        }
+    });
+
     // Lower all items
     let mut hir_items: Vec<HirItem> = expanded_items.iter().map(lower_item).collect::<Result<Vec<_>, _>>()?;
+    hir_items.extend(lower_pending_instances()?);
     
     // If this is not the main file, wrap all items in an implicit module
     let file_name = MODULE_PATH.with(|path| {
@@ -3310,4 +5799,45 @@ mod tests {
             Err(e) => assert!(false, "Unexpected error: {}", e),
         }
     }
-}
\ No newline at end of file
+
+    fn lower_main_body(source: &str) -> LowerResult<Vec<HirItem>> {
+        let tokens = crate::lexer::lex(source).expect("lex failed");
+        let ast = crate::parser::parse(tokens).expect("parse failed");
+        set_current_file("main.rs");
+        lower(&ast)
+    }
+
+    #[test]
+    fn test_constant_fold_overflow_is_error_with_checks() {
+        set_overflow_checks(true);
+        let result = lower_main_body("fn main() { let x = i64::MAX + 1; }");
+        set_overflow_checks(false);
+        let err = result.expect_err("i64::MAX + 1 should not fold to i64::MIN");
+        assert!(err.message.contains("overflow"), "unexpected error: {}", err.message);
+    }
+
+    #[test]
+    fn test_constant_fold_min_divided_by_minus_one_is_error_with_checks() {
+        for source in ["fn main() { let x = i64::MIN / -1; }", "fn main() { let x = i64::MIN % -1; }"] {
+            set_overflow_checks(true);
+            let result = lower_main_body(source);
+            set_overflow_checks(false);
+            let err = result.expect_err("i64::MIN / -1 has no i64 result");
+            assert!(err.message.contains("overflow"), "unexpected error: {}", err.message);
+        }
+    }
+
+    #[test]
+    fn test_constant_fold_overflow_left_unfolded_without_checks() {
+        set_overflow_checks(false);
+        let hir = lower_main_body("fn main() { let x = i64::MAX + 1; }").expect("lowering failed");
+        let body = hir.iter().find_map(|item| match item {
+            HirItem::Function { name, body, .. } if name == "main" => Some(body),
+            _ => None,
+        }).expect("main not found");
+        match &body[0] {
+            HirStatement::Let { init: HirExpression::BinaryOp { .. }, .. } => {}
+            other => panic!("expected unfolded addition, got {:?}", other),
+        }
+    }
+}
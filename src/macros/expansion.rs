@@ -1,5 +1,20 @@
-use crate::parser::{Item, Statement, Expression};
-use crate::macros::MacroExpander;
+use std::collections::HashSet;
+
+use crate::lexer::token::{Keyword, Token};
+use crate::parser::{Item, Statement, Expression, Parser};
+use crate::macros::{Delimiter, MacroExpander, TokenTree};
+
+/// Invocations may expand to further invocations at most this deep
+const MAX_EXPANSION_DEPTH: usize = 64;
+
+/// What the tokens at a position parse as, which decides how a macro
+/// invocation there is expanded
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MacroContext {
+    Items,
+    Statements,
+    Expression,
+}
 
 pub struct MacroExpansionPass {
     expander: MacroExpander,
@@ -10,6 +25,172 @@ impl MacroExpansionPass {
         MacroExpansionPass { expander }
     }
 
+    /// Expand the user `macro_rules!` macros of a token stream before it is
+    /// parsed. Definitions are registered with the expander and removed, and
+    /// each invocation is replaced by its expansion: spliced in among items
+    /// or statements, or parenthesized where an expression is expected.
+    pub fn expand_tokens(&mut self, tokens: Vec<Token>) -> Result<Vec<Token>, String> {
+        if !tokens.iter().any(|token| matches!(token, Token::Keyword(Keyword::MacroRules))) {
+            return Ok(tokens);
+        }
+
+        let has_eof = matches!(tokens.last(), Some(Token::Eof));
+        let trees = Parser::new(tokens).parse_token_trees().map_err(|e| e.to_string())?;
+        let mut user_macros = HashSet::new();
+        let trees = self.collect_definitions(trees, &mut user_macros)?;
+        let trees = self.expand_trees(trees, MacroContext::Items, &user_macros, 0)?;
+
+        let mut expanded = Vec::new();
+        flatten(&trees, &mut expanded);
+        if has_eof {
+            expanded.push(Token::Eof);
+        }
+        Ok(expanded)
+    }
+
+    /// Define and remove every `macro_rules! name { ... }` in `trees`
+    fn collect_definitions(&mut self, trees: Vec<TokenTree>, user_macros: &mut HashSet<String>) -> Result<Vec<TokenTree>, String> {
+        let mut remaining = Vec::new();
+        let mut i = 0;
+        while i < trees.len() {
+            if let (
+                TokenTree::Token(Token::Keyword(Keyword::MacroRules)),
+                Some(TokenTree::Token(Token::Bang)),
+                Some(TokenTree::Token(Token::Identifier(_))),
+                Some(TokenTree::Group { .. }),
+            ) = (&trees[i], trees.get(i + 1), trees.get(i + 2), trees.get(i + 3))
+            {
+                let mut definition = Vec::new();
+                flatten(&trees[i..i + 4], &mut definition);
+                let (name, rules) = Parser::new(definition).parse_macro_rules().map_err(|e| e.to_string())?;
+                user_macros.insert(name.clone());
+                self.expander.define(crate::macros::MacroDefinition { name, rules });
+                i += 4;
+                if matches!(trees.get(i), Some(TokenTree::Token(Token::Semicolon))) {
+                    i += 1;
+                }
+                continue;
+            }
+
+            match &trees[i] {
+                TokenTree::Group { delimiter, stream } => remaining.push(TokenTree::Group {
+                    delimiter: *delimiter,
+                    stream: self.collect_definitions(stream.clone(), user_macros)?,
+                }),
+                tree => remaining.push(tree.clone()),
+            }
+            i += 1;
+        }
+        Ok(remaining)
+    }
+
+    /// Replace the invocations of `user_macros` in `trees`, which parse as
+    /// `context`
+    fn expand_trees(
+        &self,
+        trees: Vec<TokenTree>,
+        context: MacroContext,
+        user_macros: &HashSet<String>,
+        depth: usize,
+    ) -> Result<Vec<TokenTree>, String> {
+        let mut expanded: Vec<TokenTree> = Vec::new();
+        // The keyword that introduced the item being read, which tells what
+        // its braces hold
+        let mut item_keyword = None;
+        let mut i = 0;
+        while i < trees.len() {
+            if let (
+                TokenTree::Token(Token::Identifier(name)),
+                Some(TokenTree::Token(Token::Bang)),
+                Some(TokenTree::Group { delimiter, stream }),
+            ) = (&trees[i], trees.get(i + 1), trees.get(i + 2))
+            {
+                if user_macros.contains(name) {
+                    if depth >= MAX_EXPANSION_DEPTH {
+                        return Err(format!("recursion limit reached while expanding `{}!`", name));
+                    }
+                    let body = self.expander.expand(name, stream.clone())?;
+                    i += 3;
+                    let terminated = matches!(trees.get(i), Some(TokenTree::Token(Token::Semicolon)));
+                    let statement_start = matches!(
+                        expanded.last(),
+                        None | Some(TokenTree::Token(Token::Semicolon) | TokenTree::Group { delimiter: Delimiter::Brace, .. })
+                    );
+                    let position = match context {
+                        MacroContext::Items => MacroContext::Items,
+                        MacroContext::Statements
+                            if statement_start && (terminated || i == trees.len() || *delimiter == Delimiter::Brace) =>
+                        {
+                            MacroContext::Statements
+                        }
+                        _ => MacroContext::Expression,
+                    };
+                    let body = self.expand_trees(body, position, user_macros, depth + 1)?;
+                    match position {
+                        MacroContext::Items => {
+                            // `name!(...);` leaves no stray `;` between items
+                            if terminated {
+                                i += 1;
+                            }
+                        }
+                        MacroContext::Statements => {
+                            // The `;` of `name!(...);` is dropped where the expansion
+                            // already ends its last statement or item
+                            let ends_statement = matches!(body.last(), None | Some(TokenTree::Token(Token::Semicolon)));
+                            if terminated && (ends_statement || is_item(&body)) {
+                                i += 1;
+                            }
+                        }
+                        MacroContext::Expression => {
+                            expanded.push(TokenTree::Group { delimiter: Delimiter::Paren, stream: body });
+                            continue;
+                        }
+                    }
+                    expanded.extend(body);
+                    continue;
+                }
+            }
+
+            match &trees[i] {
+                TokenTree::Token(Token::Keyword(keyword)) if context == MacroContext::Items => {
+                    let starts_item = matches!(
+                        keyword,
+                        Keyword::Fn | Keyword::Mod | Keyword::Impl | Keyword::Trait | Keyword::Extern
+                            | Keyword::Struct | Keyword::Enum | Keyword::Const | Keyword::Static
+                            | Keyword::Type | Keyword::Use
+                    );
+                    // `extern "C" fn` is a function, but `fn f() -> impl T` is not an impl
+                    if starts_item && (item_keyword.is_none() || (item_keyword == Some(Keyword::Extern) && *keyword == Keyword::Fn)) {
+                        item_keyword = Some(*keyword);
+                    }
+                    expanded.push(trees[i].clone());
+                }
+                TokenTree::Token(Token::Semicolon) => {
+                    item_keyword = None;
+                    expanded.push(trees[i].clone());
+                }
+                TokenTree::Group { delimiter, stream } => {
+                    let inner = match (delimiter, context) {
+                        (Delimiter::Brace, MacroContext::Items) => match item_keyword.take() {
+                            Some(Keyword::Fn) => MacroContext::Statements,
+                            Some(Keyword::Mod | Keyword::Impl | Keyword::Trait | Keyword::Extern) => MacroContext::Items,
+                            _ => MacroContext::Expression,
+                        },
+                        (Delimiter::Brace, _) => MacroContext::Statements,
+                        _ => MacroContext::Expression,
+                    };
+                    expanded.push(TokenTree::Group {
+                        delimiter: *delimiter,
+                        stream: self.expand_trees(stream.clone(), inner, user_macros, depth)?,
+                    });
+                }
+                tree => expanded.push(tree.clone()),
+            }
+            i += 1;
+        }
+        Ok(expanded)
+    }
+
     pub fn expand_items(&mut self, items: &[Item]) -> Result<Vec<Item>, String> {
         let mut expanded = Vec::new();
         for item in items {
@@ -24,12 +205,7 @@ impl MacroExpansionPass {
             Item::MacroDefinition { name, rules, .. } => {
                 let macro_def = crate::macros::MacroDefinition {
                     name: name.clone(),
-                    rules: rules.iter().map(|_r| {
-                        crate::macros::MacroRule {
-                            pattern: vec![],
-                            body: vec![],
-                        }
-                    }).collect(),
+                    rules: rules.iter().filter_map(|rule| rule.actual_rule.as_deref().cloned()).collect(),
                 };
                 self.expander.define(macro_def);
                 Ok(vec![])
@@ -46,6 +222,7 @@ impl MacroExpansionPass {
                 is_pub,
                 where_clause,
                 abi,
+                location,
             } => {
                 let expanded_body = self.expand_block(body)?;
                 Ok(vec![Item::Function {
@@ -60,6 +237,7 @@ impl MacroExpansionPass {
                     is_pub: *is_pub,
                     where_clause: where_clause.clone(),
                     abi: None,
+                    location: *location,
                 }])
             }
             Item::Struct {
@@ -373,3 +551,42 @@ impl MacroExpansionPass {
         Ok(vec![Statement::Expression(dbg_call)])
     }
 }
+
+/// Whether `trees` start with an item declaration
+fn is_item(trees: &[TokenTree]) -> bool {
+    let mut keywords = trees.iter().map_while(|tree| match tree {
+        TokenTree::Token(Token::Keyword(keyword)) => Some(*keyword),
+        _ => None,
+    });
+    let first = keywords.by_ref().find(|keyword| !matches!(keyword, Keyword::Pub | Keyword::Unsafe));
+    matches!(
+        first,
+        Some(Keyword::Fn | Keyword::Struct | Keyword::Enum | Keyword::Impl | Keyword::Trait | Keyword::Mod)
+    )
+}
+
+fn closing_token(delimiter: Delimiter) -> Token {
+    match delimiter {
+        Delimiter::Paren => Token::RightParen,
+        Delimiter::Brace => Token::RightBrace,
+        Delimiter::Bracket => Token::RightBracket,
+    }
+}
+
+/// Turn token trees back into the token stream they were built from
+fn flatten(trees: &[TokenTree], out: &mut Vec<Token>) {
+    for tree in trees {
+        match tree {
+            TokenTree::Token(token) => out.push(token.clone()),
+            TokenTree::Group { delimiter, stream } => {
+                out.push(match delimiter {
+                    Delimiter::Paren => Token::LeftParen,
+                    Delimiter::Brace => Token::LeftBrace,
+                    Delimiter::Bracket => Token::LeftBracket,
+                });
+                flatten(stream, out);
+                out.push(closing_token(*delimiter));
+            }
+        }
+    }
+}
@@ -29,7 +29,7 @@ pub enum MacroPattern {
         patterns: Vec<MacroPattern>,
     },
     Repetition {
-        pattern: Box<MacroPattern>,
+        patterns: Vec<MacroPattern>,
         separator: Option<Box<Token>>,
         kind: RepetitionKind,
     },
@@ -110,135 +110,101 @@ impl MacroExpander {
         Err(format!("No matching rule for macro '{}'", name))
     }
 
-    fn match_pattern(
-        &self,
-        pattern: &[MacroPattern],
-        input: &[TokenTree],
-    ) -> Option<HashMap<String, Vec<TokenTree>>> {
-        let mut bindings = HashMap::new();
-        if self.match_patterns(pattern, input, 0, 0, &mut bindings) {
-            Some(bindings)
-        } else {
-            None
-        }
+    fn match_pattern(&self, pattern: &[MacroPattern], input: &[TokenTree]) -> Option<Bindings> {
+        let mut bindings = Bindings::new();
+        (self.match_patterns(pattern, input, 0, None, &mut bindings)? == input.len()).then_some(bindings)
     }
 
+    /// Match `patterns` against `input` from `position`, returning the
+    /// position just past the match. `follow` is the literal token expected
+    /// after the sequence, which ends greedy fragments such as `expr`.
     fn match_patterns(
         &self,
         patterns: &[MacroPattern],
         input: &[TokenTree],
-        pat_idx: usize,
-        input_idx: usize,
-        bindings: &mut HashMap<String, Vec<TokenTree>>,
-    ) -> bool {
-        if pat_idx == patterns.len() {
-            return input_idx == input.len();
-        }
-
-        let pattern = &patterns[pat_idx];
-        match pattern {
-            MacroPattern::Token(expected) => {
-                if input_idx >= input.len() {
-                    return false;
-                }
-                if let TokenTree::Token(actual) = &input[input_idx] {
-                    if self.tokens_match(expected, actual) {
-                        return self.match_patterns(patterns, input, pat_idx + 1, input_idx + 1, bindings);
+        mut position: usize,
+        follow: Option<&Token>,
+        bindings: &mut Bindings,
+    ) -> Option<usize> {
+        for (index, pattern) in patterns.iter().enumerate() {
+            let next = match patterns.get(index + 1) {
+                Some(MacroPattern::Token(token)) => Some(token),
+                Some(_) => None,
+                None => follow,
+            };
+            match pattern {
+                MacroPattern::Token(expected) => match input.get(position) {
+                    Some(TokenTree::Token(actual)) if self.tokens_match(expected, actual) => position += 1,
+                    _ => return None,
+                },
+                MacroPattern::MetaVar { name, kind } => {
+                    let len = fragment_len(*kind, &input[position..], next)?;
+                    let mut trees = input[position..position + len].to_vec();
+                    // An expression stays one operand wherever it is substituted
+                    if *kind == MetaVarKind::Expr && len > 1 {
+                        trees = vec![TokenTree::Group { delimiter: Delimiter::Paren, stream: trees }];
                     }
+                    bindings.insert(name.clone(), MacroBinding::Single(trees));
+                    position += len;
                 }
-                false
-            }
-            MacroPattern::MetaVar { name, kind: _ } => {
-                if input_idx >= input.len() {
-                    return false;
-                }
-
-                let matched_tree = input[input_idx].clone();
-                bindings
-                    .entry(name.clone())
-                    .or_insert_with(Vec::new)
-                    .push(matched_tree);
-
-                self.match_patterns(patterns, input, pat_idx + 1, input_idx + 1, bindings)
-            }
-            MacroPattern::Group {
-                delimiter: _,
-                patterns: group_patterns,
-            } => {
-                if input_idx >= input.len() {
-                    return false;
-                }
-                if let TokenTree::Group {
-                    delimiter: _,
-                    stream,
-                } = &input[input_idx]
-                {
-                    let mut group_bindings = HashMap::new();
-                    if self.match_patterns(group_patterns, stream, 0, 0, &mut group_bindings) {
-                        for (key, value) in group_bindings {
-                            bindings
-                                .entry(key)
-                                .or_insert_with(Vec::new)
-                                .extend(value);
+                MacroPattern::Group { delimiter, patterns: inner } => match input.get(position) {
+                    Some(TokenTree::Group { delimiter: actual, stream }) if actual == delimiter => {
+                        if self.match_patterns(inner, stream, 0, None, bindings)? != stream.len() {
+                            return None;
                         }
-                        return self.match_patterns(patterns, input, pat_idx + 1, input_idx + 1, bindings);
+                        position += 1;
                     }
-                }
-                false
-            }
-            MacroPattern::Repetition {
-                pattern: rep_pattern,
-                separator: _,
-                kind,
-            } => {
-                let min_matches = match kind {
-                    RepetitionKind::ZeroOrMore => 0,
-                    RepetitionKind::OneOrMore => 1,
-                    RepetitionKind::ZeroOrOne => 0,
-                };
-
-                let max_matches = match kind {
-                    RepetitionKind::ZeroOrMore => usize::MAX,
-                    RepetitionKind::OneOrMore => usize::MAX,
-                    RepetitionKind::ZeroOrOne => 1,
-                };
-
-                let mut matched = 0;
-                let mut current_idx = input_idx;
-
-                while matched < max_matches && current_idx < input.len() {
-                    let mut rep_bindings = HashMap::new();
-                    if self.match_patterns(&[(**rep_pattern).clone()], input, 0, current_idx, &mut rep_bindings) {
-                        for (key, value) in rep_bindings {
-                            bindings
-                                .entry(key)
-                                .or_insert_with(Vec::new)
-                                .extend(value);
+                    _ => return None,
+                },
+                MacroPattern::Repetition { patterns: inner, separator, kind } => {
+                    let separator = separator.as_deref();
+                    let inner_follow = separator.or(next);
+                    let mut iterations: Vec<Bindings> = Vec::new();
+                    loop {
+                        if *kind == RepetitionKind::ZeroOrOne && iterations.len() == 1 {
+                            break;
+                        }
+                        let mut start = position;
+                        if let (false, Some(separator)) = (iterations.is_empty(), separator) {
+                            match input.get(position) {
+                                Some(TokenTree::Token(token)) if self.tokens_match(separator, token) => start += 1,
+                                _ => break,
+                            }
+                        }
+                        let mut iteration = Bindings::new();
+                        match self.match_patterns(inner, input, start, inner_follow, &mut iteration) {
+                            Some(end) if end > start => {
+                                iterations.push(iteration);
+                                position = end;
+                            }
+                            _ => break,
                         }
-                        matched += 1;
-                        current_idx += 1;
-                    } else {
-                        break;
                     }
-                }
-
-                if matched >= min_matches {
-                    self.match_patterns(patterns, input, pat_idx + 1, current_idx, bindings)
-                } else {
-                    false
-                }
-            }
-            MacroPattern::Or(alternatives) => {
-                for alt in alternatives {
-                    let mut alt_bindings = bindings.clone();
-                    if self.match_patterns(alt, input, 0, input_idx, &mut alt_bindings) {
-                        *bindings = alt_bindings;
-                        return self.match_patterns(patterns, input, pat_idx + 1, input_idx, bindings);
+                    if *kind == RepetitionKind::OneOrMore && iterations.is_empty() {
+                        return None;
                     }
+                    let mut names = Vec::new();
+                    metavar_names(inner, &mut names);
+                    for name in names {
+                        let entries = iterations
+                            .iter_mut()
+                            .map(|iteration| iteration.remove(&name).unwrap_or(MacroBinding::Repeated(Vec::new())))
+                            .collect();
+                        bindings.insert(name, MacroBinding::Repeated(entries));
+                    }
+                }
+                MacroPattern::Or(alternatives) => {
+                    let (end, alternative) = alternatives.iter().find_map(|alternative| {
+                        let mut alternative_bindings = Bindings::new();
+                        let end = self.match_patterns(alternative, input, position, next, &mut alternative_bindings)?;
+                        Some((end, alternative_bindings))
+                    })?;
+                    bindings.extend(alternative);
+                    position = end;
                 }
-                false
             }
         }
+        Some(position)
     }
 
     fn tokens_match(&self, expected: &Token, actual: &Token) -> bool {
@@ -288,24 +254,16 @@ impl MacroExpander {
             | (Token::At, Token::At)
             | (Token::Dollar, Token::Dollar)
             | (Token::Hash, Token::Hash) => true,
-            _ => false,
+            _ => expected == actual,
         }
     }
 
-    fn substitute(
-        &self,
-        body: &[TokenTree],
-        bindings: &HashMap<String, Vec<TokenTree>>,
-    ) -> Result<Vec<TokenTree>, String> {
+
+    fn substitute(&self, body: &[TokenTree], bindings: &Bindings) -> Result<Vec<TokenTree>, String> {
         self.substitute_internal(body, bindings, 0)
     }
 
-    fn substitute_internal(
-        &self,
-        body: &[TokenTree],
-        bindings: &HashMap<String, Vec<TokenTree>>,
-        depth: usize,
-    ) -> Result<Vec<TokenTree>, String> {
+    fn substitute_internal(&self, body: &[TokenTree], bindings: &Bindings, depth: usize) -> Result<Vec<TokenTree>, String> {
         if depth > 100 {
             return Err("Macro recursion depth exceeded".to_string());
         }
@@ -314,45 +272,33 @@ impl MacroExpander {
         let mut i = 0;
 
         while i < body.len() {
-            match &body[i] {
-                TokenTree::Token(Token::Dollar) => {
-                    if i + 1 < body.len() {
-                        if let TokenTree::Token(Token::Identifier(name)) = &body[i + 1] {
-                            if let Some(replacement) = bindings.get(name) {
-                                result.extend(replacement.clone());
-                                i += 2;
-                            } else {
-                                return Err(format!("Undefined meta variable: ${}", name));
-                            }
-                        } else if let TokenTree::Group {
-                            delimiter: Delimiter::Paren,
-                            stream,
-                        } = &body[i + 1]
-                        {
-                            let (expanded, _consumed) = 
-                                self.substitute_repetition(stream, bindings, depth + 1)?;
-                            result.extend(expanded);
-                            i += 2;
-                        } else {
-                            return Err("Invalid $ usage in macro body".to_string());
-                        }
-                    } else {
-                        return Err("Trailing $ in macro body".to_string());
+            if let Some((name, width)) = metavar_at(body, i) {
+                match bindings.get(name) {
+                    Some(MacroBinding::Single(trees)) => result.extend(trees.iter().cloned()),
+                    Some(MacroBinding::Repeated(_)) => {
+                        return Err(format!("Meta variable ${} is still repeating at this depth", name));
                     }
+                    None => return Err(format!("Undefined meta variable: ${}", name)),
+                }
+                i += width;
+                continue;
+            }
+            match (&body[i], body.get(i + 1)) {
+                (TokenTree::Token(Token::Dollar), Some(TokenTree::Group { delimiter: Delimiter::Paren, stream })) => {
+                    let (separator, next) = repetition_suffix(body, i + 2)?;
+                    result.extend(self.substitute_repetition(stream, separator, bindings, depth + 1)?);
+                    i = next;
                 }
-                TokenTree::Group {
-                    delimiter,
-                    stream,
-                } => {
-                    let substituted = self.substitute_internal(stream, bindings, depth + 1)?;
+                (TokenTree::Token(Token::Dollar), _) => return Err("Invalid $ usage in macro body".to_string()),
+                (TokenTree::Group { delimiter, stream }, _) => {
                     result.push(TokenTree::Group {
                         delimiter: *delimiter,
-                        stream: substituted,
+                        stream: self.substitute_internal(stream, bindings, depth + 1)?,
                     });
                     i += 1;
                 }
-                _ => {
-                    result.push(body[i].clone());
+                (tree, _) => {
+                    result.push(tree.clone());
                     i += 1;
                 }
             }
@@ -361,42 +307,155 @@ impl MacroExpander {
         Ok(result)
     }
 
+    /// Transcribe `$(stream) separator op` once per iteration of the
+    /// repeating variables it uses
     fn substitute_repetition(
         &self,
         stream: &[TokenTree],
-        bindings: &HashMap<String, Vec<TokenTree>>,
+        separator: Option<&Token>,
+        bindings: &Bindings,
         depth: usize,
-    ) -> Result<(Vec<TokenTree>, usize), String> {
+    ) -> Result<Vec<TokenTree>, String> {
+        let mut names = Vec::new();
+        body_metavars(stream, &mut names);
+
+        let mut count = None;
+        for name in &names {
+            if let Some(MacroBinding::Repeated(entries)) = bindings.get(name) {
+                if count.is_some_and(|count| count != entries.len()) {
+                    return Err(format!("Meta variable ${} repeats a different number of times", name));
+                }
+                count = Some(entries.len());
+            }
+        }
+        let count = count.ok_or_else(|| "Repetition in macro body uses no repeating meta variables".to_string())?;
+
         let mut result = Vec::new();
+        for iteration in 0..count {
+            if let (true, Some(separator)) = (iteration > 0, separator) {
+                result.push(TokenTree::Token(separator.clone()));
+            }
+            let mut iteration_bindings = bindings.clone();
+            for name in &names {
+                if let Some(MacroBinding::Repeated(entries)) = bindings.get(name) {
+                    iteration_bindings.insert(name.clone(), entries[iteration].clone());
+                }
+            }
+            result.extend(self.substitute_internal(stream, &iteration_bindings, depth)?);
+        }
+        Ok(result)
+    }
+}
 
-        if stream.len() < 3 {
-            return Err("Invalid repetition syntax".to_string());
+/// What a meta variable matched: the trees of one fragment, or one binding
+/// per iteration of the repetition it appears in
+#[derive(Debug, Clone)]
+enum MacroBinding {
+    Single(Vec<TokenTree>),
+    Repeated(Vec<MacroBinding>),
+}
+
+type Bindings = HashMap<String, MacroBinding>;
+
+fn metavar_names(patterns: &[MacroPattern], names: &mut Vec<String>) {
+    for pattern in patterns {
+        match pattern {
+            MacroPattern::MetaVar { name, .. } => names.push(name.clone()),
+            MacroPattern::Group { patterns, .. } | MacroPattern::Repetition { patterns, .. } => {
+                metavar_names(patterns, names)
+            }
+            MacroPattern::Or(alternatives) => alternatives.iter().for_each(|alternative| metavar_names(alternative, names)),
+            MacroPattern::Token(_) => {}
         }
+    }
+}
 
-        let pattern = &stream[0..stream.len() - 1];
-        let kind_token = &stream[stream.len() - 1];
+/// The meta variable named at `body[i]`, written `$name` as one token or as
+/// `$` followed by an identifier, with the number of trees it spans
+fn metavar_at(body: &[TokenTree], i: usize) -> Option<(&str, usize)> {
+    match (&body[i], body.get(i + 1)) {
+        (TokenTree::Token(Token::Metavariable(name)), _) => Some((name, 1)),
+        (TokenTree::Token(Token::Dollar), Some(TokenTree::Token(Token::Identifier(name)))) => Some((name, 2)),
+        _ => None,
+    }
+}
 
-        let repetition_kind = match kind_token {
-            TokenTree::Token(Token::Star) => RepetitionKind::ZeroOrMore,
-            TokenTree::Token(Token::Plus) => RepetitionKind::OneOrMore,
-            TokenTree::Token(Token::Question) => RepetitionKind::ZeroOrOne,
-            _ => return Err("Invalid repetition kind".to_string()),
-        };
+/// Names of the meta variables used anywhere in a macro body
+fn body_metavars(body: &[TokenTree], names: &mut Vec<String>) {
+    for i in 0..body.len() {
+        if let Some((name, _)) = metavar_at(body, i) {
+            names.push(name.to_string());
+        } else if let TokenTree::Group { stream, .. } = &body[i] {
+            body_metavars(stream, names);
+        }
+    }
+}
 
-        match repetition_kind {
-            RepetitionKind::ZeroOrMore | RepetitionKind::OneOrMore | RepetitionKind::ZeroOrOne => {
-                let count = bindings.values().next()
-                    .map(|v| v.len())
-                    .unwrap_or(0);
+fn repetition_kind(tree: Option<&TokenTree>) -> Option<RepetitionKind> {
+    match tree {
+        Some(TokenTree::Token(Token::Star)) => Some(RepetitionKind::ZeroOrMore),
+        Some(TokenTree::Token(Token::Plus)) => Some(RepetitionKind::OneOrMore),
+        Some(TokenTree::Token(Token::Question)) => Some(RepetitionKind::ZeroOrOne),
+        _ => None,
+    }
+}
 
-                for _ in 0..count {
-                    let expanded = self.substitute_internal(pattern, bindings, depth + 1)?;
-                    result.extend(expanded);
+/// The separator and operator after a `$(...)` in a macro body starting at
+/// `body[i]`, with the index just past the operator
+fn repetition_suffix(body: &[TokenTree], i: usize) -> Result<(Option<&Token>, usize), String> {
+    if repetition_kind(body.get(i)).is_some() {
+        return Ok((None, i + 1));
+    }
+    match (body.get(i), repetition_kind(body.get(i + 1))) {
+        (Some(TokenTree::Token(separator)), Some(_)) => Ok((Some(separator), i + 2)),
+        _ => Err("Expected one of `*`, `+` or `?` after `$(...)`".to_string()),
+    }
+}
+
+/// How many trees at the start of `input` a fragment of `kind` takes, or
+/// `None` if it can't match there
+fn fragment_len(kind: MetaVarKind, input: &[TokenTree], follow: Option<&Token>) -> Option<usize> {
+    let first = input.first()?;
+    match kind {
+        MetaVarKind::Tt => Some(1),
+        MetaVarKind::Ident => matches!(first, TokenTree::Token(Token::Identifier(_))).then_some(1),
+        MetaVarKind::Lifetime => matches!(first, TokenTree::Token(Token::Lifetime(_))).then_some(1),
+        MetaVarKind::Block => matches!(first, TokenTree::Group { delimiter: Delimiter::Brace, .. }).then_some(1),
+        MetaVarKind::Lit => match first {
+            TokenTree::Token(
+                Token::Integer(..)
+                | Token::Float(..)
+                | Token::String(_)
+                | Token::Char(_)
+                | Token::Keyword(Keyword::True)
+                | Token::Keyword(Keyword::False),
+            ) => Some(1),
+            _ => None,
+        },
+        MetaVarKind::Expr | MetaVarKind::Ty | MetaVarKind::Path | MetaVarKind::Pat | MetaVarKind::Stmt | MetaVarKind::Meta => {
+            // Types and paths can hold commas between angle brackets
+            let tracks_angles = matches!(kind, MetaVarKind::Ty | MetaVarKind::Path);
+            let mut angle_depth = 0usize;
+            let mut len = 0;
+            for tree in input {
+                if let TokenTree::Token(token) = tree {
+                    let ends = matches!(token, Token::Comma | Token::Semicolon | Token::FatArrow) || Some(token) == follow;
+                    if ends && angle_depth == 0 {
+                        break;
+                    }
+                    if tracks_angles {
+                        match token {
+                            Token::Less => angle_depth += 1,
+                            Token::Greater => angle_depth = angle_depth.saturating_sub(1),
+                            Token::RightShift => angle_depth = angle_depth.saturating_sub(2),
+                            _ => {}
+                        }
+                    }
                 }
+                len += 1;
             }
+            (len > 0).then_some(len)
         }
-
-        Ok((result, 1))
     }
 }
 
@@ -7,7 +7,7 @@ use crate::macros::{
 impl Parser {
     pub fn parse_macro_rules(&mut self) -> ParseResult<(String, Vec<MacroRule>)> {
         self.expect_keyword(Keyword::MacroRules)?;
-        
+
         // Check for Bang token explicitly instead of using consume
         if !self.check(&Token::Bang) {
             return Err(ParseError::InvalidSyntax("Expected ! after macro_rules".to_string()));
@@ -22,7 +22,7 @@ impl Parser {
         while !self.check(&Token::RightBrace) {
             let pattern = self.parse_macro_pattern()?;
             self.consume("=>")?;
-            let body = self.parse_token_tree_vec()?;
+            let body = self.parse_macro_body()?;
             rules.push(MacroRule { pattern, body });
 
             if !self.check(&Token::RightBrace) {
@@ -34,156 +34,124 @@ impl Parser {
         Ok((name, rules))
     }
 
+    /// Parse the delimited matcher of a rule, returning the patterns inside it
     fn parse_macro_pattern(&mut self) -> ParseResult<Vec<MacroPattern>> {
-        let mut patterns = Vec::new();
+        let closing = match self.current() {
+            Token::LeftParen => Token::RightParen,
+            Token::LeftBrace => Token::RightBrace,
+            Token::LeftBracket => Token::RightBracket,
+            _ => return Err(ParseError::InvalidSyntax("Expected macro pattern delimiters".to_string())),
+        };
+        self.advance();
+        self.parse_macro_patterns_until(&closing)
+    }
 
-        match self.current() {
-            Token::LeftParen => {
-                self.advance();
-                while !self.check(&Token::RightParen) {
-                    patterns.push(self.parse_macro_pattern_element()?);
-                    if self.check(&Token::Comma) {
-                        self.advance();
-                    }
-                }
-                self.consume(")")?;
-            }
-            Token::LeftBrace => {
-                self.advance();
-                while !self.check(&Token::RightBrace) {
-                    patterns.push(self.parse_macro_pattern_element()?);
-                    if self.check(&Token::Comma) {
-                        self.advance();
-                    }
-                }
-                self.consume("}")?;
-            }
-            Token::LeftBracket => {
-                self.advance();
-                while !self.check(&Token::RightBracket) {
-                    patterns.push(self.parse_macro_pattern_element()?);
-                    if self.check(&Token::Comma) {
-                        self.advance();
-                    }
-                }
-                self.consume("]")?;
+    /// Parse pattern elements up to and including `closing`
+    fn parse_macro_patterns_until(&mut self, closing: &Token) -> ParseResult<Vec<MacroPattern>> {
+        let mut patterns = Vec::new();
+        while self.current() != closing {
+            if self.current() == &Token::Eof {
+                return Err(ParseError::InvalidSyntax(format!("Expected `{}` to close macro pattern", closing)));
             }
-            _ => return Err(ParseError::InvalidSyntax("Expected macro pattern delimiters".to_string())),
+            patterns.push(self.parse_macro_pattern_element()?);
         }
-
+        self.advance();
         Ok(patterns)
     }
 
     fn parse_macro_pattern_element(&mut self) -> ParseResult<MacroPattern> {
-        match self.current() {
+        match self.current().clone() {
+            Token::Metavariable(name) => {
+                self.advance();
+                self.consume(":")?;
+                let kind_str = self.expect_identifier()?;
+                let kind = match kind_str.as_str() {
+                    "expr" => MetaVarKind::Expr,
+                    "ident" => MetaVarKind::Ident,
+                    "ty" => MetaVarKind::Ty,
+                    "path" => MetaVarKind::Path,
+                    "block" => MetaVarKind::Block,
+                    "stmt" => MetaVarKind::Stmt,
+                    "pat" => MetaVarKind::Pat,
+                    "lit" => MetaVarKind::Lit,
+                    "lifetime" => MetaVarKind::Lifetime,
+                    "meta" => MetaVarKind::Meta,
+                    "tt" => MetaVarKind::Tt,
+                    _ => return Err(ParseError::InvalidSyntax(format!("Unknown meta-var kind: {}", kind_str))),
+                };
+                Ok(MacroPattern::MetaVar { name, kind })
+            }
             Token::Dollar => {
                 self.advance();
-                if self.check(&Token::LeftParen) {
-                    self.advance();
-                    let pattern = Box::new(self.parse_macro_pattern_element()?);
-                    let separator = if self.check(&Token::Comma) || self.check(&Token::Semicolon) {
-                        let sep_token = self.current().clone();
-                        self.advance();
-                        Some(Box::new(sep_token))
-                    } else {
-                        None
-                    };
-
-                    let kind = if self.check(&Token::Star) {
-                        self.advance();
-                        RepetitionKind::ZeroOrMore
-                    } else if self.check(&Token::Plus) {
-                        self.advance();
-                        RepetitionKind::OneOrMore
-                    } else if self.check(&Token::Question) {
-                        self.advance();
-                        RepetitionKind::ZeroOrOne
-                    } else {
-                        return Err(ParseError::InvalidSyntax("Expected *, +, or ?".to_string()));
-                    };
-
-                    self.consume(")")?;
-                    Ok(MacroPattern::Repetition {
-                        pattern,
-                        separator,
-                        kind,
-                    })
-                } else if self.check(&Token::Identifier(String::new())) {
-                    let name = self.expect_identifier()?;
-                    self.consume(":")?;
-                    let kind_str = self.expect_identifier()?;
-                    let kind = match kind_str.as_str() {
-                        "expr" => MetaVarKind::Expr,
-                        "ident" => MetaVarKind::Ident,
-                        "ty" => MetaVarKind::Ty,
-                        "path" => MetaVarKind::Path,
-                        "block" => MetaVarKind::Block,
-                        "stmt" => MetaVarKind::Stmt,
-                        "pat" => MetaVarKind::Pat,
-                        "lit" => MetaVarKind::Lit,
-                        "lifetime" => MetaVarKind::Lifetime,
-                        "meta" => MetaVarKind::Meta,
-                        "tt" => MetaVarKind::Tt,
-                        _ => return Err(ParseError::InvalidSyntax(format!("Unknown meta-var kind: {}", kind_str))),
-                    };
-                    Ok(MacroPattern::MetaVar { name, kind })
-                } else {
-                    Err(ParseError::InvalidSyntax("Expected identifier after $".to_string()))
+                if !self.check(&Token::LeftParen) {
+                    return Err(ParseError::InvalidSyntax("Expected `$name:kind` or `$(...)` in macro pattern".to_string()));
                 }
-            }
-            Token::LeftParen | Token::LeftBrace | Token::LeftBracket => {
-                let delimiter = match self.current() {
-                    Token::LeftParen => {
-                        self.advance();
-                        Delimiter::Paren
-                    }
-                    Token::LeftBrace => {
-                        self.advance();
-                        Delimiter::Brace
-                    }
-                    Token::LeftBracket => {
+                self.advance();
+                let patterns = self.parse_macro_patterns_until(&Token::RightParen)?;
+
+                // Any single token other than an operator separates iterations
+                let separator = match self.current() {
+                    Token::Star | Token::Plus | Token::Question | Token::Eof => None,
+                    token => {
+                        let sep_token = token.clone();
                         self.advance();
-                        Delimiter::Bracket
+                        Some(Box::new(sep_token))
                     }
-                    _ => unreachable!(),
                 };
 
-                let mut patterns = Vec::new();
-                let closing = match delimiter {
-                    Delimiter::Paren => Token::RightParen,
-                    Delimiter::Brace => Token::RightBrace,
-                    Delimiter::Bracket => Token::RightBracket,
+                let kind = if self.check(&Token::Star) {
+                    self.advance();
+                    RepetitionKind::ZeroOrMore
+                } else if self.check(&Token::Plus) {
+                    self.advance();
+                    RepetitionKind::OneOrMore
+                } else if self.check(&Token::Question) {
+                    self.advance();
+                    RepetitionKind::ZeroOrOne
+                } else {
+                    return Err(ParseError::InvalidSyntax("Expected *, +, or ?".to_string()));
                 };
 
-                while self.current() != &closing {
-                    patterns.push(self.parse_macro_pattern_element()?);
-                }
-                self.advance();
-
+                Ok(MacroPattern::Repetition {
+                    patterns,
+                    separator,
+                    kind,
+                })
+            }
+            Token::LeftParen | Token::LeftBrace | Token::LeftBracket => {
+                let (delimiter, closing) = match self.advance() {
+                    Token::LeftParen => (Delimiter::Paren, Token::RightParen),
+                    Token::LeftBrace => (Delimiter::Brace, Token::RightBrace),
+                    _ => (Delimiter::Bracket, Token::RightBracket),
+                };
+                let patterns = self.parse_macro_patterns_until(&closing)?;
                 Ok(MacroPattern::Group { delimiter, patterns })
             }
-            _ => {
-                let token = self.current().clone();
+            token => {
                 self.advance();
                 Ok(MacroPattern::Token(token))
             }
         }
     }
 
-    fn parse_token_tree_vec(&mut self) -> ParseResult<Vec<TokenTree>> {
-        let mut trees = Vec::new();
-
+    /// Parse the delimited transcriber of a rule, returning the trees inside
+    /// its delimiters
+    fn parse_macro_body(&mut self) -> ParseResult<Vec<TokenTree>> {
         match self.current() {
-            Token::LeftParen | Token::LeftBrace | Token::LeftBracket => {
-                while !matches!(self.current(), Token::Semicolon | Token::RightBrace) {
-                    trees.push(self.parse_token_tree()?);
-                }
-            }
-            _ => {
-                return Err(ParseError::InvalidSyntax("Expected token tree".to_string()));
-            }
+            Token::LeftParen | Token::LeftBrace | Token::LeftBracket => match self.parse_token_tree()? {
+                TokenTree::Group { stream, .. } => Ok(stream),
+                TokenTree::Token(_) => unreachable!(),
+            },
+            _ => Err(ParseError::InvalidSyntax("Expected token tree".to_string())),
         }
+    }
 
+    /// Group the remaining tokens into trees by their delimiters
+    pub fn parse_token_trees(&mut self) -> ParseResult<Vec<TokenTree>> {
+        let mut trees = Vec::new();
+        while self.current() != &Token::Eof {
+            trees.push(self.parse_token_tree()?);
+        }
         Ok(trees)
     }
 
@@ -216,6 +184,9 @@ impl Parser {
                     stream,
                 })
             }
+            Token::RightParen | Token::RightBrace | Token::RightBracket => {
+                Err(ParseError::InvalidSyntax(format!("Unexpected closing delimiter `{}`", self.current())))
+            }
             _ => {
                 let token = self.current().clone();
                 self.advance();
@@ -227,7 +198,7 @@ impl Parser {
     fn parse_token_tree_until(&mut self, end: &Token) -> ParseResult<Vec<TokenTree>> {
         let mut trees = Vec::new();
 
-        while self.current() != end && !matches!(self.current(), Token::Semicolon | Token::RightBrace) {
+        while self.current() != end && self.current() != &Token::Eof {
             trees.push(self.parse_token_tree()?);
         }
 
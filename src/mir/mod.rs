@@ -6,7 +6,8 @@
 //! ## What we do:
 //! - Convert HIR to MIR (basic blocks, control flow)
 //! - Build control flow graph
-//! - SSA-like form (each place assigned once)
+//! - Locals may be assigned more than once; [`ssa::rename_to_ssa`]
+//!   optionally renames them so each definition has its own name
 //! - Terminator-based control flow
 //!
 //! ## MIR Structure:
@@ -16,6 +17,10 @@
 //! - **Place**: Location of data (variable, field, index)
 //! - **Operand**: Value source (move, copy, constant)
 
+pub mod ssa;
+
+pub use ssa::rename_to_ssa;
+
 use crate::lowering::{HirExpression, HirItem, HirStatement, HirType, BinaryOp, UnaryOp};
 use std::fmt;
 
@@ -42,6 +47,8 @@ pub enum Place {
     Field(Box<Place>, String),
     /// Array index
     Index(Box<Place>, usize),
+    /// Element at the runtime index held in a local: the target of `v[i] = x`
+    DynamicIndex(Box<Place>, String),
     /// Dereference: *ptr
     Deref(Box<Place>),
 }
@@ -52,6 +59,7 @@ impl fmt::Display for Place {
             Place::Local(name) => write!(f, "{}", name),
             Place::Field(place, field) => write!(f, "{}.{}", place, field),
             Place::Index(place, idx) => write!(f, "{}[{}]", place, idx),
+            Place::DynamicIndex(place, idx) => write!(f, "{}[{}]", place, idx),
             Place::Deref(place) => write!(f, "*{}", place),
         }
     }
@@ -184,6 +192,9 @@ impl fmt::Display for Rvalue {
 /// Statement in a basic block
 #[derive(Debug, Clone)]
 pub struct Statement {
+    /// Id assigned during lowering, unique within the function. Passes
+    /// rewrite statements in place, so a surviving statement keeps its id.
+    pub id: usize,
     pub place: Place,
     pub rvalue: Rvalue,
 }
@@ -203,10 +214,29 @@ pub enum Terminator {
     If(Operand, usize, usize), // condition, then_block, else_block
     /// Return value
     Return(Option<Operand>),
+    /// Continue to the block if the condition holds, otherwise panic
+    Assert(Operand, AssertKind, usize), // condition, check, success_block
     /// Unreachable code
     Unreachable,
 }
 
+/// The runtime check behind a `Terminator::Assert`, used for its panic message
+#[derive(Debug, Clone)]
+pub enum AssertKind {
+    /// `index < len` for an array access
+    BoundsCheck { len: Operand, index: Operand },
+}
+
+impl fmt::Display for AssertKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssertKind::BoundsCheck { len, index } => {
+                write!(f, "index out of bounds: the len is {} but the index is {}", len, index)
+            }
+        }
+    }
+}
+
 impl fmt::Display for Terminator {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -216,6 +246,9 @@ impl fmt::Display for Terminator {
             }
             Terminator::Return(Some(op)) => write!(f, "return {}", op),
             Terminator::Return(None) => write!(f, "return"),
+            Terminator::Assert(cond, kind, target) => {
+                write!(f, "assert({}, \"{}\") -> bb{}", cond, kind, target)
+            }
             Terminator::Unreachable => write!(f, "unreachable"),
         }
     }
@@ -243,9 +276,28 @@ pub struct MirFunction {
     pub name: String,
     pub params: Vec<(String, HirType)>,
     pub return_type: HirType,
+    /// Element type of each local known to hold a `Vec<T>`, from the HIR types
+    pub vec_element_types: std::collections::HashMap<String, HirType>,
+    /// Bit width of each local known to hold an integer narrower than 64 bits
+    pub integer_widths: std::collections::HashMap<String, u32>,
     pub basic_blocks: Vec<BasicBlock>,
 }
 
+impl MirFunction {
+    /// Look up a statement by its id
+    pub fn statement(&self, id: usize) -> Option<&Statement> {
+        self.statement_location(id)
+            .map(|(block, index)| &self.basic_blocks[block].statements[index])
+    }
+
+    /// Block index and position within that block of the statement with `id`
+    pub fn statement_location(&self, id: usize) -> Option<(usize, usize)> {
+        self.basic_blocks.iter().enumerate().find_map(|(block, bb)| {
+            bb.statements.iter().position(|stmt| stmt.id == id).map(|index| (block, index))
+        })
+    }
+}
+
 impl fmt::Display for MirFunction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "fn {}(...) -> {}", self.name, self.return_type)?;
@@ -277,14 +329,68 @@ pub struct Mir {
     pub closures: Vec<MirFunction>,
 }
 
+impl Mir {
+    /// Check that every function's control flow graph is well formed:
+    /// terminator targets are in range, every block is reachable from the
+    /// entry block, and no reachable block ends in `Unreachable`.
+    pub fn verify(&self) -> MirResult<()> {
+        for func in self.functions.iter().chain(&self.closures) {
+            func.verify()?;
+        }
+        Ok(())
+    }
+}
+
+impl MirFunction {
+    /// Check this function's control flow graph (see [`Mir::verify`])
+    pub fn verify(&self) -> MirResult<()> {
+        let block_count = self.basic_blocks.len();
+        let error = |message: String| MirError {
+            message: format!("malformed MIR in `{}`: {}", self.name, message),
+        };
+
+        for (index, block) in self.basic_blocks.iter().enumerate() {
+            if let Some(target) = successors(&block.terminator).into_iter().find(|&target| target >= block_count) {
+                return Err(error(format!(
+                    "bb{} jumps to bb{}, but the function only has {} blocks",
+                    index, target, block_count
+                )));
+            }
+        }
+
+        // Walk the CFG from the entry block
+        let mut reachable = vec![false; block_count];
+        let mut worklist = vec![0];
+        while let Some(index) = worklist.pop() {
+            if index >= block_count || reachable[index] {
+                continue;
+            }
+            reachable[index] = true;
+            let terminator = &self.basic_blocks[index].terminator;
+            if matches!(terminator, Terminator::Unreachable) {
+                return Err(error(format!("reachable bb{} ends in `unreachable`", index)));
+            }
+            worklist.extend(successors(terminator));
+        }
+
+        if let Some(index) = reachable.iter().position(|&r| !r) {
+            return Err(error(format!("bb{} is not reachable from the entry block", index)));
+        }
+        Ok(())
+    }
+}
+
 /// MIR builder
 pub struct MirBuilder {
     current_block: usize,
     blocks: Vec<BasicBlock>,
     next_var: usize,
+    next_statement_id: usize,
     pub closure_counter: usize,  // Counter for unique closure function names
     /// Closures generated during lowering
     pub closures: Vec<MirFunction>,
+    /// Where `continue` and `break` jump to, innermost loop last
+    loop_targets: Vec<(usize, usize)>,
 }
 
 impl MirBuilder {
@@ -297,8 +403,10 @@ impl MirBuilder {
                 terminator: Terminator::Unreachable,
             }],
             next_var: 0,
+            next_statement_id: 0,
             closure_counter: 0,
             closures: Vec::new(),
+            loop_targets: Vec::new(),
         }
     }
 
@@ -312,7 +420,9 @@ impl MirBuilder {
     /// Add a statement to the current block
     pub fn add_statement(&mut self, place: Place, rvalue: Rvalue) {
         if let Some(block) = self.blocks.get_mut(self.current_block) {
-            block.statements.push(Statement { place: place.clone(), rvalue: rvalue.clone() });
+            let id = self.next_statement_id;
+            self.next_statement_id += 1;
+            block.statements.push(Statement { id, place: place.clone(), rvalue: rvalue.clone() });
         } else {
         }
     }
@@ -339,10 +449,90 @@ impl MirBuilder {
         self.current_block = block_idx;
     }
 
-    /// Get all basic blocks
+    /// Get all basic blocks, dropping any the entry block can't reach
+    /// (e.g. the join block after an `if` whose arms both return)
     pub fn finish(self) -> Vec<BasicBlock> {
-        self.blocks
+        remove_unreachable_blocks(self.blocks)
+    }
+}
+
+/// The bit width of an integer type narrower than 64 bits
+fn integer_width(ty: &HirType) -> Option<u32> {
+    match ty {
+        HirType::Int32 | HirType::UInt32 => Some(32),
+        HirType::Named(name) => match name.as_str() {
+            "i16" | "u16" => Some(16),
+            "i8" | "u8" => Some(8),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `ty` with `#[repr(transparent)]` structs replaced by their field's type
+fn erase_transparent(ty: &HirType) -> HirType {
+    match ty {
+        HirType::Named(name) => match crate::lowering::transparent_field_type(name) {
+            Some(field) => erase_transparent(&field),
+            None => ty.clone(),
+        },
+        HirType::Reference(inner) => HirType::Reference(Box::new(erase_transparent(inner))),
+        HirType::MutableReference(inner) => HirType::MutableReference(Box::new(erase_transparent(inner))),
+        _ => ty.clone(),
+    }
+}
+
+/// Successor block indices of a terminator
+pub(crate) fn successors(terminator: &Terminator) -> Vec<usize> {
+    match terminator {
+        Terminator::Goto(target) => vec![*target],
+        Terminator::If(_, then_block, else_block) => vec![*then_block, *else_block],
+        Terminator::Assert(_, _, target) => vec![*target],
+        Terminator::Return(_) | Terminator::Unreachable => vec![],
+    }
+}
+
+/// Drop blocks not reachable from bb0 and renumber the remaining targets
+fn remove_unreachable_blocks(blocks: Vec<BasicBlock>) -> Vec<BasicBlock> {
+    let mut reachable = vec![false; blocks.len()];
+    let mut worklist = vec![0];
+    while let Some(index) = worklist.pop() {
+        if index < blocks.len() && !reachable[index] {
+            reachable[index] = true;
+            worklist.extend(successors(&blocks[index].terminator));
+        }
+    }
+    if reachable.iter().all(|&r| r) {
+        return blocks;
+    }
+
+    let mut new_index = vec![usize::MAX; blocks.len()];
+    let mut next = 0;
+    for (index, &is_reachable) in reachable.iter().enumerate() {
+        if is_reachable {
+            new_index[index] = next;
+            next += 1;
+        }
     }
+    // Out-of-range targets are left as they are for `verify` to report
+    let remap = |target: usize| new_index.get(target).copied().unwrap_or(target);
+
+    blocks
+        .into_iter()
+        .zip(reachable)
+        .filter(|(_, is_reachable)| *is_reachable)
+        .map(|(mut block, _)| {
+            block.terminator = match block.terminator {
+                Terminator::Goto(target) => Terminator::Goto(remap(target)),
+                Terminator::If(cond, then_block, else_block) => {
+                    Terminator::If(cond, remap(then_block), remap(else_block))
+                }
+                Terminator::Assert(cond, kind, target) => Terminator::Assert(cond, kind, remap(target)),
+                other => other,
+            };
+            block
+        })
+        .collect()
 }
 
 /// MIR lowerer: converts HIR to MIR
@@ -354,6 +544,14 @@ pub struct MirLowerer {
     available_functions: std::collections::HashSet<String>, // All functions that exist (including qualified names)
     local_types: std::collections::HashMap<String, HirType>, // Maps local variable names to their types
     var_struct_types: std::collections::HashMap<String, String>, // Maps variable names to struct type names (for operator overloading)
+    array_lengths: std::collections::HashMap<String, usize>, // Maps fixed-size array locals to their length (for bounds checks)
+    pair_pointers: std::collections::HashSet<String>, // Locals pointing at a HashMap entry's [key][value] pair
+    returned_closures: std::collections::HashMap<String, (String, Vec<(String, HirType)>)>, // Closure function name -> the closure it returns
+    closure_objects: std::collections::HashMap<String, (String, Vec<(String, HirType)>)>, // Locals holding a closure object returned by a call
+    transparent_returns: std::collections::HashMap<String, String>, // Functions returning a `#[repr(transparent)]` struct -> that struct
+    ref_self_methods: std::collections::HashSet<String>, // `Type::method`s taking `&self` or `&mut self`
+    vec_element_types: std::collections::HashMap<String, HirType>, // Vec locals of the function being lowered -> element type
+    integer_widths: std::collections::HashMap<String, u32>, // Narrow integer locals of the function being lowered -> bit width
 }
 
 impl MirLowerer {
@@ -367,6 +565,14 @@ impl MirLowerer {
             available_functions: std::collections::HashSet::new(),
             local_types: std::collections::HashMap::new(),
             var_struct_types: std::collections::HashMap::new(),
+            array_lengths: std::collections::HashMap::new(),
+            pair_pointers: std::collections::HashSet::new(),
+            returned_closures: std::collections::HashMap::new(),
+            closure_objects: std::collections::HashMap::new(),
+            transparent_returns: std::collections::HashMap::new(),
+            ref_self_methods: std::collections::HashSet::new(),
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
         }
     }
 
@@ -387,11 +593,31 @@ impl MirLowerer {
     ) -> MirResult<String> {
         let func_name = self.gen_closure_name();
         let mut builder = MirBuilder::new();
+        let enclosing_vec_types = std::mem::take(&mut self.vec_element_types);
+        let enclosing_widths = std::mem::take(&mut self.integer_widths);
+        for (param_name, param_type) in captures.iter().chain(params) {
+            self.record_vec_element_type(param_name, param_type);
+            self.record_integer_width(param_name, param_type);
+        }
 
         for stmt in body {
             self.lower_statement_in_builder(&mut builder, stmt)?;
         }
 
+        // Remember which closure this one hands back, so callers can call
+        // the returned object with its captures loaded from the heap
+        let returned = match body.last() {
+            Some(HirStatement::Return(Some(HirExpression::Variable(inner)))) => self.closure_vars.get(inner).cloned(),
+            Some(HirStatement::Return(Some(HirExpression::Closure { captures, .. }))) => self
+                .generated_functions
+                .last()
+                .map(|inner| (inner.name.clone(), captures.clone())),
+            _ => None,
+        };
+        if let Some(returned) = returned {
+            self.returned_closures.insert(func_name.clone(), returned);
+        }
+
         if matches!(builder.blocks[builder.current_block].terminator, Terminator::Unreachable) {
             builder.set_terminator(Terminator::Return(None));
         }
@@ -403,6 +629,8 @@ impl MirLowerer {
             name: func_name.clone(),
             params: all_params,
             return_type: return_type.clone(),
+            vec_element_types: std::mem::replace(&mut self.vec_element_types, enclosing_vec_types),
+            integer_widths: std::mem::replace(&mut self.integer_widths, enclosing_widths),
             basic_blocks: builder.finish(),
         };
 
@@ -419,13 +647,21 @@ impl MirLowerer {
         let mut globals = Vec::new();
         
         // Collect global constants and statics
-        self.collect_globals_recursive(items, &mut globals)?;
+        self.collect_globals_recursive(items, "", &mut globals)?;
         
         self.lower_items_recursive(items, "", &mut functions)?;
 
         // Add any generated closure functions
         functions.extend(self.generated_functions.drain(..));
 
+        // A transparent struct is passed and returned exactly like its field
+        for func in &mut functions {
+            for (_, ty) in &mut func.params {
+                *ty = erase_transparent(ty);
+            }
+            func.return_type = erase_transparent(&func.return_type);
+        }
+
         Ok(Mir { 
             functions, 
             globals,
@@ -436,12 +672,13 @@ impl MirLowerer {
     fn collect_available_functions(&mut self, items: &[HirItem], module_prefix: &str) {
         for item in items {
             match item {
-                HirItem::Function { name, .. } => {
+                HirItem::Function { name, params, return_type, .. } => {
                     let full_name = if module_prefix.is_empty() {
                         name.clone()
                     } else {
                         format!("{}::{}", module_prefix, name)
                     };
+                    self.note_signature(&full_name, params, return_type.as_ref());
                     self.available_functions.insert(full_name);
                 }
                 HirItem::Module { name, items: module_items, .. } => {
@@ -455,8 +692,9 @@ impl MirLowerer {
                 HirItem::Impl { struct_name, methods, .. } => {
                     // Collect functions from impl blocks with qualified names
                     for method_item in methods {
-                        if let HirItem::Function { name, .. } = method_item {
+                        if let HirItem::Function { name, params, return_type, .. } = method_item {
                             let qualified_name = format!("{}::{}", struct_name, name);
+                            self.note_signature(&qualified_name, params, return_type.as_ref());
                             self.available_functions.insert(qualified_name);
                         }
                     }
@@ -466,15 +704,31 @@ impl MirLowerer {
         }
     }
 
+    /// Remember which functions return a transparent struct and which
+    /// methods borrow `self`
+    fn note_signature(&mut self, name: &str, params: &[(String, HirType)], return_type: Option<&HirType>) {
+        if let Some(HirType::Named(returned)) = return_type {
+            if crate::lowering::is_transparent_struct(returned) {
+                self.transparent_returns.insert(name.to_string(), returned.clone());
+            }
+        }
+        if let Some((param, HirType::Reference(_) | HirType::MutableReference(_))) = params.first() {
+            if param == "self" {
+                self.ref_self_methods.insert(name.to_string());
+            }
+        }
+    }
+
     /// Collect global constants and static variables recursively
-    fn collect_globals_recursive(&mut self, items: &[HirItem], globals: &mut Vec<GlobalItem>) -> MirResult<()> {
+    fn collect_globals_recursive(&mut self, items: &[HirItem], module_prefix: &str, globals: &mut Vec<GlobalItem>) -> MirResult<()> {
         for item in items {
             match item {
                 HirItem::Const { name, ty: _, is_public: _, generics: _ } => {
                     // For now, const values are compiled away (inlined)
-                    // We still track them for future reference
+                    // We still track them for future reference, under a
+                    // symbol that keeps same-named consts of other modules apart
                     globals.push(GlobalItem {
-                        name: name.clone(),
+                        name: format!("{}{}", module_prefix, name),
                         is_static: false,
                         is_mutable: false,
                         value: 0,  // placeholder
@@ -490,8 +744,8 @@ impl MirLowerer {
                         is_string: false,
                     });
                 }
-                HirItem::Module { items: module_items, .. } => {
-                    self.collect_globals_recursive(module_items, globals)?;
+                HirItem::Module { name, items: module_items, .. } => {
+                    self.collect_globals_recursive(module_items, &format!("{}{}_", module_prefix, name), globals)?;
                 }
                 _ => {}
             }
@@ -514,6 +768,8 @@ impl MirLowerer {
                     // Register parameter types for this function
                     for (param_name, param_type) in params {
                         self.local_types.insert(param_name.clone(), param_type.clone());
+                        self.record_vec_element_type(param_name, param_type);
+                        self.record_integer_width(param_name, param_type);
                     }
 
                     // Lower function body
@@ -523,6 +779,7 @@ impl MirLowerer {
                     
                     // Clear local types after function lowering
                     self.local_types.clear();
+                    self.array_lengths.clear();
 
                     // Ensure proper terminator
                     if matches!(mir_builder.blocks[mir_builder.current_block].terminator, Terminator::Unreachable) {
@@ -540,6 +797,8 @@ impl MirLowerer {
                          name: full_name,
                          params: params.clone(),
                          return_type: return_type.clone().unwrap_or(HirType::Unknown),
+                         vec_element_types: std::mem::take(&mut self.vec_element_types),
+                         integer_widths: std::mem::take(&mut self.integer_widths),
                          basic_blocks,
                      };
                      functions.push(func);
@@ -560,8 +819,13 @@ impl MirLowerer {
                 HirItem::Static { .. } => {
                     // Statics don't generate code in our simplified implementation
                 }
+                HirItem::ExternBlock { .. } => {
+                    // Foreign functions are defined by the library they're linked from
+                }
                 HirItem::AssociatedType { .. } => {
                 }
+                HirItem::AssociatedConst { .. } => {
+                }
                 HirItem::Use { .. } => {
                 }
                 HirItem::Impl { struct_name, methods, .. } => {
@@ -583,10 +847,153 @@ impl MirLowerer {
         Ok(())
     }
 
+    /// Lower `for var in (start..end).rev() { body }` into a loop that counts
+    /// down from the last value of the range to `start`
+    fn lower_reverse_range_for(
+        &mut self,
+        builder: &mut MirBuilder,
+        var: &str,
+        start: &HirExpression,
+        end: &HirExpression,
+        inclusive: bool,
+        body: &[HirStatement],
+    ) -> MirResult<()> {
+        let loop_var_place = Place::Local(var.to_string());
+        let step_down = Rvalue::BinaryOp(
+            BinaryOp::Subtract,
+            Operand::Copy(loop_var_place.clone()),
+            Operand::Constant(Constant::Integer(1)),
+        );
+        
+        // Both bounds are evaluated once, in source order, before the loop
+        let start_temp = builder.gen_temp();
+        self.lower_expression_to_place(builder, start, Place::Local(start_temp.clone()))?;
+        
+        // The last value is `end` itself only for an inclusive range
+        self.lower_expression_to_place(builder, end, loop_var_place.clone())?;
+        if !inclusive {
+            builder.add_statement(loop_var_place.clone(), step_down.clone());
+        }
+        
+        let current_block = builder.current_block;
+        let loop_cond = builder.create_block();
+        let loop_body = builder.create_block();
+        let loop_end = builder.create_block();
+        builder.blocks[current_block].terminator = Terminator::Goto(loop_cond);
+        
+        // Loop condition: var >= start
+        builder.current_block = loop_cond;
+        let cond_temp = builder.gen_temp();
+        builder.add_statement(
+            Place::Local(cond_temp.clone()),
+            Rvalue::BinaryOp(
+                BinaryOp::GreaterEqual,
+                Operand::Copy(loop_var_place.clone()),
+                Operand::Copy(Place::Local(start_temp)),
+            ),
+        );
+        builder.set_terminator(Terminator::If(Operand::Copy(Place::Local(cond_temp)), loop_body, loop_end));
+        
+        // `continue` still steps the counter down
+        let loop_step = builder.create_block();
+        builder.current_block = loop_body;
+        self.lower_loop_body(builder, body, loop_step, loop_end)?;
+        builder.set_terminator(Terminator::Goto(loop_step));
+        
+        builder.current_block = loop_step;
+        builder.add_statement(loop_var_place, step_down);
+        builder.set_terminator(Terminator::Goto(loop_cond));
+        
+        builder.current_block = loop_end;
+        Ok(())
+    }
+
+    /// Lower a loop body whose `continue` jumps to `continue_to` and whose
+    /// `break` jumps to `break_to`
+    fn lower_loop_body(
+        &mut self,
+        builder: &mut MirBuilder,
+        body: &[HirStatement],
+        continue_to: usize,
+        break_to: usize,
+    ) -> MirResult<()> {
+        builder.loop_targets.push((continue_to, break_to));
+        let lowered = body.iter().try_for_each(|stmt| self.lower_statement_in_builder(builder, stmt));
+        builder.loop_targets.pop();
+        lowered
+    }
+
+    /// Remember the element type of a local declared as `Vec<T>`, `&Vec<T>`
+    /// or `&mut Vec<T>`
+    fn record_vec_element_type(&mut self, local: &str, ty: &HirType) {
+        let vec_type = match ty {
+            HirType::Reference(inner) | HirType::MutableReference(inner) => inner.as_ref(),
+            other => other,
+        };
+        if let HirType::Vec(elem) = vec_type {
+            if **elem != HirType::Unknown {
+                self.vec_element_types.insert(local.to_string(), elem.as_ref().clone());
+            }
+        }
+    }
+
+    /// Remember the width of a local declared as an integer narrower than 64 bits
+    fn record_integer_width(&mut self, local: &str, ty: &HirType) {
+        if let Some(width) = integer_width(ty) {
+            self.integer_widths.insert(local.to_string(), width);
+        }
+    }
+
+    /// The element type `value` gives a Vec it's pushed onto, when its HIR type says
+    fn pushed_element_type(&self, value: &HirExpression) -> Option<HirType> {
+        match value {
+            HirExpression::Float(_) => Some(HirType::Float64),
+            HirExpression::StructLiteral { name, .. } => Some(HirType::Named(name.clone())),
+            HirExpression::Variable(var) => match self.local_types.get(var)? {
+                HirType::Float64 => Some(HirType::Float64),
+                HirType::Named(name) if crate::lowering::get_struct_field_count(name) > 0 => Some(HirType::Named(name.clone())),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The `#[repr(transparent)]` struct `expr` evaluates to, and whether it
+    /// is only reached through a reference
+    fn transparent_struct_of(&self, expr: &HirExpression) -> Option<(String, bool)> {
+        self.struct_of(expr).filter(|(name, _)| crate::lowering::is_transparent_struct(name))
+    }
+
+    /// The struct `expr` evaluates to, and whether it is only reached
+    /// through a reference
+    fn struct_of(&self, expr: &HirExpression) -> Option<(String, bool)> {
+        let found = match expr {
+            HirExpression::Variable(var_name) => match self.local_types.get(var_name) {
+                Some(HirType::Named(name)) => Some((name.clone(), false)),
+                Some(HirType::Reference(inner) | HirType::MutableReference(inner)) => match inner.as_ref() {
+                    HirType::Named(name) => Some((name.clone(), true)),
+                    _ => None,
+                },
+                _ => self.var_struct_types.get(var_name).map(|name| (name.clone(), false)),
+            },
+            HirExpression::StructLiteral { name, .. } => Some((name.clone(), false)),
+            HirExpression::Call { func, .. } => match func.as_ref() {
+                HirExpression::Variable(func_name) => {
+                    self.transparent_returns.get(func_name).map(|name| (name.clone(), false))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        found.filter(|(name, _)| {
+            crate::lowering::is_transparent_struct(name) || crate::lowering::get_struct_field_count(name) > 0
+        })
+    }
+
     /// Lower a statement
     fn lower_statement_in_builder(&mut self, builder: &mut MirBuilder, stmt: &HirStatement) -> MirResult<()> {
         match stmt {
-            HirStatement::Let { name, init, .. } => {
+            HirStatement::Let { name, ty, init, .. } => {
                 if let HirExpression::Closure { params, body, return_type, is_move: _, captures } = init {
                     // Generate a closure function
                     let func_name = self.generate_closure_function(params, body, return_type, captures)?;
@@ -598,19 +1005,32 @@ impl MirLowerer {
                     let inferred_type = match init {
                         HirExpression::Call { func, .. } => {
                             if let HirExpression::Variable(func_name) = &**func {
-                                // Extract struct name from functions like "Counter::new" or "Point::add"
-                                // For operator impl methods (Point::add), the return type is Point
-                                let struct_name = func_name.split("::").next().map(|s| s.to_string());
-                                struct_name
+                                if func_name == "__builtin_vec_from" || func_name == "__builtin_vec_repeat" {
+                                    // vec![...] produces a Vec
+                                    Some("Vec".to_string())
+                                } else {
+                                    // Extract struct name from functions like "Counter::new" or "Point::add"
+                                    // For operator impl methods (Point::add), the return type is Point
+                                    func_name.split("::").next().map(|s| s.to_string())
+                                }
                             } else {
                                 None
                             }
                         }
-                        HirExpression::MethodCall { method, .. } => {
+                        HirExpression::MethodCall { receiver, method, .. } => {
                             // Infer type from method call return type
                             match method.as_str() {
+                                // A clone has the type of what it copies
+                                "clone" => match &**receiver {
+                                    HirExpression::Variable(var) => match self.local_types.get(var) {
+                                        Some(HirType::Vec(_)) => Some("Vec".to_string()),
+                                        Some(HirType::Named(n)) => Some(n.clone()),
+                                        _ => None,
+                                    },
+                                    _ => None,
+                                },
                                 "into_iter" => Some("Iterator".to_string()),
-                                "iter" => Some("Iterator".to_string()),
+                                "iter" | "iter_mut" => Some("Iterator".to_string()),
                                 "map" => Some("Iterator".to_string()),
                                 "filter" => Some("Iterator".to_string()),
                                 "take" => Some("Iterator".to_string()),
@@ -624,6 +1044,7 @@ impl MirLowerer {
                         HirExpression::StructLiteral { name, .. } => {
                             Some(name.clone())
                         }
+                        HirExpression::ArrayLiteral(_) => Some("Array".to_string()),
                         HirExpression::Variable(struct_name) => {
                             // Handle bare struct references (unit structs or constructors)
                             // If the variable starts with uppercase, it's likely a struct type
@@ -636,6 +1057,18 @@ impl MirLowerer {
                         _ => None,
                     };
                     
+                    // A transparent struct is known from the annotation or the value
+                    let transparent = match ty {
+                        HirType::Named(declared) if crate::lowering::is_transparent_struct(declared) => Some(declared.clone()),
+                        _ => self.transparent_struct_of(init).filter(|(_, through_ref)| !through_ref).map(|(name, _)| name),
+                    };
+                    // An annotated struct type outranks the guess from the initializer
+                    let declared = match ty {
+                        HirType::Named(declared) if crate::lowering::get_struct_field_count(declared) > 0 => Some(declared.clone()),
+                        _ => None,
+                    };
+                    let inferred_type = transparent.or(declared).or(inferred_type);
+
                     if let Some(ty_str) = inferred_type {
                         // Convert inferred type string to HirType
                         let hir_type = match ty_str.as_str() {
@@ -650,11 +1083,74 @@ impl MirLowerer {
                         self.local_types.insert(name.clone(), hir_type);
                         
                         // Also track struct types for operator overloading (PHASE 2.1)
-                        if !matches!(ty_str.as_str(), "i32" | "i64" | "u32" | "u64" | "bool" | "f64" | "Iterator" | "Option" | "Vec") {
+                        if !matches!(ty_str.as_str(), "i32" | "i64" | "u32" | "u64" | "bool" | "f64" | "Iterator" | "Option" | "Vec" | "Array") {
                             self.var_struct_types.insert(name.clone(), ty_str);
                         }
                     }
+
+                    let array_length = match (ty, init) {
+                        (HirType::Array { size: Some(size), .. }, _) => Some(*size),
+                        (_, HirExpression::ArrayLiteral(elements)) => Some(elements.len()),
+                        _ => None,
+                    };
+                    match array_length {
+                        Some(length) => self.array_lengths.insert(name.clone(), length),
+                        None => self.array_lengths.remove(name),
+                    };
                     
+                    // Copies of a HashMap entry pointer destructure the same way
+                    match init {
+                        HirExpression::Variable(source) if self.pair_pointers.contains(source) => {
+                            self.pair_pointers.insert(name.clone())
+                        }
+                        _ => self.pair_pointers.remove(name),
+                    };
+
+                    // Calling a closure that returns a closure yields a closure object
+                    let returned = match init {
+                        HirExpression::Call { func, .. } => match func.as_ref() {
+                            HirExpression::Variable(callee) => self
+                                .closure_vars
+                                .get(callee)
+                                .and_then(|(closure_fn, _)| self.returned_closures.get(closure_fn))
+                                .cloned(),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    match returned {
+                        Some(returned) => self.closure_objects.insert(name.clone(), returned),
+                        None => self.closure_objects.remove(name),
+                    };
+
+                    // The annotation says what a Vec holds; failing that, the Vec it copies
+                    let source = match init {
+                        HirExpression::Variable(source) => Some(source),
+                        HirExpression::MethodCall { receiver, method, .. } if method == "clone" => match receiver.as_ref() {
+                            HirExpression::Variable(source) => Some(source),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    let vec_type = match ty {
+                        HirType::Vec(_) => Some(ty.clone()),
+                        _ => source
+                            .and_then(|source| self.vec_element_types.get(source))
+                            .map(|elem| HirType::Vec(Box::new(elem.clone()))),
+                    };
+                    self.vec_element_types.remove(name);
+                    if let Some(vec_type) = vec_type {
+                        self.record_vec_element_type(name, &vec_type);
+                    }
+
+                    // Likewise the annotation, or the local it copies, gives an integer's width
+                    let width = integer_width(ty)
+                        .or_else(|| source.and_then(|source| self.integer_widths.get(source)).copied());
+                    match width {
+                        Some(width) => self.integer_widths.insert(name.clone(), width),
+                        None => self.integer_widths.remove(name),
+                    };
+
                     let place = Place::Local(name.clone());
                     self.lower_expression_to_place(builder, init, place)?;
                 }
@@ -679,8 +1175,14 @@ impl MirLowerer {
                 builder.blocks[return_block].terminator = Terminator::Return(None);
             }
             HirStatement::Break | HirStatement::Continue => {
-                // Simplified: treat as unreachable for now
-                builder.set_terminator(Terminator::Unreachable);
+                let keyword = if matches!(stmt, HirStatement::Break) { "break" } else { "continue" };
+                let Some(&(continue_to, break_to)) = builder.loop_targets.last() else {
+                    return Err(MirError { message: format!("`{}` outside of a loop", keyword) });
+                };
+                let target = if keyword == "break" { break_to } else { continue_to };
+                builder.set_terminator(Terminator::Goto(target));
+                // Whatever follows the jump is dead; it gets a block nothing reaches
+                builder.current_block = builder.create_block();
             }
 
             HirStatement::For { var, iter, body } => {
@@ -705,6 +1207,16 @@ impl MirLowerer {
                             );
                         }
                         
+                        // Evaluate the bound once in the preheader; `end` may have side effects
+                        let end_temp = match end {
+                            Some(e) => {
+                                let end_temp = builder.gen_temp();
+                                self.lower_expression_to_place(builder, e, Place::Local(end_temp.clone()))?;
+                                Some(end_temp)
+                            }
+                            None => None,
+                        };
+                        
                         // Create loop blocks - use separate block for condition check
                         let current_block = builder.current_block;
                         let loop_cond = builder.create_block();
@@ -718,10 +1230,7 @@ impl MirLowerer {
                         builder.current_block = loop_cond;
                         
                         // Loop condition: i < end (or i <= end if inclusive)
-                        if let Some(e) = end {
-                            let end_temp = builder.gen_temp();
-                            self.lower_expression_to_place(builder, e, Place::Local(end_temp.clone()))?;
-                            
+                        if let Some(end_temp) = end_temp {
                             let cond_op = Rvalue::BinaryOp(
                                 if *inclusive { BinaryOp::LessEqual } else { BinaryOp::Less },
                                 Operand::Copy(loop_var_place.clone()),
@@ -737,25 +1246,32 @@ impl MirLowerer {
                             ));
                         }
                         
-                        // Loop body
+                        // Loop body; `continue` still increments the counter
+                        let loop_step = builder.create_block();
                         builder.current_block = loop_body;
-                        for stmt in body {
-                            self.lower_statement_in_builder(builder, stmt)?;
-                        }
+                        self.lower_loop_body(builder, body, loop_step, loop_end)?;
+                        builder.set_terminator(Terminator::Goto(loop_step));
                         
                         // Increment counter: i = i + 1
-                        let loop_body_end = builder.current_block;
+                        builder.current_block = loop_step;
                         let inc_expr = Rvalue::BinaryOp(
                             BinaryOp::Add,
                             Operand::Copy(loop_var_place.clone()),
                             Operand::Constant(Constant::Integer(1))
                         );
                         builder.add_statement(loop_var_place, inc_expr);
-                        builder.blocks[loop_body_end].terminator = Terminator::Goto(loop_cond);
+                        builder.set_terminator(Terminator::Goto(loop_cond));
                         
                         // Continue after loop
                         builder.current_block = loop_end;
                     }
+                    HirExpression::MethodCall { receiver, method, .. }
+                        if method == "rev" && matches!(&**receiver, HirExpression::Range { start: Some(_), end: Some(_), .. }) =>
+                    {
+                        if let HirExpression::Range { start: Some(start), end: Some(end), inclusive } = &**receiver {
+                            self.lower_reverse_range_for(builder, var, start, end, *inclusive, body)?;
+                        }
+                    }
                     _ => {
                         // Implement iterator protocol: for var in iter { body }
                         // Desugars into:
@@ -770,6 +1286,36 @@ impl MirLowerer {
                         let iter_var = format!("__iter_{}", var);
                         let iter_var_place = Place::Local(iter_var.clone());
                         
+                        // A HashMap yields a pointer to each entry's [key][value] pair, or
+                        // null once the entries run out
+                        let is_hashmap = match &**iter {
+                            HirExpression::Variable(name) => {
+                                matches!(self.local_types.get(name), Some(HirType::Named(n)) if n == "HashMap")
+                            }
+                            _ => false,
+                        };
+                        
+                        // rev() walks the collection from the back: next_back() in place of next()
+                        let (iter, reversed) = match &**iter {
+                            HirExpression::MethodCall { receiver, method, .. } if method == "rev" => (&**receiver, true),
+                            iter => (iter, false),
+                        };
+                        
+                        // iter() and iter_mut() yield element references; __next_ref returns the
+                        // slot address (never null), so zero-valued elements don't end the loop
+                        // early, and writes through it land in the collection
+                        let yields_refs = matches!(iter, HirExpression::MethodCall { method, .. } if method == "iter" || method == "iter_mut");
+                        let (into_iter_fn, next_fn) = match (is_hashmap, reversed, yields_refs) {
+                            (true, _, _) => ("gaia_hashmap_iter", "gaia_hashmap_iter_next"),
+                            (false, true, true) => ("__into_iter_rev", "__next_back_ref"),
+                            (false, true, false) => ("__into_iter_rev", "__next_back"),
+                            (false, false, true) => ("__into_iter", "__next_ref"),
+                            (false, false, false) => ("__into_iter", "__next"),
+                        };
+                        if is_hashmap {
+                            self.pair_pointers.insert(var.clone());
+                        }
+                        
                         // Call into_iter() on the collection
                         let iter_temp = builder.gen_temp();
                         self.lower_expression_to_place(builder, iter, Place::Local(iter_temp.clone()))?;
@@ -777,7 +1323,7 @@ impl MirLowerer {
                         // Store the iterator result
                         builder.add_statement(
                             iter_var_place.clone(),
-                            Rvalue::Call("__into_iter".to_string(), vec![Operand::Copy(Place::Local(iter_temp))])
+                            Rvalue::Call(into_iter_fn.to_string(), vec![Operand::Copy(Place::Local(iter_temp))])
                         );
                         
                         // Create loop blocks
@@ -794,7 +1340,7 @@ impl MirLowerer {
                         let next_result = builder.gen_temp();
                         builder.add_statement(
                             Place::Local(next_result.clone()),
-                            Rvalue::Call("__next".to_string(), vec![Operand::Copy(iter_var_place)])
+                            Rvalue::Call(next_fn.to_string(), vec![Operand::Copy(iter_var_place)])
                         );
                         
                         // Check if Some(value) or None
@@ -826,9 +1372,7 @@ impl MirLowerer {
                         );
                         
                         // Execute loop body
-                        for stmt in body {
-                            self.lower_statement_in_builder(builder, stmt)?;
-                        }
+                        self.lower_loop_body(builder, body, loop_cond, loop_end)?;
                         
                         // Jump back to condition
                         let loop_body_end = builder.current_block;
@@ -880,9 +1424,7 @@ impl MirLowerer {
                 
                 // Loop body
                 builder.current_block = loop_body;
-                for stmt in body {
-                    self.lower_statement_in_builder(builder, stmt)?;
-                }
+                self.lower_loop_body(builder, body, loop_cond, loop_end)?;
                 let loop_body_end = builder.current_block;
                 builder.blocks[loop_body_end].terminator = Terminator::Goto(loop_cond);
                 
@@ -987,10 +1529,14 @@ impl MirLowerer {
                 } = &**item
                 {
                     let mut inner_builder = MirBuilder::new();
+                    let enclosing_vec_types = std::mem::take(&mut self.vec_element_types);
+                    let enclosing_widths = std::mem::take(&mut self.integer_widths);
                     
                     // Register parameter types for this function
                     for (param_name, param_type) in params {
                         self.local_types.insert(param_name.clone(), param_type.clone());
+                        self.record_vec_element_type(param_name, param_type);
+                        self.record_integer_width(param_name, param_type);
                     }
                     
                     for stmt in body {
@@ -999,6 +1545,7 @@ impl MirLowerer {
                     
                     // Clear local types after function lowering
                     self.local_types.clear();
+                    self.array_lengths.clear();
                     
                     if matches!(inner_builder.blocks[inner_builder.current_block].terminator, Terminator::Unreachable) {
                         inner_builder.set_terminator(Terminator::Return(None));
@@ -1008,6 +1555,8 @@ impl MirLowerer {
                         name: name.clone(),
                         params: params.clone(),
                         return_type: return_type.clone().unwrap_or(HirType::Unknown),
+                        vec_element_types: std::mem::replace(&mut self.vec_element_types, enclosing_vec_types),
+                        integer_widths: std::mem::replace(&mut self.integer_widths, enclosing_widths),
                         basic_blocks: inner_builder.finish(),
                     };
                     
@@ -1021,6 +1570,46 @@ impl MirLowerer {
         Ok(())
     }
 
+    /// Guard an index into `array` with a bounds check when it's a
+    /// fixed-size array; the optimizer drops it for constant indices
+    fn emit_array_bounds_check(&self, builder: &mut MirBuilder, array: &HirExpression, index: Operand) {
+        let array_length = match array {
+            HirExpression::Variable(array_name) => self.array_lengths.get(array_name).copied(),
+            _ => None,
+        };
+        if let Some(length) = array_length {
+            let len = Operand::Constant(Constant::Integer(length as i64));
+            // `Less` compares signed, so a negative index (a huge `usize`)
+            // must be ruled out on its own
+            let not_negative = builder.gen_temp();
+            builder.add_statement(
+                Place::Local(not_negative.clone()),
+                Rvalue::BinaryOp(BinaryOp::GreaterEqual, index.clone(), Operand::Constant(Constant::Integer(0))),
+            );
+            let below_len = builder.gen_temp();
+            builder.add_statement(
+                Place::Local(below_len.clone()),
+                Rvalue::BinaryOp(BinaryOp::Less, index.clone(), len.clone()),
+            );
+            let in_bounds = builder.gen_temp();
+            builder.add_statement(
+                Place::Local(in_bounds.clone()),
+                Rvalue::BinaryOp(
+                    BinaryOp::And,
+                    Operand::Copy(Place::Local(not_negative)),
+                    Operand::Copy(Place::Local(below_len)),
+                ),
+            );
+            let success_block = builder.create_block();
+            builder.set_terminator(Terminator::Assert(
+                Operand::Copy(Place::Local(in_bounds)),
+                AssertKind::BoundsCheck { len, index },
+                success_block,
+            ));
+            builder.switch_block(success_block);
+        }
+    }
+
     /// Lower an expression, storing result in place
     fn lower_expression_to_place(&mut self, builder: &mut MirBuilder, expr: &HirExpression, place: Place) -> MirResult<()> {
         match expr {
@@ -1036,6 +1625,15 @@ impl MirLowerer {
             HirExpression::Bool(b) => {
                 builder.add_statement(place, Rvalue::Use(Operand::Constant(Constant::Bool(*b))));
             }
+            HirExpression::Variable(name) if self.closure_vars.contains_key(name) => {
+                // A closure used as a value (e.g. returned) becomes a closure object
+                let (fn_ptr, captures) = self.closure_vars[name].clone();
+                let captures = captures
+                    .iter()
+                    .map(|(capture, _)| Operand::Copy(Place::Local(capture.clone())))
+                    .collect();
+                builder.add_statement(place, Rvalue::Closure { fn_ptr, captures });
+            }
             HirExpression::Variable(name) => {
                 builder.add_statement(place.clone(), Rvalue::Use(Operand::Copy(Place::Local(name.clone()))));
                 
@@ -1051,6 +1649,14 @@ impl MirLowerer {
                 let right_temp = builder.gen_temp();
                 self.lower_expression_to_place(builder, left, Place::Local(left_temp.clone()))?;
                 self.lower_expression_to_place(builder, right, Place::Local(right_temp.clone()))?;
+
+                // The temp holds as many bits as the variable it copies, which decides
+                // how far it can be shifted
+                if let HirExpression::Variable(var_name) = left.as_ref() {
+                    if let Some(&width) = self.integer_widths.get(var_name) {
+                        self.integer_widths.insert(left_temp.clone(), width);
+                    }
+                }
                 
                 // PHASE 2.1: Operator Overloading
                 // Try to determine the type of the left operand for operator impl lookup
@@ -1153,6 +1759,23 @@ impl MirLowerer {
                         builder.add_statement(Place::Local(temp.clone()), Rvalue::Use(Operand::Copy(capture_place)));
                         mir_args.push(Operand::Copy(Place::Local(temp)));
                     }
+                } else if let Some((actual_func_name, captures)) = self.closure_objects.get(&func_name).cloned() {
+                    // Load each capture out of the closure object: [fn_ptr][capture1][capture2]...
+                    let object = std::mem::replace(&mut func_name, actual_func_name);
+                    for index in 0..captures.len() {
+                        let slot = builder.gen_temp();
+                        builder.add_statement(
+                            Place::Local(slot.clone()),
+                            Rvalue::BinaryOp(
+                                BinaryOp::Add,
+                                Operand::Copy(Place::Local(object.clone())),
+                                Operand::Constant(Constant::Integer(8 + 8 * index as i64)),
+                            ),
+                        );
+                        let temp = builder.gen_temp();
+                        builder.add_statement(Place::Local(temp.clone()), Rvalue::Deref(Place::Local(slot)));
+                        mir_args.push(Operand::Copy(Place::Local(temp)));
+                    }
                 }
                 
                 // Check if this is an unresolved method call
@@ -1340,7 +1963,30 @@ impl MirLowerer {
                         builder.add_statement(Place::Deref(Box::new(Place::Local(ptr_temp))), Rvalue::Use(Operand::Copy(Place::Local(val_temp))));
                         builder.add_statement(place, Rvalue::Use(Operand::Constant(Constant::Unit)));
                     }
-                    HirExpression::FieldAccess { object, field } => {
+                    HirExpression::FieldAccess { object, .. } if matches!(object.as_ref(), HirExpression::Variable(_))
+                        && self.transparent_struct_of(object).is_some() =>
+                    {
+                        // Assigning a transparent struct's field overwrites the struct
+                        let (_, through_ref) = self.transparent_struct_of(object).unwrap_or_default();
+                        let HirExpression::Variable(var_name) = object.as_ref() else { unreachable!() };
+                        let target = if through_ref {
+                            Place::Deref(Box::new(Place::Local(var_name.clone())))
+                        } else {
+                            Place::Local(var_name.clone())
+                        };
+                        builder.add_statement(target, Rvalue::Use(Operand::Copy(Place::Local(val_temp))));
+                        builder.add_statement(place, Rvalue::Use(Operand::Constant(Constant::Unit)));
+                    }
+                    HirExpression::FieldAccess { object, field, .. } if matches!(object.as_ref(), HirExpression::Variable(var_name)
+                        if matches!(self.local_types.get(var_name), Some(HirType::Reference(_) | HirType::MutableReference(_)))) =>
+                    {
+                        // Through a reference the field is written in the borrowed struct
+                        let HirExpression::Variable(var_name) = object.as_ref() else { unreachable!() };
+                        let target = Place::Field(Box::new(Place::Deref(Box::new(Place::Local(var_name.clone())))), field.clone());
+                        builder.add_statement(target, Rvalue::Use(Operand::Copy(Place::Local(val_temp))));
+                        builder.add_statement(place, Rvalue::Use(Operand::Constant(Constant::Unit)));
+                    }
+                    HirExpression::FieldAccess { object, field, .. } => {
                         // Field assignment: obj.field = value
                         let obj_temp = builder.gen_temp();
                         self.lower_expression_to_place(builder, object, Place::Local(obj_temp.clone()))?;
@@ -1348,6 +1994,32 @@ impl MirLowerer {
                         builder.add_statement(Place::Field(Box::new(Place::Local(obj_temp)), field.clone()), Rvalue::Use(Operand::Copy(Place::Local(val_temp))));
                         builder.add_statement(place, Rvalue::Use(Operand::Constant(Constant::Unit)));
                     }
+                    HirExpression::Index { array, index } => {
+                        // Indexed assignment: v[i] = value
+                        let base = match array.as_ref() {
+                            HirExpression::Variable(name) => Place::Local(name.clone()),
+                            _ => {
+                                let arr_temp = builder.gen_temp();
+                                self.lower_expression_to_place(builder, array, Place::Local(arr_temp.clone()))?;
+                                Place::Local(arr_temp)
+                            }
+                        };
+                        let idx_local = match index.as_ref() {
+                            HirExpression::Variable(name) => name.clone(),
+                            _ => {
+                                let idx_temp = builder.gen_temp();
+                                self.lower_expression_to_place(builder, index, Place::Local(idx_temp.clone()))?;
+                                idx_temp
+                            }
+                        };
+                        self.emit_array_bounds_check(builder, array, Operand::Copy(Place::Local(idx_local.clone())));
+
+                        builder.add_statement(
+                            Place::DynamicIndex(Box::new(base), idx_local),
+                            Rvalue::Use(Operand::Copy(Place::Local(val_temp))),
+                        );
+                        builder.add_statement(place, Rvalue::Use(Operand::Constant(Constant::Unit)));
+                    }
                     _ => {
                         return Err(MirError { message: "E086: Complex assignment targets not yet supported - use simpler patterns".to_string() });
                     }
@@ -1513,9 +2185,7 @@ impl MirLowerer {
                 
                 // Loop body
                 builder.current_block = loop_body;
-                for stmt in body {
-                    self.lower_statement_in_builder(builder, stmt)?;
-                }
+                self.lower_loop_body(builder, body, loop_cond, loop_end)?;
                 let loop_body_end = builder.current_block;
                 builder.blocks[loop_body_end].terminator = Terminator::Goto(loop_cond);
                 
@@ -1523,7 +2193,34 @@ impl MirLowerer {
                 builder.current_block = loop_end;
                 builder.add_statement(place, Rvalue::Use(Operand::Constant(Constant::Unit)));
             }
-            HirExpression::FieldAccess { object, field } => {
+            HirExpression::Loop { body } => {
+                // The header re-enters the body unconditionally; `break` is the only way to `loop_end`
+                let loop_header = builder.create_block();
+                let loop_end = builder.create_block();
+                builder.set_terminator(Terminator::Goto(loop_header));
+
+                builder.current_block = loop_header;
+                self.lower_loop_body(builder, body, loop_header, loop_end)?;
+                builder.set_terminator(Terminator::Goto(loop_header));
+
+                builder.current_block = loop_end;
+                builder.add_statement(place, Rvalue::Use(Operand::Constant(Constant::Unit)));
+            }
+            HirExpression::FieldAccess { object, .. } if self.transparent_struct_of(object).is_some() => {
+                // A transparent struct's field is the struct itself
+                let (_, through_ref) = self.transparent_struct_of(object).unwrap_or_default();
+                let value = match object.as_ref() {
+                    HirExpression::Variable(var_name) => Place::Local(var_name.clone()),
+                    _ => {
+                        let obj_temp = builder.gen_temp();
+                        self.lower_expression_to_place(builder, object, Place::Local(obj_temp.clone()))?;
+                        Place::Local(obj_temp)
+                    }
+                };
+                let rvalue = if through_ref { Rvalue::Deref(value) } else { Rvalue::Use(Operand::Copy(value)) };
+                builder.add_statement(place, rvalue);
+            }
+            HirExpression::FieldAccess { object, field, .. } => {
                 // For field access, we need to handle it specially:
                 // If the object is a reference (like &self), we need to dereference it first.
                 // If the object is a simple local variable, we can directly access its field.
@@ -1565,41 +2262,57 @@ impl MirLowerer {
                     }
                 }
             }
-            HirExpression::TupleAccess { object, index: _ } => {
-                let obj_temp = builder.gen_temp();
-                self.lower_expression_to_place(builder, object, Place::Local(obj_temp.clone()))?;
-                builder.add_statement(place, Rvalue::Use(Operand::Copy(Place::Local(obj_temp))));
-            }
+            HirExpression::TupleAccess { object, index } => match &**object {
+                HirExpression::Variable(pair) if self.pair_pointers.contains(pair) => {
+                    // Load the key (index 0) or value (index 1) through the entry pointer
+                    let slot = if *index == 0 {
+                        pair.clone()
+                    } else {
+                        let slot = builder.gen_temp();
+                        builder.add_statement(
+                            Place::Local(slot.clone()),
+                            Rvalue::BinaryOp(
+                                BinaryOp::Add,
+                                Operand::Copy(Place::Local(pair.clone())),
+                                Operand::Constant(Constant::Integer(8 * *index as i64)),
+                            ),
+                        );
+                        slot
+                    };
+                    builder.add_statement(place, Rvalue::Deref(Place::Local(slot)));
+                }
+                _ => {
+                    let obj_temp = builder.gen_temp();
+                    self.lower_expression_to_place(builder, object, Place::Local(obj_temp.clone()))?;
+                    builder.add_statement(place, Rvalue::Use(Operand::Copy(Place::Local(obj_temp))));
+                }
+            },
             HirExpression::Index { array, index } => {
                 let arr_temp = builder.gen_temp();
                 self.lower_expression_to_place(builder, array, Place::Local(arr_temp.clone()))?;
                 
                 // Evaluate the index expression
-                match index.as_ref() {
-                    HirExpression::Integer(idx_val) => {
-                        // Direct integer index - use as constant operand
-                        builder.add_statement(place, Rvalue::Index(
-                            Place::Local(arr_temp),
-                            Operand::Constant(Constant::Integer(*idx_val as i64))
-                        ));
-                    }
-                    HirExpression::Variable(var_name) => {
-                        // Index from variable - use the variable as operand
-                        builder.add_statement(place, Rvalue::Index(
-                            Place::Local(arr_temp),
-                            Operand::Copy(Place::Local(var_name.clone()))
-                        ));
-                    }
+                let idx_operand = match index.as_ref() {
+                    // Direct integer index - use as constant operand
+                    HirExpression::Integer(idx_val) => Operand::Constant(Constant::Integer(*idx_val)),
+                    // Index from variable - use the variable as operand
+                    HirExpression::Variable(var_name) => Operand::Copy(Place::Local(var_name.clone())),
                     _ => {
                         // Complex index expression - evaluate it to a temporary first
                         let idx_temp = builder.gen_temp();
                         self.lower_expression_to_place(builder, index, Place::Local(idx_temp.clone()))?;
-                        // Use the temporary as the index operand
-                        builder.add_statement(place, Rvalue::Index(
-                            Place::Local(arr_temp),
-                            Operand::Copy(Place::Local(idx_temp))
-                        ));
+                        Operand::Copy(Place::Local(idx_temp))
                     }
+                };
+
+                self.emit_array_bounds_check(builder, array, idx_operand.clone());
+                builder.add_statement(place, Rvalue::Index(Place::Local(arr_temp), idx_operand));
+            }
+            HirExpression::StructLiteral { name, fields } if crate::lowering::is_transparent_struct(name) && fields.len() == 1 => {
+                // A transparent struct is stored as its one field
+                self.lower_expression_to_place(builder, &fields[0].1, place.clone())?;
+                if let Place::Local(var_name) = &place {
+                    self.var_struct_types.insert(var_name.clone(), name.clone());
                 }
             }
             HirExpression::StructLiteral { name, fields } => {
@@ -1740,12 +2453,23 @@ impl MirLowerer {
             }
             HirExpression::EnumVariant { enum_name, variant_name, args } => {
                 // Evaluate all arguments first
+                let mut payload = Vec::new();
                 for arg in args {
                     let temp = builder.gen_temp();
-                    self.lower_expression_to_place(builder, arg, Place::Local(temp))?;
-                }
-                // Store the discriminant value for this variant
-                if let Some(discriminant) = crate::lowering::get_enum_variant(enum_name, variant_name) {
+                    self.lower_expression_to_place(builder, arg, Place::Local(temp.clone()))?;
+                    payload.push(Operand::Copy(Place::Local(temp)));
+                }
+                let discriminant = crate::lowering::get_enum_variant(enum_name, variant_name);
+                if let (Some(discriminant), true) = (discriminant, crate::lowering::enum_has_payload(enum_name)) {
+                    // Payload enums are built as [tag][payload...] so match arms can bind the fields.
+                    // Every variant gets the largest variant's payload area, zero-filled past its own fields.
+                    let slots = crate::lowering::enum_payload_slots(enum_name).max(payload.len());
+                    let mut fields = vec![Operand::Constant(Constant::Integer(discriminant))];
+                    fields.extend(payload);
+                    fields.resize(slots + 1, Operand::Constant(Constant::Integer(0)));
+                    builder.add_statement(place, Rvalue::Call("__enum_new".to_string(), fields));
+                } else if let Some(discriminant) = discriminant {
+                    // Store the discriminant value for this variant
                     builder.add_statement(place, Rvalue::Use(Operand::Constant(Constant::Integer(discriminant))));
                 } else {
                     // Unknown variant, default to 0
@@ -1767,6 +2491,15 @@ impl MirLowerer {
                 }
             }
             HirExpression::MethodCall { receiver, method, args } => {
+                // An unannotated Vec holds whatever typed value is pushed onto it
+                if let (HirExpression::Variable(vec_name), "push", [value]) = (receiver.as_ref(), method.as_str(), args.as_slice()) {
+                    if !self.vec_element_types.contains_key(vec_name) {
+                        if let Some(elem) = self.pushed_element_type(value) {
+                            self.vec_element_types.insert(vec_name.clone(), elem);
+                        }
+                    }
+                }
+
                 // Evaluate receiver to a temporary
                 let receiver_temp = builder.gen_temp();
                 self.lower_expression_to_place(builder, receiver, Place::Local(receiver_temp.clone()))?;
@@ -1776,7 +2509,11 @@ impl MirLowerer {
                     HirExpression::Variable(var_name) => {
                         // First check if it's a known local variable with a tracked type
                         if let Some(ty) = self.local_types.get(var_name).cloned() {
-                            Some(ty)
+                            // Methods on `&self` resolve against the struct itself
+                            match ty {
+                                HirType::Reference(inner) | HirType::MutableReference(inner) if matches!(*inner, HirType::Named(_)) => Some(*inner),
+                                ty => Some(ty),
+                            }
                         } else {
                             // Otherwise, the variable name might be a struct type itself
                             // (e.g., unit structs used as values like `let dog = Dog;`)
@@ -1792,7 +2529,16 @@ impl MirLowerer {
                         // String literals are String type
                         Some(HirType::String)
                     }
-                    HirExpression::FieldAccess { object, field } => {
+                    // Numeric literals: `5.pow(2)`, `2.0.sqrt()`
+                    HirExpression::Integer(_) => Some(HirType::Int64),
+                    HirExpression::Float(_) => Some(HirType::Float64),
+                    HirExpression::MethodCall { method: inner_method, .. }
+                        if matches!(inner_method.as_str(), "iter" | "into_iter" | "map" | "filter" | "take" | "skip" | "chain") =>
+                    {
+                        // Adaptor chains: v.iter().sum(), v.iter().map(f), ...
+                        Some(HirType::Named("Iterator".to_string()))
+                    }
+                    HirExpression::FieldAccess { object, field, .. } => {
                         // For field accesses like self.items, check if field name is a collection
                         // e.g., if field is "items" it's likely a Vec or collection type
                         if method == "push" || method == "pop" || method == "get" || method == "len" {
@@ -1805,7 +2551,8 @@ impl MirLowerer {
                 };
                 
                 // Handle primitive type trait methods by converting to binary ops or assignments
-                if receiver_type.is_none() && args.len() == 1 {
+                let is_primitive_receiver = matches!(receiver_type, None | Some(HirType::Int64) | Some(HirType::Float64));
+                if is_primitive_receiver && args.len() == 1 {
                     match method.as_str() {
                         // Arithmetic methods on primitives - convert to binary ops
                         "add" | "sub" | "mul" | "div" | "rem" => {
@@ -1862,11 +2609,25 @@ impl MirLowerer {
                 }
 
                 // Map built-in collection methods to runtime functions
-                let func_name = if let Some(struct_type) = receiver_type {
+                let func_name = if let Some(struct_type) = receiver_type.clone() {
+                    // Trait objects carry no vtable pointer to dispatch through yet
+                    let mut pointee = &struct_type;
+                    while let HirType::Reference(inner) | HirType::MutableReference(inner) | HirType::Box(inner) = pointee {
+                        pointee = inner;
+                    }
+                    if let HirType::DynTrait { trait_name } = pointee {
+                        return Err(MirError {
+                            message: format!(
+                                "cannot call `{}` through `dyn {}`: dynamic dispatch is not supported yet",
+                                method, trait_name
+                            ),
+                        });
+                    }
                     // Convert HirType to string for matching
                     let type_str = match &struct_type {
                         HirType::Named(n) => n.clone(),
                         HirType::String => "String".to_string(),
+                        HirType::Vec(_) => "Vec".to_string(),
                         _ => format!("{}", struct_type),
                     };
                     
@@ -1876,9 +2637,20 @@ impl MirLowerer {
                             match method.as_str() {
                                 "push" => "gaia_vec_push".to_string(),
                                 "pop" => "gaia_vec_pop".to_string(),
-                                "get" => "gaia_vec_get".to_string(),
+                                "get" => "gaia_vec_get_checked".to_string(),
                                 "len" => "gaia_vec_len".to_string(),
+                                "clone" => "gaia_vec_clone".to_string(),
+                                "sort" | "sort_unstable" => "gaia_vec_sort".to_string(),
                                 "into_iter" => "Vec::into_iter".to_string(),
+                                // Iterating in place: the iterator walks the Vec's own buffer
+                                "iter" | "iter_mut" => "__into_iter".to_string(),
+                                _ => format!("{}::{}", type_str, method),
+                            }
+                        }
+                        "Array" => {
+                            match method.as_str() {
+                                // Arrays are wrapped with a [capacity][length] header by __into_iter
+                                "iter" | "into_iter" => "__into_iter".to_string(),
                                 _ => format!("{}::{}", type_str, method),
                             }
                         }
@@ -1886,6 +2658,15 @@ impl MirLowerer {
                             // Iterator methods - use qualified names
                             format!("Iterator::{}", method)
                         }
+                        "i32" | "i64" | "u32" | "u64" | "usize" | "isize" => {
+                            match method.as_str() {
+                                "pow" => "gaia_int_pow".to_string(),
+                                "abs" => "gaia_int_abs".to_string(),
+                                _ => format!("{}::{}", type_str, method),
+                            }
+                        }
+                        // Float math (sqrt, pow, floor, ...) lives in the String_impl_* runtime
+                        "f64" => format!("String::{}", method),
                         "String" => {
                             // String methods - map to runtime functions
                             match method.as_str() {
@@ -1915,7 +2696,30 @@ impl MirLowerer {
                 // For methods with &self, we pass a reference to the receiver, not the value
                 // The receiver is already stored at a stack location (receiver_temp)
                 // We pass the stack location which will be treated as a pointer by the callee
-                let mut operands = vec![Operand::Copy(Place::Local(receiver_temp))];
+                // A struct or primitive receiver holds the value itself, so a
+                // `&self` or `&mut self` method needs its address, which is
+                // also what lets `&mut self` changes reach the caller's value
+                let holds_value = matches!(self.struct_of(receiver), Some((_, false)))
+                    || matches!(
+                        receiver_type,
+                        Some(HirType::Int32 | HirType::Int64 | HirType::UInt32 | HirType::UInt64 | HirType::USize | HirType::ISize | HirType::Float64 | HirType::Bool)
+                    );
+                let by_ref = self.ref_self_methods.contains(&func_name) && holds_value;
+                let receiver_operand = if by_ref {
+                    let receiver_place = match &**receiver {
+                        HirExpression::Variable(var_name) => Place::Local(var_name.clone()),
+                        _ => Place::Local(receiver_temp),
+                    };
+                    let receiver_ref = builder.gen_temp();
+                    builder.add_statement(
+                        Place::Local(receiver_ref.clone()),
+                        Rvalue::UnaryOp(UnaryOp::Reference, Operand::Copy(receiver_place)),
+                    );
+                    Operand::Copy(Place::Local(receiver_ref))
+                } else {
+                    Operand::Copy(Place::Local(receiver_temp))
+                };
+                let mut operands = vec![receiver_operand];
                 for arg in args {
                     let arg_temp = builder.gen_temp();
                     self.lower_expression_to_place(builder, arg, Place::Local(arg_temp.clone()))?;
@@ -1944,6 +2748,7 @@ impl MirOptimizer {
         for func in &mut mir.functions {
             // O1+ passes
             Self::constant_fold(&mut func.basic_blocks)?;
+            Self::eliminate_known_asserts(&mut func.basic_blocks)?;
             Self::dead_code_elimination(&mut func.basic_blocks)?;
 
             // O2+ passes
@@ -1982,28 +2787,54 @@ impl MirOptimizer {
         Ok(())
     }
 
+    /// O1 Pass: Assert Elimination - Turn asserts whose condition is known
+    /// to hold (after constant folding) into plain gotos
+    fn eliminate_known_asserts(blocks: &mut [BasicBlock]) -> MirResult<()> {
+        for block in blocks {
+            let Terminator::Assert(cond, _, target) = &block.terminator else {
+                continue;
+            };
+            if Self::known_true(&block.statements, cond) {
+                block.terminator = Terminator::Goto(*target);
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `operand` is known to hold from the `statements` before it:
+    /// the condition is computed into temporaries just before the assert,
+    /// as a folded constant or the `&&` of such
+    fn known_true(statements: &[Statement], operand: &Operand) -> bool {
+        match operand {
+            Operand::Constant(Constant::Bool(b)) => *b,
+            Operand::Copy(place) | Operand::Move(place) => {
+                let Some(def) = statements.iter().rposition(|stmt| stmt.place == *place) else {
+                    return false;
+                };
+                let earlier = &statements[..def];
+                match &statements[def].rvalue {
+                    Rvalue::Use(value) => Self::known_true(earlier, value),
+                    Rvalue::BinaryOp(BinaryOp::And, left, right) => {
+                        Self::known_true(earlier, left) && Self::known_true(earlier, right)
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
     /// Fold binary operations with constant operands
     fn fold_binary_op(op: &BinaryOp, left: &Constant, right: &Constant) -> Option<Constant> {
         match (left, right) {
             (Constant::Integer(l), Constant::Integer(r)) => {
+                // Overflowing (or dividing by zero) leaves the operation for the runtime
                 Some(match op {
-                    BinaryOp::Add => Constant::Integer(l + r),
-                    BinaryOp::Subtract => Constant::Integer(l - r),
-                    BinaryOp::Multiply => Constant::Integer(l * r),
-                    BinaryOp::Divide => {
-                        if *r != 0 {
-                            Constant::Integer(l / r)
-                        } else {
-                            return None;
-                        }
-                    }
-                    BinaryOp::Modulo => {
-                        if *r != 0 {
-                            Constant::Integer(l % r)
-                        } else {
-                            return None;
-                        }
-                    }
+                    BinaryOp::Add => Constant::Integer(l.checked_add(*r)?),
+                    BinaryOp::Subtract => Constant::Integer(l.checked_sub(*r)?),
+                    BinaryOp::Multiply => Constant::Integer(l.checked_mul(*r)?),
+                    BinaryOp::Divide => Constant::Integer(l.checked_div(*r)?),
+                    BinaryOp::Modulo => Constant::Integer(l.checked_rem(*r)?),
                     BinaryOp::Equal => Constant::Bool(l == r),
                     BinaryOp::NotEqual => Constant::Bool(l != r),
                     BinaryOp::Less => Constant::Bool(l < r),
@@ -2015,8 +2846,8 @@ impl MirOptimizer {
                     BinaryOp::BitwiseXor => Constant::Integer(l ^ r),
                     BinaryOp::BitwiseAnd => Constant::Integer(l & r),
                     BinaryOp::BitwiseOr => Constant::Integer(l | r),
-                    BinaryOp::LeftShift => Constant::Integer(l << r),
-                    BinaryOp::RightShift => Constant::Integer(l >> r),
+                    BinaryOp::LeftShift => Constant::Integer(l.checked_shl(u32::try_from(*r).ok()?)?),
+                    BinaryOp::RightShift => Constant::Integer(l.checked_shr(u32::try_from(*r).ok()?)?),
                 })
             }
             (Constant::Float(l), Constant::Float(r)) => {
@@ -2083,6 +2914,11 @@ impl MirOptimizer {
                 Terminator::If(cond, _, _) => {
                     Self::collect_places_from_operand(cond, &mut used_places);
                 }
+                Terminator::Assert(cond, AssertKind::BoundsCheck { len, index }, _) => {
+                    for operand in [cond, len, index] {
+                        Self::collect_places_from_operand(operand, &mut used_places);
+                    }
+                }
                 Terminator::Return(Some(operand)) => {
                     Self::collect_places_from_operand(operand, &mut used_places);
                 }
@@ -2091,7 +2927,7 @@ impl MirOptimizer {
         }
 
         // Mark all operands in statements (right-hand side)
-        // Also mark the inner place of Deref assignments as used
+        // Also mark the inner place of Deref and indexed assignments as used
         for block in blocks.iter() {
             for stmt in &block.statements {
                 Self::collect_places_from_rvalue(&stmt.rvalue, &mut used_places);
@@ -2099,6 +2935,11 @@ impl MirOptimizer {
                 if let Place::Deref(inner) = &stmt.place {
                     used_places.insert((**inner).clone());
                 }
+                // An indexed store reads both the collection and the index
+                if let Place::DynamicIndex(base, index) = &stmt.place {
+                    used_places.insert((**base).clone());
+                    used_places.insert(Place::Local(index.clone()));
+                }
             }
         }
 
@@ -2106,8 +2947,8 @@ impl MirOptimizer {
         for block in blocks {
             block.statements.retain(|stmt| {
                 // Keep statement if its target is used, if it has side effects, 
-                // or if it's a dereference assignment (which has side effects: writes to memory)
-                let is_deref = matches!(&stmt.place, crate::mir::Place::Deref(_));
+                // or if it's a dereference or indexed assignment (which has side effects: writes to memory)
+                let is_deref = matches!(&stmt.place, crate::mir::Place::Deref(_) | crate::mir::Place::DynamicIndex(_, _));
                 used_places.contains(&stmt.place) || Self::has_side_effects(&stmt.rvalue) || is_deref
             });
         }
@@ -2132,7 +2973,7 @@ impl MirOptimizer {
             Place::Local(_) => {
                 places.insert(place.clone());
             }
-            Place::Field(inner, _) | Place::Index(inner, _) | Place::Deref(inner) => {
+            Place::Field(inner, _) | Place::Index(inner, _) | Place::DynamicIndex(inner, _) | Place::Deref(inner) => {
                 // Recursively collect from the inner place
                 Self::collect_places_from_place(inner, places);
                 // Also add this place itself
@@ -2219,7 +3060,7 @@ impl MirOptimizer {
                                 true
                             } else {
                                 match &b.terminator {
-                                    Terminator::Goto(t) => *t != i + 1,
+                                    Terminator::Goto(t) | Terminator::Assert(_, _, t) => *t != i + 1,
                                     Terminator::If(_, t, e) => *t != i + 1 && *e != i + 1,
                                     _ => true,
                                 }
@@ -2234,19 +3075,20 @@ impl MirOptimizer {
                 }
             }
 
-            // Perform merges in reverse order to maintain indices
-            for i in to_merge.iter().rev() {
-                if *i + 1 < blocks.len() {
-                    let mut next_block = blocks.remove(*i + 1);
-                    blocks[*i].statements.append(&mut next_block.statements);
-                    blocks[*i].terminator = next_block.terminator;
-                }
+            // Perform merges in reverse order so chains collapse into the first block
+            for &i in to_merge.iter().rev() {
+                let next_block = std::mem::replace(
+                    &mut blocks[i + 1],
+                    BasicBlock { statements: Vec::new(), terminator: Terminator::Unreachable },
+                );
+                blocks[i].statements.extend(next_block.statements);
+                blocks[i].terminator = next_block.terminator;
             }
-
-            // Update references after merging
-            Self::update_block_references(blocks);
         }
 
+        // Merged-away blocks and goto-chain intermediates are now unreachable;
+        // drop them and renumber the remaining targets
+        *blocks = remove_unreachable_blocks(std::mem::take(blocks));
         Ok(())
     }
 
@@ -2273,26 +3115,16 @@ impl MirOptimizer {
         target
     }
 
-    /// Update block reference indices after removing blocks
-    fn update_block_references(blocks: &mut [BasicBlock]) {
-        // This is a simplified version - in practice would need to track removed blocks
-        let max_idx = blocks.len().saturating_sub(1);
-        for block in blocks.iter_mut() {
-            match &mut block.terminator {
-                Terminator::Goto(ref mut t) => {
-                    *t = (*t).min(max_idx);
-                }
-                Terminator::If(_, ref mut then_bb, ref mut else_bb) => {
-                    *then_bb = (*then_bb).min(max_idx);
-                    *else_bb = (*else_bb).min(max_idx);
-                }
-                _ => {}
-            }
-        }
-    }
-
     /// O3 Pass: Copy Propagation - Replace variables with their definitions
     fn copy_propagation(blocks: &mut [BasicBlock]) -> MirResult<()> {
+        // A copy can only stand in for its destination when neither side is
+        // ever reassigned; after SSA renaming that's every local but the joins
+        let mut definitions: HashMap<&Place, usize> = HashMap::new();
+        for stmt in blocks.iter().flat_map(|block| &block.statements) {
+            *definitions.entry(&stmt.place).or_default() += 1;
+        }
+        let assigned_once = |place: &Place| definitions.get(place).copied().unwrap_or(0) <= 1;
+
         // Build a map of: place -> place it was assigned from (for simple copies)
         let mut copy_map: HashMap<Place, Operand> = HashMap::new();
 
@@ -2300,7 +3132,9 @@ impl MirOptimizer {
         for block in blocks.iter() {
             for stmt in &block.statements {
                 match &stmt.rvalue {
-                    Rvalue::Use(op @ (Operand::Copy(_) | Operand::Move(_))) => {
+                    Rvalue::Use(op @ (Operand::Copy(source) | Operand::Move(source)))
+                        if assigned_once(&stmt.place) && assigned_once(source) =>
+                    {
                         copy_map.insert(stmt.place.clone(), op.clone());
                     }
                     _ => {} // Not a simple copy
@@ -2315,7 +3149,7 @@ impl MirOptimizer {
             }
 
             match &mut block.terminator {
-                Terminator::If(op, _, _) => {
+                Terminator::If(op, _, _) | Terminator::Assert(op, _, _) => {
                     if let Operand::Move(ref mut place) | Operand::Copy(ref mut place) = op {
                         if let Some(Operand::Copy(orig) | Operand::Move(orig)) = copy_map.get(place) {
                             *place = orig.clone();
@@ -0,0 +1,249 @@
+//! # SSA Renaming
+//!
+//! Lowering reuses one local for every assignment to a variable (loop
+//! counters, `if` results), so a local can have many definitions. This pass
+//! gives each definition of a scalar local its own name, so a read names
+//! exactly the value it sees and passes like copy propagation can rewrite
+//! through it without tracking reassignment.
+//!
+//! MIR has no phi nodes. Where different versions of a local reach a block
+//! with several predecessors, the block gets a join local and every
+//! predecessor copies its version into it just before leaving
+//! (copy-on-join). Join locals are the only locals left with more than one
+//! definition.
+
+use super::{successors, MirFunction, Operand, Place, Rvalue, Statement, Terminator};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Rename `func`'s locals into SSA form, returning the join locals the pass
+/// introduced
+pub fn rename_to_ssa(func: &mut MirFunction) -> HashSet<String> {
+    let mut next_id = func
+        .basic_blocks
+        .iter()
+        .flat_map(|block| &block.statements)
+        .map(|stmt| stmt.id + 1)
+        .max()
+        .unwrap_or(0);
+
+    let mut joins = HashSet::new();
+    for local in renameable_locals(func) {
+        joins.extend(rename_local(func, &local, &mut next_id));
+    }
+    joins
+}
+
+/// Locals assigned more than once (or parameters assigned at all) that are
+/// only ever read and written whole. Anything addressed through a field,
+/// index, deref or reference, aggregates, and closures called by name keep
+/// their single storage slot.
+fn renameable_locals(func: &MirFunction) -> BTreeSet<String> {
+    let mut definitions: HashMap<&str, usize> = HashMap::new();
+    let mut pinned: HashSet<&str> = HashSet::new();
+
+    for block in &func.basic_blocks {
+        for stmt in &block.statements {
+            match &stmt.place {
+                Place::Local(name) => *definitions.entry(name).or_default() += 1,
+                place => pin_place(place, &mut pinned),
+            }
+            if let Place::DynamicIndex(_, index) = &stmt.place {
+                pinned.insert(index);
+            }
+            match &stmt.rvalue {
+                Rvalue::Aggregate(..) | Rvalue::Array(_) | Rvalue::Closure { .. } | Rvalue::Ref(_) => {
+                    if let Place::Local(name) = &stmt.place {
+                        pinned.insert(name);
+                    }
+                }
+                _ => {}
+            }
+            match &stmt.rvalue {
+                Rvalue::Ref(place) | Rvalue::Deref(place) | Rvalue::Field(place, _) | Rvalue::Index(place, _) => {
+                    pinned.insert(root(place));
+                    pin_place(place, &mut pinned);
+                }
+                Rvalue::Call(callee, _) => {
+                    pinned.insert(callee);
+                }
+                _ => {}
+            }
+            for operand in operands(&stmt.rvalue) {
+                if let Operand::Copy(place) | Operand::Move(place) = operand {
+                    pin_place(place, &mut pinned);
+                }
+            }
+        }
+        for operand in terminator_operands(&block.terminator) {
+            if let Operand::Copy(place) | Operand::Move(place) = operand {
+                pin_place(place, &mut pinned);
+            }
+        }
+    }
+
+    let params: HashSet<&str> = func.params.iter().map(|(name, _)| name.as_str()).collect();
+    definitions
+        .into_iter()
+        .filter(|(name, count)| *count > 1 || (params.contains(name) && *count > 0))
+        .filter(|(name, _)| !pinned.contains(name))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// The local a place is rooted at
+fn root(place: &Place) -> &str {
+    match place {
+        Place::Local(name) => name,
+        Place::Field(inner, _) | Place::Index(inner, _) | Place::DynamicIndex(inner, _) | Place::Deref(inner) => {
+            root(inner)
+        }
+    }
+}
+
+/// Pin the root of a projected place; a plain local is left alone
+fn pin_place<'a>(place: &'a Place, pinned: &mut HashSet<&'a str>) {
+    if !matches!(place, Place::Local(_)) {
+        pinned.insert(root(place));
+    }
+}
+
+fn operands(rvalue: &Rvalue) -> Vec<&Operand> {
+    match rvalue {
+        Rvalue::Use(op) | Rvalue::UnaryOp(_, op) | Rvalue::Index(_, op) => vec![op],
+        Rvalue::BinaryOp(_, l, r) => vec![l, r],
+        Rvalue::Call(_, args) | Rvalue::Aggregate(_, args) | Rvalue::Array(args) => args.iter().collect(),
+        Rvalue::Closure { captures, .. } => captures.iter().collect(),
+        Rvalue::Ref(_) | Rvalue::Deref(_) | Rvalue::Field(..) => Vec::new(),
+    }
+}
+
+fn operands_mut(rvalue: &mut Rvalue) -> Vec<&mut Operand> {
+    match rvalue {
+        Rvalue::Use(op) | Rvalue::UnaryOp(_, op) | Rvalue::Index(_, op) => vec![op],
+        Rvalue::BinaryOp(_, l, r) => vec![l, r],
+        Rvalue::Call(_, args) | Rvalue::Aggregate(_, args) | Rvalue::Array(args) => args.iter_mut().collect(),
+        Rvalue::Closure { captures, .. } => captures.iter_mut().collect(),
+        Rvalue::Ref(_) | Rvalue::Deref(_) | Rvalue::Field(..) => Vec::new(),
+    }
+}
+
+fn terminator_operands(terminator: &Terminator) -> Vec<&Operand> {
+    match terminator {
+        Terminator::If(cond, _, _) => vec![cond],
+        Terminator::Assert(cond, super::AssertKind::BoundsCheck { len, index }, _) => vec![cond, len, index],
+        Terminator::Return(Some(op)) => vec![op],
+        Terminator::Goto(_) | Terminator::Return(None) | Terminator::Unreachable => Vec::new(),
+    }
+}
+
+fn terminator_operands_mut(terminator: &mut Terminator) -> Vec<&mut Operand> {
+    match terminator {
+        Terminator::If(cond, _, _) => vec![cond],
+        Terminator::Assert(cond, super::AssertKind::BoundsCheck { len, index }, _) => vec![cond, len, index],
+        Terminator::Return(Some(op)) => vec![op],
+        Terminator::Goto(_) | Terminator::Return(None) | Terminator::Unreachable => Vec::new(),
+    }
+}
+
+/// Point reads of `local` at `current`
+fn rename_reads<'a>(operands: impl IntoIterator<Item = &'a mut Operand>, local: &str, current: &str) {
+    for operand in operands {
+        if let Operand::Copy(Place::Local(name)) | Operand::Move(Place::Local(name)) = operand {
+            if name == local {
+                *name = current.to_string();
+            }
+        }
+    }
+}
+
+/// Give every definition of `local` its own name and join the versions
+/// that meet at a block. Returns the join locals.
+fn rename_local(func: &mut MirFunction, local: &str, next_id: &mut usize) -> Vec<String> {
+    let blocks = &mut func.basic_blocks;
+    let count = blocks.len();
+    let mut predecessors = vec![Vec::new(); count];
+    for (index, block) in blocks.iter().enumerate() {
+        for target in successors(&block.terminator) {
+            if target < count {
+                predecessors[target].push(index);
+            }
+        }
+    }
+    // A join at the entry block would need a copy before the function starts
+    if count == 0 || !predecessors[0].is_empty() {
+        return Vec::new();
+    }
+
+    let version = |id: usize| format!("{}_ssa{}", local, id);
+    let join = |block: usize| format!("{}_phi{}", local, block);
+    // The version a block leaves with: its last definition, else what it entered with
+    let last_definition: Vec<Option<String>> = blocks
+        .iter()
+        .map(|block| {
+            block.statements.iter().rev().find_map(|stmt| match &stmt.place {
+                Place::Local(name) if name == local => Some(version(stmt.id)),
+                _ => None,
+            })
+        })
+        .collect();
+
+    // Which version each block enters with, iterated to a fixed point so
+    // loop back edges are seen. `None` means no path has reached it yet.
+    let mut entry: Vec<Option<String>> = vec![None; count];
+    let mut is_join = vec![false; count];
+    entry[0] = Some(local.to_string());
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in 1..count {
+            let incoming: BTreeSet<&String> = predecessors[block]
+                .iter()
+                .filter_map(|&pred| last_definition[pred].as_ref().or(entry[pred].as_ref()))
+                .collect();
+            if incoming.len() > 1 {
+                is_join[block] = true;
+            }
+            let name = if is_join[block] { Some(join(block)) } else { incoming.into_iter().next().cloned() };
+            if name != entry[block] {
+                entry[block] = name;
+                changed = true;
+            }
+        }
+    }
+
+    let exit: Vec<String> = (0..count)
+        .map(|block| {
+            last_definition[block]
+                .clone()
+                .or_else(|| entry[block].clone())
+                .unwrap_or_else(|| local.to_string())
+        })
+        .collect();
+
+    for (index, block) in blocks.iter_mut().enumerate() {
+        let mut current = entry[index].clone().unwrap_or_else(|| local.to_string());
+        for stmt in &mut block.statements {
+            rename_reads(operands_mut(&mut stmt.rvalue), local, &current);
+            if matches!(&stmt.place, Place::Local(name) if name == local) {
+                current = version(stmt.id);
+                stmt.place = Place::Local(current.clone());
+            }
+        }
+        rename_reads(terminator_operands_mut(&mut block.terminator), local, &current);
+    }
+
+    let mut joins = Vec::new();
+    for block in (0..count).filter(|&block| is_join[block]) {
+        let name = join(block);
+        for &pred in &predecessors[block] {
+            blocks[pred].statements.push(Statement {
+                id: *next_id,
+                place: Place::Local(name.clone()),
+                rvalue: Rvalue::Use(Operand::Copy(Place::Local(exit[pred].clone()))),
+            });
+            *next_id += 1;
+        }
+        joins.push(name);
+    }
+    joins
+}
@@ -5,6 +5,8 @@
 
 use std::fmt;
 
+use crate::utilities::error_reporting::SourceLocation;
+
 /// A complete Rust program is a list of items (functions, structs, etc.)
 pub type Program = Vec<Item>;
 
@@ -39,6 +41,8 @@ pub enum Item {
         is_pub: bool,
         attributes: Vec<Attribute>,
         abi: Option<String>,
+        /// Where the function's name is in the source, when known
+        location: Option<SourceLocation>,
     },
     /// Struct definition: `struct Name { field: Type, ... }`
     Struct {
@@ -72,6 +76,8 @@ pub enum Item {
     Impl {
         generics: Vec<GenericParam>,
         trait_name: Option<String>,
+        /// Type arguments of the trait: `[i64]` for `impl From<i64> for Celsius`
+        trait_args: Vec<Type>,
         struct_name: String,
         methods: Vec<Item>,
         where_clause: Vec<WhereConstraint>,
@@ -137,6 +143,13 @@ pub enum Item {
         ty: Option<Type>,
         attributes: Vec<Attribute>,
     },
+    /// Associated const in a trait or impl: `const MAX: i64;` or `const MAX: i64 = 10;`
+    AssociatedConst {
+        name: String,
+        ty: Type,
+        value: Option<Expression>,
+        attributes: Vec<Attribute>,
+    },
 }
 
 /// A trait bound that may include associated types
@@ -194,6 +207,7 @@ pub struct Parameter {
     pub name: String,
     pub mutable: bool,
     pub ty: Type,
+    pub attributes: Vec<Attribute>,
 }
 
 /// A struct field: `name: Type`
@@ -353,6 +367,7 @@ pub enum Expression {
     FieldAccess {
         object: Box<Expression>,
         field: String,
+        location: Option<SourceLocation>,
     },
 
     // Array: `[1, 2, 3]`
@@ -526,6 +541,12 @@ pub enum Pattern {
         path: Vec<String>,
         data: Option<Box<Pattern>>,
     },
+    /// Binding pattern: `n @ 1..=5` binds the whole value to `n` when the
+    /// inner pattern matches
+    Binding {
+        name: String,
+        pattern: Box<Pattern>,
+    },
 }
 
 /// Types in Rust
@@ -34,6 +34,7 @@
 pub mod ast;
 
 use crate::lexer::token::{Token, Keyword};
+use crate::utilities::error_reporting::SourceLocation;
 use std::fmt;
 use std::cell::RefCell;
 use std::io::Write;
@@ -89,9 +90,14 @@ enum Restrictions {
     NoStructLiteral,
 }
 
+/// Binding power shared by `==`, `!=`, `<`, `<=`, `>` and `>=`
+const COMPARISON_PRECEDENCE: u8 = 3;
+
 /// The main parser struct
 pub struct Parser {
     tokens: Vec<Token>,
+    /// Where each token starts in the source; empty when unknown
+    locations: Vec<SourceLocation>,
     position: usize,
     restrictions: Restrictions,
     errors: Vec<ParseError>,
@@ -103,6 +109,7 @@ impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
         Parser { 
             tokens, 
+            locations: Vec::new(),
             position: 0, 
             restrictions: Restrictions::None,
             errors: Vec::new(),
@@ -110,6 +117,18 @@ impl Parser {
         }
     }
 
+    /// Record where each token starts in the source, so items can be
+    /// located in diagnostics
+    pub fn with_locations(mut self, locations: Vec<SourceLocation>) -> Self {
+        self.locations = locations;
+        self
+    }
+
+    /// Where the current token starts in the source, when known
+    fn current_location(&self) -> Option<SourceLocation> {
+        self.locations.get(self.position).copied()
+    }
+
     /// Get accumulated errors
     pub fn get_errors(&self) -> Vec<ParseError> {
         self.errors.clone()
@@ -350,6 +369,7 @@ impl Parser {
             Expression::Loop(_) |
             Expression::While { .. } |
             Expression::Block(_) |
+            Expression::UnsafeBlock(_) |
             Expression::For { .. }
         )
     }
@@ -367,16 +387,16 @@ impl Parser {
         Ok(items)
     }
 
-    /// Parse a top-level item (function, struct, enum, trait, impl, mod, use)
-    fn parse_item(&mut self) -> ParseResult<Item> {
-        // Parse attributes (#[...])
+    /// Parse the `#[...]` attributes in front of an item, struct field or
+    /// parameter
+    fn parse_outer_attributes(&mut self) -> ParseResult<Vec<Attribute>> {
         let mut attributes = Vec::new();
         while self.check(&Token::Hash) {
             self.advance(); // consume #
             if self.check(&Token::LeftBracket) {
                 self.advance(); // consume [
                 // Parse attribute contents
-                let attr_name = if self.current() != &Token::RightBracket {
+                if self.current() != &Token::RightBracket {
                     let name = self.expect_identifier()?;
                     let mut args = Vec::new();
                     
@@ -404,33 +424,29 @@ impl Parser {
                         args,
                         is_macro: true,
                     });
-                    
-                    Some(())
-                } else {
-                    None
-                };
+                }
                 
-                // Skip until we find the matching ]
-                if attr_name.is_some() {
-                    if self.check(&Token::RightBracket) {
-                        self.advance();
-                    }
-                } else {
-                    let mut bracket_depth = 1;
-                    while bracket_depth > 0 && self.current() != &Token::Eof {
-                        match self.current() {
-                            Token::LeftBracket => bracket_depth += 1,
-                            Token::RightBracket => bracket_depth -= 1,
-                            _ => {}
-                        }
-                        self.advance();
+                // Skip the rest (`= "value"`, nested groups) up to the matching ]
+                let mut bracket_depth = 1;
+                while bracket_depth > 0 && self.current() != &Token::Eof {
+                    match self.current() {
+                        Token::LeftBracket => bracket_depth += 1,
+                        Token::RightBracket => bracket_depth -= 1,
+                        _ => {}
                     }
+                    self.advance();
                 }
             } else {
                 // Malformed attribute, but continue parsing
                 break;
             }
         }
+        Ok(attributes)
+    }
+
+    /// Parse a top-level item (function, struct, enum, trait, impl, mod, use)
+    fn parse_item(&mut self) -> ParseResult<Item> {
+        let attributes = self.parse_outer_attributes()?;
 
         // Handle visibility modifiers (pub, pub(crate), pub(super), pub(in path), etc.)
         let visibility = if self.check(&Token::Keyword(Keyword::Pub)) {
@@ -491,12 +507,18 @@ impl Parser {
             ast::Visibility::Private
         };
 
-        // Handle extern functions (skip the ABI string)
+        // Handle extern functions (skip the ABI string) and extern blocks
         if self.check(&Token::Keyword(Keyword::Extern)) {
             self.advance();
-            // Skip the ABI string (e.g., "C")
-            if let Token::String(_) = self.current() {
+            let abi = if let Token::String(abi) = self.current() {
+                let abi = abi.clone();
                 self.advance();
+                abi
+            } else {
+                "C".to_string()
+            };
+            if self.check(&Token::LeftBrace) {
+                return self.parse_extern_block(abi, attributes);
             }
         }
 
@@ -525,27 +547,37 @@ impl Parser {
             Token::Keyword(Keyword::Fn) => {
                 // PHASE 4.1: Handle unsafe fn
                 let mut func_item = self.parse_function(is_pub)?;
-                if is_item_unsafe {
-                    if let Item::Function { ref mut is_unsafe, .. } = func_item {
-                        *is_unsafe = true;
-                    }
+                if let Item::Function { ref mut is_unsafe, attributes: ref mut func_attributes, .. } = func_item {
+                    *is_unsafe |= is_item_unsafe;
+                    *func_attributes = attributes;
                 }
                 Ok(func_item)
             }
             Token::Keyword(Keyword::Struct) => self.parse_struct_with_attributes(attributes, is_pub),
             Token::Keyword(Keyword::Enum) => self.parse_enum(is_pub),
-            Token::Keyword(Keyword::Trait) => self.parse_trait(),
+            Token::Keyword(Keyword::Trait) => {
+                let mut trait_item = self.parse_trait()?;
+                if let Item::Trait { attributes: ref mut trait_attributes, .. } = trait_item {
+                    *trait_attributes = attributes;
+                }
+                Ok(trait_item)
+            }
             Token::Keyword(Keyword::Impl) => {
                 // PHASE 4.1: Handle unsafe impl
                 let mut impl_item = self.parse_impl()?;
-                if is_item_unsafe {
-                    if let Item::Impl { ref mut is_unsafe, .. } = impl_item {
-                        *is_unsafe = true;
-                    }
+                if let Item::Impl { ref mut is_unsafe, attributes: ref mut impl_attributes, .. } = impl_item {
+                    *is_unsafe |= is_item_unsafe;
+                    *impl_attributes = attributes;
                 }
                 Ok(impl_item)
             }
-            Token::Keyword(Keyword::Mod) => self.parse_module(is_pub),
+            Token::Keyword(Keyword::Mod) => {
+                let mut module = self.parse_module(is_pub)?;
+                if let Item::Module { attributes: ref mut module_attributes, .. } = module {
+                    *module_attributes = attributes;
+                }
+                Ok(module)
+            }
             Token::Keyword(Keyword::Use) => self.parse_use(is_pub),
             Token::Keyword(Keyword::Const) => self.parse_const_item(is_pub),
             Token::Keyword(Keyword::Static) => self.parse_static_item(is_pub),
@@ -588,13 +620,14 @@ impl Parser {
         };
 
         self.expect_keyword(Keyword::Fn)?;
+        let location = self.current_location();
         let name = self.expect_identifier()?;
 
         // Parse generic parameters
         let generics = self.parse_generics()?;
 
         // Parse where clause
-        let where_clause = self.parse_where_clause()?;
+        let mut where_clause = self.parse_where_clause()?;
 
         self.consume("(")?;
         let params = self.parse_parameters()?;
@@ -607,11 +640,10 @@ impl Parser {
             None
         };
 
-        // Skip where clause if present (consume but ignore for now)
+        // The where clause proper follows the signature. Bounds it can't
+        // record yet (`Iterator<Item = i32>`) are skipped up to the body.
         if self.check(&Token::Keyword(Keyword::Where)) {
-            self.advance();
-            
-            // Skip until we find {
+            where_clause.extend(self.parse_where_clause()?);
             while !self.check(&Token::LeftBrace) && !self.check(&Token::Eof) {
                 self.advance();
             }
@@ -634,14 +666,61 @@ impl Parser {
             attributes: Vec::new(),
             where_clause,
             abi,
+            location,
         })
     }
 
+    /// Parse the body of `extern "ABI" { fn name(params) -> T; ... }`:
+    /// foreign function declarations, which have no body
+    fn parse_extern_block(&mut self, abi: String, attributes: Vec<Attribute>) -> ParseResult<Item> {
+        self.consume("{")?;
+        let mut items = Vec::new();
+        while !self.check(&Token::RightBrace) && self.current() != &Token::Eof {
+            let is_pub = self.check(&Token::Keyword(Keyword::Pub));
+            if is_pub {
+                self.advance();
+            }
+            self.expect_keyword(Keyword::Fn)?;
+            let location = self.current_location();
+            let name = self.expect_identifier()?;
+            self.consume("(")?;
+            let params = self.parse_parameters()?;
+            self.consume(")")?;
+            let return_type = if self.check(&Token::Arrow) {
+                self.advance();
+                Some(self.parse_type()?)
+            } else {
+                None
+            };
+            self.consume(";")?;
+            items.push(Item::Function {
+                name,
+                generics: Vec::new(),
+                params,
+                return_type,
+                body: Block {
+                    statements: Vec::new(),
+                    expression: None,
+                },
+                is_unsafe: true,
+                is_async: false,
+                is_pub,
+                attributes: Vec::new(),
+                where_clause: Vec::new(),
+                abi: Some(abi.clone()),
+                location,
+            });
+        }
+        self.consume("}")?;
+        Ok(Item::ExternBlock { abi, items, attributes })
+    }
+
     /// Parse function parameters
     fn parse_parameters(&mut self) -> ParseResult<Vec<Parameter>> {
         let mut params = Vec::new();
 
         while !self.check(&Token::RightParen) {
+            let attributes = self.parse_outer_attributes()?;
             let is_ref = self.check(&Token::Ampersand);
             if is_ref {
                 self.advance();
@@ -671,7 +750,7 @@ impl Parser {
                         inner: Box::new(ty),
                     };
                 }
-                params.push(Parameter { name, mutable, ty });
+                params.push(Parameter { name, mutable, ty, attributes });
             } else if name == "self" {
                 let ty = if is_ref {
                     Type::Reference {
@@ -682,7 +761,7 @@ impl Parser {
                 } else {
                     Type::Named("Self".to_string())
                 };
-                params.push(Parameter { name, mutable, ty });
+                params.push(Parameter { name, mutable, ty, attributes });
             } else {
                 return Err(ParseError::InvalidSyntax(
                     format!("Parameter {} needs type annotation", name)
@@ -723,7 +802,9 @@ impl Parser {
                 let name = self.expect_identifier()?;
                 let mut bounds = Vec::new();
 
-                while self.check(&Token::Plus) {
+                // Bounds: `T: Trait + Other`
+                let has_bounds = self.check(&Token::Colon);
+                while has_bounds && (self.check(&Token::Colon) || self.check(&Token::Plus)) {
                     self.advance();
                     if let Token::Identifier(bound) = self.current() {
                         bounds.push(bound.clone());
@@ -893,9 +974,12 @@ impl Parser {
                         }
                     }
                     self.consume(">")?;
-                    // For now, we'll just wrap it as a named type
-                    // A full implementation would track type parameters
-                    Ok(Type::Named(name))
+                    // Only a Vec's element type is tracked; other arguments are dropped
+                    if name == "Vec" {
+                        Ok(Type::Generic { name, type_args: type_params })
+                    } else {
+                        Ok(Type::Named(name))
+                    }
                 } else {
                     Ok(Type::Named(name))
                 }
@@ -973,10 +1057,59 @@ impl Parser {
                 let inner = Box::new(self.parse_type()?);
                 Ok(Type::Pointer { mutable, inner })
             }
+            Token::Keyword(Keyword::Fn) | Token::Keyword(Keyword::Unsafe) | Token::Keyword(Keyword::Extern) => {
+                self.parse_fn_pointer_type()
+            }
             _ => Err(ParseError::InvalidSyntax("Expected type".to_string())),
         }
     }
 
+    /// Parse a function pointer type: `fn(T1, T2) -> R`, optionally prefixed
+    /// with `unsafe` and/or `extern "ABI"`
+    fn parse_fn_pointer_type(&mut self) -> ParseResult<Type> {
+        let is_unsafe = self.check(&Token::Keyword(Keyword::Unsafe));
+        if is_unsafe {
+            self.advance();
+        }
+        let abi = if self.check(&Token::Keyword(Keyword::Extern)) {
+            self.advance();
+            match self.current().clone() {
+                Token::String(abi) => {
+                    self.advance();
+                    Some(abi)
+                }
+                _ => Some("C".to_string()),
+            }
+        } else {
+            None
+        };
+        self.expect_keyword(Keyword::Fn)?;
+
+        self.consume("(")?;
+        let mut params = Vec::new();
+        while !self.check(&Token::RightParen) {
+            params.push(self.parse_type()?);
+            if !self.check(&Token::RightParen) {
+                self.consume(",")?;
+            }
+        }
+        self.consume(")")?;
+
+        let return_type = if self.check(&Token::Arrow) {
+            self.advance();
+            self.parse_type()?
+        } else {
+            Type::Tuple(Vec::new())
+        };
+
+        Ok(Type::Function {
+            params,
+            return_type: Box::new(return_type),
+            is_unsafe,
+            abi,
+        })
+    }
+
     /// Parse a struct definition
     fn parse_struct_with_attributes(&mut self, attributes: Vec<ast::Attribute>, is_pub: bool) -> ParseResult<Item> {
         self.expect_keyword(Keyword::Struct)?;
@@ -984,16 +1117,38 @@ impl Parser {
 
         let generics = self.parse_generics()?;
 
-        let where_clause = self.parse_where_clause()?;
+        let mut where_clause = self.parse_where_clause()?;
 
         let mut fields = Vec::new();
 
         if self.check(&Token::Semicolon) {
             self.consume(";")?;
+        } else if self.check(&Token::LeftParen) {
+            // Tuple struct: positional fields are named `0`, `1`, ...
+            self.advance();
+            while !self.check(&Token::RightParen) {
+                let attributes = self.parse_outer_attributes()?;
+                if self.check(&Token::Keyword(Keyword::Pub)) {
+                    self.advance();
+                }
+                let ty = self.parse_type()?;
+                fields.push(StructField {
+                    name: fields.len().to_string(),
+                    ty,
+                    attributes,
+                });
+                if !self.check(&Token::RightParen) {
+                    self.consume(",")?;
+                }
+            }
+            self.consume(")")?;
+            where_clause.extend(self.parse_where_clause()?);
+            self.consume(";")?;
         } else {
             self.consume("{")?;
 
             while !self.check(&Token::RightBrace) {
+                let attributes = self.parse_outer_attributes()?;
                 let field_name = self.expect_identifier()?;
                 self.consume(":")?;
                 let ty = self.parse_type()?;
@@ -1001,7 +1156,7 @@ impl Parser {
                 fields.push(StructField {
                     name: field_name,
                     ty,
-                    attributes: Vec::new(),
+                    attributes,
                 });
 
                 if !self.check(&Token::RightBrace) {
@@ -1145,7 +1300,7 @@ impl Parser {
     /// Parse a for statement: `for x in iter { ... }`
     fn parse_for_statement(&mut self) -> ParseResult<Statement> {
         self.expect_keyword(Keyword::For)?;
-        let var = self.expect_identifier()?;
+        let (var, destructure) = self.parse_for_binding()?;
         self.expect_keyword(Keyword::In)?;
         
         // Parse the iterator expression with NO_STRUCT_LITERAL restriction
@@ -1153,11 +1308,32 @@ impl Parser {
             parser.parse_expression()
         })?;
         
-        let body = self.parse_block()?;
+        let mut body = self.parse_block()?;
+        body.statements.splice(0..0, destructure);
         
         Ok(Statement::For { var, iter: Box::new(iter), body })
     }
 
+    /// Parse the binding of a `for` loop. A tuple pattern like `(k, v)`
+    /// binds a hidden loop variable that a `let` at the top of the body
+    /// destructures.
+    fn parse_for_binding(&mut self) -> ParseResult<(String, Option<Statement>)> {
+        if !self.check(&Token::LeftParen) {
+            return Ok((self.expect_identifier()?, None));
+        }
+        let var = format!("__for_pattern_{}", self.position);
+        let pattern = self.parse_pattern()?;
+        let destructure = Statement::Let {
+            name: "_tuple_destructure".to_string(),
+            mutable: false,
+            ty: None,
+            initializer: Expression::Variable(var.clone()),
+            attributes: Vec::new(),
+            pattern: Some(pattern),
+        };
+        Ok((var, Some(destructure)))
+    }
+
     /// Parse a while statement: `while condition { ... }`
     fn parse_while_statement(&mut self) -> ParseResult<Statement> {
         self.expect_keyword(Keyword::While)?;
@@ -1234,7 +1410,7 @@ impl Parser {
 
     /// Parse assignment: expr = expr
     fn parse_assignment(&mut self) -> ParseResult<Expression> {
-        let expr = self.parse_logical_or()?;
+        let expr = self.parse_range()?;
 
         if self.check(&Token::Equal) {
             self.advance();
@@ -1268,87 +1444,58 @@ impl Parser {
         Ok(expr)
     }
 
-    /// Parse logical OR: expr || expr
-    fn parse_logical_or(&mut self) -> ParseResult<Expression> {
-        let mut expr = self.parse_logical_and()?;
-
-        while self.check(&Token::OrOr) {
-            self.advance();
-            let right = Box::new(self.parse_logical_and()?);
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                op: BinaryOp::Or,
-                right,
-            };
-        }
-
-        Ok(expr)
-    }
-
-    /// Parse logical AND: expr && expr
-    fn parse_logical_and(&mut self) -> ParseResult<Expression> {
-        let mut expr = self.parse_bitwise_or()?;
-
-        while self.check(&Token::AndAnd) {
-            self.advance();
-            let right = Box::new(self.parse_bitwise_or()?);
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                op: BinaryOp::And,
-                right,
-            };
-        }
-
-        Ok(expr)
-    }
-
-
-    /// Parse bitwise OR: expr | expr
-    fn parse_bitwise_or(&mut self) -> ParseResult<Expression> {
-        let mut expr = self.parse_bitwise_xor()?;
-
-        while self.check(&Token::Pipe) {
-            self.advance();
-            let right = Box::new(self.parse_bitwise_xor()?);
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                op: BinaryOp::BitwiseOr,
-                right,
-            };
-        }
-
-        Ok(expr)
+    /// Binding power of a binary operator token, following Rust's table from
+    /// loosest to tightest: `||`, `&&`, comparisons, `|`, `^`, `&`,
+    /// `<<`/`>>`, `+`/`-`, `*`/`/`/`%`. Everything is left-associative except
+    /// the comparisons, which don't associate at all.
+    fn binary_operator(&self) -> Option<(BinaryOp, u8)> {
+        let op = match self.current() {
+            Token::OrOr => (BinaryOp::Or, 1),
+            Token::AndAnd => (BinaryOp::And, 2),
+            Token::EqualEqual => (BinaryOp::Equal, COMPARISON_PRECEDENCE),
+            Token::NotEqual => (BinaryOp::NotEqual, COMPARISON_PRECEDENCE),
+            Token::Less => (BinaryOp::Less, COMPARISON_PRECEDENCE),
+            Token::LessEqual => (BinaryOp::LessEq, COMPARISON_PRECEDENCE),
+            Token::Greater => (BinaryOp::Greater, COMPARISON_PRECEDENCE),
+            Token::GreaterEqual => (BinaryOp::GreaterEq, COMPARISON_PRECEDENCE),
+            Token::Pipe => (BinaryOp::BitwiseOr, 4),
+            Token::Caret => (BinaryOp::BitwiseXor, 5),
+            Token::Ampersand if !self.is_unary_ampersand() => (BinaryOp::BitwiseAnd, 6),
+            Token::LeftShift => (BinaryOp::LeftShift, 7),
+            Token::RightShift => (BinaryOp::RightShift, 7),
+            Token::Plus => (BinaryOp::Add, 8),
+            Token::Minus => (BinaryOp::Subtract, 8),
+            Token::Star => (BinaryOp::Multiply, 9),
+            Token::Slash => (BinaryOp::Divide, 9),
+            Token::Percent => (BinaryOp::Modulo, 9),
+            _ => return None,
+        };
+        Some(op)
     }
 
-    /// Parse bitwise XOR: expr ^ expr
-    fn parse_bitwise_xor(&mut self) -> ParseResult<Expression> {
-        let mut expr = self.parse_bitwise_and()?;
+    /// Parse binary operators by precedence climbing: only operators binding
+    /// at least as tightly as `min_precedence` are consumed here
+    fn parse_binary(&mut self, min_precedence: u8) -> ParseResult<Expression> {
+        let mut expr = self.parse_cast()?;
 
-        while self.check(&Token::Caret) {
+        while let Some((op, precedence)) = self.binary_operator() {
+            if precedence < min_precedence {
+                break;
+            }
             self.advance();
-            let right = Box::new(self.parse_bitwise_and()?);
+            let right = Box::new(self.parse_binary(precedence + 1)?);
+            let is_comparison = precedence == COMPARISON_PRECEDENCE;
             expr = Expression::Binary {
                 left: Box::new(expr),
-                op: BinaryOp::BitwiseXor,
+                op,
                 right,
             };
-        }
 
-        Ok(expr)
-    }
-
-    /// Parse bitwise AND: expr & expr
-    fn parse_bitwise_and(&mut self) -> ParseResult<Expression> {
-        let mut expr = self.parse_equality()?;
-
-        while self.check(&Token::Ampersand) && !self.is_unary_ampersand() {
-            self.advance();
-            let right = Box::new(self.parse_equality()?);
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                op: BinaryOp::BitwiseAnd,
-                right,
-            };
+            if is_comparison && matches!(self.binary_operator(), Some((_, COMPARISON_PRECEDENCE))) {
+                return Err(ParseError::InvalidSyntax(
+                    "comparison operators cannot be chained; use parentheses".to_string(),
+                ));
+            }
         }
 
         Ok(expr)
@@ -1366,95 +1513,17 @@ impl Parser {
             _ => false,
         }
     }
-    /// Parse equality: expr == expr, expr != expr
-    fn parse_equality(&mut self) -> ParseResult<Expression> {
-        let mut expr = self.parse_comparison()?;
-
-        loop {
-            let op = match self.current() {
-                Token::EqualEqual => BinaryOp::Equal,
-                Token::NotEqual => BinaryOp::NotEqual,
-                _ => break,
-            };
-
-            self.advance();
-            let right = Box::new(self.parse_comparison()?);
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                op,
-                right,
-            };
-        }
-
-        Ok(expr)
-    }
-
-    /// Parse comparison: expr < expr, expr <= expr, etc.
-    fn parse_comparison(&mut self) -> ParseResult<Expression> {
-        let mut expr = self.parse_addition()?;
-
-        loop {
-            let op = match self.current() {
-                Token::Less => BinaryOp::Less,
-                Token::LessEqual => BinaryOp::LessEq,
-                Token::Greater => BinaryOp::Greater,
-                Token::GreaterEqual => BinaryOp::GreaterEq,
-                _ => break,
-            };
-
-            self.advance();
-            let right = Box::new(self.parse_addition()?);
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                op,
-                right,
-            };
-        }
-
-        Ok(expr)
-    }
-
-    /// Parse addition: expr + expr, expr - expr
-    fn parse_addition(&mut self) -> ParseResult<Expression> {
-        let mut expr = self.parse_multiplication()?;
-
-        loop {
-            let op = match self.current() {
-                Token::Plus => BinaryOp::Add,
-                Token::Minus => BinaryOp::Subtract,
-                _ => break,
-            };
-
-            self.advance();
-            let right = Box::new(self.parse_multiplication()?);
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                op,
-                right,
-            };
-        }
-
-        Ok(expr)
-    }
 
-    /// Parse multiplication: expr * expr, expr / expr, expr % expr
-    fn parse_multiplication(&mut self) -> ParseResult<Expression> {
+    /// Parse cast: expr as Type (binds looser than unary operators)
+    fn parse_cast(&mut self) -> ParseResult<Expression> {
         let mut expr = self.parse_unary()?;
 
-        loop {
-            let op = match self.current() {
-                Token::Star => BinaryOp::Multiply,
-                Token::Slash => BinaryOp::Divide,
-                Token::Percent => BinaryOp::Modulo,
-                _ => break,
-            };
-
+        while self.check(&Token::Keyword(Keyword::As)) {
             self.advance();
-            let right = Box::new(self.parse_unary()?);
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                op,
-                right,
+            let ty = self.parse_type()?;
+            expr = Expression::Cast {
+                value: Box::new(expr),
+                ty,
             };
         }
 
@@ -1510,7 +1579,7 @@ impl Parser {
                     operand,
                 })
             }
-            _ => self.parse_range(),
+            _ => self.parse_postfix(),
         }
     }
 
@@ -1564,8 +1633,7 @@ impl Parser {
             }
         }
         
-        let mut expr = self.parse_postfix()?;
-        // eprintln!("[DEBUG] parse_range: After postfix, current token={:?}, expr type parsed", self.current());
+        let mut expr = self.parse_binary(0)?;
 
         // Check for range operators
         if self.check(&Token::DotDotEqual) {
@@ -1624,41 +1692,10 @@ impl Parser {
         Ok(expr)
     }
 
-    /// Parse range end - don't parse block expressions as they terminate the range
+    /// Parse the end of a range. Ranges bind looser than every binary
+    /// operator, so `0..n + 1` ends at `n + 1`.
     fn parse_range_end(&mut self) -> ParseResult<Expression> {
-        // eprintln!("[DEBUG] parse_range_end: START, current token={:?}", self.current());
-        // Parse a primary expression but stop before blocks
-        let result = match self.current().clone() {
-            Token::Integer(n, _) => {
-                // eprintln!("[DEBUG] parse_range_end: Found Integer({}), advancing...", n);
-                self.advance();
-                // eprintln!("[DEBUG] parse_range_end: After advance, current token={:?}", self.current());
-                Ok(Expression::Integer(n))
-            }
-            Token::Float(f, _) => {
-                self.advance();
-                Ok(Expression::Float(f))
-            }
-            Token::Identifier(name) => {
-                // eprintln!("[DEBUG] parse_range_end: Found Identifier({}), advancing...", name);
-                self.advance();
-                // eprintln!("[DEBUG] parse_range_end: After advance, current token={:?}", self.current());
-                if self.check(&Token::LeftParen) {
-                    self.advance();
-                    let args = self.parse_arguments()?;
-                    self.consume(")")?;
-                    Ok(Expression::FunctionCall { name, args })
-                } else {
-                    Ok(Expression::Variable(name))
-                }
-            }
-            _ => {
-                // For other cases, use regular parsing but be careful
-                self.parse_unary()
-            }
-        };
-        // eprintln!("[DEBUG] parse_range_end: END, current token={:?}", self.current());
-        result
+        self.parse_binary(0)
     }
 
     /// Check if current token is an expression terminator
@@ -1723,6 +1760,7 @@ impl Parser {
                 }
                 Token::Dot => {
                     self.advance();
+                    let field_location = self.current_location();
                     let field_or_method = match self.current() {
                         Token::Integer(n, _) => {
                             let n = *n;
@@ -1746,6 +1784,7 @@ impl Parser {
                         expr = Expression::FieldAccess {
                             object: Box::new(expr),
                             field: field_or_method,
+                            location: field_location,
                         };
                     }
                 }
@@ -1795,6 +1834,18 @@ impl Parser {
                 self.advance();
                 Ok(Expression::Char(c))
             }
+            // Byte literals are plain u8 values: `b'x'` is an integer and
+            // `b"..."` an array of them
+            Token::ByteChar(b) => {
+                self.advance();
+                Ok(Expression::Integer(b as i64))
+            }
+            Token::ByteString(bytes) => {
+                self.advance();
+                Ok(Expression::Array(
+                    bytes.into_iter().map(|b| Expression::Integer(b as i64)).collect(),
+                ))
+            }
             Token::Keyword(Keyword::True) => {
                 self.advance();
                 Ok(Expression::Bool(true))
@@ -1807,8 +1858,26 @@ impl Parser {
                 let mut path = vec![name.clone()];
                 self.advance();
 
+                let mut type_args = Vec::new();
                 while self.check(&Token::DoubleColon) {
                     self.advance();
+                    // Turbofish ends the path: `size_of::<T>()`
+                    if self.check(&Token::Less) {
+                        self.advance();
+                        while !self.check(&Token::Greater) {
+                            type_args.push(self.parse_type()?);
+                            if !self.check(&Token::Greater) {
+                                self.consume(",")?;
+                            }
+                        }
+                        self.consume(">")?;
+                        if !self.check(&Token::LeftParen) {
+                            return Err(ParseError::InvalidSyntax(
+                                "Expected '(' after turbofish".to_string(),
+                            ));
+                        }
+                        break;
+                    }
                     let next_name = self.expect_identifier()?;
                     path.push(next_name);
                 }
@@ -1847,7 +1916,11 @@ impl Parser {
                     self.consume(")")?;
                     // For now, join path with :: to create a qualified name
                     let func_name = path.join("::");
-                    Ok(Expression::FunctionCall { name: func_name, args })
+                    if type_args.is_empty() {
+                        Ok(Expression::FunctionCall { name: func_name, args })
+                    } else {
+                        Ok(Expression::GenericCall { name: func_name, type_args, args })
+                    }
                 } else if self.check(&Token::LeftBrace) && matches!(self.restrictions, Restrictions::None) {
                     // Struct literal or Enum struct literal
                     // Struct literal: Name { field: value, ... } (path.len() == 1)
@@ -2133,6 +2206,13 @@ impl Parser {
             Token::Identifier(name) => {
                 let name = name.clone();
                 self.advance();
+                if self.check(&Token::At) && name != "_" {
+                    self.advance();
+                    return Ok(Pattern::Binding {
+                        name,
+                        pattern: Box::new(self.parse_pattern()?),
+                    });
+                }
                 if name == "_" {
                     Pattern::Wildcard
                 } else if self.check(&Token::DoubleColon) {
@@ -2202,10 +2282,8 @@ impl Parser {
                     Pattern::Identifier(name)
                 }
             }
-            Token::Integer(val, _) => {
-                let val = *val;
-                self.advance();
-                Pattern::Literal(Expression::Integer(val))
+            Token::Integer(..) | Token::ByteChar(_) | Token::Char(_) | Token::Minus => {
+                Pattern::Literal(self.parse_pattern_number()?)
             }
             Token::String(s) => {
                 let s = s.clone();
@@ -2227,14 +2305,7 @@ impl Parser {
             let inclusive = self.check(&Token::DotDotEqual);
             self.advance();
             
-            let end_expr = match self.current() {
-                Token::Integer(val, _) => {
-                    let val = *val;
-                    self.advance();
-                    Expression::Integer(val)
-                }
-                _ => return Err(ParseError::InvalidSyntax("Expected integer after range operator".to_string())),
-            };
+            let end_expr = self.parse_pattern_number()?;
             
             let start_expr = match pattern {
                 Pattern::Literal(expr) => expr,
@@ -2251,6 +2322,23 @@ impl Parser {
         }
     }
 
+    /// Parse a number usable as a literal pattern or range bound: an
+    /// integer (optionally negated), a char, or a byte char
+    fn parse_pattern_number(&mut self) -> ParseResult<Expression> {
+        let negated = self.check(&Token::Minus);
+        if negated {
+            self.advance();
+        }
+        let value = match self.current() {
+            Token::Integer(val, _) => Expression::Integer(if negated { val.wrapping_neg() } else { *val }),
+            Token::Char(c) if !negated => Expression::Char(*c),
+            Token::ByteChar(b) if !negated => Expression::Integer(*b as i64),
+            _ => return Err(ParseError::InvalidSyntax("Expected an integer or char in range pattern".to_string())),
+        };
+        self.advance();
+        Ok(value)
+    }
+
     /// Parse loop expression
     fn parse_loop_expression(&mut self) -> ParseResult<Expression> {
         self.expect_keyword(Keyword::Loop)?;
@@ -2292,7 +2380,7 @@ impl Parser {
     /// Parse for loop: `for var in iter { body }`
     fn parse_for_loop(&mut self) -> ParseResult<Expression> {
         self.expect_keyword(Keyword::For)?;
-        let var = self.expect_identifier()?;
+        let (var, destructure) = self.parse_for_binding()?;
         self.expect_keyword(Keyword::In)?;
         
         // Parse the iterator expression with NO_STRUCT_LITERAL restriction
@@ -2304,7 +2392,8 @@ impl Parser {
         })?;
         // eprintln!("[DEBUG] parse_for_loop: After parsing iter, restriction={:?}", self.restrictions);
         
-        let body = self.parse_block()?;
+        let mut body = self.parse_block()?;
+        body.statements.splice(0..0, destructure);
         
         Ok(Expression::For { var, iter: Box::new(iter), body })
     }
@@ -2461,6 +2550,8 @@ impl Parser {
                     ty,
                     attributes: Vec::new(),
                 });
+            } else if self.check(&Token::Keyword(Keyword::Const)) {
+                methods.push(self.parse_associated_const()?);
             } else {
                 self.advance();
             }
@@ -2478,8 +2569,32 @@ impl Parser {
         })
     }
 
+    /// Parse an associated const: `const MAX: i64;` in a trait, or
+    /// `const MAX: i64 = 10;` in a trait (as a default) or an impl
+    fn parse_associated_const(&mut self) -> ParseResult<Item> {
+        self.expect_keyword(Keyword::Const)?;
+        let name = self.expect_identifier()?;
+        self.consume(":")?;
+        let ty = self.parse_type()?;
+        let value = if self.check(&Token::Equal) {
+            self.advance();
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+        self.consume(";")?;
+
+        Ok(Item::AssociatedConst {
+            name,
+            ty,
+            value,
+            attributes: Vec::new(),
+        })
+    }
+
     fn parse_trait_method(&mut self) -> ParseResult<Item> {
         self.expect_keyword(Keyword::Fn)?;
+        let location = self.current_location();
         let name = self.expect_identifier()?;
         let generics = self.parse_generics()?;
         let where_clause = self.parse_where_clause()?;
@@ -2517,9 +2632,28 @@ impl Parser {
             attributes: Vec::new(),
             where_clause,
             abi: None,
+            location,
         })
     }
 
+    /// The name of the trait or type in an impl header, and its type
+    /// arguments
+    fn parse_impl_type_name(&mut self) -> ParseResult<(String, Vec<Type>)> {
+        let name = self.expect_identifier()?;
+        let mut type_args = Vec::new();
+        if self.check(&Token::Less) {
+            self.advance();
+            while !self.check(&Token::Greater) {
+                type_args.push(self.parse_type()?);
+                if !self.check(&Token::Greater) {
+                    self.consume(",")?;
+                }
+            }
+            self.consume(">")?;
+        }
+        Ok((name, type_args))
+    }
+
     /// Parse impl block: `impl Name { ... }` or `impl Trait for Name { ... }`
     fn parse_impl(&mut self) -> ParseResult<Item> {
         self.expect_keyword(Keyword::Impl)?;
@@ -2527,7 +2661,7 @@ impl Parser {
         // Parse generic parameters: impl<'a> or impl<T>
         let generics = self.parse_generics()?;
         
-        let struct_name = self.expect_identifier()?;
+        let (struct_name, type_args) = self.parse_impl_type_name()?;
         
         let trait_name = if self.check(&Token::Keyword(Keyword::For)) {
             // Trait impl: impl Trait for Struct
@@ -2537,11 +2671,12 @@ impl Parser {
             None
         };
         
-        // Re-parse struct_name if we just consumed a trait
-        let struct_name = if trait_name.is_some() {
-            self.expect_identifier()?
+        // Re-parse struct_name if we just consumed a trait. The type's own
+        // arguments aren't recorded.
+        let (struct_name, trait_args) = if trait_name.is_some() {
+            (self.parse_impl_type_name()?.0, type_args)
         } else {
-            struct_name
+            (struct_name, Vec::new())
         };
         
         let where_clause = self.parse_where_clause()?;
@@ -2557,6 +2692,8 @@ impl Parser {
                 if self.check(&Token::Keyword(Keyword::Fn)) {
                     methods.push(self.parse_function(true)?);
                 }
+            } else if self.check(&Token::Keyword(Keyword::Const)) {
+                methods.push(self.parse_associated_const()?);
             } else {
                 self.advance(); // Skip unknown items
             }
@@ -2566,6 +2703,7 @@ impl Parser {
         Ok(Item::Impl { 
             generics,
             trait_name, 
+            trait_args,
             struct_name, 
             methods,
             is_unsafe: false,
@@ -2879,16 +3017,64 @@ pub fn resolve_file_modules(
     Ok(program)
 }
 
+/// The index of the innermost `{`, `(` or `[` still open when the tokens
+/// run out, if any
+pub fn unclosed_delimiter(tokens: &[Token]) -> Option<usize> {
+    let mut open = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::LeftBrace | Token::LeftParen | Token::LeftBracket => open.push(index),
+            Token::RightBrace | Token::RightParen | Token::RightBracket => {
+                let closes_top = open.last().is_some_and(|&top| {
+                    matches!(
+                        (&tokens[top], token),
+                        (Token::LeftBrace, Token::RightBrace)
+                            | (Token::LeftParen, Token::RightParen)
+                            | (Token::LeftBracket, Token::RightBracket)
+                    )
+                });
+                if closes_top {
+                    open.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+    open.pop()
+}
+
 /// The public parsing function (without file-based module resolution)
 pub fn parse(tokens: Vec<Token>) -> Result<Program, String> {
     let mut parser = Parser::new(tokens);
     parser.parse_program().map_err(|e| e.to_string())
 }
 
+/// Parse a single expression, e.g. a line typed into a REPL. Tokens left
+/// over after the expression are an error rather than being ignored.
+pub fn parse_expression(tokens: Vec<Token>) -> Result<Expression, ParseError> {
+    let mut parser = Parser::new(tokens);
+    let expr = parser.parse_expression()?;
+    // Recovery records errors instead of returning them
+    if let Some(err) = parser.errors.first() {
+        return Err(err.clone());
+    }
+    if !parser.check(&Token::Eof) {
+        return Err(ParseError::UnexpectedToken {
+            expected: "end of expression".to_string(),
+            found: format!("{:?}", parser.current()),
+        });
+    }
+    Ok(expr)
+}
+
 /// Parse with file-based module resolution
 /// Resolves `mod name;` statements to load from name.rs files
-pub fn parse_with_modules(tokens: Vec<Token>, source_file: Option<&str>) -> Result<Program, String> {
-    let mut parser = Parser::new(tokens);
+pub fn parse_with_modules(
+    tokens: Vec<Token>,
+    locations: Vec<SourceLocation>,
+    source_file: Option<&str>,
+) -> Result<Program, String> {
+    let mut parser = Parser::new(tokens).with_locations(locations);
     let ast = parser.parse_program().map_err(|e| e.to_string())?;
     
     // Get the directory of the source file
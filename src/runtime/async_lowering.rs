@@ -223,6 +223,7 @@ impl AsyncTransformer {
                     body: hir_body,
                     is_public: true,
                     where_clause: vec![],
+                    location: None,
                 })
             }
             parser_ast::Item::Function {
@@ -16,5 +16,5 @@ pub mod threading;
 pub mod state_machine_codegen;
 pub mod smart_pointer_ops;
 
-pub use runtime::{generate_main_wrapper, generate_runtime_assembly};
+pub use runtime::{generate_main_wrapper, generate_runtime_assembly, generate_syscall_io, LIBC_SYMBOLS};
 pub use state_machine_codegen::{StateMachineCodegen, StateMachineConfig, GeneratedStateMachine};
@@ -6,6 +6,14 @@
 //! - String utilities
 //! - Collection operations (Vec, HashMap, HashSet)
 
+/// The libc (and libm) functions the runtime calls. A static link has to
+/// pull every one of them out of `libc.a`/`libm.a`, so this list tracks
+/// the `call` targets the runtime leaves undefined.
+pub const LIBC_SYMBOLS: &[&str] = &[
+    "clock_gettime", "cos", "exit", "fflush", "fork", "free", "malloc", "pow", "printf", "sin",
+    "snprintf", "strcat", "strcmp", "strcpy", "strlen", "waitpid",
+];
+
 /// Generate runtime assembly that implements print functionality and collection operations
 pub fn generate_runtime_assembly() -> String {
     r#"
@@ -21,7 +29,21 @@ pub fn generate_runtime_assembly() -> String {
     todo_msg: .string "todo!(): not yet implemented\n"
     unimplemented_msg: .string "unimplemented!(): feature not implemented\n"
     panic_custom_fmt: .string "panicked at: %s\n"
+    bounds_check_fmt: .string "panicked at: index out of bounds: the len is %ld but the index is %lu\n"
+    unwrap_none_msg: .string "called `Option::unwrap()` on a `None` value"
+    unwrap_err_msg: .string "called `Result::unwrap()` on an `Err` value"
+    unwrap_err_ok_msg: .string "called `Result::unwrap_err()` on an `Ok` value"
+    shl_overflow_msg: .string "attempt to shift left with overflow"
+    shr_overflow_msg: .string "attempt to shift right with overflow"
     dbg_msg: .string "[DEBUG] value: %ld\n"
+    backtrace_header: .string "stack backtrace:\n"
+    backtrace_frame_fmt: .string "  %ld: %s\n"
+    stack_chk_fail_msg: .string "*** stack smashing detected ***: terminated\n"
+    test_ok_fmt: .string "test %s ... ok\n"
+    test_failed_fmt: .string "test %s ... FAILED\n"
+    test_summary_fmt: .string "\ntest result: %s. %ld passed; %ld failed\n"
+    test_result_ok: .string "ok"
+    test_result_failed: .string "FAILED"
 
 .section .text
 .globl gaia_print_i32
@@ -34,19 +56,46 @@ pub fn generate_runtime_assembly() -> String {
 .globl gaia_vec_push
 .globl gaia_vec_pop
 .globl gaia_vec_get
+.globl gaia_vec_get_checked
 .globl gaia_vec_len
 .globl gaia_vec_insert
 .globl gaia_vec_remove
 .globl gaia_vec_clear
 .globl gaia_vec_reserve
+.globl gaia_vec_clone
+.globl gaia_vec_sort
+.globl gaia_vec_swap
 .globl gaia_collection_is_empty
 .globl gaia_hashmap_new
 .globl gaia_hashmap_insert
 .globl gaia_hashmap_get
 .globl gaia_hashmap_contains_key
+.globl gaia_hashmap_entry_or_insert
 .globl gaia_hashmap_remove
 .globl gaia_hashmap_len
 .globl gaia_hashmap_clear
+.globl gaia_hashmap_iter
+.globl gaia_hashmap_iter_next
+.globl gaia_format
+.globl gaia_string_concat
+.globl gaia_hash_combine
+.globl gaia_hash_str
+.globl gaia_hash_key
+.globl gaia_hash_key_field
+.globl gaia_hash_key_eq
+.globl gaia_hashmap_canonical_key
+.globl gaia_cmp
+.globl gaia_ucmp
+.globl gaia_str_cmp
+.globl gaia_cmp_then
+.globl gaia_alloc
+.globl gaia_ptr_read
+.globl gaia_ptr_write
+.globl gaia_run_test
+.globl gaia_test_summary
+.globl gaia_bench_now
+.globl gaia_bench_record
+.globl gaia_bench_elapsed
 .globl gaia_hashset_new
 .globl gaia_hashset_insert
 .globl gaia_hashset_contains
@@ -71,6 +120,9 @@ pub fn generate_runtime_assembly() -> String {
 .globl gaia_string_split
 .globl __into_iter
 .globl __next
+.globl __into_iter_rev
+.globl __next_back
+.globl __next_back_ref
 .globl gaia_option_is_some
 .globl gaia_option_is_none
 .globl gaia_option_unwrap
@@ -109,6 +161,8 @@ pub fn generate_runtime_assembly() -> String {
 .globl gaia_fs_write
 .globl String_impl_sqrt
 .globl String_impl_pow
+.globl gaia_int_pow
+.globl gaia_int_abs
 .globl String_impl_sin
 .globl String_impl_cos
 .globl String_impl_floor
@@ -133,10 +187,17 @@ pub fn generate_runtime_assembly() -> String {
 .globl String_impl_pad_end
 .globl String_impl_truncate
 .globl __extract_enum_value
+.globl __enum_tag
+.globl __extract_enum_field
 .globl assert
 .globl assert_eq
 .globl assert_ne
 .globl panic
+.globl gaia_panic_bounds_check
+.globl gaia_backtrace_push
+.globl gaia_backtrace_pop
+.globl gaia_stack_chk_fail
+.globl __stack_chk_guard
 .globl format
 .globl dbg
 .globl todo
@@ -348,6 +409,41 @@ vec_get_done:
     pop rbp
     ret
 
+gaia_vec_get_checked:
+    # Vec::get: bounds-checked element access
+    # rdi = vec pointer
+    # rsi = index
+    # Returns: Option pointer [tag:i64][value:i64] - Some(data[index]), or
+    # None when index >= length (the index is unsigned, so negatives miss too)
+    push rbp
+    mov rbp, rsp
+    push rbx
+    push r12
+    
+    mov rbx, rdi            # rbx = vec pointer
+    mov r12, rsi            # r12 = index
+    mov rdi, 16
+    call malloc
+    
+    cmp r12, [rbx + 8]      # check if index < length
+    jae vec_get_checked_none
+    
+    mov rcx, [rbx + 16 + r12*8]
+    mov qword ptr [rax], 1  # tag = Some
+    mov [rax + 8], rcx      # value = data[index]
+    jmp vec_get_checked_done
+    
+vec_get_checked_none:
+    mov qword ptr [rax], 0  # tag = None
+    mov qword ptr [rax + 8], 0
+    
+vec_get_checked_done:
+    pop r12
+    pop rbx
+    mov rsp, rbp
+    pop rbp
+    ret
+
 gaia_vec_len:
     # Get length of vector
     # rdi = vec pointer
@@ -459,6 +555,88 @@ vec_reserve_done:
     pop rbp
     ret
 
+gaia_vec_clone:
+    # Copy a vector into a fresh heap buffer of the same capacity
+    # rdi = vec pointer
+    # Returns: pointer to the copy (in rax)
+    push rbp
+    mov rbp, rsp
+    push rbx
+    push r12
+
+    mov rbx, rdi            # source vector
+    mov rdi, [rbx]          # capacity
+    shl rdi, 3
+    add rdi, 16             # header + capacity * 8 bytes of data
+    call malloc
+    mov r12, rax
+
+    mov rcx, [rbx]
+    mov [r12], rcx          # copy capacity
+    mov rcx, [rbx + 8]
+    mov [r12 + 8], rcx      # copy length
+    xor rdx, rdx
+vec_clone_loop:
+    cmp rdx, rcx
+    jge vec_clone_done
+    mov r8, [rbx + 16 + rdx*8]
+    mov [r12 + 16 + rdx*8], r8
+    inc rdx
+    jmp vec_clone_loop
+
+vec_clone_done:
+    mov rax, r12
+    pop r12
+    pop rbx
+    mov rsp, rbp
+    pop rbp
+    ret
+
+gaia_vec_sort:
+    # Sort a vector's elements in place, ascending as signed integers
+    # (insertion sort: stable, and quick for the short Vecs programs build)
+    # rdi = vec pointer
+    # Returns: void
+    push rbp
+    mov rbp, rsp
+
+    mov rcx, [rdi + 8]      # length
+    lea rdx, [rdi + 16]     # data
+    mov r8, 1               # i = 1
+vec_sort_outer:
+    cmp r8, rcx
+    jge vec_sort_done
+    mov r9, [rdx + r8*8]    # key = data[i]
+    mov r10, r8             # j = i
+vec_sort_shift:
+    test r10, r10
+    jz vec_sort_place
+    mov r11, [rdx + r10*8 - 8]
+    cmp r11, r9             # stop once data[j - 1] <= key
+    jle vec_sort_place
+    mov [rdx + r10*8], r11  # data[j] = data[j - 1]
+    dec r10
+    jmp vec_sort_shift
+vec_sort_place:
+    mov [rdx + r10*8], r9   # data[j] = key
+    inc r8
+    jmp vec_sort_outer
+
+vec_sort_done:
+    mov rsp, rbp
+    pop rbp
+    ret
+
+gaia_vec_swap:
+    # Swap two of a vector's slots (sorting a Vec of structs)
+    # rdi = vec pointer, rsi = i, rdx = j
+    # Returns: void
+    mov rax, [rdi + 16 + rsi*8]
+    mov rcx, [rdi + 16 + rdx*8]
+    mov [rdi + 16 + rsi*8], rcx
+    mov [rdi + 16 + rdx*8], rax
+    ret
+
 gaia_collection_is_empty:
     # Check if any collection (Vec/HashMap/HashSet) is empty
     # All collections have size/length at offset +8
@@ -557,6 +735,47 @@ hashmap_get_done:
     pop rbp
     ret
 
+gaia_hashmap_entry_or_insert:
+    # Get a key's value slot, inserting the default first if the key is missing
+    # (`map.entry(key).or_insert(default)`)
+    # rdi = hashmap pointer
+    # rsi = key
+    # rdx = default value
+    # Returns: pointer to the value (in rax), to read or update in place
+    push rbp
+    mov rbp, rsp
+    
+    mov rcx, [rdi + 8]      # get size
+    xor r8, r8              # index = 0
+    
+hashmap_entry_loop:
+    cmp r8, rcx             # if index >= size
+    jge hashmap_entry_insert
+    
+    mov r9, r8
+    imul r9, 16
+    cmp [rdi + 16 + r9], rsi # compare stored key with lookup key
+    je hashmap_entry_found
+    
+    inc r8
+    jmp hashmap_entry_loop
+    
+hashmap_entry_insert:
+    # Append [key][default] after the last entry
+    mov r9, rcx
+    imul r9, 16
+    mov [rdi + 16 + r9], rsi
+    mov [rdi + 24 + r9], rdx
+    inc rcx
+    mov [rdi + 8], rcx      # increment size
+    
+hashmap_entry_found:
+    lea rax, [rdi + 24 + r9] # address of the value
+    
+    mov rsp, rbp
+    pop rbp
+    ret
+
 gaia_hashmap_contains_key:
     # Check if key exists in HashMap
     # rdi = hashmap pointer
@@ -622,6 +841,323 @@ gaia_hashmap_clear:
     pop rbp
     ret
 
+gaia_hashmap_iter:
+    # Start iterating a HashMap (`for (k, v) in map`)
+    # rdi = hashmap pointer
+    # Returns: iterator pointer, laid out as [map:i64][index:i64]
+    push rbx
+    mov rbx, rdi
+    mov rdi, 16
+    call malloc
+    mov [rax], rbx
+    mov qword ptr [rax + 8], 0
+    pop rbx
+    ret
+
+gaia_hashmap_iter_next:
+    # Advance a HashMap iterator
+    # rdi = iterator pointer
+    # Returns: pointer to the next entry's [key][value] pair, or 0 when done
+    mov rsi, [rdi]          # rsi = hashmap pointer
+    mov rcx, [rdi + 8]      # rcx = index of the next entry
+    cmp rcx, [rsi + 8]      # past the last entry?
+    jae hashmap_iter_done
+    lea rdx, [rcx + 1]
+    mov [rdi + 8], rdx
+    imul rcx, 16            # each entry is 16 bytes
+    lea rax, [rsi + 16 + rcx]
+    ret
+hashmap_iter_done:
+    xor rax, rax
+    ret
+
+gaia_hash_combine:
+    # Fold one field's hash into a running hash (#[derive(Hash)])
+    # rdi = running hash
+    # rsi = field hash
+    # Returns: h ^ (v + 0x9e3779b97f4a7c15 + (h << 6) + (h >> 2))
+    mov rax, 0x9e3779b97f4a7c15
+    add rax, rsi
+    mov rcx, rdi
+    shl rcx, 6
+    add rax, rcx
+    mov rcx, rdi
+    shr rcx, 2
+    add rax, rcx
+    xor rax, rdi
+    ret
+
+gaia_hash_str:
+    # FNV-1a hash of a string's bytes
+    # rdi = NUL-terminated string
+    # Returns: hash (in rax)
+    mov rax, 0xcbf29ce484222325
+    mov rcx, 0x100000001b3
+hash_str_loop:
+    movzx edx, byte ptr [rdi]
+    test edx, edx
+    jz hash_str_done
+    xor rax, rdx
+    imul rax, rcx
+    inc rdi
+    jmp hash_str_loop
+hash_str_done:
+    ret
+
+gaia_hash_key:
+    # Start a #[derive(Hash)] map key
+    # rdi = derived hash
+    # rsi = number of fields
+    # Returns: key pointer, laid out as [hash][fields][filled] followed by
+    #          one [is_string][value] pair per field
+    push rbx
+    push r12
+    sub rsp, 8
+    mov rbx, rdi
+    mov r12, rsi
+    mov rdi, rsi
+    shl rdi, 4
+    add rdi, 24
+    call malloc
+    mov [rax], rbx
+    mov [rax + 8], r12
+    mov qword ptr [rax + 16], 0
+    add rsp, 8
+    pop r12
+    pop rbx
+    ret
+
+gaia_hash_key_field:
+    # Append a field to a key from gaia_hash_key
+    # rdi = key pointer
+    # rsi = 1 if the field compares as a string, else 0
+    # rdx = field value
+    # Returns: the key pointer
+    mov rax, rdi
+    mov rcx, [rax + 16]
+    lea r8, [rcx + 1]
+    mov [rax + 16], r8
+    shl rcx, 4
+    mov [rax + 24 + rcx], rsi
+    mov [rax + 32 + rcx], rdx
+    ret
+
+gaia_hash_key_eq:
+    # Compare two keys from gaia_hash_key like the derived PartialEq
+    # rdi = key a
+    # rsi = key b
+    # Returns: 1 if equal, 0 otherwise
+    push rbx
+    push r12
+    push r13
+    mov rbx, rdi
+    mov r12, rsi
+    mov rax, [rbx]
+    cmp rax, [r12]
+    jne hash_key_ne
+    mov rax, [rbx + 8]
+    cmp rax, [r12 + 8]
+    jne hash_key_ne
+    xor r13, r13
+hash_key_eq_loop:
+    cmp r13, [rbx + 8]
+    jae hash_key_equal
+    mov rax, r13
+    shl rax, 4
+    mov rdi, [rbx + 32 + rax]
+    mov rsi, [r12 + 32 + rax]
+    cmp qword ptr [rbx + 24 + rax], 0
+    jne hash_key_eq_string
+    cmp rdi, rsi
+    jne hash_key_ne
+    jmp hash_key_eq_next
+hash_key_eq_string:
+    call strcmp
+    test eax, eax
+    jne hash_key_ne
+hash_key_eq_next:
+    inc r13
+    jmp hash_key_eq_loop
+hash_key_equal:
+    mov rax, 1
+    jmp hash_key_eq_done
+hash_key_ne:
+    xor eax, eax
+hash_key_eq_done:
+    pop r13
+    pop r12
+    pop rbx
+    ret
+
+gaia_hashmap_canonical_key:
+    # The key already stored in a map (or set) equal to `key`, so equal
+    # derived-Hash keys share one entry. `key` is freed unless the map
+    # takes it as a new entry.
+    # rdi = hashmap pointer
+    # rsi = key pointer from gaia_hash_key
+    # rdx = 1 if the caller stores a missing key (insert, entry), else 0
+    # Returns: the stored key; for a missing key, `key` when the caller
+    #          stores it, else 0, which matches no entry
+    push rbx
+    push r12
+    push r13
+    push r14
+    sub rsp, 8
+    mov rbx, rdi
+    mov r12, rsi
+    mov r14, rdx
+    xor r13, r13
+canonical_key_loop:
+    cmp r13, [rbx + 8]
+    jae canonical_key_new
+    mov rax, r13
+    shl rax, 4
+    mov rdi, [rbx + 16 + rax]
+    # Keys with different hashes can't be equal
+    mov rcx, [rdi]
+    cmp rcx, [r12]
+    jne canonical_key_next
+    mov rsi, r12
+    call gaia_hash_key_eq
+    test rax, rax
+    jnz canonical_key_found
+canonical_key_next:
+    inc r13
+    jmp canonical_key_loop
+canonical_key_found:
+    mov rdi, r12
+    call free
+    mov rax, r13
+    shl rax, 4
+    mov rax, [rbx + 16 + rax]
+    jmp canonical_key_done
+canonical_key_new:
+    mov rax, r12
+    test r14, r14
+    jnz canonical_key_done
+    mov rdi, r12
+    call free
+    xor eax, eax
+canonical_key_done:
+    add rsp, 8
+    pop r14
+    pop r13
+    pop r12
+    pop rbx
+    ret
+
+gaia_cmp:
+    # Three-way integer comparison (#[derive(PartialOrd, Ord)])
+    # rdi = a, rsi = b
+    # Returns: -1 if a < b, 0 if a == b, 1 if a > b
+    xor eax, eax
+    xor ecx, ecx
+    cmp rdi, rsi
+    setg al
+    setl cl
+    sub rax, rcx
+    ret
+
+gaia_ucmp:
+    # Three-way unsigned comparison (u64 and usize fields)
+    # rdi = a, rsi = b
+    # Returns: -1 if a < b, 0 if a == b, 1 if a > b, like gaia_cmp
+    xor eax, eax
+    xor ecx, ecx
+    cmp rdi, rsi
+    seta al
+    setb cl
+    sub rax, rcx
+    ret
+
+gaia_str_cmp:
+    # Three-way string comparison (#[derive(PartialOrd, Ord)] on String fields)
+    # rdi = a, rsi = b (NUL-terminated)
+    # Returns: -1 if a < b, 0 if a == b, 1 if a > b, like gaia_cmp
+    push rbp
+    mov rbp, rsp
+    call strcmp
+    xor ecx, ecx
+    xor edx, edx
+    test eax, eax
+    setg cl
+    setl dl
+    sub rcx, rdx
+    mov rax, rcx
+    pop rbp
+    ret
+
+gaia_cmp_then:
+    # Chain lexicographic comparisons, like Ordering::then
+    # rdi = ordering of an earlier field
+    # rsi = ordering of the remaining fields
+    # Returns: rdi unless it is Equal (0), else rsi
+    mov rax, rdi
+    test rax, rax
+    cmovz rax, rsi
+    ret
+
+gaia_alloc:
+    # Allocate raw heap memory for *const T / *mut T
+    # rdi = size in bytes
+    # Returns: pointer to the block (never freed)
+    push rbp
+    mov rbp, rsp
+    call malloc
+    mov rsp, rbp
+    pop rbp
+    ret
+
+gaia_ptr_read:
+    # Load a value narrower than 8 bytes through a raw pointer
+    # rdi = pointer, rsi = size in bytes (1, 2 or 4)
+    # rdx = 1 to sign-extend, 0 to zero-extend
+    # Returns: the value widened to 64 bits
+    test rdx, rdx
+    jnz ptr_read_signed
+    cmp rsi, 4
+    je ptr_read_u32
+    cmp rsi, 2
+    je ptr_read_u16
+    movzx eax, byte ptr [rdi]
+    ret
+ptr_read_u16:
+    movzx eax, word ptr [rdi]
+    ret
+ptr_read_u32:
+    mov eax, dword ptr [rdi]
+    ret
+ptr_read_signed:
+    cmp rsi, 4
+    je ptr_read_i32
+    cmp rsi, 2
+    je ptr_read_i16
+    movsx rax, byte ptr [rdi]
+    ret
+ptr_read_i16:
+    movsx rax, word ptr [rdi]
+    ret
+ptr_read_i32:
+    movsxd rax, dword ptr [rdi]
+    ret
+
+gaia_ptr_write:
+    # Store the low bytes of a value through a raw pointer, leaving the
+    # bytes after it untouched
+    # rdi = pointer, rsi = size in bytes (1, 2 or 4), rdx = value
+    cmp rsi, 4
+    je ptr_write_32
+    cmp rsi, 2
+    je ptr_write_16
+    mov byte ptr [rdi], dl
+    ret
+ptr_write_16:
+    mov word ptr [rdi], dx
+    ret
+ptr_write_32:
+    mov dword ptr [rdi], edx
+    ret
+
 # HashSet operations (implemented using HashMap)
 
 gaia_hashset_new:
@@ -1061,6 +1597,98 @@ gaia_string_repeat:
      pop rbp
      ret
 
+gaia_format:
+     # Format into a freshly allocated string (`format!`, `write!`)
+     # rdi = printf-style format string
+     # rsi, rdx, rcx, r8, r9 = up to five integer or pointer arguments
+     # Returns: pointer to the formatted string
+     push rbp
+     mov rbp, rsp
+     push rbx
+     push r12
+     push r13
+     push r14
+     push r15
+     sub rsp, 40
+     mov rbx, rdi
+     mov r12, rsi
+     mov r13, rdx
+     mov r14, rcx
+     mov r15, r8
+     mov [rbp - 48], r9
+
+     # Measure: snprintf(NULL, 0, fmt, ...)
+     xor rdi, rdi
+     xor rsi, rsi
+     mov rdx, rbx
+     mov rcx, r12
+     mov r8, r13
+     mov r9, r14
+     mov [rsp], r15
+     mov rax, [rbp - 48]
+     mov [rsp + 8], rax
+     xor eax, eax
+     call snprintf
+     lea rdi, [rax + 1]
+     mov [rbp - 56], rdi
+     call malloc
+     mov [rbp - 64], rax
+
+     # Format: snprintf(buf, len + 1, fmt, ...)
+     mov rdi, rax
+     mov rsi, [rbp - 56]
+     mov rdx, rbx
+     mov rcx, r12
+     mov r8, r13
+     mov r9, r14
+     mov [rsp], r15
+     mov rax, [rbp - 48]
+     mov [rsp + 8], rax
+     xor eax, eax
+     call snprintf
+     mov rax, [rbp - 64]
+
+     lea rsp, [rbp - 40]
+     pop r15
+     pop r14
+     pop r13
+     pop r12
+     pop rbx
+     pop rbp
+     ret
+
+gaia_string_concat:
+     # Concatenate two strings into a new allocation
+     # rdi = first string, rsi = second string
+     # Returns: pointer to the joined string
+     push rbp
+     mov rbp, rsp
+     push rbx
+     push r12
+     push r13
+     sub rsp, 8
+     mov rbx, rdi
+     mov r12, rsi
+     call strlen
+     mov r13, rax
+     mov rdi, r12
+     call strlen
+     lea rdi, [r13 + rax + 1]
+     call malloc
+     mov rdi, rax
+     mov rsi, rbx
+     call strcpy
+     mov rdi, rax
+     mov rsi, r12
+     call strcat
+
+     lea rsp, [rbp - 24]
+     pop r13
+     pop r12
+     pop rbx
+     pop rbp
+     ret
+
 gaia_string_chars:
      # Get iterator over characters
      # rdi = string pointer
@@ -1167,6 +1795,81 @@ __next_done_no_more:
     pop rbp
     ret
 
+__next_ref:
+    # Get a reference to the next element from iterator (for iter())
+    # rdi = iterator/collection pointer
+    # Returns: rax = address of data[index] (or 0 if iteration done)
+    push rbp
+    mov rbp, rsp
+    
+    # Load current index and collection length
+    lea rax, [rip + __current_iter_idx]
+    mov r8, qword ptr [rax]
+    mov r9, qword ptr [rdi + 8]
+    
+    # Check if index < length
+    cmp r8, r9
+    jge __next_ref_done
+    
+    # Advance index
+    lea rcx, [r8 + 1]
+    mov qword ptr [rax], rcx
+    
+    # Return &data[index]
+    lea rax, [rdi + 16 + r8*8]
+    mov rsp, rbp
+    pop rbp
+    ret
+
+__next_ref_done:
+    xor rax, rax
+    mov rsp, rbp
+    pop rbp
+    ret
+
+# DoubleEndedIterator support for rev(): the index starts at the length and
+# counts down, so next_back() yields data[len-1] down to data[0]
+__into_iter_rev:
+    # Initialize a reverse iterator for a collection
+    # rdi = collection pointer (vec metadata: capacity:i64, length:i64, data...)
+    # Returns: collection pointer (same as input)
+    lea rax, [rip + __current_iter_ptr]
+    mov qword ptr [rax], rdi
+    mov rcx, qword ptr [rdi + 8]
+    lea rax, [rip + __current_iter_idx]
+    mov qword ptr [rax], rcx
+    mov rax, rdi
+    ret
+
+__next_back:
+    # Get the previous element from a reverse iterator
+    # rdi = iterator/collection pointer
+    # Returns: rax = element value (or 0 if iteration done)
+    lea rax, [rip + __current_iter_idx]
+    mov r8, qword ptr [rax]
+    test r8, r8
+    jz __next_back_done
+    sub r8, 1
+    mov qword ptr [rax], r8
+    mov rax, qword ptr [rdi + 16 + r8*8]
+    ret
+__next_back_done:
+    xor rax, rax
+    ret
+
+__next_back_ref:
+    # Get a reference to the previous element from a reverse iterator (for iter().rev())
+    # rdi = iterator/collection pointer
+    # Returns: rax = address of data[index] (or 0 if iteration done)
+    lea rax, [rip + __current_iter_idx]
+    mov r8, qword ptr [rax]
+    test r8, r8
+    jz __next_back_done
+    sub r8, 1
+    mov qword ptr [rax], r8
+    lea rax, [rdi + 16 + r8*8]
+    ret
+
 # Option<T> methods
 # Memory layout: [tag:i64][value:i64] where tag=1 for Some, tag=0 for None
 
@@ -1209,7 +1912,7 @@ option_is_none_done:
 gaia_option_unwrap:
     # Unwrap Option value
     # rdi = Option pointer
-    # Returns: value if Some, panics if None (for now just returns 0)
+    # Returns: value if Some, panics if None
     push rbp
     mov rbp, rsp
     mov rax, [rdi]     # Load tag
@@ -1218,7 +1921,8 @@ gaia_option_unwrap:
     mov rax, [rdi + 8] # Load value at offset 8
     jmp option_unwrap_done
 option_unwrap_panic:
-    xor rax, rax       # Return 0 for None (should panic)
+    lea rsi, [rip + unwrap_none_msg]
+    jmp gaia_unwrap_failed
 option_unwrap_done:
     mov rsp, rbp
     pop rbp
@@ -1438,7 +2142,7 @@ result_is_err_done:
 gaia_result_unwrap:
     # Unwrap Result value
     # rdi = Result pointer
-    # Returns: value if Ok, panics if Err (for now just returns 0)
+    # Returns: value if Ok, panics if Err
     push rbp
     mov rbp, rsp
     mov rax, [rdi]     # Load tag
@@ -1447,7 +2151,8 @@ gaia_result_unwrap:
     mov rax, [rdi + 8] # Load value at offset 8
     jmp result_unwrap_done
 result_unwrap_panic:
-    xor rax, rax       # Return 0 for Err (should panic)
+    lea rsi, [rip + unwrap_err_msg]
+    jmp gaia_unwrap_failed
 result_unwrap_done:
     mov rsp, rbp
     pop rbp
@@ -1465,12 +2170,23 @@ gaia_result_unwrap_err:
     mov rax, [rdi + 8] # Load error at offset 8
     jmp result_unwrap_err_done
 result_unwrap_err_panic:
-    xor rax, rax       # Return 0 for Ok (should panic)
+    lea rsi, [rip + unwrap_err_ok_msg]
+    jmp gaia_unwrap_failed
 result_unwrap_err_done:
     mov rsp, rbp
     pop rbp
     ret
 
+gaia_unwrap_failed:
+    # Shared panic path of the unwrap routines (entered with rbp pushed)
+    # rsi = panic message
+    lea rdi, [rip + panic_custom_fmt]
+    xor rax, rax
+    call printf
+    call gaia_print_backtrace
+    mov rdi, 101       # Exit code 101, as for panic!
+    call exit
+
 gaia_result_unwrap_or:
      # Unwrap Result with default value
      # rdi = Result pointer
@@ -2552,6 +3268,33 @@ String_impl_pow:
       add rsp, 8
       ret
 
+gaia_int_pow:
+      # Integer pow by repeated squaring (wrapping on overflow)
+      # rdi = base
+      # rsi = exponent (u32)
+      # Returns: base^exponent in rax
+      mov rax, 1
+.int_pow_loop:
+      test rsi, rsi
+      jz .int_pow_done
+      test rsi, 1
+      jz .int_pow_square
+      imul rax, rdi
+.int_pow_square:
+      imul rdi, rdi
+      shr rsi, 1
+      jmp .int_pow_loop
+.int_pow_done:
+      ret
+
+gaia_int_abs:
+      # rdi = value
+      # Returns: |value| in rax
+      mov rax, rdi
+      neg rax
+      cmovs rax, rdi
+      ret
+
 String_impl_sin:
       # xmm0 = angle (f64)
       # Call libm sin
@@ -2808,6 +3551,24 @@ __extract_enum_value:
       pop rbp
       ret
 
+# __enum_tag: Read the tag of an enum value laid out as [tag:i64][payload:i64...]
+# rdi = pointer to the enum value (a null pointer reads as tag 0, i.e. None)
+# Returns: the tag in rax
+__enum_tag:
+      xor rax, rax
+      test rdi, rdi
+      jz .enum_tag_done
+      mov rax, [rdi]
+.enum_tag_done:
+      ret
+
+# __extract_enum_field: Read payload slot `rsi` of an enum value
+# rdi = pointer to the enum value, rsi = payload index
+# Returns: the payload in rax
+__extract_enum_field:
+      mov rax, [rdi + rsi*8 + 8]
+      ret
+
 # PHASE 5.2: Runtime support for builtin macros
 
 # assert!(condition) - takes bool in rdi, exits if false
@@ -2821,7 +3582,7 @@ assert:
       sub rsp, 8
       call printf
       add rsp, 8
-      mov rax, 1           # Exit code 1
+      mov rdi, 1           # Exit code 1
       call exit
 .assert_ok:
       mov rsp, rbp
@@ -2839,7 +3600,7 @@ assert_eq:
       sub rsp, 8
       call printf
       add rsp, 8
-      mov rax, 1           # Exit code 1
+      mov rdi, 1           # Exit code 1
       call exit
 .assert_eq_ok:
       mov rsp, rbp
@@ -2857,7 +3618,7 @@ assert_ne:
       sub rsp, 8
       call printf
       add rsp, 8
-      mov rax, 1           # Exit code 1
+      mov rdi, 1           # Exit code 1
       call exit
 .assert_ne_ok:
       mov rsp, rbp
@@ -2882,12 +3643,106 @@ panic:
       xor rax, rax
       call printf
 .panic_exit:
+      call gaia_print_backtrace
       mov rsp, rbp
       pop rbp
-      mov rax, 101         # Exit code 101
+      mov rdi, 101         # Exit code 101
       call exit
       ret
 
+# Failed array bounds check (MIR `Assert`) - rdi = index, rsi = len
+gaia_panic_bounds_check:
+      push rbp
+      mov rbp, rsp
+      mov rdx, rdi         # RDX = index
+      lea rdi, [rip + bounds_check_fmt]
+      xor rax, rax
+      call printf
+      call gaia_print_backtrace
+      mov rdi, 101         # Exit code 101, as for panic!
+      call exit
+
+# Panic backtraces (`--backtrace`). Compiled functions push their name on
+# this shadow stack on entry and pop it before returning; frames past the
+# capacity are counted but not recorded.
+.data
+    gaia_backtrace_depth: .quad 0
+    gaia_backtrace_frames: .zero 2048    # 256 name pointers
+
+.section .text
+
+# gaia_backtrace_push - r10 = function name; clobbers only r11
+gaia_backtrace_push:
+      mov r11, qword ptr [rip + gaia_backtrace_depth]
+      inc qword ptr [rip + gaia_backtrace_depth]
+      cmp r11, 256
+      jae .backtrace_push_done
+      push rax
+      lea rax, [rip + gaia_backtrace_frames]
+      mov qword ptr [rax + r11*8], r10
+      pop rax
+.backtrace_push_done:
+      ret
+
+# gaia_backtrace_pop - drops the innermost frame; clobbers nothing
+gaia_backtrace_pop:
+      dec qword ptr [rip + gaia_backtrace_depth]
+      ret
+
+# Print the shadow stack, innermost call first. Prints nothing when no
+# frames were recorded, i.e. when built without backtraces.
+gaia_print_backtrace:
+      push rbp
+      mov rbp, rsp
+      push rbx
+      push r12
+      and rsp, -16         # Callers may be mid-frame; printf needs alignment
+      mov rbx, qword ptr [rip + gaia_backtrace_depth]
+      test rbx, rbx
+      jle .backtrace_print_done
+      cmp rbx, 256
+      jbe .backtrace_print_header
+      mov rbx, 256
+.backtrace_print_header:
+      lea rdi, [rip + backtrace_header]
+      xor rax, rax
+      call printf
+      xor r12, r12         # Frame number
+.backtrace_print_frame:
+      dec rbx
+      lea rax, [rip + gaia_backtrace_frames]
+      mov rdx, qword ptr [rax + rbx*8]
+      mov rsi, r12
+      lea rdi, [rip + backtrace_frame_fmt]
+      xor rax, rax
+      call printf
+      inc r12
+      test rbx, rbx
+      jnz .backtrace_print_frame
+.backtrace_print_done:
+      lea rsp, [rbp - 16]
+      pop r12
+      pop rbx
+      pop rbp
+      ret
+
+# Stack protector (`stack_protector`). Functions with stack arrays copy the
+# guard into their frame on entry and compare it again before returning.
+# The low byte is zero so string overflows can't reproduce it.
+.data
+    __stack_chk_guard: .quad 0x5f3c9a71e2d4b600
+
+.section .text
+
+# gaia_stack_chk_fail - the canary was overwritten; never returns
+gaia_stack_chk_fail:
+      and rsp, -16         # The frame around us is not to be trusted
+      lea rdi, [rip + stack_chk_fail_msg]
+      xor rax, rax
+      call printf
+      mov rdi, 134         # Exit code of an abort, as for glibc's check
+      call exit
+
 # format!(fmt, ...) - takes format string in rdi, returns string (stub implementation)
 format:
       push rbp
@@ -2923,7 +3778,7 @@ todo:
       add rsp, 8
       mov rsp, rbp
       pop rbp
-      mov rax, 101         # Exit code 101 (convention for unimplemented)
+      mov rdi, 101         # Exit code 101 (convention for unimplemented)
       call exit
       ret
 
@@ -2937,13 +3792,202 @@ unimplemented:
       add rsp, 8
       mov rsp, rbp
       pop rbp
-      mov rax, 101         # Exit code 101 (convention for unimplemented)
+      mov rdi, 101         # Exit code 101 (convention for unimplemented)
+      call exit
+      ret
+
+# Test harness (`--test`). Each #[test] function runs in a forked child, so
+# a panic - which exits the process - fails only that test.
+.data
+    gaia_tests_passed: .quad 0
+    gaia_tests_failed: .quad 0
+
+.section .text
+
+# gaia_run_test(test, name) - rdi = test function, rsi = test name
+gaia_run_test:
+      push rbx
+      push r12
+      sub rsp, 24          # Child's wait status; keeps rsp 16-byte aligned
+      mov rbx, rdi
+      mov r12, rsi
+      mov qword ptr [rsp], 1   # Count a failed fork as a failed test
+      xor rdi, rdi         # Flush stdout so the child doesn't repeat buffered output
+      call fflush
+      call fork
+      test eax, eax
+      jz .run_test_child
+      mov edi, eax
+      lea rsi, [rsp]
+      xor rdx, rdx
+      call waitpid
+      cmp dword ptr [rsp], 0   # Passed only if the child exited with status 0
+      jne .run_test_failed
+      inc qword ptr [rip + gaia_tests_passed]
+      lea rdi, [rip + test_ok_fmt]
+      jmp .run_test_report
+.run_test_failed:
+      inc qword ptr [rip + gaia_tests_failed]
+      lea rdi, [rip + test_failed_fmt]
+.run_test_report:
+      mov rsi, r12
+      xor rax, rax
+      call printf
+      add rsp, 24
+      pop r12
+      pop rbx
+      ret
+.run_test_child:
+      call rbx
+      xor rdi, rdi
       call exit
+
+# gaia_test_summary() - prints the totals and exits, with 101 if any test failed
+gaia_test_summary:
+      push rbp
+      mov rbp, rsp
+      lea rsi, [rip + test_result_ok]
+      cmp qword ptr [rip + gaia_tests_failed], 0
+      je .test_summary_print
+      lea rsi, [rip + test_result_failed]
+.test_summary_print:
+      lea rdi, [rip + test_summary_fmt]
+      mov rdx, qword ptr [rip + gaia_tests_passed]
+      mov rcx, qword ptr [rip + gaia_tests_failed]
+      xor rax, rax
+      call printf
+      xor rdi, rdi
+      cmp qword ptr [rip + gaia_tests_failed], 0
+      je .test_summary_exit
+      mov rdi, 101
+.test_summary_exit:
+      call exit
+
+# Bench harness clock, read on either side of each Bencher::iter loop
+# gaia_bench_now() -> rax = CLOCK_MONOTONIC time in nanoseconds
+gaia_bench_now:
+      sub rsp, 24          # struct timespec, keeping rsp 16-byte aligned
+      mov rdi, 1           # CLOCK_MONOTONIC
+      mov rsi, rsp
+      call clock_gettime
+      mov rax, [rsp]       # tv_sec
+      imul rax, rax, 1000000000
+      add rax, [rsp + 8]   # tv_nsec
+      add rsp, 24
+      ret
+
+# The time the last Bencher::iter loop took. `Bencher` is passed by value, so
+# the loop leaves its time here for the harness to read after the bench returns.
+.data
+    gaia_bench_elapsed_ns: .quad 0
+
+.section .text
+
+# gaia_bench_record(ns) - rdi = nanoseconds the loop took
+gaia_bench_record:
+      mov qword ptr [rip + gaia_bench_elapsed_ns], rdi
+      ret
+
+# gaia_bench_elapsed() -> rax = nanoseconds the last loop took
+gaia_bench_elapsed:
+      mov rax, qword ptr [rip + gaia_bench_elapsed_ns]
       ret
 "#
     .to_string()
 }
 
+/// I/O helpers that go straight to the kernel through `syscall` instead of
+/// libc, for `no_std` programs. `print`/`println` of a string or an integer
+/// lower to these when libc isn't linked.
+pub fn generate_syscall_io() -> String {
+    r#"
+.section .text
+.globl gaia_write
+.globl gaia_read
+.globl gaia_write_str
+.globl gaia_write_line
+.globl gaia_write_i32_line
+.globl gaia_write_i64_line
+
+# gaia_write(fd, ptr, len) -> bytes written or -errno (write is syscall 1)
+gaia_write:
+    mov rax, 1
+    syscall
+    ret
+
+# gaia_read(fd, ptr, len) -> bytes read or -errno (read is syscall 0)
+gaia_read:
+    xor rax, rax
+    syscall
+    ret
+
+# gaia_write_str(ptr): write a NUL-terminated string to stdout
+gaia_write_str:
+    mov rsi, rdi
+    xor rdx, rdx
+.gaia_write_str_len:
+    cmp byte ptr [rsi + rdx], 0
+    je .gaia_write_str_out
+    inc rdx
+    jmp .gaia_write_str_len
+.gaia_write_str_out:
+    mov rdi, 1
+    jmp gaia_write
+
+# gaia_write_line(ptr): write a NUL-terminated string and a newline to stdout
+gaia_write_line:
+    sub rsp, 8
+    call gaia_write_str
+    mov byte ptr [rsp], 10
+    mov rdi, 1
+    mov rsi, rsp
+    mov rdx, 1
+    call gaia_write
+    add rsp, 8
+    ret
+
+# gaia_write_i32_line(value): sign-extend an i32 and write it like an i64
+gaia_write_i32_line:
+    movsxd rdi, edi
+    jmp gaia_write_i64_line
+
+# gaia_write_i64_line(value): write a signed integer in decimal and a newline
+# to stdout. The digits are built backwards in a buffer on the stack.
+gaia_write_i64_line:
+    push rbp
+    mov rbp, rsp
+    sub rsp, 32
+    mov rax, rdi
+    lea rsi, [rbp - 1]
+    mov byte ptr [rsi], 10
+    mov rcx, 10
+    test rax, rax
+    jns .gaia_write_i64_digits
+    neg rax
+.gaia_write_i64_digits:
+    xor rdx, rdx
+    div rcx
+    add dl, 48
+    dec rsi
+    mov byte ptr [rsi], dl
+    test rax, rax
+    jnz .gaia_write_i64_digits
+    test rdi, rdi
+    jns .gaia_write_i64_out
+    dec rsi
+    mov byte ptr [rsi], 45
+.gaia_write_i64_out:
+    lea rdx, [rbp]
+    sub rdx, rsi
+    mov rdi, 1
+    call gaia_write
+    mov rsp, rbp
+    pop rbp
+    ret
+"#
+    .to_string()
+}
+
 /// Generate a main function that calls the user's main entry point
 pub fn generate_main_wrapper() -> String {
      r#"
@@ -2973,6 +4017,20 @@ mod tests {
         assert!(runtime.contains("printf"));
     }
 
+    #[test]
+    fn test_libc_symbols_cover_runtime_calls() {
+        let runtime = generate_runtime_assembly();
+        let code: Vec<&str> = runtime.lines().map(|line| line.split('#').next().unwrap_or("").trim()).collect();
+        let defined: Vec<&str> = code.iter().filter_map(|line| line.strip_suffix(':')).collect();
+        for target in code.iter().filter_map(|line| line.strip_prefix("call ")) {
+            let target = target.trim();
+            let is_register = target.starts_with('r') && target.len() <= 3;
+            if !is_register && !defined.contains(&target) {
+                assert!(LIBC_SYMBOLS.contains(&target), "runtime calls `{}`, missing from LIBC_SYMBOLS", target);
+            }
+        }
+    }
+
     #[test]
     fn test_main_wrapper() {
         let main = generate_main_wrapper();
@@ -60,9 +60,9 @@ impl SourceError {
         // Header: error[CODE]: message
         output.push_str(&format!(
             "{}error[{}]{}: {}\n",
-            crate::formatter::Colors::RED,
+            crate::formatter::Colors::red(),
             self.error_code,
-            crate::formatter::Colors::RESET,
+            crate::formatter::Colors::reset(),
             self.message
         ));
 
@@ -74,10 +74,10 @@ impl SourceError {
         // Type mismatch details
         output.push_str(&format!(
             "{}  expected `{}`, found `{}`{}\n",
-            crate::formatter::Colors::CYAN,
+            crate::formatter::Colors::cyan(),
             self.expected,
             self.found,
-            crate::formatter::Colors::RESET
+            crate::formatter::Colors::reset()
         ));
 
         // Suggestions
@@ -85,8 +85,8 @@ impl SourceError {
             output.push_str("\n");
             output.push_str(&format!(
                 "{}help:{} Consider these options:\n",
-                crate::formatter::Colors::GREEN,
-                crate::formatter::Colors::RESET
+                crate::formatter::Colors::green(),
+                crate::formatter::Colors::reset()
             ));
             for (idx, suggestion) in self.suggestions.iter().enumerate() {
                 output.push_str(&format!("  {}. {}\n", idx + 1, suggestion));
@@ -158,19 +158,19 @@ impl SourceError {
         let line_num_width = self.line_number.to_string().len().max(3);
         let padding = " ".repeat(line_num_width);
 
-        output.push_str(&format!("{}  |{}\n", padding, crate::formatter::Colors::DIM));
+        output.push_str(&format!("{}  |{}\n", padding, crate::formatter::Colors::dim()));
 
         // Source code line
         output.push_str(&format!(
             "{}{}|{} {}\n",
-            crate::formatter::Colors::DIM,
+            crate::formatter::Colors::dim(),
             self.line_number,
-            crate::formatter::Colors::RESET,
+            crate::formatter::Colors::reset(),
             source_line
         ));
 
         // Error pointer line
-        output.push_str(&format!("{}  |{} ", padding, crate::formatter::Colors::DIM));
+        output.push_str(&format!("{}  |{} ", padding, crate::formatter::Colors::dim()));
 
         // Spacing to error location
         let mut pointer_line = String::new();
@@ -185,9 +185,9 @@ impl SourceError {
 
         output.push_str(&format!(
             "{}{}{}",
-            crate::formatter::Colors::RED,
+            crate::formatter::Colors::red(),
             pointer_line,
-            crate::formatter::Colors::RESET
+            crate::formatter::Colors::reset()
         ));
         output.push('\n');
 
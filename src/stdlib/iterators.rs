@@ -230,6 +230,46 @@ impl<'a> Iterator for StringIter<'a> {
     }
 }
 
+/// Lazy split iterator - yields the pieces of a string between delimiters
+pub struct SplitIter<'a> {
+    parts: std::str::Split<'a, char>,
+}
+
+impl<'a> SplitIter<'a> {
+    /// Create a new split iterator over `s`
+    pub fn new(s: &'a str, delimiter: char) -> Self {
+        SplitIter { parts: s.split(delimiter) }
+    }
+}
+
+impl<'a> Iterator for SplitIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parts.next()
+    }
+}
+
+/// Lazy line iterator - yields lines without their `\n` or `\r\n` endings
+pub struct LinesIter<'a> {
+    lines: std::str::Lines<'a>,
+}
+
+impl<'a> LinesIter<'a> {
+    /// Create a new line iterator over `s`
+    pub fn new(s: &'a str) -> Self {
+        LinesIter { lines: s.lines() }
+    }
+}
+
+impl<'a> Iterator for LinesIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lines.next()
+    }
+}
+
 /// Map adapter - transforms items with a closure
 pub struct Map<I, F>
 where
@@ -346,6 +386,15 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn test_split_iter_with_filter() {
+        let iter = SplitIter::new("a,,b", ',');
+        let mut non_empty = Filter::new(iter, |part| !part.is_empty());
+        assert_eq!(non_empty.next(), Some("a"));
+        assert_eq!(non_empty.next(), Some("b"));
+        assert_eq!(non_empty.next(), None);
+    }
+
     #[test]
     fn test_iterator_count() {
         let iter = RangeIter::new_exclusive(1, 4);
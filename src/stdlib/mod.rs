@@ -8,6 +8,7 @@ pub mod vec;
 pub mod iterators;
 pub mod options_results;
 pub mod method_resolution;
+pub mod prelude_source;
 mod integration_tests;
 
 // Re-export commonly used types and traits
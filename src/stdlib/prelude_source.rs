@@ -0,0 +1,114 @@
+//! # Source-Level Prelude
+//!
+//! Stdlib items written in Rust rather than provided by the runtime.
+//! They are lowered to HIR once per process and merged into each
+//! program compiled by `compile_files` that calls them.
+
+use crate::lexer;
+use crate::lowering::{self, HirItem};
+use crate::parser;
+use std::sync::OnceLock;
+
+/// Rust source of the prelude. Like `std::cmp`, on equal values `min`
+/// returns its first argument and `max` its second.
+pub const PRELUDE_SOURCE: &str = r#"
+fn min(a: i64, b: i64) -> i64 {
+    if b < a { b } else { a }
+}
+
+fn max(a: i64, b: i64) -> i64 {
+    if b < a { a } else { b }
+}
+"#;
+
+static PRELUDE_HIR: OnceLock<Vec<HirItem>> = OnceLock::new();
+
+/// Lowered prelude items, built on first use and cached for the process
+pub fn prelude_hir() -> &'static [HirItem] {
+    PRELUDE_HIR.get_or_init(|| {
+        let tokens = lexer::lex(PRELUDE_SOURCE).expect("stdlib prelude failed to lex");
+        let ast = parser::parse(tokens).expect("stdlib prelude failed to parse");
+        // Lower as a crate root so items keep their plain names
+        lowering::set_current_file("lib.rs");
+        lowering::lower(&ast).expect("stdlib prelude failed to lower")
+    })
+}
+
+/// Append the prelude to a program's HIR, skipping any function the
+/// program defines itself or never calls. Returns the names of the
+/// functions it added.
+pub fn merge_prelude(items: &mut Vec<HirItem>) -> Vec<String> {
+    let user_functions: Vec<String> = items
+        .iter()
+        .filter_map(|item| match item {
+            HirItem::Function { name, .. } => Some(name.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut used = crate::lints::referenced_names(items);
+
+    let mut merged = Vec::new();
+    for item in prelude_hir() {
+        if !matches!(item, HirItem::Function { .. }) {
+            items.push(item.clone());
+        }
+    }
+    // A prelude function may call another, so repeat until none is added
+    loop {
+        let mut added = false;
+        for item in prelude_hir() {
+            let HirItem::Function { name, .. } = item else { continue };
+            if user_functions.contains(name) || merged.contains(name) || !used.contains(name) {
+                continue;
+            }
+            used.extend(crate::lints::referenced_names(std::slice::from_ref(item)));
+            merged.push(name.clone());
+            items.push(item.clone());
+            added = true;
+        }
+        if !added {
+            break;
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prelude_lowers() {
+        let names: Vec<&str> = prelude_hir()
+            .iter()
+            .filter_map(|item| match item {
+                HirItem::Function { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert!(names.contains(&"min"));
+        assert!(names.contains(&"max"));
+    }
+
+    #[test]
+    fn test_merge_prelude_keeps_user_definition() {
+        let mut items = prelude_hir()
+            .iter()
+            .filter(|item| matches!(item, HirItem::Function { name, .. } if name == "min"))
+            .cloned()
+            .collect::<Vec<_>>();
+        merge_prelude(&mut items);
+        let mins = items
+            .iter()
+            .filter(|item| matches!(item, HirItem::Function { name, .. } if name == "min"))
+            .count();
+        assert_eq!(mins, 1);
+    }
+
+    #[test]
+    fn test_merge_prelude_skips_uncalled_functions() {
+        let mut items = Vec::new();
+        assert!(merge_prelude(&mut items).is_empty());
+        assert!(items.is_empty());
+    }
+}
@@ -4,6 +4,7 @@
 //! Unlike `&str` (string slice), String can be modified and extended.
 
 use std::collections::HashMap;
+use crate::stdlib::iterators::{LinesIter, SplitIter};
 
 /// Represents the String type in the type system
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -114,6 +115,13 @@ impl StringValue {
         &self.data
     }
 
+    /// Raw pointer to the UTF-8 bytes, for handing the text to C. The
+    /// bytes are not NUL-terminated, and reading through the pointer is
+    /// `unsafe` and only valid while the string is left unchanged.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.data.as_ptr()
+    }
+
     /// Concatenate with another string (implements + operator)
     pub fn concat(&self, other: &str) -> StringValue {
         let mut result = self.clone();
@@ -146,6 +154,23 @@ impl StringValue {
             .collect()
     }
 
+    /// Lazily split the string by delimiter, without collecting the parts
+    pub fn split_iter(&self, delimiter: char) -> SplitIter<'_> {
+        SplitIter::new(&self.data, delimiter)
+    }
+
+    /// Lazily iterate over the lines of the string
+    pub fn lines_iter(&self) -> LinesIter<'_> {
+        LinesIter::new(&self.data)
+    }
+
+    /// Concatenate `parts`, using this string as the separator
+    pub fn join(&self, parts: &[&str]) -> StringValue {
+        StringValue {
+            data: parts.join(&self.data),
+        }
+    }
+
     /// Replace all occurrences of a pattern with replacement
     pub fn replace(&self, from: &str, to: &str) -> StringValue {
         StringValue {
@@ -229,9 +254,13 @@ impl StringMethodRegistry {
 
         // Splitting methods
         methods.insert("split".to_string(), "(&self, char) -> Vec<String>".to_string());
+        methods.insert("split_iter".to_string(), "(&self, char) -> SplitIter".to_string());
+        methods.insert("lines_iter".to_string(), "(&self) -> LinesIter".to_string());
+        methods.insert("join".to_string(), "(&self, &[&str]) -> String".to_string());
 
         // Utility methods
         methods.insert("as_str".to_string(), "(&self) -> &str".to_string());
+        methods.insert("as_ptr".to_string(), "(&self) -> *const u8".to_string());
         methods.insert("is_whitespace".to_string(), "(&self) -> bool".to_string());
         methods.insert("substring".to_string(), "(&self, usize, usize) -> Option<String>".to_string());
 
@@ -373,6 +402,32 @@ mod tests {
         assert_eq!(parts.len(), 3);
     }
 
+    #[test]
+    fn test_string_split_iter() {
+        use crate::stdlib::iterators::Iterator;
+
+        let s = StringValue::from("a,b,c");
+        let mut parts = s.split_iter(',');
+        assert_eq!(parts.next(), Some("a"));
+        assert_eq!(parts.next(), Some("b"));
+        assert_eq!(parts.next(), Some("c"));
+        assert_eq!(parts.next(), None);
+    }
+
+    #[test]
+    fn test_string_lines_iter() {
+        use crate::stdlib::iterators::Iterator;
+
+        let s = StringValue::from("one\ntwo\r\nthree");
+        assert_eq!(s.lines_iter().count(), 3);
+    }
+
+    #[test]
+    fn test_string_join() {
+        let sep = StringValue::from("-");
+        assert_eq!(sep.join(&["a", "b"]).as_str(), "a-b");
+    }
+
     #[test]
     fn test_string_replace() {
         let s = StringValue::from("hello world");
@@ -394,6 +449,14 @@ mod tests {
         assert_eq!(reversed.as_str(), "olleh");
     }
 
+    #[test]
+    fn test_string_as_ptr() {
+        let s = StringValue::from("hi");
+        let ptr = s.as_ptr();
+        assert_eq!(ptr, s.as_str().as_ptr());
+        assert_eq!(unsafe { *ptr }, b'h');
+    }
+
     #[test]
     fn test_string_with_capacity() {
         let s = StringValue::with_capacity(100);
@@ -132,6 +132,19 @@ impl<T: Clone + PartialEq + Eq + std::fmt::Debug> VecValue<T> {
         &mut self.data
     }
 
+    /// Raw pointer to the backing buffer, for handing the elements to C.
+    /// Reading through it is `unsafe` and only valid while the vec is
+    /// neither dropped nor reallocated.
+    pub fn as_ptr(&self) -> *const T {
+        self.data.as_ptr()
+    }
+
+    /// Mutable raw pointer to the backing buffer, under the same rules as
+    /// [`VecValue::as_ptr`]
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.data.as_mut_ptr()
+    }
+
     /// Convert to the underlying Vec
     pub fn into_vec(self) -> Vec<T> {
         self.data
@@ -141,6 +154,31 @@ impl<T: Clone + PartialEq + Eq + std::fmt::Debug> VecValue<T> {
     pub fn from_vec(vec: Vec<T>) -> Self {
         VecValue { data: vec }
     }
+
+    /// A new Vec holding `f` applied to each element, in order
+    pub fn map<U, F>(&self, f: F) -> VecValue<U>
+    where
+        U: Clone + PartialEq + Eq + std::fmt::Debug,
+        F: FnMut(&T) -> U,
+    {
+        VecValue { data: self.data.iter().map(f).collect() }
+    }
+
+    /// A new Vec holding copies of the elements that satisfy `predicate`
+    pub fn filter<P>(&self, mut predicate: P) -> VecValue<T>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        VecValue { data: self.data.iter().filter(|x| predicate(x)).cloned().collect() }
+    }
+
+    /// Reduce the elements to one value, threading an accumulator from `init`
+    pub fn fold<A, F>(&self, init: A, f: F) -> A
+    where
+        F: FnMut(A, &T) -> A,
+    {
+        self.data.iter().fold(init, f)
+    }
 }
 
 impl<T: Clone + PartialEq + Eq + std::fmt::Debug + std::cmp::PartialOrd> VecValue<T> {
@@ -233,10 +271,19 @@ impl VecMethodRegistry {
         methods.insert("sort".to_string(), "(&mut self)".to_string());
         methods.insert("reverse".to_string(), "(&mut self)".to_string());
 
+        // Eager transformations
+        methods.insert("map".to_string(), "(&self, Fn(&T) -> U) -> Vec<U>".to_string());
+        methods.insert("filter".to_string(), "(&self, Fn(&T) -> bool) -> Vec<T>".to_string());
+        methods.insert("fold".to_string(), "(&self, A, Fn(A, &T) -> A) -> A".to_string());
+
         // Slice methods
         methods.insert("as_slice".to_string(), "(&self) -> &[T]".to_string());
         methods.insert("as_mut_slice".to_string(), "(&mut self) -> &mut [T]".to_string());
 
+        // Raw pointer methods
+        methods.insert("as_ptr".to_string(), "(&self) -> *const T".to_string());
+        methods.insert("as_mut_ptr".to_string(), "(&mut self) -> *mut T".to_string());
+
         methods
     }
 
@@ -416,6 +463,42 @@ mod tests {
         assert!(sig.unwrap().contains("usize"));
     }
 
+    #[test]
+    fn test_vec_map() {
+        let v = VecValue::from_vec(vec![1, 2, 3]);
+        let doubled = v.map(|x| x * 2);
+        assert_eq!(doubled.into_vec(), vec![2, 4, 6]);
+        assert_eq!(v.len(), 3);
+    }
+
+    #[test]
+    fn test_vec_filter() {
+        let v = VecValue::from_vec(vec![1, 2, 3, 4, 5, 6]);
+        let evens = v.filter(|x| x % 2 == 0);
+        assert_eq!(evens.into_vec(), vec![2, 4, 6]);
+    }
+
+    #[test]
+    fn test_vec_fold() {
+        let v = VecValue::from_vec(vec![1, 2, 3]);
+        assert_eq!(v.fold(0, |sum, x| sum + x), 6);
+        assert_eq!(VecValue::<i32>::new().fold(10, |sum, x| sum + x), 10);
+        assert!(VecMethodRegistry::get_signature("fold").is_some());
+    }
+
+    #[test]
+    fn test_vec_as_ptr() {
+        let mut v = VecValue::from_vec(vec![7, 8, 9]);
+        let ptr = v.as_ptr();
+        assert_eq!(ptr, v.as_slice().as_ptr());
+        assert_eq!(unsafe { *ptr }, 7);
+        assert_eq!(unsafe { *ptr.add(2) }, 9);
+
+        let mut_ptr = v.as_mut_ptr();
+        unsafe { *mut_ptr.add(1) = 80 };
+        assert_eq!(v.get(1), Some(&80));
+    }
+
     #[test]
     fn test_vec_as_slice() {
         let mut v: VecValue<i32> = VecValue::new();
@@ -0,0 +1,62 @@
+//! Benchmark Runner for #[bench] Functions
+//!
+//! Compiles the crate with the bench harness (`CompilationConfig::with_bench_harness`),
+//! which passes each `#[bench]` function a `Bencher`. `Bencher::iter(|| ...)`
+//! reads a monotonic clock before and after calling its closure
+//! `bench_iterations` times, so the time is measured inside the program and
+//! doesn't include starting it or printing. The harness prints one
+//! `test <name> ... bench: <n> ns/iter` line per bench, which the runner
+//! collects.
+
+use crate::compiler::compile_files;
+use crate::config::CompilationConfig;
+use std::path::Path;
+use std::process::Command;
+
+/// How long one call of a `#[bench]` function took
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub name: String,
+    pub iterations: u64,
+    pub ns_per_iter: u64,
+}
+
+impl std::fmt::Display for BenchResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "test {} ... bench: {} ns/iter", self.name, self.ns_per_iter)
+    }
+}
+
+impl BenchResult {
+    /// Read back a result line as the bench harness prints it
+    fn parse(line: &str, iterations: u64) -> Option<BenchResult> {
+        let (name, time) = line.strip_prefix("test ")?.split_once(" ... bench: ")?;
+        let ns_per_iter = time.strip_suffix(" ns/iter")?.parse().ok()?;
+        Some(BenchResult { name: name.to_string(), iterations, ns_per_iter })
+    }
+}
+
+/// Build `config`'s crate with the bench harness, run it, and collect the
+/// time of every `#[bench]` function, in the order they ran
+pub fn run_benches(config: &CompilationConfig) -> Result<Vec<BenchResult>, String> {
+    let config = config.clone().with_bench_harness(true);
+    let compiled = compile_files(&config).map_err(|e| e.to_string())?;
+    if !compiled.success {
+        let errors: Vec<String> = compiled.errors.iter().map(|e| e.to_string()).collect();
+        return Err(errors.join("\n"));
+    }
+
+    // A bare `prog` would be looked up on PATH
+    let program = Path::new(".").join(&config.output_path);
+    let output = Command::new(&program)
+        .output()
+        .map_err(|e| format!("failed to run {}: {}", program.display(), e))?;
+    if !output.status.success() {
+        return Err(format!("bench harness exited with {}", output.status));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| BenchResult::parse(line, config.bench_iterations))
+        .collect())
+}
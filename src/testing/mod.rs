@@ -1,4 +1,5 @@
 pub mod framework;
+pub mod bench;
 
 use std::collections::HashMap;
 
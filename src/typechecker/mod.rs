@@ -25,6 +25,7 @@ use crate::parser::ast::GenericParam;
 use crate::parser::Visibility;
 use crate::iterators::IteratorMethodHandler;
 use crate::compiler::{CompileError, ErrorKind};
+use crate::utilities::error_reporting::SourceLocation;
 
 use std::collections::{HashMap, HashSet};
 use std::fmt;
@@ -44,6 +45,86 @@ impl fmt::Display for TypeCheckError {
 
 type TypeCheckResult<T> = Result<T, TypeCheckError>;
 
+/// A trait method's parameters, `self` included, and its return type
+type TraitMethodSig = (Vec<(String, HirType)>, HirType);
+
+/// The trait behind `dyn Trait`, `&dyn Trait` or `Box<dyn Trait>`
+fn trait_object_name(ty: &HirType) -> Option<&str> {
+    match ty {
+        HirType::DynTrait { trait_name } => Some(trait_name),
+        HirType::Reference(inner) | HirType::MutableReference(inner) | HirType::Box(inner) => trait_object_name(inner),
+        _ => None,
+    }
+}
+
+/// The values an integer type can hold, or `None` for non-integer types
+fn integer_range(ty: &HirType) -> Option<(i128, i128)> {
+    let name = match ty {
+        HirType::Int32 => "i32",
+        HirType::Int64 => "i64",
+        HirType::UInt32 => "u32",
+        HirType::UInt64 => "u64",
+        HirType::USize => "usize",
+        HirType::ISize => "isize",
+        HirType::Named(name) => name,
+        _ => return None,
+    };
+    let range = match name {
+        "i8" => (i8::MIN as i128, i8::MAX as i128),
+        "i16" => (i16::MIN as i128, i16::MAX as i128),
+        "i32" => (i32::MIN as i128, i32::MAX as i128),
+        "i64" | "isize" => (i64::MIN as i128, i64::MAX as i128),
+        "u8" => (0, u8::MAX as i128),
+        "u16" => (0, u16::MAX as i128),
+        "u32" => (0, u32::MAX as i128),
+        "u64" | "usize" => (0, u64::MAX as i128),
+        _ => return None,
+    };
+    Some(range)
+}
+
+/// Like rustc's deny-by-default `overflowing_literals`: a literal has to
+/// fit the type it was inferred to
+fn check_literal_fits(value: i128, ty: &HirType) -> TypeCheckResult<()> {
+    match integer_range(ty) {
+        Some((min, max)) if value < min || value > max => Err(TypeCheckError {
+            message: format!(
+                "literal out of range for `{}`: the literal `{}` does not fit into the type `{}` whose range is `{}..={}`",
+                ty, value, ty, min, max
+            ),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// The value of an integer literal at the unsigned or signed type `ty`. The
+/// lexer keeps a literal above `i64::MAX` as its u64 bit pattern, so at an
+/// unsigned type the bits read back as the literal's unsigned magnitude
+fn literal_value(bits: i64, ty: &HirType) -> i128 {
+    match integer_range(ty) {
+        Some((0, _)) => bits as u64 as i128,
+        _ => bits as i128,
+    }
+}
+
+/// The value of `-literal`. The one literal whose magnitude only fits once
+/// negated, `9223372036854775808` in `i64::MIN`, arrives as `i64::MIN` bits
+fn negated_literal_value(bits: i64) -> i128 {
+    if bits == i64::MIN {
+        -(1i128 << 63)
+    } else {
+        -(bits as i128)
+    }
+}
+
+fn is_integer_literal(expr: &HirExpression) -> bool {
+    match expr {
+        HirExpression::Integer(_) => true,
+        HirExpression::UnaryOp { op: UnaryOp::Negate, operand } => matches!(**operand, HirExpression::Integer(_)),
+        _ => false,
+    }
+}
+
 /// Type environment: maps names to types
 #[derive(Debug, Clone)]
 pub struct TypeEnv {
@@ -128,6 +209,8 @@ pub struct TypeContext {
     structs: HashMap<String, Vec<(String, HirType)>>,
     /// Trait definitions: trait_name -> (method_names, method_sigs)
     traits: HashMap<String, HashMap<String, (Vec<HirType>, HirType)>>,
+    /// Methods declared in user traits, with `self`: (trait_name, method_name) -> (params, return_type)
+    trait_methods: HashMap<(String, String), TraitMethodSig>,
     /// Impl block methods: (struct_name, method_name) -> function_signature
     impl_methods: HashMap<(String, String), (Vec<HirType>, HirType)>,
     /// Generic parameter trait bounds: param_name -> Vec<trait_name>
@@ -142,6 +225,10 @@ pub struct TypeContext {
     impl_method_visibility: HashMap<(String, String), bool>,
     /// Current module path for visibility checks: e.g., "crate", "crate::utils"
     current_module: Vec<String>,
+    /// Module each function is defined in: function_name -> module path
+    function_modules: HashMap<String, String>,
+    /// Where each function's name is in the source: function_name -> location
+    function_locations: HashMap<String, SourceLocation>,
     /// Use statement aliases: short_name -> qualified_name (for resolving imports)
     use_aliases: HashMap<String, String>,
     /// Type aliases: alias_name -> target_type (Phase 6.4c)
@@ -161,6 +248,7 @@ impl TypeContext {
              functions: HashMap::new(),
              structs: HashMap::new(),
              traits: HashMap::new(),
+             trait_methods: HashMap::new(),
              impl_methods: HashMap::new(),
              generic_bounds: HashMap::new(),
              trait_impls: HashMap::new(),
@@ -168,6 +256,8 @@ impl TypeContext {
              struct_visibility: HashMap::new(),
              impl_method_visibility: HashMap::new(),
              current_module: vec!["crate".to_string()],
+             function_modules: HashMap::new(),
+             function_locations: HashMap::new(),
              use_aliases: HashMap::new(),
              type_aliases: HashMap::new(),
              generic_params: HashMap::new(),
@@ -268,6 +358,17 @@ impl TypeContext {
          item_name.contains("::")
      }
      
+     /// Whether `name` was defined in the module being checked or in one of
+     /// its ancestors. `Type::method` is qualified by its type, so this is
+     /// how a method's own module is found.
+     fn defined_in_enclosing_module(&self, name: &str) -> bool {
+         let Some(module) = self.function_modules.get(name) else {
+             return false;
+         };
+         let current = self.current_module.join("::");
+         current == *module || current.starts_with(&format!("{}::", module))
+     }
+     
      /// Check visibility of a function - returns error message if not accessible
      fn check_function_visibility(&self, func_name: &str) -> Option<String> {
          if !func_name.contains("::") {
@@ -275,6 +376,11 @@ impl TypeContext {
              return None;
          }
          
+         // Private items are visible throughout the module that defines them
+         if self.defined_in_enclosing_module(func_name) {
+             return None;
+         }
+         
          // Check advanced visibility first (from lowering phase)
          if let Some(visibility) = get_visibility(func_name) {
              match visibility {
@@ -455,11 +561,27 @@ impl TypeContext {
     fn lookup_trait_impl(&self, struct_name: &str, trait_name: &str) -> Option<HirType> {
         self.trait_impls.get(&(struct_name.to_string(), trait_name.to_string())).cloned()
     }
+
+    /// Register a method declared in a user trait
+    fn register_trait_method(&mut self, trait_name: String, method_name: String, params: Vec<(String, HirType)>, ret: HirType) {
+        self.trait_methods.insert((trait_name, method_name), (params, ret));
+    }
+
+    /// Look up a method declared in a user trait
+    fn lookup_trait_method(&self, trait_name: &str, method_name: &str) -> Option<TraitMethodSig> {
+        self.trait_methods.get(&(trait_name.to_string(), method_name.to_string())).cloned()
+    }
 }
 
 /// Type checking and inference
 pub struct TypeChecker {
     pub context: TypeContext,
+    /// The callee of a call with the wrong number of arguments, so the error
+    /// can point at its definition
+    miscalled_function: Option<String>,
+    /// Where a field the struct doesn't declare is accessed, so the error
+    /// can point at it
+    misaccessed_field: Option<SourceLocation>,
 }
 
 impl TypeChecker {
@@ -467,22 +589,51 @@ impl TypeChecker {
     pub fn new() -> Self {
         let mut checker = TypeChecker {
             context: TypeContext::new(),
+            miscalled_function: None,
+            misaccessed_field: None,
         };
         checker.register_builtin_functions();
         checker
     }
 
+    /// Error for a call whose argument count doesn't match the callee's
+    /// parameters (not counting `self`)
+    fn argument_count_error(&mut self, callee: &str, expected: usize, found: usize) -> TypeCheckError {
+        self.miscalled_function = Some(callee.to_string());
+        TypeCheckError {
+            message: format!(
+                "wrong number of arguments to `{}`: expected {} argument{}, found {}",
+                callee,
+                expected,
+                if expected == 1 { "" } else { "s" },
+                found
+            ),
+        }
+    }
+
+    /// Error for a field access naming a field the struct doesn't declare
+    fn missing_field_error(&mut self, struct_name: &str, field: &str, location: Option<SourceLocation>) -> TypeCheckError {
+        self.misaccessed_field = location;
+        TypeCheckError {
+            message: format!("no field `{}` on type `{}`", field, struct_name),
+        }
+    }
+
     /// Register all built-in functions
     fn register_builtin_functions(&mut self) {
         // Math functions
         self.context.register_function("abs".to_string(), vec![HirType::Int32], HirType::Int32);
         self.context.register_function("min".to_string(), vec![HirType::Int32, HirType::Int32], HirType::Int32);
         self.context.register_function("max".to_string(), vec![HirType::Int32, HirType::Int32], HirType::Int32);
-        self.context.register_function("pow".to_string(), vec![HirType::Int64, HirType::Int64], HirType::Int64);
+        // Float math (sqrt inline, the rest via libm)
+        self.context.register_function("pow".to_string(), vec![HirType::Float64, HirType::Float64], HirType::Float64);
         self.context.register_function("sqrt".to_string(), vec![HirType::Float64], HirType::Float64);
         self.context.register_function("floor".to_string(), vec![HirType::Float64], HirType::Float64);
         self.context.register_function("ceil".to_string(), vec![HirType::Float64], HirType::Float64);
         self.context.register_function("round".to_string(), vec![HirType::Float64], HirType::Float64);
+        self.context.register_function("sin".to_string(), vec![HirType::Float64], HirType::Float64);
+        self.context.register_function("cos".to_string(), vec![HirType::Float64], HirType::Float64);
+        self.context.register_function("ln".to_string(), vec![HirType::Float64], HirType::Float64);
 
         // String/Array functions
         self.context.register_function("len".to_string(), vec![HirType::String], HirType::Int32);
@@ -527,6 +678,9 @@ impl TypeChecker {
         // This is a polymorphic function that returns the inner type
         // Type checker will infer return type from context
         self.context.register_function("__extract_enum_value".to_string(), vec![HirType::Unknown], HirType::Unknown);
+        // Tag and payload slots of a `[tag][payload...]` enum value, used by match lowering
+        self.context.register_function("__enum_tag".to_string(), vec![HirType::Unknown], HirType::Int64);
+        self.context.register_function("__extract_enum_field".to_string(), vec![HirType::Unknown, HirType::Int64], HirType::Unknown);
         
         // Type-aware print functions (used by println! lowering for different types)
         self.context.register_function("gaia_print_i32".to_string(), vec![HirType::Int32], HirType::Tuple(vec![]));
@@ -610,7 +764,7 @@ impl TypeChecker {
         // Vec methods
          self.context.register_function("Vec::push".to_string(), vec![HirType::Named("Vec".to_string()), HirType::Unknown], HirType::Tuple(vec![]));
          self.context.register_function("Vec::pop".to_string(), vec![HirType::Named("Vec".to_string())], HirType::Unknown);
-         self.context.register_function("Vec::get".to_string(), vec![HirType::Named("Vec".to_string()), HirType::Int32], HirType::Unknown);
+         self.context.register_function("Vec::get".to_string(), vec![HirType::Named("Vec".to_string()), HirType::Int32], HirType::Option(Box::new(HirType::Unknown)));
          self.context.register_function("Vec::len".to_string(), vec![HirType::Named("Vec".to_string())], HirType::Int32);
          self.context.register_function("Vec::is_empty".to_string(), vec![HirType::Named("Vec".to_string())], HirType::Bool);
          self.context.register_function("Vec::insert".to_string(), vec![HirType::Named("Vec".to_string()), HirType::Int32, HirType::Unknown], HirType::Tuple(vec![]));
@@ -618,6 +772,12 @@ impl TypeChecker {
          self.context.register_function("Vec::clear".to_string(), vec![HirType::Named("Vec".to_string())], HirType::Tuple(vec![]));
          self.context.register_function("Vec::reserve".to_string(), vec![HirType::Named("Vec".to_string()), HirType::Int32], HirType::Tuple(vec![]));
          self.context.register_function("Vec::into_iter".to_string(), vec![HirType::Named("Vec".to_string())], HirType::Named("Iterator".to_string()));
+         self.context.register_function("Vec::clone".to_string(), vec![HirType::Named("Vec".to_string())], HirType::Named("Vec".to_string()));
+         self.context.register_function("Vec::sort".to_string(), vec![HirType::Named("Vec".to_string())], HirType::Tuple(vec![]));
+         self.context.register_function("Vec::sort_unstable".to_string(), vec![HirType::Named("Vec".to_string())], HirType::Tuple(vec![]));
+         // Deep copy of a Vec field, used by #[derive(Clone)] lowering
+         self.context.register_function("gaia_vec_clone".to_string(), vec![HirType::Unknown], HirType::Unknown);
+         self.context.register_function("gaia_vec_swap".to_string(), vec![HirType::Unknown, HirType::Int64, HirType::Int64], HirType::Tuple(vec![]));
         
         // HashMap methods
          self.context.register_function("HashMap::insert".to_string(), vec![HirType::Named("HashMap".to_string()), HirType::Unknown, HirType::Unknown], HirType::Tuple(vec![]));
@@ -627,6 +787,42 @@ impl TypeChecker {
          self.context.register_function("HashMap::len".to_string(), vec![HirType::Named("HashMap".to_string())], HirType::Int32);
          self.context.register_function("HashMap::clear".to_string(), vec![HirType::Named("HashMap".to_string())], HirType::Tuple(vec![]));
          self.context.register_function("HashMap::contains_key".to_string(), vec![HirType::Named("HashMap".to_string()), HirType::Unknown], HirType::Bool);
+         // `map.entry(key).or_insert(default)`, lowered to a pointer to the value slot
+         self.context.register_function("gaia_hashmap_entry_or_insert".to_string(), vec![HirType::Named("HashMap".to_string()), HirType::Unknown, HirType::Unknown], HirType::MutableReference(Box::new(HirType::Unknown)));
+         // Derived Hash folds fields with this (see #[derive(Hash)] lowering)
+         self.context.register_function("gaia_hash_combine".to_string(), vec![HirType::Int64, HirType::Int64], HirType::Int64);
+         self.context.register_function("gaia_hash_str".to_string(), vec![HirType::Unknown], HirType::Int64);
+         // A derived-Hash map key: hash, then (is_string, value) per field
+         self.context.register_function("gaia_hash_key".to_string(), vec![HirType::Int64, HirType::Int64], HirType::Int64);
+         self.context.register_function("gaia_hash_key_field".to_string(), vec![HirType::Int64, HirType::Int64, HirType::Unknown], HirType::Int64);
+         self.context.register_function("gaia_hashmap_canonical_key".to_string(), vec![HirType::Unknown, HirType::Int64, HirType::Int64], HirType::Int64);
+         let ordering = HirType::Named("Ordering".to_string());
+         self.context.register_function("gaia_cmp".to_string(), vec![HirType::Int64, HirType::Int64], ordering.clone());
+         self.context.register_function("gaia_ucmp".to_string(), vec![HirType::UInt64, HirType::UInt64], ordering.clone());
+         self.context.register_function("gaia_str_cmp".to_string(), vec![HirType::String, HirType::String], ordering.clone());
+         self.context.register_function("gaia_cmp_then".to_string(), vec![ordering.clone(), ordering.clone()], ordering);
+         // Raw heap allocation for `*mut T`; the caller casts to the pointee type
+         self.context.register_function("gaia_alloc".to_string(), vec![HirType::Int64], HirType::Pointer(Box::new(HirType::Unknown)));
+         self.context.register_function("gaia_ptr_read".to_string(), vec![HirType::Unknown, HirType::Int64, HirType::Int64], HirType::Unknown);
+         self.context.register_function("gaia_ptr_write".to_string(), vec![HirType::Unknown, HirType::Int64, HirType::Unknown], HirType::Tuple(vec![]));
+         // `format!` and `write!` lower to these (see lowering's formatted_string)
+         self.context.register_function("gaia_format".to_string(), vec![], HirType::String);
+         self.context.register_function("gaia_string_concat".to_string(), vec![HirType::Unknown, HirType::Unknown], HirType::String);
+         // Entry points of the `--test` harness main
+         self.context.register_function(
+             "gaia_run_test".to_string(),
+             vec![
+                 // A test without `-> ()` registers its return type as Unknown
+                 HirType::Function { params: vec![], return_type: Box::new(HirType::Unknown) },
+                 HirType::Reference(Box::new(HirType::String)),
+             ],
+             HirType::Tuple(vec![]),
+         );
+         self.context.register_function("gaia_test_summary".to_string(), vec![], HirType::Tuple(vec![]));
+         // The clock `Bencher::iter` reads around its loop, and where it leaves the time, in nanoseconds
+         self.context.register_function("gaia_bench_now".to_string(), vec![], HirType::Int64);
+         self.context.register_function("gaia_bench_record".to_string(), vec![HirType::Int64], HirType::Tuple(vec![]));
+         self.context.register_function("gaia_bench_elapsed".to_string(), vec![], HirType::Int64);
         
         // HashSet methods
          self.context.register_function("HashSet::insert".to_string(), vec![HirType::Named("HashSet".to_string()), HirType::Unknown], HirType::Tuple(vec![]));
@@ -1020,6 +1216,8 @@ impl TypeChecker {
         self.collect_definitions_recursive(items, "".to_string())?;
         // Second pass: process use statements
         self.process_use_statements(items, "".to_string())?;
+        // Every struct is known now, so `#[derive(Copy)]` fields can be checked
+        self.check_copy_derives(items)?;
         
         // DEBUG: Print all registered functions to file
         use std::io::Write;
@@ -1048,6 +1246,7 @@ impl TypeChecker {
                     generics,
                     is_public,
                     where_clause,
+                    location,
                     .. // Allow other fields we don't use here
                 } => {
                     // Register generic parameter bounds from generic parameters
@@ -1103,15 +1302,21 @@ impl TypeChecker {
                         .and_then(|mut f| writeln!(f, "[DEBUG] Registering function: {} (is_public: {})", full_name, is_public));
                     self.context
                         .register_function_with_visibility(full_name.clone(), param_types.clone(), ret_type.clone(), *is_public);
+                    let module = if module_prefix.is_empty() { "crate".to_string() } else { module_prefix.clone() };
+                    self.context.function_modules.insert(full_name.clone(), module.clone());
+                    if let Some(location) = location {
+                        self.context.function_locations.insert(full_name.clone(), *location);
+                    }
                     
                     // Also register without "crate::" prefix for easier resolution
                     // (e.g., "crate::utils::helper" also registered as "utils::helper")
                     if full_name.starts_with("crate::") {
                         let short_name = full_name.trim_start_matches("crate::").to_string();
+                        self.context.function_modules.insert(short_name.clone(), module);
                         self.context.register_function_with_visibility(short_name, param_types, ret_type, *is_public);
                     }
                 }
-                HirItem::Struct { name, fields, derives, is_public } => {
+                HirItem::Struct { name, fields, derives, is_public, .. } => {
                      self.context
                          .register_struct_with_visibility(name.clone(), fields.clone(), *is_public);
                     
@@ -1129,7 +1334,13 @@ impl TypeChecker {
                                     );
                                 }
                                 "Copy" => {
-                                    // Copy is a marker trait, no methods to register
+                                    // Copy is a marker trait: no methods, but values of
+                                    // the type are copied rather than moved
+                                    self.context.register_trait_impl(
+                                        name.clone(),
+                                        "Copy".to_string(),
+                                        HirType::Tuple(vec![]),
+                                    );
                                 }
                                 "Debug" => {
                                     // Debug::fmt(&self, f: &Formatter) -> Result
@@ -1162,7 +1373,8 @@ impl TypeChecker {
                                     // Eq is a marker trait extending PartialEq
                                 }
                                 "Ord" => {
-                                    // Ord::cmp(&self, other: &Self) -> Ordering
+                                    // Ord::cmp(&self, other: &Self) -> Ordering, expanded
+                                    // inline by lowering
                                     self.context.register_impl_method(
                                         name.clone(),
                                         "cmp".to_string(),
@@ -1180,12 +1392,12 @@ impl TypeChecker {
                                     );
                                 }
                                 "Hash" => {
-                                    // Hash::hash(&self, state: &mut H)
+                                    // Hash::hash(&self) -> u64, expanded inline by lowering
                                     self.context.register_impl_method(
                                         name.clone(),
                                         "hash".to_string(),
                                         vec![],
-                                        HirType::Tuple(vec![]),  // Unit type as empty tuple
+                                        HirType::Int64,
                                     );
                                 }
                                 _ => {
@@ -1211,8 +1423,14 @@ impl TypeChecker {
                 HirItem::Static { .. } => {
                     // Statics don't need to be registered as functions
                 }
+                HirItem::ExternBlock { functions, .. } => {
+                    // Foreign functions are called through their declared signatures
+                    self.collect_definitions_recursive(functions, module_prefix.clone())?;
+                }
                 HirItem::AssociatedType { .. } => {
                 }
+                HirItem::AssociatedConst { .. } => {
+                }
                 HirItem::Use { .. } => {
                     // Use statements are processed in second pass
                 }
@@ -1251,7 +1469,13 @@ impl TypeChecker {
                 }
                 HirItem::Enum { .. } => {
                 }
-                HirItem::Trait { .. } => {
+                HirItem::Trait { name, methods, .. } => {
+                    for method in methods {
+                        if let HirItem::Function { name: method_name, params, return_type, .. } = method {
+                            let ret = return_type.clone().unwrap_or(HirType::Tuple(vec![]));
+                            self.context.register_trait_method(name.clone(), method_name.clone(), params.clone(), ret);
+                        }
+                    }
                 }
             }
         }
@@ -1489,6 +1713,17 @@ impl TypeChecker {
                 // Mutable references can coerce to raw pointers, with type compatibility for inner types
                 self.types_compatible(inner_from, inner_to)
             }
+            // The variant constructors (`Some`, `Ok`, ...) are typed as the bare enum
+            (HirType::Named(name), HirType::Option(_)) | (HirType::Option(_), HirType::Named(name)) => name == "Option",
+            (HirType::Named(name), HirType::Result { .. }) | (HirType::Result { .. }, HirType::Named(name)) => name == "Result",
+            // `Vec::new()` is typed as the bare `Vec`; an unknown element type fits any `Vec<T>`
+            (HirType::Named(name), HirType::Vec(_)) | (HirType::Vec(_), HirType::Named(name)) => name == "Vec",
+            (HirType::Vec(elem_from), HirType::Vec(elem_to)) => {
+                **elem_from == HirType::Unknown || **elem_to == HirType::Unknown
+                    || self.types_compatible(elem_from, elem_to)
+            }
+            // Untyped allocations (`gaia_alloc`) cast to any pointee type
+            (HirType::Pointer(inner_from), HirType::Pointer(_)) => **inner_from == HirType::Unknown,
             // Reference/dereference coercion for string methods
             (HirType::Reference(inner_from), to_ty) => {
                 self.types_compatible(inner_from, to_ty)
@@ -1648,6 +1883,33 @@ impl TypeChecker {
         }
     }
     
+    /// A `#[derive(Copy)]` struct may only hold Copy fields
+    fn check_copy_derives(&self, items: &[HirItem]) -> TypeCheckResult<()> {
+        for item in items {
+            match item {
+                HirItem::Struct { name, generics, fields, derives, .. } if derives.iter().any(|d| d == "Copy") => {
+                    // The derived impl is `impl<T: Copy> Copy`, so a field of a
+                    // type parameter is Copy whenever the struct can be
+                    let params: Vec<&str> = generics.iter().filter_map(|generic| match generic {
+                        GenericParam::Type { name, .. } => Some(name.as_str()),
+                        _ => None,
+                    }).collect();
+                    if let Some((field, ty)) = fields.iter().find(|(_, ty)| !self.type_is_copy_given(ty, &params)) {
+                        return Err(TypeCheckError {
+                            message: format!(
+                                "the trait `Copy` cannot be implemented for `{}`: field `{}` of type `{}` is not `Copy`",
+                                name, field, ty
+                            ),
+                        });
+                    }
+                }
+                HirItem::Module { items: module_items, .. } => self.check_copy_derives(module_items)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
     /// Check if a type is Copy (subset of Clone, small fixed-size types)
     fn type_is_copy(&self, ty: &HirType) -> bool {
         match ty {
@@ -1661,10 +1923,28 @@ impl TypeChecker {
             HirType::Reference(_) => true,
             // Tuples of Copy types are Copy
             HirType::Tuple(types) => types.iter().all(|t| self.type_is_copy(t)),
-            // Most user-defined types are NOT Copy unless explicitly marked
+            // So are arrays of them, and fn pointers always are
+            HirType::Array { element_type, .. } => self.type_is_copy(element_type),
+            HirType::Function { .. } => true,
+            // User-defined types are Copy only with `#[derive(Copy)]`, and a
+            // generic parameter is when one of its bounds says so
+            HirType::Named(name) => {
+                self.context.lookup_trait_impl(name, "Copy").is_some()
+                    || self.context.get_generic_bounds(name).iter().any(|bound| bound == "Copy")
+            }
             _ => false
         }
     }
+
+    /// `type_is_copy`, taking the type parameters in `params` to be Copy
+    fn type_is_copy_given(&self, ty: &HirType, params: &[&str]) -> bool {
+        match ty {
+            HirType::Named(name) if params.contains(&name.as_str()) => true,
+            HirType::Tuple(types) => types.iter().all(|t| self.type_is_copy_given(t, params)),
+            HirType::Array { element_type, .. } => self.type_is_copy_given(element_type, params),
+            _ => self.type_is_copy(ty),
+        }
+    }
     
     /// Check if a type supports Default
     fn type_supports_default(&self, ty: &HirType) -> bool {
@@ -1888,11 +2168,13 @@ impl TypeChecker {
     /// This allows context-aware type inference for literals and overloaded functions
     fn infer_type_with_context(&mut self, expr: &HirExpression, expected: Option<&HirType>) -> TypeCheckResult<HirType> {
         match expr {
-            HirExpression::Integer(_) => {
+            HirExpression::Integer(value) => {
                 // Use expected type if it's an integer type, otherwise default to i32
                 match expected {
-                    Some(HirType::Int32) => Ok(HirType::Int32),
-                    Some(HirType::Int64) => Ok(HirType::Int64),
+                    Some(ty) if integer_range(ty).is_some() => {
+                        check_literal_fits(literal_value(*value, ty), ty)?;
+                        Ok(ty.clone())
+                    }
                     Some(HirType::Array { element_type, size }) => {
                         // If array element type is known, use it for array literals
                         match &**element_type {
@@ -1915,6 +2197,9 @@ impl TypeChecker {
                 } else if self.context.lookup_struct(name).is_some() {
                     // It's a struct type - unit struct or type name used as a value
                     Ok(HirType::Named(name.clone()))
+                } else if let Some((params, ret)) = self.context.lookup_function(name) {
+                    // A named function used as a value is a function pointer
+                    Ok(HirType::Function { params, return_type: Box::new(ret) })
                 } else {
                     Err(TypeCheckError {
                         message: format!("Undefined variable: {}", name),
@@ -1923,8 +2208,34 @@ impl TypeChecker {
             }
 
             HirExpression::BinaryOp { op, left, right } => {
-               let left_ty = self.infer_type(left)?;
-               let right_ty = self.infer_type(right)?;
+               // An untyped literal takes the integer type of the other operand
+               let (left_ty, right_ty) = if is_integer_literal(left) && !is_integer_literal(right) {
+                   let right_ty = self.infer_type(right)?;
+                   let context = integer_range(&right_ty).map(|_| &right_ty);
+                   (self.infer_type_with_context(left, context)?, right_ty)
+               } else if is_integer_literal(right) && !is_integer_literal(left) {
+                   let left_ty = self.infer_type(left)?;
+                   let context = integer_range(&left_ty).map(|_| &left_ty);
+                   let right_ty = self.infer_type_with_context(right, context)?;
+                   (left_ty, right_ty)
+               } else {
+                   (self.infer_type(left)?, self.infer_type(right)?)
+               };
+
+               // Like rustc's deny-by-default `unconditional_panic`: an integer
+               // divisor that is literally zero can only fault at runtime
+               if let (BinaryOp::Divide | BinaryOp::Modulo, HirExpression::Integer(0)) = (op, &**right) {
+                   if !matches!(left_ty, HirType::Float64 | HirType::Named(_)) {
+                       let attempt = if *op == BinaryOp::Divide {
+                           "attempt to divide by zero"
+                       } else {
+                           "attempt to calculate the remainder with a divisor of zero"
+                       };
+                       return Err(TypeCheckError {
+                           message: format!("this operation will panic at runtime: {}", attempt),
+                       });
+                   }
+               }
 
                // Map BinaryOp to operator trait name
                let op_trait_name = match op {
@@ -1951,6 +2262,33 @@ impl TypeChecker {
                    }
                }
 
+               // Pointer arithmetic (`p.add(n)` lowers to a byte offset)
+               if *op == BinaryOp::Add
+                   && matches!(left_ty, HirType::Pointer(_))
+                   && matches!(right_ty, HirType::Int32 | HirType::Int64 | HirType::USize | HirType::ISize | HirType::Unknown)
+               {
+                   return Ok(left_ty);
+               }
+
+               // The shift amount doesn't have to match the shifted value's type
+               if matches!(op, BinaryOp::LeftShift | BinaryOp::RightShift)
+                   && matches!(left_ty, HirType::Int32 | HirType::Int64 | HirType::USize | HirType::ISize)
+                   && matches!(right_ty, HirType::Int32 | HirType::Int64 | HirType::USize | HirType::ISize | HirType::Unknown)
+               {
+                   return Ok(left_ty);
+               }
+
+               // Char literals lower to their code point, so a char compares
+               // against an integer literal
+               let is_comparison = matches!(op,
+                   BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::Less
+                       | BinaryOp::LessEqual | BinaryOp::Greater | BinaryOp::GreaterEqual);
+               let is_char = |ty: &HirType| matches!(ty, HirType::Char) || matches!(ty, HirType::Named(name) if name == "char");
+               let is_int = |ty: &HirType| matches!(ty, HirType::Int32 | HirType::Int64);
+               if is_comparison && ((is_char(&left_ty) && is_int(&right_ty)) || (is_int(&left_ty) && is_char(&right_ty))) {
+                   return Ok(HirType::Bool);
+               }
+
                // Type compatibility check with support for Unknown (type inference)
                let result_ty = if left_ty == HirType::Unknown && right_ty != HirType::Unknown {
                    right_ty.clone()
@@ -2028,6 +2366,16 @@ impl TypeChecker {
                         
                         Ok(HirType::Reference(Box::new(operand_ty)))
                     }
+                    UnaryOp::Negate if matches!(**operand, HirExpression::Integer(_)) => {
+                        let HirExpression::Integer(value) = **operand else { unreachable!() };
+                        match expected {
+                            Some(ty) if integer_range(ty).is_some() => {
+                                check_literal_fits(negated_literal_value(value), ty)?;
+                                Ok(ty.clone())
+                            }
+                            _ => self.infer_type_with_context(operand, expected),
+                        }
+                    }
                     _ => {
                         let operand_ty = self.infer_type(operand)?;
                         match op {
@@ -2035,7 +2383,7 @@ impl TypeChecker {
                             UnaryOp::Not => Ok(HirType::Bool),
                             UnaryOp::Dereference => {
                                  match &operand_ty {
-                                     HirType::Reference(inner) => Ok((**inner).clone()),
+                                     HirType::Reference(inner) | HirType::MutableReference(inner) => Ok((**inner).clone()),
                                      HirType::Pointer(inner) => Ok((**inner).clone()),
                                      HirType::Box(inner) => Ok((**inner).clone()),
                                      HirType::Named(name) if name.starts_with("Rc<") && name.ends_with(">") => {
@@ -2080,13 +2428,22 @@ impl TypeChecker {
                     });
                 }
 
-                // Type check bodies
+                // Type check bodies; the value is the trailing expression of
+                // whichever branch has a known type
                 self.check_statements(then_body)?;
-                if let Some(else_stmts) = else_body {
+                let then_ty = self.branch_value_type(then_body)?;
+                let else_ty = if let Some(else_stmts) = else_body {
                     self.check_statements(else_stmts)?;
-                }
+                    self.branch_value_type(else_stmts)?
+                } else {
+                    HirType::Unknown
+                };
 
-                Ok(HirType::Unknown) // Could infer from body returns
+                if then_ty != HirType::Unknown {
+                    Ok(then_ty)
+                } else {
+                    Ok(else_ty)
+                }
             }
 
             HirExpression::While { condition, body } => {
@@ -2104,6 +2461,11 @@ impl TypeChecker {
                 Ok(HirType::Unknown) // Loops don't have a value type
             }
 
+            HirExpression::Loop { body } => {
+                self.check_statements(body)?;
+                Ok(HirType::Unknown)
+            }
+
             HirExpression::Match { scrutinee, arms } => {
                 let _scrutinee_ty = self.infer_type(scrutinee)?;
 
@@ -2128,6 +2490,17 @@ impl TypeChecker {
 
             HirExpression::Call { func, args } => {
                 match &**func {
+                    HirExpression::Variable(name) if name == "__builtin_vec_from" && args.len() == 1 => {
+                        // vec![a, b, c] -> Vec<T>, where T is the array literal's element type
+                        match self.infer_type(&args[0])? {
+                            HirType::Array { element_type, .. } => Ok(HirType::Vec(element_type)),
+                            _ => Ok(HirType::Vec(Box::new(HirType::Unknown))),
+                        }
+                    }
+                    HirExpression::Variable(name) if name == "gaia_vec_clone" && args.len() == 1 => {
+                        // A derived clone of a Vec field keeps the field's element type
+                        self.infer_type(&args[0])
+                    }
                     HirExpression::Variable(name) => {
                         // For method calls, try qualified name first (ReceiverType::method)
                         // This prevents generic functions from shadowing method calls
@@ -2151,14 +2524,7 @@ impl TypeChecker {
                                  // Found as a method! Check arguments
                                  let is_variadic = qualified_name.starts_with("__builtin_print");
                                  if !is_variadic && args.len() != param_types.len() {
-                                     return Err(TypeCheckError {
-                                         message: format!(
-                                             "Method {} expects {} arguments, got {}",
-                                             name,
-                                             param_types.len(),
-                                             args.len()
-                                         ),
-                                     });
+                                     return Err(self.argument_count_error(&qualified_name, param_types.len(), args.len()));
                                  }
                                  
                                  // Check argument types
@@ -2280,18 +2646,11 @@ impl TypeChecker {
                                 || name == "println" || name == "print" || name == "eprintln" 
                                 || name == "__builtin_printf" || name == "printf"
                                 // PHASE 5.2: Builtin macros are variadic
-                                || name == "format" || name == "vec" || name == "assert" || name == "assert_eq" 
+                                || name == "format" || name == "gaia_format" || name == "vec" || name == "assert" || name == "assert_eq" 
                                 || name == "assert_ne" || name == "panic" || name == "dbg" || name == "todo" 
                                 || name == "unimplemented";
                             if !is_variadic && args.len() != param_types.len() {
-                                return Err(TypeCheckError {
-                                    message: format!(
-                                        "Function {} expects {} arguments, got {}",
-                                        name,
-                                        param_types.len(),
-                                        args.len()
-                                    ),
-                                });
+                                return Err(self.argument_count_error(name, param_types.len(), args.len()));
                             }
 
                             // Check argument types and collect generic substitutions
@@ -2302,7 +2661,7 @@ impl TypeChecker {
                                 || name == "__builtin_println" || name == "__builtin_print" || name == "__builtin_eprintln"
                                 || name == "__builtin_printf" || name == "printf"
                                 // PHASE 5.2: Builtin macros are polymorphic
-                                || name == "format" || name == "vec" || name == "assert" || name == "assert_eq" 
+                                || name == "format" || name == "gaia_format" || name == "vec" || name == "assert" || name == "assert_eq" 
                                 || name == "assert_ne" || name == "panic" || name == "dbg" || name == "todo" 
                                 || name == "unimplemented";
                             
@@ -2417,9 +2776,8 @@ impl TypeChecker {
                                          let final_ret_type = self.apply_substitutions(&ret_type, &substitutions);
                                          Ok(final_ret_type)
                         } else if let Some(var_ty) = self.context.env.lookup(name) {
-                            // Check if it's a closure type
-                            if let HirType::Closure { params, return_type, .. } = var_ty {
-                                // Allow calling a closure
+                            // Closures and function pointers can be called
+                            if let HirType::Closure { params, return_type, .. } | HirType::Function { params, return_type } = var_ty {
                                 if args.len() != params.len() {
                                     return Err(TypeCheckError {
                                         message: format!(
@@ -2461,7 +2819,7 @@ impl TypeChecker {
                             })
                         }
                     }
-                    HirExpression::FieldAccess { object, field } => {
+                    HirExpression::FieldAccess { object, field, .. } => {
                         let obj_ty = self.infer_type(object)?;
                         
                         if IteratorMethodHandler::is_iterator_method(field) {
@@ -2549,7 +2907,7 @@ impl TypeChecker {
                 }
             }
 
-            HirExpression::FieldAccess { object, field } => {
+            HirExpression::FieldAccess { object, field, location } => {
                 let mut obj_ty = self.infer_type(object)?;
 
                 // Dereference references, mutable references, boxes, and Rc for field access
@@ -2578,16 +2936,11 @@ impl TypeChecker {
                                 message: format!("Unknown struct: {}", struct_name),
                             })?;
 
-                        struct_def
+                        let field_ty = struct_def
                             .iter()
                             .find(|(fname, _)| fname == field)
-                            .map(|(_, ty)| ty.clone())
-                            .ok_or_else(|| TypeCheckError {
-                                message: format!(
-                                    "Struct {} has no field {}",
-                                    struct_name, field
-                                ),
-                            })
+                            .map(|(_, ty)| ty.clone());
+                        field_ty.ok_or_else(|| self.missing_field_error(struct_name, field, *location))
                     }
                     HirType::Tuple(types) => {
                         if let Ok(index) = field.parse::<usize>() {
@@ -2639,11 +2992,11 @@ impl TypeChecker {
                     HirType::Int32 | HirType::Int64 => {
                         // Single element indexing - return element type
                         match &array_ty {
-                            HirType::Array { element_type, .. } => {
+                            HirType::Array { element_type, .. } | HirType::Vec(element_type) => {
                                 Ok(element_type.as_ref().clone())
                             }
                             HirType::Reference(inner) => {
-                                if let HirType::Array { element_type, .. } = inner.as_ref() {
+                                if let HirType::Array { element_type, .. } | HirType::Vec(element_type) = inner.as_ref() {
                                     Ok(element_type.as_ref().clone())
                                 } else {
                                     Ok(HirType::Unknown)
@@ -2847,10 +3200,12 @@ impl TypeChecker {
                     param_types.push(param_type.clone());
                 }
                 self.check_statements(body)?;
-                let inferred_return = if let Some(HirStatement::Expression(expr)) = body.last() {
-                    self.infer_type(expr)?
-                } else {
-                    HirType::Unknown
+                // Lowering turns the body's tail expression into a `return`
+                let inferred_return = match body.last() {
+                    Some(HirStatement::Expression(expr)) | Some(HirStatement::Return(Some(expr))) => {
+                        self.infer_type(expr)?
+                    }
+                    _ => HirType::Unknown,
                 };
                 self.context.env.pop_scope();
                 let final_ret = if **return_type == HirType::Unknown {
@@ -2895,6 +3250,19 @@ impl TypeChecker {
 
             HirExpression::MethodCall { receiver, method, args } => {
                 let receiver_ty = self.infer_type(receiver)?;
+
+                // Lowering turns `.into()` with a `From` impl for the expected
+                // type into a `from` call, so one left over has no conversion
+                if method == "into" && args.is_empty() {
+                    let message = match expected {
+                        Some(target) => format!(
+                            "the trait bound `{}: From<{}>` is not satisfied",
+                            target, receiver_ty
+                        ),
+                        None => "type annotations needed: cannot infer the target type of `.into()`".to_string(),
+                    };
+                    return Err(TypeCheckError { message });
+                }
                 
                 // Check if this is a primitive type with a builtin trait method
                 let is_primitive = matches!(&receiver_ty,
@@ -2986,6 +3354,55 @@ impl TypeChecker {
                     }
                 }
                 
+                // iter() over arrays and Vecs yields element references; iter_mut()
+                // only over Vecs, as arrays are iterated through a copy
+                let is_vec_type = matches!(&receiver_ty, HirType::Vec(_)) || receiver_ty == HirType::Named("Vec".to_string());
+                let is_sequence_type = is_vec_type || matches!(&receiver_ty, HirType::Array { .. });
+                if (is_sequence_type && method == "iter") || (is_vec_type && method == "iter_mut") {
+                    if !args.is_empty() {
+                        return Err(TypeCheckError {
+                            message: format!("Method iter expects 0 arguments, got {}", args.len()),
+                        });
+                    }
+                    return Ok(HirType::Named("Iterator".to_string()));
+                }
+
+                // Ranges are double-ended: rev() walks the same range backwards
+                if receiver_ty == HirType::Range && method == "rev" {
+                    if !args.is_empty() {
+                        return Err(self.argument_count_error("rev", 0, args.len()));
+                    }
+                    return Ok(receiver_ty);
+                }
+
+                // `&dyn Trait` and `Box<dyn Trait>` call through the trait's vtable
+                if let Some(trait_name) = trait_object_name(&receiver_ty) {
+                    return self.check_trait_object_call(trait_name, method, args);
+                }
+
+                // `vec![...]` values know their element type, but Vec's methods
+                // are registered on the plain `Vec` name
+                let receiver_ty = match receiver_ty {
+                    HirType::Vec(elem) if method == "get" => {
+                        if args.len() != 1 {
+                            return Err(self.argument_count_error("Vec::get", 1, args.len()));
+                        }
+                        self.infer_type(&args[0])?;
+                        return Ok(HirType::Option(elem));
+                    }
+                    // A clone keeps the element type
+                    HirType::Vec(_) if method == "clone" => {
+                        if !args.is_empty() {
+                            return Err(self.argument_count_error("Vec::clone", 0, args.len()));
+                        }
+                        return Ok(receiver_ty);
+                    }
+                    HirType::Vec(_) => HirType::Named("Vec".to_string()),
+                    // Method calls auto-deref: `self.name()` inside a `&self` method
+                    HirType::Reference(inner) | HirType::MutableReference(inner) if matches!(*inner, HirType::Named(_)) => *inner,
+                    other => other,
+                };
+                
                 // Check if it's a String or &String or &str type
                 let is_string_type = receiver_ty == HirType::String ||
                     (if let HirType::Reference(inner) = &receiver_ty {
@@ -3000,14 +3417,7 @@ impl TypeChecker {
                     if let Some((param_types, ret_type)) = self.context.lookup_function(&qualified_name) {
                         // Check argument count
                         if args.len() != param_types.len() - 1 {
-                            return Err(TypeCheckError {
-                                message: format!(
-                                    "Method {} expects {} arguments, got {}",
-                                    method,
-                                    param_types.len() - 1,
-                                    args.len()
-                                ),
-                            });
+                            return Err(self.argument_count_error(&qualified_name, param_types.len() - 1, args.len()));
                         }
                         
                         // Type check arguments
@@ -3034,14 +3444,8 @@ impl TypeChecker {
                     if let Some((param_types, ret_type)) = self.context.lookup_impl_method(&struct_name, method) {
                         // For instance methods in impl blocks, no implicit self in param_types
                         if args.len() != param_types.len() {
-                            return Err(TypeCheckError {
-                                message: format!(
-                                    "Method {} expects {} arguments, got {}",
-                                    method,
-                                    param_types.len(),
-                                    args.len()
-                                ),
-                            });
+                            let callee = format!("{}::{}", struct_name, method);
+                            return Err(self.argument_count_error(&callee, param_types.len(), args.len()));
                         }
                         
                         for (i, (arg, param_ty)) in args.iter().zip(param_types.iter()).enumerate() {
@@ -3076,14 +3480,8 @@ impl TypeChecker {
                         // For instance methods, first param is implicit self, rest are explicit args
                         let expected_args = param_types.len() - 1;
                         if args.len() != expected_args {
-                            return Err(TypeCheckError {
-                                message: format!(
-                                    "Method {} expects {} arguments, got {}",
-                                    method,
-                                    expected_args,
-                                    args.len()
-                                ),
-                            });
+                            let callee = format!("{}::{}", struct_name, method);
+                            return Err(self.argument_count_error(&callee, expected_args, args.len()));
                         }
                         
                         for (i, (arg, param_ty)) in args.iter().zip(param_types.iter().skip(1)).enumerate() {
@@ -3116,6 +3514,38 @@ impl TypeChecker {
         }
     }
 
+    /// Check a method call on a trait object. Only methods with a slot in
+    /// the trait's vtable can be called; see `typesystem::is_vtable_method`.
+    fn check_trait_object_call(&mut self, trait_name: &str, method: &str, args: &[HirExpression]) -> TypeCheckResult<HirType> {
+        let Some((params, ret_type)) = self.context.lookup_trait_method(trait_name, method) else {
+            return Err(TypeCheckError {
+                message: format!("no method named `{}` found for trait object `dyn {}`", method, trait_name),
+            });
+        };
+        if !crate::typesystem::is_vtable_method(&params) {
+            return Err(TypeCheckError {
+                message: format!(
+                    "the `{}` method cannot be invoked on a trait object `dyn {}`: it requires `Self: Sized`",
+                    method, trait_name
+                ),
+            });
+        }
+
+        let callee = format!("{}::{}", trait_name, method);
+        if args.len() != params.len() - 1 {
+            return Err(self.argument_count_error(&callee, params.len() - 1, args.len()));
+        }
+        for (i, (arg, (_, param_ty))) in args.iter().zip(params.iter().skip(1)).enumerate() {
+            let arg_ty = self.infer_type(arg)?;
+            if !self.types_compatible(&arg_ty, param_ty) && *param_ty != HirType::Unknown {
+                return Err(TypeCheckError {
+                    message: format!("Argument {} has type {}, expected {}", i, arg_ty, param_ty),
+                });
+            }
+        }
+        Ok(ret_type)
+    }
+
     /// Infer the type of an expression (without expected type context)
     fn infer_type(&mut self, expr: &HirExpression) -> TypeCheckResult<HirType> {
         self.infer_type_with_context(expr, None)
@@ -3212,7 +3642,9 @@ impl TypeChecker {
                     iter_ty,
                     HirType::Vec(_) | HirType::Array { .. } | HirType::Range { .. } | 
                     HirType::String
-                ) || iter_ty.to_string().contains("IntoIterator");
+                ) || iter_ty == HirType::Named("Iterator".to_string())
+                  || iter_ty == HirType::Named("HashMap".to_string())
+                  || iter_ty.to_string().contains("IntoIterator");
                 
                 if !is_iterable {
                     eprintln!(
@@ -3221,8 +3653,13 @@ impl TypeChecker {
                     );
                 }
                 
-                // Infer the loop variable type from the iterator
-                let var_type = match &**iter {
+                // Infer the loop variable type from the iterator; rev()
+                // yields the same items in the opposite order
+                let items = match &**iter {
+                    HirExpression::MethodCall { receiver, method, .. } if method == "rev" => &**receiver,
+                    iter => iter,
+                };
+                let var_type = match items {
                     HirExpression::Range { start, end, .. } => {
                         // Get the type from the start of the range
                         if let Some(start_expr) = start {
@@ -3235,6 +3672,26 @@ impl TypeChecker {
                             HirType::Int32
                         }
                     }
+                    // A HashMap yields (key, value) pairs; its key and value
+                    // types aren't tracked
+                    _ if iter_ty == HirType::Named("HashMap".to_string()) => {
+                        HirType::Tuple(vec![HirType::Unknown, HirType::Unknown])
+                    }
+                    HirExpression::MethodCall { receiver, method, .. } if method == "iter" || method == "iter_mut" => {
+                        // iter() yields references to the collection's elements,
+                        // iter_mut() mutable ones
+                        let element_ty = match self.infer_type(receiver)? {
+                            HirType::Vec(element) => *element,
+                            HirType::Array { element_type, .. } => *element_type,
+                            _ => HirType::Int32,
+                        };
+                        let element_ty = if element_ty == HirType::Unknown { HirType::Int32 } else { element_ty };
+                        if method == "iter_mut" {
+                            HirType::MutableReference(Box::new(element_ty))
+                        } else {
+                            HirType::Reference(Box::new(element_ty))
+                        }
+                    }
                     _ => {
                         // For non-range iterators, default to i32
                         HirType::Int32
@@ -3336,6 +3793,14 @@ impl TypeChecker {
         }
     }
 
+    /// Type of the trailing expression statement of an `if` branch, if any
+    fn branch_value_type(&mut self, stmts: &[HirStatement]) -> TypeCheckResult<HirType> {
+        match stmts.last() {
+            Some(HirStatement::Expression(expr)) => self.infer_type(expr),
+            _ => Ok(HirType::Unknown),
+        }
+    }
+
     /// Type check a list of statements
     fn check_statements(&mut self, stmts: &[HirStatement]) -> TypeCheckResult<()> {
         for stmt in stmts {
@@ -3424,8 +3889,11 @@ impl TypeChecker {
                 }
                 HirItem::Struct { .. } => {
                 }
-                HirItem::Module { items: module_items, .. } => {
-                    self.check_items_recursive(module_items)?;
+                HirItem::Module { name, items: module_items, .. } => {
+                    self.context.current_module.push(name.clone());
+                    let result = self.check_items_recursive(module_items);
+                    self.context.current_module.pop();
+                    result?;
                 }
                 HirItem::Const { .. } => {
                     // Constants are compile-time values
@@ -3433,8 +3901,13 @@ impl TypeChecker {
                 HirItem::Static { .. } => {
                     // Statics are runtime values
                 }
+                HirItem::ExternBlock { .. } => {
+                    // Declarations only; the bodies live in the C library
+                }
                 HirItem::AssociatedType { .. } => {
                 }
+                HirItem::AssociatedConst { .. } => {
+                }
                 HirItem::Use { .. } => {
                 }
                 HirItem::Impl { methods, .. } => {
@@ -3672,7 +4145,8 @@ pub fn check_types(items: &[HirItem]) -> Result<(), CompileError> {
          .and_then(|mut f| writeln!(f, "check_types called with {} items", items.len()));
      
      let mut checker = TypeChecker::new();
-     checker.check_items(items).map_err(|e| {
+     let result = checker.check_items(items);
+     result.map_err(|e| {
         let message = e.message.clone();
         let kind = if message.contains("not yet supported") || 
                       message.contains("not supported") ||
@@ -3685,7 +4159,17 @@ pub fn check_types(items: &[HirItem]) -> Result<(), CompileError> {
         } else {
             ErrorKind::CodeIssue
         };
-        CompileError::new("Type Checking", &message, kind)
+        let mut error = CompileError::new("Type Checking", &message, kind);
+        if let Some(callee) = &checker.miscalled_function {
+            if let Some(location) = checker.context.function_locations.get(callee) {
+                error.help = Some(format!("`{}` is defined at line {}, column {}", callee, location.line, location.column));
+            }
+        }
+        if let Some(location) = checker.misaccessed_field {
+            error.line = Some(location.line);
+            error.column = Some(location.column);
+        }
+        error
     })
 }
 
@@ -4085,4 +4569,34 @@ mod tests {
             assert!(checker.context.lookup_function("sub").is_some());
         }
     }
+
+    fn check_source(source: &str) -> Result<(), CompileError> {
+        let tokens = crate::lexer::lex(source).expect("lex failed");
+        let ast = crate::parser::parse(tokens).expect("parse failed");
+        let hir = crate::lowering::lower(&ast).expect("lowering failed");
+        check_types(&hir)
+    }
+
+    #[test]
+    fn test_let_annotation_mismatch_is_error() {
+        let result = check_source("fn main() { let x: bool = 5; }");
+        let err = result.expect_err("`let x: bool = 5;` should not type check");
+        assert!(err.message.contains("mismatched types"), "unexpected error: {}", err.message);
+        assert!(err.message.contains("Expected: bool"), "unexpected error: {}", err.message);
+    }
+
+    #[test]
+    fn test_let_annotation_pins_integer_literal() {
+        assert!(check_source("fn main() { let x: i64 = 5; }").is_ok());
+
+        let mut checker = TypeChecker::new();
+        let stmt = HirStatement::Let {
+            name: "x".to_string(),
+            mutable: false,
+            ty: HirType::Int64,
+            init: HirExpression::Integer(5),
+        };
+        checker.check_statement(&stmt).expect("annotated let should type check");
+        assert_eq!(checker.context.env.lookup("x"), Some(HirType::Int64));
+    }
 }
@@ -486,7 +486,7 @@ pub fn convert_expression(expr: &parser_ast::Expression) -> BridgeResult<AstExpr
                 Ok(AstExpr::Tuple(exprs))
             }
         }
-        parser_ast::Expression::FieldAccess { object, field } => {
+        parser_ast::Expression::FieldAccess { object, field, .. } => {
             let obj_expr = convert_expression(object)?;
             Ok(AstExpr::FieldAccess {
                 object: Box::new(obj_expr),
@@ -742,11 +742,13 @@ mod tests {
                     name: "a".to_string(),
                     mutable: false,
                     ty: parser_ast::Type::Named("i32".to_string()),
+                    attributes: Vec::new(),
                 },
                 parser_ast::Parameter {
                     name: "b".to_string(),
                     mutable: false,
                     ty: parser_ast::Type::Named("i32".to_string()),
+                    attributes: Vec::new(),
                 },
             ],
             return_type: Some(parser_ast::Type::Named("i32".to_string())),
@@ -760,6 +762,7 @@ mod tests {
             attributes: vec![],
             where_clause: vec![],
             abi: None,
+            location: None,
         };
 
         registry.register_item(&func)
@@ -781,6 +784,7 @@ mod tests {
                 name: "x".to_string(),
                 mutable: false,
                 ty: parser_ast::Type::TypeVar("T".to_string()),
+                attributes: Vec::new(),
             }],
             return_type: Some(parser_ast::Type::TypeVar("T".to_string())),
             body: parser_ast::Block {
@@ -793,6 +797,7 @@ mod tests {
             attributes: vec![],
             where_clause: vec![],
             abi: None,
+            location: None,
         };
 
         let mut ctx = ConversionContext::new();
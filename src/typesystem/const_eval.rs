@@ -403,6 +403,14 @@ impl ConstEvaluator {
             }
         }
 
+        // Casts bind tighter than binary operators but looser than unary ones
+        if let Some(pos) = expr.rfind(" as ") {
+            if !self.is_in_parens(expr, pos) {
+                let value = self.parse_expression(&expr[..pos], depth + 1)?;
+                return Self::wrapping_cast(value, expr[pos + 4..].trim());
+            }
+        }
+
         // Unary operators
         if expr.starts_with('-') && expr.len() > 1 {
             let rest = &expr[1..];
@@ -420,6 +428,20 @@ impl ConstEvaluator {
         Err(format!("Invalid const expression: '{}'", expr))
     }
 
+    /// Cast an integer to another integer type with Rust's wrapping semantics
+    fn wrapping_cast(value: i64, target: &str) -> Result<i64, String> {
+        Ok(match target {
+            "i8" => value as i8 as i64,
+            "i16" => value as i16 as i64,
+            "i32" => value as i32 as i64,
+            "i64" | "isize" | "u64" | "usize" => value,
+            "u8" => value as u8 as i64,
+            "u16" => value as u16 as i64,
+            "u32" => value as u32 as i64,
+            _ => return Err(format!("Unsupported cast target in const expression: '{}'", target)),
+        })
+    }
+
     /// Find the rightmost position of an operator not within parentheses
     fn find_operator(&self, expr: &str, ops: &[&str]) -> Option<usize> {
         for op in ops {
@@ -704,6 +726,15 @@ mod tests {
         assert_eq!(result.unwrap(), 5);
     }
 
+    #[test]
+    fn test_wrapping_casts() {
+        let mut eval = create_test_evaluator();
+        assert_eq!(eval.evaluate_expression("3 as usize").unwrap(), 3);
+        assert_eq!(eval.evaluate_expression("300 as u8").unwrap(), 44);
+        assert_eq!(eval.evaluate_expression("200 as i8").unwrap(), -56);
+        assert_eq!(eval.evaluate_expression("(1 + 2) as u32 * 2").unwrap(), 6);
+    }
+
     #[test]
     fn test_register_const_value() {
         let mut eval = create_test_evaluator();
@@ -140,7 +140,7 @@ pub use smart_pointers::{
     BoxType, RcType, ArcType, SmartPointerOp, SmartPointerError,
 };
 pub use trait_objects::{
-    DynTraitObject, FatPointer, ObjectSafetyValidator,
+    DynTraitObject, FatPointer, ObjectSafetyValidator, is_vtable_method,
 };
 
 /// Type checking result
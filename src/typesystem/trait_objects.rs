@@ -1,6 +1,7 @@
 //! Type System Support for Trait Objects (dyn Trait)
 //! Handles object safety checking and trait object type inference
 
+use crate::lowering::HirType;
 use std::collections::HashSet;
 
 /// Trait object type: dyn Trait
@@ -39,6 +40,20 @@ impl DynTraitObject {
     }
 }
 
+/// Whether a trait method can be called through a vtable, given its
+/// parameters. Only a `self` behind a pointer can be: by-value `self` needs
+/// the concrete type's size (the implicit `where Self: Sized`), and a method
+/// without `self` has no object to dispatch on.
+pub fn is_vtable_method(params: &[(String, HirType)]) -> bool {
+    match params.first() {
+        Some((name, ty)) if name == "self" => matches!(
+            ty,
+            HirType::Reference(_) | HirType::MutableReference(_) | HirType::Box(_) | HirType::Pointer(_)
+        ),
+        _ => false,
+    }
+}
+
 /// Fat pointer for trait objects: [data_ptr: *const T, vtable_ptr: *const VTable]
 #[derive(Debug, Clone)]
 pub struct FatPointer {
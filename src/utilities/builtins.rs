@@ -12,6 +12,8 @@
 
 use std::fmt;
 
+use crate::lowering::{HirItem, HirType};
+
 /// Built-in function identifiers
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BuiltinFunction {
@@ -565,6 +567,50 @@ impl BuiltinFunction {
     }
 }
 
+/// A builtin supplied by the host application embedding the compiler.
+/// Compiled code calls it by `name` with the declared signature, and
+/// each call is emitted as a `call` to the external `symbol` the host
+/// links in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomBuiltin {
+    pub name: String,
+    pub params: Vec<HirType>,
+    pub return_type: HirType,
+    pub symbol: String,
+}
+
+impl CustomBuiltin {
+    /// A builtin called through the external symbol of the same name
+    pub fn new(name: &str, params: Vec<HirType>, return_type: HirType) -> Self {
+        CustomBuiltin {
+            name: name.to_string(),
+            params,
+            return_type,
+            symbol: name.to_string(),
+        }
+    }
+
+    /// Emit calls to `symbol` instead of the builtin's own name
+    pub fn calling(mut self, symbol: &str) -> Self {
+        self.symbol = symbol.to_string();
+        self
+    }
+
+    /// The bodiless declaration the type checker sees for this builtin
+    pub fn declaration(&self) -> HirItem {
+        HirItem::Function {
+            name: self.name.clone(),
+            generics: Vec::new(),
+            params: self.params.iter().enumerate().map(|(i, ty)| (format!("arg{}", i), ty.clone())).collect(),
+            return_type: Some(self.return_type.clone()),
+            body: Vec::new(),
+            is_public: true,
+            where_clause: Vec::new(),
+            location: None,
+        }
+    }
+}
+
 /// Generate standard library function stubs (assembly for math functions)
 pub fn generate_stdlib_stubs() -> String {
     let mut code = String::new();
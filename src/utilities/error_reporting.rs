@@ -6,9 +6,27 @@
 //! categorization and multi-phase error accumulation.
 
 use std::fmt;
+use std::io::IsTerminal;
 use std::path::PathBuf;
 use std::collections::HashMap;
 
+/// Whether diagnostics written to stderr should use ANSI colors and
+/// Unicode markers: only when stderr is a terminal and `NO_COLOR` is unset
+/// or empty
+pub fn stderr_supports_color() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    !no_color && std::io::stderr().is_terminal()
+}
+
+/// Wrap `text` in an ANSI escape `code` when `color` is on
+fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
 /// Location of an error in source code
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SourceLocation {
@@ -190,6 +208,16 @@ impl Diagnostic {
 
     /// Format diagnostic with source context and colored output (v0.0.3+)
     pub fn format_detailed(&self, source: Option<&str>) -> String {
+        self.render(source, true)
+    }
+
+    /// Format diagnostic with source context as plain ASCII, without escape
+    /// codes, for dumb terminals and log files
+    pub fn format_plain(&self, source: Option<&str>) -> String {
+        self.render(source, false)
+    }
+
+    fn render(&self, source: Option<&str>, color: bool) -> String {
         let mut output = String::new();
 
         // Header line with color
@@ -201,11 +229,12 @@ impl Diagnostic {
             .unwrap_or_else(|| "<stdin>".to_string());
 
         // Color the severity label (v0.0.3 enhancement)
-        let severity_label = match self.severity {
-            Severity::Error => format!("\x1b[31m{}\x1b[0m", self.severity),
-            Severity::Warning => format!("\x1b[33m{}\x1b[0m", self.severity),
-            Severity::Note => format!("\x1b[36m{}\x1b[0m", self.severity),
+        let severity_code = match self.severity {
+            Severity::Error => "31",
+            Severity::Warning => "33",
+            Severity::Note => "36",
         };
+        let severity_label = paint(&self.severity.to_string(), severity_code, color);
 
         // Include category in header
         let category_str = if self.category != ErrorCategory::Other {
@@ -240,8 +269,8 @@ impl Diagnostic {
                         output.push_str(&format!("  {} | {}\n", 
                             format!("{:4}", line_num), line));
                         // Highlight error location with red caret
-                        let caret_line = format!("    | {}\x1b[31m^\x1b[0m", 
-                            " ".repeat(loc.column.saturating_sub(1)));
+                        let caret_line = format!("    | {}{}",
+                            " ".repeat(loc.column.saturating_sub(1)), paint("^", "31", color));
                         output.push_str(&caret_line);
                         output.push('\n');
                     } else {
@@ -255,22 +284,22 @@ impl Diagnostic {
 
         // Context information
         if let Some(context) = &self.context {
-            output.push_str(&format!("  \x1b[35mcontext:\x1b[0m {}\n", context));
+            output.push_str(&format!("  {} {}\n", paint("context:", "35", color), context));
         }
 
         // Suggestion with yellow highlight
         if let Some(suggestion) = &self.suggestion {
-            output.push_str(&format!("  \x1b[33msuggestion:\x1b[0m {}\n", suggestion));
+            output.push_str(&format!("  {} {}\n", paint("suggestion:", "33", color), suggestion));
         }
 
         // Help text with cyan highlight
         if let Some(help) = &self.help {
-            output.push_str(&format!("  \x1b[36mhelp:\x1b[0m {}\n", help));
+            output.push_str(&format!("  {} {}\n", paint("help:", "36", color), help));
         }
 
         // Related items
         if !self.related_items.is_empty() {
-            output.push_str("  \x1b[36mrelated items:\x1b[0m\n");
+            output.push_str(&format!("  {}\n", paint("related items:", "36", color)));
             for item in &self.related_items {
                 output.push_str(&format!("    - {}\n", item));
             }
@@ -301,14 +330,18 @@ impl std::error::Error for Diagnostic {}
 pub struct ErrorReporter {
     diagnostics: Vec<Diagnostic>,
     source: Option<String>,
+    warnings_as_errors: bool,
+    color: bool,
 }
 
 impl ErrorReporter {
-    /// Create a new error reporter
+    /// Create a new error reporter, colored only if stderr supports it
     pub fn new() -> Self {
         ErrorReporter {
             diagnostics: Vec::new(),
             source: None,
+            warnings_as_errors: false,
+            color: stderr_supports_color(),
         }
     }
 
@@ -318,8 +351,23 @@ impl ErrorReporter {
         self
     }
 
+    /// Promote every warning added from now on to an error (`--werror`)
+    pub fn with_warnings_as_errors(mut self, enabled: bool) -> Self {
+        self.warnings_as_errors = enabled;
+        self
+    }
+
+    /// Choose between colored Unicode output and plain ASCII
+    pub fn with_color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
+    }
+
     /// Add a diagnostic
-    pub fn add(&mut self, diagnostic: Diagnostic) {
+    pub fn add(&mut self, mut diagnostic: Diagnostic) {
+        if self.warnings_as_errors && diagnostic.severity == Severity::Warning {
+            diagnostic.severity = Severity::Error;
+        }
         self.diagnostics.push(diagnostic);
     }
 
@@ -331,7 +379,7 @@ impl ErrorReporter {
 
     /// Add a warning
     pub fn warning(&mut self, phase: &str, message: &str) -> &mut Diagnostic {
-        self.diagnostics.push(Diagnostic::warning(phase, message));
+        self.add(Diagnostic::warning(phase, message));
         self.diagnostics.last_mut().unwrap()
     }
 
@@ -409,9 +457,9 @@ impl ErrorReporter {
         
         for phase in phases {
             if let Some(diags) = by_phase.get(phase) {
-                output.push_str(&format!("\n\x1b[1m[{}]\x1b[0m\n", phase));
+                output.push_str(&format!("\n{}\n", paint(&format!("[{}]", phase), "1", self.color)));
                 for (idx, diagnostic) in diags.iter().enumerate() {
-                    output.push_str(&diagnostic.format_detailed(self.source.as_deref()));
+                    output.push_str(&diagnostic.render(self.source.as_deref(), self.color));
                     if idx < diags.len() - 1 {
                         output.push('\n');
                     }
@@ -426,23 +474,29 @@ impl ErrorReporter {
             .filter(|d| d.severity == Severity::Note).count();
         
         output.push('\n');
-        output.push_str("═══════════════════════════════════════════════\n");
+        let (rule, error_marker, warning_marker, note_marker) = if self.color {
+            ("═", "✗ ", "⚠ ", "ℹ ")
+        } else {
+            ("=", "", "", "")
+        };
+        output.push_str(&rule.repeat(47));
+        output.push('\n');
         
         let mut summary_parts = Vec::new();
         
         if error_count > 0 {
-            summary_parts.push(format!("\x1b[31m✗ {} error{}\x1b[0m", 
-                error_count, if error_count == 1 { "" } else { "s" }));
+            summary_parts.push(paint(&format!("{}{} error{}", error_marker,
+                error_count, if error_count == 1 { "" } else { "s" }), "31", self.color));
         }
         
         if warning_count > 0 {
-            summary_parts.push(format!("\x1b[33m⚠ {} warning{}\x1b[0m", 
-                warning_count, if warning_count == 1 { "" } else { "s" }));
+            summary_parts.push(paint(&format!("{}{} warning{}", warning_marker,
+                warning_count, if warning_count == 1 { "" } else { "s" }), "33", self.color));
         }
         
         if note_count > 0 {
-            summary_parts.push(format!("\x1b[36mℹ {} note{}\x1b[0m", 
-                note_count, if note_count == 1 { "" } else { "s" }));
+            summary_parts.push(paint(&format!("{}{} note{}", note_marker,
+                note_count, if note_count == 1 { "" } else { "s" }), "36", self.color));
         }
         
         output.push_str(&summary_parts.join(", "));
@@ -28,4 +28,42 @@ fn test_output_format_executable() {
 fn test_output_format_library() {
     let fmt = OutputFormat::Library;
     let _ = fmt;
-}
\ No newline at end of file
+}
+fn scratch_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("gaiarusted_config_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("failed to create scratch dir");
+    dir
+}
+
+#[test]
+fn test_add_source_file_rejects_missing_file() {
+    let dir = scratch_dir("missing");
+    let err = CompilationConfig::new().add_source_file(dir.join("nope.rs")).unwrap_err();
+    assert!(err.contains("Source file not found"), "{}", err);
+}
+
+#[test]
+fn test_add_source_file_rejects_other_extensions() {
+    let dir = scratch_dir("extension");
+    let notes = dir.join("notes.txt");
+    std::fs::write(&notes, "fn main() {}").unwrap();
+    let err = CompilationConfig::new().add_source_file(&notes).unwrap_err();
+    assert!(err.contains("must have .rs extension"), "{}", err);
+
+    let folder = dir.join("folder.rs");
+    std::fs::create_dir_all(&folder).unwrap();
+    let err = CompilationConfig::new().add_source_file(&folder).unwrap_err();
+    assert!(err.contains("not a file"), "{}", err);
+}
+
+#[test]
+fn test_add_source_file_accepts_and_canonicalizes_rs_file() {
+    let dir = scratch_dir("valid");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, "fn main() {}").unwrap();
+    let config = CompilationConfig::new()
+        .add_source_file(dir.join(".").join("main.rs"))
+        .expect("a readable .rs file is accepted");
+    assert_eq!(config.source_files, vec![std::fs::canonicalize(&main_rs).unwrap()]);
+}
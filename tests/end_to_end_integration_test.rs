@@ -175,11 +175,13 @@ fn test_type_registry() {
                 name: "x".to_string(),
                 mutable: false,
                 ty: ast::Type::Named("i32".to_string()),
+                attributes: Vec::new(),
             },
             ast::Parameter {
                 name: "y".to_string(),
                 mutable: false,
                 ty: ast::Type::Named("i32".to_string()),
+                attributes: Vec::new(),
             },
         ],
         return_type: Some(ast::Type::Named("i32".to_string())),
@@ -193,6 +195,7 @@ fn test_type_registry() {
         attributes: vec![],
         where_clause: vec![],
         abi: None,
+        location: None,
     };
     
     registry.register_item(&func).expect("Failed to register");
@@ -57,6 +57,7 @@ fn test_function_lifetime_extraction_with_reference_param() {
             mutable: false,
             inner: Box::new(Type::Named("str".to_string())),
         },
+        attributes: Vec::new(),
     }];
     let return_type = None;
 
@@ -311,6 +312,7 @@ fn test_function_with_multiple_lifetimes() {
                 mutable: false,
                 inner: Box::new(Type::Named("str".to_string())),
             },
+            attributes: Vec::new(),
         },
         Parameter {
             name: "y".to_string(),
@@ -320,6 +322,7 @@ fn test_function_with_multiple_lifetimes() {
                 mutable: false,
                 inner: Box::new(Type::Named("str".to_string())),
             },
+            attributes: Vec::new(),
         },
     ];
     let return_type = Some(Type::Reference {
@@ -394,6 +397,7 @@ fn test_function_lifetime_context_integration() {
             mutable: false,
             inner: Box::new(Type::Named("str".to_string())),
         },
+        attributes: Vec::new(),
     }];
     let return_type = Some(Type::Reference {
         lifetime: Some("a".to_string()),
@@ -476,6 +480,7 @@ fn test_function_lifetime_scenario_simple_reference() {
             mutable: false,
             inner: Box::new(Type::Named("i32".to_string())),
         },
+        attributes: Vec::new(),
     }];
     let return_type = None;
 
@@ -54,4 +54,196 @@ mod builtin_tests {
         assert!(stubs.contains("_builtin_min"));
         assert!(stubs.contains("_builtin_max"));
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod lexer_tests {
+    use gaiarusted::lexer::{self, LexError};
+
+    #[test]
+    fn test_unterminated_string_points_at_opening_quote() {
+        let err = lexer::lex("fn main() {\n    let s = \"never closed;\n}\n").unwrap_err();
+        assert!(matches!(err, LexError::UnterminatedString(_)));
+        let opened = err.location().expect("location of the opening quote");
+        assert_eq!((opened.line, opened.column, opened.byte_pos), (2, 13, 24));
+        assert_eq!(err.to_string(), "unterminated string literal opened at 2:13");
+    }
+
+    #[test]
+    fn test_unterminated_char_points_at_opening_quote() {
+        let err = lexer::lex("let c = '\\").unwrap_err();
+        assert!(matches!(err, LexError::UnterminatedChar(_)));
+        let opened = err.location().unwrap();
+        assert_eq!((opened.line, opened.column), (1, 9));
+    }
+
+    #[test]
+    fn test_unclosed_block_comment_points_at_opening() {
+        let err = lexer::lex("let x = 1;\n  /* no end\n let y = 2;").unwrap_err();
+        assert!(matches!(err, LexError::UnterminatedBlockComment(_)));
+        let opened = err.location().unwrap();
+        assert_eq!((opened.line, opened.column), (2, 3));
+        assert_eq!(err.to_string(), "unterminated block comment opened at 2:3");
+
+        assert!(lexer::lex("/* closed */ let x = 1;").is_ok());
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use gaiarusted::lexer::{self, token::Token};
+    use gaiarusted::parser::{self, BinaryOp, Expression, Item, Type};
+
+    #[test]
+    fn test_parse_expression_respects_precedence() {
+        let tokens = lexer::lex("1 + 2 * 3").unwrap();
+        let expr = parser::parse_expression(tokens).unwrap();
+
+        let expected = Expression::Binary {
+            left: Box::new(Expression::Integer(1)),
+            op: BinaryOp::Add,
+            right: Box::new(Expression::Binary {
+                left: Box::new(Expression::Integer(2)),
+                op: BinaryOp::Multiply,
+                right: Box::new(Expression::Integer(3)),
+            }),
+        };
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn test_parse_expression_rejects_trailing_tokens() {
+        let tokens = lexer::lex("1 + 2 3").unwrap();
+        assert!(parser::parse_expression(tokens).is_err());
+    }
+
+    fn parse(source: &str) -> Expression {
+        parser::parse_expression(lexer::lex(source).unwrap()).unwrap()
+    }
+
+    fn binary(left: Expression, op: BinaryOp, right: Expression) -> Expression {
+        Expression::Binary { left: Box::new(left), op, right: Box::new(right) }
+    }
+
+    #[test]
+    fn test_bitwise_binds_tighter_than_comparison() {
+        // (x & 1) == 1, not x & (1 == 1)
+        let expected = binary(
+            binary(Expression::Variable("x".to_string()), BinaryOp::BitwiseAnd, Expression::Integer(1)),
+            BinaryOp::Equal,
+            Expression::Integer(1),
+        );
+        assert_eq!(parse("x & 1 == 1"), expected);
+    }
+
+    #[test]
+    fn test_shift_binds_looser_than_addition() {
+        let expected = binary(
+            Expression::Integer(1),
+            BinaryOp::LeftShift,
+            binary(Expression::Integer(2), BinaryOp::Add, Expression::Integer(3)),
+        );
+        assert_eq!(parse("1 << 2 + 3"), expected);
+    }
+
+    #[test]
+    fn test_subtraction_is_left_associative() {
+        let expected = binary(
+            binary(Expression::Integer(8), BinaryOp::Subtract, Expression::Integer(3)),
+            BinaryOp::Subtract,
+            Expression::Integer(1),
+        );
+        assert_eq!(parse("8 - 3 - 1"), expected);
+    }
+
+    #[test]
+    fn test_range_binds_looser_than_arithmetic() {
+        let expected = Expression::Range {
+            start: Some(Box::new(Expression::Integer(0))),
+            end: Some(Box::new(binary(Expression::Variable("n".to_string()), BinaryOp::Add, Expression::Integer(1)))),
+            inclusive: false,
+        };
+        assert_eq!(parse("0..n + 1"), expected);
+    }
+
+    #[test]
+    fn test_inclusive_range_is_distinct_from_exclusive() {
+        let tokens = lexer::lex("0..=3 0..3").unwrap();
+        assert_eq!(tokens[1], Token::DotDotEqual);
+        assert_eq!(tokens[4], Token::DotDot);
+
+        let Expression::Range { inclusive, .. } = parse("0..=3") else { panic!("expected a range") };
+        assert!(inclusive);
+        let Expression::Range { inclusive, .. } = parse("0..3") else { panic!("expected a range") };
+        assert!(!inclusive);
+    }
+
+    #[test]
+    fn test_chained_comparison_is_rejected() {
+        let tokens = lexer::lex("a < b < c").unwrap();
+        assert!(parser::parse_expression(tokens).is_err());
+    }
+
+    #[test]
+    fn test_tuple_struct_fields_are_positional() {
+        let tokens = lexer::lex("struct Point(i64, pub i64);").unwrap();
+        let program = parser::parse(tokens).unwrap();
+        let Item::Struct { fields, .. } = &program[0] else { panic!("expected a struct") };
+        let names: Vec<&str> = fields.iter().map(|field| field.name.as_str()).collect();
+        assert_eq!(names, ["0", "1"]);
+        assert_eq!(fields[1].ty, Type::Named("i64".to_string()));
+    }
+
+    #[test]
+    fn test_fn_pointer_parameter_type() {
+        let tokens = lexer::lex("fn apply(f: fn(i64) -> i64, g: unsafe extern \"C\" fn()) {}").unwrap();
+        let program = parser::parse(tokens).unwrap();
+        let Item::Function { params, .. } = &program[0] else { panic!("expected a function") };
+        assert_eq!(
+            params[0].ty,
+            Type::Function {
+                params: vec![Type::Named("i64".to_string())],
+                return_type: Box::new(Type::Named("i64".to_string())),
+                is_unsafe: false,
+                abi: None,
+            }
+        );
+        assert_eq!(
+            params[1].ty,
+            Type::Function {
+                params: vec![],
+                return_type: Box::new(Type::Tuple(vec![])),
+                is_unsafe: true,
+                abi: Some("C".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_attributes_on_struct_fields_and_parameters_are_kept() {
+        let source = r#"
+struct Config {
+    #[serde(rename = "max")]
+    limit: i64,
+    plain: i64,
+}
+
+fn scale(#[allow(unused)] factor: i64, value: i64) -> i64 {
+    value
+}
+"#;
+        let program = parser::parse(lexer::lex(source).unwrap()).unwrap();
+        let Item::Struct { fields, .. } = &program[0] else { panic!("expected a struct") };
+        assert_eq!(fields[0].name, "limit");
+        assert_eq!(fields[0].attributes.len(), 1);
+        assert_eq!(fields[0].attributes[0].name, "serde");
+        assert_eq!(fields[0].attributes[0].args, ["rename"]);
+        assert_eq!(fields[1].name, "plain");
+        assert!(fields[1].attributes.is_empty());
+
+        let Item::Function { params, .. } = &program[1] else { panic!("expected a function") };
+        assert_eq!(params[0].name, "factor");
+        assert_eq!(params[0].attributes[0].name, "allow");
+        assert_eq!(params[0].attributes[0].args, ["unused"]);
+        assert!(params[1].attributes.is_empty());
+    }
+}
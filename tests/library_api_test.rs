@@ -126,4 +126,64 @@ mod library_api_tests {
         let result = config.validate();
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_build_hir_function_and_struct() {
+        use gaiarusted::frontend::build_hir;
+        use gaiarusted::lowering::HirItem;
+        use std::path::Path;
+
+        let source = "struct Point { x: i64, y: i64 }\nfn main() { let p = Point { x: 1, y: 2 }; }\n";
+        let (items, diagnostics) = build_hir(source, Path::new("."));
+
+        // The lints run as they do in `compile_files`
+        assert_eq!(diagnostics.len(), 1, "unexpected diagnostics: {:?}", diagnostics);
+        assert_eq!(diagnostics[0].message, "unused variable: `p`");
+        assert_eq!(diagnostics[0].location.map(|location| location.line), Some(2));
+        // The program's two items; the stdlib prelude's functions are only
+        // merged in when the program calls them
+        assert_eq!(items.len(), 2);
+        assert!(matches!(&items[0], HirItem::Struct { name, .. } if name == "Point"));
+        assert!(matches!(&items[1], HirItem::Function { name, .. } if name == "main"));
+    }
+
+    #[test]
+    fn test_build_hir_reports_parse_error() {
+        let (items, diagnostics) = gaiarusted::frontend::build_hir("fn main( {", std::path::Path::new("."));
+        assert!(items.is_empty());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].phase, "Parsing");
+        assert_eq!(diagnostics[0].file, Some(std::path::Path::new(".").join("main.rs")));
+        assert_eq!(diagnostics[0].location.map(|location| (location.line, location.column)), Some((1, 10)));
+    }
+
+    #[test]
+    fn test_byte_literals_lower_to_u8_values() {
+        use gaiarusted::frontend::build_hir;
+        use gaiarusted::lowering::{HirExpression, HirItem, HirStatement};
+        use gaiarusted::utilities::error_reporting::Severity;
+        use std::path::Path;
+
+        let source = "fn main() { let s = b\"AB\"; let c = b'\\n'; }\n";
+        let (items, diagnostics) = build_hir(source, Path::new("."));
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning), "unexpected diagnostics: {:?}", diagnostics);
+
+        let body = items.iter().find_map(|item| match item {
+            HirItem::Function { name, body, .. } if name == "main" => Some(body),
+            _ => None,
+        }).expect("main should be lowered");
+        let inits: Vec<&HirExpression> = body.iter().filter_map(|stmt| match stmt {
+            HirStatement::Let { init, .. } => Some(init),
+            _ => None,
+        }).collect();
+
+        match inits[0] {
+            HirExpression::ArrayLiteral(elements) => assert!(matches!(
+                elements.as_slice(),
+                [HirExpression::Integer(65), HirExpression::Integer(66)]
+            )),
+            other => panic!("expected a byte array, got {:?}", other),
+        }
+        assert!(matches!(inits[1], HirExpression::Integer(10)));
+    }
 }
\ No newline at end of file
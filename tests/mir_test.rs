@@ -1,4 +1,5 @@
-use gaiarusted::mir::{MirBuilder, Place, Operand, Constant, Rvalue, Terminator};
+use gaiarusted::lowering::HirType;
+use gaiarusted::mir::{AssertKind, BasicBlock, Mir, MirBuilder, MirFunction, Place, Operand, Constant, Rvalue, Terminator};
 
 #[test]
 fn test_mir_builder_creation() {
@@ -48,4 +49,264 @@ fn test_terminator_return() {
 fn test_terminator_goto() {
     let term = Terminator::Goto(1);
     assert_eq!(term.to_string(), "goto bb1");
-}
\ No newline at end of file
+}
+
+fn mir_with_blocks(terminators: Vec<Terminator>) -> Mir {
+    let basic_blocks = terminators
+        .into_iter()
+        .map(|terminator| BasicBlock { statements: Vec::new(), terminator })
+        .collect();
+    Mir {
+        functions: vec![MirFunction {
+            name: "broken".to_string(),
+            params: Vec::new(),
+            return_type: HirType::Int64,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
+            basic_blocks,
+        }],
+        globals: Vec::new(),
+        closures: Vec::new(),
+    }
+}
+
+#[test]
+fn test_verify_accepts_well_formed_cfg() {
+    let cond = Operand::Constant(Constant::Bool(true));
+    let mir = mir_with_blocks(vec![
+        Terminator::If(cond, 1, 2),
+        Terminator::Goto(2),
+        Terminator::Return(None),
+    ]);
+    assert!(mir.verify().is_ok());
+}
+
+#[test]
+fn test_verify_reports_out_of_range_target() {
+    let mir = mir_with_blocks(vec![Terminator::Goto(5), Terminator::Return(None)]);
+    let err = mir.verify().unwrap_err();
+    assert!(err.message.contains("bb0 jumps to bb5"), "{}", err);
+}
+
+#[test]
+fn test_verify_reports_reachable_unreachable_terminator() {
+    let mir = mir_with_blocks(vec![Terminator::Goto(1), Terminator::Unreachable]);
+    let err = mir.verify().unwrap_err();
+    assert!(err.message.contains("reachable bb1 ends in `unreachable`"), "{}", err);
+}
+
+#[test]
+fn test_verify_reports_orphan_block() {
+    let mir = mir_with_blocks(vec![Terminator::Return(None), Terminator::Return(None)]);
+    let err = mir.verify().unwrap_err();
+    assert!(err.message.contains("bb1 is not reachable"), "{}", err);
+}
+
+fn mir_for_source(source: &str, opt_level: u32) -> Mir {
+    let tokens = gaiarusted::lexer::lex(source).expect("lexing failed");
+    let ast = gaiarusted::parser::parse(tokens).expect("parsing failed");
+    let hir = gaiarusted::lowering::lower(&ast).expect("lowering failed");
+    let mut mir = gaiarusted::mir::lower_to_mir(&hir).expect("MIR lowering failed");
+    gaiarusted::mir::optimize_mir(&mut mir, opt_level).expect("optimization failed");
+    mir
+}
+
+fn bounds_checks(mir: &Mir) -> usize {
+    mir.functions
+        .iter()
+        .flat_map(|func| &func.basic_blocks)
+        .filter(|block| matches!(block.terminator, Terminator::Assert(_, AssertKind::BoundsCheck { .. }, _)))
+        .count()
+}
+
+#[test]
+fn test_constant_index_bounds_check_is_eliminated() {
+    let source = "fn main() { let a = [1, 2, 3]; let x = a[2]; }";
+    assert_eq!(bounds_checks(&mir_for_source(source, 0)), 1);
+    assert_eq!(bounds_checks(&mir_for_source(source, 1)), 0);
+}
+
+#[test]
+fn test_dynamic_index_keeps_bounds_check() {
+    let source = "fn main() { let a = [1, 2, 3]; let i = 4; let x = a[i]; }";
+    let mir = mir_for_source(source, 2);
+    assert_eq!(bounds_checks(&mir), 1);
+    mir.verify().expect("MIR should stay well formed");
+}
+
+#[test]
+fn test_size_of_folds_to_constant() {
+    let source = "
+struct Header { flag: bool, id: i64, kind: u8, count: i32 }
+fn main() {
+    let word = size_of::<i64>();
+    let header = std::mem::size_of::<Header>();
+    let align = std::mem::align_of::<Header>();
+}";
+    let mir = mir_for_source(source, 0);
+    let statements: Vec<_> = mir
+        .functions
+        .iter()
+        .flat_map(|func| &func.basic_blocks)
+        .flat_map(|block| &block.statements)
+        .collect();
+    assert!(!statements.iter().any(|stmt| matches!(stmt.rvalue, Rvalue::Call(..))));
+
+    let constant_for = |name: &str| {
+        statements.iter().find_map(|stmt| match (&stmt.place, &stmt.rvalue) {
+            (Place::Local(local), Rvalue::Use(Operand::Constant(Constant::Integer(n)))) if local == name => Some(*n),
+            _ => None,
+        })
+    };
+    assert_eq!(constant_for("word"), Some(8));
+    // bool + i64 + u8 + i32 = 14 bytes, rounded up to the 8-byte alignment
+    assert_eq!(constant_for("header"), Some(16));
+    assert_eq!(constant_for("align"), Some(8));
+}
+
+#[test]
+fn test_statement_ids_are_unique_and_survive_optimization() {
+    let source = "
+fn add(a: i64, b: i64) -> i64 { let sum = a + b; sum }
+fn main() {
+    let x = add(2, 3);
+    if x > 4 { println!(\"{}\", x); } else { println!(\"{}\", 0); }
+}";
+    let mir = mir_for_source(source, 0);
+    for func in &mir.functions {
+        let mut ids: Vec<usize> = func.basic_blocks.iter()
+            .flat_map(|block| &block.statements)
+            .map(|stmt| stmt.id)
+            .collect();
+        let count = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), count, "duplicate statement ids in {}", func.name);
+    }
+
+    let mut optimized = mir.clone();
+    gaiarusted::mir::optimize_mir(&mut optimized, 0).expect("optimization failed");
+    for (before, after) in mir.functions.iter().zip(&optimized.functions) {
+        for block in &after.basic_blocks {
+            for stmt in &block.statements {
+                let original = before.statement(stmt.id).expect("statement id should be preserved");
+                assert_eq!(original.to_string(), stmt.to_string());
+                assert_eq!(before.statement_location(stmt.id), after.statement_location(stmt.id));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_if_with_constant_condition_lowers_without_a_branch() {
+    let source = "
+fn f() -> i64 { 1 }
+fn main() {
+    let x = if false { f() } else { 7 };
+    let y = if 2 > 1 && !false { x } else { f() };
+}";
+    let mir = mir_for_source(source, 0);
+    let main = mir.functions.iter().find(|func| func.name.ends_with("main")).expect("main should be lowered");
+    assert!(
+        main.basic_blocks.iter().all(|block| !matches!(block.terminator, Terminator::If(..))),
+        "constant conditions should not branch:\n{}",
+        main
+    );
+}
+
+#[test]
+fn test_ssa_renaming_leaves_one_definition_per_local() {
+    let source = "
+fn main() {
+    let mut total = 0;
+    let mut i = 0;
+    while i < 5 {
+        if i % 2 == 0 {
+            total = total + i;
+        } else {
+            total = total - 1;
+        }
+        i = i + 1;
+    }
+    let label = if total > 3 { 1 } else { 2 };
+    println!(\"{} {}\", total, label);
+}";
+    let mut mir = mir_for_source(source, 0);
+    let main = mir.functions.iter_mut().find(|func| func.name.ends_with("main")).expect("main should be lowered");
+    let joins = gaiarusted::mir::rename_to_ssa(main);
+    assert!(!joins.is_empty(), "the loop and the if-merges should need join locals");
+
+    let mut definitions: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    for block in &main.basic_blocks {
+        for stmt in &block.statements {
+            if let Place::Local(name) = &stmt.place {
+                *definitions.entry(name.clone()).or_default() += 1;
+            }
+        }
+    }
+    for (name, count) in &definitions {
+        if !joins.contains(name) {
+            assert_eq!(*count, 1, "`{}` should be defined once after renaming:\n{}", name, main);
+        }
+    }
+    for name in ["total", "i", "label"] {
+        assert!(!definitions.contains_key(name), "`{}` should have been renamed:\n{}", name, main);
+    }
+}
+
+#[test]
+fn test_loop_variables_keep_registers_while_cold_locals_spill() {
+    use gaiarusted::codegen::register_pressure::{loop_depths, RegisterPressureAnalyzer};
+
+    let mir = mir_for_source(
+        r#"
+fn sum(n: i32) -> i32 {
+    let cold = n * 3;
+    let mut total = 0;
+    let mut i = 0;
+    while i < n {
+        total = total + i;
+        i = i + 1;
+    }
+    total + cold
+}
+
+fn main() {
+    println!("{}", sum(4));
+}
+"#,
+        0,
+    );
+    let func = mir.functions.iter().find(|f| f.name.ends_with("::sum")).expect("sum not lowered");
+    let depths = loop_depths(func);
+    assert_eq!(depths[0], 0, "the entry block is outside the loop");
+    assert!(depths.contains(&1), "the while loop was not found: {:?}", depths);
+
+    let mut analyzer = RegisterPressureAnalyzer::new();
+    analyzer.registers.truncate(2);
+    let spilled = analyzer.allocate_by_loop_depth(func);
+
+    assert!(analyzer.register_of("i").is_some(), "loop counter spilled: {:?}", spilled);
+    assert!(analyzer.stack_slots.contains_key("cold"), "cold local kept a register");
+    let cold = spilled.iter().position(|name| name == "cold").unwrap();
+    let loop_temp = spilled.iter().position(|name| name == "_t3").unwrap();
+    assert!(cold < loop_temp, "loop temporaries spilled before cold locals: {:?}", spilled);
+}
+
+#[test]
+fn test_loop_re_enters_its_body_without_a_condition() {
+    let source = "
+fn main() {
+    let mut i = 0;
+    loop {
+        i += 1;
+        if i == 3 {
+            break;
+        }
+    }
+}";
+    let mir = mir_for_source(source, 0);
+    let main = mir.functions.iter().find(|func| func.name.ends_with("main")).expect("main should be lowered");
+    let branches = main.basic_blocks.iter().filter(|block| matches!(block.terminator, Terminator::If(..))).count();
+    assert_eq!(branches, 1, "only `i == 3` should branch:\n{}", main);
+}
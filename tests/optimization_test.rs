@@ -11,9 +11,12 @@ fn test_constant_folding_binary_add() {
             name: "test".to_string(),
             params: vec![],
             return_type: HirType::Int32,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
             basic_blocks: vec![BasicBlock {
                 statements: vec![
                     Statement {
+                        id: 0,
                         place: Place::Local("result".to_string()),
                         rvalue: Rvalue::BinaryOp(
                             BinaryOp::Add,
@@ -55,9 +58,12 @@ fn test_constant_folding_multiply() {
             name: "test".to_string(),
             params: vec![],
             return_type: HirType::Int32,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
             basic_blocks: vec![BasicBlock {
                 statements: vec![
                     Statement {
+                        id: 0,
                         place: Place::Local("result".to_string()),
                         rvalue: Rvalue::BinaryOp(
                             BinaryOp::Multiply,
@@ -89,9 +95,12 @@ fn test_constant_folding_unary_negate() {
             name: "test".to_string(),
             params: vec![],
             return_type: HirType::Int32,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
             basic_blocks: vec![BasicBlock {
                 statements: vec![
                     Statement {
+                        id: 0,
                         place: Place::Local("result".to_string()),
                         rvalue: Rvalue::UnaryOp(
                             UnaryOp::Negate,
@@ -122,9 +131,12 @@ fn test_constant_folding_comparison() {
             name: "test".to_string(),
             params: vec![],
             return_type: HirType::Bool,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
             basic_blocks: vec![BasicBlock {
                 statements: vec![
                     Statement {
+                        id: 0,
                         place: Place::Local("result".to_string()),
                         rvalue: Rvalue::BinaryOp(
                             BinaryOp::Greater,
@@ -156,15 +168,19 @@ fn test_dead_code_elimination() {
             name: "test".to_string(),
             params: vec![],
             return_type: HirType::Int32,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
             basic_blocks: vec![BasicBlock {
                 statements: vec![
                     // This assignment is dead - x is never used
                     Statement {
+                        id: 0,
                         place: Place::Local("x".to_string()),
                         rvalue: Rvalue::Use(Operand::Constant(Constant::Integer(42))),
                     },
                     // This assignment is live - result is returned
                     Statement {
+                        id: 1,
                         place: Place::Local("result".to_string()),
                         rvalue: Rvalue::Use(Operand::Constant(Constant::Integer(100))),
                     },
@@ -202,9 +218,12 @@ fn test_no_optimization_level_0() {
             name: "test".to_string(),
             params: vec![],
             return_type: HirType::Int32,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
             basic_blocks: vec![BasicBlock {
                 statements: vec![
                     Statement {
+                        id: 0,
                         place: Place::Local("result".to_string()),
                         rvalue: Rvalue::BinaryOp(
                             BinaryOp::Add,
@@ -240,6 +259,8 @@ fn test_simplify_goto_chain() {
             name: "test".to_string(),
             params: vec![],
             return_type: HirType::Int32,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
             basic_blocks: vec![
                 BasicBlock {
                     statements: vec![],
@@ -278,20 +299,25 @@ fn test_copy_propagation() {
             name: "test".to_string(),
             params: vec![],
             return_type: HirType::Int32,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
             basic_blocks: vec![BasicBlock {
                 statements: vec![
                     // x = 42
                     Statement {
+                        id: 0,
                         place: Place::Local("x".to_string()),
                         rvalue: Rvalue::Use(Operand::Constant(Constant::Integer(42))),
                     },
                     // y = x (copy)
                     Statement {
+                        id: 1,
                         place: Place::Local("y".to_string()),
                         rvalue: Rvalue::Use(Operand::Copy(Place::Local("x".to_string()))),
                     },
                     // z = y + 1
                     Statement {
+                        id: 2,
                         place: Place::Local("z".to_string()),
                         rvalue: Rvalue::BinaryOp(
                             BinaryOp::Add,
@@ -328,10 +354,13 @@ fn test_optimization_cumulative_effect() {
             name: "test".to_string(),
             params: vec![],
             return_type: HirType::Int32,
+            vec_element_types: std::collections::HashMap::new(),
+            integer_widths: std::collections::HashMap::new(),
             basic_blocks: vec![BasicBlock {
                 statements: vec![
                     // x = 2 + 3 (will be folded to 5)
                     Statement {
+                        id: 0,
                         place: Place::Local("x".to_string()),
                         rvalue: Rvalue::BinaryOp(
                             BinaryOp::Add,
@@ -341,11 +370,13 @@ fn test_optimization_cumulative_effect() {
                     },
                     // unused_var = 99 (dead code)
                     Statement {
+                        id: 1,
                         place: Place::Local("unused_var".to_string()),
                         rvalue: Rvalue::Use(Operand::Constant(Constant::Integer(99))),
                     },
                     // result = x * 2 (folding wouldn't work here because x is now used)
                     Statement {
+                        id: 2,
                         place: Place::Local("result".to_string()),
                         rvalue: Rvalue::Use(Operand::Copy(Place::Local("x".to_string()))),
                     },
@@ -0,0 +1,3543 @@
+//! End-to-end tests: compile a program with the full pipeline, run it,
+//! and check what it prints.
+
+use gaiarusted::{compile_files, AsmSyntax, CompilationConfig, CompilationResult, CompileError, OutputFormat};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Compile `source` as `main.rs` in a scratch directory and return the
+/// program's stdout.
+fn compile_and_run(test_name: &str, source: &str) -> String {
+    let dir = scratch_dir(test_name);
+    compile_in(&dir, source);
+    run_in(&dir)
+}
+
+fn compile_in(dir: &Path, source: &str) -> CompilationResult {
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, source).expect("failed to write main.rs");
+
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"));
+    let result = compile_files(&config).unwrap_or_else(|e| panic!("compilation failed: {}", e));
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+    result
+}
+
+/// Compile `source`, expecting it to be rejected, and return the errors
+fn compile_errors(test_name: &str, source: &str) -> Vec<CompileError> {
+    let dir = scratch_dir(test_name);
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, source).expect("failed to write main.rs");
+
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"));
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(!result.success, "compilation should have failed");
+    result.errors
+}
+
+fn run_in(dir: &Path) -> String {
+    let output = Command::new(dir.join("prog"))
+        .output()
+        .expect("failed to run compiled program");
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn scratch_dir(test_name: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("gaiarusted_run_pass_{}_{}", test_name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("failed to create scratch dir");
+    dir
+}
+
+#[test]
+fn test_vec_iter_sum() {
+    let stdout = compile_and_run("vec_iter_sum", r#"
+fn main() {
+    let v = vec![1, 2, 3, 4];
+    let mut total = 0;
+    for x in v.iter() {
+        total = total + *x;
+    }
+    println!("{}", total);
+    println!("{}", v.iter().sum());
+}
+"#);
+    assert_eq!(stdout, "10\n10\n");
+}
+
+#[test]
+fn test_array_iter_zero_element() {
+    let stdout = compile_and_run("array_iter_zero", r#"
+fn main() {
+    let a = [10, 0, 30];
+    let mut total = 0;
+    for x in a.iter() {
+        total = total + *x;
+    }
+    println!("{}", total);
+}
+"#);
+    assert_eq!(stdout, "40\n");
+}
+
+#[test]
+fn test_prelude_lowered_once_across_compilations() {
+    let first_dir = scratch_dir("prelude_first");
+    compile_in(&first_dir, "fn main() { println!(\"{}\", min(3, 7)); }");
+    assert_eq!(run_in(&first_dir), "3\n");
+
+    let second_dir = scratch_dir("prelude_second");
+    let second = compile_in(&second_dir, "fn main() { println!(\"{}\", max(3, 7)); }");
+    assert_eq!(run_in(&second_dir), "7\n");
+
+    // The second compilation reuses the cached prelude HIR
+    assert!(
+        second.stats.prelude_lowering_time_us < 1000,
+        "prelude was not cached: {}us",
+        second.stats.prelude_lowering_time_us
+    );
+}
+
+#[test]
+fn test_match_some_binds_payload() {
+    let stdout = compile_and_run("match_some_binds", r#"
+fn main() {
+    let opt = Some(42);
+    match opt {
+        None => println!("none"),
+        Some(x) => {
+            if x == 42 {
+                println!("bound {}", x);
+            }
+        }
+    }
+}
+"#);
+    assert_eq!(stdout, "bound 42\n");
+}
+
+#[test]
+fn test_match_user_enum_binds_payload() {
+    let stdout = compile_and_run("match_enum_binds", r#"
+enum Shape {
+    Square(i64),
+    Rect(i64, i64),
+    Empty,
+}
+
+fn area(s: Shape) -> i64 {
+    match s {
+        Shape::Square(w) => w * w,
+        Shape::Rect(w, h) => w * h,
+        Shape::Empty => 0,
+    }
+}
+
+fn main() {
+    println!("{}", area(Shape::Square(7)));
+    println!("{}", area(Shape::Rect(3, 5)));
+    println!("{}", area(Shape::Empty));
+}
+"#);
+    assert_eq!(stdout, "49\n15\n0\n");
+}
+
+#[test]
+fn test_derive_hash_struct_as_hashmap_key() {
+    let stdout = compile_and_run("derive_hash_key", r#"
+use std::collections::HashMap;
+
+#[derive(Hash)]
+struct Point {
+    x: i64,
+    y: i64,
+}
+
+fn main() {
+    let mut m = HashMap::new();
+    m.insert(Point { x: 1, y: 2 }, 100);
+    m.insert(Point { x: 2, y: 1 }, 200);
+    let key = Point { x: 2, y: 1 };
+    println!("{}", m.get(&key));
+    let other = Point { x: 1, y: 2 };
+    println!("{}", m.get(&other));
+    let missing = Point { x: 5, y: 5 };
+    println!("{}", m.contains_key(&missing));
+    m.insert(Point { x: 5, y: 5 }, 300);
+    println!("{} {}", m.contains_key(&missing), m.get(&missing));
+}
+"#);
+    assert_eq!(stdout, "200\n100\n0\n1 300\n");
+}
+
+#[test]
+fn test_method_calls_on_literals() {
+    let stdout = compile_and_run("literal_methods", r#"
+fn main() {
+    println!("{}", 10.pow(3));
+    println!("{}", "abc".len());
+}
+"#);
+    assert_eq!(stdout, "1000\n3\n");
+}
+
+#[test]
+fn test_float_math_builtins() {
+    let stdout = compile_and_run("float_math", r#"
+fn main() {
+    println!("{}", sqrt(16.0));
+    println!("{}", pow(2.0, 10.0));
+    println!("{}", floor(2.7));
+    println!("{}", ceil(2.2));
+}
+"#);
+    assert_eq!(stdout, "4.000000\n1024.000000\n2.000000\n3.000000\n");
+}
+
+#[test]
+fn test_const_casts_in_array_lengths() {
+    let stdout = compile_and_run("const_casts", r#"
+const N: usize = 3i64 as usize;
+const BYTE: u8 = 300 as u8;
+const HALF: f64 = N as f64 / 2.0;
+fn main() {
+    let a: [i64; N] = [1, 2, 3];
+    println!("{}", a[N - 1]);
+    println!("{}", BYTE);
+    println!("{}", HALF);
+    let x = 200;
+    println!("{}", x as i8);
+}
+"#);
+    assert_eq!(stdout, "3\n44\n1.500000\n-56\n");
+}
+
+#[test]
+fn test_locals_shadow_consts_and_consts_resolve_by_module() {
+    let stdout = compile_and_run("const_scoping", r#"
+const N: i64 = 10;
+
+mod config {
+    pub const N: i64 = 42;
+    pub const TWICE: i64 = N * 2;
+}
+
+use config::TWICE;
+
+fn f(N: i64) -> i64 { N }
+
+fn total() -> i64 { N + config::N + TWICE }
+
+fn main() {
+    let N = 3;
+    println!("{} {}", N, f(7));
+    println!("{}", total());
+}
+"#);
+    assert_eq!(stdout, "3 7\n136\n");
+}
+
+#[test]
+fn test_derived_ord_compares_fields_lexicographically() {
+    let stdout = compile_and_run("derived_ord", r#"
+use std::cmp::Ordering;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    major: i64,
+    minor: i64,
+}
+
+fn main() {
+    let a = Version { major: 2, minor: 0 };
+    let b = Version { major: 1, minor: 9 };
+    let c = Version { major: 1, minor: 10 };
+    let d = Version { major: 0, minor: 99 };
+    // Sorted position of each version: how many others order before it
+    let mut rank_a = 0;
+    let mut rank_b = 0;
+    let mut rank_c = 0;
+    let mut rank_d = 0;
+    if b < a { rank_a = rank_a + 1; }
+    if c < a { rank_a = rank_a + 1; }
+    if d < a { rank_a = rank_a + 1; }
+    if a < b { rank_b = rank_b + 1; }
+    if c < b { rank_b = rank_b + 1; }
+    if d < b { rank_b = rank_b + 1; }
+    if a < c { rank_c = rank_c + 1; }
+    if b < c { rank_c = rank_c + 1; }
+    if d < c { rank_c = rank_c + 1; }
+    if a < d { rank_d = rank_d + 1; }
+    if b < d { rank_d = rank_d + 1; }
+    if c < d { rank_d = rank_d + 1; }
+    println!("{} {} {} {}", rank_a, rank_b, rank_c, rank_d);
+    if b.cmp(&c) == Ordering::Less && c.cmp(&b) == Ordering::Greater && b.cmp(&b) == Ordering::Equal {
+        println!("cmp ok");
+    }
+    let e = Version { major: 2, minor: 0 };
+    if a == b { println!("a == b"); }
+    if a != e { println!("a != e"); }
+    if c >= b && b <= c && a > d { println!("ok"); }
+}
+"#);
+    assert_eq!(stdout, "3 1 2 0\ncmp ok\nok\n");
+}
+
+#[test]
+fn test_att_syntax_assembles_to_same_code_as_intel() {
+    let dir = scratch_dir("att_syntax");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn scale(x: i64, y: f64) -> f64 {
+    let a = [1, 2, 3];
+    let shifted = (x << 2) >> 1;
+    if shifted / 3 != a[2] % 2 {
+        return y * 2.5;
+    }
+    y - 1.0
+}
+fn main() {
+    let n = -42;
+    println!("{}", scale(n, 3.0));
+}
+"#).expect("failed to write main.rs");
+
+    let assemble = |syntax: AsmSyntax, name: &str| {
+        let config = CompilationConfig::new()
+            .add_source_file(&main_rs)
+            .expect("failed to add source file")
+            .set_output(dir.join(name))
+            .set_output_format(OutputFormat::Assembly)
+            .set_asm_syntax(syntax);
+        let result = compile_files(&config).expect("compilation should run");
+        assert!(result.success, "compilation failed: {:?}", result.errors);
+        let asm = dir.join(format!("{}.s", name));
+        let object = dir.join(format!("{}.o", name));
+        let status = Command::new("as").arg(&asm).arg("-o").arg(&object).status().expect("failed to run as");
+        assert!(status.success(), "as rejected {}", asm.display());
+        (std::fs::read_to_string(asm).unwrap(), std::fs::read(object).unwrap())
+    };
+
+    let (_, intel_object) = assemble(AsmSyntax::Intel, "intel");
+    let (att_asm, att_object) = assemble(AsmSyntax::Att, "att");
+    assert!(att_asm.starts_with(".att_syntax"));
+    assert!(att_asm.contains("movq") && att_asm.contains("%rax"));
+    assert!(intel_object == att_object, "AT&T and Intel output assemble differently");
+}
+
+#[test]
+fn test_must_use_result_discarded_warns() {
+    let dir = scratch_dir("must_use");
+    let result = compile_in(&dir, r#"
+#[must_use]
+fn check() -> bool {
+    true
+}
+fn main() {
+    check();
+    let _ = check();
+    if check() {
+        println!("checked");
+    }
+}
+"#);
+    assert_eq!(result.warnings.len(), 1, "{:?}", result.warnings);
+    assert!(result.warnings[0].message.contains("unused return value of `check` that must be used"));
+    assert_eq!(run_in(&dir), "checked\n");
+}
+
+#[test]
+fn test_must_use_resolves_the_called_path() {
+    let dir = scratch_dir("must_use_path");
+    let result = compile_in(&dir, r#"
+mod strict {
+    #[must_use]
+    pub fn check() -> i64 {
+        1
+    }
+}
+mod lax {
+    pub fn check() -> i64 {
+        2
+    }
+}
+fn main() {
+    lax::check();
+    strict::check();
+    println!("done");
+}
+"#);
+    assert_eq!(result.warnings.len(), 1, "{:?}", result.warnings);
+    assert!(result.warnings[0].message.contains("unused return value of `strict::check` that must be used"));
+    assert_eq!(run_in(&dir), "done\n");
+}
+
+#[test]
+fn test_allow_attributes_silence_lints() {
+    let dir = scratch_dir("allow_lints");
+    let result = compile_in(&dir, r#"
+#[allow(unconditional_recursion)]
+fn spin() -> i64 {
+    spin()
+}
+
+#[allow(unused)]
+mod quiet {
+    pub fn f() -> i64 {
+        let unused = 1;
+        2
+    }
+}
+
+struct S {
+    v: i64,
+}
+
+#[allow(unused_variables)]
+impl S {
+    fn get(&self) -> i64 {
+        let spare = 3;
+        self.v
+    }
+}
+
+#[must_use]
+fn check() -> i64 {
+    1
+}
+
+#[allow(unused_must_use, dead_code)]
+fn caller() {
+    check();
+}
+
+fn main() {
+    let loud = 4;
+    let s = S { v: 5 };
+    println!("{}", s.get() + quiet::f());
+    caller();
+}
+"#);
+    let messages: Vec<&str> = result.warnings.iter().map(|w| w.message.as_str()).collect();
+    assert_eq!(messages, ["unused variable: `loud`"]);
+    assert_eq!(run_in(&dir), "7\n");
+}
+
+#[test]
+fn test_werror_rejects_unused_variable() {
+    let dir = scratch_dir("werror");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, "fn main() {\n    let unused = 5;\n}\n").expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"));
+
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+    assert_eq!(result.warnings.len(), 1);
+    assert!(result.warnings[0].message.contains("unused variable: `unused`"));
+
+    let result = compile_files(&config.warnings_as_errors(true)).expect("compilation should run");
+    assert!(!result.success);
+    assert!(result.warnings.is_empty());
+    assert!(result.errors.iter().any(|e| e.message.contains("unused variable: `unused`")));
+}
+
+#[test]
+fn test_unconditional_recursion_warning() {
+    let dir = scratch_dir("unconditional_recursion");
+    let result = compile_in(&dir, r#"
+fn f() {
+    f()
+}
+
+fn factorial(n: i64) -> i64 {
+    if n <= 1 {
+        1
+    } else {
+        n * factorial(n - 1)
+    }
+}
+
+fn fact(n: i64) -> i64 { if n == 0 { return 1; } n * fact(n - 1) }
+
+fn main() {
+    println!("{} {}", factorial(5), fact(4));
+}
+"#);
+    assert_eq!(result.warnings.len(), 1, "{:?}", result.warnings);
+    assert!(result.warnings[0].message.contains("function cannot return without recursing"));
+    assert_eq!(result.warnings[0].context.as_deref(), Some("in function `f`"));
+    assert_eq!(result.warnings[0].file, Some(dir.join("main.rs")));
+    assert_eq!(result.warnings[0].location.map(|location| (location.line, location.column)), Some((2, 4)));
+    assert_eq!(run_in(&dir), "120 24\n");
+}
+
+#[test]
+fn test_raw_pointer_arithmetic() {
+    let stdout = compile_and_run("raw_pointer_arithmetic", r#"
+fn main() {
+    let p: *mut i64 = gaia_alloc(32) as *mut i64;
+    unsafe {
+        *p = 7;
+        *p.add(1) = 9;
+        *p.offset(3) = 11;
+    }
+    let q: *mut i64 = p.add(1);
+    unsafe {
+        println!("{} {} {}", *p, *q, *q.add(2));
+    }
+}
+"#);
+    assert_eq!(stdout, "7 9 11\n");
+
+    let errors = compile_errors("raw_pointer_deref_safe", r#"
+fn main() {
+    let p: *mut i64 = gaia_alloc(8) as *mut i64;
+    let v = *p;
+}
+"#);
+    assert!(errors.iter().any(|e| e.message.contains("cannot dereference raw pointer outside of unsafe block")));
+}
+
+#[test]
+fn test_wrong_argument_count_is_rejected() {
+    let errors = compile_errors("wrong_argument_count", r#"
+fn add(a: i64, b: i64) -> i64 {
+    a + b
+}
+fn main() {
+    println!("{}", add(1, 2, 3));
+}
+"#);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].message.contains("expected 2 arguments, found 3"), "{}", errors[0].message);
+    let help = errors[0].help.as_deref().unwrap_or_default();
+    assert_eq!(help, "`add` is defined at line 2, column 4");
+}
+
+#[test]
+fn test_empty_file_executable_needs_main() {
+    let errors = compile_errors("empty_executable", "// nothing but a comment\n");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].message, "no `main` function found");
+}
+
+#[test]
+fn test_missing_main_explains_a_wrapped_crate_root() {
+    let dir = scratch_dir("wrapped_root");
+    let app_rs = dir.join("app.rs");
+    std::fs::write(&app_rs, "fn main() {\n    println!(\"{}\", 1);\n}\n").expect("failed to write app.rs");
+
+    let config = CompilationConfig::new()
+        .add_source_file(&app_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"));
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(!result.success, "compilation should have failed");
+    assert_eq!(result.errors.len(), 1, "{:?}", result.errors);
+    assert_eq!(result.errors[0].message, "no `main` function found");
+    let help = result.errors[0].help.as_deref().unwrap_or_default();
+    assert!(help.contains("compiled as module `app`"), "{}", help);
+}
+
+#[test]
+fn test_empty_file_library_builds() {
+    let dir = scratch_dir("empty_library");
+    let lib_rs = dir.join("lib.rs");
+    std::fs::write(&lib_rs, "").expect("failed to write lib.rs");
+
+    let config = CompilationConfig::new()
+        .add_source_file(&lib_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("empty"))
+        .set_output_format(OutputFormat::Library);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+    assert!(dir.join("empty.a").exists());
+}
+
+#[test]
+fn test_harness_survives_panicking_test() {
+    let dir = scratch_dir("test_harness");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn add(a: i64, b: i64) -> i64 {
+    a + b
+}
+
+#[test]
+fn adds_small_numbers() {
+    assert_eq!(add(1, 2), 3);
+}
+
+#[test]
+fn unwraps_none() {
+    let value: Option<i64> = None;
+    value.unwrap();
+}
+
+#[test]
+fn adds_negative_numbers() {
+    assert_eq!(add(-1, -2), -3);
+}
+
+fn main() {
+    println!("not run under --test");
+}
+"#).expect("failed to write main.rs");
+
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .with_test_harness(true);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+
+    let output = Command::new(dir.join("prog")).output().expect("failed to run test harness");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("test adds_small_numbers ... ok"), "{}", stdout);
+    assert!(stdout.contains("test unwraps_none ... FAILED"), "{}", stdout);
+    assert!(stdout.contains("test adds_negative_numbers ... ok"), "{}", stdout);
+    assert!(stdout.ends_with("test result: FAILED. 2 passed; 1 failed\n"), "{}", stdout);
+    assert!(!stdout.contains("not run under --test"), "{}", stdout);
+    assert_eq!(output.status.code(), Some(101));
+}
+
+#[test]
+fn test_option_and_result_methods() {
+    let stdout = compile_and_run("option_result_methods", r#"
+fn parse(n: i64) -> Result<i64, i64> {
+    if n > 0 {
+        return Ok(n);
+    }
+    Err(n)
+}
+fn main() {
+    let a = Some(5);
+    let b: Option<i64> = None;
+    println!("{} {}", a.unwrap_or(0), b.unwrap_or(9));
+    println!("{} {}", parse(3).unwrap(), parse(-1).unwrap_or(7));
+    if parse(-2).is_err() && a.is_some() && b.is_none() {
+        println!("flags ok");
+    }
+    println!("{}", b.unwrap());
+    println!("unreachable");
+}
+"#);
+    assert_eq!(stdout, "5 9\n3 7\nflags ok\npanicked at: called `Option::unwrap()` on a `None` value\n");
+}
+
+#[test]
+fn test_operator_precedence() {
+    let stdout = compile_and_run("operator_precedence", r#"
+fn mixed(x: i64, y: i64) -> i64 {
+    x + y * 4 - x % y << 1 >> 1
+}
+fn main() {
+    println!("{} {} {}", 2 + 3 * 4, 20 - 6 / 2 - 4, 10 % 4 * 3);
+    println!("{} {} {}", 1 << 2 + 1, 6 & 3 ^ 5 | 8, 64 >> 2 >> 1);
+    let x = 5;
+    let y = 3;
+    println!("{} {} {}", x - y - 1, x * y % 4, -x + y * 2);
+    println!("{} {} {}", x | y & 1, x ^ y << 2, mixed(x, y));
+    if x & 1 == 1 && y > 1 << 1 || x < 0 {
+        println!("logic ok");
+    }
+    if x + 1 >= y * 2 && x >= y && !(x == y) {
+        println!("compare ok");
+    }
+    let mut total = 0;
+    for i in 0..x - 2 {
+        total = total + i;
+    }
+    println!("{}", total);
+}
+"#);
+    assert_eq!(stdout, "14 13 6\n8 15 8\n1 3 1\n5 9 15\nlogic ok\ncompare ok\n3\n");
+}
+
+#[test]
+fn test_negative_array_index_panics() {
+    let dir = scratch_dir("negative_array_index");
+    compile_in(&dir, r#"
+fn main() {
+    let a = [10, 20, 30];
+    let i: i64 = -1;
+    println!("{}", a[i as usize]);
+}
+"#);
+    let output = Command::new(dir.join("prog")).output().expect("failed to run compiled program");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "panicked at: index out of bounds: the len is 3 but the index is 18446744073709551615\n");
+    assert_eq!(output.status.code(), Some(101));
+}
+
+#[test]
+fn test_vec_get_is_bounds_checked() {
+    let stdout = compile_and_run("vec_get_checked", r#"
+fn main() {
+    let v = vec![10, 20, 30];
+    let a = v.get(0);
+    let b = v.get(99);
+    if a.is_some() {
+        println!("some {}", a.unwrap());
+    }
+    if b.is_none() {
+        println!("none");
+    }
+    match v.get(2) {
+        Some(x) => println!("got {}", x),
+        None => println!("missing"),
+    }
+    let mut w = Vec::new();
+    w.push(5);
+    println!("{}", w.get(0).unwrap_or(-1));
+    println!("{}", w.get(3).unwrap_or(-1));
+}
+"#);
+    assert_eq!(stdout, "some 10\nnone\ngot 30\n5\n-1\n");
+}
+
+#[test]
+fn test_impl_block_on_primitive() {
+    let stdout = compile_and_run("primitive_impl", r#"
+impl i64 {
+    pub fn double(self) -> i64 {
+        self * 2
+    }
+
+    pub fn clamp_to(self, limit: i64) -> i64 {
+        if self > limit {
+            limit
+        } else {
+            self
+        }
+    }
+}
+
+fn main() {
+    let x: i64 = 21;
+    println!("{}", 5.double());
+    println!("{} {}", x.double(), x.clamp_to(10));
+}
+"#);
+    assert_eq!(stdout, "10\n42 10\n");
+}
+
+#[test]
+fn test_function_pointer_parameter() {
+    let stdout = compile_and_run("fn_pointer", r#"
+fn double(x: i64) -> i64 {
+    x * 2
+}
+
+fn negate(x: i64) -> i64 {
+    0 - x
+}
+
+fn apply(f: fn(i64) -> i64, x: i64) -> i64 {
+    f(x)
+}
+
+fn main() {
+    println!("{}", apply(double, 21));
+    println!("{}", apply(negate, 5));
+}
+"#);
+    assert_eq!(stdout, "42\n-5\n");
+}
+
+#[test]
+fn test_hashmap_for_loop() {
+    let stdout = compile_and_run("hashmap_for_loop", r#"
+use std::collections::HashMap;
+
+fn main() {
+    let mut map = HashMap::new();
+    map.insert(1, 10);
+    map.insert(2, 20);
+    map.insert(3, 12);
+    let mut total = 0;
+    for (_key, value) in map {
+        total = total + value;
+    }
+    let mut weighted = 0;
+    for (key, value) in map {
+        weighted = weighted + key * value;
+    }
+    println!("{} {}", total, weighted);
+}
+"#);
+    assert_eq!(stdout, "42 86\n");
+}
+
+#[test]
+fn test_reverse_iteration() {
+    let stdout = compile_and_run("reverse_iteration", r#"
+fn main() {
+    let v = vec![1, 2, 3];
+    for x in v.iter().rev() {
+        println!("{}", *x);
+    }
+    for i in (0..3).rev() {
+        println!("{}", i);
+    }
+    for i in (1..=2).rev() {
+        println!("{}", i);
+    }
+}
+"#);
+    assert_eq!(stdout, "3\n2\n1\n2\n1\n0\n2\n1\n");
+}
+
+#[test]
+fn test_print_symbols_lists_defined_and_referenced() {
+    let dir = scratch_dir("print_symbols");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn square(x: i64) -> i64 {
+    x * x
+}
+#[test]
+fn squares() {
+    assert_eq!(square(3), 9);
+}
+fn main() {
+    println!("{}", square(min(4, 5)));
+}
+"#).expect("failed to write main.rs");
+
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .with_print_symbols(true);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+
+    let symbols = result.symbols.expect("symbols should be collected");
+    assert!(symbols.defined.contains(&"gaia_main".to_string()), "{:?}", symbols);
+    assert!(symbols.defined.contains(&"square".to_string()), "{:?}", symbols);
+    assert!(symbols.referenced.contains(&"printf".to_string()), "{:?}", symbols);
+    // Calls between the unit's own functions aren't external references
+    assert!(!symbols.referenced.contains(&"square".to_string()), "{:?}", symbols);
+    assert!(symbols.to_string().contains("U printf\n"));
+    // Without `--test` the tests aren't compiled, and the prelude's
+    // functions come from the stdlib rather than the unit
+    assert_eq!(symbols.defined, vec!["square".to_string(), "gaia_main".to_string()]);
+    assert!(symbols.referenced.contains(&"min".to_string()), "{:?}", symbols);
+    assert!(!symbols.referenced.contains(&"max".to_string()), "{:?}", symbols);
+}
+
+#[test]
+fn test_assembler_error_points_at_generated_line() {
+    let dir = scratch_dir("assembler_error");
+    let asm = dir.join("broken.s");
+    let obj = dir.join("broken.o");
+    std::fs::write(&asm, ".intel_syntax noprefix\n.text\nf:\n    mov rax, 1\n    bogus rax\n")
+        .expect("failed to write broken.s");
+
+    let errors = gaiarusted::compiler_integration::assemble_verified(&asm, &obj).unwrap_err();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].line, Some(5));
+    assert!(errors[0].message.contains("no such instruction"), "{}", errors[0].message);
+    assert!(errors[0].help.as_deref().unwrap_or_default().contains("bogus rax"));
+    assert!(!obj.exists());
+}
+
+#[test]
+fn test_assembler_warnings_are_fatal() {
+    let dir = scratch_dir("assembler_warning");
+    let asm = dir.join("warn.s");
+    std::fs::write(&asm, ".data\n.byte 256\n").expect("failed to write warn.s");
+
+    let errors = gaiarusted::compiler_integration::assemble_verified(&asm, &dir.join("warn.o")).unwrap_err();
+    assert_eq!(errors[0].line, Some(2));
+    assert!(errors[0].message.starts_with("assembler warning"), "{}", errors[0].message);
+}
+
+#[test]
+fn test_nested_closure_captures_outer_parameter() {
+    let stdout = compile_and_run("nested_closure", r#"
+fn main() {
+    let base = 100;
+    let make_adder = |x: i64| {
+        let inner = |y: i64| x + y + base;
+        inner
+    };
+    let add5 = make_adder(5);
+    println!("{}", add5(1));
+    println!("{}", add5(2));
+}
+"#);
+    assert_eq!(stdout, "106\n107\n");
+}
+
+#[test]
+fn test_write_appends_to_string() {
+    let stdout = compile_and_run("write_to_string", r#"
+fn main() {
+    let mut buf = String::new();
+    let first = 7;
+    write!(buf, "first={}", first);
+    let result = write!(buf, ", second={}", first * 6);
+    if result.is_ok() {
+        println!("{}", buf);
+    }
+}
+"#);
+    assert_eq!(stdout, "first=7, second=42\n");
+}
+
+#[test]
+fn test_rodata_strings_are_reproducible() {
+    let source = r#"
+fn greet() {
+    println!("hello {}", 1);
+    println!("shared");
+}
+
+fn main() {
+    println!("shared");
+    greet();
+    println!("bye {}", 2);
+}
+"#;
+    let rodata_strings = |test_name: &str| {
+        let dir = scratch_dir(test_name);
+        let main_rs = dir.join("main.rs");
+        std::fs::write(&main_rs, source).expect("failed to write main.rs");
+        let config = CompilationConfig::new()
+            .add_source_file(&main_rs)
+            .expect("failed to add source file")
+            .set_output(dir.join("prog"))
+            .keep_intermediates(true);
+        let result = compile_files(&config).expect("compilation should run");
+        assert!(result.success, "compilation failed: {:?}", result.errors);
+        let asm = std::fs::read_to_string(dir.join("prog.s")).expect("failed to read prog.s");
+        asm.lines()
+            .filter(|line| line.trim_start().starts_with("str_"))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    let first = rodata_strings("rodata_first");
+    assert_eq!(first, rodata_strings("rodata_second"));
+    // Labels follow the order literals appear in, each string once
+    assert_eq!(first.len(), 3, "{:?}", first);
+    for (index, line) in first.iter().enumerate() {
+        assert!(line.trim_start().starts_with(&format!("str_{}:", index)), "{:?}", first);
+    }
+}
+
+#[test]
+fn test_enum_method_matches_self() {
+    let stdout = compile_and_run("enum_method", r#"
+enum Sign {
+    Pos,
+    Neg,
+}
+
+impl Sign {
+    fn flip(self) -> Sign {
+        match self {
+            Sign::Pos => Sign::Neg,
+            Sign::Neg => Sign::Pos,
+        }
+    }
+
+    fn value(self) -> i64 {
+        match self {
+            Sign::Pos => 1,
+            Sign::Neg => -1,
+        }
+    }
+}
+
+fn main() {
+    let flipped = Sign::Pos.flip();
+    match flipped {
+        Sign::Pos => println!("pos"),
+        Sign::Neg => println!("neg"),
+    }
+    println!("{}", flipped.flip().value());
+}
+"#);
+    assert_eq!(stdout, "neg\n1\n");
+}
+
+#[test]
+fn test_range_for_bounds_evaluated_once() {
+    let stdout = compile_and_run("range_bounds_once", r#"
+fn bound() -> i64 {
+    println!("bound");
+    3
+}
+
+fn lower() -> i64 {
+    println!("lower");
+    1
+}
+
+fn main() {
+    for i in 0..bound() {
+        println!("{}", i);
+    }
+    for j in (lower()..bound()).rev() {
+        println!("{}", j);
+    }
+    let mut n = 4;
+    let mut runs = 0;
+    for _k in 0..n {
+        n -= 1;
+        runs += 1;
+    }
+    println!("{} {}", runs, n);
+    for i in 0..2 {
+        for j in 0..i + bound() {
+            print!("{} ", j);
+        }
+        println!("");
+    }
+}
+"#);
+    assert_eq!(stdout, "bound\n0\n1\n2\nlower\nbound\n2\n1\n4 0\nbound\n0 1 2 \nbound\n0 1 2 3 \n");
+}
+
+#[test]
+fn test_vec_f64_elements_round_trip() {
+    let stdout = compile_and_run("vec_f64", r#"
+fn sum(v: Vec<f64>) -> f64 {
+    v[0] + v[1]
+}
+
+fn main() {
+    let mut v: Vec<f64> = Vec::new();
+    v.push(1.5);
+    v.push(3.141592653589793);
+    let pi = v[1];
+    if pi == 3.141592653589793 {
+        println!("exact");
+    }
+    println!("{}", pi * 1000000000.0);
+    println!("{}", sum(v));
+}
+"#);
+    assert_eq!(stdout, "exact\n3141592653.589793\n4.641593\n");
+}
+
+#[test]
+fn test_vec_struct_elements_by_pointer() {
+    let stdout = compile_and_run("vec_struct", r#"
+struct Point {
+    x: i64,
+    y: i64,
+}
+
+fn main() {
+    let mut points: Vec<Point> = Vec::new();
+    points.push(Point { x: 1, y: 2 });
+    points.push(Point { x: 3, y: 4 });
+    println!("{}", points[1].y);
+    println!("{}", points[0].y + points[1].x);
+}
+"#);
+    assert_eq!(stdout, "4\n5\n");
+}
+
+#[test]
+fn test_no_std_omits_main_wrapper_and_runtime() {
+    let dir = scratch_dir("no_std");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn add(a: i64, b: i64) -> i64 {
+    a + b
+}
+
+fn main() {
+    let sum = add(1, 2);
+}
+"#).expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .set_output_format(OutputFormat::Assembly)
+        .set_no_std(true);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+
+    let asm = std::fs::read_to_string(dir.join("prog.s")).expect("failed to read prog.s");
+    assert!(asm.contains("\nadd:") && asm.contains("\nmain:"), "{}", asm);
+    assert!(asm.contains(".globl add"));
+    assert!(!asm.contains("gaia_main"), "{}", asm);
+    assert!(!asm.contains("gaia_print_i64") && !asm.contains("gaia_vec_push"), "{}", asm);
+    let status = Command::new("as").arg(dir.join("prog.s")).arg("-o").arg(dir.join("prog.o")).status().expect("failed to run as");
+    assert!(status.success());
+}
+
+#[test]
+fn test_trait_default_method_is_inherited() {
+    let stdout = compile_and_run("trait_default_method", r#"
+trait Greeter {
+    fn id(&self) -> i64;
+
+    fn greet(&self) {
+        println!("hello {}", self.id());
+    }
+}
+
+struct Dog {
+    tag: i64,
+}
+
+struct Cat {
+    tag: i64,
+}
+
+impl Greeter for Dog {
+    fn id(&self) -> i64 {
+        self.tag
+    }
+}
+
+impl Greeter for Cat {
+    fn id(&self) -> i64 {
+        self.tag
+    }
+
+    fn greet(&self) {
+        println!("meow {}", self.id());
+    }
+}
+
+fn main() {
+    let dog = Dog { tag: 7 };
+    let cat = Cat { tag: 9 };
+    dog.greet();
+    cat.greet();
+}
+"#);
+    assert_eq!(stdout, "hello 7\nmeow 9\n");
+}
+
+#[test]
+fn test_max_errors_truncates_error_flood() {
+    let dir = scratch_dir("max_errors");
+    let main_rs = dir.join("main.rs");
+    let mut source = String::from("fn main() {\n");
+    for i in 0..200 {
+        source.push_str(&format!("    let unused{} = {};\n", i, i));
+    }
+    source.push_str("}\n");
+    std::fs::write(&main_rs, source).expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .warnings_as_errors(true);
+
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(!result.success);
+    assert_eq!(result.errors.len(), 101, "expected 100 errors plus the notice");
+    assert_eq!(result.errors[100].message, "aborting due to 100 previous errors; more may exist");
+
+    let result = compile_files(&config.set_max_errors(0)).expect("compilation should run");
+    assert_eq!(result.errors.len(), 200);
+}
+
+#[test]
+fn test_index_assignment_writes_element() {
+    let stdout = compile_and_run("index_assign", r#"
+fn main() {
+    let mut v = Vec::new();
+    for i in 0..5 {
+        v.push(i * 10);
+    }
+    v[2] = 99;
+    println!("{}", v[2]);
+    println!("{}", v[1]);
+
+    let mut a = [1, 2, 3];
+    let j = 1;
+    a[j] = 7;
+    println!("{}", a[1]);
+}
+"#);
+    assert_eq!(stdout, "99\n10\n7\n");
+}
+
+#[test]
+fn test_inclusive_range_includes_end() {
+    let stdout = compile_and_run("inclusive_range", r#"
+fn main() {
+    let mut inclusive = 0;
+    for i in 0..=3 {
+        println!("{}", i);
+        inclusive = inclusive + 1;
+    }
+    let mut exclusive = 0;
+    for i in 0..3 {
+        exclusive = exclusive + 1;
+    }
+    println!("{}", inclusive);
+    println!("{}", exclusive);
+}
+"#);
+    assert_eq!(stdout, "0\n1\n2\n3\n4\n3\n");
+}
+
+#[test]
+fn test_block_expression_value_in_let() {
+    let stdout = compile_and_run("block_let", r#"
+fn side() -> i64 {
+    println!("side");
+    3
+}
+
+fn main() {
+    let x = { let a = 5; a * 2 };
+    println!("{}", x);
+    let y = { let b = 1; side() + b };
+    println!("{}", y);
+    let z = { if x > 5 { 100 } else { 200 } };
+    println!("{}", z);
+}
+"#);
+    assert_eq!(stdout, "10\nside\n4\n100\n");
+}
+
+#[test]
+fn test_derive_copy_struct_is_not_moved() {
+    let stdout = compile_and_run("derive_copy", r#"
+#[derive(Copy, Clone)]
+struct Point {
+    x: i64,
+    y: i64,
+}
+
+fn main() {
+    let a = Point { x: 1, y: 2 };
+    let b = a;
+    let c = a;
+    println!("{}", b.x + c.y + a.x);
+}
+"#);
+    assert_eq!(stdout, "4\n");
+
+    let dir = scratch_dir("non_copy_moved");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+struct Point {
+    x: i64,
+}
+
+fn main() {
+    let a = Point { x: 1 };
+    let b = a;
+    let c = a;
+    println!("{}", b.x + c.x);
+}
+"#).expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"));
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(!result.success);
+    assert!(result.errors.iter().any(|e| e.message.contains("Value a used after move")), "{:?}", result.errors);
+}
+
+#[test]
+fn test_unclosed_brace_points_at_opening_delimiter() {
+    let dir = scratch_dir("unclosed_brace");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, "fn helper() -> i64 {\n    1\n}\n\nfn main() {\n    let x = helper();\n    if x > 0 {\n        println!(\"{}\", x);\n    }\n")
+        .expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"));
+
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(!result.success);
+    assert_eq!(result.errors.len(), 1, "{:?}", result.errors);
+    assert_eq!(result.errors[0].message, "unclosed delimiter `{` opened here");
+    assert_eq!(result.errors[0].line, Some(5));
+    assert_eq!(result.errors[0].column, Some(11));
+}
+
+#[test]
+fn test_intermediates_removed_unless_kept() {
+    let dir = scratch_dir("intermediates");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, "fn main() {\n    println!(\"{}\", 1);\n}\n").expect("failed to write main.rs");
+    let temp_dir = dir.join("tmp");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .set_temp_dir(&temp_dir);
+
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+    assert!(dir.join("prog").exists());
+    assert!(!dir.join("prog.s").exists());
+    assert!(!dir.join("prog.o").exists());
+    assert_eq!(std::fs::read_dir(&temp_dir).expect("temp dir should exist").count(), 0);
+
+    let result = compile_files(&config.keep_intermediates(true)).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+    assert!(dir.join("prog.s").exists());
+    assert!(result.output_files.contains(&dir.join("prog.s")));
+}
+
+#[test]
+fn test_failed_assembly_keeps_the_listing_its_errors_point_at() {
+    let dir = scratch_dir("intermediates_failed");
+    let main_rs = dir.join("main.rs");
+    // Clashes with the runtime's own `gaia_cmp` label
+    std::fs::write(&main_rs, "fn gaia_cmp() -> i64 {\n    1\n}\n\nfn main() {\n    println!(\"{}\", gaia_cmp());\n}\n")
+        .expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .set_temp_dir(dir.join("tmp"));
+
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(!result.success);
+    let listing = result.errors[0].file.as_ref().expect("assembler errors point into the listing");
+    assert!(listing.exists(), "{} was removed", listing.display());
+}
+
+#[test]
+fn test_option_and_then_or_else() {
+    let stdout = compile_and_run("and_then", r#"
+fn main() {
+    match Some(5).and_then(|x| Some(x + 1)) {
+        Some(v) => println!("Some({})", v),
+        None => println!("None"),
+    }
+    let none: Option<i64> = None;
+    match none.and_then(|x| Some(x + 1)) {
+        Some(v) => println!("Some({})", v),
+        None => println!("None"),
+    }
+    match none.or_else(|| Some(42)) {
+        Some(v) => println!("Some({})", v),
+        None => println!("None"),
+    }
+    let step = 10;
+    match Some(1).and_then(|x| Some(x * step)).and_then(|y| Some(y + 1)) {
+        Some(v) => println!("Some({})", v),
+        None => println!("None"),
+    }
+}
+"#);
+    assert_eq!(stdout, "Some(6)\nNone\nSome(42)\nSome(11)\n");
+}
+
+#[test]
+fn test_inherent_method_shadows_trait_method() {
+    let stdout = compile_and_run("inherent_shadows_trait", r#"
+struct Counter { count: i64 }
+
+trait Describe {
+    fn foo(&self) -> i64;
+}
+
+impl Describe for Counter {
+    fn foo(&self) -> i64 { 100 }
+}
+
+impl Counter {
+    fn foo(&self) -> i64 { self.count }
+}
+
+fn main() {
+    let c = Counter { count: 7 };
+    println!("{}", c.foo());
+}
+"#);
+    assert_eq!(stdout, "7\n");
+}
+
+#[test]
+fn test_method_from_two_traits_is_ambiguous() {
+    let errors = compile_errors("ambiguous_trait_method", r#"
+struct Counter { count: i64 }
+
+trait First {
+    fn foo(&self) -> i64;
+}
+
+trait Second {
+    fn foo(&self) -> i64;
+}
+
+impl First for Counter {
+    fn foo(&self) -> i64 { 1 }
+}
+
+impl Second for Counter {
+    fn foo(&self) -> i64 { 2 }
+}
+
+fn main() {
+    let c = Counter { count: 0 };
+    println!("{}", c.foo());
+}
+"#);
+    assert!(
+        errors.iter().any(|e| e.message.contains("multiple applicable items in scope")
+            && e.message.contains("`First` and `Second`")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_shift_overflow_panics_with_overflow_checks() {
+    let dir = scratch_dir("shift_overflow");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn shift(value: i64, amount: i64) -> i64 {
+    value << amount
+}
+
+fn main() {
+    println!("{}", shift(1, 3));
+    println!("{}", 1i64 << 64);
+}
+"#).expect("failed to write main.rs");
+
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .with_overflow_checks(true);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+
+    let output = Command::new(dir.join("prog")).output().expect("failed to run compiled program");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "8\npanicked at: attempt to shift left with overflow\n");
+    assert_eq!(output.status.code(), Some(101));
+}
+
+#[test]
+fn test_shift_overflow_checks_use_the_operand_width() {
+    let dir = scratch_dir("shift_overflow_i32");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn shift(value: i32, amount: i32) -> i32 {
+    value >> amount
+}
+
+fn main() {
+    let big: i32 = 1 << 30;
+    println!("{}", shift(big, 30));
+    println!("{}", shift(big, 32));
+}
+"#).expect("failed to write main.rs");
+
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .with_overflow_checks(true);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+
+    let output = Command::new(dir.join("prog")).output().expect("failed to run compiled program");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "1\npanicked at: attempt to shift right with overflow\n");
+    assert_eq!(output.status.code(), Some(101));
+}
+
+#[test]
+fn test_trait_associated_const_through_generic_function() {
+    let stdout = compile_and_run("assoc_const", r#"
+struct Meters { value: i64 }
+struct Feet { value: i64 }
+
+trait Zero {
+    const ZERO: i64;
+    const STEP: i64 = 1;
+}
+
+impl Zero for Meters {
+    const ZERO: i64 = 10;
+}
+
+impl Zero for Feet {
+    const ZERO: i64 = 20;
+    const STEP: i64 = 5;
+}
+
+fn zero_of<T: Zero>(_value: T) -> i64 {
+    T::ZERO
+}
+
+fn next_after_zero<T: Zero>(_value: T) -> i64 {
+    T::ZERO + T::STEP
+}
+
+fn main() {
+    println!("{}", zero_of(Meters { value: 1 }));
+    println!("{}", zero_of(Feet { value: 2 }));
+    println!("{}", next_after_zero(Meters { value: 3 }));
+    println!("{}", next_after_zero::<Feet>(Feet { value: 4 }));
+    println!("{}", Feet::ZERO);
+}
+"#);
+    assert_eq!(stdout, "10\n20\n11\n25\n20\n");
+}
+
+#[test]
+fn test_trait_associated_const_through_references_and_primitives() {
+    let stdout = compile_and_run("assoc_const_refs", r#"
+struct Meters { value: i64 }
+
+trait Zero {
+    const ZERO: i64;
+    const STEP: i64 = 1;
+}
+
+impl Zero for Meters {
+    const ZERO: i64 = 10;
+}
+
+impl Zero for i64 {
+    const ZERO: i64 = 0;
+    const STEP: i64 = 3;
+}
+
+fn zero_through_ref<T: Zero>(_value: &T) -> i64 {
+    T::ZERO
+}
+
+fn next_after_zero<T: Zero>(_value: T) -> i64 {
+    T::ZERO + T::STEP
+}
+
+fn main() {
+    let m = Meters { value: 1 };
+    println!("{}", zero_through_ref(&m));
+    println!("{}", next_after_zero(7));
+    let n: i64 = 4;
+    println!("{}", zero_through_ref(&n));
+    println!("{}", next_after_zero(n));
+    println!("{}", i64::STEP);
+}
+"#);
+    assert_eq!(stdout, "10\n3\n0\n3\n3\n");
+}
+
+#[test]
+fn test_clone_deep_copies_vecs_and_derived_structs() {
+    let stdout = compile_and_run("clone_deep_copy", r#"
+#[derive(Clone)]
+struct Bag {
+    id: i64,
+    items: Vec<i32>,
+}
+
+fn fresh(id: i64) -> Bag {
+    println!("made {}", id);
+    Bag { id: id, items: vec![4] }
+}
+
+fn main() {
+    let mut v = Vec::new();
+    v.push(1);
+    v.push(2);
+    let mut w = v.clone();
+    w.push(3);
+    w[0] = 100;
+    println!("{} {}", v.len(), v[0]);
+    println!("{} {} {}", w.len(), w[0], w[2]);
+
+    let a = Bag { id: 7, items: vec![1, 2] };
+    let mut b = a.clone();
+    b.items.push(5);
+    println!("{} {} {}", b.id, a.items.len(), b.items.len());
+
+    let c = fresh(9).clone();
+    println!("{}", c.id);
+}
+"#);
+    assert_eq!(stdout, "2 1\n3 100 3\n7 2 3\nmade 9\n9\n");
+}
+
+#[test]
+fn test_macro_rules_max_with_fixed_and_variadic_arms() {
+    let stdout = compile_and_run("macro_rules_max", r#"
+macro_rules! max {
+    ($x:expr) => { $x };
+    ($x:expr, $y:expr) => { if $x > $y { $x } else { $y } };
+    ($x:expr, $($rest:expr),+) => { max!($x, max!($($rest),+)) };
+}
+
+fn main() {
+    let a = 3;
+    println!("{}", max!(a, 9));
+    println!("{}", max!(12, a * 2));
+    println!("{}", max!(4, 1 + 10, 8, 2));
+    println!("{}", max!(7));
+}
+"#);
+    assert_eq!(stdout, "9\n12\n11\n7\n");
+}
+
+#[test]
+fn test_nonexistent_field_is_reported_at_the_access() {
+    let errors = compile_errors("nonexistent_field", r#"
+struct Point {
+    x: i64,
+    y: i64,
+}
+
+fn main() {
+    let p = Point { x: 1, y: 2 };
+    println!("{}", p.nonexistent);
+}
+"#);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "no field `nonexistent` on type `Point`");
+    assert_eq!(errors[0].line, Some(9));
+    assert_eq!(errors[0].column, Some(22));
+}
+
+#[test]
+fn test_nonexistent_field_is_reported_at_the_failing_access() {
+    let errors = compile_errors("nonexistent_field_named_elsewhere", r#"
+struct Point {
+    x: i64,
+    y: i64,
+}
+
+struct Point3 {
+    x: i64,
+    y: i64,
+    z: i64,
+}
+
+fn main() {
+    let q = Point3 { x: 1, y: 2, z: 3 };
+    println!("{}", q.z);
+    let p = Point { x: 1, y: 2 };
+    println!("{}", p.z);
+}
+"#);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "no field `z` on type `Point`");
+    assert_eq!(errors[0].line, Some(17));
+    assert_eq!(errors[0].column, Some(22));
+}
+
+#[test]
+fn test_enum_variants_share_the_largest_payload_layout() {
+    let stdout = compile_and_run("enum_payload_layout", r#"
+enum E {
+    A(i64),
+    B(i64, i64),
+    C,
+}
+
+fn payload_sum(e: E) -> i64 {
+    match e {
+        E::A(x) => x,
+        E::B(x, y) => x * 10 + y,
+        E::C => -1,
+    }
+}
+
+fn main() {
+    let a = E::A(1);
+    let b = E::B(2, 3);
+    println!("{} {}", payload_sum(a), payload_sum(b));
+    println!("{}", payload_sum(E::C));
+
+    let mut e = E::A(4);
+    e = E::B(5, 6);
+    match e {
+        E::A(x) => println!("A {}", x),
+        E::B(x, y) => println!("B {} {}", x, y),
+        E::C => println!("C"),
+    }
+}
+"#);
+    assert_eq!(stdout, "1 23\n-1\nB 5 6\n");
+}
+
+#[test]
+fn test_panic_prints_backtrace_of_nested_calls() {
+    let dir = scratch_dir("panic_backtrace");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn check(n: i64) -> i64 {
+    if n > 2 {
+        panic!("too big");
+    }
+    n * 2
+}
+
+fn outer(n: i64) -> i64 {
+    let doubled = check(n);
+    doubled + 1
+}
+
+fn main() {
+    println!("{}", outer(1));
+    println!("{}", outer(5));
+}
+"#).expect("failed to write main.rs");
+
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .backtrace(true);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+
+    let output = Command::new(dir.join("prog")).output().expect("failed to run compiled program");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "3\npanicked at: too big\nstack backtrace:\n  0: check\n  1: outer\n  2: main\n");
+    assert_eq!(output.status.code(), Some(101));
+}
+
+#[test]
+fn test_iter_position_and_find_stop_at_first_match() {
+    let output = compile_and_run("iter_search", r#"
+struct Point {
+    x: i64,
+    y: i64,
+}
+
+fn main() {
+    let mut points: Vec<Point> = Vec::new();
+    points.push(Point { x: 1, y: 10 });
+    points.push(Point { x: 3, y: 30 });
+    match points.iter().position(|p| p.y == 30) {
+        Some(i) => println!("point {}", i),
+        None => println!("missing"),
+    }
+    let v = vec![1, 2, 3, 4];
+    match v.iter().position(|x| *x == 3) {
+        Some(i) => println!("at {}", i),
+        None => println!("missing"),
+    }
+    match v.iter().position(|x| *x == 9) {
+        Some(i) => println!("at {}", i),
+        None => println!("missing"),
+    }
+    let found = v.iter().find(|x| {
+        println!("visit {}", **x);
+        **x > 1
+    });
+    match found {
+        Some(x) => println!("found {}", x),
+        None => println!("missing"),
+    }
+}
+"#);
+    assert_eq!(output, "point 1\nat 2\nmissing\nvisit 1\nvisit 2\nfound 2\n");
+}
+
+#[test]
+fn test_by_value_self_method_cannot_be_called_on_trait_object() {
+    let errors = compile_errors("dyn_by_value_self", r#"
+trait Shape {
+    fn area(&self) -> i64;
+    fn consume(self) -> i64;
+}
+
+struct Square { side: i64 }
+
+impl Shape for Square {
+    fn area(&self) -> i64 { self.side * self.side }
+    fn consume(self) -> i64 { self.side }
+}
+
+fn finish(shape: &dyn Shape) -> i64 {
+    shape.consume()
+}
+
+fn main() {
+    let sq = Square { side: 3 };
+    println!("{}", sq.consume());
+}
+"#);
+    assert!(
+        errors.iter().any(|e| e.message.contains("the `consume` method cannot be invoked on a trait object")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_stack_protector_guards_functions_with_arrays() {
+    let dir = scratch_dir("stack_protector");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn sum3() -> i64 {
+    let values = [1, 2, 3];
+    values[0] + values[1] + values[2]
+}
+
+fn double(x: i64) -> i64 {
+    x * 2
+}
+
+fn main() {
+    println!("{}", sum3());
+    println!("{}", double(4));
+}
+"#).expect("failed to write main.rs");
+
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .keep_intermediates(true)
+        .stack_protector(true);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+
+    let asm = std::fs::read_to_string(dir.join("prog.s")).expect("failed to read prog.s");
+    let function = |name: &str| {
+        asm.split(&format!("\n{}:\n", name))
+            .nth(1)
+            .and_then(|body| body.split("\n    ret").next())
+            .unwrap_or_else(|| panic!("no function {} in:\n{}", name, asm))
+            .to_string()
+    };
+    let sum3 = function("sum3");
+    assert!(sum3.contains("lea r11, [rip + __stack_chk_guard]"), "{}", sum3);
+    assert!(sum3.contains("mov qword ptr [rbp - 8], r11"), "{}", sum3);
+    assert!(sum3.contains("cmp qword ptr [rbp - 8], r11"), "{}", sum3);
+    assert!(sum3.contains("jne sum3_stack_chk_fail"), "{}", sum3);
+    assert!(asm.contains("sum3_stack_chk_fail:\n    call gaia_stack_chk_fail"));
+    assert!(!function("double").contains("__stack_chk_guard"));
+
+    let output = Command::new(dir.join("prog")).output().expect("failed to run compiled program");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "6\n8\n");
+}
+
+#[test]
+fn test_iter_mut_writes_through_to_the_vec() {
+    let output = compile_and_run("iter_mut", r#"
+fn main() {
+    let mut v = vec![1, 2, 3, 4];
+    for x in v.iter_mut() {
+        *x *= 2;
+    }
+    println!("{} {} {} {}", v[0], v[1], v[2], v[3]);
+    for x in v.iter_mut() {
+        *x = *x + 1;
+    }
+    let mut total = 0;
+    for x in v.iter() {
+        total += *x;
+    }
+    println!("{} {}", v[3], total);
+}
+"#);
+    assert_eq!(output, "2 4 6 8\n9 24\n");
+}
+
+#[test]
+fn test_static_libc_linkage_produces_a_self_contained_binary() {
+    use gaiarusted::codegen::backend::assembler::Assembler;
+    use gaiarusted::LibcLinkage;
+
+    if !Path::new("/usr/lib/x86_64-linux-gnu/libc.a").exists() {
+        eprintln!("skipping: no static libc installed");
+        return;
+    }
+
+    let dir = scratch_dir("static_libc");
+    let object = dir.join("prog.o");
+    let args = |linkage| -> Vec<String> {
+        let command = Assembler::new(&dir).with_libc_linkage(linkage).link_command(&[&object], &dir.join("prog"), &[]);
+        command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect()
+    };
+    let static_args = args(LibcLinkage::Static);
+    assert!(static_args.contains(&"-static".to_string()), "{:?}", static_args);
+    assert!(!static_args.contains(&"-dynamic-linker".to_string()), "{:?}", static_args);
+    assert!(!args(LibcLinkage::Dynamic).contains(&"-static".to_string()));
+
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, "fn main() {\n    let v = vec![4, 5, 6];\n    println!(\"{} {}\", v.len(), v[1]);\n}\n")
+        .expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add main.rs")
+        .set_output(dir.join("prog"))
+        .set_libc_linkage(LibcLinkage::Static);
+    let result = compile_files(&config).expect("compilation failed");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+
+    let binary = std::fs::read(dir.join("prog")).expect("no executable");
+    // No PT_INTERP program header, so the kernel runs it without ld.so
+    let field = |at: usize, len: usize| binary[at..at + len].iter().rev().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+    let (phoff, phentsize, phnum) = (field(0x20, 8), field(0x36, 2), field(0x38, 2));
+    assert!((0..phnum).all(|i| field(phoff + i * phentsize, 4) != 3), "binary still asks for the dynamic loader");
+    let output = Command::new(dir.join("prog")).output().expect("failed to run compiled program");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3 5\n");
+}
+
+#[test]
+fn test_if_with_constant_condition_takes_the_folded_branch() {
+    let output = compile_and_run("const_if", r#"
+fn f() -> i32 {
+    println!("f called");
+    1
+}
+
+fn main() {
+    let x = if false { f() } else { 7 };
+    let y = if 3 * 2 == 6 { let z = x; z * 2 } else { f() };
+    println!("{} {}", x, y);
+}
+"#);
+    assert_eq!(output, "7 14\n");
+}
+
+#[test]
+fn test_extern_block_declares_c_functions() {
+    let output = compile_and_run("extern_block", r#"
+extern "C" {
+    fn abs(x: i32) -> i32;
+    fn labs(x: i64) -> i64;
+}
+
+fn main() {
+    let a = unsafe { abs(-5) };
+    let b = unsafe { labs(-4000000000) };
+    println!("{} {}", a, b);
+}
+"#);
+    assert_eq!(output, "5 4000000000\n");
+
+    let errors = compile_errors("extern_block_signature", r#"
+extern "C" {
+    fn abs(x: i32) -> i32;
+}
+
+fn main() {
+    let flag: bool = unsafe { abs(-5) };
+}
+"#);
+    assert!(errors.iter().any(|e| e.message.contains("mismatch")), "{:?}", errors);
+}
+
+#[test]
+fn test_instruction_counts_are_reported_per_function() {
+    let result = compile_in(&scratch_dir("instruction_counts"), r#"
+fn one() -> i32 {
+    1
+}
+
+fn sum_to(n: i32) -> i32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < n {
+        total = total + i;
+        i = i + 1;
+    }
+    total
+}
+
+fn main() {
+    println!("{} {}", one(), sum_to(4));
+}
+"#);
+    let counts = &result.stats.instructions_per_function;
+    let (one, sum_to) = (counts["one"], counts["sum_to"]);
+    assert!(one > 0);
+    assert!(sum_to > one, "loop: {}, trivial: {}", sum_to, one);
+    assert!(counts.contains_key("main"), "{:?}", counts);
+
+    let largest = result.stats.largest_functions();
+    let position = |name: &str| largest.iter().position(|(f, _)| *f == name).unwrap();
+    assert!(position("sum_to") < position("one"), "{:?}", largest);
+}
+
+#[test]
+fn test_at_binding_patterns_bind_the_matched_value() {
+    let output = compile_and_run("at_binding", r#"
+fn double_small(x: i32) -> i32 {
+    match x {
+        n @ 1..=10 => n * 2,
+        _ => 0,
+    }
+}
+
+fn main() {
+    println!("{} {} {} {}", double_small(0), double_small(1), double_small(7), double_small(10));
+    println!("{} {}", double_small(11), double_small(-3));
+    let r = match 40 + 2 {
+        small @ 0..10 => small,
+        big @ _ => big + 1,
+    };
+    println!("{}", r);
+}
+"#);
+    assert_eq!(output, "0 2 14 20\n0 0\n43\n");
+}
+
+#[test]
+fn test_range_patterns_compare_both_bounds() {
+    let output = compile_and_run("range_patterns", r#"
+fn kind(x: i32) -> &'static str {
+    match x {
+        -9..=-1 => "negative digit",
+        0..=9 => "digit",
+        10..100 => "two digits",
+        _ => "other",
+    }
+}
+
+fn letter(c: char) -> i32 {
+    match c {
+        'a'..='z' => 1,
+        'A'..='Z' => 2,
+        _ => 0,
+    }
+}
+
+fn main() {
+    println!("{} {}", kind(5), kind(42));
+    println!("{} {} {} {}", kind(9), kind(-3), kind(99), kind(100));
+    println!("{} {} {}", letter('q'), letter('Q'), letter('!'));
+}
+"#);
+    assert_eq!(output, "digit two digits\ndigit negative digit two digits other\n1 2 0\n");
+}
+
+#[test]
+fn test_custom_builtin_calls_the_host_symbol() {
+    use gaiarusted::lowering::HirType;
+    use gaiarusted::CustomBuiltin;
+
+    let dir = scratch_dir("custom_builtin");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, "fn main() {\n    host_log(42);\n    let n: i64 = host_clock();\n    host_log(n);\n}\n")
+        .expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add main.rs")
+        .set_output(dir.join("prog"))
+        .set_output_format(OutputFormat::Assembly)
+        .register_builtin(CustomBuiltin::new("host_log", vec![HirType::Int64], HirType::Tuple(Vec::new())))
+        .register_builtin(CustomBuiltin::new("host_clock", Vec::new(), HirType::Int64).calling("gaia_host_clock_v2"));
+    let result = compile_files(&config).expect("compilation failed");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+
+    let asm = std::fs::read_to_string(dir.join("prog.s")).expect("no assembly");
+    assert_eq!(asm.matches("call host_log\n").count(), 2, "{}", asm);
+    assert!(asm.contains("call gaia_host_clock_v2\n"), "{}", asm);
+    assert!(!asm.contains("call host_clock\n"));
+}
+
+#[test]
+fn test_cmp_min_max_on_derived_ord_structs() {
+    let stdout = compile_and_run("cmp_min_max", r#"
+use std::cmp;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    major: i32,
+    minor: i32,
+}
+
+fn main() {
+    let a = Version { major: 1, minor: 9 };
+    let b = Version { major: 2, minor: 0 };
+    let c = Version { major: 1, minor: 3 };
+    let lo = std::cmp::min(a, b);
+    let hi = cmp::max(a, b);
+    let first = min(a, c);
+    let last = std::cmp::max(c, a);
+    println!("{}.{} {}.{}", lo.major, lo.minor, hi.major, hi.minor);
+    println!("{}.{} {}.{}", first.major, first.minor, last.major, last.minor);
+    println!("{} {}", std::cmp::min(3, 8), std::cmp::max(3, 8));
+}
+"#);
+    assert_eq!(stdout, "1.9 2.0\n1.3 1.9\n3 8\n");
+}
+
+#[test]
+fn test_cmp_min_max_on_equal_values_pick_like_std() {
+    let stdout = compile_and_run("cmp_min_max_ties", r#"
+use std::cmp::{self, Ordering};
+
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+struct Score {
+    points: i64,
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (mine, theirs) = (self.points / 10, other.points / 10);
+        if mine < theirs {
+            Ordering::Less
+        } else if mine > theirs {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+}
+
+fn main() {
+    let a = Score { points: 41 };
+    let b = Score { points: 47 };
+    let c = Score { points: 12 };
+    let (lo, hi) = (cmp::min(a, b), cmp::max(a, b));
+    println!("{} {}", lo.points, hi.points);
+    let (lo, hi) = (cmp::min(b, a), cmp::max(b, a));
+    println!("{} {}", lo.points, hi.points);
+    let (lo, hi) = (cmp::min(a, c), cmp::max(c, a));
+    println!("{} {}", lo.points, hi.points);
+    println!("{} {}", cmp::min(3, 3), cmp::max(8, 3));
+}
+"#);
+    // `min` returns the first of two equal values and `max` the second
+    assert_eq!(stdout, "41 47\n47 41\n12 41\n3 8\n");
+}
+
+#[test]
+fn test_unterminated_string_error_reports_where_it_opened() {
+    let errors = compile_errors("unterminated_string", "fn main() {\n    println!(\"hi);\n}\n");
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].message.contains("unterminated string literal"), "{}", errors[0].message);
+    assert_eq!((errors[0].line, errors[0].column), (Some(2), Some(14)));
+}
+
+#[test]
+fn test_division_by_literal_zero_is_a_compile_error() {
+    let errors = compile_errors("divide_by_zero", r#"
+fn main() {
+    let x = 5 / 0;
+    println!("{}", x);
+}
+"#);
+    assert!(errors.iter().any(|e| e.message.contains("this operation will panic at runtime: attempt to divide by zero")));
+
+    let errors = compile_errors("remainder_by_zero", r#"
+fn main() {
+    let n = 7;
+    println!("{}", n % 0);
+}
+"#);
+    assert!(errors.iter().any(|e| e.message.contains("attempt to calculate the remainder with a divisor of zero")));
+
+    let stdout = compile_and_run("divide_by_variable", r#"
+fn main() {
+    let n = 2;
+    let x = 5 / n;
+    let _infinite = 1.0 / 0.0;
+    println!("{}", x);
+}
+"#);
+    assert_eq!(stdout, "2\n");
+}
+
+#[test]
+fn test_ssa_renaming_preserves_loop_and_branch_results() {
+    let dir = scratch_dir("ssa_renaming");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn main() {
+    let mut total = 0;
+    let mut i = 0;
+    while i < 5 {
+        if i % 2 == 0 {
+            total = total + i;
+        } else {
+            total = total - 1;
+        }
+        i = i + 1;
+    }
+    let label = if total > 3 { 1 } else { 2 };
+    println!("{} {}", total, label);
+}
+"#).expect("failed to write main.rs");
+
+    for opt_level in [0, 3] {
+        let config = CompilationConfig::new()
+            .add_source_file(&main_rs)
+            .expect("failed to add source file")
+            .set_output(dir.join("prog"))
+            .set_opt_level(opt_level)
+            .ssa_renaming(true);
+        let result = compile_files(&config).expect("compilation should run");
+        assert!(result.success, "{:?}", result.errors);
+        assert_eq!(run_in(&dir), "4 1\n");
+    }
+}
+
+#[test]
+fn test_vec_sort_orders_runtime_vec_in_place() {
+    let stdout = compile_and_run("vec_sort", r#"
+fn main() {
+    let mut v = Vec::new();
+    v.push(3);
+    v.push(1);
+    v.push(2);
+    v.sort();
+    println!("{} {} {}", v[0], v[1], v[2]);
+
+    let mut w = vec![5, -4, 9, 0, 5, -10];
+    w.sort_unstable();
+    let mut i = 0;
+    while i < w.len() {
+        print!("{} ", w[i]);
+        i += 1;
+    }
+    println!("");
+}
+"#);
+    assert_eq!(stdout, "1 2 3\n-10 -4 0 5 5 9 \n");
+}
+
+#[test]
+fn test_vec_of_ordered_structs_sorts_by_cmp() {
+    let stdout = compile_and_run("vec_sort_structs", r#"
+use std::cmp::Ordering;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    major: u64,
+    minor: i64,
+}
+
+struct Task {
+    id: i64,
+    priority: i64,
+}
+
+impl Ord for Task {
+    fn cmp(&self, other: &Task) -> Ordering {
+        if self.priority < other.priority {
+            Ordering::Less
+        } else if self.priority > other.priority {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+}
+
+fn newest() -> Version {
+    println!("built");
+    Version { major: 2, minor: 1 }
+}
+
+fn main() {
+    let mut v: Vec<Version> = Vec::new();
+    v.push(Version { major: 18446744073709551615, minor: 0 });
+    v.push(Version { major: 1, minor: 5 });
+    v.push(Version { major: 2, minor: 1 });
+    v.push(Version { major: 1, minor: -2 });
+    v.sort();
+    let mut i = 0;
+    while i < v.len() {
+        print!("{} ", v[i].minor);
+        i += 1;
+    }
+    println!("");
+
+    let mut tasks: Vec<Task> = Vec::new();
+    tasks.push(Task { id: 1, priority: 2 });
+    tasks.push(Task { id: 2, priority: 9 });
+    tasks.push(Task { id: 3, priority: 2 });
+    tasks.sort();
+    println!("{} {} {}", tasks[0].id, tasks[1].id, tasks[2].id);
+
+    let latest = Version { major: 2, minor: 1 };
+    println!("{}", newest() == latest);
+}
+"#);
+    // `major` compares unsigned, ties keep their order, and `newest()` runs once
+    assert_eq!(stdout, "-2 5 1 0 \n1 3 2\nbuilt\n1\n");
+}
+
+#[test]
+fn test_tuple_struct_construction_and_field_access() {
+    let output = compile_and_run(
+        "tuple_struct",
+        r#"
+struct Point(i64, i64);
+struct Meters(pub i64);
+
+impl Meters {
+    fn double(&self) -> i64 {
+        self.0 * 2
+    }
+}
+
+fn main() {
+    let p = Point(3, 4);
+    let m = Meters(21);
+    println!("{} {}", p.0, p.1);
+    println!("{}", m.double());
+}
+"#,
+    );
+    assert_eq!(output, "3 4\n42\n");
+}
+
+#[test]
+fn test_repr_transparent_struct_is_passed_like_its_field() {
+    let dir = scratch_dir("repr_transparent");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(
+        &main_rs,
+        r#"
+#[repr(transparent)]
+struct Meters(i64);
+
+impl Meters {
+    fn bump(&mut self) {
+        self.0 = self.0 + 1;
+    }
+}
+
+fn plain(x: i64) -> i64 {
+    x
+}
+
+fn unwrap(m: Meters) -> i64 {
+    m.0
+}
+
+fn by_ref(m: &Meters) -> i64 {
+    m.0
+}
+
+fn main() {
+    let mut m = Meters(41);
+    m.bump();
+    println!("{} {} {}", plain(7), unwrap(Meters(8)), by_ref(&m));
+}
+"#,
+    )
+    .expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .keep_intermediates(true);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+    assert_eq!(run_in(&dir), "7 8 42\n");
+
+    // Same body as the plain i64 function: the value arrives in rdi, not behind a
+    // pointer, so identical code folding emits `unwrap` as an alias of `plain`
+    let asm = std::fs::read_to_string(dir.join("prog.s")).expect("failed to read prog.s");
+    let body = |name: &str| {
+        asm.lines()
+            .skip_while(|line| *line != format!("{}:", name))
+            .take_while(|line| line.trim() != "ret")
+            .map(|line| line.replace(name, "f"))
+            .collect::<Vec<_>>()
+    };
+    assert!(body("plain").iter().any(|line| line.contains("rdi")), "{:?}", body("plain"));
+    assert!(asm.contains(".set unwrap, plain\n"), "{}", asm);
+}
+
+#[test]
+fn test_repr_transparent_needs_exactly_one_field() {
+    let errors = compile_errors(
+        "repr_transparent_two_fields",
+        r#"
+#[repr(transparent)]
+struct Pair(i64, i64);
+
+fn main() {}
+"#,
+    );
+    assert!(
+        errors.iter().any(|e| e.message.contains("transparent struct `Pair` needs exactly one field, but has 2")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_break_and_continue_inside_if_target_the_enclosing_loop() {
+    let output = compile_and_run(
+        "break_continue_in_if",
+        r#"
+fn main() {
+    let mut i = 0;
+    let mut seen = 0;
+    let stop = true;
+    while i < 10 {
+        i = i + 1;
+        if i % 2 == 0 {
+            continue;
+        }
+        if stop && i > 6 {
+            break;
+        }
+        seen = seen + i;
+    }
+    println!("after the loop: {} {}", i, seen);
+
+    let mut total = 0;
+    for n in 0..10 {
+        if n == 3 {
+            continue;
+        } else if n == 7 {
+            break;
+        }
+        total = total + n;
+    }
+    println!("{}", total);
+}
+"#,
+    );
+    assert_eq!(output, "after the loop: 7 9\n18\n");
+}
+
+#[test]
+fn test_integer_literals_take_their_type_from_context() {
+    let output = compile_and_run(
+        "literal_inference",
+        r#"
+fn main() {
+    let x: u8 = 200;
+    let y: i8 = -5;
+    let big: i64 = 5000000000;
+    let z = x + 50;
+    println!("{} {} {} {}", x, y, big, z);
+}
+"#,
+    );
+    assert_eq!(output, "200 -5 5000000000 250\n");
+}
+
+#[test]
+fn test_integer_literal_out_of_range_is_an_error() {
+    let errors = compile_errors(
+        "literal_out_of_range",
+        r#"
+fn main() {
+    let x: u8 = 300;
+    println!("{}", x);
+}
+"#,
+    );
+    assert!(
+        errors.iter().any(|e| e.message.contains("literal out of range for `u8`")
+            && e.message.contains("the literal `300` does not fit into the type `u8` whose range is `0..=255`")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_trait_bounded_generic_dispatches_to_each_impl() {
+    let output = compile_and_run(
+        "trait_bounded_generic",
+        r#"
+trait Shape {
+    fn area(&self) -> i64;
+}
+
+struct Square {
+    side: i64,
+}
+
+struct Circle {
+    radius: i64,
+}
+
+impl Shape for Square {
+    fn area(&self) -> i64 {
+        self.side * self.side
+    }
+}
+
+impl Shape for Circle {
+    fn area(&self) -> i64 {
+        3 * self.radius * self.radius
+    }
+}
+
+fn print_area<T: Shape>(s: T) {
+    println!("{}", s.area());
+}
+
+fn doubled<S>(s: S) -> i64 where S: Shape {
+    s.area() * 2
+}
+
+fn main() {
+    print_area(Square { side: 3 });
+    print_area(Circle { radius: 2 });
+    let sq = Square { side: 5 };
+    print_area(sq);
+    let c = Circle { radius: 1 };
+    println!("{}", doubled(c));
+}
+"#,
+    );
+    assert_eq!(output, "9\n12\n25\n6\n");
+}
+
+#[test]
+fn test_trait_bounded_generic_takes_references_and_primitives() {
+    let output = compile_and_run(
+        "trait_bounded_generic_refs",
+        r#"
+trait Describe {
+    fn describe(&self) -> i64;
+}
+
+trait Scale {
+    fn scale(&self, by: i64) -> i64;
+}
+
+struct Point {
+    x: i64,
+}
+
+impl Describe for Point {
+    fn describe(&self) -> i64 {
+        self.x + 100
+    }
+}
+
+impl Scale for i64 {
+    fn scale(&self, by: i64) -> i64 {
+        *self * by
+    }
+}
+
+fn show<T: Describe>(item: &T) -> i64 {
+    item.describe()
+}
+
+fn twice<T: Scale>(value: T, by: i64) -> i64 {
+    value.scale(by) * 2
+}
+
+// Named the way an instance of `show` for `Point` used to be
+fn show_Point(_item: &Point) -> i64 {
+    1
+}
+
+fn main() {
+    let a = Point { x: 5 };
+    println!("{}", show(&a));
+    println!("{}", show_Point(&a));
+    println!("{}", twice(7, 5));
+    let n: i64 = 3;
+    println!("{}", twice(n, 4));
+}
+"#,
+    );
+    assert_eq!(output, "105\n1\n70\n24\n");
+}
+
+#[test]
+fn test_into_calls_the_from_impl_of_the_annotated_type() {
+    let output = compile_and_run(
+        "from_into",
+        r#"
+struct Celsius {
+    degrees: i64,
+}
+
+impl From<i64> for Celsius {
+    fn from(value: i64) -> Celsius {
+        Celsius { degrees: value }
+    }
+}
+
+fn main() {
+    let c: Celsius = 100i64.into();
+    println!("{}", c.degrees);
+    let d = Celsius::from(-40);
+    println!("{}", d.degrees);
+}
+"#,
+    );
+    assert_eq!(output, "100\n-40\n");
+}
+
+#[test]
+fn test_into_without_a_from_impl_is_an_error() {
+    let errors = compile_errors(
+        "into_without_from",
+        r#"
+struct Kelvin {
+    k: i64,
+}
+
+fn main() {
+    let k: Kelvin = 5.into();
+    println!("{}", k.k);
+}
+"#,
+    );
+    assert!(
+        errors.iter().any(|e| e.message.contains("the trait bound `Kelvin: From<")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_returning_a_reference_to_a_local_is_an_error() {
+    let errors = compile_errors(
+        "return_local_ref",
+        r#"
+fn f() -> &i64 {
+    let x = 5;
+    &x
+}
+
+fn main() {
+    println!("{}", f());
+}
+"#,
+    );
+    assert!(
+        errors.iter().any(|e| e.message.contains("cannot return reference to local variable `x`")
+            && e.message.contains("returns a reference to data owned by the current function")
+            && e.line == Some(2)),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_returning_a_reference_to_a_parameter_is_allowed() {
+    let output = compile_and_run(
+        "return_param_ref",
+        r#"
+fn pick(a: &i64) -> &i64 {
+    a
+}
+
+fn first(v: &Vec<i64>) -> &i64 {
+    &v[0]
+}
+
+fn main() {
+    let n = 4;
+    let r = pick(&n);
+    println!("{}", *r + 1);
+}
+"#,
+    );
+    assert_eq!(output, "5\n");
+}
+
+#[test]
+fn test_no_std_prints_through_the_write_syscall() {
+    let dir = scratch_dir("no_std_write");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn main() {
+    println!("hello from no_std");
+    let answer = 40 + 2;
+    println!("{}", answer);
+    print!("done");
+}
+"#).expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .set_output_format(OutputFormat::Assembly)
+        .set_no_std(true);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+
+    let asm = std::fs::read_to_string(dir.join("prog.s")).expect("failed to read prog.s");
+    assert!(!asm.contains("printf"), "{}", asm);
+
+    // A freestanding entry point: call main, then exit without libc
+    std::fs::write(dir.join("start.s"), ".intel_syntax noprefix\n.globl _start\n_start:\n    and rsp, -16\n    call main\n    mov edi, 0\n    mov eax, 60\n    syscall\n")
+        .expect("failed to write start.s");
+    for name in ["prog", "start"] {
+        let status = Command::new("as")
+            .arg(dir.join(format!("{}.s", name)))
+            .arg("-o")
+            .arg(dir.join(format!("{}.o", name)))
+            .status()
+            .expect("failed to run as");
+        assert!(status.success());
+    }
+    let status = Command::new("ld")
+        .arg(dir.join("start.o"))
+        .arg(dir.join("prog.o"))
+        .arg("-o")
+        .arg(dir.join("prog"))
+        .status()
+        .expect("failed to run ld");
+    assert!(status.success());
+    assert_eq!(run_in(&dir), "hello from no_std\n42\ndone");
+}
+
+#[test]
+fn test_identical_instantiations_are_folded_into_one_function() {
+    let dir = scratch_dir("identical_code_folding");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+trait Shape {
+    fn sides(&self) -> i64;
+}
+
+struct Square {
+    side: i64,
+}
+
+struct Circle {
+    radius: i64,
+}
+
+impl Shape for Square {
+    fn sides(&self) -> i64 {
+        4
+    }
+}
+
+impl Shape for Circle {
+    fn sides(&self) -> i64 {
+        0
+    }
+}
+
+fn doubled<T: Shape>(shape: T, n: i64) -> i64 {
+    n * 2
+}
+
+fn main() {
+    let square = Square { side: 3 };
+    let circle = Circle { radius: 5 };
+    println!("{}", doubled(square, 20));
+    println!("{}", doubled(circle, 4));
+}
+"#).expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .keep_intermediates(true);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+    assert_eq!(run_in(&dir), "40\n8\n");
+
+    let asm = std::fs::read_to_string(dir.join("prog.s")).expect("failed to read prog.s");
+    let definitions: Vec<&str> = asm.lines().filter(|line| line.starts_with("doubled.") && line.ends_with(':') && !line.contains("_bb")).collect();
+    assert_eq!(definitions.len(), 1, "{}", asm);
+    let kept = definitions[0].trim_end_matches(':');
+    let calls: Vec<&str> = asm.lines().filter(|line| line.trim().starts_with("call doubled.")).collect();
+    assert_eq!(calls.len(), 2, "{}", asm);
+    assert!(calls.iter().all(|call| call.trim() == format!("call {}", kept)), "{}", asm);
+}
+
+#[test]
+fn test_loop_runs_until_break() {
+    let stdout = compile_and_run("loop_until_break", r#"
+fn first_multiple(n: i64) -> i64 {
+    let mut k = 1;
+    loop {
+        if k % n == 0 {
+            return k;
+        }
+        k += 1;
+    }
+}
+
+fn main() {
+    let mut i = 0;
+    loop {
+        i += 1;
+        if i == 3 {
+            break;
+        }
+    }
+    println!("{}", i);
+    println!("{}", first_multiple(7));
+}
+"#);
+    assert_eq!(stdout, "3\n7\n");
+}
+
+#[test]
+fn test_entry_or_insert_counts_words_in_place() {
+    let stdout = compile_and_run("entry_or_insert", r#"
+use std::collections::HashMap;
+
+fn main() {
+    let mut counts = HashMap::new();
+    let words = ["apple", "pear", "apple", "fig", "apple", "pear"];
+    for word in words {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+    println!("{} {} {}", counts.get("apple"), counts.get("pear"), counts.get("fig"));
+    println!("{}", counts.len());
+
+    let slot = counts.entry("kiwi").or_insert(5);
+    *slot = *slot * 2;
+    println!("{} {}", counts.get("kiwi"), counts.len());
+
+    let mut sums = HashMap::new();
+    for n in [3, 4, 3, 3] {
+        *sums.entry(n).or_insert(100) += n;
+    }
+    for (key, sum) in sums {
+        println!("{} {}", key, sum);
+    }
+}
+"#);
+    assert_eq!(stdout, "3 2 1\n3\n10 4\n3 109\n4 104\n");
+}
+
+#[test]
+fn test_emit_tac_feeds_the_multiply_into_the_add_through_a_register() {
+    let dir = scratch_dir("emit_tac");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn madd(a: i64, b: i64, c: i64) -> i64 {
+    a + b * c
+}
+
+fn main() {
+    println!("{}", madd(1, 2, 3));
+}
+"#).expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .set_output_format(OutputFormat::ThreeAddressCode);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+
+    let tac = std::fs::read_to_string(dir.join("prog.tac")).expect("failed to read prog.tac");
+    let madd: Vec<&str> = tac
+        .lines()
+        .skip_while(|line| !line.starts_with("fn madd("))
+        .take_while(|line| *line != "}")
+        .map(str::trim)
+        .collect();
+    assert_eq!(madd.first(), Some(&"fn madd(%v0, %v1, %v2) {"), "{}", tac);
+    let product = madd
+        .iter()
+        .find_map(|line| line.split_once(" = mul ").map(|(dst, _)| dst))
+        .unwrap_or_else(|| panic!("no multiply in:\n{}", tac));
+    assert!(product.starts_with("%v"), "{}", tac);
+    let sum = madd
+        .iter()
+        .find(|line| line.contains(" = add "))
+        .unwrap_or_else(|| panic!("no add in:\n{}", tac));
+    assert!(sum.ends_with(&format!(", {}", product)), "{}", tac);
+    assert!(!tac.contains("rax") && !tac.contains("rbp"), "{}", tac);
+}
+
+#[test]
+fn test_emit_tac_lists_only_the_functions_the_program_uses() {
+    let dir = scratch_dir("emit_tac_used");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn pick(a: i64, b: i64) -> i64 {
+    if b < a { b } else { a }
+}
+
+fn main() {
+    println!("{}", pick(3, 2));
+}
+"#).expect("failed to write main.rs");
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .set_output_format(OutputFormat::ThreeAddressCode);
+    let result = compile_files(&config).expect("compilation should run");
+    assert!(result.success, "compilation failed: {:?}", result.errors);
+
+    let tac = std::fs::read_to_string(dir.join("prog.tac")).expect("failed to read prog.tac");
+    // The prelude's `min` and `max` are left out when nothing calls them
+    let functions: Vec<&str> = tac.lines().filter(|line| line.starts_with("fn ")).collect();
+    assert_eq!(functions, vec!["fn pick(%v0, %v1) {", "fn main() {"], "{}", tac);
+    // The `else` arm is a plain block, not a branch on a constant
+    let branches = tac.lines().filter(|line| line.trim_start().starts_with("br ")).count();
+    assert_eq!(branches, 1, "{}", tac);
+}
+
+#[test]
+fn test_for_loop_drives_a_user_iterator_through_next() {
+    let output = compile_and_run(
+        "user_iterator_for",
+        r#"
+struct Countdown {
+    remaining: i64,
+}
+
+impl Iterator for Countdown {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        if self.remaining == 0 {
+            None
+        } else {
+            self.remaining -= 1;
+            Some(self.remaining + 1)
+        }
+    }
+}
+
+struct Launch {
+    from: i64,
+}
+
+impl IntoIterator for Launch {
+    type Item = i64;
+    type IntoIter = Countdown;
+
+    fn into_iter(self) -> Countdown {
+        Countdown { remaining: self.from }
+    }
+}
+
+fn main() {
+    let countdown = Countdown { remaining: 5 };
+    for n in countdown {
+        println!("{}", n);
+    }
+    let launch = Launch { from: 3 };
+    let mut total = 0;
+    for n in launch {
+        total += n;
+    }
+    println!("{}", total);
+}
+"#,
+    );
+    assert_eq!(output, "5\n4\n3\n2\n1\n6\n");
+}
+
+#[test]
+fn test_for_loop_keeps_a_two_field_iterator_advancing() {
+    let output = compile_and_run(
+        "two_field_iterator_for",
+        r#"
+struct Range2 {
+    cur: i64,
+    end: i64,
+}
+
+impl Iterator for Range2 {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        if self.cur >= self.end {
+            None
+        } else {
+            self.cur += 1;
+            Some(self.cur)
+        }
+    }
+}
+
+fn main() {
+    let range = Range2 { cur: 0, end: 3 };
+    for n in range {
+        println!("{}", n);
+    }
+}
+"#,
+    );
+    assert_eq!(output, "1\n2\n3\n");
+}
+
+#[test]
+fn test_forced_frame_pointers_keep_the_prologue_of_leaf_functions() {
+    let source = r#"
+fn nothing() {}
+
+fn main() {
+    nothing();
+    println!("done");
+}
+"#;
+    let leaf_of = |force: bool| {
+        let dir = scratch_dir(if force { "frame_pointers_forced" } else { "frame_pointers_omitted" });
+        let main_rs = dir.join("main.rs");
+        std::fs::write(&main_rs, source).expect("failed to write main.rs");
+        let config = CompilationConfig::new()
+            .add_source_file(&main_rs)
+            .expect("failed to add source file")
+            .set_output(dir.join("prog"))
+            .set_opt_level(3)
+            .force_frame_pointers(force)
+            .keep_intermediates(true);
+        let result = compile_files(&config).expect("compilation should run");
+        assert!(result.success, "compilation failed: {:?}", result.errors);
+        assert_eq!(run_in(&dir), "done\n");
+        let asm = std::fs::read_to_string(dir.join("prog.s")).expect("failed to read prog.s");
+        asm.lines()
+            .skip_while(|line| *line != "nothing:")
+            .take_while(|line| line.trim() != "ret")
+            .map(|line| line.trim().to_string())
+            .collect::<Vec<String>>()
+    };
+
+    let forced = leaf_of(true);
+    assert_eq!(forced[1..3], ["push rbp", "mov rbp, rsp"], "{:?}", forced);
+    assert!(forced.ends_with(&["mov rsp, rbp".to_string(), "pop rbp".to_string()]), "{:?}", forced);
+
+    let omitted = leaf_of(false);
+    assert!(!omitted.iter().any(|line| line.contains("rbp")), "{:?}", omitted);
+}
+
+#[test]
+fn test_partially_moved_struct_keeps_its_other_fields_usable() {
+    let stdout = compile_and_run("partial_move_ok", r#"
+struct Inner {
+    v: i64,
+}
+
+struct Pair {
+    a: Inner,
+    b: i64,
+}
+
+fn main() {
+    let s = Pair { a: Inner { v: 1 }, b: 7 };
+    let a = s.a;
+    println!("{}", s.b + a.v);
+}
+"#);
+    assert_eq!(stdout, "8\n");
+
+    let errors = compile_errors("partial_move_whole", r#"
+struct Inner {
+    v: i64,
+}
+
+struct Pair {
+    a: Inner,
+    b: i64,
+}
+
+fn consume(p: Pair) -> i64 {
+    p.b
+}
+
+fn main() {
+    let s = Pair { a: Inner { v: 1 }, b: 7 };
+    let a = s.a;
+    println!("{}", consume(s));
+}
+"#);
+    assert!(
+        errors.iter().any(|e| e.message.contains("Value s used after partial move of field a")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_bench_harness_reports_time_per_iteration() {
+    let dir = scratch_dir("bench_harness");
+    let main_rs = dir.join("main.rs");
+    std::fs::write(&main_rs, r#"
+fn sum_to(n: i64) -> i64 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < n {
+        total += i;
+        i += 1;
+    }
+    total
+}
+
+#[bench]
+fn bench_sum(b: &mut Bencher) {
+    let n = 1000;
+    b.iter(|| sum_to(n));
+}
+
+#[bench]
+fn bench_nothing(b: &mut Bencher) {
+    b.iter(|| 0);
+}
+
+fn main() {
+    println!("not run under the bench harness");
+}
+"#).expect("failed to write main.rs");
+
+    let config = CompilationConfig::new()
+        .add_source_file(&main_rs)
+        .expect("failed to add source file")
+        .set_output(dir.join("prog"))
+        .set_bench_iterations(200);
+    let results = gaiarusted::testing::bench::run_benches(&config).expect("benches should run");
+    assert_eq!(results.len(), 2, "{:?}", results);
+    assert_eq!(results[0].name, "bench_sum");
+    assert_eq!(results[0].iterations, 200);
+    assert!(results[0].ns_per_iter > 0, "{:?}", results);
+    assert!(results[0].to_string().ends_with(" ns/iter"), "{}", results[0]);
+    // The loop in `sum_to` is all that's timed, so it dwarfs an empty closure
+    assert_eq!(results[1].name, "bench_nothing");
+    assert!(results[0].ns_per_iter > results[1].ns_per_iter, "{:?}", results);
+}
+
+#[test]
+fn test_single_field_struct_returned_by_value_from_a_method() {
+    let output = compile_and_run(
+        "single_field_struct_by_value",
+        r#"
+struct Meters {
+    v: i64,
+}
+
+impl Meters {
+    fn double(self) -> Meters {
+        Meters { v: self.v * 2 }
+    }
+}
+
+fn main() {
+    let m = Meters { v: 5 };
+    let d = m.double();
+    println!("{}", d.v);
+}
+"#,
+    );
+    assert_eq!(output, "10\n");
+}
+
+#[test]
+fn test_derive_hash_keys_with_colliding_hashes_stay_distinct() {
+    let stdout = compile_and_run("derive_hash_collision", r#"
+use std::collections::HashMap;
+
+#[derive(Hash)]
+struct Point {
+    x: i64,
+    y: i64,
+}
+
+fn main() {
+    let mut m = HashMap::new();
+    // Both keys fold to the same derived hash
+    m.insert(Point { x: 1, y: 2 }, 100);
+    m.insert(Point { x: 3, y: -137 }, 200);
+    let a = Point { x: 1, y: 2 };
+    let b = Point { x: 3, y: -137 };
+    println!("{}", m.get(&a));
+    println!("{}", m.get(&b));
+}
+"#);
+    assert_eq!(stdout, "100\n200\n");
+}
+
+#[test]
+fn test_derive_copy_accepts_array_fn_pointer_and_generic_fields() {
+    let stdout = compile_and_run("derive_copy_compound_fields", r#"
+#[derive(Clone, Copy)]
+struct P {
+    a: [i64; 2],
+    b: i64,
+}
+
+#[derive(Clone, Copy)]
+struct W<T> {
+    v: T,
+}
+
+#[derive(Clone, Copy)]
+struct F {
+    f: fn(i64) -> i64,
+}
+
+fn main() {
+    let p = P { a: [1, 2], b: 3 };
+    let q = p;
+    let w = W { v: 4 };
+    println!("{}", q.b + p.b);
+    println!("{}", w.v);
+}
+"#);
+    assert_eq!(stdout, "6\n4\n");
+
+    let errors = compile_errors("derive_copy_string_field", r#"
+#[derive(Clone, Copy)]
+struct Named {
+    name: String,
+}
+
+fn main() {
+}
+"#);
+    assert!(errors.iter().any(|e| e.message.contains("field `name` of type")), "{:?}", errors);
+}
+
+#[test]
+fn test_integer_literals_at_the_edges_of_i64_and_u64() {
+    let output = compile_and_run(
+        "literal_range_edges",
+        r#"
+fn main() {
+    let min: i64 = -9223372036854775808;
+    let max: u64 = 18446744073709551615;
+    let half: u64 = 9223372036854775808;
+    println!("{}", min);
+    println!("{}", max - half);
+}
+"#,
+    );
+    assert_eq!(output, "-9223372036854775808\n9223372036854775807\n");
+
+    let errors = compile_errors(
+        "literal_u64_bits_out_of_range",
+        r#"
+fn main() {
+    let x: u32 = 18446744073709551615;
+    println!("{}", x);
+}
+"#,
+    );
+    assert!(
+        errors.iter().any(|e| e.message.contains("the literal `18446744073709551615` does not fit into the type `u32`")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_extern_i32_return_is_sign_extended() {
+    let output = compile_and_run(
+        "extern_i32_return_is_sign_extended",
+        r#"
+extern "C" {
+    fn toupper(c: i32) -> i32;
+}
+
+fn main() {
+    let eof = unsafe { toupper(-1) };
+    if eof < 0 {
+        println!("eof {}", eof);
+    } else {
+        println!("byte {}", eof);
+    }
+}
+"#,
+    );
+    assert_eq!(output, "eof -1\n");
+}
+
+#[test]
+fn test_calling_extern_function_requires_unsafe() {
+    let errors = compile_errors(
+        "calling_extern_function_requires_unsafe",
+        r#"
+extern "C" {
+    fn abs(x: i32) -> i32;
+}
+
+fn main() {
+    println!("{}", abs(-5));
+}
+"#,
+    );
+    assert!(errors.iter().any(|e| e.message.contains("unsafe")), "{:?}", errors);
+}
+
+#[test]
+fn test_raw_pointer_arithmetic_steps_by_pointee_size() {
+    let stdout = compile_and_run("raw_pointer_arithmetic_steps_by_pointee_size", r#"
+fn main() {
+    let p: *mut i32 = gaia_alloc(16) as *mut i32;
+    unsafe {
+        *p = -3;
+        *p.add(1) = 9;
+        *p.add(2) = 11;
+        *p.add(3) = -1;
+        *p.add(1) += 5;
+        println!("{} {} {} {}", *p, *p.add(1), *p.add(2), *p.add(3));
+    }
+    let b: *mut u8 = gaia_alloc(2) as *mut u8;
+    unsafe {
+        *b = 255;
+        *b.add(1) = 7;
+        println!("{} {}", *b, *b.add(1));
+    }
+}
+"#);
+    assert_eq!(stdout, "-3 14 11 -1\n255 7\n");
+}
+
+#[test]
+fn test_raw_pointer_to_struct_is_rejected() {
+    let errors = compile_errors("raw_pointer_to_struct", r#"
+struct P {
+    x: i64,
+    y: i64,
+}
+
+fn main() {
+    let p: *mut P = gaia_alloc(32) as *mut P;
+    unsafe {
+        *p.add(1) = P { x: 3, y: 4 };
+    }
+}
+"#);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].message.contains("raw pointers to structs are not supported"), "{}", errors[0].message);
+}
+
+#[test]
+fn test_vec_f64_element_type_comes_from_the_annotation() {
+    let stdout = compile_and_run("vec_f64_element_type_from_annotation", r#"
+fn make() -> Vec<f64> {
+    let mut v = Vec::new();
+    let x: f64 = 2.5;
+    v.push(x);
+    v
+}
+
+fn main() {
+    let mut v: Vec<f64> = Vec::new();
+    let mut i = 0;
+    while i < 3 {
+        if i > 0 {
+            println!("{}", v[i - 1]);
+        }
+        v.push(1.5);
+        i += 1;
+    }
+    let w: Vec<f64> = make();
+    println!("{}", w[0]);
+}
+"#);
+    assert_eq!(stdout, "1.500000\n1.500000\n2.500000\n");
+}
+
+#[test]
+fn test_derived_ord_compares_strings_by_content_and_nested_fields_by_their_ord() {
+    let stdout = compile_and_run("derived_ord_strings_and_nested", r#"
+use std::cmp::Ordering;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    major: i64,
+    minor: i64,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Package {
+    name: &'static str,
+    version: Version,
+}
+
+fn main() {
+    let a = Package { name: "beta", version: Version { major: 1, minor: 0 } };
+    let b = Package { name: "alpha", version: Version { major: 2, minor: 0 } };
+    let c = Package { name: "beta", version: Version { major: 0, minor: 9 } };
+    if b < a { println!("alpha < beta"); }
+    if c < a { println!("beta 0.9 < beta 1.0"); }
+    match a.cmp(&c) {
+        Ordering::Less => println!("less"),
+        Ordering::Equal => println!("equal"),
+        Ordering::Greater => println!("greater"),
+    }
+    if 3.cmp(&5) == Ordering::Less { println!("3 < 5"); }
+}
+"#);
+    assert_eq!(stdout, "alpha < beta\nbeta 0.9 < beta 1.0\ngreater\n3 < 5\n");
+}
+
+#[test]
+fn test_returning_a_borrow_through_a_reference_local_is_allowed() {
+    let output = compile_and_run(
+        "return_ref_local",
+        r#"
+struct Pair {
+    a: i64,
+    b: i64,
+}
+
+fn first(p: &Pair) -> &i64 {
+    let q = p;
+    &q.a
+}
+
+fn pick(a: &i64) -> &i64 {
+    let r = a;
+    r
+}
+
+fn main() {
+    let n = 4;
+    println!("{}", *pick(&n) + 1);
+}
+"#,
+    );
+    assert_eq!(output, "5\n");
+}
+
+#[test]
+fn test_macro_rules_expand_as_items_statements_or_expressions_by_position() {
+    let stdout = compile_and_run("macro_rules_positions", r#"
+macro_rules! getter {
+    ($name:ident, $field:ident) => {
+        fn $name(&self) -> i64 { self.$field }
+    };
+}
+
+macro_rules! adder {
+    ($name:ident, $n:expr) => {
+        fn $name(x: i64) -> i64 { x + $n }
+    };
+}
+
+macro_rules! pub_adder {
+    ($name:ident, $n:expr) => {
+        pub fn $name(x: i64) -> i64 { x + $n }
+    };
+}
+
+macro_rules! print_squares {
+    ($($x:expr),*) => { $( println!("{}", $x * $x); )* };
+}
+
+macro_rules! dot {
+    ($($k:expr => $v:expr),*) => { 0 $( + $k * $v )* };
+}
+
+struct P { a: i64 }
+
+impl P {
+    getter!(get_a, a);
+}
+
+adder!(add_five, 5);
+
+mod m {
+    pub_adder!(add_two, 2);
+}
+
+fn main() {
+    let p = P { a: 41 };
+    println!("{} {} {}", p.get_a(), add_five(1), m::add_two(1));
+    adder!(add_ten, 10);
+    println!("{}", add_ten(1));
+    print_squares!(2, 3);
+    println!("{}", dot!(1 => 2, 3 => 4) * 2);
+}
+"#);
+    assert_eq!(stdout, "41 6 3\n11\n4\n9\n28\n");
+}
+
+#[test]
+fn test_private_methods_are_callable_in_their_defining_module() {
+    let stdout = compile_and_run("private_methods_same_module", r#"
+struct P { a: i64 }
+
+impl P {
+    fn make(a: i64) -> P { P { a: a } }
+    fn hidden(&self) -> i64 { self.a + 1 }
+}
+
+fn main() {
+    let p = P::make(1);
+    println!("{}", p.hidden());
+}
+"#);
+    assert_eq!(stdout, "2\n");
+}
+
+#[test]
+fn test_private_function_is_not_callable_outside_its_module() {
+    let errors = compile_errors("private_fn_other_module", r#"
+mod helpers {
+    fn private_helper() -> i64 { 5 }
+}
+
+fn main() {
+    println!("{}", helpers::private_helper());
+}
+"#);
+    assert!(
+        errors.iter().any(|e| e.message.contains("Private function `helpers::private_helper` is not accessible")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_into_picks_the_from_impl_by_source_type_in_every_position() {
+    let output = compile_and_run(
+        "from_into_by_source",
+        r#"
+struct Celsius {
+    degrees: i64,
+}
+
+impl From<i64> for Celsius {
+    fn from(value: i64) -> Celsius {
+        Celsius { degrees: value }
+    }
+}
+
+impl From<bool> for Celsius {
+    fn from(hot: bool) -> Celsius {
+        if hot { Celsius { degrees: 40 } } else { Celsius { degrees: 0 } }
+    }
+}
+
+fn show(c: Celsius) {
+    println!("{}", c.degrees);
+}
+
+fn boiling() -> Celsius {
+    100i64.into()
+}
+
+fn freezing() -> Celsius {
+    return false.into();
+}
+
+fn main() {
+    let a: Celsius = 7i64.into();
+    let b: Celsius = true.into();
+    println!("{} {}", a.degrees, b.degrees);
+    show(12i64.into());
+    show(true.into());
+    println!("{} {}", boiling().degrees, freezing().degrees);
+    println!("{} {}", Celsius::from(-40).degrees, Celsius::from(true).degrees);
+    let s: String = "text".into();
+    println!("{}", s);
+}
+"#,
+    );
+    assert_eq!(output, "7 40\n12\n40\n100 0\n-40 40\ntext\n");
+}
@@ -96,4 +96,35 @@ mod error_reporting_tests {
         assert_eq!(Severity::Warning.to_string(), "warning");
         assert_eq!(Severity::Note.to_string(), "note");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_plain_output_has_no_escapes_or_unicode() {
+        let source = "fn main() {\n    let x = 42\n}".to_string();
+        let mut reporter = ErrorReporter::new()
+            .with_source(source)
+            .with_color(false);
+        reporter.add(
+            Diagnostic::error("Parser", "Expected semicolon")
+                .with_location(SourceLocation::new(2, 15, 26))
+                .with_suggestion("add `;`")
+                .with_help("statements end with a semicolon"),
+        );
+        reporter.warning("Lints", "unused variable `x`");
+        reporter.note("Lints", "see the style guide");
+
+        let formatted = reporter.format_all();
+        assert!(!formatted.contains("\x1b["), "escape codes in: {:?}", formatted);
+        assert!(formatted.is_ascii(), "non-ASCII bytes in: {:?}", formatted);
+        assert!(formatted.contains("error: Parser: Expected semicolon"));
+        assert!(formatted.contains("warning: Lints: unused variable `x`"));
+        assert!(formatted.contains("1 error, 1 warning, 1 note"));
+    }
+
+    #[test]
+    fn test_colored_output_uses_escapes() {
+        let mut reporter = ErrorReporter::new().with_color(true);
+        reporter.error("Parser", "Expected semicolon");
+
+        assert!(reporter.format_all().contains("\x1b[31merror\x1b[0m"));
+    }
+}